@@ -0,0 +1,305 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::Parser;
+use log::error;
+use serde::Deserialize;
+
+use crate::recorder::RecorderConfig;
+
+/// PX4 camera-trigger GPIO recorder.
+///
+/// Settings are resolved with the following precedence (highest wins):
+/// CLI flags, then environment variables (`CAMERA_TRIGGER_*`), then the
+/// `--config` TOML file, then the built-in defaults.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to an optional TOML config file.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// GPIO character device to request the trigger line from.
+    #[arg(long)]
+    pub gpiochip: Option<PathBuf>,
+
+    /// Line offset of the trigger signal on `gpiochip`.
+    #[arg(long)]
+    pub line_offset: Option<u32>,
+
+    /// Consumer label the trigger line is requested under.
+    #[arg(long)]
+    pub consumer_label: Option<String>,
+
+    /// Minimum pulse width, in milliseconds, trusted as a real edge rather
+    /// than contact bounce.
+    #[arg(long)]
+    pub min_pulse_width_ms: Option<u64>,
+
+    /// Treat a falling edge as the start of a recording and a rising edge
+    /// as the end, for airframes with an inverted trigger line.
+    #[arg(long)]
+    pub invert_polarity: Option<bool>,
+
+    /// Capture device for the recording pipeline, e.g. `/dev/video0`.
+    #[arg(long)]
+    pub source_device: Option<PathBuf>,
+
+    /// Directory finalized recordings are written into.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// `splitmuxsink` location pattern, relative to `output_dir`.
+    #[arg(long)]
+    pub file_pattern: Option<String>,
+}
+
+/// Mirrors [`Cli`]'s overridable fields for deserializing a `--config` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileConfig {
+    gpiochip: Option<PathBuf>,
+    line_offset: Option<u32>,
+    consumer_label: Option<String>,
+    min_pulse_width_ms: Option<u64>,
+    invert_polarity: Option<bool>,
+    source_device: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    file_pattern: Option<String>,
+}
+
+/// Fully resolved configuration the rest of the program runs with.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub gpiochip: PathBuf,
+    pub line_offset: u32,
+    pub consumer_label: String,
+    pub min_pulse_width: Duration,
+    pub invert_polarity: bool,
+    pub recorder: RecorderConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let recorder = RecorderConfig::default();
+
+        Self {
+            gpiochip: PathBuf::from("/dev/gpiochip0"),
+            line_offset: 18,
+            consumer_label: "px4-camera-trigger-gpio".to_string(),
+            min_pulse_width: Duration::from_millis(10),
+            invert_polarity: false,
+            recorder,
+        }
+    }
+}
+
+impl Config {
+    /// Resolves a [`Config`] from the file (if any), environment and CLI
+    /// flags in `cli`, falling back to defaults for anything left unset.
+    /// Exits the process with a log message if `--config` points at a file
+    /// that cannot be read or parsed.
+    pub fn load(cli: Cli) -> Self {
+        let mut config = Self::default();
+
+        if let Some(path) = &cli.config {
+            match Self::read_file(path) {
+                Ok(file) => config.apply_file(file),
+                Err(error) => {
+                    error!("failed to load config file {}: {error}", path.display());
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        config.apply_env();
+        config.apply_cli(cli);
+        config
+    }
+
+    fn read_file(path: &Path) -> Result<FileConfig, String> {
+        let contents = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        toml::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    fn apply_file(&mut self, file: FileConfig) {
+        if let Some(gpiochip) = file.gpiochip {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = file.line_offset {
+            self.line_offset = line_offset;
+        }
+        if let Some(consumer_label) = file.consumer_label {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(min_pulse_width_ms) = file.min_pulse_width_ms {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(invert_polarity) = file.invert_polarity {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(source_device) = file.source_device {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(output_dir) = file.output_dir {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(file_pattern) = file.file_pattern {
+            self.recorder.file_pattern = file_pattern;
+        }
+    }
+
+    fn apply_env(&mut self) {
+        if let Some(gpiochip) = env_var("CAMERA_TRIGGER_GPIOCHIP") {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = env_var("CAMERA_TRIGGER_LINE_OFFSET") {
+            self.line_offset = line_offset;
+        }
+        if let Some(consumer_label) = env_var("CAMERA_TRIGGER_CONSUMER_LABEL") {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(min_pulse_width_ms) = env_var::<u64>("CAMERA_TRIGGER_MIN_PULSE_WIDTH_MS") {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(invert_polarity) = env_var("CAMERA_TRIGGER_INVERT_POLARITY") {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(source_device) = env_var("CAMERA_TRIGGER_SOURCE_DEVICE") {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(output_dir) = env_var("CAMERA_TRIGGER_OUTPUT_DIR") {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(file_pattern) = env_var("CAMERA_TRIGGER_FILE_PATTERN") {
+            self.recorder.file_pattern = file_pattern;
+        }
+    }
+
+    fn apply_cli(&mut self, cli: Cli) {
+        if let Some(gpiochip) = cli.gpiochip {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = cli.line_offset {
+            self.line_offset = line_offset;
+        }
+        if let Some(consumer_label) = cli.consumer_label {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(min_pulse_width_ms) = cli.min_pulse_width_ms {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(invert_polarity) = cli.invert_polarity {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(source_device) = cli.source_device {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(output_dir) = cli.output_dir {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(file_pattern) = cli.file_pattern {
+            self.recorder.file_pattern = file_pattern;
+        }
+    }
+}
+
+/// Parses an environment variable, if set, logging (and ignoring) it if it
+/// fails to parse as `T` rather than silently falling back to the default.
+fn env_var<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = std::env::var(name).ok()?;
+
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(error) => {
+            error!("ignoring {name}={value:?}: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // apply_env() reads a process-wide environment variable, so serialize
+    // the tests that touch it to avoid one clobbering another's value.
+    static LINE_OFFSET_ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const LINE_OFFSET_ENV_VAR: &str = "CAMERA_TRIGGER_LINE_OFFSET";
+
+    fn empty_cli() -> Cli {
+        Cli {
+            config: None,
+            gpiochip: None,
+            line_offset: None,
+            consumer_label: None,
+            min_pulse_width_ms: None,
+            invert_polarity: None,
+            source_device: None,
+            output_dir: None,
+            file_pattern: None,
+        }
+    }
+
+    fn resolve(file_line_offset: Option<u32>, cli_line_offset: Option<u32>) -> u32 {
+        let mut config = Config::default();
+
+        config.apply_file(FileConfig {
+            line_offset: file_line_offset,
+            ..Default::default()
+        });
+        config.apply_env();
+        config.apply_cli(Cli {
+            line_offset: cli_line_offset,
+            ..empty_cli()
+        });
+
+        config.line_offset
+    }
+
+    #[test]
+    fn default_wins_when_nothing_overrides_it() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+
+        assert_eq!(resolve(None, None), Config::default().line_offset);
+    }
+
+    #[test]
+    fn file_overrides_default() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+
+        assert_eq!(resolve(Some(5), None), 5);
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::set_var(LINE_OFFSET_ENV_VAR, "7");
+
+        let line_offset = resolve(Some(5), None);
+
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+        assert_eq!(line_offset, 7);
+    }
+
+    #[test]
+    fn cli_overrides_env_and_file() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::set_var(LINE_OFFSET_ENV_VAR, "7");
+
+        let line_offset = resolve(Some(5), Some(9));
+
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+        assert_eq!(line_offset, 9);
+    }
+}
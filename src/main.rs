@@ -1,13 +1,34 @@
-use std::time::{Instant, SystemTime};
+mod config;
+mod recorder;
+mod supervisor;
+mod trigger;
+mod watchdog;
 
-use gpio_cdev::{Chip, EventRequestFlags, LineEvent, LineRequestFlags};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use futures::stream::StreamExt;
+use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, LineRequestFlags};
 use log::{debug, error, info, trace, warn};
 use simplelog::TermLogger;
 use systemd_journal_logger::JournalLog;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use config::{Cli, Config};
+use recorder::Recorder;
+use supervisor::LineSupervisor;
+use trigger::{Transition, TriggerStateMachine};
+use watchdog::Watchdog;
 
-const GPIO_PIN: u32 = 18;
+/// How long the event loop can go without observing a GPIO edge before it's
+/// treated as stalled and the systemd watchdog stops being petted. This is
+/// well above any expected quiescent period between trigger pulses.
+const EVENT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
 
-fn main() {
+#[tokio::main]
+async fn main() {
     let system_start = Instant::now();
 
     if systemd_journal_logger::connected_to_journal() {
@@ -33,40 +54,161 @@ fn main() {
 
     log::set_max_level(log::LevelFilter::Trace);
 
-    let mut chip = Chip::new("/dev/gpiochip0").expect("gpio chip should be accessible");
-    let input = chip.get_line(GPIO_PIN).expect("gpio pin should exist");
+    let config = Config::load(Cli::parse());
 
-    let event_iterator = input
-        .events(
-            LineRequestFlags::INPUT,
-            EventRequestFlags::FALLING_EDGE,
-            "px4-camera-trigger-gpio",
-        )
-        .expect("input events should be subscribable");
+    let mut chip = match Chip::new(&config.gpiochip) {
+        Ok(chip) => chip,
+        Err(error) => {
+            error!("gpio chip {} is not accessible: {error}", config.gpiochip.display());
+            std::process::exit(1);
+        }
+    };
+
+    let input = match chip.get_line(config.line_offset) {
+        Ok(line) => line,
+        Err(error) => {
+            error!(
+                "line {} does not exist on {}: {error}",
+                config.line_offset,
+                config.gpiochip.display()
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let mut event_stream = match input.events(
+        LineRequestFlags::INPUT,
+        EventRequestFlags::BOTH_EDGES,
+        &config.consumer_label,
+    ) {
+        Ok(events) => AsyncLineEventHandle::new(events)
+            .expect("gpio event stream should be pollable on the tokio runtime"),
+        Err(error) => {
+            error!(
+                "line {} on {} is already in use: {error}",
+                config.line_offset,
+                config.gpiochip.display()
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let mut trigger = TriggerStateMachine::new(config.min_pulse_width, config.invert_polarity);
+
+    let watchdog = Watchdog::new();
+    let recorder = {
+        let watchdog = watchdog.clone();
+        match Recorder::new(&config.recorder, move || watchdog.mark_unhealthy()) {
+            Ok(recorder) => Arc::new(recorder),
+            Err(error) => {
+                error!("failed to initialize recording pipeline: {error}");
+                std::process::exit(1);
+            }
+        }
+    };
+
+    // Drive start/stop through a single consumer, rather than firing an
+    // unordered `spawn_blocking` task per edge: tokio's blocking pool gives
+    // no ordering guarantee across threads, so a short trigger pulse could
+    // otherwise run `stop()` to completion before `start()` lands.
+    let (transition_tx, mut transition_rx) = mpsc::unbounded_channel::<Transition>();
+    let transition_worker = {
+        let recorder = Arc::clone(&recorder);
+        tokio::task::spawn_blocking(move || {
+            while let Some(transition) = transition_rx.blocking_recv() {
+                match transition {
+                    Transition::Start => recorder.start(),
+                    Transition::Stop => recorder.stop(),
+                }
+            }
+        })
+    };
+
+    // Reap SIGTERM (the signal systemd sends on `systemctl stop`) alongside
+    // SIGINT so an in-progress recording is still given a chance to finalize.
+    let mut sigterm = signal(SignalKind::terminate()).expect("SIGTERM handler should install");
+
+    // The GPIO line is requested and the recorder is initialized, so we're
+    // as ready as we'll ever be: tell systemd and start petting the
+    // watchdog, if it asked for one.
+    watchdog.notify_ready();
+    watchdog.spawn_keepalive();
 
-    // TODO: start the recording
+    let (recovered_tx, mut recovered_rx) = mpsc::unbounded_channel();
+    LineSupervisor::new(
+        config.gpiochip.clone(),
+        config.line_offset,
+        config.consumer_label.clone(),
+    )
+    .spawn(recovered_tx);
 
     info!("initialized, program will gracefully handle errors from now on");
 
-    for event in event_iterator {
-        match event {
-            Ok(event) => {
-                info!("recording requested to stop at {}", event.timestamp());
+    loop {
+        tokio::select! {
+            event = tokio::time::timeout(EVENT_STALL_TIMEOUT, event_stream.next()) => {
+                match event {
+                    Ok(Some(Ok(event))) => {
+                        watchdog.mark_healthy();
 
-                // TODO: Stop the recording
+                        let timestamp = event.timestamp();
 
-                info!(
-                    "recording successfully stopped at {}",
-                    SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_nanos()
-                )
+                        match trigger.on_event(timestamp, event.event_type()) {
+                            Some(transition @ Transition::Start) => {
+                                info!("trigger armed at {timestamp}, starting recording");
+                                let _ = transition_tx.send(transition);
+                            }
+                            Some(transition @ Transition::Stop) => {
+                                info!("trigger released at {timestamp}, stopping recording");
+                                let _ = transition_tx.send(transition);
+                            }
+                            None => {}
+                        }
+                    }
+                    Ok(Some(Err(error))) => {
+                        error!("{error}");
+                        warn!("encountered error reading event from event stream, skipping...");
+                    }
+                    Ok(None) => {
+                        warn!("gpio event stream ended unexpectedly, shutting down");
+                        watchdog.mark_unhealthy();
+                        break;
+                    }
+                    Err(_) => {
+                        warn!(
+                            "no gpio event observed in {EVENT_STALL_TIMEOUT:?}, \
+                             treating event loop as stalled"
+                        );
+                        watchdog.mark_unhealthy();
+                    }
+                }
             }
-            Err(error) => {
-                error!("{error}");
-                warn!("encountered error reading event from event iterator, skipping...");
+            Some(new_event_stream) = recovered_rx.recv() => {
+                warn!(
+                    "swapping in re-acquired trigger line event stream, an edge may have been \
+                     missed during the gap; resyncing trigger state to idle"
+                );
+                event_stream = new_event_stream;
+                trigger.reset();
+                watchdog.mark_healthy();
+            }
+            _ = sigterm.recv() => {
+                info!("received SIGTERM, shutting down");
+                break;
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("received SIGINT, shutting down");
+                break;
             }
         }
     }
+
+    watchdog.notify_stopping();
+
+    // Send the final stop through the same queue as every other
+    // transition, then drop the sender and wait for the worker to drain so
+    // shutdown doesn't race an in-flight start/stop either.
+    let _ = transition_tx.send(Transition::Stop);
+    drop(transition_tx);
+    let _ = transition_worker.await;
 }
@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use gstreamer::prelude::*;
+use gstreamer::{self as gst, MessageView};
+use log::{debug, error, warn};
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Parameters describing how a [`Recorder`]'s capture pipeline is built.
+///
+/// Resolved by [`crate::config::Config`] from CLI flags, environment
+/// variables and the `--config` file, falling back to
+/// [`RecorderConfig::default`] for anything left unset.
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    /// V4L2 (or libcamera) capture device to read frames from.
+    pub source_device: PathBuf,
+    /// Directory that finalized recordings are written into.
+    pub output_dir: PathBuf,
+    /// `splitmuxsink` location pattern, relative to `output_dir`. Must
+    /// contain a `{trigger}` placeholder (substituted with a counter unique
+    /// to each trigger) as well as a printf integer directive such as
+    /// `%05d` (substituted by `splitmuxsink` itself, per output fragment of
+    /// that trigger's recording).
+    pub file_pattern: String,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            source_device: PathBuf::from("/dev/video0"),
+            output_dir: PathBuf::from("/var/lib/px4-camera-trigger/recordings"),
+            file_pattern: "trigger-{trigger}-%05d.mp4".to_string(),
+        }
+    }
+}
+
+/// Outcome of waiting for the pipeline to finish finalizing after EOS,
+/// reported by the bus-watching thread to a waiting [`Recorder::stop`].
+enum StopOutcome {
+    Finalized,
+    Errored,
+}
+
+/// Wraps a GStreamer pipeline that captures from a camera source, encodes to
+/// H.264 and muxes into one file per trigger via `splitmuxsink`.
+///
+/// The pipeline is built once in [`Recorder::new`] and then driven between
+/// `Playing` (while a recording is armed) and `Null` (otherwise) by
+/// [`Recorder::start`] and [`Recorder::stop`].
+///
+/// A `gst::Bus` has a single message queue shared by all readers, so only
+/// one thread may ever be parked popping messages off it: the background
+/// thread spawned by [`Recorder::watch_bus`]. [`Recorder::stop`] never reads
+/// the bus itself; it instead registers a one-shot channel that the watcher
+/// thread fires when it sees the `Eos`/`Error` for the current stop.
+///
+/// `splitmuxsink` resets its own fragment counter every time the pipeline
+/// passes through `Null`, so reusing one `location` pattern across triggers
+/// would make every recording after the first overwrite the last one's
+/// first fragment. [`Recorder::start`] works around this by substituting a
+/// trigger counter of its own into the location before each `Playing`.
+pub struct Recorder {
+    pipeline: gst::Pipeline,
+    location_pattern: String,
+    next_trigger_id: AtomicU64,
+    stop_waiter: Arc<Mutex<Option<mpsc::Sender<StopOutcome>>>>,
+}
+
+impl Recorder {
+    /// Builds the capture pipeline and starts watching its bus for errors.
+    ///
+    /// The pipeline is left in the `Null` state; call [`Recorder::start`] to
+    /// begin capturing. Returns `Err` (rather than panicking) if GStreamer
+    /// cannot be initialized, the output directory cannot be created, or the
+    /// pipeline description fails to parse, since all three stem from
+    /// user-configurable values and should be handled with the same
+    /// log-and-exit pattern used for the GPIO chip/line at startup.
+    ///
+    /// `on_fatal_error` is invoked (from the bus-watching thread) whenever
+    /// the pipeline reports an error, so callers can react, e.g. by no
+    /// longer petting the systemd watchdog.
+    pub fn new(
+        config: &RecorderConfig,
+        on_fatal_error: impl Fn() + Send + 'static,
+    ) -> Result<Self, String> {
+        gst::init().map_err(|error| format!("failed to initialize gstreamer: {error}"))?;
+
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+
+        let location_pattern = config
+            .output_dir
+            .join(&config.file_pattern)
+            .display()
+            .to_string();
+        let initial_location = location_pattern.replace("{trigger}", "0");
+
+        let description = format!(
+            "v4l2src device={} ! video/x-raw,format=YUY2 ! videoconvert ! \
+             x264enc tune=zerolatency key-int-max=30 ! h264parse ! \
+             splitmuxsink name=sink location={initial_location} muxer-factory=mp4mux max-size-time=0",
+            config.source_device.display(),
+        );
+
+        let pipeline = gst::parse::launch(&description)
+            .map_err(|error| format!("failed to parse recording pipeline description: {error}"))?
+            .downcast::<gst::Pipeline>()
+            .map_err(|_| "parsed recording pipeline was not a gst::Pipeline".to_string())?;
+
+        let recorder = Self {
+            pipeline,
+            location_pattern,
+            next_trigger_id: AtomicU64::new(0),
+            stop_waiter: Arc::new(Mutex::new(None)),
+        };
+        recorder.watch_bus(on_fatal_error);
+        Ok(recorder)
+    }
+
+    /// Moves the pipeline to `Playing`, beginning capture into a new file.
+    ///
+    /// Substitutes a fresh trigger counter into the `splitmuxsink` location
+    /// before doing so, since the sink's own fragment index resets to 0
+    /// every time the pipeline passes through `Null`.
+    pub fn start(&self) {
+        let trigger_id = self.next_trigger_id.fetch_add(1, Ordering::Relaxed);
+        let location = self.location_pattern.replace("{trigger}", &trigger_id.to_string());
+
+        debug!("starting recording pipeline, location = {location}");
+
+        match self.pipeline.by_name("sink") {
+            Some(sink) => sink.set_property("location", &location),
+            None => error!("recording pipeline has no element named \"sink\" to set location on"),
+        }
+
+        if let Err(error) = self.pipeline.set_state(gst::State::Playing) {
+            error!("failed to start recording pipeline: {error}");
+        }
+    }
+
+    /// Sends EOS and waits for the muxer to flush before tearing the
+    /// pipeline down, so the output file is left in a valid state.
+    pub fn stop(&self) {
+        debug!("stopping recording pipeline");
+
+        let (sender, receiver) = mpsc::channel();
+        *self.stop_waiter.lock().unwrap() = Some(sender);
+
+        if !self.pipeline.send_event(gst::event::Eos::new()) {
+            warn!("failed to send EOS to recording pipeline, file may not finalize cleanly");
+            self.stop_waiter.lock().unwrap().take();
+        } else {
+            match receiver.recv_timeout(STOP_TIMEOUT) {
+                Ok(StopOutcome::Finalized) => {}
+                Ok(StopOutcome::Errored) => {
+                    warn!("recording pipeline errored while finalizing, file may be invalid");
+                }
+                Err(_) => {
+                    warn!("timed out waiting for recording pipeline to finalize");
+                    self.stop_waiter.lock().unwrap().take();
+                }
+            }
+        }
+
+        if let Err(error) = self.pipeline.set_state(gst::State::Null) {
+            error!("failed to stop recording pipeline: {error}");
+        }
+    }
+
+    /// Spawns the sole background thread allowed to read the pipeline's
+    /// bus. It logs errors through the `log` facade instead of letting them
+    /// panic the process, notifies `on_fatal_error`, and wakes up whichever
+    /// [`Recorder::stop`] call is currently waiting on `Eos`/`Error`.
+    fn watch_bus(&self, on_fatal_error: impl Fn() + Send + 'static) {
+        let bus = self.pipeline.bus().expect("pipeline should have a bus");
+        let stop_waiter = Arc::clone(&self.stop_waiter);
+
+        std::thread::spawn(move || {
+            for message in bus.iter_timed(gst::ClockTime::NONE) {
+                match message.view() {
+                    MessageView::Error(error) => {
+                        error!(
+                            "recording pipeline error from {}: {} ({:?})",
+                            error
+                                .src()
+                                .map(|source| source.path_string())
+                                .unwrap_or_else(|| "<unknown>".into()),
+                            error.error(),
+                            error.debug(),
+                        );
+                        on_fatal_error();
+
+                        if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                            let _ = waiter.send(StopOutcome::Errored);
+                        }
+                    }
+                    MessageView::Eos(_) => {
+                        debug!("pipeline reported end-of-stream");
+
+                        if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                            let _ = waiter.send(StopOutcome::Finalized);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+}
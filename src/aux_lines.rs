@@ -0,0 +1,125 @@
+//! Extra GPIO lines beyond the primary trigger line, each wired straight to
+//! one fixed action instead of pulse-width decoded, for airframes that wire
+//! separate PX4 AUX outputs per function (e.g. one line for photo, a second
+//! for video start/stop) rather than multiplexing everything onto a single
+//! pulse line. See [`crate::trigger_source`] for the primary line.
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use clap::ValueEnum;
+use futures::stream::{Stream, StreamExt};
+use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, LineRequestFlags};
+use log::info;
+use serde::Deserialize;
+
+/// What an extra line does when it pulses, independent of the primary
+/// trigger line's pulse-width decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineAction {
+    /// Starts video recording, as if the primary trigger line had toggled
+    /// on. A no-op if already recording.
+    StartVideo,
+    /// Stops video recording. A no-op if not recording.
+    StopVideo,
+    /// Requests a still capture on every camera that has it enabled.
+    CaptureStill,
+    /// Logs a marker row in the trigger event log without affecting any
+    /// camera, for lining up an external event (e.g. a waypoint reached)
+    /// against the recording timeline in post-flight review.
+    MarkEvent,
+    /// Finalizes every camera's recording and exits, for an AUX output PX4
+    /// pulses shortly before it cuts power to the companion computer.
+    SafeShutdown,
+}
+
+/// One extra GPIO line and the action it performs when it sees a rising
+/// edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuxLineConfig {
+    /// Overrides `config.gpiochip` for this one line, for boards that wire
+    /// some AUX outputs through a different gpiochip than the primary
+    /// trigger line's - e.g. a CM4 carrier's I2C GPIO expander. Falls back
+    /// to the primary trigger's gpiochip if unset, same as `line_offset`s
+    /// only ever having meant "on the primary chip" before this existed.
+    pub gpiochip: Option<PathBuf>,
+    pub line_offset: u32,
+    pub action: LineAction,
+    /// Distinguishes this line in logs, e.g. `"waypoint"` for a
+    /// `mark-event` line monitoring a different AUX output than a
+    /// `"geofence"` one. Defaults to the action's name if not given.
+    pub label: String,
+}
+
+/// Every configured extra line, monitored concurrently as a single merged
+/// stream so [`crate::main::run`]'s event loop only needs one `select!` arm
+/// regardless of how many lines are configured.
+pub struct AuxLines {
+    stream: Pin<Box<dyn Stream<Item = (AuxLineConfig, Result<u64, String>)> + Send>>,
+}
+
+impl AuxLines {
+    /// Requests every line in `lines` for rising-edge events only - an extra
+    /// line is a momentary pulse, not a held level, so there's no falling
+    /// edge to decode. Each line is requested on its own `gpiochip` if it
+    /// has one, or `default_gpiochip` (the primary trigger line's chip)
+    /// otherwise, so lines spread across multiple gpiochips (e.g. some on
+    /// the SoC's own controller, some on a carrier board's I2C GPIO
+    /// expander) are all merged into the one polled stream. Fails fast if
+    /// any line can't be requested.
+    pub fn new(default_gpiochip: &Path, lines: &[AuxLineConfig], consumer_label: &str) -> Result<Self, String> {
+        let mut chips: Vec<(PathBuf, Chip)> = Vec::new();
+
+        let mut streams: Vec<Pin<Box<dyn Stream<Item = (AuxLineConfig, Result<u64, String>)> + Send>>> = Vec::new();
+        for line in lines {
+            let gpiochip = line.gpiochip.as_deref().unwrap_or(default_gpiochip);
+
+            let chip_index = match chips.iter().position(|(path, _)| path == gpiochip) {
+                Some(index) => index,
+                None => {
+                    let chip = Chip::new(gpiochip)
+                        .map_err(|error| format!("gpio chip {} is not accessible: {error}", gpiochip.display()))?;
+                    chips.push((gpiochip.to_path_buf(), chip));
+                    chips.len() - 1
+                }
+            };
+            let chip = &mut chips[chip_index].1;
+
+            let handle = chip
+                .get_line(line.line_offset)
+                .map_err(|error| format!("line {} does not exist on {}: {error}", line.line_offset, gpiochip.display()))
+                .and_then(|input| {
+                    input
+                        .events(LineRequestFlags::INPUT, EventRequestFlags::RISING_EDGE, consumer_label)
+                        .map_err(|error| {
+                            format!("line {} on {} is already in use: {error}", line.line_offset, gpiochip.display())
+                        })
+                })
+                .map(|events| {
+                    AsyncLineEventHandle::new(events).expect("gpio event stream should be pollable on the tokio runtime")
+                })?;
+
+            info!(
+                "watching aux line {} on {} for {:?} ({})",
+                line.line_offset,
+                gpiochip.display(),
+                line.action,
+                line.label
+            );
+
+            let config = line.clone();
+            streams.push(Box::pin(handle.map(move |result| {
+                (config.clone(), result.map(|event| event.timestamp()).map_err(|error| error.to_string()))
+            })));
+        }
+
+        Ok(Self { stream: Box::pin(futures::stream::select_all(streams)) })
+    }
+
+    /// Waits for the next pulse on any configured line. Returns `None` once
+    /// every line's event stream has ended.
+    pub async fn next_pulse(&mut self) -> Option<(AuxLineConfig, Result<u64, String>)> {
+        self.stream.next().await
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, LineRequestFlags};
+use log::{info, warn};
+use tokio::sync::mpsc::UnboundedSender;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineSnapshot {
+    consumer: Option<String>,
+    requested: bool,
+}
+
+/// Watches the trigger line for external requests or release, and
+/// re-requests it if another process takes ownership.
+///
+/// Only the line's consumer label and whether it is requested are
+/// compared; changes to its direction or other flags are not detected.
+///
+/// `gpio-cdev`'s chardev v1 ABI doesn't expose a line-info *watch* the way
+/// libgpiod's `watch_line_info`/`wait_info_event` do, so this approximates
+/// one by polling the line's info on an interval and diffing it against the
+/// last observed snapshot.
+pub struct LineSupervisor {
+    gpiochip: PathBuf,
+    line_offset: u32,
+    consumer_label: String,
+}
+
+impl LineSupervisor {
+    pub fn new(gpiochip: PathBuf, line_offset: u32, consumer_label: String) -> Self {
+        Self {
+            gpiochip,
+            line_offset,
+            consumer_label,
+        }
+    }
+
+    /// Spawns the supervisory task. Whenever it successfully re-requests the
+    /// line after losing ownership, the new event stream is sent down
+    /// `recovered` so the caller can swap it into its event loop.
+    pub fn spawn(self, recovered: UnboundedSender<AsyncLineEventHandle>) {
+        tokio::spawn(async move {
+            let mut last = self.read_snapshot();
+
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                let current = self.read_snapshot();
+                if current == last {
+                    continue;
+                }
+
+                info!(
+                    "trigger line {} on {} changed: consumer {:?} -> {:?}, requested {} -> {}",
+                    self.line_offset,
+                    self.gpiochip.display(),
+                    last.consumer,
+                    current.consumer,
+                    last.requested,
+                    current.requested,
+                );
+
+                let owned_by_us = current.consumer.as_deref() == Some(self.consumer_label.as_str());
+
+                if !owned_by_us {
+                    warn!(
+                        "lost ownership of trigger line {} on {} to {:?}, re-requesting",
+                        self.line_offset,
+                        self.gpiochip.display(),
+                        current.consumer
+                    );
+
+                    match self.reacquire(&recovered).await {
+                        Some(handle) => {
+                            if recovered.send(handle).is_err() {
+                                // Event loop is gone, nothing left to supervise.
+                                return;
+                            }
+                        }
+                        None => return,
+                    }
+                }
+
+                last = self.read_snapshot();
+            }
+        });
+    }
+
+    /// Retries [`LineSupervisor::try_request`] with exponential backoff
+    /// until it succeeds, or returns `None` once the receiving end of
+    /// `recovered` has been dropped.
+    async fn reacquire(
+        &self,
+        recovered: &UnboundedSender<AsyncLineEventHandle>,
+    ) -> Option<AsyncLineEventHandle> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            if recovered.is_closed() {
+                return None;
+            }
+
+            match self.try_request() {
+                Ok(handle) => {
+                    info!(
+                        "re-acquired trigger line {} on {}",
+                        self.line_offset,
+                        self.gpiochip.display()
+                    );
+                    return Some(handle);
+                }
+                Err(error) => {
+                    warn!(
+                        "failed to re-request trigger line {} on {} ({error}), retrying in {backoff:?}",
+                        self.line_offset,
+                        self.gpiochip.display()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    fn try_request(&self) -> Result<AsyncLineEventHandle, String> {
+        let mut chip = Chip::new(&self.gpiochip).map_err(|error| error.to_string())?;
+        let line = chip
+            .get_line(self.line_offset)
+            .map_err(|error| error.to_string())?;
+        let events = line
+            .events(
+                LineRequestFlags::INPUT,
+                EventRequestFlags::BOTH_EDGES,
+                &self.consumer_label,
+            )
+            .map_err(|error| error.to_string())?;
+
+        AsyncLineEventHandle::new(events).map_err(|error| error.to_string())
+    }
+
+    fn read_snapshot(&self) -> LineSnapshot {
+        Chip::new(&self.gpiochip)
+            .ok()
+            .and_then(|mut chip| chip.get_line(self.line_offset).ok())
+            .and_then(|line| line.info().ok())
+            .map(|info| LineSnapshot {
+                consumer: (!info.consumer().is_empty()).then(|| info.consumer().to_string()),
+                requested: info.is_used(),
+            })
+            .unwrap_or(LineSnapshot {
+                consumer: None,
+                requested: false,
+            })
+    }
+}
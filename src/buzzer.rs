@@ -0,0 +1,143 @@
+//! Drives a GPIO-attached buzzer with a distinct beep pattern for
+//! "recording started", "recording stopped" and "error", so the launch crew
+//! gets unambiguous audible confirmation without staring at a laptop screen
+//! or a status LED they may not have line of sight to.
+//!
+//! Event-driven rather than polling [`crate::metrics::Metrics`] the way
+//! [`crate::status_led`] does: a beep pattern is a one-shot performance tied
+//! to a specific transition, not a continuously-reflected state, so it fits
+//! the same "channel of events, one background task renders them" shape
+//! [`crate::mqtt`] uses instead.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use log::{error, info, warn};
+use tokio::sync::mpsc;
+
+/// One beep, on for `on` then off for `off`, in a [`BuzzerEvent`]'s pattern.
+struct Beep {
+    on: Duration,
+    off: Duration,
+}
+
+const fn beep(on_ms: u64, off_ms: u64) -> Beep {
+    Beep { on: Duration::from_millis(on_ms), off: Duration::from_millis(off_ms) }
+}
+
+/// One long beep - easy to tell apart from the two shorter patterns below at
+/// a glance (or rather, a listen).
+const RECORDING_STARTED_PATTERN: &[Beep] = &[beep(400, 0)];
+
+/// Two short beeps, distinct from the single long "started" beep.
+const RECORDING_STOPPED_PATTERN: &[Beep] = &[beep(100, 100), beep(100, 0)];
+
+/// Three short, rapid beeps - the busiest pattern, so it stands out as
+/// needing attention.
+const ERROR_PATTERN: &[Beep] = &[beep(80, 80), beep(80, 80), beep(80, 0)];
+
+/// Whether/how to drive a buzzer. Off by default since not every rig has one
+/// wired up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuzzerConfig {
+    pub enabled: bool,
+    /// GPIO chip carrying the buzzer line. Only used if `line_offset` is
+    /// also set.
+    pub gpiochip: Option<PathBuf>,
+    /// Line offset of the buzzer on `gpiochip`. Only used if `gpiochip` is
+    /// also set.
+    pub line_offset: Option<u32>,
+}
+
+impl Default for BuzzerConfig {
+    fn default() -> Self {
+        Self { enabled: false, gpiochip: None, line_offset: None }
+    }
+}
+
+/// An event that plays a distinct beep pattern on [`spawn`]'s background
+/// task.
+#[derive(Debug, Clone, Copy)]
+pub enum BuzzerEvent {
+    RecordingStarted,
+    RecordingStopped,
+    Error,
+}
+
+impl BuzzerEvent {
+    fn pattern(self) -> &'static [Beep] {
+        match self {
+            BuzzerEvent::RecordingStarted => RECORDING_STARTED_PATTERN,
+            BuzzerEvent::RecordingStopped => RECORDING_STOPPED_PATTERN,
+            BuzzerEvent::Error => ERROR_PATTERN,
+        }
+    }
+}
+
+/// A handle for sending [`BuzzerEvent`]s to the buzzer task. Cheap to clone,
+/// same reasoning as [`crate::mqtt::MqttPublisher`].
+#[derive(Clone)]
+pub struct BuzzerHandle {
+    events: mpsc::UnboundedSender<BuzzerEvent>,
+}
+
+impl BuzzerHandle {
+    /// A handle that drops every event, for when `BuzzerConfig::enabled` is
+    /// off - callers don't need to branch on whether a buzzer is configured.
+    pub fn disabled() -> Self {
+        let (events, _rx) = mpsc::unbounded_channel();
+        Self { events }
+    }
+
+    /// Fire-and-forget: queued events play back-to-back on the background
+    /// task, so a burst (e.g. an error right after a stop) is heard in full
+    /// rather than the later one cutting the earlier one off.
+    pub fn signal(&self, event: BuzzerEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Requests `line_offset` on `gpiochip` as an output and spawns a background
+/// task that plays each [`BuzzerEvent`] sent to the returned
+/// [`BuzzerHandle`] in turn, for the process's lifetime.
+pub fn spawn(gpiochip: PathBuf, line_offset: u32) -> Result<BuzzerHandle, String> {
+    let mut chip = Chip::new(&gpiochip)
+        .map_err(|error| format!("buzzer gpio chip {} is not accessible: {error}", gpiochip.display()))?;
+
+    let handle = chip
+        .get_line(line_offset)
+        .map_err(|error| format!("line {line_offset} does not exist on {}: {error}", gpiochip.display()))?
+        .request(LineRequestFlags::OUTPUT, 0, "px4-camera-trigger-buzzer")
+        .map_err(|error| format!("line {line_offset} on {} is already in use: {error}", gpiochip.display()))?;
+
+    info!("driving buzzer on line {line_offset} of {}", gpiochip.display());
+
+    let (events, mut rx) = mpsc::unbounded_channel::<BuzzerEvent>();
+    tokio::spawn(async move {
+        while let Some(event) = rx.recv().await {
+            play(&handle, event.pattern()).await;
+        }
+    });
+
+    Ok(BuzzerHandle { events })
+}
+
+/// Toggles `handle` through `pattern`, sleeping (not blocking - this runs on
+/// a shared tokio task) between each beep's on/off phases.
+async fn play(handle: &LineHandle, pattern: &[Beep]) {
+    for beep in pattern {
+        if let Err(error) = handle.set_value(1) {
+            warn!("failed to raise buzzer line: {error}");
+            return;
+        }
+        tokio::time::sleep(beep.on).await;
+        if let Err(error) = handle.set_value(0) {
+            error!("failed to lower buzzer line: {error}");
+            return;
+        }
+        if !beep.off.is_zero() {
+            tokio::time::sleep(beep.off).await;
+        }
+    }
+}
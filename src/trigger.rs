@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use gpio_cdev::EventType;
+use log::debug;
+
+/// The action a caller should take in response to a trigger edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// The trigger line went active (high, or low with `invert_polarity`):
+    /// arm/start a new recording.
+    Start,
+    /// The trigger line went inactive again: end the current recording.
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Recording,
+}
+
+/// Maps GPIO edges on the trigger line to recording start/stop transitions.
+///
+/// PX4 pulses the camera trigger line high for the duration of each shot, so
+/// a rising edge arms recording and the matching falling edge ends it.
+/// Repeated edges of the same polarity are idempotent (a second rising edge
+/// while already recording is a no-op), and edges that arrive less than
+/// `min_pulse_width` after the previous one are treated as contact bounce
+/// and dropped.
+///
+/// Some airframes wire the trigger line inverted (idle high, pulsed low for
+/// each shot); `invert_polarity` swaps which edge is treated as the start of
+/// a recording without the caller needing to know which wiring is in use.
+pub struct TriggerStateMachine {
+    state: State,
+    min_pulse_width: Duration,
+    invert_polarity: bool,
+    last_edge_timestamp: Option<u64>,
+}
+
+impl TriggerStateMachine {
+    /// `min_pulse_width` is the shortest gap between edges that is trusted
+    /// as a real transition rather than bounce. `invert_polarity` treats a
+    /// falling edge as the start of a recording and a rising edge as the
+    /// end, for airframes with an inverted trigger line.
+    pub fn new(min_pulse_width: Duration, invert_polarity: bool) -> Self {
+        Self {
+            state: State::Idle,
+            min_pulse_width,
+            invert_polarity,
+            last_edge_timestamp: None,
+        }
+    }
+
+    /// Feeds a hardware edge into the state machine, returning the
+    /// transition to act on, if the edge is both debounced and a real state
+    /// change. `timestamp` is the edge's hardware timestamp in nanoseconds.
+    pub fn on_event(&mut self, timestamp: u64, event_type: EventType) -> Option<Transition> {
+        if let Some(last_edge_timestamp) = self.last_edge_timestamp {
+            let elapsed = Duration::from_nanos(timestamp.saturating_sub(last_edge_timestamp));
+
+            if elapsed < self.min_pulse_width {
+                debug!(
+                    "ignoring bouncy edge at {timestamp} ({elapsed:?} since last edge, \
+                     minimum pulse width is {:?})",
+                    self.min_pulse_width
+                );
+                return None;
+            }
+        }
+        self.last_edge_timestamp = Some(timestamp);
+
+        let event_type = if self.invert_polarity {
+            match event_type {
+                EventType::RisingEdge => EventType::FallingEdge,
+                EventType::FallingEdge => EventType::RisingEdge,
+            }
+        } else {
+            event_type
+        };
+
+        match (event_type, self.state) {
+            (EventType::RisingEdge, State::Idle) => {
+                self.state = State::Recording;
+                Some(Transition::Start)
+            }
+            (EventType::FallingEdge, State::Recording) => {
+                self.state = State::Idle;
+                Some(Transition::Stop)
+            }
+            (EventType::RisingEdge, State::Recording) => {
+                debug!("rising edge at {timestamp} while already recording, ignoring");
+                None
+            }
+            (EventType::FallingEdge, State::Idle) => {
+                debug!("falling edge at {timestamp} while idle, ignoring");
+                None
+            }
+        }
+    }
+
+    /// Resyncs to [`State::Idle`] and forgets the last edge's timestamp,
+    /// without emitting a [`Transition`]. Used after a gap in the event
+    /// stream (e.g. the trigger line was re-requested by
+    /// [`crate::supervisor::LineSupervisor`]) during which an edge could
+    /// have been missed, so a stale `Recording` state doesn't swallow the
+    /// next real rising edge.
+    pub fn reset(&mut self) {
+        self.state = State::Idle;
+        self.last_edge_timestamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_PULSE_WIDTH: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn rising_edge_starts_and_falling_edge_stops() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+        assert_eq!(
+            trigger.on_event(20_000_000, EventType::FallingEdge),
+            Some(Transition::Stop)
+        );
+    }
+
+    #[test]
+    fn edge_faster_than_min_pulse_width_is_debounced() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+        // 1ms later, well under the 10ms minimum pulse width.
+        assert_eq!(trigger.on_event(1_000_000, EventType::FallingEdge), None);
+    }
+
+    #[test]
+    fn edge_at_exactly_min_pulse_width_is_accepted() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+        assert_eq!(
+            trigger.on_event(10_000_000, EventType::FallingEdge),
+            Some(Transition::Stop)
+        );
+    }
+
+    #[test]
+    fn repeated_rising_edges_are_idempotent() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+        assert_eq!(trigger.on_event(20_000_000, EventType::RisingEdge), None);
+        assert_eq!(trigger.on_event(40_000_000, EventType::RisingEdge), None);
+    }
+
+    #[test]
+    fn repeated_falling_edges_are_idempotent() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(trigger.on_event(0, EventType::FallingEdge), None);
+        assert_eq!(trigger.on_event(20_000_000, EventType::FallingEdge), None);
+    }
+
+    #[test]
+    fn reset_forgets_recording_state_and_debounce() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, false);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+
+        trigger.reset();
+
+        // Idle again, and the debounce clock was forgotten, so an edge
+        // arriving sooner than `min_pulse_width` after the pre-reset edge
+        // is still accepted as a fresh transition.
+        assert_eq!(
+            trigger.on_event(1_000_000, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+    }
+
+    #[test]
+    fn inverted_polarity_swaps_start_and_stop_edges() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, true);
+
+        assert_eq!(
+            trigger.on_event(0, EventType::FallingEdge),
+            Some(Transition::Start)
+        );
+        assert_eq!(
+            trigger.on_event(20_000_000, EventType::RisingEdge),
+            Some(Transition::Stop)
+        );
+    }
+}
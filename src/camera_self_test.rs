@@ -0,0 +1,116 @@
+//! Startup sanity check: captures one throwaway frame from a camera and
+//! makes sure it's a real image (nonzero size, and if it decodes as a JPEG,
+//! plausible dimensions) rather than the empty or corrupt file a camera
+//! that's electrically present but misbehaving (bad power, wrong sensor
+//! mode, a stuck ISP) tends to produce.
+//!
+//! See [`crate::recorder::RecorderConfig::self_test_enabled`] and
+//! [`crate::recorder::RecorderConfig::self_test_degraded_on_failure`], and
+//! their use in [`crate::session::Session::new`].
+
+use log::{info, warn};
+
+use crate::recorder::{self, RecorderConfig};
+
+/// A decoded width/height below this is treated the same as a missing one -
+/// real hardware, once actually producing frames, never reports single-digit
+/// dimensions.
+const MIN_PLAUSIBLE_DIMENSION: u32 = 16;
+
+/// Captures one throwaway frame from `config`'s source into a scratch file
+/// under `config.output_dir`, checks it, and deletes it either way. Returns
+/// `Ok(())` if the frame looked like a real image; otherwise an error
+/// describing what looked wrong, suitable for logging or aborting on.
+///
+/// A no-op returning `Ok(())` immediately if `config.self_test_enabled` is
+/// off.
+pub fn run(config: &RecorderConfig, camera_id: &str) -> Result<(), String> {
+    if !config.self_test_enabled {
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(&config.output_dir)
+        .map_err(|error| format!("failed to create {}: {error}", config.output_dir.display()))?;
+    let probe_path = config.output_dir.join(format!(".self-test-{camera_id}.jpg"));
+    let location = probe_path.display().to_string();
+
+    let captured = recorder::capture_probe_frame(config, &location);
+    let result = check_probe_frame(&probe_path, captured);
+    let _ = std::fs::remove_file(&probe_path);
+
+    match &result {
+        Ok((bytes, dimensions)) => info!(
+            "camera {camera_id} self-test passed: captured {bytes} byte frame{}",
+            dimensions.map(|(w, h)| format!(" ({w}x{h})")).unwrap_or_default()
+        ),
+        Err(error) => warn!("camera {camera_id} self-test failed: {error}"),
+    }
+
+    result.map(|_| ())
+}
+
+/// Reads back `probe_path` (only if `captured` reports the capture itself
+/// succeeded) and checks it's nonempty with plausible JPEG dimensions,
+/// returning the byte count and, if the file decoded as a JPEG, its
+/// dimensions.
+fn check_probe_frame(probe_path: &std::path::Path, captured: bool) -> Result<(u64, Option<(u32, u32)>), String> {
+    if !captured {
+        return Err("frame capture failed; see the preceding error".to_string());
+    }
+
+    let data = std::fs::read(probe_path).map_err(|error| format!("failed to read captured frame: {error}"))?;
+    if data.is_empty() {
+        return Err("captured frame is empty".to_string());
+    }
+
+    match jpeg_dimensions(&data) {
+        Some((width, height)) if width < MIN_PLAUSIBLE_DIMENSION || height < MIN_PLAUSIBLE_DIMENSION => {
+            Err(format!("captured frame decoded to implausible dimensions {width}x{height}"))
+        }
+        Some((width, height)) => Ok((data.len() as u64, Some((width, height)))),
+        // Not a JPEG we can parse (e.g. an unexpected container from a
+        // backend we don't specifically handle here) - the nonzero-size
+        // check above is still meaningful, so don't fail just for this.
+        None => Ok((data.len() as u64, None)),
+    }
+}
+
+/// Scans `data` for a JPEG SOF (start-of-frame) marker and returns the
+/// `(width, height)` it encodes, or `None` if `data` isn't a JPEG or no SOF
+/// marker is found before EOF.
+fn jpeg_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.len() < 4 || data[0..2] != [0xFF, 0xD8] {
+        return None;
+    }
+
+    let mut i = 2;
+    while i + 9 <= data.len() {
+        if data[i] != 0xFF {
+            i += 1;
+            continue;
+        }
+        let marker = data[i + 1];
+        // Markers with no payload: SOI, standalone (TEM), and restart markers.
+        if marker == 0xD8 || marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            i += 2;
+            continue;
+        }
+        if marker == 0xD9 {
+            break; // EOI
+        }
+
+        let segment_len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        // SOF0-SOF15, excluding DHT (0xC4), JPG (0xC8) and DAC (0xCC), which
+        // share the numeric range but aren't frame headers.
+        let is_sof = matches!(marker, 0xC0..=0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF);
+        if is_sof {
+            let height = u16::from_be_bytes([data[i + 5], data[i + 6]]) as u32;
+            let width = u16::from_be_bytes([data[i + 7], data[i + 8]]) as u32;
+            return Some((width, height));
+        }
+
+        i += 2 + segment_len;
+    }
+
+    None
+}
@@ -0,0 +1,80 @@
+//! Pulses a GPIO output line the instant a frame is actually captured, for
+//! wiring straight into a flight controller's hardware camera-feedback
+//! input - PX4's `CAM_FEEDBACK` circuitry timestamps the rising edge itself,
+//! so geotagging reflects the real exposure instant instead of whatever
+//! latency sits between the commanded trigger and the frame actually
+//! landing (see [`crate::session`]'s trigger-to-first-frame latency log,
+//! which uses the same underlying timestamp).
+//!
+//! Only the v4l2-direct and libcamera-native backends can drive this: they
+//! own their own per-frame capture loop and know the instant a frame lands,
+//! the same reason [`crate::recorder::Recorder::new`]'s `on_first_frame`
+//! callback - which this module is normally wired up to - is scoped to just
+//! those two backends.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpio_cdev::{Chip, LineHandle, LineRequestFlags};
+use log::warn;
+
+/// How long the feedback line is held high per pulse. PX4's capture-feedback
+/// input just needs a clean rising edge to timestamp, so this is chosen to
+/// be comfortably longer than typical GPIO/interrupt jitter while staying
+/// far shorter than the interval between frames.
+const PULSE_WIDTH: Duration = Duration::from_micros(500);
+
+/// Whether/how to pulse a hardware capture-feedback line. Off by default
+/// since not every rig wires PX4's feedback pin up to this process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureFeedbackConfig {
+    pub enabled: bool,
+    /// GPIO chip carrying the feedback line. Only used if `line_offset` is
+    /// also set.
+    pub gpiochip: Option<PathBuf>,
+    /// Line offset of the feedback pulse on `gpiochip`.
+    pub line_offset: Option<u32>,
+}
+
+impl Default for CaptureFeedbackConfig {
+    fn default() -> Self {
+        Self { enabled: false, gpiochip: None, line_offset: None }
+    }
+}
+
+/// A requested GPIO output line, held for the process's lifetime, that
+/// [`CaptureFeedback::pulse`] toggles high then low.
+pub struct CaptureFeedback {
+    handle: LineHandle,
+}
+
+impl CaptureFeedback {
+    /// Requests `line_offset` on `gpiochip` as an output, initially low.
+    pub fn open(gpiochip: &PathBuf, line_offset: u32) -> Result<Self, String> {
+        let mut chip = Chip::new(gpiochip)
+            .map_err(|error| format!("capture feedback gpio chip {} is not accessible: {error}", gpiochip.display()))?;
+
+        let handle = chip
+            .get_line(line_offset)
+            .map_err(|error| format!("line {line_offset} does not exist on {}: {error}", gpiochip.display()))?
+            .request(LineRequestFlags::OUTPUT, 0, "px4-camera-trigger-capture-feedback")
+            .map_err(|error| format!("line {line_offset} on {} is already in use: {error}", gpiochip.display()))?;
+
+        Ok(Self { handle })
+    }
+
+    /// Raises the line, holds it for [`PULSE_WIDTH`], then lowers it again.
+    /// Blocks the calling thread for that duration, so this should only be
+    /// called from a dedicated capture thread (as `on_first_frame` callbacks
+    /// already run on), never from an async task.
+    pub fn pulse(&self) {
+        if let Err(error) = self.handle.set_value(1) {
+            warn!("failed to raise capture feedback line: {error}");
+            return;
+        }
+        std::thread::sleep(PULSE_WIDTH);
+        if let Err(error) = self.handle.set_value(0) {
+            warn!("failed to lower capture feedback line: {error}");
+        }
+    }
+}
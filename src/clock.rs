@@ -0,0 +1,132 @@
+//! Bridges `CLOCK_MONOTONIC` (what GPIO edge timestamps, and the v4l2-direct
+//! and libcamera-native backends' frame timestamps, are all counted in) and
+//! `CLOCK_REALTIME`/the Unix epoch (what [`crate::trigger_log::TriggerLog`]
+//! and the outside world want to see), by sampling both clocks together and
+//! keeping a running offset - the same "periodically resampled `AtomicI64`
+//! offset" shape as [`crate::mavlink::MavlinkFeedback`]'s PX4 clock sync,
+//! just against the kernel's own realtime clock instead of a remote one.
+//!
+//! Translating at read time (rather than capturing `SystemTime::now()` when
+//! a monotonic timestamp happens to be logged) matters because logging can
+//! lag the event it describes by an unpredictable amount; folding that lag
+//! into the reported time would make it look like clock drift.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::warn;
+
+/// How often the offset is resampled, to track long-run drift between the
+/// two clocks (e.g. NTP slewing the realtime one).
+const RESAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Largest single correction [`RealtimeClock::discipline_pps`] will apply.
+/// A pulse implying a bigger correction means the offset's whole-second
+/// value (not just its phase) is wrong, which a PPS pulse alone can't fix -
+/// applying it anyway would yank the reported time by however far off that
+/// whole-second guess is, instead of just tightening its phase.
+const MAX_PPS_CORRECTION: Duration = Duration::from_millis(500);
+
+/// The current best estimate of `realtime_ns - monotonic_ns`, shared with
+/// every camera's transition worker so translating a trigger timestamp
+/// never needs to touch the clocks itself.
+pub struct RealtimeClock {
+    offset_ns: AtomicI64,
+}
+
+impl RealtimeClock {
+    /// Samples both clocks once and spawns a background task to keep
+    /// resampling every [`RESAMPLE_INTERVAL`].
+    pub fn spawn() -> Arc<Self> {
+        let clock = Arc::new(Self { offset_ns: AtomicI64::new(sample_offset_ns()) });
+
+        let resample = Arc::clone(&clock);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(RESAMPLE_INTERVAL);
+            ticker.tick().await; // fires immediately; the offset above already covers it
+
+            loop {
+                ticker.tick().await;
+                resample.offset_ns.store(sample_offset_ns(), Ordering::Relaxed);
+            }
+        });
+
+        clock
+    }
+
+    /// Translates a `CLOCK_MONOTONIC` nanosecond timestamp (e.g.
+    /// [`crate::trigger::TriggerEvent::gpio_timestamp_ns`]) to nanoseconds
+    /// since the Unix epoch, using the most recently sampled offset.
+    pub fn to_unix_nanos(&self, monotonic_ns: u64) -> i64 {
+        monotonic_ns as i64 + self.offset_ns.load(Ordering::Relaxed)
+    }
+
+    /// Nudges the offset so `monotonic_ns` (a GPS PPS pulse's edge
+    /// timestamp, see [`crate::pps`]) translates to the nearest whole
+    /// second instead of wherever it currently lands, disciplining the
+    /// offset's sub-second phase to GPS time. Ignored if the correction
+    /// implied is bigger than [`MAX_PPS_CORRECTION`]; see its docs.
+    pub fn discipline_pps(&self, monotonic_ns: u64) {
+        let estimated_unix_ns = self.to_unix_nanos(monotonic_ns);
+        let nearest_second_ns = (estimated_unix_ns + 500_000_000).div_euclid(1_000_000_000) * 1_000_000_000;
+        let correction_ns = nearest_second_ns - estimated_unix_ns;
+
+        if correction_ns.unsigned_abs() > MAX_PPS_CORRECTION.as_nanos() as u64 {
+            warn!(
+                "pps pulse implies a {correction_ns} ns clock correction, over the {MAX_PPS_CORRECTION:?} \
+                 trusted window; ignoring (system clock's whole-second value may be wrong)"
+            );
+            return;
+        }
+
+        self.offset_ns.fetch_add(correction_ns, Ordering::Relaxed);
+    }
+
+    /// Folds a `CLOCK_REALTIME`-domain GPIO edge timestamp (from a v2 uAPI
+    /// request built with `EventClock::Realtime`, see
+    /// [`crate::trigger_source::GpioTriggerSource`]) back into the
+    /// `CLOCK_MONOTONIC` domain every other timestamp in the pipeline
+    /// (`trigger.rs`, `session.rs`, `trigger_log.rs`, `ros2_bridge.rs`)
+    /// expects, so requesting the realtime clock for the trigger line stays
+    /// entirely local to how its edges are read in.
+    ///
+    /// The kernel's realtime timestamp is authoritative (it comes straight
+    /// off `CLOCK_REALTIME` at the moment of the interrupt, unlike
+    /// [`RealtimeClock::discipline_pps`]'s two separate clock reads), so
+    /// this both disciplines the offset with it and returns the
+    /// monotonic-domain equivalent in one step, without the
+    /// [`MAX_PPS_CORRECTION`] guard - there's no risk of mistaking a whole
+    /// second of drift for a bad reading, since both timestamps describe the
+    /// very same edge.
+    pub fn discipline_from_realtime_edge(&self, realtime_ns: u64) -> u64 {
+        let offset_ns = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i64
+            - monotonic_now_ns() as i64;
+        self.offset_ns.store(offset_ns, Ordering::Relaxed);
+        (realtime_ns as i64 - offset_ns).max(0) as u64
+    }
+}
+
+/// Reads `CLOCK_MONOTONIC` and `CLOCK_REALTIME` back-to-back and returns
+/// their difference in nanoseconds. The two reads aren't perfectly
+/// simultaneous, but the scheduling gap between them is negligible next to
+/// what this offset is used for: reporting trigger times to the millisecond.
+fn sample_offset_ns() -> i64 {
+    let monotonic_ns = monotonic_now_ns() as i64;
+    let realtime_ns = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as i64;
+    realtime_ns - monotonic_ns
+}
+
+/// The same clock GPIO edge timestamps are counted from, read directly via
+/// `clock_gettime` since `std::time::Instant` doesn't expose a raw
+/// nanosecond count comparable to one. `pub(crate)` so
+/// [`crate::recorder::sensor_clock`] can sample it alongside a frame's own
+/// sensor timestamp.
+pub(crate) fn monotonic_now_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid, correctly-sized out-parameter for `clock_gettime`.
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+    }
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
@@ -0,0 +1,3924 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use log::error;
+use serde::Deserialize;
+
+use crate::aux_lines::{AuxLineConfig, LineAction};
+use crate::buzzer::BuzzerConfig;
+use crate::capture_feedback::CaptureFeedbackConfig;
+use crate::control_api::ControlApiConfig;
+use crate::dbus_api::DbusConfig;
+use crate::dronecan::DroneCanConfig;
+use crate::durability::DurabilityConfig;
+use crate::grpc_api::GrpcConfig;
+use crate::intervalometer::IntervalometerConfig;
+use crate::mavlink::MavlinkConfig;
+use crate::metrics::MetricsConfig;
+use crate::mqtt::MqttConfig;
+use crate::network_trigger::NetworkTriggerConfig;
+use crate::offload::OffloadConfig;
+use crate::pps::PpsConfig;
+use crate::privsep::PrivsepConfig;
+use crate::recorder::{
+    BackpressurePolicy, CameraControls, CaptureSource, ContainerFormat, Encoder, NamingMode, Orientation, RecorderConfig,
+    RecordingBackend, VideoCodec,
+};
+use crate::retention::RetentionConfig;
+use crate::ros2_bridge::RosConfig;
+use crate::shutdown_inhibitor::ShutdownInhibitorConfig;
+use crate::status::StatusConfig;
+use crate::status_led::StatusLedConfig;
+use crate::storage_health::StorageHealthConfig;
+use crate::subtitle_log::SubtitleConfig;
+use crate::thermal::ThermalConfig;
+use crate::time_sync_check::TimeSyncConfig;
+use crate::trigger_generator::GeneratorConfig;
+use crate::trigger_log::TriggerLogConfig;
+
+/// What to do once configuration has been resolved.
+///
+/// Defaults to [`Command::Run`] when no subcommand is given, so existing
+/// invocations that just pass flags keep working unchanged.
+#[derive(Debug, Clone, Copy, Default, Subcommand)]
+pub enum Command {
+    /// Run the trigger service. This is the default.
+    #[default]
+    Run,
+    /// Resolve configuration from file/env/CLI and print it, without
+    /// requesting the GPIO line or touching the recording pipeline.
+    CheckConfig,
+    /// List every line on `--gpiochip` with its offset, consumer and
+    /// direction, to help pick `--line-offset` on unfamiliar hardware.
+    ListLines,
+    /// Request the trigger line, run one start/stop cycle through the
+    /// configured recording backend, and report whether it produced output.
+    TestCapture,
+    /// Connect to a running instance's Unix status socket and print its
+    /// JSON status response, without touching the recording pipeline. See
+    /// [`crate::status`].
+    Status,
+    /// Enumerate every gpiochip's lines and every detected V4L2/UVC and
+    /// libcamera camera, with the capture modes each advertises, without
+    /// requesting the trigger line or touching the recording pipeline. See
+    /// [`crate::discovery`].
+    Probe,
+    /// Request the trigger line as an *output* and pulse it per
+    /// `--generate-*`, for bench-testing a third-party camera's trigger
+    /// input or PX4's `CAMERA_TRIGGER` feedback wiring without a flight
+    /// controller in the loop. See [`crate::trigger_generator`].
+    Generate,
+    /// Run the configured recording pipeline for `--bench-duration-secs`
+    /// without requesting the trigger line, and report the achievable
+    /// framerate, encode latency, CPU usage and write throughput, so a new
+    /// SD card or camera mode can be validated on the bench before it's
+    /// trusted on a flight.
+    Bench,
+    /// Reconstruct a playable file from `file`, an mp4 recording truncated
+    /// by power loss or a crash, using `--codec`/`--encoder`/`--capture-*`
+    /// (and, if present, its `.timestamps.csv` frame index sidecar) to
+    /// rebuild the index its `moov` box lost. See
+    /// [`crate::recorder::recover_recording`].
+    Recover {
+        /// The truncated recording to recover. Written alongside it, with
+        /// its extension replaced by `recovered.mp4`.
+        file: PathBuf,
+    },
+    /// Decrypt `file`, a recording written under `--encryption-recipient`,
+    /// with the matching age identity (private key) in `identity_file`. For
+    /// the ground workstation; the drone itself never needs this, since it
+    /// only ever holds the public half. See
+    /// [`crate::recorder::decrypt_recording`].
+    Decrypt {
+        /// The encrypted recording to decrypt. Written alongside it with
+        /// `.decrypted` inserted before the extension.
+        file: PathBuf,
+        /// Path to an age identity file, as written by `age-keygen -o`.
+        identity_file: PathBuf,
+    },
+}
+
+/// Log output format for the non-journald fallback path (see
+/// [`Cli::log_format`]). Has no effect when connected to the systemd
+/// journal, which already gets structured fields regardless.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Colored, human-readable text. The long-standing default.
+    #[default]
+    Text,
+    /// Newline-delimited JSON, one object per log record.
+    Json,
+}
+
+/// Internal pull resistor to request on the trigger line, for flight
+/// controllers that drive it open-drain and would otherwise need an
+/// external resistor to hold a defined level between pulses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineBias {
+    /// Let the kernel/board default apply; this process doesn't request a
+    /// bias either way. The long-standing default.
+    #[default]
+    Disabled,
+    /// Request an internal pull-up.
+    PullUp,
+    /// Request an internal pull-down.
+    PullDown,
+}
+
+impl std::str::FromStr for LineBias {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true).map_err(|_| format!("unknown line bias {value:?}"))
+    }
+}
+
+impl LineBias {
+    /// Translates to the corresponding gpio uAPI v2 line setting, via the
+    /// `gpiocdev` crate. See [`Config::trigger_line_config`].
+    pub(crate) fn v2_bias(self) -> Option<gpiocdev::line::Bias> {
+        match self {
+            LineBias::Disabled => None,
+            LineBias::PullUp => Some(gpiocdev::line::Bias::PullUp),
+            LineBias::PullDown => Some(gpiocdev::line::Bias::PullDown),
+        }
+    }
+}
+
+/// PX4 camera-trigger GPIO recorder.
+///
+/// Settings are resolved with the following precedence (highest wins):
+/// CLI flags, then environment variables (`CAMERA_TRIGGER_*`), then the
+/// `--config` TOML file, then the built-in defaults.
+#[derive(Debug, Clone, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to an optional TOML config file.
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Names this instance, e.g. the `%i` a `px4-camera-trigger@.service`
+    /// systemd template unit passes on `ExecStart` when started as
+    /// `px4-camera-trigger@<name>.service`. Lets several independent
+    /// instances (one per payload bay) run on the same companion computer
+    /// without their `--config` and `--pidfile` defaults colliding: unless
+    /// overridden, `config` defaults to
+    /// `/etc/px4-camera-trigger/<instance>.toml` instead of
+    /// [`DEFAULT_CONFIG_PATH`], and `pidfile` defaults to
+    /// `/run/px4-camera-trigger-<instance>.pid`. Everything else that could
+    /// otherwise collide between instances (the dbus service name, MQTT
+    /// topic prefix, control/gRPC/metrics listen addresses, `output_dir`,
+    /// `gpiochip`/`line_offset`) is already a per-instance override in each
+    /// instance's own config file; this flag only picks which file.
+    #[arg(long, global = true)]
+    pub instance: Option<String>,
+
+    /// `flock`ed pidfile guarding against a second instance running
+    /// alongside this one. See [`crate::single_instance`].
+    #[arg(long, global = true)]
+    pub pidfile: Option<PathBuf>,
+
+    /// Drop root and every Linux capability once startup has finished
+    /// opening the gpiochip, camera devices and output directory, switching
+    /// to `--privsep-user` before any network-facing control surface starts
+    /// accepting connections. See [`crate::privsep`].
+    #[arg(long, global = true)]
+    pub privsep_enabled: Option<bool>,
+
+    /// Unprivileged user to switch to. Required if `--privsep-enabled` is
+    /// set; its groups must already cover every device this process needs
+    /// to keep accessing after the drop.
+    #[arg(long, global = true)]
+    pub privsep_user: Option<String>,
+
+    /// How long to wait at startup for `source_device` to appear and, if
+    /// `storage_mount_point` is set, for it to be mounted, before giving up.
+    /// Useful when this service's systemd unit starts before udev has
+    /// finished enumerating a USB camera or an external SSD has finished
+    /// mounting. Unset (the default) disables the wait, failing immediately
+    /// as before. See [`crate::device_wait`].
+    #[arg(long, global = true)]
+    pub device_ready_timeout_secs: Option<u64>,
+
+    /// Directory expected to be a mount point (e.g. an external SSD) rather
+    /// than a plain directory on the root filesystem, checked at startup
+    /// alongside `device_ready_timeout_secs`. Typically an ancestor of
+    /// `output_dir`. Unset disables the check.
+    #[arg(long, global = true)]
+    pub storage_mount_point: Option<PathBuf>,
+
+    /// Minimum free space, in bytes, `storage_mount_point` must have at
+    /// startup. Catches a degraded mount (e.g. a fallback tmpfs quietly
+    /// mounted in place of a missing SSD) that passes the mount-point check
+    /// but can't actually hold a flight's worth of recordings. Unset
+    /// disables the check. See [`crate::device_wait::check_free_space`].
+    #[arg(long, global = true)]
+    pub storage_min_free_bytes: Option<u64>,
+
+    /// Log output format to use when not connected to the systemd journal
+    /// (which is always structured regardless of this setting). Defaults to
+    /// colored text; `json` emits newline-delimited JSON to stderr instead,
+    /// for environments like our containerized HIL rig where a text-mode
+    /// terminal isn't watching but log lines still need to be machine-parsed.
+    #[arg(long, global = true)]
+    pub log_format: Option<LogFormat>,
+
+    /// GPIO character device to request the trigger line from.
+    #[arg(long, alias = "chip", global = true)]
+    pub gpiochip: Option<PathBuf>,
+
+    /// Line offset of the trigger signal on `gpiochip`.
+    #[arg(long, alias = "pin", global = true)]
+    pub line_offset: Option<u32>,
+
+    /// Finds `gpiochip` by its device tree label (e.g. `"pinctrl-bcm2711"`)
+    /// instead of a hard-coded path, so the same config works across boards
+    /// whose chip numbering differs. Overrides `gpiochip` when set. See
+    /// [`crate::gpio_discovery`].
+    #[arg(long, global = true)]
+    pub gpiochip_label: Option<String>,
+
+    /// Finds the trigger line by its device tree name (e.g. `"GPIO18"`)
+    /// instead of a hard-coded `line_offset`, so the same config works
+    /// across boards whose line offsets differ. Overrides `line_offset`
+    /// when set. See [`crate::gpio_discovery`].
+    #[arg(long, global = true)]
+    pub line_name: Option<String>,
+
+    /// Consumer label the trigger line is requested under.
+    #[arg(long, global = true)]
+    pub consumer_label: Option<String>,
+
+    /// Internal pull resistor to request on the trigger line.
+    #[arg(long, global = true)]
+    pub line_bias: Option<LineBias>,
+
+    /// Request the trigger line as active-low, so the kernel reports a
+    /// pulled-low level as logical high. This is a hardware-level inversion
+    /// (the request's `active_low` setting) independent of `invert_polarity`,
+    /// which only changes how a pulse's already-reported edges are decoded.
+    #[arg(long, global = true)]
+    pub active_low: Option<bool>,
+
+    /// Kernel-side debounce period for the trigger line, in microseconds,
+    /// applied by the gpio uAPI v2 driver itself rather than in software.
+    /// Zero (the default) requests no debounce, matching the old v1 uAPI's
+    /// behavior. See [`crate::trigger_source::GpioTriggerSource`].
+    #[arg(long, global = true)]
+    pub debounce_period_micros: Option<u64>,
+
+    /// Timestamp trigger-line edges from `CLOCK_REALTIME` instead of the
+    /// default `CLOCK_MONOTONIC`, using the gpio uAPI v2 driver's own
+    /// realtime clock selection rather than [`crate::clock::RealtimeClock`]'s
+    /// sampled offset. The edge is still translated back to the monotonic
+    /// domain immediately on receipt (disciplining that offset in the
+    /// process), so nothing downstream of `GpioTriggerSource` needs to know;
+    /// this only matters if the two clocks can drift out of step with each
+    /// other faster than [`crate::clock::RealtimeClock`] resamples.
+    #[arg(long, global = true)]
+    pub event_clock_realtime: Option<bool>,
+
+    /// Minimum pulse width, in milliseconds, trusted as a real edge rather
+    /// than contact bounce.
+    #[arg(long, global = true)]
+    pub min_pulse_width_ms: Option<u64>,
+
+    /// Longest pulse width, in milliseconds, still decoded as a still-image
+    /// capture command rather than a video start/stop toggle.
+    #[arg(long, global = true)]
+    pub short_pulse_max_ms: Option<u64>,
+
+    /// Treat a falling edge as the start of a pulse and a rising edge as
+    /// its end, for airframes with an inverted trigger line.
+    #[arg(long, global = true)]
+    pub invert_polarity: Option<bool>,
+
+    /// Decode the trigger line as an RC/PWM signal (a 1-2ms pulse width
+    /// repeated at some fixed rate) instead of the still/toggle logic
+    /// above, for integrations that feed a PWM channel rather than a clean
+    /// logic edge. `min_pulse_width` should be set well under a millisecond
+    /// in this mode, since a real PWM pulse is much shorter than the
+    /// contact-bounce debounce the still/toggle logic expects.
+    #[arg(long, global = true)]
+    pub pwm_mode: Option<bool>,
+
+    /// A pulse at or above this width, in microseconds, is decoded as
+    /// "record". Only used if `pwm_mode` is set.
+    #[arg(long, global = true)]
+    pub pwm_record_above_us: Option<u64>,
+
+    /// A pulse at or below this width, in microseconds, is decoded as
+    /// "stop". Only used if `pwm_mode` is set. See
+    /// [`crate::trigger::PwmThresholds`] for the hysteresis band this and
+    /// `pwm_record_above_us` form.
+    #[arg(long, global = true)]
+    pub pwm_stop_below_us: Option<u64>,
+
+    /// Start recording as soon as the service is ready, without waiting for
+    /// a trigger edge, so a mission that must never miss the beginning
+    /// doesn't depend on the GPIO pulse arriving in time. The trigger line
+    /// still toggles the recording afterwards - stopping it on the first
+    /// edge, and starting a new one on the next - it just no longer has to
+    /// be the one to start the first recording.
+    #[arg(long, global = true)]
+    pub auto_start_recording: Option<bool>,
+
+    /// Replace the GPIO trigger line with a timer-based synthetic one, so
+    /// the recording path can be exercised on a laptop without
+    /// `/dev/gpiochip0`. See [`crate::main::run`].
+    #[arg(long, global = true)]
+    pub simulate: Option<bool>,
+
+    /// How often the simulated trigger toggles start/stop, in seconds. Only
+    /// used if `--simulate` is set.
+    #[arg(long, global = true)]
+    pub simulate_interval_secs: Option<u64>,
+
+    /// Replace the GPIO trigger line with a replay of a previously recorded
+    /// `trigger-events.csv` sidecar (see [`crate::trigger_log::TriggerLog`]),
+    /// re-issuing its events with their original inter-event timing, to
+    /// reproduce a field issue (double triggers, rapid sequences) on the
+    /// bench. Takes priority over `--simulate` if both are set. See
+    /// [`crate::trigger_source::ReplayTriggerSource`].
+    #[arg(long, global = true)]
+    pub replay_log: Option<PathBuf>,
+
+    /// Time between the start of one generated pulse and the next, in
+    /// milliseconds. Only used by `generate`. See
+    /// [`crate::trigger_generator`].
+    #[arg(long, global = true)]
+    pub generate_interval_ms: Option<u64>,
+
+    /// How long a generated pulse is held active before releasing it, in
+    /// milliseconds. Only used by `generate`.
+    #[arg(long, global = true)]
+    pub generate_pulse_width_ms: Option<u64>,
+
+    /// Number of pulses `generate` emits before exiting. Unset runs until
+    /// interrupted.
+    #[arg(long, global = true)]
+    pub generate_count: Option<u64>,
+
+    /// How long `bench` runs the recording pipeline for, in seconds. Only
+    /// used by `bench`.
+    #[arg(long, global = true)]
+    pub bench_duration_secs: Option<u64>,
+
+    /// Which recording implementation to use.
+    #[arg(long, global = true)]
+    pub backend: Option<RecordingBackend>,
+
+    /// Which GStreamer source element the recording pipeline is built
+    /// around. Only used by the `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub source: Option<CaptureSource>,
+
+    /// Capture device for the recording pipeline, e.g. `/dev/video0`.
+    #[arg(long, global = true)]
+    pub source_device: Option<PathBuf>,
+
+    /// Resolve `--source-device` by USB serial number instead. See
+    /// [`crate::usb_discovery`].
+    #[arg(long, global = true)]
+    pub usb_serial: Option<String>,
+
+    /// Resolve `--source-device` by USB port path instead. See
+    /// [`crate::usb_discovery`].
+    #[arg(long, global = true)]
+    pub usb_port_path: Option<String>,
+
+    /// Which video codec to encode recordings as. Only used by the
+    /// `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub codec: Option<VideoCodec>,
+
+    /// Which encoder implementation to use for `--codec`: `software`
+    /// (`x264enc`/`x265enc`) or `hardware` (the V4L2 stateful codec driver).
+    /// Only used by the `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub encoder: Option<Encoder>,
+
+    /// Imports capture buffers into `--encoder hardware`'s codec as DMABUFs
+    /// instead of copying each frame through `videoconvert`, to keep CPU
+    /// usage down at high resolutions. Only takes effect with the v4l2
+    /// capture source and `--encoder hardware`. Only used by the
+    /// `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub zero_copy_enabled: Option<bool>,
+
+    /// Requested capture width, checked against `--encoder hardware`'s
+    /// advertised capabilities at startup. Only used by the `gstreamer`
+    /// backend.
+    #[arg(long, global = true)]
+    pub capture_width: Option<u32>,
+
+    /// Requested capture height, checked against `--encoder hardware`'s
+    /// advertised capabilities at startup. Only used by the `gstreamer`
+    /// backend.
+    #[arg(long, global = true)]
+    pub capture_height: Option<u32>,
+
+    /// Requested capture framerate, in frames per second, checked against
+    /// `--encoder hardware`'s advertised capabilities at startup. Only used
+    /// by the `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub capture_framerate: Option<u32>,
+
+    /// Target video bitrate, in kbit/s, for `--codec`. Leaves the encoder
+    /// element at its own default bitrate when unset. Only used by the
+    /// `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub video_bitrate_kbps: Option<u32>,
+
+    /// Step `--video-bitrate-kbps` down toward `--adaptive-bitrate-min-kbps`
+    /// on dropped frames, and back up after `--adaptive-bitrate-recovery-secs`
+    /// without one, so a degraded SD card degrades quality instead of
+    /// dropping frames. Requires `--video-bitrate-kbps` to be set, and only
+    /// takes effect without `--preroll-duration-secs` set. Only used by the
+    /// `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub adaptive_bitrate_enabled: Option<bool>,
+
+    /// Floor for `--adaptive-bitrate-enabled`'s bitrate stepping, in kbit/s.
+    #[arg(long, global = true)]
+    pub adaptive_bitrate_min_kbps: Option<u32>,
+
+    /// How much to step the bitrate by, in kbit/s, per drop event or
+    /// recovery interval elapsed. Only used if `--adaptive-bitrate-enabled`
+    /// is set.
+    #[arg(long, global = true)]
+    pub adaptive_bitrate_step_kbps: Option<u32>,
+
+    /// How long the encoder must go without a reported drop before
+    /// `--adaptive-bitrate-enabled` steps the bitrate back up. Only used if
+    /// `--adaptive-bitrate-enabled` is set.
+    #[arg(long, global = true)]
+    pub adaptive_bitrate_recovery_secs: Option<u64>,
+
+    /// Which container to mux recordings into. `fragmented-mp4`/`matroska`
+    /// keep everything up to the last flushed fragment playable after an
+    /// unclean shutdown, at the cost of the file's index normally written
+    /// only at finalize time. Only used by the `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub container: Option<ContainerFormat>,
+
+    /// `libcamera-vid`/`rpicam-vid` binary to spawn. Only used by the
+    /// `libcamera-vid` backend.
+    #[arg(long, global = true)]
+    pub libcamera_vid_binary: Option<PathBuf>,
+
+    /// Sensor mode index to request from libcamera, e.g. a CSI sensor's
+    /// binned high-frame-rate mode. `None` leaves the choice to libcamera's
+    /// own pipeline handler. Only used by the `libcamera-native` backend.
+    #[arg(long, global = true)]
+    pub libcamera_sensor_mode: Option<u32>,
+
+    /// Whether to leave auto-exposure enabled. Only used by the
+    /// `libcamera-native` backend.
+    #[arg(long, global = true)]
+    pub libcamera_ae_enabled: Option<bool>,
+
+    /// Whether to leave auto white balance enabled. Only used by the
+    /// `libcamera-native` backend.
+    #[arg(long, global = true)]
+    pub libcamera_awb_enabled: Option<bool>,
+
+    /// Lower bound on frame duration (upper bound on frame rate), in
+    /// microseconds. Setting this equal to `--libcamera-max-frame-duration-micros`
+    /// pins the sensor to a fixed frame rate. Only used by the
+    /// `libcamera-native` backend.
+    #[arg(long, global = true)]
+    pub libcamera_min_frame_duration_micros: Option<u32>,
+
+    /// Upper bound on frame duration (lower bound on frame rate), in
+    /// microseconds. Only used by the `libcamera-native` backend.
+    #[arg(long, global = true)]
+    pub libcamera_max_frame_duration_micros: Option<u32>,
+
+    /// How many captured frames the `v4l2-direct`/`libcamera-native`
+    /// backends' dedicated writer thread may queue before newly-captured
+    /// frames are dropped instead of blocking capture.
+    #[arg(long, global = true)]
+    pub write_queue_depth: Option<usize>,
+
+    /// What the `v4l2-direct`/`libcamera-native`/`gige-vision` backends'
+    /// dedicated writer thread does to a newly-captured frame once
+    /// `write_queue_depth` is exceeded.
+    #[arg(long, global = true)]
+    pub backpressure_policy: Option<BackpressurePolicy>,
+
+    /// Directory finalized recordings are written into.
+    #[arg(long, alias = "output-dir", global = true)]
+    pub output_dir: Option<PathBuf>,
+
+    /// Also write every recording into this directory, e.g. a second SD
+    /// card or a USB SSD, so losing one medium in a hard landing doesn't
+    /// lose the footage. Unset (the default) disables the redundant copy.
+    /// Only the `v4l2-direct`, `libcamera-native` and `gige-vision` backends
+    /// support this.
+    #[arg(long, global = true)]
+    pub secondary_output_dir: Option<PathBuf>,
+
+    /// Age public key (`age1...`, as printed by `age-keygen`) that
+    /// recordings are encrypted to as they're written. Unset (the default)
+    /// writes recordings in the clear. Only the `v4l2-direct`,
+    /// `libcamera-native` and `gige-vision` backends support this; see
+    /// `cameras decrypt` for the ground-workstation side.
+    #[arg(long, global = true)]
+    pub encryption_recipient: Option<String>,
+
+    /// How to re-orient captured frames before encoding, e.g. for a camera
+    /// mounted upside down or sideways on this airframe.
+    #[arg(long, global = true)]
+    pub orientation: Option<Orientation>,
+
+    /// Pixels to crop from the left edge of the captured frame before
+    /// encoding, applied after `orientation`.
+    #[arg(long, global = true)]
+    pub crop_left: Option<u32>,
+
+    /// Pixels to crop from the right edge of the captured frame before
+    /// encoding, applied after `orientation`.
+    #[arg(long, global = true)]
+    pub crop_right: Option<u32>,
+
+    /// Pixels to crop from the top edge of the captured frame before
+    /// encoding, applied after `orientation`.
+    #[arg(long, global = true)]
+    pub crop_top: Option<u32>,
+
+    /// Pixels to crop from the bottom edge of the captured frame before
+    /// encoding, applied after `orientation`.
+    #[arg(long, global = true)]
+    pub crop_bottom: Option<u32>,
+
+    /// `splitmuxsink` location pattern, relative to `output_dir`.
+    #[arg(long, global = true)]
+    pub file_pattern: Option<String>,
+
+    /// Which strategy picks each recording's output path.
+    #[arg(long, global = true)]
+    pub naming: Option<NamingMode>,
+
+    /// Groups this run's recordings under `<output_dir>/<flight_session>`
+    /// when `--naming structured` is used. Defaults to today's UTC date plus
+    /// an incrementing flight index (see [`Config::load`]'s use of
+    /// `next_flight_session_id`), so restarts within the same physical
+    /// flight should usually set this explicitly to keep recordings grouped
+    /// together.
+    #[arg(long, global = true)]
+    pub flight_session: Option<String>,
+
+    /// Split a recording into fragments no longer than this many seconds,
+    /// so a crash or power loss only loses the most recent fragment. 0
+    /// (the default) disables splitting.
+    #[arg(long, global = true)]
+    pub segment_duration_secs: Option<u64>,
+
+    /// Keep this many seconds of footage from just before each trigger and
+    /// prepend it to the resulting recording. 0 (the default) disables
+    /// pre-roll. Only used by the `gstreamer` backend.
+    #[arg(long, global = true)]
+    pub preroll_duration_secs: Option<u64>,
+
+    /// If no new frame arrives from the capture pipeline for this many
+    /// seconds while a recording is armed, tear it down and rebuild it. 0
+    /// (the default) disables the check. Only used by the `gstreamer`
+    /// backend's non-preroll mode.
+    #[arg(long, global = true)]
+    pub frame_stall_timeout_secs: Option<u64>,
+
+    /// Hard cap, in seconds, on how long a single recording may run once
+    /// started; if no stop trigger arrives before then, it's finalized
+    /// automatically. 0 (the default) disables the cap.
+    #[arg(long, global = true)]
+    pub max_recording_duration_secs: Option<u64>,
+
+    /// Capture one throwaway frame at startup and verify it's nonempty with
+    /// plausible dimensions before this camera is considered ready. On by
+    /// default.
+    #[arg(long, global = true)]
+    pub self_test_enabled: Option<bool>,
+
+    /// If the startup self-test fails, skip this camera's recording pipeline
+    /// and keep running the others instead of aborting the whole process.
+    #[arg(long, global = true)]
+    pub self_test_degraded_on_failure: Option<bool>,
+
+    /// If building this camera's recording pipeline fails outright, enter a
+    /// degraded mode instead of aborting the whole process: keep subscribing
+    /// to the trigger line, keep logging its trigger events (with no
+    /// filename) and retrying camera bring-up in the background. See
+    /// [`crate::recorder::RecorderConfig::init_degraded_on_failure`].
+    #[arg(long, global = true)]
+    pub init_degraded_on_failure: Option<bool>,
+
+    /// How often to retry building this camera's recording pipeline while
+    /// degraded from an init failure. Only used if
+    /// `--init-degraded-on-failure` is set.
+    #[arg(long, global = true)]
+    pub init_retry_interval_secs: Option<u64>,
+
+    /// Capture a still image on a short trigger pulse instead of leaving it
+    /// unhandled. Independent of video recording.
+    #[arg(long, global = true)]
+    pub still_capture: Option<bool>,
+
+    /// How many frames to capture per still trigger, when `--still-capture`
+    /// is on.
+    #[arg(long, global = true)]
+    pub still_burst_count: Option<u32>,
+
+    /// Output file location pattern for still captures, relative to
+    /// `output_dir`.
+    #[arg(long, global = true)]
+    pub still_file_pattern: Option<String>,
+
+    /// Capture an auto-exposure bracket instead of `still_burst_count`
+    /// identical frames. Only the `libcamera-native` backend can drive
+    /// per-shot exposure.
+    #[arg(long, global = true)]
+    pub still_aeb_enabled: Option<bool>,
+
+    /// Comma-separated EV offsets to bracket across when
+    /// `--still-aeb-enabled` is on, e.g. `-2,0,2`.
+    #[arg(long, global = true)]
+    pub still_aeb_ev_stops: Option<String>,
+
+    /// Also save each still capture's raw Bayer sensor data as a `.dng`
+    /// alongside its JPEG, for radiometric post-processing. Only the
+    /// `libcamera-native` backend can drive a raw stream, and only for
+    /// sensor raw formats this crate knows how to unpack (8/16-bit and MIPI
+    /// RAW10/RAW12 packed Bayer).
+    #[arg(long, global = true)]
+    pub still_raw_enabled: Option<bool>,
+
+    /// Save each still capture as a 16-bit grayscale TIFF of the raw sensor
+    /// readout instead of a JPEG, for FLIR Boson/Lepton-style radiometric
+    /// thermal cameras where the normal AGC/YUV preview mode throws away the
+    /// actual per-pixel temperature data. Only the `v4l2-direct` backend can
+    /// switch the device into its raw `Y16` output mode.
+    #[arg(long, global = true)]
+    pub still_thermal_radiometric_enabled: Option<bool>,
+
+    /// Capture stills off the always-on preview/preroll capture pipeline's
+    /// tee instead of opening a second, independent pipeline against the
+    /// same device, so a camera can record continuous video and take
+    /// full-resolution triggered stills from the same sensor at once. Only
+    /// takes effect for the `gstreamer` backend with `preroll_duration` set.
+    #[arg(long, global = true)]
+    pub still_dual_stream_enabled: Option<bool>,
+
+    /// `libcamera-still`/`rpicam-still` binary to spawn for still captures.
+    /// Only used by the `libcamera-vid` backend.
+    #[arg(long, global = true)]
+    pub libcamera_still_binary: Option<PathBuf>,
+
+    /// Serve a low-bitrate RTSP preview of the camera feed at `/preview`.
+    /// Only takes effect for the `gstreamer` backend with
+    /// `--preroll-duration-secs` set.
+    #[arg(long, global = true)]
+    pub rtsp_preview_enabled: Option<bool>,
+
+    /// `host:port` to serve the RTSP preview on. Only used if
+    /// `--rtsp-preview-enabled` is set.
+    #[arg(long, global = true)]
+    pub rtsp_preview_address: Option<String>,
+
+    /// Bitrate, in kbps, of the RTSP preview encode.
+    #[arg(long, global = true)]
+    pub rtsp_preview_bitrate_kbps: Option<u32>,
+
+    /// Push a low-latency WebRTC preview of the camera feed to
+    /// `--webrtc-preview-whip-endpoint` via WHIP. Same capture-pipeline
+    /// requirement as `--rtsp-preview-enabled`.
+    #[arg(long, global = true)]
+    pub webrtc_preview_enabled: Option<bool>,
+
+    /// WHIP ingest URL to push the WebRTC preview to, e.g.
+    /// `http://10.0.0.1:8889/preview/whip`. Only used if
+    /// `--webrtc-preview-enabled` is set.
+    #[arg(long, global = true)]
+    pub webrtc_preview_whip_endpoint: Option<String>,
+
+    /// Bitrate, in kbps, of the WebRTC preview encode.
+    #[arg(long, global = true)]
+    pub webrtc_preview_bitrate_kbps: Option<u32>,
+
+    /// Simultaneously push the camera feed to the ground station over SRT,
+    /// with automatic reconnection over the long-range link.
+    #[arg(long, global = true)]
+    pub srt_output_enabled: Option<bool>,
+
+    /// `host:port` of the ground station's SRT listener. Only used if
+    /// `--srt-output-enabled` is set.
+    #[arg(long, global = true)]
+    pub srt_output_address: Option<String>,
+
+    /// Bitrate, in kbps, of the SRT output's encode, capped separately from
+    /// the full-quality recording's.
+    #[arg(long, global = true)]
+    pub srt_output_bitrate_kbps: Option<u32>,
+
+    /// Embed each frame's capture timestamp and trigger sequence as an SEI
+    /// NAL in the encoded bitstream. Only used by the gstreamer backend.
+    #[arg(long, global = true)]
+    pub embed_frame_metadata: Option<bool>,
+
+    /// Burn a telemetry overlay (timestamp, flight session id,
+    /// altitude/ground speed from MAVLink) into the enabled preview/output
+    /// branches, never into the archival recording. Only used by the
+    /// gstreamer backend.
+    #[arg(long, global = true)]
+    pub osd_overlay_enabled: Option<bool>,
+
+    /// How often the OSD overlay text refreshes, in seconds. Only used if
+    /// `osd_overlay_enabled` is set.
+    #[arg(long, global = true)]
+    pub osd_overlay_interval_secs: Option<f64>,
+
+    /// Capture an ALSA audio track alongside the video and mux it into the
+    /// same archival file. Only used by the gstreamer backend.
+    #[arg(long, global = true)]
+    pub audio_capture_enabled: Option<bool>,
+
+    /// ALSA device to capture from, e.g. `hw:1,0`. Only used if
+    /// `audio_capture_enabled` is set.
+    #[arg(long, global = true)]
+    pub audio_device: Option<String>,
+
+    /// Bitrate, in kbps, of the audio track's AAC encode. Only used if
+    /// `audio_capture_enabled` is set.
+    #[arg(long, global = true)]
+    pub audio_bitrate_kbps: Option<u32>,
+
+    /// Fix the exposure time, in microseconds, instead of leaving it on the
+    /// backend's auto-exposure. Only the `v4l2-direct` and `libcamera-native`
+    /// backends can drive this.
+    #[arg(long, global = true)]
+    pub exposure_micros: Option<u32>,
+
+    /// Fix the sensor/analogue gain instead of leaving it on auto. Same
+    /// backend support as `--exposure-micros`.
+    #[arg(long, global = true)]
+    pub gain: Option<f32>,
+
+    /// Fix the white balance colour temperature, in kelvin, instead of
+    /// leaving it on auto. Same backend support as `--exposure-micros`.
+    #[arg(long, global = true)]
+    pub white_balance_kelvin: Option<u32>,
+
+    /// Fix the focus position instead of leaving autofocus on. Units are
+    /// backend-specific (libcamera dioptres for `libcamera-native`, a raw
+    /// UVC focus step for `v4l2-direct`).
+    #[arg(long, global = true)]
+    pub focus_position: Option<f32>,
+
+    /// Publish `CAMERA_TRIGGER`/`CAMERA_IMAGE_CAPTURED` feedback to PX4 over
+    /// MAVLink.
+    #[arg(long, global = true)]
+    pub mavlink_enabled: Option<bool>,
+
+    /// MAVLink connection address, e.g. `udpout:127.0.0.1:14550` or
+    /// `serial:/dev/ttyS0:57600`. Only used if `--mavlink-enabled` is set.
+    #[arg(long, global = true)]
+    pub mavlink_address: Option<String>,
+
+    /// This component's MAVLink system ID.
+    #[arg(long, global = true)]
+    pub mavlink_system_id: Option<u8>,
+
+    /// This component's MAVLink component ID.
+    #[arg(long, global = true)]
+    pub mavlink_component_id: Option<u8>,
+
+    /// Ignore trigger edges while PX4 reports itself disarmed. Only used if
+    /// `--mavlink-enabled` is set.
+    #[arg(long, global = true)]
+    pub mavlink_require_armed: Option<bool>,
+
+    /// Also stop an in-progress recording the moment PX4 reports disarmed.
+    /// Only used if `--mavlink-require-armed` is also set.
+    #[arg(long, global = true)]
+    pub mavlink_auto_stop_on_disarm: Option<bool>,
+
+    /// Decode incoming `CAMERA_TRIGGER` messages (e.g. PX4's own
+    /// distance-based camera trigger driver) and `MAV_CMD_DO_DIGICAM_CONTROL`
+    /// commands (a GCS's manual trigger button) as this process's trigger
+    /// source, feeding the same [`crate::trigger::TriggerStateMachine`] a
+    /// physical GPIO pulse would, instead of requesting a trigger line at
+    /// all. For airframes with no spare GPIO wiring to the companion
+    /// computer. Only used if `--mavlink-enabled` is set; mutually exclusive
+    /// with `--simulate`/`--replay-log`. See also `--mavlink-trigger-fusion`
+    /// for combining this with a physical trigger line instead of replacing
+    /// it.
+    #[arg(long, global = true)]
+    pub mavlink_trigger_source: Option<bool>,
+
+    /// Like `--mavlink-trigger-source`, but combined with the physical
+    /// trigger line rather than instead of it: both feed the trigger state
+    /// machine, with the GPIO line taking priority whenever an edge from
+    /// each arrives within `--mavlink-trigger-fusion-dedup-window-ms` of the
+    /// other, so a GCS's manual digicam button and the real trigger line
+    /// don't double-trigger a single physical event. For airframes that
+    /// have the wiring but still want a MAVLink command as a backup trigger
+    /// path. Only used if `--mavlink-enabled` is set; mutually exclusive
+    /// with `--mavlink-trigger-source`/`--simulate`/`--replay-log`.
+    #[arg(long, global = true)]
+    pub mavlink_trigger_fusion: Option<bool>,
+
+    /// How close together an edge from the physical trigger line and one
+    /// decoded from MAVLink have to arrive to be treated as the same
+    /// physical event, so only the (higher-priority) GPIO edge is kept. Only
+    /// used if `--mavlink-trigger-fusion` is set.
+    #[arg(long, global = true)]
+    pub mavlink_trigger_fusion_dedup_window_ms: Option<u64>,
+
+    /// Ignore Start/CaptureStill trigger edges until relative altitude has
+    /// climbed above this many meters at least once since arming, and again
+    /// whenever it drops back below it - covering both ground handling
+    /// before takeoff and landing bounce pulses after touchdown. Unset (the
+    /// default) disables this gate. Only used if `--mavlink-enabled` is set.
+    #[arg(long, global = true)]
+    pub mavlink_min_altitude_gate_m: Option<f32>,
+
+    /// Also ignore Start/CaptureStill trigger edges while PX4 reports itself
+    /// in RTL (return-to-launch). Only used if `--mavlink-enabled` is set.
+    #[arg(long, global = true)]
+    pub mavlink_block_triggers_during_rtl: Option<bool>,
+
+    /// Discipline the local clock's monotonic-to-realtime offset against a
+    /// GPS pulse-per-second signal, for sub-millisecond accuracy
+    /// independent of NTP. See [`crate::pps`].
+    #[arg(long, global = true)]
+    pub pps_enabled: Option<bool>,
+
+    /// GPIO chip carrying the PPS line. Only used if `--pps-enabled` is set
+    /// and `--pps-line-offset` is also given.
+    #[arg(long, global = true)]
+    pub pps_gpiochip: Option<PathBuf>,
+
+    /// Line offset of the PPS signal on `--pps-gpiochip`.
+    #[arg(long, global = true)]
+    pub pps_line_offset: Option<u32>,
+
+    /// A LinuxPPS sysfs assert file (e.g. `/sys/class/pps/pps0/assert`) to
+    /// read pulses from instead of a GPIO line. Only used if
+    /// `--pps-gpiochip`/`--pps-line-offset` aren't both given.
+    #[arg(long, global = true)]
+    pub pps_device: Option<PathBuf>,
+
+    /// Stop recording if free space on a camera's output filesystem drops
+    /// below this many bytes.
+    #[arg(long, global = true)]
+    pub min_free_disk_bytes: Option<u64>,
+
+    /// Delete old completed flight-session directories, oldest first, to
+    /// stay within `--retention-max-bytes`/`--retention-min-free-bytes`.
+    /// Never deletes the current run's own session. See
+    /// [`crate::retention`].
+    #[arg(long, global = true)]
+    pub retention_enabled: Option<bool>,
+
+    /// Delete the oldest completed session once a camera's `output_dir`
+    /// exceeds this many bytes. Only used if `--retention-enabled` is set.
+    #[arg(long, global = true)]
+    pub retention_max_bytes: Option<u64>,
+
+    /// Delete the oldest completed session once free space on a camera's
+    /// output filesystem drops below this many bytes. Only used if
+    /// `--retention-enabled` is set.
+    #[arg(long, global = true)]
+    pub retention_min_free_bytes: Option<u64>,
+
+    /// Rotate the `trigger-events.csv` sidecar (gzip-compressing the closed
+    /// file) once it exceeds this many bytes. `None` (the default) never
+    /// rotates by size. See [`crate::trigger_log::TriggerLogConfig`].
+    #[arg(long, global = true)]
+    pub trigger_log_max_bytes: Option<u64>,
+
+    /// Rotate the `trigger-events.csv` sidecar (gzip-compressing the closed
+    /// file) once it has been open this many seconds, regardless of size.
+    /// `None` (the default) never rotates by age.
+    #[arg(long, global = true)]
+    pub trigger_log_max_age_secs: Option<u64>,
+
+    /// Serve a Unix-socket status query interface (and, if
+    /// `--status-file-path` is set, a periodic status file) for other
+    /// onboard services to read this process's state. See [`crate::status`].
+    #[arg(long, global = true)]
+    pub status_enabled: Option<bool>,
+
+    /// Filesystem path of the status socket. Only used if `--status-enabled`
+    /// is set.
+    #[arg(long, global = true)]
+    pub status_socket_path: Option<PathBuf>,
+
+    /// If set, the status JSON is also written to this path every
+    /// `--status-file-interval-secs`, e.g. on a tmpfs `/run` mount for a
+    /// reader that would rather poll a file than open a socket. Only used if
+    /// `--status-enabled` is set.
+    #[arg(long, global = true)]
+    pub status_file_path: Option<PathBuf>,
+
+    /// How often the status file is rewritten. Only used if
+    /// `--status-file-path` is also set.
+    #[arg(long, global = true)]
+    pub status_file_interval_secs: Option<u64>,
+
+    /// Sync an in-progress recording's file to disk at least this often,
+    /// bounding how much footage a power loss can cost to at most this
+    /// window. `None` (the default) never syncs on a timer. See
+    /// [`crate::durability::DurabilityConfig`].
+    #[arg(long, global = true)]
+    pub durability_interval_secs: Option<u64>,
+
+    /// Sync an in-progress recording's file to disk once it has grown by at
+    /// least this many bytes since the last sync. `None` (the default)
+    /// never syncs on size.
+    #[arg(long, global = true)]
+    pub durability_max_bytes: Option<u64>,
+
+    /// Serve a Prometheus metrics endpoint for an ops dashboard to scrape.
+    #[arg(long, global = true)]
+    pub metrics_enabled: Option<bool>,
+
+    /// `host:port` to serve the metrics endpoint on. Only used if
+    /// `--metrics-enabled` is set.
+    #[arg(long, global = true)]
+    pub metrics_address: Option<String>,
+
+    /// Serve an HTTP control API (`/start`, `/stop`, `/snapshot`,
+    /// `/status`) for manual control without GPIO access.
+    #[arg(long, global = true)]
+    pub control_api_enabled: Option<bool>,
+
+    /// `host:port` to serve the control API on. Only used if
+    /// `--control-api-enabled` is set.
+    #[arg(long, global = true)]
+    pub control_api_address: Option<String>,
+
+    /// Listen for small authenticated UDP start/stop/photo packets, so a
+    /// HIL simulation or an indoor bench test can drive the recorder with no
+    /// GPIO/MAVLink wiring at all. See [`crate::network_trigger`].
+    #[arg(long, global = true)]
+    pub network_trigger_enabled: Option<bool>,
+
+    /// `host:port` to listen for network trigger packets on. Only used if
+    /// `--network-trigger-enabled` is set.
+    #[arg(long, global = true)]
+    pub network_trigger_address: Option<String>,
+
+    /// Shared secret every network trigger packet must carry. Required if
+    /// `--network-trigger-enabled` is set.
+    #[arg(long, global = true)]
+    pub network_trigger_shared_secret: Option<String>,
+
+    /// Bridge camera trigger commands and capture feedback onto a
+    /// DroneCAN/UAVCAN bus over SocketCAN, for airframes with the payload
+    /// bay wired over CAN instead of GPIO. See [`crate::dronecan`].
+    #[arg(long, global = true)]
+    pub dronecan_enabled: Option<bool>,
+
+    /// SocketCAN interface to bridge on, e.g. `can0`. Only used if
+    /// `--dronecan-enabled` is set.
+    #[arg(long, global = true)]
+    pub dronecan_interface: Option<String>,
+
+    /// This node's ID, carried in every capture-feedback frame. Only used
+    /// if `--dronecan-enabled` is set.
+    #[arg(long, global = true)]
+    pub dronecan_node_id: Option<u8>,
+
+    /// Standard (11-bit) CAN ID trigger commands are received on. Only
+    /// used if `--dronecan-enabled` is set.
+    #[arg(long, global = true)]
+    pub dronecan_trigger_can_id: Option<u16>,
+
+    /// Standard (11-bit) CAN ID capture feedback is sent on. Only used if
+    /// `--dronecan-enabled` is set.
+    #[arg(long, global = true)]
+    pub dronecan_feedback_can_id: Option<u16>,
+
+    /// Publish recorder state changes, trigger events, and errors to an
+    /// MQTT broker, so an onboard telemetry aggregator can fuse recorder
+    /// status with other subsystems' data.
+    #[arg(long, global = true)]
+    pub mqtt_enabled: Option<bool>,
+
+    /// `host:port` of the MQTT broker. Only used if `--mqtt-enabled` is set.
+    #[arg(long, global = true)]
+    pub mqtt_address: Option<String>,
+
+    /// Prepended to every published MQTT topic, e.g. `"px4-camera-trigger"`
+    /// publishes recorder state to `px4-camera-trigger/state/<camera>`.
+    #[arg(long, global = true)]
+    pub mqtt_topic_prefix: Option<String>,
+
+    /// Register a D-Bus service exposing `StartRecording`/`StopRecording`/
+    /// `GetStatus` and a `StateChanged` signal, for `busctl` debugging and
+    /// other onboard services.
+    #[arg(long, global = true)]
+    pub dbus_enabled: Option<bool>,
+
+    /// The well-known bus name to reserve. Only used if `--dbus-enabled` is
+    /// set.
+    #[arg(long, global = true)]
+    pub dbus_service_name: Option<String>,
+
+    /// Take a systemd-logind shutdown inhibitor lock while a recording is
+    /// active, so an operator-initiated `poweroff`/`reboot` waits for it to
+    /// finalize instead of killing the process mid-write.
+    #[arg(long, global = true)]
+    pub shutdown_inhibitor_enabled: Option<bool>,
+
+    /// Serve a gRPC control service with unary start/stop/snapshot RPCs and
+    /// a server-streaming `StreamStatus` RPC, for the mission computer to
+    /// subscribe to recorder state without polling.
+    #[arg(long, global = true)]
+    pub grpc_enabled: Option<bool>,
+
+    /// `host:port` to serve the gRPC service on. Only used if
+    /// `--grpc-enabled` is set.
+    #[arg(long, global = true)]
+    pub grpc_address: Option<String>,
+
+    /// How often, in seconds, a `StreamStatus` subscriber receives a new
+    /// status update.
+    #[arg(long, global = true)]
+    pub grpc_status_interval_secs: Option<u64>,
+
+    /// Start a ROS 2 bridge node publishing trigger timestamps and
+    /// captured-image paths as topics, and offering start/stop as
+    /// `std_srvs/Trigger` services, for the perception stack.
+    #[arg(long, global = true)]
+    pub ros_enabled: Option<bool>,
+
+    /// The bridge node's name within its ROS 2 graph. Only used if
+    /// `--ros-enabled` is set.
+    #[arg(long, global = true)]
+    pub ros_node_name: Option<String>,
+
+    /// Drive a GPIO output line as a status LED: solid when idle-ready,
+    /// blinking while recording, fast-blinking once an error has occurred.
+    /// See [`crate::status_led`].
+    #[arg(long, global = true)]
+    pub status_led_enabled: Option<bool>,
+
+    /// GPIO chip carrying the status LED line. Only used if
+    /// `--status-led-enabled` is set and `--status-led-line-offset` is also
+    /// given.
+    #[arg(long, global = true)]
+    pub status_led_gpiochip: Option<PathBuf>,
+
+    /// Line offset of the status LED on `--status-led-gpiochip`.
+    #[arg(long, global = true)]
+    pub status_led_line_offset: Option<u32>,
+
+    /// Pulse a GPIO output line the instant a frame is actually captured,
+    /// for wiring to PX4's hardware camera-feedback input. See
+    /// [`crate::capture_feedback`].
+    #[arg(long, global = true)]
+    pub capture_feedback_enabled: Option<bool>,
+
+    /// GPIO chip carrying the capture-feedback line. Only used if
+    /// `--capture-feedback-enabled` is set and
+    /// `--capture-feedback-line-offset` is also given.
+    #[arg(long, global = true)]
+    pub capture_feedback_gpiochip: Option<PathBuf>,
+
+    /// Line offset of the capture-feedback pulse on
+    /// `--capture-feedback-gpiochip`.
+    #[arg(long, global = true)]
+    pub capture_feedback_line_offset: Option<u32>,
+
+    /// Drive a GPIO-attached buzzer with a distinct beep pattern for
+    /// recording started/stopped/error. See [`crate::buzzer`].
+    #[arg(long, global = true)]
+    pub buzzer_enabled: Option<bool>,
+
+    /// GPIO chip carrying the buzzer line. Only used if `--buzzer-enabled`
+    /// is set and `--buzzer-line-offset` is also given.
+    #[arg(long, global = true)]
+    pub buzzer_gpiochip: Option<PathBuf>,
+
+    /// Line offset of the buzzer on `--buzzer-gpiochip`.
+    #[arg(long, global = true)]
+    pub buzzer_line_offset: Option<u32>,
+
+    /// Once a trigger `Start` arms the session, capture stills on a fixed
+    /// cadence until the matching `Stop`, independent of further GPIO
+    /// pulses. See [`crate::intervalometer`].
+    #[arg(long, global = true)]
+    pub intervalometer_enabled: Option<bool>,
+
+    /// Capture every this many seconds of wall-clock time. Only used if
+    /// `--intervalometer-enabled` is set; takes priority over
+    /// `--intervalometer-distance-meters` if both are given.
+    #[arg(long, global = true)]
+    pub intervalometer_interval_secs: Option<f64>,
+
+    /// Capture every time the vehicle has moved at least this many meters,
+    /// per MAVLink `GLOBAL_POSITION_INT`. Only used if
+    /// `--intervalometer-enabled` is set and `--intervalometer-interval-secs`
+    /// isn't.
+    #[arg(long, global = true)]
+    pub intervalometer_distance_meters: Option<f64>,
+
+    /// While recording, write a `.srt` sidecar with periodic GPS/altitude/
+    /// heading cues from MAVLink, so a reviewer can see where the aircraft
+    /// was for any given frame in a standard video player. See
+    /// [`crate::subtitle_log`].
+    #[arg(long, global = true)]
+    pub subtitle_enabled: Option<bool>,
+
+    /// How often a subtitle cue is written. Only used if
+    /// `--subtitle-enabled` is set.
+    #[arg(long, global = true)]
+    pub subtitle_interval_secs: Option<f64>,
+
+    /// Periodically check SoC temperature and Raspberry Pi throttling flags,
+    /// warning as they approach limits that tend to cause dropped frames.
+    #[arg(long, global = true)]
+    pub thermal_enabled: Option<bool>,
+
+    /// Warn once SoC temperature reaches this many degrees Celsius. Only
+    /// used if `--thermal-enabled` is set.
+    #[arg(long, global = true)]
+    pub thermal_warn_temp_celsius: Option<f32>,
+
+    /// Stop the active recording as soon as the Pi firmware reports the 5V
+    /// rail is currently below its brownout threshold, so the current
+    /// segment is finalized before a brownout takes the companion computer
+    /// down mid-write. Only used if `--thermal-enabled` is set.
+    #[arg(long, global = true)]
+    pub thermal_finalize_on_undervoltage: Option<bool>,
+
+    /// Periodically check the recording medium's SMART (NVMe/USB-SATA) or
+    /// eMMC/SD wear indicators, warning before a flight when it's near end
+    /// of life. See [`crate::storage_health`].
+    #[arg(long, global = true)]
+    pub storage_health_enabled: Option<bool>,
+
+    /// Block device backing the recording output directory, e.g.
+    /// `/dev/mmcblk0` or `/dev/nvme0n1`. Required if
+    /// `--storage-health-enabled` is set.
+    #[arg(long, global = true)]
+    pub storage_health_device: Option<PathBuf>,
+
+    /// Warn once the recording medium's reported wear reaches this
+    /// percentage of its rated life. Only used if `--storage-health-enabled`
+    /// is set.
+    #[arg(long, global = true)]
+    pub storage_health_warn_percent_used: Option<u8>,
+
+    /// Before the first recording, check that the system realtime clock is
+    /// synchronized (via `timedatectl`), warning loudly if it isn't since
+    /// geotagging depends on it.
+    #[arg(long, global = true)]
+    pub time_sync_check_enabled: Option<bool>,
+
+    /// Offload completed sessions to a ground station over the WiFi link on
+    /// disarm or `/offload`. See [`crate::offload`].
+    #[arg(long, global = true)]
+    pub offload_enabled: Option<bool>,
+
+    /// `user@host` rsync/ssh connect to for offload. Required if
+    /// `--offload-enabled` is set.
+    #[arg(long, global = true)]
+    pub offload_ground_host: Option<String>,
+
+    /// Destination directory on the offload ground host.
+    #[arg(long, global = true)]
+    pub offload_remote_dir: Option<PathBuf>,
+
+    /// `ssh -i` private key path for the offload ground host.
+    #[arg(long, global = true)]
+    pub offload_ssh_key_path: Option<PathBuf>,
+
+    /// `rsync --bwlimit` in KB/s for offload transfers. Unlimited if unset.
+    #[arg(long, global = true)]
+    pub offload_bandwidth_limit_kbps: Option<u32>,
+
+    /// Delete a recording's local copy once offload has verified its
+    /// transfer against the manifest. A file that fails verification is
+    /// always kept.
+    #[arg(long, global = true)]
+    pub offload_delete_after_verified: Option<bool>,
+}
+
+/// Mirrors [`Cli`]'s overridable fields for deserializing a `--config` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileConfig {
+    pidfile: Option<PathBuf>,
+    device_ready_timeout_secs: Option<u64>,
+    storage_mount_point: Option<PathBuf>,
+    storage_min_free_bytes: Option<u64>,
+    gpiochip: Option<PathBuf>,
+    line_offset: Option<u32>,
+    gpiochip_label: Option<String>,
+    line_name: Option<String>,
+    consumer_label: Option<String>,
+    line_bias: Option<LineBias>,
+    active_low: Option<bool>,
+    debounce_period_micros: Option<u64>,
+    event_clock_realtime: Option<bool>,
+    min_pulse_width_ms: Option<u64>,
+    short_pulse_max_ms: Option<u64>,
+    invert_polarity: Option<bool>,
+    pwm_mode: Option<bool>,
+    pwm_record_above_us: Option<u64>,
+    pwm_stop_below_us: Option<u64>,
+    auto_start_recording: Option<bool>,
+    simulate: Option<bool>,
+    simulate_interval_secs: Option<u64>,
+    replay_log: Option<PathBuf>,
+    generate_interval_ms: Option<u64>,
+    generate_pulse_width_ms: Option<u64>,
+    generate_count: Option<u64>,
+    bench_duration_secs: Option<u64>,
+    backend: Option<RecordingBackend>,
+    source: Option<CaptureSource>,
+    source_device: Option<PathBuf>,
+    usb_serial: Option<String>,
+    usb_port_path: Option<String>,
+    codec: Option<VideoCodec>,
+    encoder: Option<Encoder>,
+    zero_copy_enabled: Option<bool>,
+    capture_width: Option<u32>,
+    capture_height: Option<u32>,
+    capture_framerate: Option<u32>,
+    video_bitrate_kbps: Option<u32>,
+    adaptive_bitrate_enabled: Option<bool>,
+    adaptive_bitrate_min_kbps: Option<u32>,
+    adaptive_bitrate_step_kbps: Option<u32>,
+    adaptive_bitrate_recovery_secs: Option<u64>,
+    container: Option<ContainerFormat>,
+    libcamera_vid_binary: Option<PathBuf>,
+    libcamera_sensor_mode: Option<u32>,
+    libcamera_ae_enabled: Option<bool>,
+    libcamera_awb_enabled: Option<bool>,
+    libcamera_min_frame_duration_micros: Option<u32>,
+    libcamera_max_frame_duration_micros: Option<u32>,
+    write_queue_depth: Option<usize>,
+    backpressure_policy: Option<BackpressurePolicy>,
+    output_dir: Option<PathBuf>,
+    secondary_output_dir: Option<PathBuf>,
+    encryption_recipient: Option<String>,
+    orientation: Option<Orientation>,
+    crop_left: Option<u32>,
+    crop_right: Option<u32>,
+    crop_top: Option<u32>,
+    crop_bottom: Option<u32>,
+    file_pattern: Option<String>,
+    naming: Option<NamingMode>,
+    flight_session: Option<String>,
+    segment_duration_secs: Option<u64>,
+    preroll_duration_secs: Option<u64>,
+    frame_stall_timeout_secs: Option<u64>,
+    max_recording_duration_secs: Option<u64>,
+    self_test_enabled: Option<bool>,
+    self_test_degraded_on_failure: Option<bool>,
+    init_degraded_on_failure: Option<bool>,
+    init_retry_interval_secs: Option<u64>,
+    still_capture: Option<bool>,
+    still_burst_count: Option<u32>,
+    still_file_pattern: Option<String>,
+    still_aeb_enabled: Option<bool>,
+    still_aeb_ev_stops: Option<String>,
+    still_raw_enabled: Option<bool>,
+    still_thermal_radiometric_enabled: Option<bool>,
+    still_dual_stream_enabled: Option<bool>,
+    libcamera_still_binary: Option<PathBuf>,
+    rtsp_preview_enabled: Option<bool>,
+    rtsp_preview_address: Option<String>,
+    rtsp_preview_bitrate_kbps: Option<u32>,
+    webrtc_preview_enabled: Option<bool>,
+    webrtc_preview_whip_endpoint: Option<String>,
+    webrtc_preview_bitrate_kbps: Option<u32>,
+    srt_output_enabled: Option<bool>,
+    srt_output_address: Option<String>,
+    srt_output_bitrate_kbps: Option<u32>,
+    embed_frame_metadata: Option<bool>,
+    osd_overlay_enabled: Option<bool>,
+    osd_overlay_interval_secs: Option<f64>,
+    audio_capture_enabled: Option<bool>,
+    audio_device: Option<String>,
+    audio_bitrate_kbps: Option<u32>,
+    exposure_micros: Option<u32>,
+    gain: Option<f32>,
+    white_balance_kelvin: Option<u32>,
+    focus_position: Option<f32>,
+    mavlink_enabled: Option<bool>,
+    mavlink_address: Option<String>,
+    mavlink_system_id: Option<u8>,
+    mavlink_component_id: Option<u8>,
+    mavlink_require_armed: Option<bool>,
+    mavlink_auto_stop_on_disarm: Option<bool>,
+    mavlink_trigger_source: Option<bool>,
+    mavlink_trigger_fusion: Option<bool>,
+    mavlink_trigger_fusion_dedup_window_ms: Option<u64>,
+    mavlink_min_altitude_gate_m: Option<f32>,
+    mavlink_block_triggers_during_rtl: Option<bool>,
+    pps_enabled: Option<bool>,
+    pps_gpiochip: Option<PathBuf>,
+    pps_line_offset: Option<u32>,
+    pps_device: Option<PathBuf>,
+    min_free_disk_bytes: Option<u64>,
+    retention_enabled: Option<bool>,
+    retention_max_bytes: Option<u64>,
+    retention_min_free_bytes: Option<u64>,
+    trigger_log_max_bytes: Option<u64>,
+    trigger_log_max_age_secs: Option<u64>,
+    status_enabled: Option<bool>,
+    status_socket_path: Option<PathBuf>,
+    status_file_path: Option<PathBuf>,
+    status_file_interval_secs: Option<u64>,
+    durability_interval_secs: Option<u64>,
+    durability_max_bytes: Option<u64>,
+    metrics_enabled: Option<bool>,
+    metrics_address: Option<String>,
+    control_api_enabled: Option<bool>,
+    control_api_address: Option<String>,
+    network_trigger_enabled: Option<bool>,
+    network_trigger_address: Option<String>,
+    network_trigger_shared_secret: Option<String>,
+    dronecan_enabled: Option<bool>,
+    dronecan_interface: Option<String>,
+    dronecan_node_id: Option<u8>,
+    dronecan_trigger_can_id: Option<u16>,
+    dronecan_feedback_can_id: Option<u16>,
+    mqtt_enabled: Option<bool>,
+    mqtt_address: Option<String>,
+    mqtt_topic_prefix: Option<String>,
+    dbus_enabled: Option<bool>,
+    dbus_service_name: Option<String>,
+    shutdown_inhibitor_enabled: Option<bool>,
+    privsep_enabled: Option<bool>,
+    privsep_user: Option<String>,
+    grpc_enabled: Option<bool>,
+    grpc_address: Option<String>,
+    grpc_status_interval_secs: Option<u64>,
+    ros_enabled: Option<bool>,
+    ros_node_name: Option<String>,
+    status_led_enabled: Option<bool>,
+    status_led_gpiochip: Option<PathBuf>,
+    status_led_line_offset: Option<u32>,
+    capture_feedback_enabled: Option<bool>,
+    capture_feedback_gpiochip: Option<PathBuf>,
+    capture_feedback_line_offset: Option<u32>,
+    buzzer_enabled: Option<bool>,
+    buzzer_gpiochip: Option<PathBuf>,
+    buzzer_line_offset: Option<u32>,
+    intervalometer_enabled: Option<bool>,
+    intervalometer_interval_secs: Option<f64>,
+    intervalometer_distance_meters: Option<f64>,
+    subtitle_enabled: Option<bool>,
+    subtitle_interval_secs: Option<f64>,
+    thermal_enabled: Option<bool>,
+    thermal_warn_temp_celsius: Option<f32>,
+    thermal_finalize_on_undervoltage: Option<bool>,
+    storage_health_enabled: Option<bool>,
+    storage_health_device: Option<PathBuf>,
+    storage_health_warn_percent_used: Option<u8>,
+    time_sync_check_enabled: Option<bool>,
+    offload_enabled: Option<bool>,
+    offload_ground_host: Option<String>,
+    offload_remote_dir: Option<PathBuf>,
+    offload_ssh_key_path: Option<PathBuf>,
+    offload_bandwidth_limit_kbps: Option<u32>,
+    offload_delete_after_verified: Option<bool>,
+    /// Additional cameras beyond the primary one described by the fields
+    /// above, e.g. a `[[cameras]]` array of tables in the TOML file. There's
+    /// no CLI/env equivalent since flags don't have a natural way to name an
+    /// arbitrary-length list of cameras.
+    #[serde(default)]
+    cameras: Vec<ExtraCameraFileConfig>,
+    /// Extra GPIO lines beyond the primary trigger line, e.g. a
+    /// `[[aux-lines]]` array of tables in the TOML file. Same reasoning as
+    /// `cameras` for having no CLI/env equivalent.
+    #[serde(default)]
+    aux_lines: Vec<AuxLineFileConfig>,
+}
+
+/// One entry of an `[[aux-lines]]` array in the config file, describing a
+/// GPIO line beyond the primary trigger line.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct AuxLineFileConfig {
+    /// Overrides the top-level `gpiochip` for this one line. See
+    /// [`AuxLineConfig::gpiochip`].
+    gpiochip: Option<PathBuf>,
+    line_offset: u32,
+    action: LineAction,
+    label: Option<String>,
+}
+
+/// One entry of a `[[cameras]]` array in the config file, describing a
+/// camera beyond the primary one.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ExtraCameraFileConfig {
+    /// Distinguishes this camera in logs, e.g. `"nadir"` or `"oblique"`.
+    name: String,
+    backend: Option<RecordingBackend>,
+    source: Option<CaptureSource>,
+    source_device: PathBuf,
+    usb_serial: Option<String>,
+    usb_port_path: Option<String>,
+    codec: Option<VideoCodec>,
+    encoder: Option<Encoder>,
+    zero_copy_enabled: Option<bool>,
+    capture_width: Option<u32>,
+    capture_height: Option<u32>,
+    capture_framerate: Option<u32>,
+    video_bitrate_kbps: Option<u32>,
+    adaptive_bitrate_enabled: Option<bool>,
+    adaptive_bitrate_min_kbps: Option<u32>,
+    adaptive_bitrate_step_kbps: Option<u32>,
+    adaptive_bitrate_recovery_secs: Option<u64>,
+    container: Option<ContainerFormat>,
+    output_dir: Option<PathBuf>,
+    secondary_output_dir: Option<PathBuf>,
+    encryption_recipient: Option<String>,
+    orientation: Option<Orientation>,
+    crop_left: Option<u32>,
+    crop_right: Option<u32>,
+    crop_top: Option<u32>,
+    crop_bottom: Option<u32>,
+    file_pattern: Option<String>,
+    naming: Option<NamingMode>,
+    segment_duration_secs: Option<u64>,
+    preroll_duration_secs: Option<u64>,
+    frame_stall_timeout_secs: Option<u64>,
+    max_recording_duration_secs: Option<u64>,
+    self_test_enabled: Option<bool>,
+    self_test_degraded_on_failure: Option<bool>,
+    init_degraded_on_failure: Option<bool>,
+    init_retry_interval_secs: Option<u64>,
+    still_capture: Option<bool>,
+    still_burst_count: Option<u32>,
+    still_file_pattern: Option<String>,
+    still_aeb_enabled: Option<bool>,
+    still_aeb_ev_stops: Option<String>,
+    still_raw_enabled: Option<bool>,
+    still_thermal_radiometric_enabled: Option<bool>,
+    still_dual_stream_enabled: Option<bool>,
+    libcamera_still_binary: Option<PathBuf>,
+    libcamera_vid_binary: Option<PathBuf>,
+    libcamera_sensor_mode: Option<u32>,
+    libcamera_ae_enabled: Option<bool>,
+    libcamera_awb_enabled: Option<bool>,
+    libcamera_min_frame_duration_micros: Option<u32>,
+    libcamera_max_frame_duration_micros: Option<u32>,
+    write_queue_depth: Option<usize>,
+    backpressure_policy: Option<BackpressurePolicy>,
+    rtsp_preview_enabled: Option<bool>,
+    rtsp_preview_address: Option<String>,
+    rtsp_preview_bitrate_kbps: Option<u32>,
+    webrtc_preview_enabled: Option<bool>,
+    webrtc_preview_whip_endpoint: Option<String>,
+    webrtc_preview_bitrate_kbps: Option<u32>,
+    srt_output_enabled: Option<bool>,
+    srt_output_address: Option<String>,
+    srt_output_bitrate_kbps: Option<u32>,
+    embed_frame_metadata: Option<bool>,
+    osd_overlay_enabled: Option<bool>,
+    osd_overlay_interval_secs: Option<f64>,
+    audio_capture_enabled: Option<bool>,
+    audio_device: Option<String>,
+    audio_bitrate_kbps: Option<u32>,
+    exposure_micros: Option<u32>,
+    gain: Option<f32>,
+    white_balance_kelvin: Option<u32>,
+    focus_position: Option<f32>,
+}
+
+/// A named camera and the pipeline config it records with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraConfig {
+    pub name: String,
+    pub recorder: RecorderConfig,
+}
+
+/// Fully resolved configuration the rest of the program runs with.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Locked (via `flock`) and stamped with this process's PID for as long
+    /// as it's running, so a second instance started alongside a systemd
+    /// unit (or a stray manual run) fails fast instead of fighting the first
+    /// one over the GPIO line and camera devices. See
+    /// [`crate::single_instance`].
+    pub pidfile: PathBuf,
+    /// How long to wait at startup for `recorder.source_device` to appear
+    /// and, if `storage_mount_point` is set, for it to be mounted.
+    /// [`Duration::ZERO`] (the default) disables the wait. See
+    /// [`crate::device_wait`].
+    pub device_ready_timeout: Duration,
+    /// Directory expected to be a mount point, checked alongside
+    /// `device_ready_timeout`. See [`crate::device_wait::wait_for_mount`].
+    pub storage_mount_point: Option<PathBuf>,
+    /// Minimum free space `storage_mount_point` must report at startup. `0`
+    /// (the default) disables the check. See
+    /// [`crate::device_wait::check_free_space`].
+    pub storage_min_free_bytes: u64,
+    pub gpiochip: PathBuf,
+    pub line_offset: u32,
+    /// If set, resolved to `gpiochip` at startup by device tree label
+    /// instead of using the configured path. See [`crate::gpio_discovery`].
+    pub gpiochip_label: Option<String>,
+    /// If set, resolved to `line_offset` at startup by device tree line name
+    /// instead of using the configured offset. See [`crate::gpio_discovery`].
+    pub line_name: Option<String>,
+    pub consumer_label: String,
+    /// Internal pull resistor to request on the trigger line. See
+    /// [`LineBias`].
+    pub line_bias: LineBias,
+    /// Requests the trigger line as active-low. See [`Cli::active_low`].
+    pub active_low: bool,
+    /// Kernel-side debounce period for the trigger line, requested via the
+    /// gpio uAPI v2 driver. [`Duration::ZERO`] (the default) requests no
+    /// debounce. See [`Cli::debounce_period_micros`].
+    pub debounce_period: Duration,
+    /// Timestamps trigger-line edges from `CLOCK_REALTIME` instead of
+    /// `CLOCK_MONOTONIC`. See [`Cli::event_clock_realtime`].
+    pub event_clock_realtime: bool,
+    pub min_pulse_width: Duration,
+    /// Longest pulse still decoded as a still-image capture command rather
+    /// than a video start/stop toggle. See [`crate::trigger::Transition`].
+    pub short_pulse_max: Duration,
+    pub invert_polarity: bool,
+    /// Decode the trigger line as RC/PWM instead of still/toggle logic. See
+    /// [`Cli::pwm_mode`].
+    pub pwm_mode: bool,
+    /// See [`crate::trigger::PwmThresholds::record_above`] and
+    /// [`Cli::pwm_record_above_us`].
+    pub pwm_record_above: Duration,
+    /// See [`crate::trigger::PwmThresholds::stop_below`] and
+    /// [`Cli::pwm_stop_below_us`].
+    pub pwm_stop_below: Duration,
+    /// Start recording immediately on startup instead of waiting for a
+    /// trigger edge. See [`Cli::auto_start_recording`] and
+    /// [`crate::main::run`].
+    pub auto_start_recording: bool,
+    /// Replace the GPIO trigger line with a timer-based synthetic one. See
+    /// [`crate::main::run`].
+    pub simulate: bool,
+    /// How often the simulated trigger toggles start/stop. Only used if
+    /// `simulate` is set.
+    pub simulate_interval: Duration,
+    /// Replay a previously recorded trigger event log instead of reading
+    /// the GPIO line. Takes priority over `simulate` if both are set. See
+    /// [`Cli::replay_log`].
+    pub replay_log: Option<PathBuf>,
+    /// Rate/pattern for the `generate` command's output pulses. See
+    /// [`crate::trigger_generator`].
+    pub generate: GeneratorConfig,
+    /// How long the `bench` command runs the recording pipeline for. See
+    /// [`crate::main::bench`].
+    pub bench_duration: Duration,
+    /// The primary (and, on a single-camera setup, only) camera's pipeline
+    /// config, overridable via CLI flags/env/file.
+    pub recorder: RecorderConfig,
+    /// Cameras beyond the primary one, only configurable via a `[[cameras]]`
+    /// table in the config file. See [`Config::cameras`].
+    extra_cameras: Vec<CameraConfig>,
+    /// Extra GPIO lines beyond the primary trigger line, each mapped to a
+    /// fixed action, only configurable via an `[[aux-lines]]` table in the
+    /// config file. See [`crate::aux_lines`].
+    pub aux_lines: Vec<AuxLineConfig>,
+    /// PX4 capture feedback, published over MAVLink.
+    pub mavlink: MavlinkConfig,
+    /// GPS PPS-disciplined clock. See [`crate::pps`].
+    pub pps: PpsConfig,
+    /// Recording is stopped if free space on a camera's output filesystem
+    /// drops below this many bytes.
+    pub min_free_disk_bytes: u64,
+    /// Old completed flight sessions are deleted, oldest first, to enforce a
+    /// size/free-space quota. See [`crate::retention`].
+    pub retention: RetentionConfig,
+    /// Rotation/compression of the `trigger-events.csv` sidecar. See
+    /// [`crate::trigger_log::TriggerLogConfig`].
+    pub trigger_log: TriggerLogConfig,
+    /// Unix-socket status query interface and periodic status file. See
+    /// [`crate::status`].
+    pub status: StatusConfig,
+    /// Periodic fsync of an in-progress recording. See
+    /// [`crate::durability::DurabilityConfig`].
+    pub durability: DurabilityConfig,
+    /// Prometheus metrics endpoint for an ops dashboard to scrape.
+    pub metrics: MetricsConfig,
+    /// HTTP control API for manual start/stop/snapshot without GPIO access.
+    pub control_api: ControlApiConfig,
+    /// UDP start/stop/photo trigger for HIL/bench tests with no GPIO or
+    /// MAVLink wiring at all. See [`crate::network_trigger`].
+    pub network_trigger: NetworkTriggerConfig,
+    /// DroneCAN/UAVCAN trigger command and capture feedback bridge over
+    /// SocketCAN, for a payload bay wired over CAN instead of GPIO. See
+    /// [`crate::dronecan`].
+    pub dronecan: DroneCanConfig,
+    /// MQTT status/event publisher for fusing recorder state into an
+    /// onboard telemetry aggregator. See [`crate::mqtt`].
+    pub mqtt: MqttConfig,
+    /// D-Bus control/introspection service for `busctl` and other onboard
+    /// services. See [`crate::dbus_api`].
+    pub dbus: DbusConfig,
+    /// Drops root and every Linux capability once startup is done acquiring
+    /// resources. See [`crate::privsep`].
+    pub privsep: PrivsepConfig,
+    /// gRPC control service with a streaming status RPC, for the mission
+    /// computer. See [`crate::grpc_api`].
+    pub grpc: GrpcConfig,
+    /// ROS 2 bridge node for the perception stack. See
+    /// [`crate::ros2_bridge`].
+    pub ros: RosConfig,
+    /// Status LED reflecting idle/recording/error state. See
+    /// [`crate::status_led`].
+    pub status_led: StatusLedConfig,
+    /// Hardware capture-feedback pulse to PX4 on every real frame capture.
+    /// See [`crate::capture_feedback`].
+    pub capture_feedback: CaptureFeedbackConfig,
+    /// Audible buzzer beep patterns for recording started/stopped/error. See
+    /// [`crate::buzzer`].
+    pub buzzer: BuzzerConfig,
+    /// Timelapse/survey still capture on a fixed cadence once armed. See
+    /// [`crate::intervalometer`].
+    pub intervalometer: IntervalometerConfig,
+    /// Telemetry subtitle (`.srt`) sidecar written alongside each recording.
+    /// See [`crate::subtitle_log`].
+    pub subtitle: SubtitleConfig,
+    /// Periodic SoC temperature/throttling monitoring. See
+    /// [`crate::thermal`].
+    pub thermal: ThermalConfig,
+    /// Periodic recording medium wear monitoring. See
+    /// [`crate::storage_health`].
+    pub storage_health: StorageHealthConfig,
+    /// Pre-flight system clock sync check. See [`crate::time_sync_check`].
+    pub time_sync_check: TimeSyncConfig,
+    /// Post-flight offload to a ground station. See [`crate::offload`].
+    pub offload: OffloadConfig,
+    /// systemd-logind shutdown inhibitor lock held while a recording is
+    /// active, so an operator-initiated `poweroff`/`reboot` waits for it to
+    /// finalize. See [`crate::shutdown_inhibitor`].
+    pub shutdown_inhibitor: ShutdownInhibitorConfig,
+    /// Groups this run's recordings when [`RecorderConfig::naming`] is
+    /// [`NamingMode::Structured`]. Defaults to today's UTC date plus an
+    /// incrementing flight index; see [`Cli::flight_session`].
+    pub flight_session: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let recorder = RecorderConfig::default();
+
+        Self {
+            pidfile: PathBuf::from("/run/px4-camera-trigger.pid"),
+            device_ready_timeout: Duration::ZERO,
+            storage_mount_point: None,
+            storage_min_free_bytes: 0,
+            gpiochip: PathBuf::from("/dev/gpiochip0"),
+            line_offset: 18,
+            gpiochip_label: None,
+            line_name: None,
+            consumer_label: "px4-camera-trigger-gpio".to_string(),
+            line_bias: LineBias::Disabled,
+            active_low: false,
+            debounce_period: Duration::ZERO,
+            event_clock_realtime: false,
+            min_pulse_width: Duration::from_millis(10),
+            short_pulse_max: Duration::from_millis(50),
+            invert_polarity: false,
+            pwm_mode: false,
+            pwm_record_above: Duration::from_micros(1800),
+            pwm_stop_below: Duration::from_micros(1200),
+            auto_start_recording: false,
+            simulate: false,
+            simulate_interval: Duration::from_secs(10),
+            replay_log: None,
+            generate: GeneratorConfig::default(),
+            bench_duration: Duration::from_secs(30),
+            recorder,
+            extra_cameras: Vec::new(),
+            aux_lines: Vec::new(),
+            mavlink: MavlinkConfig::default(),
+            pps: PpsConfig::default(),
+            min_free_disk_bytes: 500_000_000,
+            retention: RetentionConfig::default(),
+            trigger_log: TriggerLogConfig::default(),
+            status: StatusConfig::default(),
+            durability: DurabilityConfig::default(),
+            metrics: MetricsConfig::default(),
+            control_api: ControlApiConfig::default(),
+            network_trigger: NetworkTriggerConfig::default(),
+            dronecan: DroneCanConfig::default(),
+            mqtt: MqttConfig::default(),
+            dbus: DbusConfig::default(),
+            privsep: PrivsepConfig::default(),
+            grpc: GrpcConfig::default(),
+            ros: RosConfig::default(),
+            status_led: StatusLedConfig::default(),
+            capture_feedback: CaptureFeedbackConfig::default(),
+            buzzer: BuzzerConfig::default(),
+            intervalometer: IntervalometerConfig::default(),
+            subtitle: SubtitleConfig::default(),
+            thermal: ThermalConfig::default(),
+            storage_health: StorageHealthConfig::default(),
+            time_sync_check: TimeSyncConfig::default(),
+            offload: OffloadConfig::default(),
+            shutdown_inhibitor: ShutdownInhibitorConfig::default(),
+            // Left empty; `Config::load` fills in a timestamp-based default
+            // once resolution is complete, since generating one here would
+            // make every unconfigured `Config::default()` call (including
+            // in tests) time-dependent.
+            flight_session: String::new(),
+        }
+    }
+}
+
+const DEFAULT_CONFIG_PATH: &str = "/etc/px4-camera-trigger/config.toml";
+
+/// Picks the next flight session id for `output_dir`: today's UTC date plus
+/// an incrementing index, e.g. `2026-08-08-1`, `2026-08-08-2`. The index is
+/// derived from existing `<date>-<n>` session directories already under
+/// `output_dir` rather than a separate counter file, so a fresh `output_dir`
+/// (a new SD card, a wiped rig) naturally restarts at 1 instead of carrying
+/// over a number from state that no longer exists.
+fn next_flight_session_id(output_dir: &Path) -> String {
+    let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let prefix = format!("{date}-");
+
+    let highest_existing = std::fs::read_dir(output_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| name.strip_prefix(&prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+
+    format!("{prefix}{}", highest_existing + 1)
+}
+
+impl Config {
+    /// Every camera the same trigger edge should start/stop, primary first.
+    /// A single-camera deployment (the common case) gets one entry back.
+    pub fn cameras(&self) -> Vec<CameraConfig> {
+        std::iter::once(CameraConfig {
+            name: "primary".to_string(),
+            recorder: self.recorder.clone(),
+        })
+        .chain(self.extra_cameras.iter().cloned())
+        .collect()
+    }
+
+    /// The gpio uAPI v2 line settings to request the trigger line with:
+    /// both-edge detection plus `line_bias`/`active_low`/`debounce_period`/
+    /// `event_clock_realtime`. Only the trigger line is requested through v2
+    /// (for `debounce_period` and `event_clock_realtime`, neither of which
+    /// the `gpio_cdev` v1 ABI exposes); every other GPIO line this process
+    /// touches (aux lines, capture feedback, PPS, status LED) still uses v1
+    /// flags built inline where they're requested, since none of them need
+    /// hardware debounce or non-default event clocks. Shared by the initial
+    /// request and every re-request [`crate::supervisor::LineSupervisor`]
+    /// makes after recovering a dropped line, so a recovery never silently
+    /// drops the requested settings.
+    pub fn trigger_line_config(&self) -> gpiocdev::line::Config {
+        let mut config = gpiocdev::line::Config::default();
+        config.direction = Some(gpiocdev::line::Direction::Input);
+        config.edge_detection = Some(gpiocdev::line::EdgeDetection::BothEdges);
+        config.active_low = self.active_low;
+        config.bias = self.line_bias.v2_bias();
+        if !self.debounce_period.is_zero() {
+            config.debounce_period = Some(self.debounce_period);
+        }
+        config.event_clock = self
+            .event_clock_realtime
+            .then_some(gpiocdev::line::EventClock::Realtime);
+        config
+    }
+
+    /// Resolves a [`Config`] from the file (if any), environment and CLI
+    /// flags in `cli`, falling back to defaults for anything left unset.
+    ///
+    /// If `--config` isn't given, `--instance <name>` (if given) makes
+    /// `/etc/px4-camera-trigger/<name>.toml` the default instead of
+    /// [`DEFAULT_CONFIG_PATH`], and `pidfile`'s default is likewise
+    /// instance-scoped; see [`Cli::instance`]. Either default is only read
+    /// if it exists, so a config file dropped at the conventional location
+    /// is picked up without every deployment needing to pass the flag
+    /// explicitly. Exits the process with a log message if a config path
+    /// (explicit or default) exists but cannot be read or parsed.
+    pub fn load(cli: Cli) -> Self {
+        let mut config = Self::default();
+
+        if let Some(instance) = &cli.instance {
+            config.pidfile = PathBuf::from(format!("/run/px4-camera-trigger-{instance}.pid"));
+        }
+
+        let path = cli.config.clone().or_else(|| {
+            let instance_path = cli
+                .instance
+                .as_ref()
+                .map(|instance| PathBuf::from(format!("/etc/px4-camera-trigger/{instance}.toml")))
+                .filter(|path| path.exists());
+            instance_path.or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_PATH)).filter(|path| path.exists()))
+        });
+
+        if let Some(path) = path {
+            match Self::read_file(&path) {
+                Ok(file) => config.apply_file(file),
+                Err(error) => {
+                    crate::exit_code::ExitReason::Config
+                        .exit(&format!("failed to load config file {}: {error}", path.display()));
+                }
+            }
+        }
+
+        config.apply_env();
+        config.apply_cli(cli);
+
+        if config.flight_session.is_empty() {
+            config.flight_session = next_flight_session_id(&config.recorder.output_dir);
+        }
+
+        config
+    }
+
+    fn read_file(path: &Path) -> Result<FileConfig, String> {
+        let contents = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        toml::from_str(&contents).map_err(|error| error.to_string())
+    }
+
+    fn apply_file(&mut self, file: FileConfig) {
+        if let Some(pidfile) = file.pidfile {
+            self.pidfile = pidfile;
+        }
+        if let Some(device_ready_timeout_secs) = file.device_ready_timeout_secs {
+            self.device_ready_timeout = Duration::from_secs(device_ready_timeout_secs);
+        }
+        if let Some(storage_mount_point) = file.storage_mount_point {
+            self.storage_mount_point = Some(storage_mount_point);
+        }
+        if let Some(storage_min_free_bytes) = file.storage_min_free_bytes {
+            self.storage_min_free_bytes = storage_min_free_bytes;
+        }
+        if let Some(gpiochip) = file.gpiochip {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = file.line_offset {
+            self.line_offset = line_offset;
+        }
+        if let Some(gpiochip_label) = file.gpiochip_label {
+            self.gpiochip_label = Some(gpiochip_label);
+        }
+        if let Some(line_name) = file.line_name {
+            self.line_name = Some(line_name);
+        }
+        if let Some(consumer_label) = file.consumer_label {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(line_bias) = file.line_bias {
+            self.line_bias = line_bias;
+        }
+        if let Some(active_low) = file.active_low {
+            self.active_low = active_low;
+        }
+        if let Some(debounce_period_micros) = file.debounce_period_micros {
+            self.debounce_period = Duration::from_micros(debounce_period_micros);
+        }
+        if let Some(event_clock_realtime) = file.event_clock_realtime {
+            self.event_clock_realtime = event_clock_realtime;
+        }
+        if let Some(min_pulse_width_ms) = file.min_pulse_width_ms {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(short_pulse_max_ms) = file.short_pulse_max_ms {
+            self.short_pulse_max = Duration::from_millis(short_pulse_max_ms);
+        }
+        if let Some(invert_polarity) = file.invert_polarity {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(pwm_mode) = file.pwm_mode {
+            self.pwm_mode = pwm_mode;
+        }
+        if let Some(pwm_record_above_us) = file.pwm_record_above_us {
+            self.pwm_record_above = Duration::from_micros(pwm_record_above_us);
+        }
+        if let Some(pwm_stop_below_us) = file.pwm_stop_below_us {
+            self.pwm_stop_below = Duration::from_micros(pwm_stop_below_us);
+        }
+        if let Some(auto_start_recording) = file.auto_start_recording {
+            self.auto_start_recording = auto_start_recording;
+        }
+        if let Some(simulate) = file.simulate {
+            self.simulate = simulate;
+        }
+        if let Some(simulate_interval_secs) = file.simulate_interval_secs {
+            self.simulate_interval = Duration::from_secs(simulate_interval_secs);
+        }
+        if let Some(replay_log) = file.replay_log {
+            self.replay_log = Some(replay_log);
+        }
+        if let Some(generate_interval_ms) = file.generate_interval_ms {
+            self.generate.interval = Duration::from_millis(generate_interval_ms);
+        }
+        if let Some(generate_pulse_width_ms) = file.generate_pulse_width_ms {
+            self.generate.pulse_width = Duration::from_millis(generate_pulse_width_ms);
+        }
+        if let Some(generate_count) = file.generate_count {
+            self.generate.count = Some(generate_count);
+        }
+        if let Some(bench_duration_secs) = file.bench_duration_secs {
+            self.bench_duration = Duration::from_secs(bench_duration_secs);
+        }
+        if let Some(backend) = file.backend {
+            self.recorder.backend = backend;
+        }
+        if let Some(source) = file.source {
+            self.recorder.source = source;
+        }
+        if let Some(source_device) = file.source_device {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(usb_serial) = file.usb_serial {
+            self.recorder.usb_serial = Some(usb_serial);
+        }
+        if let Some(usb_port_path) = file.usb_port_path {
+            self.recorder.usb_port_path = Some(usb_port_path);
+        }
+        if let Some(codec) = file.codec {
+            self.recorder.codec = codec;
+        }
+        if let Some(encoder) = file.encoder {
+            self.recorder.encoder = encoder;
+        }
+        if let Some(zero_copy_enabled) = file.zero_copy_enabled {
+            self.recorder.zero_copy_enabled = zero_copy_enabled;
+        }
+        if let Some(capture_width) = file.capture_width {
+            self.recorder.capture_width = Some(capture_width);
+        }
+        if let Some(capture_height) = file.capture_height {
+            self.recorder.capture_height = Some(capture_height);
+        }
+        if let Some(capture_framerate) = file.capture_framerate {
+            self.recorder.capture_framerate = Some(capture_framerate);
+        }
+        if let Some(video_bitrate_kbps) = file.video_bitrate_kbps {
+            self.recorder.video_bitrate_kbps = Some(video_bitrate_kbps);
+        }
+        if let Some(adaptive_bitrate_enabled) = file.adaptive_bitrate_enabled {
+            self.recorder.adaptive_bitrate_enabled = adaptive_bitrate_enabled;
+        }
+        if let Some(adaptive_bitrate_min_kbps) = file.adaptive_bitrate_min_kbps {
+            self.recorder.adaptive_bitrate_min_kbps = adaptive_bitrate_min_kbps;
+        }
+        if let Some(adaptive_bitrate_step_kbps) = file.adaptive_bitrate_step_kbps {
+            self.recorder.adaptive_bitrate_step_kbps = adaptive_bitrate_step_kbps;
+        }
+        if let Some(adaptive_bitrate_recovery_secs) = file.adaptive_bitrate_recovery_secs {
+            self.recorder.adaptive_bitrate_recovery_secs = adaptive_bitrate_recovery_secs;
+        }
+        if let Some(container) = file.container {
+            self.recorder.container = container;
+        }
+        if let Some(libcamera_vid_binary) = file.libcamera_vid_binary {
+            self.recorder.libcamera_vid_binary = libcamera_vid_binary;
+        }
+        if let Some(libcamera_sensor_mode) = file.libcamera_sensor_mode {
+            self.recorder.libcamera_sensor_mode = Some(libcamera_sensor_mode);
+        }
+        if let Some(libcamera_ae_enabled) = file.libcamera_ae_enabled {
+            self.recorder.libcamera_ae_enabled = libcamera_ae_enabled;
+        }
+        if let Some(libcamera_awb_enabled) = file.libcamera_awb_enabled {
+            self.recorder.libcamera_awb_enabled = libcamera_awb_enabled;
+        }
+        if let Some(libcamera_min_frame_duration_micros) = file.libcamera_min_frame_duration_micros {
+            self.recorder.libcamera_min_frame_duration_micros = Some(libcamera_min_frame_duration_micros);
+        }
+        if let Some(libcamera_max_frame_duration_micros) = file.libcamera_max_frame_duration_micros {
+            self.recorder.libcamera_max_frame_duration_micros = Some(libcamera_max_frame_duration_micros);
+        }
+        if let Some(write_queue_depth) = file.write_queue_depth {
+            self.recorder.write_queue_depth = write_queue_depth;
+        }
+        if let Some(backpressure_policy) = file.backpressure_policy {
+            self.recorder.backpressure_policy = backpressure_policy;
+        }
+        if let Some(output_dir) = file.output_dir {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(secondary_output_dir) = file.secondary_output_dir {
+            self.recorder.secondary_output_dir = Some(secondary_output_dir);
+        }
+        if let Some(encryption_recipient) = file.encryption_recipient {
+            self.recorder.encryption_recipient = Some(encryption_recipient);
+        }
+        if let Some(orientation) = file.orientation {
+            self.recorder.orientation = orientation;
+        }
+        if let Some(crop_left) = file.crop_left {
+            self.recorder.crop_left = crop_left;
+        }
+        if let Some(crop_right) = file.crop_right {
+            self.recorder.crop_right = crop_right;
+        }
+        if let Some(crop_top) = file.crop_top {
+            self.recorder.crop_top = crop_top;
+        }
+        if let Some(crop_bottom) = file.crop_bottom {
+            self.recorder.crop_bottom = crop_bottom;
+        }
+        if let Some(file_pattern) = file.file_pattern {
+            self.recorder.file_pattern = file_pattern;
+        }
+        if let Some(naming) = file.naming {
+            self.recorder.naming = naming;
+        }
+        if let Some(flight_session) = file.flight_session {
+            self.flight_session = flight_session;
+        }
+        if let Some(segment_duration_secs) = file.segment_duration_secs {
+            self.recorder.segment_duration = Duration::from_secs(segment_duration_secs);
+        }
+        if let Some(preroll_duration_secs) = file.preroll_duration_secs {
+            self.recorder.preroll_duration = Duration::from_secs(preroll_duration_secs);
+        }
+        if let Some(frame_stall_timeout_secs) = file.frame_stall_timeout_secs {
+            self.recorder.frame_stall_timeout = Duration::from_secs(frame_stall_timeout_secs);
+        }
+        if let Some(max_recording_duration_secs) = file.max_recording_duration_secs {
+            self.recorder.max_recording_duration = Duration::from_secs(max_recording_duration_secs);
+        }
+        if let Some(self_test_enabled) = file.self_test_enabled {
+            self.recorder.self_test_enabled = self_test_enabled;
+        }
+        if let Some(self_test_degraded_on_failure) = file.self_test_degraded_on_failure {
+            self.recorder.self_test_degraded_on_failure = self_test_degraded_on_failure;
+        }
+        if let Some(init_degraded_on_failure) = file.init_degraded_on_failure {
+            self.recorder.init_degraded_on_failure = init_degraded_on_failure;
+        }
+        if let Some(init_retry_interval_secs) = file.init_retry_interval_secs {
+            self.recorder.init_retry_interval = Duration::from_secs(init_retry_interval_secs);
+        }
+        if let Some(still_capture) = file.still_capture {
+            self.recorder.still_capture = still_capture;
+        }
+        if let Some(still_burst_count) = file.still_burst_count {
+            self.recorder.still_burst_count = still_burst_count;
+        }
+        if let Some(still_file_pattern) = file.still_file_pattern {
+            self.recorder.still_file_pattern = still_file_pattern;
+        }
+        if let Some(still_aeb_enabled) = file.still_aeb_enabled {
+            self.recorder.still_aeb_enabled = still_aeb_enabled;
+        }
+        if let Some(still_aeb_ev_stops) = file.still_aeb_ev_stops {
+            self.recorder.still_aeb_ev_stops = still_aeb_ev_stops;
+        }
+        if let Some(still_raw_enabled) = file.still_raw_enabled {
+            self.recorder.still_raw_enabled = still_raw_enabled;
+        }
+        if let Some(still_thermal_radiometric_enabled) = file.still_thermal_radiometric_enabled {
+            self.recorder.still_thermal_radiometric_enabled = still_thermal_radiometric_enabled;
+        }
+        if let Some(still_dual_stream_enabled) = file.still_dual_stream_enabled {
+            self.recorder.still_dual_stream_enabled = still_dual_stream_enabled;
+        }
+        if let Some(libcamera_still_binary) = file.libcamera_still_binary {
+            self.recorder.libcamera_still_binary = libcamera_still_binary;
+        }
+        if let Some(rtsp_preview_enabled) = file.rtsp_preview_enabled {
+            self.recorder.rtsp_preview_enabled = rtsp_preview_enabled;
+        }
+        if let Some(rtsp_preview_address) = file.rtsp_preview_address {
+            self.recorder.rtsp_preview_address = rtsp_preview_address;
+        }
+        if let Some(rtsp_preview_bitrate_kbps) = file.rtsp_preview_bitrate_kbps {
+            self.recorder.rtsp_preview_bitrate_kbps = rtsp_preview_bitrate_kbps;
+        }
+        if let Some(webrtc_preview_enabled) = file.webrtc_preview_enabled {
+            self.recorder.webrtc_preview_enabled = webrtc_preview_enabled;
+        }
+        if let Some(webrtc_preview_whip_endpoint) = file.webrtc_preview_whip_endpoint {
+            self.recorder.webrtc_preview_whip_endpoint = webrtc_preview_whip_endpoint;
+        }
+        if let Some(webrtc_preview_bitrate_kbps) = file.webrtc_preview_bitrate_kbps {
+            self.recorder.webrtc_preview_bitrate_kbps = webrtc_preview_bitrate_kbps;
+        }
+        if let Some(srt_output_enabled) = file.srt_output_enabled {
+            self.recorder.srt_output_enabled = srt_output_enabled;
+        }
+        if let Some(srt_output_address) = file.srt_output_address {
+            self.recorder.srt_output_address = srt_output_address;
+        }
+        if let Some(srt_output_bitrate_kbps) = file.srt_output_bitrate_kbps {
+            self.recorder.srt_output_bitrate_kbps = srt_output_bitrate_kbps;
+        }
+        if let Some(embed_frame_metadata) = file.embed_frame_metadata {
+            self.recorder.embed_frame_metadata = embed_frame_metadata;
+        }
+        if let Some(osd_overlay_enabled) = file.osd_overlay_enabled {
+            self.recorder.osd_overlay_enabled = osd_overlay_enabled;
+        }
+        if let Some(osd_overlay_interval_secs) = file.osd_overlay_interval_secs {
+            self.recorder.osd_overlay_interval_secs = osd_overlay_interval_secs;
+        }
+        if let Some(audio_capture_enabled) = file.audio_capture_enabled {
+            self.recorder.audio_capture_enabled = audio_capture_enabled;
+        }
+        if let Some(audio_device) = file.audio_device {
+            self.recorder.audio_device = audio_device;
+        }
+        if let Some(audio_bitrate_kbps) = file.audio_bitrate_kbps {
+            self.recorder.audio_bitrate_kbps = audio_bitrate_kbps;
+        }
+        if let Some(exposure_micros) = file.exposure_micros {
+            self.recorder.initial_controls.exposure_micros = Some(exposure_micros);
+        }
+        if let Some(gain) = file.gain {
+            self.recorder.initial_controls.gain = Some(gain);
+        }
+        if let Some(white_balance_kelvin) = file.white_balance_kelvin {
+            self.recorder.initial_controls.white_balance_kelvin = Some(white_balance_kelvin);
+        }
+        if let Some(focus_position) = file.focus_position {
+            self.recorder.initial_controls.focus_position = Some(focus_position);
+        }
+        if let Some(mavlink_enabled) = file.mavlink_enabled {
+            self.mavlink.enabled = mavlink_enabled;
+        }
+        if let Some(mavlink_address) = file.mavlink_address {
+            self.mavlink.address = mavlink_address;
+        }
+        if let Some(mavlink_system_id) = file.mavlink_system_id {
+            self.mavlink.system_id = mavlink_system_id;
+        }
+        if let Some(mavlink_component_id) = file.mavlink_component_id {
+            self.mavlink.component_id = mavlink_component_id;
+        }
+        if let Some(mavlink_require_armed) = file.mavlink_require_armed {
+            self.mavlink.require_armed = mavlink_require_armed;
+        }
+        if let Some(mavlink_auto_stop_on_disarm) = file.mavlink_auto_stop_on_disarm {
+            self.mavlink.auto_stop_on_disarm = mavlink_auto_stop_on_disarm;
+        }
+        if let Some(mavlink_trigger_source) = file.mavlink_trigger_source {
+            self.mavlink.trigger_source = mavlink_trigger_source;
+        }
+        if let Some(mavlink_trigger_fusion) = file.mavlink_trigger_fusion {
+            self.mavlink.trigger_fusion = mavlink_trigger_fusion;
+        }
+        if let Some(mavlink_trigger_fusion_dedup_window_ms) = file.mavlink_trigger_fusion_dedup_window_ms {
+            self.mavlink.trigger_fusion_dedup_window = Duration::from_millis(mavlink_trigger_fusion_dedup_window_ms);
+        }
+        if let Some(mavlink_min_altitude_gate_m) = file.mavlink_min_altitude_gate_m {
+            self.mavlink.min_altitude_gate_m = Some(mavlink_min_altitude_gate_m);
+        }
+        if let Some(mavlink_block_triggers_during_rtl) = file.mavlink_block_triggers_during_rtl {
+            self.mavlink.block_triggers_during_rtl = mavlink_block_triggers_during_rtl;
+        }
+        if let Some(pps_enabled) = file.pps_enabled {
+            self.pps.enabled = pps_enabled;
+        }
+        if let Some(pps_gpiochip) = file.pps_gpiochip {
+            self.pps.gpiochip = Some(pps_gpiochip);
+        }
+        if let Some(pps_line_offset) = file.pps_line_offset {
+            self.pps.line_offset = Some(pps_line_offset);
+        }
+        if let Some(pps_device) = file.pps_device {
+            self.pps.device = Some(pps_device);
+        }
+        if let Some(min_free_disk_bytes) = file.min_free_disk_bytes {
+            self.min_free_disk_bytes = min_free_disk_bytes;
+        }
+        if let Some(retention_enabled) = file.retention_enabled {
+            self.retention.enabled = retention_enabled;
+        }
+        if let Some(retention_max_bytes) = file.retention_max_bytes {
+            self.retention.max_bytes = Some(retention_max_bytes);
+        }
+        if let Some(retention_min_free_bytes) = file.retention_min_free_bytes {
+            self.retention.min_free_bytes = Some(retention_min_free_bytes);
+        }
+        if let Some(trigger_log_max_bytes) = file.trigger_log_max_bytes {
+            self.trigger_log.max_bytes = Some(trigger_log_max_bytes);
+        }
+        if let Some(trigger_log_max_age_secs) = file.trigger_log_max_age_secs {
+            self.trigger_log.max_age = Some(Duration::from_secs(trigger_log_max_age_secs));
+        }
+        if let Some(status_enabled) = file.status_enabled {
+            self.status.enabled = status_enabled;
+        }
+        if let Some(status_socket_path) = file.status_socket_path {
+            self.status.socket_path = status_socket_path;
+        }
+        if let Some(status_file_path) = file.status_file_path {
+            self.status.file_path = Some(status_file_path);
+        }
+        if let Some(status_file_interval_secs) = file.status_file_interval_secs {
+            self.status.file_interval = Duration::from_secs(status_file_interval_secs);
+        }
+        if let Some(durability_interval_secs) = file.durability_interval_secs {
+            self.durability.interval = Some(Duration::from_secs(durability_interval_secs));
+        }
+        if let Some(durability_max_bytes) = file.durability_max_bytes {
+            self.durability.max_bytes = Some(durability_max_bytes);
+        }
+        if let Some(metrics_enabled) = file.metrics_enabled {
+            self.metrics.enabled = metrics_enabled;
+        }
+        if let Some(metrics_address) = file.metrics_address {
+            self.metrics.address = metrics_address;
+        }
+        if let Some(control_api_enabled) = file.control_api_enabled {
+            self.control_api.enabled = control_api_enabled;
+        }
+        if let Some(control_api_address) = file.control_api_address {
+            self.control_api.address = control_api_address;
+        }
+        if let Some(network_trigger_enabled) = file.network_trigger_enabled {
+            self.network_trigger.enabled = network_trigger_enabled;
+        }
+        if let Some(network_trigger_address) = file.network_trigger_address {
+            self.network_trigger.address = network_trigger_address;
+        }
+        if let Some(network_trigger_shared_secret) = file.network_trigger_shared_secret {
+            self.network_trigger.shared_secret = network_trigger_shared_secret;
+        }
+        if let Some(dronecan_enabled) = file.dronecan_enabled {
+            self.dronecan.enabled = dronecan_enabled;
+        }
+        if let Some(dronecan_interface) = file.dronecan_interface {
+            self.dronecan.interface = dronecan_interface;
+        }
+        if let Some(dronecan_node_id) = file.dronecan_node_id {
+            self.dronecan.node_id = dronecan_node_id;
+        }
+        if let Some(dronecan_trigger_can_id) = file.dronecan_trigger_can_id {
+            self.dronecan.trigger_can_id = dronecan_trigger_can_id;
+        }
+        if let Some(dronecan_feedback_can_id) = file.dronecan_feedback_can_id {
+            self.dronecan.feedback_can_id = dronecan_feedback_can_id;
+        }
+        if let Some(mqtt_enabled) = file.mqtt_enabled {
+            self.mqtt.enabled = mqtt_enabled;
+        }
+        if let Some(mqtt_address) = file.mqtt_address {
+            self.mqtt.address = mqtt_address;
+        }
+        if let Some(mqtt_topic_prefix) = file.mqtt_topic_prefix {
+            self.mqtt.topic_prefix = mqtt_topic_prefix;
+        }
+        if let Some(dbus_enabled) = file.dbus_enabled {
+            self.dbus.enabled = dbus_enabled;
+        }
+        if let Some(dbus_service_name) = file.dbus_service_name {
+            self.dbus.service_name = dbus_service_name;
+        }
+        if let Some(shutdown_inhibitor_enabled) = file.shutdown_inhibitor_enabled {
+            self.shutdown_inhibitor.enabled = shutdown_inhibitor_enabled;
+        }
+        if let Some(privsep_enabled) = file.privsep_enabled {
+            self.privsep.enabled = privsep_enabled;
+        }
+        if let Some(privsep_user) = file.privsep_user {
+            self.privsep.user = Some(privsep_user);
+        }
+        if let Some(grpc_enabled) = file.grpc_enabled {
+            self.grpc.enabled = grpc_enabled;
+        }
+        if let Some(grpc_address) = file.grpc_address {
+            self.grpc.address = grpc_address;
+        }
+        if let Some(grpc_status_interval_secs) = file.grpc_status_interval_secs {
+            self.grpc.status_interval = Duration::from_secs(grpc_status_interval_secs);
+        }
+        if let Some(ros_enabled) = file.ros_enabled {
+            self.ros.enabled = ros_enabled;
+        }
+        if let Some(ros_node_name) = file.ros_node_name {
+            self.ros.node_name = ros_node_name;
+        }
+        if let Some(status_led_enabled) = file.status_led_enabled {
+            self.status_led.enabled = status_led_enabled;
+        }
+        if let Some(status_led_gpiochip) = file.status_led_gpiochip {
+            self.status_led.gpiochip = Some(status_led_gpiochip);
+        }
+        if let Some(status_led_line_offset) = file.status_led_line_offset {
+            self.status_led.line_offset = Some(status_led_line_offset);
+        }
+        if let Some(capture_feedback_enabled) = file.capture_feedback_enabled {
+            self.capture_feedback.enabled = capture_feedback_enabled;
+        }
+        if let Some(capture_feedback_gpiochip) = file.capture_feedback_gpiochip {
+            self.capture_feedback.gpiochip = Some(capture_feedback_gpiochip);
+        }
+        if let Some(capture_feedback_line_offset) = file.capture_feedback_line_offset {
+            self.capture_feedback.line_offset = Some(capture_feedback_line_offset);
+        }
+        if let Some(buzzer_enabled) = file.buzzer_enabled {
+            self.buzzer.enabled = buzzer_enabled;
+        }
+        if let Some(buzzer_gpiochip) = file.buzzer_gpiochip {
+            self.buzzer.gpiochip = Some(buzzer_gpiochip);
+        }
+        if let Some(buzzer_line_offset) = file.buzzer_line_offset {
+            self.buzzer.line_offset = Some(buzzer_line_offset);
+        }
+        if let Some(intervalometer_enabled) = file.intervalometer_enabled {
+            self.intervalometer.enabled = intervalometer_enabled;
+        }
+        if let Some(intervalometer_interval_secs) = file.intervalometer_interval_secs {
+            self.intervalometer.interval_secs = Some(intervalometer_interval_secs);
+        }
+        if let Some(intervalometer_distance_meters) = file.intervalometer_distance_meters {
+            self.intervalometer.distance_meters = Some(intervalometer_distance_meters);
+        }
+        if let Some(subtitle_enabled) = file.subtitle_enabled {
+            self.subtitle.enabled = subtitle_enabled;
+        }
+        if let Some(subtitle_interval_secs) = file.subtitle_interval_secs {
+            self.subtitle.interval_secs = subtitle_interval_secs;
+        }
+        if let Some(thermal_enabled) = file.thermal_enabled {
+            self.thermal.enabled = thermal_enabled;
+        }
+        if let Some(thermal_warn_temp_celsius) = file.thermal_warn_temp_celsius {
+            self.thermal.warn_temp_celsius = thermal_warn_temp_celsius;
+        }
+        if let Some(thermal_finalize_on_undervoltage) = file.thermal_finalize_on_undervoltage {
+            self.thermal.finalize_on_undervoltage = thermal_finalize_on_undervoltage;
+        }
+        if let Some(storage_health_enabled) = file.storage_health_enabled {
+            self.storage_health.enabled = storage_health_enabled;
+        }
+        if let Some(storage_health_device) = file.storage_health_device {
+            self.storage_health.device = Some(storage_health_device);
+        }
+        if let Some(storage_health_warn_percent_used) = file.storage_health_warn_percent_used {
+            self.storage_health.warn_percent_used = storage_health_warn_percent_used;
+        }
+        if let Some(time_sync_check_enabled) = file.time_sync_check_enabled {
+            self.time_sync_check.enabled = time_sync_check_enabled;
+        }
+        if let Some(offload_enabled) = file.offload_enabled {
+            self.offload.enabled = offload_enabled;
+        }
+        if let Some(offload_ground_host) = file.offload_ground_host {
+            self.offload.ground_host = Some(offload_ground_host);
+        }
+        if let Some(offload_remote_dir) = file.offload_remote_dir {
+            self.offload.remote_dir = Some(offload_remote_dir);
+        }
+        if let Some(offload_ssh_key_path) = file.offload_ssh_key_path {
+            self.offload.ssh_key_path = Some(offload_ssh_key_path);
+        }
+        if let Some(offload_bandwidth_limit_kbps) = file.offload_bandwidth_limit_kbps {
+            self.offload.bandwidth_limit_kbps = Some(offload_bandwidth_limit_kbps);
+        }
+        if let Some(offload_delete_after_verified) = file.offload_delete_after_verified {
+            self.offload.delete_after_verified = offload_delete_after_verified;
+        }
+
+        self.extra_cameras = file
+            .cameras
+            .into_iter()
+            .map(|camera| {
+                let defaults = RecorderConfig::default();
+
+                CameraConfig {
+                    name: camera.name,
+                    recorder: RecorderConfig {
+                        backend: camera.backend.unwrap_or(defaults.backend),
+                        source: camera.source.unwrap_or(defaults.source),
+                        source_device: camera.source_device,
+                        usb_serial: camera.usb_serial.or(defaults.usb_serial),
+                        usb_port_path: camera.usb_port_path.or(defaults.usb_port_path),
+                        codec: camera.codec.unwrap_or(defaults.codec),
+                        encoder: camera.encoder.unwrap_or(defaults.encoder),
+                        zero_copy_enabled: camera.zero_copy_enabled.unwrap_or(defaults.zero_copy_enabled),
+                        capture_width: camera.capture_width.or(defaults.capture_width),
+                        capture_height: camera.capture_height.or(defaults.capture_height),
+                        capture_framerate: camera.capture_framerate.or(defaults.capture_framerate),
+                        video_bitrate_kbps: camera.video_bitrate_kbps.or(defaults.video_bitrate_kbps),
+                        adaptive_bitrate_enabled: camera
+                            .adaptive_bitrate_enabled
+                            .unwrap_or(defaults.adaptive_bitrate_enabled),
+                        adaptive_bitrate_min_kbps: camera
+                            .adaptive_bitrate_min_kbps
+                            .unwrap_or(defaults.adaptive_bitrate_min_kbps),
+                        adaptive_bitrate_step_kbps: camera
+                            .adaptive_bitrate_step_kbps
+                            .unwrap_or(defaults.adaptive_bitrate_step_kbps),
+                        adaptive_bitrate_recovery_secs: camera
+                            .adaptive_bitrate_recovery_secs
+                            .unwrap_or(defaults.adaptive_bitrate_recovery_secs),
+                        container: camera.container.unwrap_or(defaults.container),
+                        output_dir: camera.output_dir.unwrap_or(defaults.output_dir),
+                        secondary_output_dir: camera.secondary_output_dir.or(defaults.secondary_output_dir),
+                        encryption_recipient: camera.encryption_recipient.or(defaults.encryption_recipient),
+                        orientation: camera.orientation.unwrap_or(defaults.orientation),
+                        crop_left: camera.crop_left.unwrap_or(defaults.crop_left),
+                        crop_right: camera.crop_right.unwrap_or(defaults.crop_right),
+                        crop_top: camera.crop_top.unwrap_or(defaults.crop_top),
+                        crop_bottom: camera.crop_bottom.unwrap_or(defaults.crop_bottom),
+                        file_pattern: camera.file_pattern.unwrap_or(defaults.file_pattern),
+                        naming: camera.naming.unwrap_or(defaults.naming),
+                        segment_duration: camera
+                            .segment_duration_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(defaults.segment_duration),
+                        preroll_duration: camera
+                            .preroll_duration_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(defaults.preroll_duration),
+                        frame_stall_timeout: camera
+                            .frame_stall_timeout_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(defaults.frame_stall_timeout),
+                        max_recording_duration: camera
+                            .max_recording_duration_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(defaults.max_recording_duration),
+                        self_test_enabled: camera.self_test_enabled.unwrap_or(defaults.self_test_enabled),
+                        self_test_degraded_on_failure: camera
+                            .self_test_degraded_on_failure
+                            .unwrap_or(defaults.self_test_degraded_on_failure),
+                        init_degraded_on_failure: camera
+                            .init_degraded_on_failure
+                            .unwrap_or(defaults.init_degraded_on_failure),
+                        init_retry_interval: camera
+                            .init_retry_interval_secs
+                            .map(Duration::from_secs)
+                            .unwrap_or(defaults.init_retry_interval),
+                        still_capture: camera.still_capture.unwrap_or(defaults.still_capture),
+                        still_burst_count: camera.still_burst_count.unwrap_or(defaults.still_burst_count),
+                        still_file_pattern: camera.still_file_pattern.unwrap_or(defaults.still_file_pattern),
+                        still_aeb_enabled: camera.still_aeb_enabled.unwrap_or(defaults.still_aeb_enabled),
+                        still_aeb_ev_stops: camera.still_aeb_ev_stops.unwrap_or(defaults.still_aeb_ev_stops),
+                        still_raw_enabled: camera.still_raw_enabled.unwrap_or(defaults.still_raw_enabled),
+                        still_thermal_radiometric_enabled: camera
+                            .still_thermal_radiometric_enabled
+                            .unwrap_or(defaults.still_thermal_radiometric_enabled),
+                        still_dual_stream_enabled: camera
+                            .still_dual_stream_enabled
+                            .unwrap_or(defaults.still_dual_stream_enabled),
+                        libcamera_still_binary: camera
+                            .libcamera_still_binary
+                            .unwrap_or(defaults.libcamera_still_binary),
+                        libcamera_vid_binary: camera
+                            .libcamera_vid_binary
+                            .unwrap_or(defaults.libcamera_vid_binary),
+                        libcamera_sensor_mode: camera.libcamera_sensor_mode.or(defaults.libcamera_sensor_mode),
+                        libcamera_ae_enabled: camera.libcamera_ae_enabled.unwrap_or(defaults.libcamera_ae_enabled),
+                        libcamera_awb_enabled: camera.libcamera_awb_enabled.unwrap_or(defaults.libcamera_awb_enabled),
+                        libcamera_min_frame_duration_micros: camera
+                            .libcamera_min_frame_duration_micros
+                            .or(defaults.libcamera_min_frame_duration_micros),
+                        libcamera_max_frame_duration_micros: camera
+                            .libcamera_max_frame_duration_micros
+                            .or(defaults.libcamera_max_frame_duration_micros),
+                        write_queue_depth: camera.write_queue_depth.unwrap_or(defaults.write_queue_depth),
+                        backpressure_policy: camera.backpressure_policy.unwrap_or(defaults.backpressure_policy),
+                        rtsp_preview_enabled: camera
+                            .rtsp_preview_enabled
+                            .unwrap_or(defaults.rtsp_preview_enabled),
+                        rtsp_preview_address: camera
+                            .rtsp_preview_address
+                            .unwrap_or(defaults.rtsp_preview_address),
+                        rtsp_preview_bitrate_kbps: camera
+                            .rtsp_preview_bitrate_kbps
+                            .unwrap_or(defaults.rtsp_preview_bitrate_kbps),
+                        webrtc_preview_enabled: camera
+                            .webrtc_preview_enabled
+                            .unwrap_or(defaults.webrtc_preview_enabled),
+                        webrtc_preview_whip_endpoint: camera
+                            .webrtc_preview_whip_endpoint
+                            .unwrap_or(defaults.webrtc_preview_whip_endpoint),
+                        webrtc_preview_bitrate_kbps: camera
+                            .webrtc_preview_bitrate_kbps
+                            .unwrap_or(defaults.webrtc_preview_bitrate_kbps),
+                        srt_output_enabled: camera.srt_output_enabled.unwrap_or(defaults.srt_output_enabled),
+                        srt_output_address: camera.srt_output_address.unwrap_or(defaults.srt_output_address),
+                        srt_output_bitrate_kbps: camera
+                            .srt_output_bitrate_kbps
+                            .unwrap_or(defaults.srt_output_bitrate_kbps),
+                        embed_frame_metadata: camera
+                            .embed_frame_metadata
+                            .unwrap_or(defaults.embed_frame_metadata),
+                        osd_overlay_enabled: camera.osd_overlay_enabled.unwrap_or(defaults.osd_overlay_enabled),
+                        osd_overlay_interval_secs: camera
+                            .osd_overlay_interval_secs
+                            .unwrap_or(defaults.osd_overlay_interval_secs),
+                        audio_capture_enabled: camera
+                            .audio_capture_enabled
+                            .unwrap_or(defaults.audio_capture_enabled),
+                        audio_device: camera.audio_device.unwrap_or(defaults.audio_device),
+                        audio_bitrate_kbps: camera.audio_bitrate_kbps.unwrap_or(defaults.audio_bitrate_kbps),
+                        initial_controls: CameraControls {
+                            exposure_micros: camera.exposure_micros.or(defaults.initial_controls.exposure_micros),
+                            gain: camera.gain.or(defaults.initial_controls.gain),
+                            white_balance_kelvin: camera
+                                .white_balance_kelvin
+                                .or(defaults.initial_controls.white_balance_kelvin),
+                            focus_position: camera.focus_position.or(defaults.initial_controls.focus_position),
+                        },
+                    },
+                }
+            })
+            .collect();
+
+        self.aux_lines = file
+            .aux_lines
+            .into_iter()
+            .map(|line| AuxLineConfig {
+                gpiochip: line.gpiochip,
+                line_offset: line.line_offset,
+                action: line.action,
+                label: line.label.unwrap_or_else(|| format!("{:?}", line.action)),
+            })
+            .collect();
+    }
+
+    fn apply_env(&mut self) {
+        if let Some(pidfile) = env_var("CAMERA_TRIGGER_PIDFILE") {
+            self.pidfile = pidfile;
+        }
+        if let Some(device_ready_timeout_secs) = env_var::<u64>("CAMERA_TRIGGER_DEVICE_READY_TIMEOUT_SECS") {
+            self.device_ready_timeout = Duration::from_secs(device_ready_timeout_secs);
+        }
+        if let Some(storage_mount_point) = env_var("CAMERA_TRIGGER_STORAGE_MOUNT_POINT") {
+            self.storage_mount_point = Some(storage_mount_point);
+        }
+        if let Some(storage_min_free_bytes) = env_var::<u64>("CAMERA_TRIGGER_STORAGE_MIN_FREE_BYTES") {
+            self.storage_min_free_bytes = storage_min_free_bytes;
+        }
+        if let Some(gpiochip) = env_var("CAMERA_TRIGGER_GPIOCHIP") {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = env_var("CAMERA_TRIGGER_LINE_OFFSET") {
+            self.line_offset = line_offset;
+        }
+        if let Some(gpiochip_label) = env_var("CAMERA_TRIGGER_GPIOCHIP_LABEL") {
+            self.gpiochip_label = Some(gpiochip_label);
+        }
+        if let Some(line_name) = env_var("CAMERA_TRIGGER_LINE_NAME") {
+            self.line_name = Some(line_name);
+        }
+        if let Some(consumer_label) = env_var("CAMERA_TRIGGER_CONSUMER_LABEL") {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(line_bias) = env_var("CAMERA_TRIGGER_LINE_BIAS") {
+            self.line_bias = line_bias;
+        }
+        if let Some(active_low) = env_var("CAMERA_TRIGGER_ACTIVE_LOW") {
+            self.active_low = active_low;
+        }
+        if let Some(debounce_period_micros) = env_var::<u64>("CAMERA_TRIGGER_DEBOUNCE_PERIOD_MICROS") {
+            self.debounce_period = Duration::from_micros(debounce_period_micros);
+        }
+        if let Some(event_clock_realtime) = env_var("CAMERA_TRIGGER_EVENT_CLOCK_REALTIME") {
+            self.event_clock_realtime = event_clock_realtime;
+        }
+        if let Some(min_pulse_width_ms) = env_var::<u64>("CAMERA_TRIGGER_MIN_PULSE_WIDTH_MS") {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(short_pulse_max_ms) = env_var::<u64>("CAMERA_TRIGGER_SHORT_PULSE_MAX_MS") {
+            self.short_pulse_max = Duration::from_millis(short_pulse_max_ms);
+        }
+        if let Some(invert_polarity) = env_var("CAMERA_TRIGGER_INVERT_POLARITY") {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(pwm_mode) = env_var("CAMERA_TRIGGER_PWM_MODE") {
+            self.pwm_mode = pwm_mode;
+        }
+        if let Some(pwm_record_above_us) = env_var::<u64>("CAMERA_TRIGGER_PWM_RECORD_ABOVE_US") {
+            self.pwm_record_above = Duration::from_micros(pwm_record_above_us);
+        }
+        if let Some(pwm_stop_below_us) = env_var::<u64>("CAMERA_TRIGGER_PWM_STOP_BELOW_US") {
+            self.pwm_stop_below = Duration::from_micros(pwm_stop_below_us);
+        }
+        if let Some(auto_start_recording) = env_var("CAMERA_TRIGGER_AUTO_START_RECORDING") {
+            self.auto_start_recording = auto_start_recording;
+        }
+        if let Some(simulate) = env_var("CAMERA_TRIGGER_SIMULATE") {
+            self.simulate = simulate;
+        }
+        if let Some(simulate_interval_secs) = env_var::<u64>("CAMERA_TRIGGER_SIMULATE_INTERVAL_SECS") {
+            self.simulate_interval = Duration::from_secs(simulate_interval_secs);
+        }
+        if let Some(replay_log) = env_var::<PathBuf>("CAMERA_TRIGGER_REPLAY_LOG") {
+            self.replay_log = Some(replay_log);
+        }
+        if let Some(generate_interval_ms) = env_var::<u64>("CAMERA_TRIGGER_GENERATE_INTERVAL_MS") {
+            self.generate.interval = Duration::from_millis(generate_interval_ms);
+        }
+        if let Some(generate_pulse_width_ms) = env_var::<u64>("CAMERA_TRIGGER_GENERATE_PULSE_WIDTH_MS") {
+            self.generate.pulse_width = Duration::from_millis(generate_pulse_width_ms);
+        }
+        if let Some(generate_count) = env_var::<u64>("CAMERA_TRIGGER_GENERATE_COUNT") {
+            self.generate.count = Some(generate_count);
+        }
+        if let Some(bench_duration_secs) = env_var::<u64>("CAMERA_TRIGGER_BENCH_DURATION_SECS") {
+            self.bench_duration = Duration::from_secs(bench_duration_secs);
+        }
+        if let Some(backend) = env_var("CAMERA_TRIGGER_BACKEND") {
+            self.recorder.backend = backend;
+        }
+        if let Some(source) = env_var("CAMERA_TRIGGER_SOURCE") {
+            self.recorder.source = source;
+        }
+        if let Some(source_device) = env_var("CAMERA_TRIGGER_SOURCE_DEVICE") {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(usb_serial) = env_var("CAMERA_TRIGGER_USB_SERIAL") {
+            self.recorder.usb_serial = Some(usb_serial);
+        }
+        if let Some(usb_port_path) = env_var("CAMERA_TRIGGER_USB_PORT_PATH") {
+            self.recorder.usb_port_path = Some(usb_port_path);
+        }
+        if let Some(codec) = env_var("CAMERA_TRIGGER_CODEC") {
+            self.recorder.codec = codec;
+        }
+        if let Some(encoder) = env_var("CAMERA_TRIGGER_ENCODER") {
+            self.recorder.encoder = encoder;
+        }
+        if let Some(zero_copy_enabled) = env_var("CAMERA_TRIGGER_ZERO_COPY_ENABLED") {
+            self.recorder.zero_copy_enabled = zero_copy_enabled;
+        }
+        if let Some(capture_width) = env_var::<u32>("CAMERA_TRIGGER_CAPTURE_WIDTH") {
+            self.recorder.capture_width = Some(capture_width);
+        }
+        if let Some(capture_height) = env_var::<u32>("CAMERA_TRIGGER_CAPTURE_HEIGHT") {
+            self.recorder.capture_height = Some(capture_height);
+        }
+        if let Some(capture_framerate) = env_var::<u32>("CAMERA_TRIGGER_CAPTURE_FRAMERATE") {
+            self.recorder.capture_framerate = Some(capture_framerate);
+        }
+        if let Some(video_bitrate_kbps) = env_var::<u32>("CAMERA_TRIGGER_VIDEO_BITRATE_KBPS") {
+            self.recorder.video_bitrate_kbps = Some(video_bitrate_kbps);
+        }
+        if let Some(adaptive_bitrate_enabled) =
+            env_var("CAMERA_TRIGGER_ADAPTIVE_BITRATE_ENABLED")
+        {
+            self.recorder.adaptive_bitrate_enabled = adaptive_bitrate_enabled;
+        }
+        if let Some(adaptive_bitrate_min_kbps) =
+            env_var::<u32>("CAMERA_TRIGGER_ADAPTIVE_BITRATE_MIN_KBPS")
+        {
+            self.recorder.adaptive_bitrate_min_kbps = adaptive_bitrate_min_kbps;
+        }
+        if let Some(adaptive_bitrate_step_kbps) =
+            env_var::<u32>("CAMERA_TRIGGER_ADAPTIVE_BITRATE_STEP_KBPS")
+        {
+            self.recorder.adaptive_bitrate_step_kbps = adaptive_bitrate_step_kbps;
+        }
+        if let Some(adaptive_bitrate_recovery_secs) =
+            env_var::<u64>("CAMERA_TRIGGER_ADAPTIVE_BITRATE_RECOVERY_SECS")
+        {
+            self.recorder.adaptive_bitrate_recovery_secs = adaptive_bitrate_recovery_secs;
+        }
+        if let Some(container) = env_var("CAMERA_TRIGGER_CONTAINER") {
+            self.recorder.container = container;
+        }
+        if let Some(libcamera_vid_binary) = env_var("CAMERA_TRIGGER_LIBCAMERA_VID_BINARY") {
+            self.recorder.libcamera_vid_binary = libcamera_vid_binary;
+        }
+        if let Some(libcamera_sensor_mode) = env_var("CAMERA_TRIGGER_LIBCAMERA_SENSOR_MODE") {
+            self.recorder.libcamera_sensor_mode = Some(libcamera_sensor_mode);
+        }
+        if let Some(libcamera_ae_enabled) = env_var("CAMERA_TRIGGER_LIBCAMERA_AE_ENABLED") {
+            self.recorder.libcamera_ae_enabled = libcamera_ae_enabled;
+        }
+        if let Some(libcamera_awb_enabled) = env_var("CAMERA_TRIGGER_LIBCAMERA_AWB_ENABLED") {
+            self.recorder.libcamera_awb_enabled = libcamera_awb_enabled;
+        }
+        if let Some(libcamera_min_frame_duration_micros) =
+            env_var("CAMERA_TRIGGER_LIBCAMERA_MIN_FRAME_DURATION_MICROS")
+        {
+            self.recorder.libcamera_min_frame_duration_micros = Some(libcamera_min_frame_duration_micros);
+        }
+        if let Some(libcamera_max_frame_duration_micros) =
+            env_var("CAMERA_TRIGGER_LIBCAMERA_MAX_FRAME_DURATION_MICROS")
+        {
+            self.recorder.libcamera_max_frame_duration_micros = Some(libcamera_max_frame_duration_micros);
+        }
+        if let Some(write_queue_depth) = env_var("CAMERA_TRIGGER_WRITE_QUEUE_DEPTH") {
+            self.recorder.write_queue_depth = write_queue_depth;
+        }
+        if let Some(backpressure_policy) = env_var("CAMERA_TRIGGER_BACKPRESSURE_POLICY") {
+            self.recorder.backpressure_policy = backpressure_policy;
+        }
+        if let Some(output_dir) = env_var("CAMERA_TRIGGER_OUTPUT_DIR") {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(secondary_output_dir) = env_var("CAMERA_TRIGGER_SECONDARY_OUTPUT_DIR") {
+            self.recorder.secondary_output_dir = Some(secondary_output_dir);
+        }
+        if let Some(encryption_recipient) = env_var("CAMERA_TRIGGER_ENCRYPTION_RECIPIENT") {
+            self.recorder.encryption_recipient = Some(encryption_recipient);
+        }
+        if let Some(orientation) = env_var("CAMERA_TRIGGER_ORIENTATION") {
+            self.recorder.orientation = orientation;
+        }
+        if let Some(crop_left) = env_var("CAMERA_TRIGGER_CROP_LEFT") {
+            self.recorder.crop_left = crop_left;
+        }
+        if let Some(crop_right) = env_var("CAMERA_TRIGGER_CROP_RIGHT") {
+            self.recorder.crop_right = crop_right;
+        }
+        if let Some(crop_top) = env_var("CAMERA_TRIGGER_CROP_TOP") {
+            self.recorder.crop_top = crop_top;
+        }
+        if let Some(crop_bottom) = env_var("CAMERA_TRIGGER_CROP_BOTTOM") {
+            self.recorder.crop_bottom = crop_bottom;
+        }
+        if let Some(file_pattern) = env_var("CAMERA_TRIGGER_FILE_PATTERN") {
+            self.recorder.file_pattern = file_pattern;
+        }
+        if let Some(naming) = env_var("CAMERA_TRIGGER_NAMING") {
+            self.recorder.naming = naming;
+        }
+        if let Some(flight_session) = env_var("CAMERA_TRIGGER_FLIGHT_SESSION") {
+            self.flight_session = flight_session;
+        }
+        if let Some(segment_duration_secs) =
+            env_var::<u64>("CAMERA_TRIGGER_SEGMENT_DURATION_SECS")
+        {
+            self.recorder.segment_duration = Duration::from_secs(segment_duration_secs);
+        }
+        if let Some(preroll_duration_secs) =
+            env_var::<u64>("CAMERA_TRIGGER_PREROLL_DURATION_SECS")
+        {
+            self.recorder.preroll_duration = Duration::from_secs(preroll_duration_secs);
+        }
+        if let Some(frame_stall_timeout_secs) =
+            env_var::<u64>("CAMERA_TRIGGER_FRAME_STALL_TIMEOUT_SECS")
+        {
+            self.recorder.frame_stall_timeout = Duration::from_secs(frame_stall_timeout_secs);
+        }
+        if let Some(max_recording_duration_secs) =
+            env_var::<u64>("CAMERA_TRIGGER_MAX_RECORDING_DURATION_SECS")
+        {
+            self.recorder.max_recording_duration = Duration::from_secs(max_recording_duration_secs);
+        }
+        if let Some(self_test_enabled) = env_var("CAMERA_TRIGGER_SELF_TEST_ENABLED") {
+            self.recorder.self_test_enabled = self_test_enabled;
+        }
+        if let Some(self_test_degraded_on_failure) = env_var("CAMERA_TRIGGER_SELF_TEST_DEGRADED_ON_FAILURE") {
+            self.recorder.self_test_degraded_on_failure = self_test_degraded_on_failure;
+        }
+        if let Some(init_degraded_on_failure) = env_var("CAMERA_TRIGGER_INIT_DEGRADED_ON_FAILURE") {
+            self.recorder.init_degraded_on_failure = init_degraded_on_failure;
+        }
+        if let Some(init_retry_interval_secs) = env_var::<u64>("CAMERA_TRIGGER_INIT_RETRY_INTERVAL_SECS") {
+            self.recorder.init_retry_interval = Duration::from_secs(init_retry_interval_secs);
+        }
+        if let Some(still_capture) = env_var("CAMERA_TRIGGER_STILL_CAPTURE") {
+            self.recorder.still_capture = still_capture;
+        }
+        if let Some(still_burst_count) = env_var("CAMERA_TRIGGER_STILL_BURST_COUNT") {
+            self.recorder.still_burst_count = still_burst_count;
+        }
+        if let Some(still_file_pattern) = env_var("CAMERA_TRIGGER_STILL_FILE_PATTERN") {
+            self.recorder.still_file_pattern = still_file_pattern;
+        }
+        if let Some(still_aeb_enabled) = env_var("CAMERA_TRIGGER_STILL_AEB_ENABLED") {
+            self.recorder.still_aeb_enabled = still_aeb_enabled;
+        }
+        if let Some(still_aeb_ev_stops) = env_var("CAMERA_TRIGGER_STILL_AEB_EV_STOPS") {
+            self.recorder.still_aeb_ev_stops = still_aeb_ev_stops;
+        }
+        if let Some(still_raw_enabled) = env_var("CAMERA_TRIGGER_STILL_RAW_ENABLED") {
+            self.recorder.still_raw_enabled = still_raw_enabled;
+        }
+        if let Some(still_thermal_radiometric_enabled) =
+            env_var("CAMERA_TRIGGER_STILL_THERMAL_RADIOMETRIC_ENABLED")
+        {
+            self.recorder.still_thermal_radiometric_enabled = still_thermal_radiometric_enabled;
+        }
+        if let Some(still_dual_stream_enabled) = env_var("CAMERA_TRIGGER_STILL_DUAL_STREAM_ENABLED") {
+            self.recorder.still_dual_stream_enabled = still_dual_stream_enabled;
+        }
+        if let Some(libcamera_still_binary) = env_var("CAMERA_TRIGGER_LIBCAMERA_STILL_BINARY") {
+            self.recorder.libcamera_still_binary = libcamera_still_binary;
+        }
+        if let Some(rtsp_preview_enabled) = env_var("CAMERA_TRIGGER_RTSP_PREVIEW_ENABLED") {
+            self.recorder.rtsp_preview_enabled = rtsp_preview_enabled;
+        }
+        if let Some(rtsp_preview_address) = env_var("CAMERA_TRIGGER_RTSP_PREVIEW_ADDRESS") {
+            self.recorder.rtsp_preview_address = rtsp_preview_address;
+        }
+        if let Some(rtsp_preview_bitrate_kbps) = env_var("CAMERA_TRIGGER_RTSP_PREVIEW_BITRATE_KBPS") {
+            self.recorder.rtsp_preview_bitrate_kbps = rtsp_preview_bitrate_kbps;
+        }
+        if let Some(webrtc_preview_enabled) = env_var("CAMERA_TRIGGER_WEBRTC_PREVIEW_ENABLED") {
+            self.recorder.webrtc_preview_enabled = webrtc_preview_enabled;
+        }
+        if let Some(webrtc_preview_whip_endpoint) = env_var("CAMERA_TRIGGER_WEBRTC_PREVIEW_WHIP_ENDPOINT") {
+            self.recorder.webrtc_preview_whip_endpoint = webrtc_preview_whip_endpoint;
+        }
+        if let Some(webrtc_preview_bitrate_kbps) = env_var("CAMERA_TRIGGER_WEBRTC_PREVIEW_BITRATE_KBPS") {
+            self.recorder.webrtc_preview_bitrate_kbps = webrtc_preview_bitrate_kbps;
+        }
+        if let Some(srt_output_enabled) = env_var("CAMERA_TRIGGER_SRT_OUTPUT_ENABLED") {
+            self.recorder.srt_output_enabled = srt_output_enabled;
+        }
+        if let Some(srt_output_address) = env_var("CAMERA_TRIGGER_SRT_OUTPUT_ADDRESS") {
+            self.recorder.srt_output_address = srt_output_address;
+        }
+        if let Some(srt_output_bitrate_kbps) = env_var("CAMERA_TRIGGER_SRT_OUTPUT_BITRATE_KBPS") {
+            self.recorder.srt_output_bitrate_kbps = srt_output_bitrate_kbps;
+        }
+        if let Some(embed_frame_metadata) = env_var("CAMERA_TRIGGER_EMBED_FRAME_METADATA") {
+            self.recorder.embed_frame_metadata = embed_frame_metadata;
+        }
+        if let Some(osd_overlay_enabled) = env_var("CAMERA_TRIGGER_OSD_OVERLAY_ENABLED") {
+            self.recorder.osd_overlay_enabled = osd_overlay_enabled;
+        }
+        if let Some(osd_overlay_interval_secs) = env_var("CAMERA_TRIGGER_OSD_OVERLAY_INTERVAL_SECS") {
+            self.recorder.osd_overlay_interval_secs = osd_overlay_interval_secs;
+        }
+        if let Some(audio_capture_enabled) = env_var("CAMERA_TRIGGER_AUDIO_CAPTURE_ENABLED") {
+            self.recorder.audio_capture_enabled = audio_capture_enabled;
+        }
+        if let Some(audio_device) = env_var("CAMERA_TRIGGER_AUDIO_DEVICE") {
+            self.recorder.audio_device = audio_device;
+        }
+        if let Some(audio_bitrate_kbps) = env_var("CAMERA_TRIGGER_AUDIO_BITRATE_KBPS") {
+            self.recorder.audio_bitrate_kbps = audio_bitrate_kbps;
+        }
+        if let Some(exposure_micros) = env_var("CAMERA_TRIGGER_EXPOSURE_MICROS") {
+            self.recorder.initial_controls.exposure_micros = Some(exposure_micros);
+        }
+        if let Some(gain) = env_var("CAMERA_TRIGGER_GAIN") {
+            self.recorder.initial_controls.gain = Some(gain);
+        }
+        if let Some(white_balance_kelvin) = env_var("CAMERA_TRIGGER_WHITE_BALANCE_KELVIN") {
+            self.recorder.initial_controls.white_balance_kelvin = Some(white_balance_kelvin);
+        }
+        if let Some(focus_position) = env_var("CAMERA_TRIGGER_FOCUS_POSITION") {
+            self.recorder.initial_controls.focus_position = Some(focus_position);
+        }
+        if let Some(mavlink_enabled) = env_var("CAMERA_TRIGGER_MAVLINK_ENABLED") {
+            self.mavlink.enabled = mavlink_enabled;
+        }
+        if let Some(mavlink_address) = env_var("CAMERA_TRIGGER_MAVLINK_ADDRESS") {
+            self.mavlink.address = mavlink_address;
+        }
+        if let Some(mavlink_system_id) = env_var("CAMERA_TRIGGER_MAVLINK_SYSTEM_ID") {
+            self.mavlink.system_id = mavlink_system_id;
+        }
+        if let Some(mavlink_component_id) = env_var("CAMERA_TRIGGER_MAVLINK_COMPONENT_ID") {
+            self.mavlink.component_id = mavlink_component_id;
+        }
+        if let Some(mavlink_require_armed) = env_var("CAMERA_TRIGGER_MAVLINK_REQUIRE_ARMED") {
+            self.mavlink.require_armed = mavlink_require_armed;
+        }
+        if let Some(mavlink_auto_stop_on_disarm) = env_var("CAMERA_TRIGGER_MAVLINK_AUTO_STOP_ON_DISARM") {
+            self.mavlink.auto_stop_on_disarm = mavlink_auto_stop_on_disarm;
+        }
+        if let Some(mavlink_trigger_source) = env_var("CAMERA_TRIGGER_MAVLINK_TRIGGER_SOURCE") {
+            self.mavlink.trigger_source = mavlink_trigger_source;
+        }
+        if let Some(mavlink_trigger_fusion) = env_var("CAMERA_TRIGGER_MAVLINK_TRIGGER_FUSION") {
+            self.mavlink.trigger_fusion = mavlink_trigger_fusion;
+        }
+        if let Some(mavlink_trigger_fusion_dedup_window_ms) =
+            env_var::<u64>("CAMERA_TRIGGER_MAVLINK_TRIGGER_FUSION_DEDUP_WINDOW_MS")
+        {
+            self.mavlink.trigger_fusion_dedup_window = Duration::from_millis(mavlink_trigger_fusion_dedup_window_ms);
+        }
+        if let Some(mavlink_min_altitude_gate_m) = env_var("CAMERA_TRIGGER_MAVLINK_MIN_ALTITUDE_GATE_M") {
+            self.mavlink.min_altitude_gate_m = Some(mavlink_min_altitude_gate_m);
+        }
+        if let Some(mavlink_block_triggers_during_rtl) = env_var("CAMERA_TRIGGER_MAVLINK_BLOCK_TRIGGERS_DURING_RTL") {
+            self.mavlink.block_triggers_during_rtl = mavlink_block_triggers_during_rtl;
+        }
+        if let Some(pps_enabled) = env_var("CAMERA_TRIGGER_PPS_ENABLED") {
+            self.pps.enabled = pps_enabled;
+        }
+        if let Some(pps_gpiochip) = env_var("CAMERA_TRIGGER_PPS_GPIOCHIP") {
+            self.pps.gpiochip = Some(pps_gpiochip);
+        }
+        if let Some(pps_line_offset) = env_var("CAMERA_TRIGGER_PPS_LINE_OFFSET") {
+            self.pps.line_offset = Some(pps_line_offset);
+        }
+        if let Some(pps_device) = env_var("CAMERA_TRIGGER_PPS_DEVICE") {
+            self.pps.device = Some(pps_device);
+        }
+        if let Some(min_free_disk_bytes) = env_var("CAMERA_TRIGGER_MIN_FREE_DISK_BYTES") {
+            self.min_free_disk_bytes = min_free_disk_bytes;
+        }
+        if let Some(retention_enabled) = env_var("CAMERA_TRIGGER_RETENTION_ENABLED") {
+            self.retention.enabled = retention_enabled;
+        }
+        if let Some(retention_max_bytes) = env_var("CAMERA_TRIGGER_RETENTION_MAX_BYTES") {
+            self.retention.max_bytes = Some(retention_max_bytes);
+        }
+        if let Some(retention_min_free_bytes) = env_var("CAMERA_TRIGGER_RETENTION_MIN_FREE_BYTES") {
+            self.retention.min_free_bytes = Some(retention_min_free_bytes);
+        }
+        if let Some(trigger_log_max_bytes) = env_var("CAMERA_TRIGGER_TRIGGER_LOG_MAX_BYTES") {
+            self.trigger_log.max_bytes = Some(trigger_log_max_bytes);
+        }
+        if let Some(trigger_log_max_age_secs) = env_var("CAMERA_TRIGGER_TRIGGER_LOG_MAX_AGE_SECS") {
+            self.trigger_log.max_age = Some(Duration::from_secs(trigger_log_max_age_secs));
+        }
+        if let Some(status_enabled) = env_var("CAMERA_TRIGGER_STATUS_ENABLED") {
+            self.status.enabled = status_enabled;
+        }
+        if let Some(status_socket_path) = env_var("CAMERA_TRIGGER_STATUS_SOCKET_PATH") {
+            self.status.socket_path = status_socket_path;
+        }
+        if let Some(status_file_path) = env_var("CAMERA_TRIGGER_STATUS_FILE_PATH") {
+            self.status.file_path = Some(status_file_path);
+        }
+        if let Some(status_file_interval_secs) = env_var("CAMERA_TRIGGER_STATUS_FILE_INTERVAL_SECS") {
+            self.status.file_interval = Duration::from_secs(status_file_interval_secs);
+        }
+        if let Some(durability_interval_secs) = env_var::<u64>("CAMERA_TRIGGER_DURABILITY_INTERVAL_SECS") {
+            self.durability.interval = Some(Duration::from_secs(durability_interval_secs));
+        }
+        if let Some(durability_max_bytes) = env_var::<u64>("CAMERA_TRIGGER_DURABILITY_MAX_BYTES") {
+            self.durability.max_bytes = Some(durability_max_bytes);
+        }
+        if let Some(metrics_enabled) = env_var("CAMERA_TRIGGER_METRICS_ENABLED") {
+            self.metrics.enabled = metrics_enabled;
+        }
+        if let Some(metrics_address) = env_var("CAMERA_TRIGGER_METRICS_ADDRESS") {
+            self.metrics.address = metrics_address;
+        }
+        if let Some(control_api_enabled) = env_var("CAMERA_TRIGGER_CONTROL_API_ENABLED") {
+            self.control_api.enabled = control_api_enabled;
+        }
+        if let Some(control_api_address) = env_var("CAMERA_TRIGGER_CONTROL_API_ADDRESS") {
+            self.control_api.address = control_api_address;
+        }
+        if let Some(network_trigger_enabled) = env_var("CAMERA_TRIGGER_NETWORK_TRIGGER_ENABLED") {
+            self.network_trigger.enabled = network_trigger_enabled;
+        }
+        if let Some(network_trigger_address) = env_var("CAMERA_TRIGGER_NETWORK_TRIGGER_ADDRESS") {
+            self.network_trigger.address = network_trigger_address;
+        }
+        if let Some(network_trigger_shared_secret) = env_var("CAMERA_TRIGGER_NETWORK_TRIGGER_SHARED_SECRET") {
+            self.network_trigger.shared_secret = network_trigger_shared_secret;
+        }
+        if let Some(dronecan_enabled) = env_var("CAMERA_TRIGGER_DRONECAN_ENABLED") {
+            self.dronecan.enabled = dronecan_enabled;
+        }
+        if let Some(dronecan_interface) = env_var("CAMERA_TRIGGER_DRONECAN_INTERFACE") {
+            self.dronecan.interface = dronecan_interface;
+        }
+        if let Some(dronecan_node_id) = env_var::<u8>("CAMERA_TRIGGER_DRONECAN_NODE_ID") {
+            self.dronecan.node_id = dronecan_node_id;
+        }
+        if let Some(dronecan_trigger_can_id) = env_var::<u16>("CAMERA_TRIGGER_DRONECAN_TRIGGER_CAN_ID") {
+            self.dronecan.trigger_can_id = dronecan_trigger_can_id;
+        }
+        if let Some(dronecan_feedback_can_id) = env_var::<u16>("CAMERA_TRIGGER_DRONECAN_FEEDBACK_CAN_ID") {
+            self.dronecan.feedback_can_id = dronecan_feedback_can_id;
+        }
+        if let Some(mqtt_enabled) = env_var("CAMERA_TRIGGER_MQTT_ENABLED") {
+            self.mqtt.enabled = mqtt_enabled;
+        }
+        if let Some(mqtt_address) = env_var("CAMERA_TRIGGER_MQTT_ADDRESS") {
+            self.mqtt.address = mqtt_address;
+        }
+        if let Some(mqtt_topic_prefix) = env_var("CAMERA_TRIGGER_MQTT_TOPIC_PREFIX") {
+            self.mqtt.topic_prefix = mqtt_topic_prefix;
+        }
+        if let Some(dbus_enabled) = env_var("CAMERA_TRIGGER_DBUS_ENABLED") {
+            self.dbus.enabled = dbus_enabled;
+        }
+        if let Some(dbus_service_name) = env_var("CAMERA_TRIGGER_DBUS_SERVICE_NAME") {
+            self.dbus.service_name = dbus_service_name;
+        }
+        if let Some(shutdown_inhibitor_enabled) = env_var("CAMERA_TRIGGER_SHUTDOWN_INHIBITOR_ENABLED") {
+            self.shutdown_inhibitor.enabled = shutdown_inhibitor_enabled;
+        }
+        if let Some(privsep_enabled) = env_var("CAMERA_TRIGGER_PRIVSEP_ENABLED") {
+            self.privsep.enabled = privsep_enabled;
+        }
+        if let Some(privsep_user) = env_var::<String>("CAMERA_TRIGGER_PRIVSEP_USER") {
+            self.privsep.user = Some(privsep_user);
+        }
+        if let Some(grpc_enabled) = env_var("CAMERA_TRIGGER_GRPC_ENABLED") {
+            self.grpc.enabled = grpc_enabled;
+        }
+        if let Some(grpc_address) = env_var("CAMERA_TRIGGER_GRPC_ADDRESS") {
+            self.grpc.address = grpc_address;
+        }
+        if let Some(grpc_status_interval_secs) = env_var::<u64>("CAMERA_TRIGGER_GRPC_STATUS_INTERVAL_SECS") {
+            self.grpc.status_interval = Duration::from_secs(grpc_status_interval_secs);
+        }
+        if let Some(ros_enabled) = env_var("CAMERA_TRIGGER_ROS_ENABLED") {
+            self.ros.enabled = ros_enabled;
+        }
+        if let Some(ros_node_name) = env_var("CAMERA_TRIGGER_ROS_NODE_NAME") {
+            self.ros.node_name = ros_node_name;
+        }
+        if let Some(status_led_enabled) = env_var("CAMERA_TRIGGER_STATUS_LED_ENABLED") {
+            self.status_led.enabled = status_led_enabled;
+        }
+        if let Some(status_led_gpiochip) = env_var("CAMERA_TRIGGER_STATUS_LED_GPIOCHIP") {
+            self.status_led.gpiochip = Some(status_led_gpiochip);
+        }
+        if let Some(status_led_line_offset) = env_var("CAMERA_TRIGGER_STATUS_LED_LINE_OFFSET") {
+            self.status_led.line_offset = Some(status_led_line_offset);
+        }
+        if let Some(capture_feedback_enabled) = env_var("CAMERA_TRIGGER_CAPTURE_FEEDBACK_ENABLED") {
+            self.capture_feedback.enabled = capture_feedback_enabled;
+        }
+        if let Some(capture_feedback_gpiochip) = env_var("CAMERA_TRIGGER_CAPTURE_FEEDBACK_GPIOCHIP") {
+            self.capture_feedback.gpiochip = Some(capture_feedback_gpiochip);
+        }
+        if let Some(capture_feedback_line_offset) = env_var("CAMERA_TRIGGER_CAPTURE_FEEDBACK_LINE_OFFSET") {
+            self.capture_feedback.line_offset = Some(capture_feedback_line_offset);
+        }
+        if let Some(buzzer_enabled) = env_var("CAMERA_TRIGGER_BUZZER_ENABLED") {
+            self.buzzer.enabled = buzzer_enabled;
+        }
+        if let Some(buzzer_gpiochip) = env_var("CAMERA_TRIGGER_BUZZER_GPIOCHIP") {
+            self.buzzer.gpiochip = Some(buzzer_gpiochip);
+        }
+        if let Some(buzzer_line_offset) = env_var("CAMERA_TRIGGER_BUZZER_LINE_OFFSET") {
+            self.buzzer.line_offset = Some(buzzer_line_offset);
+        }
+        if let Some(intervalometer_enabled) = env_var("CAMERA_TRIGGER_INTERVALOMETER_ENABLED") {
+            self.intervalometer.enabled = intervalometer_enabled;
+        }
+        if let Some(intervalometer_interval_secs) = env_var("CAMERA_TRIGGER_INTERVALOMETER_INTERVAL_SECS") {
+            self.intervalometer.interval_secs = Some(intervalometer_interval_secs);
+        }
+        if let Some(intervalometer_distance_meters) = env_var("CAMERA_TRIGGER_INTERVALOMETER_DISTANCE_METERS") {
+            self.intervalometer.distance_meters = Some(intervalometer_distance_meters);
+        }
+        if let Some(subtitle_enabled) = env_var("CAMERA_TRIGGER_SUBTITLE_ENABLED") {
+            self.subtitle.enabled = subtitle_enabled;
+        }
+        if let Some(subtitle_interval_secs) = env_var("CAMERA_TRIGGER_SUBTITLE_INTERVAL_SECS") {
+            self.subtitle.interval_secs = subtitle_interval_secs;
+        }
+        if let Some(thermal_enabled) = env_var("CAMERA_TRIGGER_THERMAL_ENABLED") {
+            self.thermal.enabled = thermal_enabled;
+        }
+        if let Some(thermal_warn_temp_celsius) = env_var("CAMERA_TRIGGER_THERMAL_WARN_TEMP_CELSIUS") {
+            self.thermal.warn_temp_celsius = thermal_warn_temp_celsius;
+        }
+        if let Some(thermal_finalize_on_undervoltage) = env_var("CAMERA_TRIGGER_THERMAL_FINALIZE_ON_UNDERVOLTAGE") {
+            self.thermal.finalize_on_undervoltage = thermal_finalize_on_undervoltage;
+        }
+        if let Some(storage_health_enabled) = env_var("CAMERA_TRIGGER_STORAGE_HEALTH_ENABLED") {
+            self.storage_health.enabled = storage_health_enabled;
+        }
+        if let Some(storage_health_device) = env_var("CAMERA_TRIGGER_STORAGE_HEALTH_DEVICE") {
+            self.storage_health.device = Some(storage_health_device);
+        }
+        if let Some(storage_health_warn_percent_used) = env_var("CAMERA_TRIGGER_STORAGE_HEALTH_WARN_PERCENT_USED") {
+            self.storage_health.warn_percent_used = storage_health_warn_percent_used;
+        }
+        if let Some(time_sync_check_enabled) = env_var("CAMERA_TRIGGER_TIME_SYNC_CHECK_ENABLED") {
+            self.time_sync_check.enabled = time_sync_check_enabled;
+        }
+        if let Some(offload_enabled) = env_var("CAMERA_TRIGGER_OFFLOAD_ENABLED") {
+            self.offload.enabled = offload_enabled;
+        }
+        if let Some(offload_ground_host) = env_var("CAMERA_TRIGGER_OFFLOAD_GROUND_HOST") {
+            self.offload.ground_host = Some(offload_ground_host);
+        }
+        if let Some(offload_remote_dir) = env_var("CAMERA_TRIGGER_OFFLOAD_REMOTE_DIR") {
+            self.offload.remote_dir = Some(offload_remote_dir);
+        }
+        if let Some(offload_ssh_key_path) = env_var("CAMERA_TRIGGER_OFFLOAD_SSH_KEY_PATH") {
+            self.offload.ssh_key_path = Some(offload_ssh_key_path);
+        }
+        if let Some(offload_bandwidth_limit_kbps) = env_var("CAMERA_TRIGGER_OFFLOAD_BANDWIDTH_LIMIT_KBPS") {
+            self.offload.bandwidth_limit_kbps = Some(offload_bandwidth_limit_kbps);
+        }
+        if let Some(offload_delete_after_verified) = env_var("CAMERA_TRIGGER_OFFLOAD_DELETE_AFTER_VERIFIED") {
+            self.offload.delete_after_verified = offload_delete_after_verified;
+        }
+    }
+
+    fn apply_cli(&mut self, cli: Cli) {
+        if let Some(pidfile) = cli.pidfile {
+            self.pidfile = pidfile;
+        }
+        if let Some(device_ready_timeout_secs) = cli.device_ready_timeout_secs {
+            self.device_ready_timeout = Duration::from_secs(device_ready_timeout_secs);
+        }
+        if let Some(storage_mount_point) = cli.storage_mount_point {
+            self.storage_mount_point = Some(storage_mount_point);
+        }
+        if let Some(storage_min_free_bytes) = cli.storage_min_free_bytes {
+            self.storage_min_free_bytes = storage_min_free_bytes;
+        }
+        if let Some(gpiochip) = cli.gpiochip {
+            self.gpiochip = gpiochip;
+        }
+        if let Some(line_offset) = cli.line_offset {
+            self.line_offset = line_offset;
+        }
+        if let Some(gpiochip_label) = cli.gpiochip_label {
+            self.gpiochip_label = Some(gpiochip_label);
+        }
+        if let Some(line_name) = cli.line_name {
+            self.line_name = Some(line_name);
+        }
+        if let Some(consumer_label) = cli.consumer_label {
+            self.consumer_label = consumer_label;
+        }
+        if let Some(line_bias) = cli.line_bias {
+            self.line_bias = line_bias;
+        }
+        if let Some(active_low) = cli.active_low {
+            self.active_low = active_low;
+        }
+        if let Some(debounce_period_micros) = cli.debounce_period_micros {
+            self.debounce_period = Duration::from_micros(debounce_period_micros);
+        }
+        if let Some(event_clock_realtime) = cli.event_clock_realtime {
+            self.event_clock_realtime = event_clock_realtime;
+        }
+        if let Some(min_pulse_width_ms) = cli.min_pulse_width_ms {
+            self.min_pulse_width = Duration::from_millis(min_pulse_width_ms);
+        }
+        if let Some(short_pulse_max_ms) = cli.short_pulse_max_ms {
+            self.short_pulse_max = Duration::from_millis(short_pulse_max_ms);
+        }
+        if let Some(invert_polarity) = cli.invert_polarity {
+            self.invert_polarity = invert_polarity;
+        }
+        if let Some(pwm_mode) = cli.pwm_mode {
+            self.pwm_mode = pwm_mode;
+        }
+        if let Some(pwm_record_above_us) = cli.pwm_record_above_us {
+            self.pwm_record_above = Duration::from_micros(pwm_record_above_us);
+        }
+        if let Some(pwm_stop_below_us) = cli.pwm_stop_below_us {
+            self.pwm_stop_below = Duration::from_micros(pwm_stop_below_us);
+        }
+        if let Some(auto_start_recording) = cli.auto_start_recording {
+            self.auto_start_recording = auto_start_recording;
+        }
+        if let Some(simulate) = cli.simulate {
+            self.simulate = simulate;
+        }
+        if let Some(simulate_interval_secs) = cli.simulate_interval_secs {
+            self.simulate_interval = Duration::from_secs(simulate_interval_secs);
+        }
+        if let Some(replay_log) = cli.replay_log {
+            self.replay_log = Some(replay_log);
+        }
+        if let Some(generate_interval_ms) = cli.generate_interval_ms {
+            self.generate.interval = Duration::from_millis(generate_interval_ms);
+        }
+        if let Some(generate_pulse_width_ms) = cli.generate_pulse_width_ms {
+            self.generate.pulse_width = Duration::from_millis(generate_pulse_width_ms);
+        }
+        if let Some(generate_count) = cli.generate_count {
+            self.generate.count = Some(generate_count);
+        }
+        if let Some(bench_duration_secs) = cli.bench_duration_secs {
+            self.bench_duration = Duration::from_secs(bench_duration_secs);
+        }
+        if let Some(backend) = cli.backend {
+            self.recorder.backend = backend;
+        }
+        if let Some(source) = cli.source {
+            self.recorder.source = source;
+        }
+        if let Some(source_device) = cli.source_device {
+            self.recorder.source_device = source_device;
+        }
+        if let Some(usb_serial) = cli.usb_serial {
+            self.recorder.usb_serial = Some(usb_serial);
+        }
+        if let Some(usb_port_path) = cli.usb_port_path {
+            self.recorder.usb_port_path = Some(usb_port_path);
+        }
+        if let Some(codec) = cli.codec {
+            self.recorder.codec = codec;
+        }
+        if let Some(encoder) = cli.encoder {
+            self.recorder.encoder = encoder;
+        }
+        if let Some(zero_copy_enabled) = cli.zero_copy_enabled {
+            self.recorder.zero_copy_enabled = zero_copy_enabled;
+        }
+        if let Some(capture_width) = cli.capture_width {
+            self.recorder.capture_width = Some(capture_width);
+        }
+        if let Some(capture_height) = cli.capture_height {
+            self.recorder.capture_height = Some(capture_height);
+        }
+        if let Some(capture_framerate) = cli.capture_framerate {
+            self.recorder.capture_framerate = Some(capture_framerate);
+        }
+        if let Some(video_bitrate_kbps) = cli.video_bitrate_kbps {
+            self.recorder.video_bitrate_kbps = Some(video_bitrate_kbps);
+        }
+        if let Some(adaptive_bitrate_enabled) = cli.adaptive_bitrate_enabled {
+            self.recorder.adaptive_bitrate_enabled = adaptive_bitrate_enabled;
+        }
+        if let Some(adaptive_bitrate_min_kbps) = cli.adaptive_bitrate_min_kbps {
+            self.recorder.adaptive_bitrate_min_kbps = adaptive_bitrate_min_kbps;
+        }
+        if let Some(adaptive_bitrate_step_kbps) = cli.adaptive_bitrate_step_kbps {
+            self.recorder.adaptive_bitrate_step_kbps = adaptive_bitrate_step_kbps;
+        }
+        if let Some(adaptive_bitrate_recovery_secs) = cli.adaptive_bitrate_recovery_secs {
+            self.recorder.adaptive_bitrate_recovery_secs = adaptive_bitrate_recovery_secs;
+        }
+        if let Some(container) = cli.container {
+            self.recorder.container = container;
+        }
+        if let Some(libcamera_vid_binary) = cli.libcamera_vid_binary {
+            self.recorder.libcamera_vid_binary = libcamera_vid_binary;
+        }
+        if let Some(libcamera_sensor_mode) = cli.libcamera_sensor_mode {
+            self.recorder.libcamera_sensor_mode = Some(libcamera_sensor_mode);
+        }
+        if let Some(libcamera_ae_enabled) = cli.libcamera_ae_enabled {
+            self.recorder.libcamera_ae_enabled = libcamera_ae_enabled;
+        }
+        if let Some(libcamera_awb_enabled) = cli.libcamera_awb_enabled {
+            self.recorder.libcamera_awb_enabled = libcamera_awb_enabled;
+        }
+        if let Some(libcamera_min_frame_duration_micros) = cli.libcamera_min_frame_duration_micros {
+            self.recorder.libcamera_min_frame_duration_micros = Some(libcamera_min_frame_duration_micros);
+        }
+        if let Some(libcamera_max_frame_duration_micros) = cli.libcamera_max_frame_duration_micros {
+            self.recorder.libcamera_max_frame_duration_micros = Some(libcamera_max_frame_duration_micros);
+        }
+        if let Some(write_queue_depth) = cli.write_queue_depth {
+            self.recorder.write_queue_depth = write_queue_depth;
+        }
+        if let Some(backpressure_policy) = cli.backpressure_policy {
+            self.recorder.backpressure_policy = backpressure_policy;
+        }
+        if let Some(output_dir) = cli.output_dir {
+            self.recorder.output_dir = output_dir;
+        }
+        if let Some(secondary_output_dir) = cli.secondary_output_dir {
+            self.recorder.secondary_output_dir = Some(secondary_output_dir);
+        }
+        if let Some(encryption_recipient) = cli.encryption_recipient {
+            self.recorder.encryption_recipient = Some(encryption_recipient);
+        }
+        if let Some(orientation) = cli.orientation {
+            self.recorder.orientation = orientation;
+        }
+        if let Some(crop_left) = cli.crop_left {
+            self.recorder.crop_left = crop_left;
+        }
+        if let Some(crop_right) = cli.crop_right {
+            self.recorder.crop_right = crop_right;
+        }
+        if let Some(crop_top) = cli.crop_top {
+            self.recorder.crop_top = crop_top;
+        }
+        if let Some(crop_bottom) = cli.crop_bottom {
+            self.recorder.crop_bottom = crop_bottom;
+        }
+        if let Some(file_pattern) = cli.file_pattern {
+            self.recorder.file_pattern = file_pattern;
+        }
+        if let Some(naming) = cli.naming {
+            self.recorder.naming = naming;
+        }
+        if let Some(flight_session) = cli.flight_session {
+            self.flight_session = flight_session;
+        }
+        if let Some(segment_duration_secs) = cli.segment_duration_secs {
+            self.recorder.segment_duration = Duration::from_secs(segment_duration_secs);
+        }
+        if let Some(preroll_duration_secs) = cli.preroll_duration_secs {
+            self.recorder.preroll_duration = Duration::from_secs(preroll_duration_secs);
+        }
+        if let Some(frame_stall_timeout_secs) = cli.frame_stall_timeout_secs {
+            self.recorder.frame_stall_timeout = Duration::from_secs(frame_stall_timeout_secs);
+        }
+        if let Some(max_recording_duration_secs) = cli.max_recording_duration_secs {
+            self.recorder.max_recording_duration = Duration::from_secs(max_recording_duration_secs);
+        }
+        if let Some(self_test_enabled) = cli.self_test_enabled {
+            self.recorder.self_test_enabled = self_test_enabled;
+        }
+        if let Some(self_test_degraded_on_failure) = cli.self_test_degraded_on_failure {
+            self.recorder.self_test_degraded_on_failure = self_test_degraded_on_failure;
+        }
+        if let Some(init_degraded_on_failure) = cli.init_degraded_on_failure {
+            self.recorder.init_degraded_on_failure = init_degraded_on_failure;
+        }
+        if let Some(init_retry_interval_secs) = cli.init_retry_interval_secs {
+            self.recorder.init_retry_interval = Duration::from_secs(init_retry_interval_secs);
+        }
+        if let Some(still_capture) = cli.still_capture {
+            self.recorder.still_capture = still_capture;
+        }
+        if let Some(still_burst_count) = cli.still_burst_count {
+            self.recorder.still_burst_count = still_burst_count;
+        }
+        if let Some(still_file_pattern) = cli.still_file_pattern {
+            self.recorder.still_file_pattern = still_file_pattern;
+        }
+        if let Some(still_aeb_enabled) = cli.still_aeb_enabled {
+            self.recorder.still_aeb_enabled = still_aeb_enabled;
+        }
+        if let Some(still_aeb_ev_stops) = cli.still_aeb_ev_stops {
+            self.recorder.still_aeb_ev_stops = still_aeb_ev_stops;
+        }
+        if let Some(still_raw_enabled) = cli.still_raw_enabled {
+            self.recorder.still_raw_enabled = still_raw_enabled;
+        }
+        if let Some(still_thermal_radiometric_enabled) = cli.still_thermal_radiometric_enabled {
+            self.recorder.still_thermal_radiometric_enabled = still_thermal_radiometric_enabled;
+        }
+        if let Some(still_dual_stream_enabled) = cli.still_dual_stream_enabled {
+            self.recorder.still_dual_stream_enabled = still_dual_stream_enabled;
+        }
+        if let Some(libcamera_still_binary) = cli.libcamera_still_binary {
+            self.recorder.libcamera_still_binary = libcamera_still_binary;
+        }
+        if let Some(rtsp_preview_enabled) = cli.rtsp_preview_enabled {
+            self.recorder.rtsp_preview_enabled = rtsp_preview_enabled;
+        }
+        if let Some(rtsp_preview_address) = cli.rtsp_preview_address {
+            self.recorder.rtsp_preview_address = rtsp_preview_address;
+        }
+        if let Some(rtsp_preview_bitrate_kbps) = cli.rtsp_preview_bitrate_kbps {
+            self.recorder.rtsp_preview_bitrate_kbps = rtsp_preview_bitrate_kbps;
+        }
+        if let Some(webrtc_preview_enabled) = cli.webrtc_preview_enabled {
+            self.recorder.webrtc_preview_enabled = webrtc_preview_enabled;
+        }
+        if let Some(webrtc_preview_whip_endpoint) = cli.webrtc_preview_whip_endpoint {
+            self.recorder.webrtc_preview_whip_endpoint = webrtc_preview_whip_endpoint;
+        }
+        if let Some(webrtc_preview_bitrate_kbps) = cli.webrtc_preview_bitrate_kbps {
+            self.recorder.webrtc_preview_bitrate_kbps = webrtc_preview_bitrate_kbps;
+        }
+        if let Some(srt_output_enabled) = cli.srt_output_enabled {
+            self.recorder.srt_output_enabled = srt_output_enabled;
+        }
+        if let Some(srt_output_address) = cli.srt_output_address {
+            self.recorder.srt_output_address = srt_output_address;
+        }
+        if let Some(srt_output_bitrate_kbps) = cli.srt_output_bitrate_kbps {
+            self.recorder.srt_output_bitrate_kbps = srt_output_bitrate_kbps;
+        }
+        if let Some(embed_frame_metadata) = cli.embed_frame_metadata {
+            self.recorder.embed_frame_metadata = embed_frame_metadata;
+        }
+        if let Some(osd_overlay_enabled) = cli.osd_overlay_enabled {
+            self.recorder.osd_overlay_enabled = osd_overlay_enabled;
+        }
+        if let Some(osd_overlay_interval_secs) = cli.osd_overlay_interval_secs {
+            self.recorder.osd_overlay_interval_secs = osd_overlay_interval_secs;
+        }
+        if let Some(audio_capture_enabled) = cli.audio_capture_enabled {
+            self.recorder.audio_capture_enabled = audio_capture_enabled;
+        }
+        if let Some(audio_device) = cli.audio_device {
+            self.recorder.audio_device = audio_device;
+        }
+        if let Some(audio_bitrate_kbps) = cli.audio_bitrate_kbps {
+            self.recorder.audio_bitrate_kbps = audio_bitrate_kbps;
+        }
+        if let Some(exposure_micros) = cli.exposure_micros {
+            self.recorder.initial_controls.exposure_micros = Some(exposure_micros);
+        }
+        if let Some(gain) = cli.gain {
+            self.recorder.initial_controls.gain = Some(gain);
+        }
+        if let Some(white_balance_kelvin) = cli.white_balance_kelvin {
+            self.recorder.initial_controls.white_balance_kelvin = Some(white_balance_kelvin);
+        }
+        if let Some(focus_position) = cli.focus_position {
+            self.recorder.initial_controls.focus_position = Some(focus_position);
+        }
+        if let Some(mavlink_enabled) = cli.mavlink_enabled {
+            self.mavlink.enabled = mavlink_enabled;
+        }
+        if let Some(mavlink_address) = cli.mavlink_address {
+            self.mavlink.address = mavlink_address;
+        }
+        if let Some(mavlink_system_id) = cli.mavlink_system_id {
+            self.mavlink.system_id = mavlink_system_id;
+        }
+        if let Some(mavlink_component_id) = cli.mavlink_component_id {
+            self.mavlink.component_id = mavlink_component_id;
+        }
+        if let Some(mavlink_require_armed) = cli.mavlink_require_armed {
+            self.mavlink.require_armed = mavlink_require_armed;
+        }
+        if let Some(mavlink_auto_stop_on_disarm) = cli.mavlink_auto_stop_on_disarm {
+            self.mavlink.auto_stop_on_disarm = mavlink_auto_stop_on_disarm;
+        }
+        if let Some(mavlink_trigger_source) = cli.mavlink_trigger_source {
+            self.mavlink.trigger_source = mavlink_trigger_source;
+        }
+        if let Some(mavlink_trigger_fusion) = cli.mavlink_trigger_fusion {
+            self.mavlink.trigger_fusion = mavlink_trigger_fusion;
+        }
+        if let Some(mavlink_trigger_fusion_dedup_window_ms) = cli.mavlink_trigger_fusion_dedup_window_ms {
+            self.mavlink.trigger_fusion_dedup_window = Duration::from_millis(mavlink_trigger_fusion_dedup_window_ms);
+        }
+        if let Some(mavlink_min_altitude_gate_m) = cli.mavlink_min_altitude_gate_m {
+            self.mavlink.min_altitude_gate_m = Some(mavlink_min_altitude_gate_m);
+        }
+        if let Some(mavlink_block_triggers_during_rtl) = cli.mavlink_block_triggers_during_rtl {
+            self.mavlink.block_triggers_during_rtl = mavlink_block_triggers_during_rtl;
+        }
+        if let Some(pps_enabled) = cli.pps_enabled {
+            self.pps.enabled = pps_enabled;
+        }
+        if let Some(pps_gpiochip) = cli.pps_gpiochip {
+            self.pps.gpiochip = Some(pps_gpiochip);
+        }
+        if let Some(pps_line_offset) = cli.pps_line_offset {
+            self.pps.line_offset = Some(pps_line_offset);
+        }
+        if let Some(pps_device) = cli.pps_device {
+            self.pps.device = Some(pps_device);
+        }
+        if let Some(min_free_disk_bytes) = cli.min_free_disk_bytes {
+            self.min_free_disk_bytes = min_free_disk_bytes;
+        }
+        if let Some(retention_enabled) = cli.retention_enabled {
+            self.retention.enabled = retention_enabled;
+        }
+        if let Some(retention_max_bytes) = cli.retention_max_bytes {
+            self.retention.max_bytes = Some(retention_max_bytes);
+        }
+        if let Some(retention_min_free_bytes) = cli.retention_min_free_bytes {
+            self.retention.min_free_bytes = Some(retention_min_free_bytes);
+        }
+        if let Some(trigger_log_max_bytes) = cli.trigger_log_max_bytes {
+            self.trigger_log.max_bytes = Some(trigger_log_max_bytes);
+        }
+        if let Some(trigger_log_max_age_secs) = cli.trigger_log_max_age_secs {
+            self.trigger_log.max_age = Some(Duration::from_secs(trigger_log_max_age_secs));
+        }
+        if let Some(status_enabled) = cli.status_enabled {
+            self.status.enabled = status_enabled;
+        }
+        if let Some(status_socket_path) = cli.status_socket_path {
+            self.status.socket_path = status_socket_path;
+        }
+        if let Some(status_file_path) = cli.status_file_path {
+            self.status.file_path = Some(status_file_path);
+        }
+        if let Some(status_file_interval_secs) = cli.status_file_interval_secs {
+            self.status.file_interval = Duration::from_secs(status_file_interval_secs);
+        }
+        if let Some(durability_interval_secs) = cli.durability_interval_secs {
+            self.durability.interval = Some(Duration::from_secs(durability_interval_secs));
+        }
+        if let Some(durability_max_bytes) = cli.durability_max_bytes {
+            self.durability.max_bytes = Some(durability_max_bytes);
+        }
+        if let Some(metrics_enabled) = cli.metrics_enabled {
+            self.metrics.enabled = metrics_enabled;
+        }
+        if let Some(metrics_address) = cli.metrics_address {
+            self.metrics.address = metrics_address;
+        }
+        if let Some(control_api_enabled) = cli.control_api_enabled {
+            self.control_api.enabled = control_api_enabled;
+        }
+        if let Some(control_api_address) = cli.control_api_address {
+            self.control_api.address = control_api_address;
+        }
+        if let Some(network_trigger_enabled) = cli.network_trigger_enabled {
+            self.network_trigger.enabled = network_trigger_enabled;
+        }
+        if let Some(network_trigger_address) = cli.network_trigger_address {
+            self.network_trigger.address = network_trigger_address;
+        }
+        if let Some(network_trigger_shared_secret) = cli.network_trigger_shared_secret {
+            self.network_trigger.shared_secret = network_trigger_shared_secret;
+        }
+        if let Some(dronecan_enabled) = cli.dronecan_enabled {
+            self.dronecan.enabled = dronecan_enabled;
+        }
+        if let Some(dronecan_interface) = cli.dronecan_interface {
+            self.dronecan.interface = dronecan_interface;
+        }
+        if let Some(dronecan_node_id) = cli.dronecan_node_id {
+            self.dronecan.node_id = dronecan_node_id;
+        }
+        if let Some(dronecan_trigger_can_id) = cli.dronecan_trigger_can_id {
+            self.dronecan.trigger_can_id = dronecan_trigger_can_id;
+        }
+        if let Some(dronecan_feedback_can_id) = cli.dronecan_feedback_can_id {
+            self.dronecan.feedback_can_id = dronecan_feedback_can_id;
+        }
+        if let Some(mqtt_enabled) = cli.mqtt_enabled {
+            self.mqtt.enabled = mqtt_enabled;
+        }
+        if let Some(mqtt_address) = cli.mqtt_address {
+            self.mqtt.address = mqtt_address;
+        }
+        if let Some(mqtt_topic_prefix) = cli.mqtt_topic_prefix {
+            self.mqtt.topic_prefix = mqtt_topic_prefix;
+        }
+        if let Some(dbus_enabled) = cli.dbus_enabled {
+            self.dbus.enabled = dbus_enabled;
+        }
+        if let Some(dbus_service_name) = cli.dbus_service_name {
+            self.dbus.service_name = dbus_service_name;
+        }
+        if let Some(shutdown_inhibitor_enabled) = cli.shutdown_inhibitor_enabled {
+            self.shutdown_inhibitor.enabled = shutdown_inhibitor_enabled;
+        }
+        if let Some(privsep_enabled) = cli.privsep_enabled {
+            self.privsep.enabled = privsep_enabled;
+        }
+        if let Some(privsep_user) = cli.privsep_user {
+            self.privsep.user = Some(privsep_user);
+        }
+        if let Some(grpc_enabled) = cli.grpc_enabled {
+            self.grpc.enabled = grpc_enabled;
+        }
+        if let Some(grpc_address) = cli.grpc_address {
+            self.grpc.address = grpc_address;
+        }
+        if let Some(grpc_status_interval_secs) = cli.grpc_status_interval_secs {
+            self.grpc.status_interval = Duration::from_secs(grpc_status_interval_secs);
+        }
+        if let Some(ros_enabled) = cli.ros_enabled {
+            self.ros.enabled = ros_enabled;
+        }
+        if let Some(ros_node_name) = cli.ros_node_name {
+            self.ros.node_name = ros_node_name;
+        }
+        if let Some(status_led_enabled) = cli.status_led_enabled {
+            self.status_led.enabled = status_led_enabled;
+        }
+        if let Some(status_led_gpiochip) = cli.status_led_gpiochip {
+            self.status_led.gpiochip = Some(status_led_gpiochip);
+        }
+        if let Some(status_led_line_offset) = cli.status_led_line_offset {
+            self.status_led.line_offset = Some(status_led_line_offset);
+        }
+        if let Some(capture_feedback_enabled) = cli.capture_feedback_enabled {
+            self.capture_feedback.enabled = capture_feedback_enabled;
+        }
+        if let Some(capture_feedback_gpiochip) = cli.capture_feedback_gpiochip {
+            self.capture_feedback.gpiochip = Some(capture_feedback_gpiochip);
+        }
+        if let Some(capture_feedback_line_offset) = cli.capture_feedback_line_offset {
+            self.capture_feedback.line_offset = Some(capture_feedback_line_offset);
+        }
+        if let Some(buzzer_enabled) = cli.buzzer_enabled {
+            self.buzzer.enabled = buzzer_enabled;
+        }
+        if let Some(buzzer_gpiochip) = cli.buzzer_gpiochip {
+            self.buzzer.gpiochip = Some(buzzer_gpiochip);
+        }
+        if let Some(buzzer_line_offset) = cli.buzzer_line_offset {
+            self.buzzer.line_offset = Some(buzzer_line_offset);
+        }
+        if let Some(intervalometer_enabled) = cli.intervalometer_enabled {
+            self.intervalometer.enabled = intervalometer_enabled;
+        }
+        if let Some(intervalometer_interval_secs) = cli.intervalometer_interval_secs {
+            self.intervalometer.interval_secs = Some(intervalometer_interval_secs);
+        }
+        if let Some(intervalometer_distance_meters) = cli.intervalometer_distance_meters {
+            self.intervalometer.distance_meters = Some(intervalometer_distance_meters);
+        }
+        if let Some(subtitle_enabled) = cli.subtitle_enabled {
+            self.subtitle.enabled = subtitle_enabled;
+        }
+        if let Some(subtitle_interval_secs) = cli.subtitle_interval_secs {
+            self.subtitle.interval_secs = subtitle_interval_secs;
+        }
+        if let Some(thermal_enabled) = cli.thermal_enabled {
+            self.thermal.enabled = thermal_enabled;
+        }
+        if let Some(thermal_warn_temp_celsius) = cli.thermal_warn_temp_celsius {
+            self.thermal.warn_temp_celsius = thermal_warn_temp_celsius;
+        }
+        if let Some(thermal_finalize_on_undervoltage) = cli.thermal_finalize_on_undervoltage {
+            self.thermal.finalize_on_undervoltage = thermal_finalize_on_undervoltage;
+        }
+        if let Some(storage_health_enabled) = cli.storage_health_enabled {
+            self.storage_health.enabled = storage_health_enabled;
+        }
+        if let Some(storage_health_device) = cli.storage_health_device {
+            self.storage_health.device = Some(storage_health_device);
+        }
+        if let Some(storage_health_warn_percent_used) = cli.storage_health_warn_percent_used {
+            self.storage_health.warn_percent_used = storage_health_warn_percent_used;
+        }
+        if let Some(time_sync_check_enabled) = cli.time_sync_check_enabled {
+            self.time_sync_check.enabled = time_sync_check_enabled;
+        }
+        if let Some(offload_enabled) = cli.offload_enabled {
+            self.offload.enabled = offload_enabled;
+        }
+        if let Some(offload_ground_host) = cli.offload_ground_host {
+            self.offload.ground_host = Some(offload_ground_host);
+        }
+        if let Some(offload_remote_dir) = cli.offload_remote_dir {
+            self.offload.remote_dir = Some(offload_remote_dir);
+        }
+        if let Some(offload_ssh_key_path) = cli.offload_ssh_key_path {
+            self.offload.ssh_key_path = Some(offload_ssh_key_path);
+        }
+        if let Some(offload_bandwidth_limit_kbps) = cli.offload_bandwidth_limit_kbps {
+            self.offload.bandwidth_limit_kbps = Some(offload_bandwidth_limit_kbps);
+        }
+        if let Some(offload_delete_after_verified) = cli.offload_delete_after_verified {
+            self.offload.delete_after_verified = offload_delete_after_verified;
+        }
+    }
+}
+
+/// Parses an environment variable, if set, logging (and ignoring) it if it
+/// fails to parse as `T` rather than silently falling back to the default.
+fn env_var<T>(name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = std::env::var(name).ok()?;
+
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(error) => {
+            error!("ignoring {name}={value:?}: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    // apply_env() reads a process-wide environment variable, so serialize
+    // the tests that touch it to avoid one clobbering another's value.
+    static LINE_OFFSET_ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    const LINE_OFFSET_ENV_VAR: &str = "CAMERA_TRIGGER_LINE_OFFSET";
+
+    fn empty_cli() -> Cli {
+        Cli {
+            command: None,
+            config: None,
+            instance: None,
+            pidfile: None,
+            device_ready_timeout_secs: None,
+            storage_mount_point: None,
+            storage_min_free_bytes: None,
+            log_format: None,
+            gpiochip: None,
+            line_offset: None,
+            gpiochip_label: None,
+            line_name: None,
+            consumer_label: None,
+            line_bias: None,
+            active_low: None,
+            debounce_period_micros: None,
+            event_clock_realtime: None,
+            min_pulse_width_ms: None,
+            short_pulse_max_ms: None,
+            invert_polarity: None,
+            pwm_mode: None,
+            pwm_record_above_us: None,
+            pwm_stop_below_us: None,
+            auto_start_recording: None,
+            simulate: None,
+            simulate_interval_secs: None,
+            replay_log: None,
+            generate_interval_ms: None,
+            generate_pulse_width_ms: None,
+            generate_count: None,
+            bench_duration_secs: None,
+            backend: None,
+            source: None,
+            source_device: None,
+            usb_serial: None,
+            usb_port_path: None,
+            codec: None,
+            encoder: None,
+            zero_copy_enabled: None,
+            capture_width: None,
+            capture_height: None,
+            capture_framerate: None,
+            video_bitrate_kbps: None,
+            adaptive_bitrate_enabled: None,
+            adaptive_bitrate_min_kbps: None,
+            adaptive_bitrate_step_kbps: None,
+            adaptive_bitrate_recovery_secs: None,
+            container: None,
+            libcamera_vid_binary: None,
+            libcamera_sensor_mode: None,
+            libcamera_ae_enabled: None,
+            libcamera_awb_enabled: None,
+            libcamera_min_frame_duration_micros: None,
+            libcamera_max_frame_duration_micros: None,
+            write_queue_depth: None,
+            backpressure_policy: None,
+            output_dir: None,
+            secondary_output_dir: None,
+            encryption_recipient: None,
+            orientation: None,
+            crop_left: None,
+            crop_right: None,
+            crop_top: None,
+            crop_bottom: None,
+            file_pattern: None,
+            naming: None,
+            flight_session: None,
+            segment_duration_secs: None,
+            preroll_duration_secs: None,
+            frame_stall_timeout_secs: None,
+            max_recording_duration_secs: None,
+            self_test_enabled: None,
+            self_test_degraded_on_failure: None,
+            init_degraded_on_failure: None,
+            init_retry_interval_secs: None,
+            still_capture: None,
+            still_burst_count: None,
+            still_file_pattern: None,
+            still_aeb_enabled: None,
+            still_aeb_ev_stops: None,
+            still_raw_enabled: None,
+            still_thermal_radiometric_enabled: None,
+            still_dual_stream_enabled: None,
+            libcamera_still_binary: None,
+            rtsp_preview_enabled: None,
+            rtsp_preview_address: None,
+            rtsp_preview_bitrate_kbps: None,
+            webrtc_preview_enabled: None,
+            webrtc_preview_whip_endpoint: None,
+            webrtc_preview_bitrate_kbps: None,
+            srt_output_enabled: None,
+            srt_output_address: None,
+            srt_output_bitrate_kbps: None,
+            embed_frame_metadata: None,
+            osd_overlay_enabled: None,
+            osd_overlay_interval_secs: None,
+            audio_capture_enabled: None,
+            audio_device: None,
+            audio_bitrate_kbps: None,
+            exposure_micros: None,
+            gain: None,
+            white_balance_kelvin: None,
+            focus_position: None,
+            mavlink_enabled: None,
+            mavlink_address: None,
+            mavlink_system_id: None,
+            mavlink_component_id: None,
+            mavlink_require_armed: None,
+            mavlink_auto_stop_on_disarm: None,
+            mavlink_trigger_source: None,
+            mavlink_trigger_fusion: None,
+            mavlink_trigger_fusion_dedup_window_ms: None,
+            mavlink_min_altitude_gate_m: None,
+            mavlink_block_triggers_during_rtl: None,
+            pps_enabled: None,
+            pps_gpiochip: None,
+            pps_line_offset: None,
+            pps_device: None,
+            min_free_disk_bytes: None,
+            retention_enabled: None,
+            retention_max_bytes: None,
+            retention_min_free_bytes: None,
+            trigger_log_max_bytes: None,
+            trigger_log_max_age_secs: None,
+            status_enabled: None,
+            status_socket_path: None,
+            status_file_path: None,
+            status_file_interval_secs: None,
+            durability_interval_secs: None,
+            durability_max_bytes: None,
+            metrics_enabled: None,
+            metrics_address: None,
+            control_api_enabled: None,
+            control_api_address: None,
+            network_trigger_enabled: None,
+            network_trigger_address: None,
+            network_trigger_shared_secret: None,
+            dronecan_enabled: None,
+            dronecan_interface: None,
+            dronecan_node_id: None,
+            dronecan_trigger_can_id: None,
+            dronecan_feedback_can_id: None,
+            mqtt_enabled: None,
+            mqtt_address: None,
+            mqtt_topic_prefix: None,
+            dbus_enabled: None,
+            dbus_service_name: None,
+            shutdown_inhibitor_enabled: None,
+            privsep_enabled: None,
+            privsep_user: None,
+            grpc_enabled: None,
+            grpc_address: None,
+            grpc_status_interval_secs: None,
+            ros_enabled: None,
+            ros_node_name: None,
+            status_led_enabled: None,
+            status_led_gpiochip: None,
+            status_led_line_offset: None,
+            capture_feedback_enabled: None,
+            capture_feedback_gpiochip: None,
+            capture_feedback_line_offset: None,
+            buzzer_enabled: None,
+            buzzer_gpiochip: None,
+            buzzer_line_offset: None,
+            intervalometer_enabled: None,
+            intervalometer_interval_secs: None,
+            intervalometer_distance_meters: None,
+            subtitle_enabled: None,
+            subtitle_interval_secs: None,
+            thermal_enabled: None,
+            thermal_warn_temp_celsius: None,
+            thermal_finalize_on_undervoltage: None,
+            storage_health_enabled: None,
+            storage_health_device: None,
+            storage_health_warn_percent_used: None,
+            time_sync_check_enabled: None,
+            offload_enabled: None,
+            offload_ground_host: None,
+            offload_remote_dir: None,
+            offload_ssh_key_path: None,
+            offload_bandwidth_limit_kbps: None,
+            offload_delete_after_verified: None,
+        }
+    }
+
+    fn resolve(file_line_offset: Option<u32>, cli_line_offset: Option<u32>) -> u32 {
+        let mut config = Config::default();
+
+        config.apply_file(FileConfig {
+            line_offset: file_line_offset,
+            ..Default::default()
+        });
+        config.apply_env();
+        config.apply_cli(Cli {
+            line_offset: cli_line_offset,
+            ..empty_cli()
+        });
+
+        config.line_offset
+    }
+
+    #[test]
+    fn default_wins_when_nothing_overrides_it() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+
+        assert_eq!(resolve(None, None), Config::default().line_offset);
+    }
+
+    #[test]
+    fn file_overrides_default() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+
+        assert_eq!(resolve(Some(5), None), 5);
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::set_var(LINE_OFFSET_ENV_VAR, "7");
+
+        let line_offset = resolve(Some(5), None);
+
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+        assert_eq!(line_offset, 7);
+    }
+
+    #[test]
+    fn cli_overrides_env_and_file() {
+        let _guard = LINE_OFFSET_ENV_LOCK.lock().unwrap();
+        std::env::set_var(LINE_OFFSET_ENV_VAR, "7");
+
+        let line_offset = resolve(Some(5), Some(9));
+
+        std::env::remove_var(LINE_OFFSET_ENV_VAR);
+        assert_eq!(line_offset, 9);
+    }
+}
@@ -0,0 +1,312 @@
+//! An optional HTTP control API, so the ground crew can start/stop recording,
+//! pull a snapshot, and adjust camera controls over the telemetry WiFi link
+//! when there's no GPIO trigger wired up (e.g. bench testing, or a
+//! ground-crew override during a flight).
+//!
+//! Also serves a minimal status dashboard at `GET /` (see
+//! [`DASHBOARD_HTML`]) on the same port, so the field crew can check on
+//! the payload from a phone on the aircraft's WiFi without SSH.
+//!
+//! Hand-rolled over a bare [`TcpListener`], same as [`crate::metrics`]: a
+//! handful of fixed routes don't need a framework's routing/middleware
+//! machinery.
+
+use std::sync::Arc;
+
+use log::{info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use crate::metrics::Metrics;
+use crate::recorder::{CameraControls, RegionOfInterest};
+
+/// Parameters for the control HTTP API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlApiConfig {
+    /// Whether to serve the endpoint at all. Off by default: the GPIO
+    /// trigger line is the primary control path, this is a fallback.
+    pub enabled: bool,
+    /// `host:port` to listen for control requests on.
+    pub address: String,
+}
+
+impl Default for ControlApiConfig {
+    fn default() -> Self {
+        Self { enabled: false, address: "0.0.0.0:9274".to_string() }
+    }
+}
+
+/// A command decoded from a control API request, handled by
+/// [`crate::main::run`]'s event loop alongside real GPIO edges.
+#[derive(Debug, Clone, Copy)]
+pub enum ControlCommand {
+    Start,
+    Stop,
+    Snapshot,
+    SetCameraControls(CameraControls),
+    /// Punches in a digital zoom/pan; see [`crate::recorder::Recorder::set_roi`].
+    SetRegionOfInterest(RegionOfInterest),
+    /// Requests an offload run; see [`crate::offload`]. A no-op if offload
+    /// isn't configured.
+    Offload,
+}
+
+/// Binds `address` and serves `/start`, `/stop`, `/snapshot`, `/controls`,
+/// `/roi`, `/offload` (all POST, each forwarded to `commands` for the event
+/// loop to act on), `/status` (GET, rendered from `metrics`) and `/` (GET,
+/// the status dashboard) until the process exits.
+pub async fn spawn_server(
+    address: &str,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|error| format!("failed to bind control api on {address}: {error}"))?;
+
+    info!("control api listening on {address}");
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    warn!("failed to accept control api connection: {error}");
+                    continue;
+                }
+            };
+
+            tokio::spawn(handle_connection(socket, commands.clone(), Arc::clone(&metrics)));
+        }
+    });
+
+    Ok(())
+}
+
+/// Reads a single request off `socket` and dispatches it by method + path.
+async fn handle_connection(
+    mut socket: TcpStream,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+) {
+    let mut buffer = [0u8; 1024];
+    let read = match socket.read(&mut buffer).await {
+        Ok(read) => read,
+        Err(error) => {
+            warn!("failed to read control api request: {error}");
+            return;
+        }
+    };
+
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path_and_query = request_line.next().unwrap_or("/");
+    let (path, query) = path_and_query.split_once('?').unwrap_or((path_and_query, ""));
+
+    let response = match (method, path) {
+        ("POST", "/start") => dispatch(&commands, ControlCommand::Start),
+        ("POST", "/stop") => dispatch(&commands, ControlCommand::Stop),
+        ("POST", "/snapshot") => dispatch(&commands, ControlCommand::Snapshot),
+        ("POST", "/controls") => dispatch(&commands, ControlCommand::SetCameraControls(parse_camera_controls(query))),
+        ("POST", "/roi") => dispatch(&commands, ControlCommand::SetRegionOfInterest(parse_roi(query))),
+        ("POST", "/offload") => dispatch(&commands, ControlCommand::Offload),
+        ("GET", "/status") => text_response("200 OK", "application/json", &render_status(&metrics)),
+        ("GET", "/") => text_response("200 OK", "text/html", DASHBOARD_HTML),
+        _ => text_response("404 Not Found", "text/plain", "not found"),
+    };
+
+    if let Err(error) = socket.write_all(response.as_bytes()).await {
+        warn!("failed to write control api response: {error}");
+    }
+}
+
+/// Forwards `command` to the event loop, responding `202 Accepted` if the
+/// channel is still open or `503 Service Unavailable` if the event loop has
+/// already shut down.
+fn dispatch(commands: &mpsc::UnboundedSender<ControlCommand>, command: ControlCommand) -> String {
+    match commands.send(command) {
+        Ok(()) => text_response("202 Accepted", "text/plain", "accepted"),
+        Err(_) => text_response("503 Service Unavailable", "text/plain", "event loop is shutting down"),
+    }
+}
+
+/// Decodes `POST /controls?exposure_micros=...&gain=...&white_balance_kelvin=...&focus_position=...`'s
+/// query string into a [`CameraControls`]. Unrecognized keys and values that
+/// fail to parse are silently ignored (left `None`) rather than rejecting
+/// the request, since a typo in one field shouldn't block the others from
+/// taking effect.
+fn parse_camera_controls(query: &str) -> CameraControls {
+    let mut controls = CameraControls::default();
+    for pair in query.split('&') {
+        let Some((key, value)) = pair.split_once('=') else { continue };
+        match key {
+            "exposure_micros" => controls.exposure_micros = value.parse().ok(),
+            "gain" => controls.gain = value.parse().ok(),
+            "white_balance_kelvin" => controls.white_balance_kelvin = value.parse().ok(),
+            "focus_position" => controls.focus_position = value.parse().ok(),
+            _ => {}
+        }
+    }
+    controls
+}
+
+/// Decodes `POST /roi?x=...&y=...&width=...&height=...`'s query string into a
+/// [`RegionOfInterest`]. Same unrecognized-key/bad-value handling as
+/// [`parse_camera_controls`].
+fn parse_roi(query: &str) -> RegionOfInterest {
+    let mut roi = RegionOfInterest::default();
+    for pair in query.split('&') {
+        let Some((key, value)) = pair.split_once('=') else { continue };
+        match key {
+            "x" => roi.x = value.parse().ok(),
+            "y" => roi.y = value.parse().ok(),
+            "width" => roi.width = value.parse().ok(),
+            "height" => roi.height = value.parse().ok(),
+            _ => {}
+        }
+    }
+    roi
+}
+
+fn text_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Hand-rolled rather than pulling in `serde_json` for a handful of fields.
+/// Also used by [`crate::dbus_api`]'s `GetStatus` method and
+/// [`crate::status`]'s Unix status socket/file, so every status surface
+/// agrees on what "status" means.
+pub(crate) fn render_status(metrics: &Metrics) -> String {
+    let mut cameras = Vec::new();
+    for (name, camera) in metrics.cameras() {
+        let current_file = camera
+            .current_file()
+            .map(|path| format!("{:?}", path.display().to_string()))
+            .unwrap_or_else(|| "null".to_string());
+        let free_disk_bytes = fs4::available_space(camera.output_dir())
+            .map(|bytes| bytes.to_string())
+            .unwrap_or_else(|_| "null".to_string());
+        cameras.push(format!(
+            "{{\"camera\":{name:?},\"recording\":{},\"current_file\":{current_file},\"free_disk_bytes\":{free_disk_bytes},\"errors\":{}}}",
+            camera.is_recording(),
+            camera.errors(),
+        ));
+    }
+
+    let storage_health_percent_used = metrics
+        .storage_health_percent_used()
+        .map(|percent| percent.to_string())
+        .unwrap_or_else(|| "null".to_string());
+
+    let recent_triggers: Vec<String> = metrics
+        .recent_triggers()
+        .into_iter()
+        .map(|trigger| format!("{{\"timestamp\":{},\"kind\":{:?}}}", trigger.timestamp, trigger.kind))
+        .collect();
+
+    format!(
+        "{{\"trigger_count\":{},\"last_trigger_timestamp\":{},\"storage_health_percent_used\":{storage_health_percent_used},\"recent_triggers\":[{}],\"cameras\":[{}]}}",
+        metrics.trigger_count(),
+        metrics.last_trigger_timestamp(),
+        recent_triggers.join(","),
+        cameras.join(",")
+    )
+}
+
+/// Static single-page dashboard served at `GET /`: polls [`render_status`]
+/// (the same `/status` JSON every other status surface agrees on, per its
+/// doc comment) every couple of seconds and renders live state, recent
+/// triggers, per-camera health and disk usage. Inline CSS/JS rather than a
+/// separate asset, since there's exactly one page and nothing else in this
+/// crate serves static files.
+const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>camera trigger status</title>
+<style>
+body { font-family: sans-serif; margin: 1em; background: #111; color: #eee; }
+h1 { font-size: 1.2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+td, th { border: 1px solid #444; padding: 0.3em 0.6em; text-align: left; font-size: 0.9em; }
+.recording { color: #4f4; }
+.idle { color: #888; }
+.errors { color: #f44; }
+button { margin-right: 0.5em; }
+</style>
+</head>
+<body>
+<h1>camera trigger status</h1>
+<p id="summary">loading...</p>
+<table id="cameras"><thead><tr><th>camera</th><th>state</th><th>current file</th><th>free disk</th><th>errors</th></tr></thead><tbody></tbody></table>
+<h2>recent triggers</h2>
+<table id="triggers"><thead><tr><th>time</th><th>kind</th></tr></thead><tbody></tbody></table>
+<p>
+<button onclick="post('/start')">start</button>
+<button onclick="post('/stop')">stop</button>
+<button onclick="post('/snapshot')">snapshot</button>
+</p>
+<script>
+function post(path) {
+    fetch(path, { method: 'POST' });
+}
+
+function formatBytes(bytes) {
+    if (bytes === null) return 'unknown';
+    const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+    let value = bytes, index = 0;
+    while (value >= 1024 && index < units.length - 1) {
+        value /= 1024;
+        index += 1;
+    }
+    return value.toFixed(1) + ' ' + units[index];
+}
+
+async function refresh() {
+    let status;
+    try {
+        status = await (await fetch('/status')).json();
+    } catch (error) {
+        document.getElementById('summary').textContent = 'status endpoint unreachable: ' + error;
+        return;
+    }
+
+    const lastTrigger = status.last_trigger_timestamp
+        ? new Date(status.last_trigger_timestamp * 1000).toLocaleString()
+        : 'never';
+    const wear = status.storage_health_percent_used === null ? 'unknown' : status.storage_health_percent_used + '%';
+    document.getElementById('summary').textContent =
+        'triggers: ' + status.trigger_count + ' | last trigger: ' + lastTrigger + ' | recording medium wear: ' + wear;
+
+    const camerasBody = document.getElementById('cameras').querySelector('tbody');
+    camerasBody.innerHTML = '';
+    for (const camera of status.cameras) {
+        const row = camerasBody.insertRow();
+        row.innerHTML =
+            '<td>' + camera.camera + '</td>' +
+            '<td class="' + (camera.recording ? 'recording' : 'idle') + '">' + (camera.recording ? 'recording' : 'idle') + '</td>' +
+            '<td>' + (camera.current_file || '-') + '</td>' +
+            '<td>' + formatBytes(camera.free_disk_bytes) + '</td>' +
+            '<td class="' + (camera.errors > 0 ? 'errors' : '') + '">' + camera.errors + '</td>';
+    }
+
+    const triggersBody = document.getElementById('triggers').querySelector('tbody');
+    triggersBody.innerHTML = '';
+    for (const trigger of status.recent_triggers.slice().reverse()) {
+        const row = triggersBody.insertRow();
+        row.innerHTML = '<td>' + new Date(trigger.timestamp * 1000).toLocaleTimeString() + '</td><td>' + trigger.kind + '</td>';
+    }
+}
+
+refresh();
+setInterval(refresh, 2000);
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,133 @@
+//! An optional D-Bus control/introspection service, so other onboard
+//! services and `busctl` debugging can start/stop recording and read status
+//! the same way they do with our other systemd-managed daemons, instead of
+//! needing to know [`crate::control_api`]'s HTTP routes.
+//!
+//! `zbus` handles the wire protocol (SASL auth handshake, message framing,
+//! introspection XML), same reasoning as [`crate::mavlink`] and
+//! [`crate::mqtt`] pulling in a real crate for a protocol with its own
+//! handshake/state machine rather than hand-rolling it.
+
+use std::sync::Arc;
+
+use log::{info, warn};
+use tokio::sync::mpsc;
+use zbus::{connection, interface, object_server::SignalEmitter};
+
+use crate::control_api::{render_status, ControlCommand};
+use crate::metrics::Metrics;
+
+/// Parameters for the D-Bus control/introspection service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbusConfig {
+    /// Whether to register the service at all. Off by default: the GPIO
+    /// trigger line is the primary control path, this is a fallback.
+    pub enabled: bool,
+    /// The well-known bus name to reserve, e.g. `"com.aeroteameindhoven.CameraTrigger"`.
+    pub service_name: String,
+}
+
+impl Default for DbusConfig {
+    fn default() -> Self {
+        Self { enabled: false, service_name: "com.aeroteameindhoven.CameraTrigger".to_string() }
+    }
+}
+
+/// Where [`RecorderInterface`] is served, and where [`DbusPublisher`] emits
+/// `StateChanged` from.
+const OBJECT_PATH: &str = "/com/aeroteameindhoven/CameraTrigger";
+
+/// The D-Bus-exposed object; method calls are forwarded to the event loop
+/// the same way [`crate::control_api`]'s HTTP routes are.
+struct RecorderInterface {
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+}
+
+#[interface(name = "com.aeroteameindhoven.CameraTrigger1")]
+impl RecorderInterface {
+    /// Forwards a start request to the event loop. Returns `false` if the
+    /// event loop has already shut down, same distinction
+    /// [`crate::control_api::dispatch`] reports as `503`.
+    async fn start_recording(&self) -> bool {
+        self.commands.send(ControlCommand::Start).is_ok()
+    }
+
+    async fn stop_recording(&self) -> bool {
+        self.commands.send(ControlCommand::Stop).is_ok()
+    }
+
+    async fn snapshot(&self) -> bool {
+        self.commands.send(ControlCommand::Snapshot).is_ok()
+    }
+
+    /// Same JSON [`crate::control_api`]'s `GET /status` returns.
+    async fn get_status(&self) -> String {
+        render_status(&self.metrics)
+    }
+
+    /// Emitted by [`DbusPublisher::publish_state_changed`] whenever a camera
+    /// starts or stops recording.
+    #[zbus(signal)]
+    async fn state_changed(signal_emitter: &SignalEmitter<'_>, camera: &str, recording: bool) -> zbus::Result<()>;
+}
+
+/// A handle for emitting `StateChanged` signals from outside the object
+/// server, cheap to clone same as [`crate::mqtt::MqttPublisher`].
+#[derive(Clone)]
+pub struct DbusPublisher {
+    connection: Option<zbus::Connection>,
+}
+
+impl DbusPublisher {
+    /// A publisher that drops every signal, for when `DbusConfig::enabled`
+    /// is off - callers don't need to branch on whether D-Bus is configured.
+    pub fn disabled() -> Self {
+        Self { connection: None }
+    }
+
+    /// Fire-and-forget, same as [`crate::mqtt::MqttPublisher::publish`]: a
+    /// signal that fails to emit is logged and dropped rather than
+    /// propagated to the caller.
+    pub fn publish_state_changed(&self, camera: &str, recording: bool) {
+        let Some(connection) = self.connection.clone() else { return };
+        let camera = camera.to_string();
+        tokio::spawn(async move {
+            let emitter = match SignalEmitter::new(&connection, OBJECT_PATH) {
+                Ok(emitter) => emitter,
+                Err(error) => {
+                    warn!("failed to build dbus signal emitter: {error}");
+                    return;
+                }
+            };
+            if let Err(error) = RecorderInterface::state_changed(&emitter, &camera, recording).await {
+                warn!("failed to emit dbus StateChanged signal: {error}");
+            }
+        });
+    }
+}
+
+/// Connects to the system bus, reserves `config.service_name` and serves
+/// [`RecorderInterface`] at `/com/aeroteameindhoven/CameraTrigger` until the
+/// process exits.
+pub async fn spawn(
+    config: &DbusConfig,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+) -> Result<DbusPublisher, String> {
+    let interface = RecorderInterface { commands, metrics };
+
+    let connection = connection::Builder::system()
+        .map_err(|error| format!("failed to connect to system bus: {error}"))?
+        .name(config.service_name.as_str())
+        .map_err(|error| format!("failed to reserve dbus name {:?}: {error}", config.service_name))?
+        .serve_at(OBJECT_PATH, interface)
+        .map_err(|error| format!("failed to serve dbus object: {error}"))?
+        .build()
+        .await
+        .map_err(|error| format!("failed to start dbus service: {error}"))?;
+
+    info!("dbus service registered as {}", config.service_name);
+
+    Ok(DbusPublisher { connection: Some(connection) })
+}
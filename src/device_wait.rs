@@ -0,0 +1,91 @@
+//! Bounded waiting for a camera device node to appear and for external
+//! storage to be mounted, since this service's systemd unit often starts
+//! before udev has finished enumerating a USB camera or before an external
+//! SSD has finished mounting - without this, startup would fail immediately
+//! on a race that resolves itself half a second later. Also a one-shot free
+//! space check ([`check_free_space`]) for once the mount itself shows up, so
+//! a degraded mount doesn't pass silently.
+//!
+//! See [`crate::config::Config::device_ready_timeout`],
+//! [`crate::config::Config::storage_mount_point`] and
+//! [`crate::config::Config::storage_min_free_bytes`], and their use in
+//! [`crate::main::run`].
+
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often to re-check while waiting.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Waits for `device_node` to exist, up to `timeout`. A `timeout` of
+/// [`Duration::ZERO`] disables the wait entirely, matching the convention
+/// used by other startup timeouts in [`crate::config`].
+pub async fn wait_for_device_node(device_node: &Path, timeout: Duration) -> Result<(), String> {
+    wait_until(timeout, || device_node.exists())
+        .await
+        .map_err(|()| format!("timed out after {timeout:?} waiting for {} to appear", device_node.display()))
+}
+
+/// Waits for `mount_point` to become an actual mount point rather than a
+/// plain directory on whatever filesystem its parent lives on, up to
+/// `timeout`. Catches the case where an external SSD hasn't finished
+/// mounting yet and recordings would otherwise be silently written to the
+/// root filesystem instead.
+pub async fn wait_for_mount(mount_point: &Path, timeout: Duration) -> Result<(), String> {
+    wait_until(timeout, || is_mount_point(mount_point))
+        .await
+        .map_err(|()| format!("timed out after {timeout:?} waiting for {} to be mounted", mount_point.display()))
+}
+
+/// Checks that `mount_point`'s filesystem reports at least `min_free_bytes`
+/// available. Catches a degraded mount - a fallback tmpfs quietly mounted in
+/// place of a missing SSD, or an SSD that mounted fine but is already
+/// nearly full from a prior flight - that would otherwise pass
+/// [`wait_for_mount`] and only fail once recordings are already underway.
+/// `min_free_bytes` of `0` disables the check.
+pub fn check_free_space(mount_point: &Path, min_free_bytes: u64) -> Result<(), String> {
+    if min_free_bytes == 0 {
+        return Ok(());
+    }
+
+    let available = fs4::available_space(mount_point)
+        .map_err(|error| format!("failed to check free space on {}: {error}", mount_point.display()))?;
+    if available < min_free_bytes {
+        return Err(format!(
+            "{} has only {available} bytes free, below the {min_free_bytes} byte minimum",
+            mount_point.display()
+        ));
+    }
+    Ok(())
+}
+
+async fn wait_until(timeout: Duration, mut ready: impl FnMut() -> bool) -> Result<(), ()> {
+    if timeout.is_zero() {
+        return Ok(());
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if ready() {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// `path` is a mount point if its device differs from its parent's - the
+/// same check the `mountpoint(1)` command line tool uses. A path that
+/// doesn't exist yet, or has no parent, is never a mount point.
+fn is_mount_point(path: &Path) -> bool {
+    let (Ok(metadata), Some(parent)) = (std::fs::metadata(path), path.parent()) else {
+        return false;
+    };
+    let Ok(parent_metadata) = std::fs::metadata(parent) else {
+        return false;
+    };
+    metadata.dev() != parent_metadata.dev()
+}
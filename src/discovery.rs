@@ -0,0 +1,164 @@
+//! Enumerates GPIO chips/lines and detected cameras (V4L2/UVC and
+//! libcamera), for the `cameras probe` subcommand (see [`crate::main::probe`])
+//! so field setup on a new board doesn't need separate `gpioinfo`/`v4l2-ctl`
+//! invocations to find a chip label, line offset, or a camera's supported
+//! capture resolutions.
+
+use std::path::PathBuf;
+
+use log::warn;
+use v4l::video::Capture;
+use v4l::Device;
+
+/// One line on a discovered GPIO chip.
+pub struct GpioLineInfo {
+    pub offset: u32,
+    pub name: String,
+    pub consumer: Option<String>,
+    pub direction: String,
+    pub used: bool,
+}
+
+/// One discovered GPIO chip and its lines.
+pub struct GpioChipInfo {
+    pub path: PathBuf,
+    pub label: String,
+    pub lines: Vec<GpioLineInfo>,
+}
+
+/// Enumerates every `/dev/gpiochipN` visible to this process, each with
+/// every line's name/consumer/direction, the same information
+/// [`crate::main::list_lines`] prints for a single already-chosen chip.
+/// Chips/lines that fail to open are logged and skipped rather than failing
+/// the whole probe.
+pub fn list_gpiochips() -> Vec<GpioChipInfo> {
+    let chips = match gpio_cdev::chips() {
+        Ok(chips) => chips,
+        Err(error) => {
+            warn!("failed to enumerate gpio chips: {error}");
+            return Vec::new();
+        }
+    };
+
+    let mut result = Vec::new();
+    for chip in chips {
+        let mut chip = match chip {
+            Ok(chip) => chip,
+            Err(error) => {
+                warn!("failed to open a gpio chip: {error}");
+                continue;
+            }
+        };
+
+        let path = chip.path().to_path_buf();
+        let label = chip.label().to_string();
+        let mut lines = Vec::new();
+        for offset in 0..chip.num_lines() {
+            match chip.get_line(offset).and_then(|line| line.info()) {
+                Ok(info) => lines.push(GpioLineInfo {
+                    offset,
+                    name: info.name().to_string(),
+                    consumer: (!info.consumer().is_empty()).then(|| info.consumer().to_string()),
+                    direction: format!("{:?}", info.direction()),
+                    used: info.is_used(),
+                }),
+                Err(error) => warn!("failed to read line {offset} on {}: {error}", path.display()),
+            }
+        }
+        result.push(GpioChipInfo { path, label, lines });
+    }
+    result
+}
+
+/// One resolution a V4L2 device advertises for a given pixel format.
+pub struct V4l2Mode {
+    pub fourcc: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// One detected V4L2/UVC capture device and the capture modes it advertises.
+pub struct V4l2CameraInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub modes: Vec<V4l2Mode>,
+}
+
+/// Enumerates every `/dev/videoN` capture device and each pixel format's
+/// advertised resolutions (its largest reported size, for a stepwise-sized
+/// format). Devices that fail to open (e.g. a metadata-only node alongside
+/// the real capture node on some UVC cameras) are logged and skipped.
+pub fn list_v4l2_cameras() -> Vec<V4l2CameraInfo> {
+    let mut result = Vec::new();
+
+    for node in v4l::context::enum_devices() {
+        let path = node.path().to_path_buf();
+        let device = match Device::with_path(&path) {
+            Ok(device) => device,
+            Err(error) => {
+                warn!("failed to open {}: {error}", path.display());
+                continue;
+            }
+        };
+
+        let mut modes = Vec::new();
+        match device.enum_formats() {
+            Ok(formats) => {
+                for format in formats {
+                    let fourcc = format.fourcc.str().unwrap_or("????").to_string();
+                    match device.enum_framesizes(format.fourcc) {
+                        Ok(framesizes) => {
+                            for framesize in framesizes {
+                                match framesize.size {
+                                    v4l::framesize::FrameSizeEnum::Discrete(discrete) => {
+                                        modes.push(V4l2Mode { fourcc: fourcc.clone(), width: discrete.width, height: discrete.height });
+                                    }
+                                    v4l::framesize::FrameSizeEnum::Stepwise(stepwise) => {
+                                        modes.push(V4l2Mode {
+                                            fourcc: fourcc.clone(),
+                                            width: stepwise.max_width,
+                                            height: stepwise.max_height,
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        Err(error) => {
+                            warn!("failed to enumerate frame sizes for {fourcc} on {}: {error}", path.display())
+                        }
+                    }
+                }
+            }
+            Err(error) => warn!("failed to enumerate formats on {}: {error}", path.display()),
+        }
+
+        result.push(V4l2CameraInfo { path, name: node.name().unwrap_or_else(|| "unknown".to_string()), modes });
+    }
+
+    result
+}
+
+/// One camera libcamera's pipeline handlers detect.
+pub struct LibcameraCameraInfo {
+    pub id: String,
+}
+
+/// Enumerates every camera libcamera detects, by id only (the id string is
+/// what [`crate::recorder::RecorderConfig::source_device`] expects for
+/// [`crate::recorder::CaptureSource::Libcamera`]). Doesn't break out
+/// supported modes: unlike V4L2's `enum_framesizes`, libcamera only reports
+/// stream configurations once a camera is `acquire()`d, which would take
+/// exclusive ownership of it for the duration of the probe - not
+/// appropriate for a discovery command that might run alongside an already
+/// -recording instance.
+pub fn list_libcamera_cameras() -> Vec<LibcameraCameraInfo> {
+    let manager = match libcamera::camera_manager::CameraManager::new() {
+        Ok(manager) => manager,
+        Err(error) => {
+            warn!("failed to start libcamera camera manager: {error}");
+            return Vec::new();
+        }
+    };
+
+    manager.cameras().iter().map(|camera| LibcameraCameraInfo { id: camera.id().to_string() }).collect()
+}
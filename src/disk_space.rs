@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::{error, warn};
+
+/// How often free space is checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Spawns a background task that checks free space on the filesystem
+/// holding `path` every [`CHECK_INTERVAL`], calling `on_low_space` the first
+/// time it drops below `min_free_bytes` so a recording can be stopped
+/// before the encoder fails with `ENOSPC` mid-write, instead of after.
+///
+/// `on_low_space` is only called once per low-space episode: it's not
+/// re-fired on every tick while space stays low, but does fire again if
+/// space recovers (e.g. an old recording is cleaned up) and drops again
+/// later.
+pub fn spawn_monitor(path: PathBuf, min_free_bytes: u64, on_low_space: impl Fn() + Send + 'static) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        let mut below_threshold = false;
+
+        loop {
+            ticker.tick().await;
+
+            match fs4::available_space(&path) {
+                Ok(available) if available < min_free_bytes => {
+                    if !below_threshold {
+                        error!(
+                            "free space on {} is {available} bytes, below the {min_free_bytes} \
+                             byte threshold; stopping recording",
+                            path.display()
+                        );
+                        on_low_space();
+                        below_threshold = true;
+                    }
+                }
+                Ok(_) => below_threshold = false,
+                Err(error) => {
+                    warn!("failed to check free space on {}: {error}", path.display());
+                }
+            }
+        }
+    });
+}
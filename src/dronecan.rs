@@ -0,0 +1,175 @@
+//! An optional DroneCAN/UAVCAN interface over SocketCAN, so an airframe
+//! whose payload bay is wired over CAN rather than GPIO can command the
+//! camera trigger and receive capture feedback without a MAVLink link - see
+//! [`crate::mavlink`] for the equivalent over a serial/UDP MAVLink
+//! connection, and [`crate::network_trigger`] for the equivalent over UDP.
+//!
+//! Only a lightweight single-frame subset of DroneCAN's broadcast framing is
+//! implemented: a fixed, configurable 11-bit standard CAN ID per message
+//! rather than the full priority/data-type-id/source-node-id arbitration
+//! encoding, and no multi-frame transfers, dynamic node-ID allocation, or
+//! DSDL type registry - this integration's trigger command and capture
+//! feedback both fit in one classic CAN frame, so none of that machinery is
+//! needed. `node_id` is still carried so a bus with more than one of these
+//! processes on it (e.g. one per payload bay) can tell whose feedback frame
+//! is whose.
+
+use log::{info, warn};
+use socketcan::tokio::CanSocket;
+use socketcan::{CanFrame, EmbeddedFrame, Id, StandardId};
+use tokio::sync::mpsc;
+
+use crate::control_api::ControlCommand;
+
+/// Command byte values recognized in a trigger frame's first payload byte,
+/// same values as [`crate::network_trigger`]'s UDP protocol, since both
+/// decode to the same three [`ControlCommand`]s.
+const COMMAND_START: u8 = 1;
+const COMMAND_STOP: u8 = 2;
+const COMMAND_PHOTO: u8 = 3;
+
+/// Whether/how to bridge camera trigger commands and capture feedback onto a
+/// DroneCAN/UAVCAN bus. Off by default: the GPIO trigger line is the primary
+/// control path, this is for airframes wired over CAN instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroneCanConfig {
+    pub enabled: bool,
+    /// SocketCAN interface name, e.g. `"can0"`.
+    pub interface: String,
+    /// This node's ID on the bus, carried in every feedback frame's payload
+    /// so a listener can tell which payload bay reported it.
+    pub node_id: u8,
+    /// Standard (11-bit) CAN ID trigger commands are received on.
+    pub trigger_can_id: u16,
+    /// Standard (11-bit) CAN ID capture feedback is sent on.
+    pub feedback_can_id: u16,
+}
+
+impl Default for DroneCanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: "can0".to_string(),
+            node_id: 100,
+            trigger_can_id: 0x7c0,
+            feedback_can_id: 0x7c1,
+        }
+    }
+}
+
+/// A capture event forwarded to [`spawn`]'s publisher task, mirroring
+/// [`crate::ros2_bridge::RosEvent::Trigger`].
+#[derive(Debug, Clone, Copy)]
+pub struct DroneCanEvent {
+    pub sequence: u64,
+    pub gpio_timestamp_ns: u64,
+}
+
+/// A handle for sending [`DroneCanEvent`]s to the publisher task. Cheap to
+/// clone, same as [`crate::mqtt::MqttPublisher`].
+#[derive(Clone)]
+pub struct DroneCanPublisher {
+    events: mpsc::UnboundedSender<DroneCanEvent>,
+}
+
+impl DroneCanPublisher {
+    /// A publisher that drops every event, for when [`DroneCanConfig::enabled`]
+    /// is off - callers don't need to branch on whether the bus is
+    /// configured.
+    pub fn disabled() -> Self {
+        let (events, _rx) = mpsc::unbounded_channel();
+        Self { events }
+    }
+
+    /// Fire-and-forget, same as [`crate::mqtt::MqttPublisher::publish`].
+    pub fn publish(&self, event: DroneCanEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Opens `config.interface` and spawns a background task that forwards
+/// decoded trigger frames to `commands`, plus another that serializes and
+/// sends [`DroneCanEvent`]s from the returned [`DroneCanPublisher`] as
+/// capture-feedback frames, until the process exits.
+pub fn spawn(config: &DroneCanConfig, commands: mpsc::UnboundedSender<ControlCommand>) -> Result<DroneCanPublisher, String> {
+    let trigger_id = StandardId::new(config.trigger_can_id)
+        .ok_or_else(|| format!("dronecan trigger-can-id {:#x} is not a valid 11-bit standard id", config.trigger_can_id))?;
+    let feedback_id = StandardId::new(config.feedback_can_id)
+        .ok_or_else(|| format!("dronecan feedback-can-id {:#x} is not a valid 11-bit standard id", config.feedback_can_id))?;
+
+    let read_socket = CanSocket::open(&config.interface)
+        .map_err(|error| format!("failed to open dronecan interface {}: {error}", config.interface))?;
+
+    tokio::spawn(async move {
+        loop {
+            let frame = match read_socket.read_frame().await {
+                Ok(frame) => frame,
+                Err(error) => {
+                    warn!("failed to read dronecan frame: {error}");
+                    continue;
+                }
+            };
+
+            if frame.id() != Id::Standard(trigger_id) {
+                continue;
+            }
+
+            match decode_trigger_frame(&frame) {
+                Some(command) => {
+                    if commands.send(command).is_err() {
+                        warn!("dronecan trigger frame received, but the event loop has shut down");
+                    }
+                }
+                None => warn!("dropping malformed dronecan trigger frame"),
+            }
+        }
+    });
+
+    let write_socket = CanSocket::open(&config.interface)
+        .map_err(|error| format!("failed to open dronecan interface {}: {error}", config.interface))?;
+    let node_id = config.node_id;
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel::<DroneCanEvent>();
+    tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            let frame = encode_feedback_frame(feedback_id, node_id, event);
+            if let Err(error) = write_socket.write_frame(&frame).await {
+                warn!("failed to send dronecan capture feedback frame: {error}");
+            }
+        }
+    });
+
+    info!("dronecan bridge listening for triggers and publishing feedback on {}", config.interface);
+
+    Ok(DroneCanPublisher { events: events_tx })
+}
+
+/// Decodes a single command byte the same way
+/// [`crate::network_trigger::decode_packet`] does, since both protocols
+/// agree on the command byte values.
+fn decode_trigger_frame(frame: &CanFrame) -> Option<ControlCommand> {
+    match frame.data().first()? {
+        &COMMAND_START => Some(ControlCommand::Start),
+        &COMMAND_STOP => Some(ControlCommand::Stop),
+        &COMMAND_PHOTO => Some(ControlCommand::Snapshot),
+        _ => None,
+    }
+}
+
+/// Packs `event` into one classic CAN frame: `node_id` (1 byte), `sequence`
+/// truncated to its low 32 bits (4 bytes, little-endian), and
+/// `gpio_timestamp_ns` truncated to milliseconds and its low 24 bits (3
+/// bytes, little-endian) - eight bytes total, the most a classic CAN frame
+/// carries. The truncation only matters for a session logging past 2^32
+/// trigger events or running for more than ~4.6 hours without a timestamp
+/// rolling over on the bus, neither of which changes what a listener does
+/// with the feedback.
+fn encode_feedback_frame(feedback_id: StandardId, node_id: u8, event: DroneCanEvent) -> CanFrame {
+    let mut data = [0u8; 8];
+    data[0] = node_id;
+    data[1..5].copy_from_slice(&(event.sequence as u32).to_le_bytes());
+    let timestamp_ms = (event.gpio_timestamp_ns / 1_000_000) as u32;
+    data[5..8].copy_from_slice(&timestamp_ms.to_le_bytes()[..3]);
+
+    CanFrame::new(Id::Standard(feedback_id), &data)
+        .expect("an 8-byte payload always fits a classic CAN frame")
+}
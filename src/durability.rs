@@ -0,0 +1,96 @@
+//! Periodic `fdatasync` of an in-progress recording, and an `fsync` of its
+//! directory once it closes, so an in-flight power loss (this hardware runs
+//! without a UPS) costs at most the configured sync window of footage
+//! instead of losing whatever the filesystem was still holding in its page
+//! cache. See [`DurabilityConfig`] and its use in [`crate::session`].
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+
+/// Durability knobs for a recording in progress. Both `None` (the default)
+/// disables periodic syncing entirely, matching this backend's existing
+/// buffered-write behavior.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DurabilityConfig {
+    /// Sync the active file at least this often.
+    pub interval: Option<Duration>,
+    /// Sync the active file once it has grown by at least this many bytes
+    /// since the last sync.
+    pub max_bytes: Option<u64>,
+}
+
+/// How often to poll the file's size and age while waiting for `interval`
+/// or `max_bytes` to trip. Small relative to realistic settings for either,
+/// so both are honored close to their configured value rather than only on
+/// a coarse tick.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Spawns a task that calls [`sync_file`] on `path` whenever `config`'s
+/// interval/size threshold trips, and returns a handle the caller stores and
+/// sets on the matching `Stop` to close it out - the same "one task per
+/// armed recording" shape as [`crate::subtitle_log::spawn`]. Callers are
+/// expected to only call this when `config.interval`/`config.max_bytes` is
+/// actually set, the same way [`crate::session`] only calls
+/// `subtitle_log::spawn` when `SubtitleConfig::enabled` is set.
+pub fn spawn(config: DurabilityConfig, path: &Path) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let task_stop = Arc::clone(&stop);
+    let path = path.to_path_buf();
+
+    tokio::spawn(async move {
+        let stop = task_stop;
+        let mut last_sync = tokio::time::Instant::now();
+        let mut synced_len = 0u64;
+
+        while !stop.load(Ordering::Relaxed) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            let due_by_time = config.interval.is_some_and(|interval| last_sync.elapsed() >= interval);
+            let due_by_size = config.max_bytes.is_some_and(|max_bytes| {
+                std::fs::metadata(&path)
+                    .map(|metadata| metadata.len().saturating_sub(synced_len) >= max_bytes)
+                    .unwrap_or(false)
+            });
+
+            if due_by_time || due_by_size {
+                match sync_file(&path) {
+                    Ok(len) => {
+                        last_sync = tokio::time::Instant::now();
+                        synced_len = len;
+                    }
+                    Err(error) => warn!("failed to sync {}: {error}", path.display()),
+                }
+            }
+        }
+    });
+
+    stop
+}
+
+/// Opens `path` fresh and calls `fdatasync` on it. A separate handle from
+/// whatever backend is actively writing the file works fine here:
+/// `fdatasync` flushes the file's data to the underlying device regardless
+/// of which descriptor requested it. Returns the file's length as of the
+/// sync, so the caller can track how much new data must accumulate before
+/// the next one is due.
+fn sync_file(path: &Path) -> std::io::Result<u64> {
+    let file = std::fs::File::open(path)?;
+    file.sync_data()?;
+    Ok(file.metadata()?.len())
+}
+
+/// Calls `fsync` on `dir` itself, not just the file inside it, so a power
+/// loss immediately after a recording closes doesn't leave the directory
+/// entry unpersisted even though the file's data already made it to disk.
+/// Logs and drops the error rather than failing the caller: a recording
+/// that's otherwise complete and synced shouldn't be treated as lost over
+/// this.
+pub fn sync_directory(dir: &Path) {
+    if let Err(error) = std::fs::File::open(dir).and_then(|handle| handle.sync_all()) {
+        warn!("failed to sync directory {}: {error}", dir.display());
+    }
+}
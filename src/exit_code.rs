@@ -0,0 +1,47 @@
+//! A small typed taxonomy of *why* this process exited nonzero at startup,
+//! so a systemd `Restart=on-failure` policy or an ops runbook can react
+//! differently per failure class (e.g. don't bother restarting on a config
+//! error, but do on a transient GPIO/camera one) instead of treating every
+//! startup failure as the same generic error.
+//!
+//! Codes 3 and 4 are reserved for
+//! [`crate::single_instance::ALREADY_RUNNING_EXIT_CODE`] and this binary's
+//! own `EVENT_RECOVERY_EXIT_CODE` (assigned before this taxonomy existed,
+//! and kept as standalone constants rather than folded in here since both
+//! already have call sites that only care about the raw code) - no
+//! [`ExitReason`] variant should reuse either.
+
+/// Which class of startup failure caused the process to exit. See each
+/// variant's doc comment for what maps to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// A config file/env/CLI value was missing, invalid, or contradictory.
+    /// Also the default for startup failures that predate this taxonomy and
+    /// haven't been classified into one of the more specific reasons below.
+    Config,
+    /// The GPIO chip/line couldn't be opened, requested, or subscribed to.
+    Gpio,
+    /// A configured camera's recording pipeline couldn't be initialized.
+    Camera,
+    /// The output directory, trigger log, or session manifest couldn't be
+    /// opened or written to.
+    Storage,
+}
+
+impl ExitReason {
+    /// The exit code a systemd unit/ops runbook can match on.
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Config => 1,
+            Self::Gpio => 2,
+            Self::Camera => 5,
+            Self::Storage => 6,
+        }
+    }
+
+    /// Logs `message` at error level and exits with this reason's code.
+    pub fn exit(self, message: &str) -> ! {
+        log::error!("{message}");
+        std::process::exit(self.exit_code());
+    }
+}
@@ -0,0 +1,93 @@
+//! Embeds PX4 position/attitude into a still capture's EXIF metadata, so
+//! photogrammetry tools can consume it directly without a separate
+//! geotagging pass over the flight log.
+
+use std::path::Path;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use little_exif::exif_tag::ExifTag;
+use little_exif::metadata::Metadata;
+use little_exif::rational::uR64;
+use log::debug;
+
+use crate::mavlink::{GimbalAttitude, Position};
+
+/// Embeds `position`'s GPS fix, altitude and yaw, plus `captured_at` and
+/// `gimbal` (if a gimbal is present), into `path`'s EXIF metadata.
+///
+/// A `(0, 0)` position (`position`'s "no fix yet" sentinel, see
+/// [`Position`]) is treated as "nothing to embed" rather than written
+/// literally, since it almost certainly means PX4 hadn't reported a real fix
+/// yet when this still was captured. `gimbal` has no such sentinel - it's
+/// `None`, not a bogus zero, until a `GIMBAL_DEVICE_ATTITUDE_STATUS` arrives.
+///
+/// `gimbal`'s roll/pitch/yaw aren't standard EXIF GPS fields - unlike
+/// `GPSImgDirection` above, there's no dedicated tag for a payload's
+/// orientation independent of the vehicle's - so they're packed into
+/// `UserComment` instead, the same place photogrammetry tools already look
+/// for DJI/Pix4D-style gimbal metadata when a dedicated tag isn't available.
+pub fn embed_gps_exif(
+    path: &Path,
+    position: &Position,
+    captured_at: SystemTime,
+    gimbal: Option<GimbalAttitude>,
+) -> Result<(), String> {
+    if position.lat == 0 && position.lon == 0 {
+        debug!("skipping EXIF geotag for {}: no position fix yet", path.display());
+        return Ok(());
+    }
+
+    let latitude = position.lat as f64 / 1e7;
+    let longitude = position.lon as f64 / 1e7;
+    let altitude_m = position.alt as f64 / 1000.0;
+    let yaw_deg = (position.yaw.to_degrees() as f64).rem_euclid(360.0);
+
+    let mut metadata = Metadata::new();
+
+    metadata.set_tag(ExifTag::GPSLatitudeRef(if latitude >= 0.0 { "N" } else { "S" }.to_string()));
+    metadata.set_tag(ExifTag::GPSLatitude(decimal_to_dms(latitude.abs())));
+    metadata.set_tag(ExifTag::GPSLongitudeRef(if longitude >= 0.0 { "E" } else { "W" }.to_string()));
+    metadata.set_tag(ExifTag::GPSLongitude(decimal_to_dms(longitude.abs())));
+    metadata.set_tag(ExifTag::GPSAltitudeRef(vec![if altitude_m >= 0.0 { 0 } else { 1 }]));
+    metadata.set_tag(ExifTag::GPSAltitude(vec![to_rational(altitude_m.abs())]));
+    metadata.set_tag(ExifTag::GPSImgDirectionRef("T".to_string()));
+    metadata.set_tag(ExifTag::GPSImgDirection(vec![to_rational(yaw_deg)]));
+
+    let captured_at: DateTime<Utc> = captured_at.into();
+    metadata.set_tag(ExifTag::DateTimeOriginal(captured_at.format("%Y:%m:%d %H:%M:%S").to_string()));
+
+    if let Some(gimbal) = gimbal {
+        metadata.set_tag(ExifTag::UserComment(format!(
+            "gimbal_roll_deg={:.2};gimbal_pitch_deg={:.2};gimbal_yaw_deg={:.2}",
+            gimbal.roll.to_degrees(),
+            gimbal.pitch.to_degrees(),
+            gimbal.yaw.to_degrees(),
+        )));
+    }
+
+    metadata
+        .write_to_file(path)
+        .map_err(|error| format!("failed to write EXIF metadata to {}: {error}", path.display()))
+}
+
+/// Converts a non-negative decimal-degree value into EXIF's
+/// degrees/minutes/seconds rational triplet.
+fn decimal_to_dms(value: f64) -> Vec<uR64> {
+    let degrees = value.trunc();
+    let minutes_full = (value - degrees) * 60.0;
+    let minutes = minutes_full.trunc();
+    let seconds = (minutes_full - minutes) * 60.0;
+
+    vec![
+        uR64 { nominator: degrees as u32, denominator: 1 },
+        uR64 { nominator: minutes as u32, denominator: 1 },
+        to_rational(seconds),
+    ]
+}
+
+/// Converts a non-negative value into a rational with two decimal digits of
+/// precision, which is what EXIF's `RATIONAL` fields expect.
+fn to_rational(value: f64) -> uR64 {
+    uR64 { nominator: (value * 100.0).round() as u32, denominator: 100 }
+}
@@ -0,0 +1,69 @@
+//! Resolves a GPIO chip and line by the label/name the kernel's device tree
+//! assigns them (e.g. chip label `"pinctrl-bcm2711"`, line name `"GPIO18"`),
+//! instead of a hard-coded `/dev/gpiochipN` path and offset. Line offsets
+//! shift between board revisions (Pi 4 vs Pi 5 vs our CM4 carrier all number
+//! their headers differently); a label/name survives that.
+//!
+//! Both [`crate::config::Config::gpiochip`]/[`crate::config::Config::line_offset`]
+//! remain the primary, always-available way to select a line - this is an
+//! opt-in resolution step `main::run`/`main::list_lines` run once at startup
+//! when `--gpiochip-label`/`--line-name` are set, since it needs to actually
+//! open hardware to look labels/names up and so can't live in [`crate::config`]
+//! itself.
+
+use std::path::PathBuf;
+
+use gpio_cdev::Chip;
+
+/// Finds the `/dev/gpiochipN` whose label matches `label` exactly. Returns an
+/// error listing every chip's label if none match, so a typo'd label is easy
+/// to spot without needing `gpiodetect` on hand.
+pub fn find_chip_by_label(label: &str) -> Result<PathBuf, String> {
+    let chips = gpio_cdev::chips().map_err(|error| format!("failed to enumerate gpio chips: {error}"))?;
+
+    let mut seen = Vec::new();
+    for chip in chips {
+        let chip = match chip {
+            Ok(chip) => chip,
+            Err(error) => {
+                seen.push(format!("<unreadable: {error}>"));
+                continue;
+            }
+        };
+        let chip_label = chip.label().to_string();
+        if chip_label == label {
+            return Ok(chip.path().to_path_buf());
+        }
+        seen.push(format!("{} ({chip_label})", chip.path().display()));
+    }
+
+    Err(format!("no gpio chip labeled {label:?} found; available chips: [{}]", seen.join(", ")))
+}
+
+/// Finds the offset of the line named `name` (as exposed by the device tree,
+/// e.g. `"GPIO18"`) on `chip`. Returns an error listing every line's name if
+/// none match.
+pub fn find_line_by_name(chip: &mut Chip, name: &str) -> Result<u32, String> {
+    let mut seen = Vec::new();
+    for offset in 0..chip.num_lines() {
+        let line_name = match chip.get_line(offset).and_then(|line| line.info()) {
+            Ok(info) => info.name().to_string(),
+            Err(error) => {
+                seen.push(format!("{offset}=<unreadable: {error}>"));
+                continue;
+            }
+        };
+        if line_name == name {
+            return Ok(offset);
+        }
+        if !line_name.is_empty() {
+            seen.push(format!("{offset}={line_name}"));
+        }
+    }
+
+    Err(format!(
+        "no line named {name:?} found on {}; available named lines: [{}]",
+        chip.path().display(),
+        seen.join(", ")
+    ))
+}
@@ -0,0 +1,122 @@
+//! An optional gRPC control service, so the mission computer (also
+//! Rust/tonic) can issue start/stop/snapshot commands and subscribe to a
+//! server-streaming `Status` RPC instead of polling
+//! [`crate::control_api`]'s HTTP `/status`.
+//!
+//! `tonic` handles the wire protocol (HTTP/2 framing, protobuf codec), same
+//! reasoning as [`crate::mavlink`], [`crate::mqtt`] and [`crate::dbus_api`]
+//! pulling in a real crate for a protocol with its own framing/state machine
+//! rather than hand-rolling it. The message/service types are generated at
+//! build time from `proto/camera_trigger.proto` by `build.rs`.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status as TonicStatus};
+
+use crate::control_api::ControlCommand;
+use crate::metrics::Metrics;
+
+pub mod proto {
+    tonic::include_proto!("camera_trigger");
+}
+
+use proto::camera_trigger_server::{CameraTrigger, CameraTriggerServer};
+use proto::{CameraStatus, CommandResult, Empty, Status as ProtoStatus};
+
+/// Parameters for the gRPC control service.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcConfig {
+    /// Whether to serve the endpoint at all. Off by default: the GPIO
+    /// trigger line is the primary control path, this is a fallback.
+    pub enabled: bool,
+    /// `host:port` to listen for gRPC requests on.
+    pub address: String,
+    /// How often a `StreamStatus` subscriber receives a new [`ProtoStatus`].
+    pub status_interval: Duration,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self { enabled: false, address: "0.0.0.0:50051".to_string(), status_interval: Duration::from_secs(1) }
+    }
+}
+
+/// The gRPC-exposed service; unary RPCs are forwarded to the event loop the
+/// same way [`crate::control_api`]'s HTTP routes are.
+struct RecorderService {
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+    status_interval: Duration,
+}
+
+#[tonic::async_trait]
+impl CameraTrigger for RecorderService {
+    async fn start_recording(&self, _request: Request<Empty>) -> Result<Response<CommandResult>, TonicStatus> {
+        Ok(Response::new(CommandResult { accepted: self.commands.send(ControlCommand::Start).is_ok() }))
+    }
+
+    async fn stop_recording(&self, _request: Request<Empty>) -> Result<Response<CommandResult>, TonicStatus> {
+        Ok(Response::new(CommandResult { accepted: self.commands.send(ControlCommand::Stop).is_ok() }))
+    }
+
+    async fn snapshot(&self, _request: Request<Empty>) -> Result<Response<CommandResult>, TonicStatus> {
+        Ok(Response::new(CommandResult { accepted: self.commands.send(ControlCommand::Snapshot).is_ok() }))
+    }
+
+    type StreamStatusStream = Pin<Box<dyn Stream<Item = Result<ProtoStatus, TonicStatus>> + Send + 'static>>;
+
+    /// Polls `metrics` on `status_interval` and pushes a snapshot to the
+    /// subscriber until it disconnects, rather than pushing on every change
+    /// - a subscriber that briefly lags behind just sees the next tick.
+    async fn stream_status(&self, _request: Request<Empty>) -> Result<Response<Self::StreamStatusStream>, TonicStatus> {
+        let (tx, rx) = mpsc::channel(4);
+        let metrics = Arc::clone(&self.metrics);
+        let status_interval = self.status_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(status_interval);
+            loop {
+                ticker.tick().await;
+                let cameras = metrics
+                    .cameras()
+                    .map(|(name, camera)| CameraStatus { camera: name.clone(), recording: camera.is_recording() })
+                    .collect();
+                let status = ProtoStatus { trigger_count: metrics.trigger_count(), cameras };
+                if tx.send(Ok(status)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Binds `config.address` and serves [`RecorderService`] until the process
+/// exits.
+pub async fn spawn(
+    config: &GrpcConfig,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    metrics: Arc<Metrics>,
+) -> Result<(), String> {
+    let address =
+        config.address.parse().map_err(|error| format!("invalid grpc address {:?}: {error}", config.address))?;
+    let service = RecorderService { commands, metrics, status_interval: config.status_interval };
+
+    info!("grpc control service listening on {}", config.address);
+
+    tokio::spawn(async move {
+        if let Err(error) = Server::builder().add_service(CameraTriggerServer::new(service)).serve(address).await {
+            warn!("grpc server exited: {error}");
+        }
+    });
+
+    Ok(())
+}
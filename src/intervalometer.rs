@@ -0,0 +1,118 @@
+//! Once a trigger `Start` arms the session, captures stills on a fixed
+//! cadence - wall-clock interval or GPS distance travelled - independent of
+//! further GPIO pulses, until the matching `Stop`. For a surveying flight
+//! where a single arm pulse should kick off a whole mapping run rather than
+//! the operator pulsing the trigger for every photo.
+//!
+//! Distance mode reads [`crate::mavlink::MavlinkFeedback::latest_position`],
+//! so it only works with MAVLink connected; interval mode works regardless.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::mavlink::{MavlinkFeedback, Position};
+
+/// How often distance mode samples PX4's position to check how far the
+/// vehicle has moved since the last capture. Wall-clock interval mode
+/// instead ticks directly on `interval_secs`.
+const DISTANCE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Mean Earth radius, for [`haversine_distance_meters`]. Accurate enough for
+/// mapping-flight capture spacing; this isn't a navigation system.
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalometerConfig {
+    /// Off by default: most deployments still want every capture triggered
+    /// by an explicit pulse.
+    pub enabled: bool,
+    /// Capture every `interval_secs` seconds of wall-clock time. Mutually
+    /// exclusive with `distance_meters` - if both are set, interval mode
+    /// wins.
+    pub interval_secs: Option<f64>,
+    /// Capture every time the vehicle has moved at least `distance_meters`
+    /// since the last capture, per MAVLink `GLOBAL_POSITION_INT`. Ignored if
+    /// `interval_secs` is also set.
+    pub distance_meters: Option<f64>,
+}
+
+impl Default for IntervalometerConfig {
+    fn default() -> Self {
+        Self { enabled: false, interval_secs: None, distance_meters: None }
+    }
+}
+
+/// Spawns the background task driving one armed session's captures, and
+/// returns a handle the caller stores and sets on the matching `Stop` to
+/// tear it down. A fresh task (and fresh distance baseline) is expected for
+/// every `Start`, so a survey's spacing always counts from the moment
+/// recording actually began, not from process startup.
+pub fn spawn(
+    config: IntervalometerConfig,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+    on_capture: impl Fn() + Send + 'static,
+) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let task_stop = Arc::clone(&stop);
+
+    tokio::spawn(async move {
+        if let Some(interval_secs) = config.interval_secs {
+            let mut ticker = tokio::time::interval(Duration::from_secs_f64(interval_secs.max(0.001)));
+            // The trigger edge that armed the session already started
+            // recording; the first tick just marks time zero.
+            ticker.tick().await;
+            while !task_stop.load(Ordering::Relaxed) {
+                ticker.tick().await;
+                if task_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                on_capture();
+            }
+        } else if let Some(distance_meters) = config.distance_meters {
+            let mut last_position: Option<Position> = None;
+            let mut ticker = tokio::time::interval(DISTANCE_POLL_INTERVAL);
+            while !task_stop.load(Ordering::Relaxed) {
+                ticker.tick().await;
+                if task_stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let Some(mavlink_feedback) = mavlink_feedback.as_ref() else {
+                    warn!("intervalometer distance mode is configured but mavlink isn't connected");
+                    continue;
+                };
+
+                let position = mavlink_feedback.latest_position();
+                let moved_far_enough = match last_position {
+                    Some(previous) => haversine_distance_meters(previous, position) >= distance_meters,
+                    None => true,
+                };
+                if moved_far_enough {
+                    last_position = Some(position);
+                    on_capture();
+                }
+            }
+        } else {
+            warn!("intervalometer is enabled but neither interval-secs nor distance-meters is set");
+        }
+    });
+
+    stop
+}
+
+/// Great-circle distance between two [`Position`]s, in meters, from their
+/// `lat`/`lon` fields (degrees * 1e7, per `GLOBAL_POSITION_INT`).
+fn haversine_distance_meters(a: Position, b: Position) -> f64 {
+    let lat1 = (a.lat as f64 * 1e-7).to_radians();
+    let lat2 = (b.lat as f64 * 1e-7).to_radians();
+    let delta_lat = lat2 - lat1;
+    let delta_lon = ((b.lon - a.lon) as f64 * 1e-7).to_radians();
+
+    let sin_half_lat = (delta_lat / 2.0).sin();
+    let sin_half_lon = (delta_lon / 2.0).sin();
+    let h = sin_half_lat * sin_half_lat + lat1.cos() * lat2.cos() * sin_half_lon * sin_half_lon;
+
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
@@ -0,0 +1,97 @@
+//! Newline-delimited JSON log output for [`crate::main`]'s non-journald
+//! fallback path (see [`crate::config::LogFormat`]), for environments like
+//! our containerized HIL rig where nothing is watching a text-mode terminal
+//! but log lines - including the structured fields attached in
+//! [`crate::trigger_log`] - still need to be machine-parsed.
+//!
+//! Hand-rolled rather than pulling in a JSON crate purely for this, the same
+//! way [`crate::trigger_log`]'s CSV sidecar hand-rolls its own escaping.
+
+use std::io::Write;
+
+use chrono::Utc;
+use log::{Log, Metadata, Record};
+
+pub struct JsonLogger;
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut line = String::from("{");
+        push_field(&mut line, "timestamp", &Utc::now().to_rfc3339());
+        line.push(',');
+        push_field(&mut line, "level", record.level().as_str());
+        line.push(',');
+        push_field(&mut line, "target", record.target());
+        line.push(',');
+        push_field(&mut line, "message", &record.args().to_string());
+
+        struct FieldVisitor<'a>(&'a mut String);
+        impl<'kvs, 'a> log::kv::VisitSource<'kvs> for FieldVisitor<'a> {
+            fn visit_pair(
+                &mut self,
+                key: log::kv::Key<'kvs>,
+                value: log::kv::Value<'kvs>,
+            ) -> Result<(), log::kv::Error> {
+                self.0.push(',');
+                push_field(self.0, key.as_str(), &value.to_string());
+                Ok(())
+            }
+        }
+        let _ = record.key_values().visit(&mut FieldVisitor(&mut line));
+
+        line.push('}');
+        let _ = writeln!(std::io::stderr(), "{line}");
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+fn push_field(line: &mut String, key: &str, value: &str) {
+    line.push('"');
+    line.push_str(&json_escape(key));
+    line.push_str("\":\"");
+    line.push_str(&json_escape(value));
+    line.push('"');
+}
+
+/// Escapes `value` for a JSON string, per RFC 8259.
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if (control as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", control as u32)),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_control_characters_and_quotes() {
+        assert_eq!(json_escape("hello \"world\"\n\t"), "hello \\\"world\\\"\\n\\t");
+    }
+
+    #[test]
+    fn leaves_plain_text_untouched() {
+        assert_eq!(json_escape("camera-1"), "camera-1");
+    }
+}
@@ -0,0 +1,59 @@
+//! Library half of the trigger service: everything except the binary's
+//! `main`/subcommand handlers, so [`Session`] and [`TriggerSource`] can be
+//! unit tested with mocks and reused from our other onboard services
+//! without linking against a binary crate.
+
+pub mod aux_lines;
+pub mod buzzer;
+pub mod camera_self_test;
+pub mod capture_feedback;
+pub mod clock;
+pub mod config;
+pub mod control_api;
+pub mod dbus_api;
+pub mod device_wait;
+pub mod discovery;
+pub mod disk_space;
+pub mod dronecan;
+pub mod durability;
+pub mod exit_code;
+pub mod geotag;
+pub mod gpio_discovery;
+pub mod grpc_api;
+pub mod intervalometer;
+pub mod json_logger;
+pub mod manifest;
+pub mod mavlink;
+pub mod metrics;
+pub mod mqtt;
+pub mod naming;
+pub mod network_trigger;
+pub mod offload;
+pub mod pps;
+pub mod privsep;
+pub mod recorder;
+pub mod retention;
+pub mod ros2_bridge;
+pub mod session;
+pub mod session_log;
+pub mod shutdown_inhibitor;
+pub mod single_instance;
+pub mod state_journal;
+pub mod status;
+pub mod status_led;
+pub mod storage_health;
+pub mod subtitle_log;
+pub mod summary;
+pub mod supervisor;
+pub mod thermal;
+pub mod time_sync_check;
+pub mod trigger;
+pub mod trigger_generator;
+pub mod trigger_log;
+pub mod trigger_source;
+pub mod usb_discovery;
+pub mod watchdog;
+
+pub use recorder::Recorder;
+pub use session::Session;
+pub use trigger_source::{Edge, GpioTriggerSource, SimulatedTriggerSource, TriggerSource};
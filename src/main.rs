@@ -1,72 +1,1541 @@
-use std::time::{Instant, SystemTime};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use gpio_cdev::{Chip, EventRequestFlags, LineEvent, LineRequestFlags};
-use log::{debug, error, info, trace, warn};
+use clap::Parser;
+use gpio_cdev::Chip;
+use gpiocdev::tokio::AsyncRequest;
+use log::{error, info, warn};
 use simplelog::TermLogger;
 use systemd_journal_logger::JournalLog;
+use tokio::io::AsyncReadExt;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 
-const GPIO_PIN: u32 = 18;
+use px4_camera_trigger::aux_lines::{AuxLines, LineAction};
+use px4_camera_trigger::buzzer;
+use px4_camera_trigger::capture_feedback::CaptureFeedback;
+use px4_camera_trigger::clock::RealtimeClock;
+use px4_camera_trigger::config::{Cli, Command, Config, LogFormat};
+use px4_camera_trigger::control_api::{self, ControlCommand};
+use px4_camera_trigger::dbus_api;
+use px4_camera_trigger::device_wait;
+use px4_camera_trigger::discovery;
+use px4_camera_trigger::dronecan;
+use px4_camera_trigger::exit_code::ExitReason;
+use px4_camera_trigger::gpio_discovery;
+use px4_camera_trigger::grpc_api;
+use px4_camera_trigger::json_logger::JsonLogger;
+use px4_camera_trigger::manifest::Manifest;
+use px4_camera_trigger::mavlink::{MavlinkFeedback, VideoStreamInfo};
+use px4_camera_trigger::metrics::{self, Metrics};
+use px4_camera_trigger::mqtt;
+use px4_camera_trigger::network_trigger;
+use px4_camera_trigger::pps;
+use px4_camera_trigger::privsep;
+use px4_camera_trigger::recorder::{self, CaptureSource, RecorderConfig, RecordingBackend};
+use px4_camera_trigger::retention;
+use px4_camera_trigger::ros2_bridge;
+use px4_camera_trigger::session_log::{self, SessionLog};
+use px4_camera_trigger::shutdown_inhibitor;
+use px4_camera_trigger::single_instance;
+use px4_camera_trigger::state_journal::StateJournal;
+use px4_camera_trigger::status;
+use px4_camera_trigger::status_led;
+use px4_camera_trigger::storage_health;
+use px4_camera_trigger::supervisor::LineSupervisor;
+use px4_camera_trigger::thermal;
+use px4_camera_trigger::time_sync_check;
+use px4_camera_trigger::trigger_generator;
+use px4_camera_trigger::trigger_log::TriggerLog;
+use px4_camera_trigger::trigger_source::{
+    Edge, FusedTriggerSource, GpioTriggerSource, Next, ReplayTriggerSource, SimulatedTriggerSource, TriggerSource,
+    DRAIN_BATCH_CAPACITY,
+};
+use px4_camera_trigger::watchdog::Watchdog;
+use px4_camera_trigger::{Recorder, Session};
 
-fn main() {
+/// How long the event loop can go without observing a trigger edge before
+/// it's treated as stalled and the systemd watchdog stops being petted.
+/// This is well above any expected quiescent period between trigger pulses.
+const EVENT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Consecutive `Next::Error`s from the primary event stream before it's
+/// treated as a dead fd rather than a transient read error.
+const MAX_CONSECUTIVE_EVENT_ERRORS: u32 = 5;
+
+/// Bounded retries [`reacquire_event_stream`] makes before giving up -
+/// unlike [`px4_camera_trigger::supervisor::LineSupervisor`], which retries
+/// forever waiting for an external ownership change, a dead fd needs an
+/// operator/systemd to notice, not an unbounded spin loop.
+const EVENT_RECOVERY_ATTEMPTS: u32 = 5;
+const EVENT_RECOVERY_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const EVENT_RECOVERY_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Distinct from the generic `exit(1)` used for startup failures, so a
+/// `systemd` `Restart=`/operator watching exit codes can tell "trigger line
+/// recovery exhausted its retries" apart from a config or startup error.
+const EVENT_RECOVERY_EXIT_CODE: i32 = 3;
+
+#[tokio::main]
+async fn main() {
     let system_start = Instant::now();
 
-    if systemd_journal_logger::connected_to_journal() {
+    // Parsed ahead of everything else, purely so `--log-format` can pick the
+    // non-journald fallback's output format below; `Config::load` (which
+    // needs a working logger for its own diagnostics) still resolves the
+    // rest of `cli` afterwards.
+    let cli = Cli::parse();
+
+    // Built rather than installed directly, so `SessionLog` below can wrap
+    // it and stamp every record with the flight session id once one is
+    // resolved.
+    let inner_logger: Box<dyn log::Log> = if systemd_journal_logger::connected_to_journal() {
         // If the output streams of this process are directly connected to the
         // systemd journal log directly to the journal to preserve structured
         // log entries (e.g. proper multiline messages, metadata fields, etc.)
-        JournalLog::empty()
-            .with_syslog_identifier(
-                systemd_journal_logger::current_exe_identifier().unwrap_or_default(),
-            )
-            .install()
-            .unwrap();
-    } else {
-        // Otherwise fall back to logging to standard error.
-        TermLogger::init(
-            log::LevelFilter::Trace,
-            simplelog::ConfigBuilder::new().build(),
-            simplelog::TerminalMode::Mixed,
-            simplelog::ColorChoice::Auto,
+        Box::new(
+            JournalLog::empty()
+                .with_syslog_identifier(
+                    systemd_journal_logger::current_exe_identifier().unwrap_or_default(),
+                )
+                .build()
+                .unwrap(),
         )
-        .unwrap();
+    } else {
+        match cli.log_format.unwrap_or_default() {
+            // Newline-delimited JSON, for environments (e.g. our
+            // containerized HIL rig) where nothing is watching a terminal
+            // but log lines still need to be machine-parsed.
+            LogFormat::Json => Box::new(JsonLogger),
+            // Otherwise fall back to colored text on standard error.
+            LogFormat::Text => TermLogger::new(
+                log::LevelFilter::Trace,
+                simplelog::ConfigBuilder::new().build(),
+                simplelog::TerminalMode::Mixed,
+                simplelog::ColorChoice::Auto,
+            ),
+        }
+    };
+    SessionLog::install(inner_logger);
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let command = cli.command.unwrap_or_default();
+    let config = Config::load(cli.clone());
+    session_log::set_session_id(&config.flight_session);
+
+    match command {
+        Command::Run => run(config, cli).await,
+        Command::CheckConfig => check_config(config),
+        Command::ListLines => list_lines(&config),
+        Command::TestCapture => test_capture(config),
+        Command::Status => status_query(&config).await,
+        Command::Probe => probe(),
+        Command::Generate => generate(&config).await,
+        Command::Bench => bench(config),
+        Command::Recover { file } => recover_recording(&config, &file),
+        Command::Decrypt { file, identity_file } => decrypt_recording(&file, &identity_file),
     }
+}
 
-    log::set_max_level(log::LevelFilter::Trace);
+/// `check-config`: resolve configuration, print it, and validate it against
+/// the actual system - the GPIO chip/line, each camera's source device, and
+/// each camera's output directory - without requesting the trigger line or
+/// starting the recording pipeline. Useful for catching a bad `--config`
+/// file/env/flag combination (or a since-unplugged camera, or a since-full
+/// SD card) before deploying it, rather than finding out at the next
+/// `Restart=` cycle.
+///
+/// Every problem found is collected and printed rather than bailing out on
+/// the first one, so a single run surfaces everything that needs fixing.
+fn check_config(config: Config) {
+    println!("{config:#?}");
 
-    let mut chip = Chip::new("/dev/gpiochip0").expect("gpio chip should be accessible");
-    let input = chip.get_line(GPIO_PIN).expect("gpio pin should exist");
+    let mut problems = Vec::new();
 
-    let event_iterator = input
-        .events(
-            LineRequestFlags::INPUT,
-            EventRequestFlags::FALLING_EDGE,
-            "px4-camera-trigger-gpio",
-        )
-        .expect("input events should be subscribable");
+    if let Err(problem) = check_gpio_line(&config) {
+        problems.push(problem);
+    }
+
+    for camera in config.cameras() {
+        if let Err(problem) = check_source_device(&camera.name, &camera.recorder) {
+            problems.push(problem);
+        }
+        if let Err(problem) = check_output_dir(&camera.recorder.output_dir) {
+            problems.push(format!("{}: {problem}", camera.name));
+        }
+        if let Some(secondary_output_dir) = &camera.recorder.secondary_output_dir {
+            if let Err(problem) = check_output_dir(secondary_output_dir) {
+                problems.push(format!("{}: {problem}", camera.name));
+            }
+        }
+        if let Some(encryption_recipient) = &camera.recorder.encryption_recipient {
+            if let Err(problem) = recorder::validate_encryption_recipient(encryption_recipient) {
+                problems.push(format!("{}: {problem}", camera.name));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        println!("ok: no problems found");
+        return;
+    }
+
+    println!("problems found:");
+    for problem in &problems {
+        println!("  - {problem}");
+    }
+    std::process::exit(1);
+}
+
+/// Checks that `config.gpiochip`/`config.line_offset` (or their
+/// `--gpiochip-label`/`--line-name` equivalents) resolve to an accessible
+/// line that isn't already claimed by another consumer. Doesn't use
+/// [`resolve_gpio_target`], since that exits the process immediately on
+/// failure instead of returning a problem to collect alongside the others.
+fn check_gpio_line(config: &Config) -> Result<(), String> {
+    let gpiochip = match &config.gpiochip_label {
+        Some(label) => gpio_discovery::find_chip_by_label(label)?,
+        None => config.gpiochip.clone(),
+    };
+
+    let mut chip = Chip::new(&gpiochip)
+        .map_err(|error| format!("gpio chip {} is not accessible: {error}", gpiochip.display()))?;
+
+    let line_offset = match &config.line_name {
+        Some(name) => gpio_discovery::find_line_by_name(&mut chip, name)?,
+        None => config.line_offset,
+    };
+
+    let info = chip
+        .get_line(line_offset)
+        .and_then(|line| line.info())
+        .map_err(|error| format!("line {line_offset} on {} is not accessible: {error}", gpiochip.display()))?;
+    if info.is_used() {
+        return Err(format!(
+            "line {line_offset} on {} is already claimed by consumer {:?}",
+            gpiochip.display(),
+            info.consumer()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that `recorder.source_device` refers to a camera that's actually
+/// present, as far as this process can tell without taking exclusive
+/// ownership of it. Only meaningful for backends where `source_device` names
+/// a filesystem device node ([`RecordingBackend::V4l2Direct`], and
+/// [`RecordingBackend::Gstreamer`] with [`CaptureSource::V4l2`]) or a
+/// libcamera camera id ([`RecordingBackend::LibcameraNative`],
+/// [`RecordingBackend::LibcameraVid`], and [`RecordingBackend::Gstreamer`]
+/// with [`CaptureSource::Libcamera`]); the gphoto2/PTP-IP/GigE Vision
+/// backends' `source_device` is a port/host/device-id string with no
+/// equivalent "is it there" probe that doesn't also open the camera, so
+/// those are left unchecked here.
+fn check_source_device(camera: &str, recorder: &RecorderConfig) -> Result<(), String> {
+    let uses_libcamera = match recorder.backend {
+        RecordingBackend::LibcameraNative | RecordingBackend::LibcameraVid => true,
+        RecordingBackend::Gstreamer => recorder.source == CaptureSource::Libcamera,
+        _ => false,
+    };
+    let uses_v4l2 = match recorder.backend {
+        RecordingBackend::V4l2Direct => true,
+        RecordingBackend::Gstreamer => recorder.source == CaptureSource::V4l2,
+        _ => false,
+    };
+
+    if uses_libcamera {
+        let id = recorder.source_device.to_string_lossy().into_owned();
+        let known = discovery::list_libcamera_cameras().iter().any(|detected| detected.id == id);
+        if !known {
+            return Err(format!("camera {camera}: no libcamera camera with id {id:?} is currently detected"));
+        }
+    } else if uses_v4l2 && !recorder.source_device.exists() {
+        return Err(format!("camera {camera}: source device {} does not exist", recorder.source_device.display()));
+    }
+
+    Ok(())
+}
+
+/// Checks that `output_dir` exists (creating it if not) and is actually
+/// writable, by creating and removing a throwaway probe file - catching a
+/// read-only remount or a permissions mistake before a flight, rather than
+/// at the first trigger pulse.
+fn check_output_dir(output_dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(output_dir)
+        .map_err(|error| format!("output dir {} could not be created: {error}", output_dir.display()))?;
+
+    let probe_path = output_dir.join(".cameras-check-write-probe");
+    std::fs::write(&probe_path, b"")
+        .map_err(|error| format!("output dir {} is not writable: {error}", output_dir.display()))?;
+    let _ = std::fs::remove_file(&probe_path);
+
+    Ok(())
+}
+
+/// `list-lines`: enumerate every line on `--gpiochip` (or the chip found by
+/// `--gpiochip-label`), to help pick `--line-offset`/`--line-name` on
+/// unfamiliar carrier boards without a datasheet handy.
+fn list_lines(config: &Config) {
+    let gpiochip = resolve_gpiochip(config);
+
+    let mut chip = match Chip::new(&gpiochip) {
+        Ok(chip) => chip,
+        Err(error) => {
+            error!("gpio chip {} is not accessible: {error}", gpiochip.display());
+            std::process::exit(1);
+        }
+    };
+
+    for offset in 0..chip.num_lines() {
+        match chip.get_line(offset).and_then(|line| line.info()) {
+            Ok(info) => {
+                let consumer = (!info.consumer().is_empty())
+                    .then(|| info.consumer().to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{offset:>3}  {:<9}  used={:<5}  consumer={consumer}",
+                    format!("{:?}", info.direction()),
+                    info.is_used(),
+                );
+            }
+            Err(error) => println!("{offset:>3}  <error: {error}>"),
+        }
+    }
+}
+
+/// `test-capture`: request the trigger line's recording pipeline directly
+/// (bypassing the event loop) and run one start/stop cycle, so a deployment
+/// can be sanity-checked without physically pulsing the trigger.
+fn test_capture(config: Config) {
+    let recorder = match Recorder::new(
+        &config.recorder,
+        "primary",
+        &config.flight_session,
+        || {},
+        |_| {},
+        |_| {},
+        || {},
+        |_| {},
+        |reason| warn!("degraded encoding: {reason}"),
+        Arc::new(None),
+        RealtimeClock::spawn(),
+    ) {
+        Ok(recorder) => recorder,
+        Err(error) => {
+            error!("failed to initialize recording pipeline: {error}");
+            std::process::exit(1);
+        }
+    };
+
+    info!("starting a {TEST_CAPTURE_DURATION:?} test recording");
+    recorder.start(0);
+    std::thread::sleep(TEST_CAPTURE_DURATION);
+    recorder.stop();
+    info!(
+        "test recording finished; check {} for output",
+        config.recorder.output_dir.display()
+    );
+}
+
+/// How long `test-capture` records for.
+const TEST_CAPTURE_DURATION: Duration = Duration::from_secs(3);
+
+/// `status`: connect to a running instance's Unix status socket, print its
+/// JSON response, and exit - a one-shot query, not a persistent connection,
+/// matching how [`status::spawn_server`] answers each connection.
+async fn status_query(config: &Config) {
+    let mut socket = match tokio::net::UnixStream::connect(&config.status.socket_path).await {
+        Ok(socket) => socket,
+        Err(error) => {
+            error!("failed to connect to status socket {}: {error}", config.status.socket_path.display());
+            std::process::exit(1);
+        }
+    };
+
+    let mut response = String::new();
+    if let Err(error) = socket.read_to_string(&mut response).await {
+        error!("failed to read status socket response: {error}");
+        std::process::exit(1);
+    }
+
+    println!("{response}");
+}
+
+/// `probe`: enumerate every gpiochip's lines and every detected V4L2/UVC and
+/// libcamera camera, so field setup on a new board doesn't need separate
+/// `gpioinfo`/`v4l2-ctl` invocations to find a chip label, line offset, or a
+/// camera's supported capture resolutions. See [`discovery`].
+fn probe() {
+    println!("gpio chips:");
+    for chip in discovery::list_gpiochips() {
+        println!("  {} ({})", chip.path.display(), chip.label);
+        for line in chip.lines {
+            let consumer = line.consumer.unwrap_or_else(|| "-".to_string());
+            println!(
+                "    {:>3}  {:<20}  {:<9}  used={:<5}  consumer={consumer}",
+                line.offset, line.name, line.direction, line.used,
+            );
+        }
+    }
+
+    println!("v4l2 cameras:");
+    for camera in discovery::list_v4l2_cameras() {
+        println!("  {} ({})", camera.path.display(), camera.name);
+        for mode in camera.modes {
+            println!("    {}  {}x{}", mode.fourcc, mode.width, mode.height);
+        }
+    }
+
+    println!("libcamera cameras:");
+    for camera in discovery::list_libcamera_cameras() {
+        println!("  {}", camera.id);
+    }
+}
+
+/// `generate`: request the trigger line as an output and pulse it per
+/// `config.generate`, for bench-testing a third-party camera's trigger
+/// input or PX4's `CAMERA_TRIGGER` feedback wiring without a flight
+/// controller in the loop. See [`trigger_generator`].
+async fn generate(config: &Config) {
+    let (gpiochip, line_offset) = resolve_gpio_target(config);
+
+    let count_display = config.generate.count.map(|count| count.to_string()).unwrap_or_else(|| "unlimited".to_string());
+    info!(
+        "generating pulses on {} line {line_offset} every {:?} ({:?} active, {count_display} total)",
+        gpiochip.display(),
+        config.generate.interval,
+        config.generate.pulse_width,
+    );
+
+    if let Err(error) =
+        trigger_generator::run(&gpiochip, line_offset, &config.consumer_label, &config.generate).await
+    {
+        error!("{error}");
+        std::process::exit(1);
+    }
+}
 
-    // TODO: start the recording
+/// `bench`: run the configured recording pipeline for
+/// `config.bench_duration` without requesting the trigger line, and report
+/// the achievable framerate, encode latency, CPU usage and write throughput,
+/// so a new SD card or camera mode can be validated on the bench before it's
+/// trusted on a flight.
+///
+/// Framerate and encode latency come from the same `on_frame`/`on_first_frame`
+/// hooks [`crate::metrics::CameraMetrics`] uses (see [`Recorder::new`]); only
+/// the v4l2-direct, libcamera-native and GigE Vision backends report them; on
+/// the others the framerate/encode-latency lines read "unavailable on this
+/// backend". Write throughput is the growth of `config.recorder.output_dir`
+/// over the run, so it reflects encoded output size, not raw sensor
+/// bandwidth.
+fn bench(config: Config) {
+    let frame_count = Arc::new(AtomicU64::new(0));
+    let first_frame_latency_ms = Arc::new(AtomicU64::new(u64::MAX));
+    let dropped_frames = Arc::new(AtomicU64::new(0));
+    let fatal_error = Arc::new(AtomicBool::new(false));
+
+    let bench_start_ns = monotonic_now_ns();
+    let bytes_before = retention::directory_size(&config.recorder.output_dir).unwrap_or(0);
+    // SAFETY: a zeroed `rusage` is a valid initial value for `getrusage` to overwrite.
+    let mut cpu_before: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `cpu_before` is a valid, correctly-sized out-parameter for `getrusage`.
+    unsafe {
+        libc::getrusage(libc::RUSAGE_SELF, &mut cpu_before);
+    }
+
+    let counted_frames = Arc::clone(&frame_count);
+    let latency_sample = Arc::clone(&first_frame_latency_ms);
+    let dropped = Arc::clone(&dropped_frames);
+    let fatal = Arc::clone(&fatal_error);
+
+    let recorder = match Recorder::new(
+        &config.recorder,
+        "primary",
+        &config.flight_session,
+        move || fatal.store(true, Ordering::Relaxed),
+        move |newly_dropped| {
+            dropped.fetch_add(newly_dropped, Ordering::Relaxed);
+        },
+        move |frame_timestamp_ns| {
+            latency_sample.store(frame_timestamp_ns.saturating_sub(bench_start_ns) / 1_000_000, Ordering::Relaxed);
+        },
+        move || {
+            counted_frames.fetch_add(1, Ordering::Relaxed);
+        },
+        |_| {},
+        |reason| warn!("degraded encoding: {reason}"),
+        Arc::new(None),
+        RealtimeClock::spawn(),
+    ) {
+        Ok(recorder) => recorder,
+        Err(error) => {
+            error!("failed to initialize recording pipeline: {error}");
+            std::process::exit(1);
+        }
+    };
+
+    info!("benchmarking the recording pipeline for {:?}", config.bench_duration);
+    recorder.start(0);
+    std::thread::sleep(config.bench_duration);
+    recorder.stop();
+
+    let elapsed_secs = config.bench_duration.as_secs_f64();
+    // SAFETY: a zeroed `rusage` is a valid initial value for `getrusage` to overwrite.
+    let mut cpu_after: libc::rusage = unsafe { std::mem::zeroed() };
+    // SAFETY: `cpu_after` is a valid, correctly-sized out-parameter for `getrusage`.
+    unsafe {
+        libc::getrusage(libc::RUSAGE_SELF, &mut cpu_after);
+    }
+    let bytes_after = retention::directory_size(&config.recorder.output_dir).unwrap_or(0);
+
+    let frames = frame_count.load(Ordering::Relaxed);
+    let framerate = frames as f64 / elapsed_secs;
+    let latency_ms = first_frame_latency_ms.load(Ordering::Relaxed);
+    let cpu_percent = (cpu_seconds(&cpu_after) - cpu_seconds(&cpu_before)) / elapsed_secs * 100.0;
+    let throughput_bytes_per_sec = bytes_after.saturating_sub(bytes_before) as f64 / elapsed_secs;
+
+    if fatal_error.load(Ordering::Relaxed) {
+        warn!("recording pipeline reported a fatal error during the benchmark; results below may be incomplete");
+    }
+
+    println!("duration:            {elapsed_secs:.1}s");
+    println!("frames written:      {frames}");
+    println!(
+        "achievable framerate: {}",
+        if frames > 0 { format!("{framerate:.2} fps") } else { "unavailable on this backend".to_string() }
+    );
+    println!(
+        "encode latency:       {}",
+        if latency_ms == u64::MAX { "unavailable on this backend".to_string() } else { format!("{latency_ms} ms") }
+    );
+    println!("dropped frames:       {}", dropped_frames.load(Ordering::Relaxed));
+    println!("cpu usage:            {cpu_percent:.1}%");
+    println!("write throughput:     {:.1} KB/s", throughput_bytes_per_sec / 1024.0);
+}
+
+/// `recover`: rebuild a playable file from `file`, an mp4 recording
+/// truncated mid-write, using `config.recorder`'s codec/encoder/resolution
+/// as the "known encoder parameters" to reconstruct the index it lost. See
+/// [`px4_camera_trigger::recorder::recover_recording`].
+fn recover_recording(config: &Config, file: &Path) {
+    match recorder::recover_recording(file, &config.recorder) {
+        Ok(output) => info!("recovered {} -> {}", file.display(), output.display()),
+        Err(error) => {
+            error!("failed to recover {}: {error}", file.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `decrypt`: recover the plaintext of `file`, a recording written under
+/// `--encryption-recipient`, using the age identity in `identity_file`. See
+/// [`px4_camera_trigger::recorder::decrypt_recording`].
+fn decrypt_recording(file: &Path, identity_file: &Path) {
+    match recorder::decrypt_recording(file, identity_file) {
+        Ok(output) => info!("decrypted {} -> {}", file.display(), output.display()),
+        Err(error) => {
+            error!("failed to decrypt {}: {error}", file.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The same `CLOCK_MONOTONIC` domain [`Recorder`]'s `on_first_frame` reports
+/// timestamps in; mirrors [`px4_camera_trigger::clock`]'s own reader, which
+/// is `pub(crate)` there and so isn't reachable from this binary crate.
+fn monotonic_now_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: `ts` is a valid, correctly-sized out-parameter for `clock_gettime`.
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+    }
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+/// User+system CPU time consumed so far, in fractional seconds.
+fn cpu_seconds(usage: &libc::rusage) -> f64 {
+    let user = usage.ru_utime.tv_sec as f64 + usage.ru_utime.tv_usec as f64 / 1_000_000.0;
+    let sys = usage.ru_stime.tv_sec as f64 + usage.ru_stime.tv_usec as f64 / 1_000_000.0;
+    user + sys
+}
+
+/// `run`: the trigger service's main loop. This is what the systemd unit
+/// runs. It only decides *when* something happened - a trigger edge, a
+/// manual command, a signal - and hands it to [`Session`]; the actual
+/// per-camera fan-out lives there so it's reusable outside this binary.
+///
+/// `cli` is kept around (rather than only threading `config` through) so a
+/// SIGHUP can re-resolve configuration from the same file/env/flag sources
+/// used at startup; see [`reload_config`].
+async fn run(mut config: Config, cli: Cli) {
+    // Held for the rest of this function's lifetime; a second instance
+    // (systemd restarting on top of a still-running one, or an operator's
+    // manual run alongside the unit) fails here instead of racing this one
+    // for the GPIO line and camera devices.
+    let _instance_lock = match single_instance::acquire(&config.pidfile) {
+        Ok(lock) => lock,
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(single_instance::ALREADY_RUNNING_EXIT_CODE);
+        }
+    };
+
+    // Gives udev/the mount unit a chance to catch up when this service's
+    // systemd unit races them at boot, instead of failing immediately on a
+    // camera or SSD that's about to show up. Skips a camera whose
+    // `source_device` is resolved dynamically by USB serial/port (see
+    // `crate::usb_discovery`), since that lookup already walks sysfs on its
+    // own each time `Session::new` runs.
+    for camera in config.cameras() {
+        if camera.recorder.usb_serial.is_some() || camera.recorder.usb_port_path.is_some() {
+            continue;
+        }
+        if let Err(error) =
+            device_wait::wait_for_device_node(&camera.recorder.source_device, config.device_ready_timeout).await
+        {
+            ExitReason::Camera.exit(&format!("camera {}: {error}", camera.name));
+        }
+    }
+    if let Some(mount_point) = &config.storage_mount_point {
+        if let Err(error) = device_wait::wait_for_mount(mount_point, config.device_ready_timeout).await {
+            ExitReason::Storage.exit(&error);
+        }
+        if let Err(error) = device_wait::check_free_space(mount_point, config.storage_min_free_bytes) {
+            ExitReason::Storage.exit(&error);
+        }
+    }
+
+    // `config.gpiochip`/`config.line_offset` are only the fallback; if
+    // `--gpiochip-label`/`--line-name` are set, a device tree lookup takes
+    // over. Resolved once up front, since supervisor/aux-line hookup below
+    // all need the same physical chip/line this run settled on.
+    let (gpiochip, line_offset) = resolve_gpio_target(&config);
+
+    // Needed up front (rather than where it's otherwise first used, below)
+    // if the trigger line is requested with `event_clock_realtime`, so its
+    // edges can be disciplined back into the monotonic domain as soon as
+    // they're read. See [`GpioTriggerSource`].
+    let clock = RealtimeClock::spawn();
+
+    // Set in the real-GPIO arm below and reused by `LineSupervisor`/
+    // `reacquire_event_stream` recovery, so a driver that rejected
+    // `event_clock_realtime` at startup isn't asked for it again on every
+    // recovery (see [`request_trigger_line`]). Left `false` for
+    // `--simulate`/`--replay-log`, which never touch the trigger line.
+    let mut trigger_uses_realtime_clock = false;
+
+    if config.mavlink.trigger_source && !config.mavlink.enabled {
+        error!("mavlink-trigger-source is enabled but mavlink-enabled isn't set");
+        std::process::exit(1);
+    }
+
+    if config.mavlink.trigger_fusion {
+        if !config.mavlink.enabled {
+            error!("mavlink-trigger-fusion is enabled but mavlink-enabled isn't set");
+            std::process::exit(1);
+        }
+        if config.mavlink.trigger_source {
+            error!("mavlink-trigger-fusion and mavlink-trigger-source are mutually exclusive");
+            std::process::exit(1);
+        }
+        if config.simulate || config.replay_log.is_some() {
+            error!("mavlink-trigger-fusion needs the real trigger line and can't be combined with --simulate/--replay-log");
+            std::process::exit(1);
+        }
+    }
+
+    // Fed by the control API and MAVLink command handling below and drained
+    // by the event loop, alongside real trigger edges, so ground crew
+    // requests go through the same per-camera fan-out and trigger event log
+    // as a physical trigger pulse.
+    let (control_tx, mut control_rx) = mpsc::unbounded_channel::<ControlCommand>();
+
+    // Shared (rather than owned by the session alone) since the per-camera
+    // disk-space monitors also want to raise a STATUSTEXT when they stop a
+    // recording. Connected before `trigger_source` is chosen below, since
+    // `--mavlink-trigger-source` needs a live connection to pull a
+    // [`px4_camera_trigger::trigger_source::MavlinkTriggerSource`] from;
+    // [`MavlinkFeedback::spawn_timesync`] (which starts decoding messages
+    // off it) is deferred until just after, per its own doc comment, so no
+    // message a `MavlinkTriggerSource` would want to see is missed.
+    let mavlink_feedback = Arc::new(if config.mavlink.enabled {
+        match MavlinkFeedback::connect(&config.mavlink, &config.recorder.output_dir) {
+            Ok(feedback) => Some(feedback),
+            Err(error) => {
+                error!("failed to connect to mavlink: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    });
+
+    let mut trigger_source: Box<dyn TriggerSource> = if config.mavlink.trigger_source {
+        info!(
+            "mavlink trigger mode: not touching {}, decoding CAMERA_TRIGGER/MAV_CMD_DO_DIGICAM_CONTROL over {} instead",
+            gpiochip.display(),
+            config.mavlink.address
+        );
+        let feedback = mavlink_feedback.as_ref().as_ref().expect("validated above");
+        Box::new(feedback.trigger_source())
+    } else if let Some(replay_log) = &config.replay_log {
+        info!("replay mode: not touching {}, replaying {} instead", gpiochip.display(), replay_log.display());
+        match ReplayTriggerSource::open(replay_log) {
+            Ok(source) => Box::new(source),
+            Err(error) => ExitReason::Config.exit(&format!("failed to open replay log: {error}")),
+        }
+    } else if config.simulate {
+        info!(
+            "simulate mode: not touching {}, synthesizing a start/stop toggle every {:?} instead",
+            gpiochip.display(),
+            config.simulate_interval
+        );
+        Box::new(SimulatedTriggerSource::new(config.simulate_interval))
+    } else {
+        let (request, got_realtime_clock) = open_event_stream(&gpiochip, line_offset, &config.consumer_label, &config);
+        trigger_uses_realtime_clock = got_realtime_clock;
+        let gpio = GpioTriggerSource::new(request, EVENT_STALL_TIMEOUT, got_realtime_clock.then(|| Arc::clone(&clock)));
+
+        if config.mavlink.trigger_fusion {
+            info!(
+                "mavlink trigger fusion: also decoding CAMERA_TRIGGER/MAV_CMD_DO_DIGICAM_CONTROL over {} as a \
+                 backup to {}, deduping edges within {:?} of each other",
+                config.mavlink.address,
+                gpiochip.display(),
+                config.mavlink.trigger_fusion_dedup_window
+            );
+        }
+        wrap_gpio_trigger_source(Box::new(gpio), &config, &mavlink_feedback)
+    };
+
+    if let Some(feedback) = mavlink_feedback.as_ref() {
+        feedback.spawn_timesync(control_tx.clone());
+    }
+
+    let capture_feedback = Arc::new(if config.capture_feedback.enabled {
+        match (&config.capture_feedback.gpiochip, config.capture_feedback.line_offset) {
+            (Some(gpiochip), Some(line_offset)) => match CaptureFeedback::open(gpiochip, line_offset) {
+                Ok(capture_feedback) => Some(capture_feedback),
+                Err(error) => {
+                    error!("failed to open capture feedback line: {error}");
+                    std::process::exit(1);
+                }
+            },
+            _ => {
+                error!(
+                    "capture feedback is enabled but capture-feedback-gpiochip/capture-feedback-line-offset \
+                     aren't both set"
+                );
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    });
+
+    if config.pps.enabled {
+        match config.pps.source() {
+            Ok(source) => pps::spawn(source, Arc::clone(&clock)),
+            Err(error) => {
+                error!("failed to start pps discipline: {error}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let trigger_log = Arc::new(
+        match TriggerLog::open(&config.recorder.output_dir, &config.flight_session, Arc::clone(&clock), config.trigger_log) {
+            Ok(trigger_log) => trigger_log,
+            Err(error) => ExitReason::Storage.exit(&format!("failed to open trigger event log: {error}")),
+        },
+    );
+
+    let manifest = Arc::new(match Manifest::open(&config.recorder.output_dir, &config.flight_session) {
+        Ok(manifest) => manifest,
+        Err(error) => ExitReason::Storage.exit(&format!("failed to open session manifest: {error}")),
+    });
+
+    let (state_journal, recovered_state) =
+        match StateJournal::open(&config.recorder.output_dir, &config.flight_session) {
+            Ok(opened) => opened,
+            Err(error) => ExitReason::Storage.exit(&format!("failed to open state journal: {error}")),
+        };
+    let state_journal = Arc::new(state_journal);
+    for orphaned in recovered_state.orphaned {
+        warn!(
+            "camera {} had a recording still open in the state journal at startup - the previous run likely crashed or was killed mid-recording",
+            orphaned.camera
+        );
+        manifest.record_orphaned_recording(&orphaned.camera, orphaned.file.as_deref(), orphaned.sequence);
+    }
+
+    time_sync_check::run(&config.time_sync_check, &mavlink_feedback, &manifest);
+
+    let metrics = Arc::new(Metrics::new());
+    if config.metrics.enabled {
+        if let Err(error) = metrics::spawn_server(Arc::clone(&metrics), &config.metrics.address).await {
+            error!("failed to start metrics endpoint: {error}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+        let video_streams = config
+            .cameras()
+            .iter()
+            .enumerate()
+            .map(|(index, camera)| VideoStreamInfo {
+                stream_id: index as u8 + 1,
+                enabled: camera.recorder.rtsp_preview_enabled,
+                bitrate_kbps: camera.recorder.rtsp_preview_bitrate_kbps,
+                width: camera.recorder.capture_width,
+                height: camera.recorder.capture_height,
+            })
+            .collect();
+        mavlink_feedback.spawn_status(Arc::clone(&metrics), video_streams);
+    }
+
+    if config.status_led.enabled {
+        match (&config.status_led.gpiochip, config.status_led.line_offset) {
+            (Some(gpiochip), Some(line_offset)) => {
+                status_led::spawn(gpiochip.clone(), line_offset, Arc::clone(&metrics))
+            }
+            _ => error!("status led is enabled but status-led-gpiochip/status-led-line-offset aren't both set"),
+        }
+    }
+
+    let buzzer = if config.buzzer.enabled {
+        match (&config.buzzer.gpiochip, config.buzzer.line_offset) {
+            (Some(gpiochip), Some(line_offset)) => match buzzer::spawn(gpiochip.clone(), line_offset) {
+                Ok(buzzer) => buzzer,
+                Err(error) => {
+                    error!("failed to start buzzer: {error}");
+                    buzzer::BuzzerHandle::disabled()
+                }
+            },
+            _ => {
+                error!("buzzer is enabled but buzzer-gpiochip/buzzer-line-offset aren't both set");
+                buzzer::BuzzerHandle::disabled()
+            }
+        }
+    } else {
+        buzzer::BuzzerHandle::disabled()
+    };
+
+    // Only ever sent on if `config.thermal.finalize_on_undervoltage` is
+    // set; see the `thermal::spawn_monitor` call below and its handling in
+    // the event loop.
+    let (undervoltage_tx, mut undervoltage_rx) = mpsc::unbounded_channel();
+    if config.thermal.enabled {
+        thermal::spawn_monitor(config.thermal.clone(), Arc::clone(&metrics), Arc::clone(&mavlink_feedback), undervoltage_tx);
+    }
+
+    if config.storage_health.enabled {
+        storage_health::spawn_monitor(
+            config.storage_health.clone(),
+            Arc::clone(&metrics),
+            Arc::clone(&manifest),
+            Arc::clone(&mavlink_feedback),
+        );
+    }
+
+    if config.control_api.enabled {
+        if let Err(error) =
+            control_api::spawn_server(&config.control_api.address, control_tx.clone(), Arc::clone(&metrics)).await
+        {
+            error!("failed to start control api: {error}");
+            std::process::exit(1);
+        }
+    }
+
+    if config.network_trigger.enabled {
+        if config.network_trigger.shared_secret.is_empty() {
+            error!("network-trigger-enabled is set but network-trigger-shared-secret isn't");
+            std::process::exit(1);
+        }
+        if let Err(error) = network_trigger::spawn_server(&config.network_trigger, control_tx.clone()).await {
+            error!("failed to start network trigger: {error}");
+            std::process::exit(1);
+        }
+    }
+
+    if config.status.enabled {
+        if let Err(error) = status::spawn_server(&config.status, Arc::clone(&metrics)).await {
+            error!("failed to start status socket: {error}");
+            std::process::exit(1);
+        }
+    }
+
+    let mqtt = if config.mqtt.enabled {
+        match mqtt::spawn(&config.mqtt) {
+            Ok(mqtt) => mqtt,
+            Err(error) => {
+                error!("failed to start mqtt publisher: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        mqtt::MqttPublisher::disabled()
+    };
+
+    if config.grpc.enabled {
+        if let Err(error) = grpc_api::spawn(&config.grpc, control_tx.clone(), Arc::clone(&metrics)).await {
+            error!("failed to start grpc control service: {error}");
+            std::process::exit(1);
+        }
+    }
+
+    let dbus = if config.dbus.enabled {
+        match dbus_api::spawn(&config.dbus, control_tx.clone(), Arc::clone(&metrics)).await {
+            Ok(dbus) => dbus,
+            Err(error) => {
+                error!("failed to start dbus service: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        dbus_api::DbusPublisher::disabled()
+    };
+
+    let ros = if config.ros.enabled {
+        match ros2_bridge::spawn(&config.ros, control_tx.clone()) {
+            Ok(ros) => ros,
+            Err(error) => {
+                error!("failed to start ros2 bridge: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        ros2_bridge::RosPublisher::disabled()
+    };
+
+    let dronecan = if config.dronecan.enabled {
+        match dronecan::spawn(&config.dronecan, control_tx.clone()) {
+            Ok(dronecan) => dronecan,
+            Err(error) => {
+                error!("failed to start dronecan bridge: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        dronecan::DroneCanPublisher::disabled()
+    };
+
+    let watchdog = Watchdog::new();
+
+    // Delivers logind's `PrepareForShutdown(active: true)` into the event
+    // loop below, so a shutdown that actually proceeds - whether we
+    // released our own inhibitor lock or logind's delay timeout ran out
+    // first - gets the same clean finalization a SIGTERM does.
+    let (shutdown_signal_tx, mut shutdown_signal_rx) = mpsc::unbounded_channel();
+    let shutdown_inhibitor = if config.shutdown_inhibitor.enabled {
+        match shutdown_inhibitor::spawn(&config.shutdown_inhibitor, shutdown_signal_tx).await {
+            Ok(shutdown_inhibitor) => shutdown_inhibitor,
+            Err(error) => {
+                error!("failed to start shutdown inhibitor: {error}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        shutdown_inhibitor::ShutdownInhibitor::disabled()
+    };
+
+    let mut session = match Session::new(
+        &config,
+        Arc::clone(&mavlink_feedback),
+        Arc::clone(&trigger_log),
+        Arc::clone(&manifest),
+        Arc::clone(&state_journal),
+        recovered_state.next_sequence,
+        Arc::clone(&metrics),
+        Arc::clone(&capture_feedback),
+        watchdog.clone(),
+        mqtt,
+        dbus,
+        ros,
+        dronecan,
+        buzzer,
+        shutdown_inhibitor,
+        Arc::clone(&clock),
+    ) {
+        Ok(session) => session,
+        Err(error) => ExitReason::Camera.exit(&error.to_string()),
+    };
+
+    // Every resource requiring root (the gpiochip line, camera devices, the
+    // output directory) is already open by this point; nothing below needs
+    // more than what an unprivileged `--privsep-user` retains through its
+    // already-open fds and group memberships.
+    privsep::drop_privileges(&config.privsep);
+
+    // Reap SIGTERM (the signal systemd sends on `systemctl stop`) alongside
+    // SIGINT so an in-progress recording is still given a chance to finalize.
+    let mut sigterm = signal(SignalKind::terminate()).expect("SIGTERM handler should install");
+
+    // Lets bench testers exercise the recording path with
+    // `systemctl kill -s SIGUSR1/SIGUSR2` when there's no GPIO line wired up,
+    // same as the control API but without needing it enabled.
+    let mut sigusr1 = signal(SignalKind::user_defined1()).expect("SIGUSR1 handler should install");
+    let mut sigusr2 = signal(SignalKind::user_defined2()).expect("SIGUSR2 handler should install");
+
+    // `systemctl kill -s SIGRTMIN+3` for a quick framing-check snapshot from
+    // the same bench-testing toolbox, without wiring up the control API.
+    let mut sigsnapshot =
+        signal(SignalKind::from_raw(libc::SIGRTMIN() + 3)).expect("SIGRTMIN+3 handler should install");
+
+    // Reap SIGHUP (what `systemctl reload` sends) to pick up config file
+    // changes without dropping the GPIO subscription. See [`reload_config`].
+    let mut sighup = signal(SignalKind::hangup()).expect("SIGHUP handler should install");
+
+    // The trigger source is set up and the recorders are initialized, so
+    // we're as ready as we'll ever be: tell systemd and start petting the
+    // watchdog, if it asked for one.
+    watchdog.notify_ready();
+    watchdog.spawn_keepalive();
+
+    if config.auto_start_recording {
+        // Goes through the same dispatch path as a manual start (SIGUSR1,
+        // the control API), so a mission that must never miss the beginning
+        // gets a recording before the first trigger edge even arrives; the
+        // trigger line then only needs to stop it and, on the next pulse,
+        // start the next one.
+        session.dispatch(ControlCommand::Start, "auto-start");
+    }
+
+    let (recovered_tx, mut recovered_rx) = mpsc::unbounded_channel();
+    if !config.simulate && config.replay_log.is_none() && !config.mavlink.trigger_source {
+        // Mirror whatever `open_event_stream` above actually got, not what
+        // `config` asked for: if the driver already rejected
+        // `event_clock_realtime` once, re-requesting it on every recovery
+        // would just log the same rejection warning forever.
+        let mut supervised_line_config = config.trigger_line_config();
+        if !trigger_uses_realtime_clock {
+            supervised_line_config.event_clock = None;
+        }
+        LineSupervisor::new(gpiochip.clone(), line_offset, config.consumer_label.clone(), supervised_line_config)
+            .spawn(recovered_tx);
+    }
+
+    // `--simulate`/`--replay-log` only replace the primary trigger line;
+    // extra lines have no synthetic/replayed equivalent, since neither
+    // records or synthesizes anything for them.
+    let mut aux_lines = if config.simulate || config.replay_log.is_some() || config.aux_lines.is_empty() {
+        None
+    } else {
+        match AuxLines::new(&gpiochip, &config.aux_lines, &config.consumer_label) {
+            Ok(aux_lines) => Some(aux_lines),
+            Err(error) => {
+                error!("{error}");
+                std::process::exit(1);
+            }
+        }
+    };
 
     info!("initialized, program will gracefully handle errors from now on");
 
-    for event in event_iterator {
-        match event {
-            Ok(event) => {
-                info!("recording requested to stop at {}", event.timestamp());
+    // Consecutive `Next::Error`s from the primary event stream, reset on
+    // every good edge. A handful in a row means the underlying fd, not a
+    // single read, is the problem (e.g. the gpiochip was removed/re-probed);
+    // see the recovery attempt below.
+    let mut consecutive_event_errors: u32 = 0;
+
+    // Reused across iterations rather than allocated per edge, so draining a
+    // burst at a high trigger rate (see `TriggerSource::drain_ready`) never
+    // needs its own allocation on the hot path.
+    let mut edge_batch: Vec<Edge> = Vec::with_capacity(DRAIN_BATCH_CAPACITY);
+
+    loop {
+        tokio::select! {
+            event = trigger_source.next_edge() => {
+                match event {
+                    Next::Edge(edge) => {
+                        consecutive_event_errors = 0;
+                        watchdog.mark_healthy();
+                        session.handle_edge(edge);
+
+                        edge_batch.clear();
+                        trigger_source.drain_ready(&mut edge_batch);
+                        for edge in edge_batch.drain(..) {
+                            session.handle_edge(edge);
+                        }
+                    }
+                    Next::Error(error) => {
+                        error!("{error}");
+                        consecutive_event_errors += 1;
+
+                        if consecutive_event_errors < MAX_CONSECUTIVE_EVENT_ERRORS {
+                            warn!("encountered error reading event from event stream, skipping...");
+                        } else {
+                            warn!(
+                                "{consecutive_event_errors} consecutive errors reading the trigger event \
+                                 stream, treating it as dead and re-requesting the line"
+                            );
+                            match reacquire_event_stream(
+                                &gpiochip,
+                                line_offset,
+                                &config.consumer_label,
+                                &config,
+                                Arc::clone(&clock),
+                            )
+                            .await
+                            {
+                                Some(recovered) => {
+                                    trigger_source = wrap_gpio_trigger_source(Box::new(recovered), &config, &mavlink_feedback);
+                                    consecutive_event_errors = 0;
+                                    session.reset_trigger();
+                                    watchdog.mark_healthy();
+                                }
+                                None => {
+                                    error!(
+                                        "failed to recover trigger line {line_offset} on {} after \
+                                         {EVENT_RECOVERY_ATTEMPTS} attempts, giving up",
+                                        gpiochip.display()
+                                    );
+                                    std::process::exit(EVENT_RECOVERY_EXIT_CODE);
+                                }
+                            }
+                        }
+                    }
+                    Next::Ended => {
+                        if config.replay_log.is_some() {
+                            info!("replay log exhausted, shutting down");
+                        } else {
+                            warn!("trigger source ended unexpectedly, shutting down");
+                            watchdog.mark_unhealthy();
+                        }
+                        break;
+                    }
+                    Next::TimedOut => {
+                        warn!(
+                            "no trigger edge observed in {EVENT_STALL_TIMEOUT:?}, \
+                             treating event loop as stalled"
+                        );
+                        watchdog.mark_unhealthy();
+                    }
+                }
+            }
+            event = async {
+                match aux_lines.as_mut() {
+                    Some(lines) => lines.next_pulse().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                let Some((line, result)) = event else {
+                    warn!("aux line event stream ended unexpectedly");
+                    continue;
+                };
+                match result {
+                    Ok(_timestamp) => match line.action {
+                        LineAction::StartVideo => {
+                            session.dispatch(ControlCommand::Start, &format!("aux line {}", line.label));
+                        }
+                        LineAction::StopVideo => {
+                            session.dispatch(ControlCommand::Stop, &format!("aux line {}", line.label));
+                        }
+                        LineAction::CaptureStill => {
+                            session.dispatch(ControlCommand::Snapshot, &format!("aux line {}", line.label));
+                        }
+                        LineAction::MarkEvent => session.mark_event(&line.label),
+                        LineAction::SafeShutdown => {
+                            info!("aux line {} requested a safe shutdown", line.label);
+                            break;
+                        }
+                    },
+                    Err(error) => {
+                        error!("{error}");
+                        warn!("encountered error reading event from aux line {}, skipping...", line.label);
+                    }
+                }
+            }
+            Some(new_event_stream) = recovered_rx.recv() => {
+                warn!(
+                    "swapping in re-acquired trigger line event stream, an edge may have been \
+                     missed during the gap; resyncing trigger state to idle"
+                );
+                let recovered = GpioTriggerSource::new(
+                    new_event_stream,
+                    EVENT_STALL_TIMEOUT,
+                    trigger_uses_realtime_clock.then(|| Arc::clone(&clock)),
+                );
+                trigger_source = wrap_gpio_trigger_source(Box::new(recovered), &config, &mavlink_feedback);
+                session.reset_trigger();
+                watchdog.mark_healthy();
+            }
+            Some(command) = control_rx.recv() => {
+                session.dispatch(command, "control api");
+            }
+            _ = sigusr1.recv() => {
+                session.dispatch(ControlCommand::Start, "SIGUSR1");
+            }
+            _ = sigusr2.recv() => {
+                session.dispatch(ControlCommand::Stop, "SIGUSR2");
+            }
+            _ = sigsnapshot.recv() => {
+                session.dispatch(ControlCommand::Snapshot, "SIGRTMIN+3");
+            }
+            _ = sighup.recv() => {
+                info!("received SIGHUP, reloading config from {:?}", cli.config);
+                reload_config(&mut config, &cli, &mut session);
+            }
+            _ = sigterm.recv() => {
+                info!("received SIGTERM, shutting down");
+                break;
+            }
+            Some(()) = shutdown_signal_rx.recv() => {
+                info!("received shutdown signal from logind, shutting down");
+                break;
+            }
+            Some(()) = undervoltage_rx.recv() => {
+                session.dispatch(ControlCommand::Stop, "undervoltage");
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("received SIGINT, shutting down");
+                break;
+            }
+        }
+    }
+
+    watchdog.notify_stopping();
+    info!("shutting down, rejected {} bouncy/glitch edges over this run", session.glitch_count());
+
+    session.shutdown().await;
 
-                // TODO: Stop the recording
+    info!("shutdown complete, all cameras finalized");
+}
+
+/// Requests `line_offset` on `gpiochip` with `config`'s bias/active-low/
+/// debounce/event-clock settings (see [`Config::trigger_line_config`]),
+/// falling back to `CLOCK_MONOTONIC` event timestamps and retrying once if
+/// `event_clock_realtime` was set and the kernel/driver rejected it - not
+/// every gpiochip implements `GPIO_V2_LINE_FLAG_EVENT_CLOCK_REALTIME`, and
+/// refusing to start over a "where the kernel supports it" knob is worse
+/// than falling back. Returns the request and whether it actually got the
+/// realtime clock.
+fn request_trigger_line(
+    gpiochip: &Path,
+    line_offset: u32,
+    consumer_label: &str,
+    config: &Config,
+) -> Result<(gpiocdev::Request, bool), String> {
+    let request = gpiocdev::Request::builder()
+        .on_chip(gpiochip)
+        .with_consumer(consumer_label)
+        .with_line(line_offset)
+        .with_line_config(&config.trigger_line_config())
+        .request();
+
+    match request {
+        Ok(request) => Ok((request, config.event_clock_realtime)),
+        Err(error) if config.event_clock_realtime => {
+            warn!(
+                "line {line_offset} on {} rejected event-clock-realtime ({error}); falling back to \
+                 CLOCK_MONOTONIC event timestamps",
+                gpiochip.display()
+            );
+            let mut fallback_config = config.trigger_line_config();
+            fallback_config.event_clock = None;
+            gpiocdev::Request::builder()
+                .on_chip(gpiochip)
+                .with_consumer(consumer_label)
+                .with_line(line_offset)
+                .with_line_config(&fallback_config)
+                .request()
+                .map(|request| (request, false))
+                .map_err(|error| error.to_string())
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+/// Requests `line_offset` on `gpiochip` through the gpio uAPI v2 driver via
+/// [`request_trigger_line`], exiting the process on any failure - there's no
+/// useful fallback left if the trigger line still isn't available at
+/// startup. Returns whether the request actually got the realtime clock, so
+/// the caller only threads a [`RealtimeClock`] through to
+/// [`GpioTriggerSource`] when there's really a realtime-domain timestamp to
+/// discipline it with.
+fn open_event_stream(gpiochip: &Path, line_offset: u32, consumer_label: &str, config: &Config) -> (AsyncRequest, bool) {
+    let (request, got_realtime_clock) = match request_trigger_line(gpiochip, line_offset, consumer_label, config) {
+        Ok(result) => result,
+        Err(error) => ExitReason::Gpio.exit(&format!(
+            "line {line_offset} on {} does not exist or is already in use: {error}",
+            gpiochip.display()
+        )),
+    };
 
+    if config.event_clock_realtime {
+        info!(
+            "trigger line {line_offset} event clock: {}",
+            if got_realtime_clock { "CLOCK_REALTIME (disciplined back to monotonic)" } else { "CLOCK_MONOTONIC" }
+        );
+    }
+
+    match AsyncRequest::new(request) {
+        Ok(request) => (request, got_realtime_clock),
+        Err(error) => ExitReason::Gpio.exit(&format!(
+            "gpio event stream on {} is not pollable on the tokio runtime: {error}",
+            gpiochip.display()
+        )),
+    }
+}
+
+/// Wraps a freshly (re)acquired GPIO source in a [`FusedTriggerSource`] if
+/// `config.mavlink.trigger_fusion` is set, so a line recovery after a fd
+/// error doesn't silently drop the MAVLink backup path. Pulls a brand new
+/// [`px4_camera_trigger::mavlink::MavlinkFeedback::trigger_source`] each
+/// time rather than reusing one from before the recovery, since that method
+/// is cheap (it just swaps the sender end of an internal channel) and the
+/// old one's receiver would otherwise be left dangling.
+fn wrap_gpio_trigger_source(
+    gpio: Box<dyn TriggerSource>,
+    config: &Config,
+    mavlink_feedback: &Arc<Option<MavlinkFeedback>>,
+) -> Box<dyn TriggerSource> {
+    if !config.mavlink.trigger_fusion {
+        return gpio;
+    }
+
+    let feedback = mavlink_feedback.as_ref().as_ref().expect("validated at startup");
+    Box::new(FusedTriggerSource::new(gpio, Box::new(feedback.trigger_source()), config.mavlink.trigger_fusion_dedup_window))
+}
+
+/// Bounded-backoff retries to re-request `line_offset` on `gpiochip`, via
+/// the same [`request_trigger_line`] fallback `open_event_stream` uses, for
+/// recovering from a dead event stream fd (e.g. the gpiochip was removed
+/// and re-probed). Gives up and returns `None` after
+/// [`EVENT_RECOVERY_ATTEMPTS`] failed attempts, rather than retrying
+/// forever. `realtime_clock` is only threaded through to the recovered
+/// [`GpioTriggerSource`] if this re-request actually got the realtime
+/// clock, same reasoning as `open_event_stream`.
+async fn reacquire_event_stream(
+    gpiochip: &Path,
+    line_offset: u32,
+    consumer_label: &str,
+    config: &Config,
+    realtime_clock: Arc<RealtimeClock>,
+) -> Option<GpioTriggerSource> {
+    let mut backoff = EVENT_RECOVERY_INITIAL_BACKOFF;
+
+    for attempt in 1..=EVENT_RECOVERY_ATTEMPTS {
+        let opened = request_trigger_line(gpiochip, line_offset, consumer_label, config).and_then(
+            |(request, got_realtime_clock)| {
+                AsyncRequest::new(request)
+                    .map(|request| (request, got_realtime_clock))
+                    .map_err(|error| error.to_string())
+            },
+        );
+
+        match opened {
+            Ok((request, got_realtime_clock)) => {
                 info!(
-                    "recording successfully stopped at {}",
-                    SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap()
-                        .as_nanos()
-                )
+                    "re-acquired trigger line {line_offset} on {} after {attempt} attempt(s)",
+                    gpiochip.display()
+                );
+                return Some(GpioTriggerSource::new(
+                    request,
+                    EVENT_STALL_TIMEOUT,
+                    got_realtime_clock.then(|| Arc::clone(&realtime_clock)),
+                ));
             }
             Err(error) => {
-                error!("{error}");
-                warn!("encountered error reading event from event iterator, skipping...");
+                warn!(
+                    "attempt {attempt}/{EVENT_RECOVERY_ATTEMPTS}: failed to re-request trigger line \
+                     {line_offset} on {} ({error})",
+                    gpiochip.display()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(EVENT_RECOVERY_MAX_BACKOFF);
+            }
+        }
+    }
+
+    None
+}
+
+/// Resolves `config.gpiochip`, replacing it with a device tree label lookup
+/// if `--gpiochip-label` is set. Exits the process on a failed lookup, same
+/// as every other GPIO setup failure.
+fn resolve_gpiochip(config: &Config) -> PathBuf {
+    match &config.gpiochip_label {
+        Some(label) => match gpio_discovery::find_chip_by_label(label) {
+            Ok(path) => path,
+            Err(error) => ExitReason::Gpio.exit(&error.to_string()),
+        },
+        None => config.gpiochip.clone(),
+    }
+}
+
+/// Resolves both `config.gpiochip` and `config.line_offset`, additionally
+/// replacing the line with a device tree name lookup if `--line-name` is
+/// set. Only used where a specific line must be pinned down (the trigger
+/// event stream, the line supervisor); `list-lines` only needs the chip, so
+/// it calls [`resolve_gpiochip`] directly instead - a bad `--line-name`
+/// shouldn't stop it from showing what lines *are* available.
+fn resolve_gpio_target(config: &Config) -> (PathBuf, u32) {
+    let gpiochip = resolve_gpiochip(config);
+
+    let line_offset = match &config.line_name {
+        Some(name) => {
+            let mut chip = match Chip::new(&gpiochip) {
+                Ok(chip) => chip,
+                Err(error) => {
+                    ExitReason::Gpio.exit(&format!("gpio chip {} is not accessible: {error}", gpiochip.display()));
+                }
+            };
+            match gpio_discovery::find_line_by_name(&mut chip, name) {
+                Ok(offset) => offset,
+                Err(error) => ExitReason::Gpio.exit(&error.to_string()),
             }
         }
+        None => config.line_offset,
+    };
+
+    (gpiochip, line_offset)
+}
+
+/// Re-resolves configuration from `cli`'s file/env/flag sources, as
+/// [`Config::load`] does at startup, and applies whatever settings this
+/// process can actually change without restarting.
+///
+/// Today that's only the trigger decode parameters (`min_pulse_width`,
+/// `short_pulse_max`, `invert_polarity`, `pwm_mode` and its thresholds),
+/// since those live in `session`'s plain in-memory trigger state machine.
+/// Everything else -
+/// `gpiochip`/`line_offset` (the subscription this reload must not drop),
+/// each camera's recording pipeline, MAVLink/metrics/control-api endpoints -
+/// is built once at startup and would need to be torn down and rebuilt to
+/// pick up a change, so those are only logged, not applied; an operator
+/// sees exactly what to restart for instead of a config file that silently
+/// didn't take effect.
+fn reload_config(config: &mut Config, cli: &Cli, session: &mut Session) {
+    let new_config = Config::load(cli.clone());
+
+    if new_config.min_pulse_width != config.min_pulse_width {
+        info!("reload: min_pulse_width {:?} -> {:?}", config.min_pulse_width, new_config.min_pulse_width);
+        session.set_min_pulse_width(new_config.min_pulse_width);
+        config.min_pulse_width = new_config.min_pulse_width;
+    }
+    if new_config.short_pulse_max != config.short_pulse_max {
+        info!("reload: short_pulse_max {:?} -> {:?}", config.short_pulse_max, new_config.short_pulse_max);
+        session.set_short_pulse_max(new_config.short_pulse_max);
+        config.short_pulse_max = new_config.short_pulse_max;
+    }
+    if new_config.invert_polarity != config.invert_polarity {
+        info!("reload: invert_polarity {} -> {}", config.invert_polarity, new_config.invert_polarity);
+        session.set_invert_polarity(new_config.invert_polarity);
+        config.invert_polarity = new_config.invert_polarity;
+    }
+    if new_config.pwm_mode != config.pwm_mode
+        || new_config.pwm_record_above != config.pwm_record_above
+        || new_config.pwm_stop_below != config.pwm_stop_below
+    {
+        info!(
+            "reload: pwm_mode {} -> {} (record_above {:?} -> {:?}, stop_below {:?} -> {:?})",
+            config.pwm_mode,
+            new_config.pwm_mode,
+            config.pwm_record_above,
+            new_config.pwm_record_above,
+            config.pwm_stop_below,
+            new_config.pwm_stop_below
+        );
+        session.set_pwm_mode(new_config.pwm_mode, new_config.pwm_record_above, new_config.pwm_stop_below);
+        config.pwm_mode = new_config.pwm_mode;
+        config.pwm_record_above = new_config.pwm_record_above;
+        config.pwm_stop_below = new_config.pwm_stop_below;
+    }
+
+    let mut restart_required = Vec::new();
+    if new_config.gpiochip != config.gpiochip {
+        restart_required.push("gpiochip");
+    }
+    if new_config.line_offset != config.line_offset {
+        restart_required.push("line_offset");
+    }
+    if new_config.gpiochip_label != config.gpiochip_label {
+        restart_required.push("gpiochip_label");
+    }
+    if new_config.line_name != config.line_name {
+        restart_required.push("line_name");
+    }
+    if new_config.consumer_label != config.consumer_label {
+        restart_required.push("consumer_label");
+    }
+    if new_config.line_bias != config.line_bias || new_config.active_low != config.active_low {
+        restart_required.push("line_bias/active_low");
+    }
+    if new_config.simulate != config.simulate || new_config.simulate_interval != config.simulate_interval {
+        restart_required.push("simulate");
+    }
+    if new_config.replay_log != config.replay_log {
+        restart_required.push("replay_log");
+    }
+    if new_config.min_free_disk_bytes != config.min_free_disk_bytes {
+        restart_required.push("min_free_disk_bytes");
+    }
+    if new_config.retention != config.retention {
+        restart_required.push("retention");
+    }
+    if new_config.trigger_log != config.trigger_log {
+        restart_required.push("trigger_log");
+    }
+    if new_config.flight_session != config.flight_session {
+        restart_required.push("flight_session");
+    }
+    if new_config.cameras() != config.cameras() {
+        restart_required.push("cameras (recording pipeline)");
+    }
+    if new_config.aux_lines != config.aux_lines {
+        restart_required.push("aux_lines");
+    }
+    if new_config.mavlink != config.mavlink {
+        restart_required.push("mavlink");
+    }
+    if new_config.pps != config.pps {
+        restart_required.push("pps");
+    }
+    if new_config.metrics != config.metrics {
+        restart_required.push("metrics");
+    }
+    if new_config.control_api != config.control_api {
+        restart_required.push("control_api");
+    }
+    if new_config.network_trigger != config.network_trigger {
+        restart_required.push("network_trigger");
+    }
+    if new_config.status != config.status {
+        restart_required.push("status");
+    }
+    if new_config.durability != config.durability {
+        restart_required.push("durability");
+    }
+    if new_config.status_led != config.status_led {
+        restart_required.push("status_led");
+    }
+    if new_config.capture_feedback != config.capture_feedback {
+        restart_required.push("capture_feedback");
+    }
+    if new_config.buzzer != config.buzzer {
+        restart_required.push("buzzer");
+    }
+    if new_config.intervalometer != config.intervalometer {
+        restart_required.push("intervalometer");
+    }
+    if new_config.thermal != config.thermal {
+        restart_required.push("thermal");
+    }
+    if new_config.storage_health != config.storage_health {
+        restart_required.push("storage_health");
+    }
+
+    if restart_required.is_empty() {
+        info!("reload: no changes require a restart");
+    } else {
+        warn!(
+            "reload: {} changed but can only take effect after a restart (`systemctl restart`)",
+            restart_required.join(", ")
+        );
     }
 }
@@ -0,0 +1,462 @@
+//! Writes `<output_dir>/<flight_session>/manifest.json`, listing every
+//! recorded file this session has produced, its SHA-256, duration, frame
+//! count, average bitrate, dropped frame count and the trigger events that
+//! bounded it, so the ground pipeline can verify a transfer's completeness
+//! and judge whether a file is actually any good without re-deriving
+//! anything from the video itself. Also lists AEB brackets
+//! (see [`Manifest::record_bracket`]), so the ground pipeline can group a
+//! still trigger's frames back into one HDR stack instead of treating them
+//! as unrelated stills, and whether the system clock was synchronized at
+//! startup (see [`Manifest::record_clock_sync`]), so a session whose
+//! geotags might be offset from true time can be flagged without needing
+//! the clock state, which isn't recoverable once the flight is over. Also
+//! lists which cameras, if any, fell back to a degraded encoding at startup
+//! (see [`Manifest::record_degraded_encoding`]), so the ground pipeline can
+//! flag a session's footage as lower-quality than requested. Also lists the
+//! most recent recording medium wear reading (see
+//! [`Manifest::record_storage_health`]), so a card or drive that was already
+//! near end of life during the flight doesn't need to be pieced together
+//! from log timestamps after the fact. Also lists any recording
+//! [`crate::state_journal::StateJournal`] found still open at startup (see
+//! [`Manifest::record_orphaned_recording`]), so a recording cut short by an
+//! unclean shutdown is flagged instead of just missing a `stop_sequence`.
+//!
+//! Unlike [`crate::trigger_log`]'s CSV sidecar, this is rewritten in full
+//! after every completed recording rather than appended to (JSON has no
+//! append-a-line equivalent that doesn't touch the closing bracket), and -
+//! unlike `TriggerLog::open` - a restart starts a fresh manifest rather than
+//! resuming the previous one, since parsing it back in would need a JSON
+//! parser this crate doesn't otherwise carry; the CSV sidecar remains the
+//! authoritative record of what happened before a restart.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::warn;
+use sha2::{Digest, Sha256};
+
+/// One completed recording: the file it produced, its checksum, and the
+/// trigger events that bounded it.
+struct ManifestEntry {
+    camera: String,
+    file: PathBuf,
+    sha256: String,
+    duration_seconds: f64,
+    /// `None` for backends that can't report a frame count; see
+    /// [`crate::recorder::Recorder::stop`].
+    frame_count: Option<u64>,
+    /// `file`'s size in bits divided by `duration_seconds`. `None` if the
+    /// file couldn't be stat'd or the recording was instantaneous (a
+    /// zero-length `duration_seconds` would divide by zero).
+    average_bitrate_kbps: Option<f64>,
+    /// Frames dropped or arrived late during this recording specifically,
+    /// per [`crate::metrics::CameraMetrics::dropped_frames`]'s delta across
+    /// the recording. Peak bitrate and min/max inter-frame gap aren't
+    /// tracked here: no backend currently timestamps individual frames, and
+    /// none of the existing per-backend hooks (`on_dropped_frames`,
+    /// `on_fatal_error`) carry the timing data needed to compute them
+    /// without adding frame-level instrumentation to every backend.
+    dropped_frames: u64,
+    start_sequence: u64,
+    stop_sequence: u64,
+}
+
+/// One auto-exposure bracket: the frames captured for a single
+/// [`crate::trigger::Transition::CaptureStill`] with `still_aeb_enabled` on,
+/// grouped together so the ground pipeline can tell they belong to the same
+/// HDR stack rather than treating each as an independent still.
+struct BracketEntry {
+    camera: String,
+    files: Vec<PathBuf>,
+    ev_stops: Vec<f32>,
+    sequence: u64,
+}
+
+/// Whether the system realtime clock was synchronized at startup, per
+/// [`crate::time_sync_check`]. Recorded here (rather than only logged) since
+/// the clock state isn't recoverable once the flight is over, but a
+/// suspect-geotag session still needs to be identifiable from the ground.
+struct ClockSyncStatus {
+    synchronized: bool,
+    detail: String,
+}
+
+/// One camera falling back to a degraded encoding at startup, e.g. MJPEG
+/// instead of the configured hardware codec; see
+/// [`crate::recorder::gstreamer_backend::GstreamerRecorder::new`]'s
+/// hardware-encoder-unavailable fallback. Recorded here (rather than only
+/// logged) so the ground pipeline can flag a session's footage as
+/// lower-quality than requested without re-deriving the codec from the file
+/// itself.
+struct DegradedEncodingEntry {
+    camera: String,
+    reason: String,
+}
+
+/// The most recent wear reading for the recording medium, per
+/// [`crate::storage_health`]. Recorded here (rather than only logged) for
+/// the same reason as [`ClockSyncStatus`]: the reading at the time of the
+/// flight isn't recoverable from the ground once the medium itself has
+/// moved on to its next session.
+struct StorageHealthStatus {
+    device: String,
+    percent_used: u8,
+}
+
+/// A recording [`crate::state_journal::StateJournal`] found still open (no
+/// matching `stop` row) when the previous run's journal was recovered at
+/// startup - i.e. this process was killed or crashed mid-recording rather
+/// than shutting down cleanly. Recorded here so the ground pipeline can flag
+/// the file (if it even exists) as an unverified, possibly-truncated
+/// recording instead of it just silently missing a `stop_sequence`.
+struct OrphanedRecordingEntry {
+    camera: String,
+    file: Option<PathBuf>,
+    sequence: u64,
+}
+
+/// Accumulates one [`ManifestEntry`] per completed recording and one
+/// [`BracketEntry`] per completed AEB burst, across every camera, and
+/// rewrites the session's `manifest.json` after each one.
+pub struct Manifest {
+    path: PathBuf,
+    entries: Mutex<Vec<ManifestEntry>>,
+    brackets: Mutex<Vec<BracketEntry>>,
+    clock_sync: Mutex<Option<ClockSyncStatus>>,
+    degraded_encodings: Mutex<Vec<DegradedEncodingEntry>>,
+    storage_health: Mutex<Option<StorageHealthStatus>>,
+    orphaned_recordings: Mutex<Vec<OrphanedRecordingEntry>>,
+}
+
+impl Manifest {
+    /// Points at `<output_dir>/<flight_session>/manifest.json`, creating the
+    /// session directory if needed. Starts empty; see the module docs for
+    /// why a restart doesn't resume a prior manifest.
+    pub fn open(output_dir: &Path, flight_session: &str) -> Result<Self, String> {
+        let session_dir = output_dir.join(flight_session);
+        std::fs::create_dir_all(&session_dir).map_err(|error| {
+            format!("failed to create session directory {}: {error}", session_dir.display())
+        })?;
+
+        Ok(Self {
+            path: session_dir.join("manifest.json"),
+            entries: Mutex::new(Vec::new()),
+            brackets: Mutex::new(Vec::new()),
+            clock_sync: Mutex::new(None),
+            degraded_encodings: Mutex::new(Vec::new()),
+            storage_health: Mutex::new(None),
+            orphaned_recordings: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Returns `(file, sha256)` for every recording finalized so far, for a
+    /// caller (e.g. [`crate::offload`]) that wants to verify a transferred
+    /// copy against what was actually written, independent of whatever
+    /// checksum the transfer mechanism itself used.
+    pub fn recorded_files(&self) -> Vec<(PathBuf, String)> {
+        self.entries.lock().unwrap().iter().map(|entry| (entry.file.clone(), entry.sha256.clone())).collect()
+    }
+
+    /// Records whether the system realtime clock was synchronized at
+    /// startup (see [`crate::time_sync_check`]) and rewrites the manifest.
+    /// Meant to be called at most once, before the first recording; a later
+    /// call overwrites the earlier one.
+    pub fn record_clock_sync(&self, synchronized: bool, detail: &str) {
+        *self.clock_sync.lock().unwrap() = Some(ClockSyncStatus { synchronized, detail: detail.to_string() });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &self.entries.lock().unwrap(),
+            &self.brackets.lock().unwrap(),
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+
+    /// Records that `camera` fell back to a degraded encoding at startup
+    /// (see [`DegradedEncodingEntry`]) and rewrites the manifest. Meant to be
+    /// called at most once per camera, before its first recording.
+    pub fn record_degraded_encoding(&self, camera: &str, reason: &str) {
+        self.degraded_encodings
+            .lock()
+            .unwrap()
+            .push(DegradedEncodingEntry { camera: camera.to_string(), reason: reason.to_string() });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &self.entries.lock().unwrap(),
+            &self.brackets.lock().unwrap(),
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+
+    /// Hashes `file` and appends an entry for it, then rewrites the
+    /// manifest. Logs and drops the entry (rather than failing the caller)
+    /// if `file` can't be read, since a completed recording that can't be
+    /// hashed shouldn't also crash the trigger worker that's reporting it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_recording(
+        &self,
+        camera: &str,
+        file: &Path,
+        duration_seconds: f64,
+        frame_count: Option<u64>,
+        average_bitrate_kbps: Option<f64>,
+        dropped_frames: u64,
+        start_sequence: u64,
+        stop_sequence: u64,
+    ) {
+        let sha256 = match hash_file(file) {
+            Ok(sha256) => sha256,
+            Err(error) => {
+                warn!("manifest: failed to hash {}: {error}", file.display());
+                return;
+            }
+        };
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(ManifestEntry {
+            camera: camera.to_string(),
+            file: file.to_path_buf(),
+            sha256,
+            duration_seconds,
+            frame_count,
+            average_bitrate_kbps,
+            dropped_frames,
+            start_sequence,
+            stop_sequence,
+        });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &entries,
+            &self.brackets.lock().unwrap(),
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+
+    /// Appends an AEB bracket - the frames captured for one still trigger
+    /// alongside the EV offset each was shot at - then rewrites the
+    /// manifest. `files` and `ev_stops` are expected to be the same length
+    /// and in shooting order; a mismatch (e.g. a frame within the bracket
+    /// failed to capture) is recorded as-is rather than dropped, since a
+    /// partial bracket is still useful for HDR stacking.
+    pub fn record_bracket(&self, camera: &str, files: &[PathBuf], ev_stops: &[f32], sequence: u64) {
+        if files.is_empty() {
+            return;
+        }
+
+        let mut brackets = self.brackets.lock().unwrap();
+        brackets.push(BracketEntry {
+            camera: camera.to_string(),
+            files: files.to_vec(),
+            ev_stops: ev_stops.to_vec(),
+            sequence,
+        });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &self.entries.lock().unwrap(),
+            &brackets,
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+
+    /// Records the recording medium's latest wear reading (see
+    /// [`crate::storage_health`]) and rewrites the manifest. Meant to be
+    /// called every time a fresh reading is available; a later call
+    /// overwrites the earlier one, since only the most recent reading
+    /// before a flight is actionable.
+    pub fn record_storage_health(&self, device: &str, percent_used: u8) {
+        *self.storage_health.lock().unwrap() = Some(StorageHealthStatus { device: device.to_string(), percent_used });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &self.entries.lock().unwrap(),
+            &self.brackets.lock().unwrap(),
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+
+    /// Records a recording [`crate::state_journal::StateJournal::open`]
+    /// found still open at startup (see [`OrphanedRecordingEntry`]) and
+    /// rewrites the manifest. Meant to be called at most once per camera,
+    /// before its first recording of this run.
+    pub fn record_orphaned_recording(&self, camera: &str, file: Option<&Path>, sequence: u64) {
+        self.orphaned_recordings.lock().unwrap().push(OrphanedRecordingEntry {
+            camera: camera.to_string(),
+            file: file.map(Path::to_path_buf),
+            sequence,
+        });
+
+        if let Err(error) = write_atomic(
+            &self.path,
+            &self.entries.lock().unwrap(),
+            &self.brackets.lock().unwrap(),
+            &self.clock_sync.lock().unwrap(),
+            &self.degraded_encodings.lock().unwrap(),
+            &self.storage_health.lock().unwrap(),
+            &self.orphaned_recordings.lock().unwrap(),
+        ) {
+            warn!("manifest: failed to write {}: {error}", self.path.display());
+        }
+    }
+}
+
+/// Streams `path` through SHA-256 in fixed-size chunks rather than reading
+/// it into memory whole, since a recording can be far larger than this
+/// process's working set on constrained flight hardware.
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Serializes `entries` to `path` via a temporary file and rename, so a
+/// crash mid-write never leaves a truncated manifest behind for the ground
+/// pipeline to trip over.
+#[allow(clippy::too_many_arguments)]
+fn write_atomic(
+    path: &Path,
+    entries: &[ManifestEntry],
+    brackets: &[BracketEntry],
+    clock_sync: &Option<ClockSyncStatus>,
+    degraded_encodings: &[DegradedEncodingEntry],
+    storage_health: &Option<StorageHealthStatus>,
+    orphaned_recordings: &[OrphanedRecordingEntry],
+) -> std::io::Result<()> {
+    let mut json = String::from("{\"recordings\":[");
+    for (index, entry) in entries.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"camera\":{},\"file\":{},\"sha256\":{},\"duration_seconds\":{},\"frame_count\":{},\
+             \"average_bitrate_kbps\":{},\"dropped_frames\":{},\"start_sequence\":{},\"stop_sequence\":{}}}",
+            json_string(&entry.camera),
+            json_string(&entry.file.display().to_string()),
+            json_string(&entry.sha256),
+            entry.duration_seconds,
+            entry.frame_count.map(|count| count.to_string()).unwrap_or_else(|| "null".to_string()),
+            entry.average_bitrate_kbps.map(|kbps| kbps.to_string()).unwrap_or_else(|| "null".to_string()),
+            entry.dropped_frames,
+            entry.start_sequence,
+            entry.stop_sequence,
+        ));
+    }
+    json.push_str("],\"stills_brackets\":[");
+    for (index, bracket) in brackets.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        let files = bracket
+            .files
+            .iter()
+            .map(|file| json_string(&file.display().to_string()))
+            .collect::<Vec<_>>()
+            .join(",");
+        let ev_stops = bracket.ev_stops.iter().map(f32::to_string).collect::<Vec<_>>().join(",");
+        json.push_str(&format!(
+            "{{\"camera\":{},\"files\":[{files}],\"ev_stops\":[{ev_stops}],\"sequence\":{}}}",
+            json_string(&bracket.camera),
+            bracket.sequence,
+        ));
+    }
+    json.push_str("],\"clock_sync\":");
+    match clock_sync {
+        Some(status) => json.push_str(&format!(
+            "{{\"synchronized\":{},\"detail\":{}}}",
+            status.synchronized,
+            json_string(&status.detail),
+        )),
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"degraded_encodings\":[");
+    for (index, degraded) in degraded_encodings.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"camera\":{},\"reason\":{}}}",
+            json_string(&degraded.camera),
+            json_string(&degraded.reason),
+        ));
+    }
+    json.push_str("],\"storage_health\":");
+    match storage_health {
+        Some(status) => json.push_str(&format!(
+            "{{\"device\":{},\"percent_used\":{}}}",
+            json_string(&status.device),
+            status.percent_used,
+        )),
+        None => json.push_str("null"),
+    }
+    json.push_str(",\"orphaned_recordings\":[");
+    for (index, orphaned) in orphaned_recordings.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"camera\":{},\"file\":{},\"sequence\":{}}}",
+            json_string(&orphaned.camera),
+            orphaned
+                .file
+                .as_ref()
+                .map(|file| json_string(&file.display().to_string()))
+                .unwrap_or_else(|| "null".to_string()),
+            orphaned.sequence,
+        ));
+    }
+    json.push_str("]}");
+
+    let tmp_path = path.with_extension("json.tmp");
+    let mut tmp_file = std::fs::File::create(&tmp_path)?;
+    tmp_file.write_all(json.as_bytes())?;
+    tmp_file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Renders `value` as a quoted JSON string, per RFC 8259.
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if (control as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", control as u32)),
+            other => escaped.push(other),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,851 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{error, warn};
+use mavlink::common::{
+    MavCmd, MavMessage, MavModeFlag, MavParamType, MavResult, MavSeverity, CAMERA_CAPTURE_STATUS_DATA,
+    CAMERA_IMAGE_CAPTURED_DATA, CAMERA_TRIGGER_DATA, COMMAND_ACK_DATA, PARAM_SET_DATA, PARAM_VALUE_DATA,
+    STATUSTEXT_DATA, TIMESYNC_DATA, VIDEO_STREAM_STATUS_DATA,
+};
+use mavlink::{MavConnection, MavHeader};
+use tokio::sync::mpsc;
+
+use crate::control_api::ControlCommand;
+use crate::metrics::Metrics;
+use crate::naming::write_atomically;
+use crate::recorder::{CameraControls, RegionOfInterest};
+
+/// PX4-style onboard parameter names accepted by [`decode_param_set`], one
+/// per [`CameraControls`] field. Kept short (PX4 truncates `param_id` to 16
+/// characters) and prefixed `CAM_` to avoid colliding with PX4's own params.
+const PARAM_EXPOSURE_MICROS: &str = "CAM_EXP_US";
+const PARAM_GAIN: &str = "CAM_GAIN";
+const PARAM_WHITE_BALANCE_KELVIN: &str = "CAM_WB_K";
+const PARAM_FOCUS_POSITION: &str = "CAM_FOCUS";
+
+/// PX4-style onboard parameter names accepted by [`decode_roi_param_set`],
+/// one per [`RegionOfInterest`] field. Same naming scheme as the
+/// `CameraControls` params above.
+const PARAM_ROI_X: &str = "CAM_ROI_X";
+const PARAM_ROI_Y: &str = "CAM_ROI_Y";
+const PARAM_ROI_WIDTH: &str = "CAM_ROI_W";
+const PARAM_ROI_HEIGHT: &str = "CAM_ROI_H";
+
+/// How often the timesync task pings PX4 for a fresh offset sample.
+const TIMESYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// PX4 packs its own flight mode into `HEARTBEAT.custom_mode` as
+/// `(main_mode << 16) | (sub_mode << 24)` - see PX4's `px4_custom_mode`
+/// union. `AUTO`/`RTL` identify the return-to-launch mode
+/// [`MavlinkFeedback::is_rtl`] checks for.
+const PX4_CUSTOM_MAIN_MODE_AUTO: u32 = 4;
+const PX4_CUSTOM_SUB_MODE_AUTO_RTL: u32 = 5;
+
+/// How often `CAMERA_CAPTURE_STATUS`/`VIDEO_STREAM_STATUS` are published, so
+/// QGroundControl's camera view stays live without flooding the link.
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often [`MavlinkFeedback::spawn_disarm_watch`] polls
+/// [`MavlinkFeedback::is_armed`] for a disarm edge. Coarser than
+/// `TIMESYNC_INTERVAL` since a disarm auto-stop doesn't need to react
+/// within milliseconds - `HEARTBEAT` itself only lands once a second.
+const DISARM_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Static per-camera RTSP preview stream parameters for periodic
+/// `VIDEO_STREAM_STATUS` publishing, built once from each camera's
+/// [`crate::recorder::RecorderConfig`] at startup - unlike `CAMERA_CAPTURE_STATUS`,
+/// nothing here changes at runtime, so there's no need to route it through
+/// [`crate::metrics::Metrics`].
+#[derive(Debug, Clone)]
+pub struct VideoStreamInfo {
+    /// Distinguishes streams in the (rare) multi-camera case; also used as
+    /// `VIDEO_STREAM_STATUS.stream_id`, 1-indexed per the MAVLink spec.
+    pub stream_id: u8,
+    pub enabled: bool,
+    pub bitrate_kbps: u32,
+    /// `None` when the camera doesn't force a fixed capture resolution; sent
+    /// as 0 (unknown) since `VIDEO_STREAM_STATUS` has no "unset" sentinel.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// The latest position/attitude sample received from PX4, used to fill
+/// `CAMERA_IMAGE_CAPTURED`'s position fields and to geotag stills (see
+/// [`crate::geotag`]).
+///
+/// `GLOBAL_POSITION_INT` and `ATTITUDE` are separate MAVLink messages that
+/// arrive at their own independent rates, so this is best-effort: each
+/// field reflects whichever message last updated it, not necessarily the
+/// same instant. All-zero (the [`Default`]) means no fix has been received
+/// yet, since `(0, 0)` is not a plausible real-world position.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Position {
+    /// Latitude, in degrees * 1e7 (`GLOBAL_POSITION_INT` units).
+    pub lat: i32,
+    /// Longitude, in degrees * 1e7.
+    pub lon: i32,
+    /// Altitude above mean sea level, in millimeters.
+    pub alt: i32,
+    /// Altitude above the home/takeoff point, in millimeters.
+    pub relative_alt: i32,
+    /// Ground speed, in meters/second, derived from `GLOBAL_POSITION_INT`'s
+    /// `vx`/`vy` (horizontal velocity, ignoring climb rate).
+    pub ground_speed_mps: f32,
+    /// Roll, in radians.
+    pub roll: f32,
+    /// Pitch, in radians.
+    pub pitch: f32,
+    /// Yaw, in radians, 0 at true north, increasing clockwise.
+    pub yaw: f32,
+}
+
+/// The latest `HIGHRES_IMU` sample received from PX4, best-effort same as
+/// [`Position`]: each field reflects whichever `HIGHRES_IMU` last updated
+/// it, and all-zero (the [`Default`]) means none has arrived yet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImuSample {
+    /// Body-frame X/Y/Z acceleration, in m/s^2.
+    pub xacc: f32,
+    pub yacc: f32,
+    pub zacc: f32,
+    /// Body-frame X/Y/Z angular rate, in rad/s.
+    pub xgyro: f32,
+    pub ygyro: f32,
+    pub zgyro: f32,
+}
+
+/// The latest gimbal orientation reported by a `GIMBAL_DEVICE_ATTITUDE_STATUS`
+/// message, decoded from its quaternion into Euler angles for the same
+/// reason [`Position`]'s `roll`/`pitch`/`yaw` are: callers (EXIF/XMP
+/// geotagging, [`crate::trigger_log::TriggerLog`]) want a human-readable
+/// angle, not a quaternion to decode themselves.
+///
+/// Unlike `Position`/[`ImuSample`], this has no "no fix yet" sentinel value
+/// - a centered gimbal genuinely reports all-zero - so
+/// [`MavlinkFeedback::latest_gimbal_attitude`] wraps it in an `Option`
+/// instead, `None` until the first status message arrives (or forever, on
+/// an airframe with no gimbal at all).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GimbalAttitude {
+    /// Roll, in radians.
+    pub roll: f32,
+    /// Pitch, in radians; roughly -90 degrees (straight down) for nadir
+    /// imagery, 0 for forward-facing/oblique.
+    pub pitch: f32,
+    /// Yaw, in radians, relative to the vehicle (not true north, unlike
+    /// [`Position::yaw`]) per the `GIMBAL_DEVICE_ATTITUDE_STATUS` spec.
+    pub yaw: f32,
+}
+
+/// A trigger-time snapshot of [`Position`]'s attitude fields, the latest
+/// [`ImuSample`], and the latest [`GimbalAttitude`] (if a gimbal is present),
+/// attached to a [`crate::trigger_log::TriggerLog`] row so
+/// structure-from-motion tools get an initial orientation estimate per image
+/// without cross-referencing a separate PX4 log. See
+/// [`MavlinkFeedback::latest_capture_telemetry`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaptureTelemetry {
+    pub roll: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub imu: ImuSample,
+    pub gimbal: Option<GimbalAttitude>,
+}
+
+/// Parameters for connecting to PX4 over MAVLink to publish capture
+/// feedback.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MavlinkConfig {
+    /// Whether to connect to PX4 at all. Off by default since not every
+    /// deployment wires up a MAVLink link to this process.
+    pub enabled: bool,
+    /// A `mavlink` crate connection address, e.g. `udpout:127.0.0.1:14550`
+    /// or `serial:/dev/ttyS0:57600`.
+    pub address: String,
+    /// This component's MAVLink system ID. Should match the id PX4 expects
+    /// its camera to report under.
+    pub system_id: u8,
+    /// This component's MAVLink component ID, conventionally
+    /// `MAV_COMP_ID_CAMERA` (100).
+    pub component_id: u8,
+    /// Ignore trigger edges while PX4 reports itself disarmed, so a GPIO
+    /// glitch or a bench test with the trigger line still wired up doesn't
+    /// start a recording nobody wants. Permissive (armed) until the first
+    /// `HEARTBEAT` arrives, so a slow or misconfigured link doesn't block
+    /// every trigger.
+    pub require_armed: bool,
+    /// Only meaningful if `require_armed` is set: also stop an in-progress
+    /// recording the moment PX4 reports disarmed, rather than waiting for a
+    /// stop edge that a disarm on the bench may never produce.
+    pub auto_stop_on_disarm: bool,
+    /// Decode incoming `CAMERA_TRIGGER` messages and
+    /// `MAV_CMD_DO_DIGICAM_CONTROL` commands as this process's trigger
+    /// source instead of requesting a physical GPIO line at all. See
+    /// [`MavlinkTriggerSource`].
+    pub trigger_source: bool,
+    /// Like `trigger_source`, but fused with the physical GPIO trigger line
+    /// instead of replacing it, via
+    /// [`crate::trigger_source::FusedTriggerSource`]: both feed the trigger
+    /// state machine, with a GPIO edge always taking priority over a MAVLink
+    /// one that arrives within `trigger_fusion_dedup_window` of it. Mutually
+    /// exclusive with `trigger_source`.
+    pub trigger_fusion: bool,
+    /// See `trigger_fusion`. Only meaningful if `trigger_fusion` is set.
+    pub trigger_fusion_dedup_window: Duration,
+    /// Ignore Start/CaptureStill trigger edges until the vehicle's altitude
+    /// above the home point has climbed above this threshold at least once
+    /// since arming, and again whenever it drops back below it - covering
+    /// both "still on the ground before takeoff" and "landing bounce after
+    /// touchdown" in a single gate, since both are the same physical
+    /// condition (below the gate altitude) at different points in the
+    /// flight. `None` (the default) disables this gate. See
+    /// [`MavlinkFeedback::takeoff_detected`].
+    pub min_altitude_gate_m: Option<f32>,
+    /// Also ignore Start/CaptureStill trigger edges while PX4 reports itself
+    /// in RTL (return-to-launch), so an automatic return-and-land doesn't
+    /// produce a recording nobody asked for. See [`MavlinkFeedback::is_rtl`].
+    pub block_triggers_during_rtl: bool,
+}
+
+impl Default for MavlinkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "udpout:127.0.0.1:14550".to_string(),
+            system_id: 1,
+            component_id: 100,
+            require_armed: false,
+            auto_stop_on_disarm: false,
+            trigger_source: false,
+            trigger_fusion: false,
+            trigger_fusion_dedup_window: Duration::from_millis(500),
+            min_altitude_gate_m: None,
+            block_triggers_during_rtl: false,
+        }
+    }
+}
+
+/// Publishes `CAMERA_TRIGGER`/`CAMERA_IMAGE_CAPTURED` feedback to PX4 over a
+/// MAVLink connection, so the autopilot log carries authoritative capture
+/// timestamps for later geotagging.
+///
+/// The connection is held behind an `Arc` rather than owned outright since
+/// [`MavlinkFeedback::spawn_timesync`] needs a second thread reading replies
+/// off the same link while the main event loop keeps sending feedback
+/// messages on it.
+pub struct MavlinkFeedback {
+    connection: Arc<dyn MavConnection<MavMessage> + Send + Sync>,
+    header: MavHeader,
+    next_sequence: AtomicU32,
+    /// Where [`MavlinkFeedback::publish_trigger`] persists `next_sequence`
+    /// after every `CAMERA_IMAGE_CAPTURED`, so it keeps counting up across a
+    /// power cycle instead of restarting from 0 and confusing a downstream
+    /// ingest pipeline that assumes image indices only ever increase.
+    sequence_state_path: PathBuf,
+    /// Best estimate of `autopilot_clock - our_clock`, in nanoseconds, kept
+    /// current by [`MavlinkFeedback::spawn_timesync`]. Zero (i.e. "assume
+    /// the clocks already agree") until the first sample comes in.
+    clock_offset_ns: Arc<AtomicI64>,
+    /// Latest `GLOBAL_POSITION_INT`/`ATTITUDE` sample, kept current by
+    /// [`MavlinkFeedback::spawn_timesync`]. A `Mutex` rather than a set of
+    /// atomics since callers want a consistent snapshot of all fields
+    /// together, not each field read independently.
+    latest_position: Arc<Mutex<Position>>,
+    /// Latest `HIGHRES_IMU` sample, kept current by
+    /// [`MavlinkFeedback::spawn_timesync`] the same way `latest_position` is.
+    latest_imu: Arc<Mutex<ImuSample>>,
+    /// Latest `GIMBAL_DEVICE_ATTITUDE_STATUS` sample, kept current by
+    /// [`MavlinkFeedback::spawn_timesync`]. `None` until the first one
+    /// arrives, or forever on an airframe with no gimbal; see
+    /// [`GimbalAttitude`].
+    latest_gimbal_attitude: Arc<Mutex<Option<GimbalAttitude>>>,
+    /// Whether PX4's last `HEARTBEAT` reported `MAV_MODE_FLAG_SAFETY_ARMED`,
+    /// kept current by [`MavlinkFeedback::spawn_timesync`]. Defaults to
+    /// `true` (permissive) until the first heartbeat arrives, same
+    /// "assume the best until told otherwise" default as `clock_offset_ns`.
+    armed: Arc<AtomicBool>,
+    /// The `seq` field of the last `CAMERA_TRIGGER` PX4 sent us, kept current
+    /// by [`MavlinkFeedback::spawn_timesync`], for
+    /// [`crate::session::Session::check_missed_triggers`] to cross-check
+    /// against our own trigger sequence. `-1` until the first one arrives.
+    px4_trigger_sequence: Arc<AtomicI64>,
+    /// Set by [`MavlinkFeedback::trigger_source`] when
+    /// [`MavlinkConfig::trigger_source`] is enabled, so
+    /// [`MavlinkFeedback::spawn_timesync`]'s reply-reading task can forward
+    /// each incoming `CAMERA_TRIGGER`/`MAV_CMD_DO_DIGICAM_CONTROL` onto the
+    /// returned [`crate::trigger_source::MavlinkTriggerSource`]. `None` for
+    /// every other deployment, which don't pay for the channel send.
+    trigger_tx: Arc<Mutex<Option<mpsc::UnboundedSender<()>>>>,
+    /// See [`MavlinkConfig::min_altitude_gate_m`]. `None` disables takeoff
+    /// tracking entirely, so [`Self::takeoff_detected`] can stay permissive
+    /// (`true`) without [`Self::spawn_timesync`] having to special-case it.
+    min_altitude_gate_mm: Option<i32>,
+    /// Set by [`Self::spawn_timesync`] once `latest_position.relative_alt`
+    /// has climbed above `min_altitude_gate_mm` while armed, and cleared
+    /// again on the next disarm - see [`Self::takeoff_detected`]. `true`
+    /// (permissive) if `min_altitude_gate_mm` is `None`.
+    takeoff_detected: Arc<AtomicBool>,
+    /// Raw `HEARTBEAT.custom_mode`, kept current by
+    /// [`Self::spawn_timesync`], decoded by [`Self::is_rtl`] using PX4's
+    /// mavlink custom-mode bit layout.
+    custom_mode: Arc<AtomicU32>,
+}
+
+impl MavlinkFeedback {
+    /// Opens the configured MAVLink connection. Returns `Err` (rather than
+    /// panicking) since a bad `--mavlink-address` or an unreachable serial
+    /// device are user-configuration errors, handled the same
+    /// log-and-exit way as the GPIO chip/line and recording pipeline.
+    ///
+    /// `state_dir` (the primary camera's `output_dir`) is where the
+    /// `CAMERA_IMAGE_CAPTURED` sequence counter is persisted, in a file
+    /// shared across flight sessions rather than per-session like
+    /// [`crate::naming::NamingScheme`]'s, since downstream ingest expects it
+    /// to keep increasing across power cycles regardless of session
+    /// boundaries.
+    pub fn connect(config: &MavlinkConfig, state_dir: &Path) -> Result<Self, String> {
+        let connection = mavlink::connect(&config.address).map_err(|error| {
+            format!("failed to open mavlink connection {}: {error}", config.address)
+        })?;
+
+        std::fs::create_dir_all(state_dir)
+            .map_err(|error| format!("failed to create {}: {error}", state_dir.display()))?;
+        let sequence_state_path = state_dir.join(".mavlink-image-sequence");
+        let next_sequence = std::fs::read_to_string(&sequence_state_path)
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+            .unwrap_or(0);
+
+        Ok(Self {
+            connection: Arc::from(connection),
+            header: MavHeader {
+                system_id: config.system_id,
+                component_id: config.component_id,
+                sequence: 0,
+            },
+            next_sequence: AtomicU32::new(next_sequence),
+            sequence_state_path,
+            clock_offset_ns: Arc::new(AtomicI64::new(0)),
+            latest_position: Arc::new(Mutex::new(Position::default())),
+            latest_imu: Arc::new(Mutex::new(ImuSample::default())),
+            latest_gimbal_attitude: Arc::new(Mutex::new(None)),
+            armed: Arc::new(AtomicBool::new(true)),
+            px4_trigger_sequence: Arc::new(AtomicI64::new(-1)),
+            trigger_tx: Arc::new(Mutex::new(None)),
+            min_altitude_gate_mm: config.min_altitude_gate_m.map(|meters| (meters * 1000.0) as i32),
+            takeoff_detected: Arc::new(AtomicBool::new(config.min_altitude_gate_m.is_none())),
+            custom_mode: Arc::new(AtomicU32::new(0)),
+        })
+    }
+
+    /// Returns a [`crate::trigger_source::MavlinkTriggerSource`] fed by every
+    /// `CAMERA_TRIGGER`/`MAV_CMD_DO_DIGICAM_CONTROL` [`Self::spawn_timesync`]'s
+    /// reply-reading task decodes off this connection from here on, for
+    /// [`MavlinkConfig::trigger_source`] deployments with no spare GPIO
+    /// wiring to a trigger line. Must be called before [`Self::spawn_timesync`]
+    /// so that task's very first messages aren't decoded before a receiver
+    /// exists to forward them to.
+    pub fn trigger_source(&self) -> crate::trigger_source::MavlinkTriggerSource {
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.trigger_tx.lock().unwrap() = Some(tx);
+        crate::trigger_source::MavlinkTriggerSource::new(rx)
+    }
+
+    /// Spawns the background tasks that keep `clock_offset_ns`,
+    /// `latest_position` and `latest_imu` current: one task periodically
+    /// pinging PX4 with a `TIMESYNC` request carrying our current time, and
+    /// one task reading the link for PX4's replies, turning each `TIMESYNC`
+    /// round trip into a fresh offset estimate, each
+    /// `GLOBAL_POSITION_INT`/`ATTITUDE` into a fresh position sample, and
+    /// each `HIGHRES_IMU` into a fresh IMU sample.
+    ///
+    /// The reply-reading task also decodes `COMMAND_LONG` requests addressed
+    /// to this component - `MAV_CMD_VIDEO_START_CAPTURE`/`_STOP_CAPTURE`, so
+    /// a GCS's "record" button drives the same [`ControlCommand`] path as
+    /// the control API and GPIO aux lines - and answers each with a
+    /// `COMMAND_ACK`. It has to live in this same task rather than a
+    /// dedicated one: [`MavConnection::recv`] is a single shared queue, so a
+    /// second reader would only get whichever messages this one didn't.
+    /// `commands` is forwarded straight to [`crate::main::run`]'s event
+    /// loop, which already resolves conflicts between trigger sources (e.g.
+    /// a start command while already recording) the same way regardless of
+    /// which source issued it.
+    ///
+    /// Both spawned tasks run on tokio's blocking thread pool
+    /// (`spawn_blocking`), same as each camera's transition worker, since
+    /// [`MavConnection::send`]/[`MavConnection::recv`] block the calling
+    /// thread and a task alternating between them would only ping PX4 as
+    /// often as replies happen to arrive.
+    pub fn spawn_timesync(&self, commands: mpsc::UnboundedSender<ControlCommand>) {
+        let requester = Arc::clone(&self.connection);
+        let header = self.header;
+        tokio::task::spawn_blocking(move || loop {
+            let request = MavMessage::TIMESYNC(TIMESYNC_DATA {
+                tc1: 0,
+                ts1: now_ns() as i64,
+            });
+            if let Err(error) = requester.send(&header, &request) {
+                warn!("failed to send TIMESYNC request: {error}");
+            }
+            std::thread::sleep(TIMESYNC_INTERVAL);
+        });
+
+        let responses = Arc::clone(&self.connection);
+        let clock_offset_ns = Arc::clone(&self.clock_offset_ns);
+        let latest_position = Arc::clone(&self.latest_position);
+        let latest_imu = Arc::clone(&self.latest_imu);
+        let latest_gimbal_attitude = Arc::clone(&self.latest_gimbal_attitude);
+        let armed = Arc::clone(&self.armed);
+        let px4_trigger_sequence = Arc::clone(&self.px4_trigger_sequence);
+        let trigger_tx = Arc::clone(&self.trigger_tx);
+        let min_altitude_gate_mm = self.min_altitude_gate_mm;
+        let takeoff_detected = Arc::clone(&self.takeoff_detected);
+        let custom_mode = Arc::clone(&self.custom_mode);
+        let header = self.header;
+        tokio::task::spawn_blocking(move || loop {
+            match responses.recv() {
+                // A `TIMESYNC` with `tc1` set is PX4 echoing a request of
+                // ours back with its own clock reading filled in.
+                Ok((_, MavMessage::TIMESYNC(data))) if data.tc1 != 0 => {
+                    let round_trip_ns = (now_ns() as i64 - data.ts1).max(0) as u64;
+                    // Assume symmetric latency: PX4 read its clock halfway
+                    // through the round trip.
+                    let our_time_at_tc1 = data.ts1 + (round_trip_ns / 2) as i64;
+                    clock_offset_ns.store(data.tc1 - our_time_at_tc1, Ordering::Relaxed);
+                }
+                Ok((_, MavMessage::GLOBAL_POSITION_INT(data))) => {
+                    let mut position = latest_position.lock().unwrap();
+                    position.lat = data.lat;
+                    position.lon = data.lon;
+                    position.alt = data.alt;
+                    position.relative_alt = data.relative_alt;
+                    position.ground_speed_mps =
+                        ((data.vx as f32).powi(2) + (data.vy as f32).powi(2)).sqrt() / 100.0;
+
+                    if let Some(gate_mm) = min_altitude_gate_mm {
+                        if data.relative_alt >= gate_mm && armed.load(Ordering::Relaxed) {
+                            takeoff_detected.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+                Ok((_, MavMessage::ATTITUDE(data))) => {
+                    let mut position = latest_position.lock().unwrap();
+                    position.roll = data.roll;
+                    position.pitch = data.pitch;
+                    position.yaw = data.yaw;
+                }
+                Ok((_, MavMessage::HIGHRES_IMU(data))) => {
+                    let mut imu = latest_imu.lock().unwrap();
+                    imu.xacc = data.xacc;
+                    imu.yacc = data.yacc;
+                    imu.zacc = data.zacc;
+                    imu.xgyro = data.xgyro;
+                    imu.ygyro = data.ygyro;
+                    imu.zgyro = data.zgyro;
+                }
+                Ok((_, MavMessage::GIMBAL_DEVICE_ATTITUDE_STATUS(data))) => {
+                    let (roll, pitch, yaw) = quaternion_to_euler(data.q);
+                    *latest_gimbal_attitude.lock().unwrap() = Some(GimbalAttitude { roll, pitch, yaw });
+                }
+                Ok((_, MavMessage::HEARTBEAT(data))) => {
+                    let is_armed = data.base_mode.intersects(MavModeFlag::MAV_MODE_FLAG_SAFETY_ARMED);
+                    let was_armed = armed.swap(is_armed, Ordering::Relaxed);
+                    custom_mode.store(data.custom_mode, Ordering::Relaxed);
+
+                    if was_armed && !is_armed && min_altitude_gate_mm.is_some() {
+                        // Clear the gate on every disarm, so the next flight
+                        // has to climb back above it too, rather than
+                        // inheriting a takeoff detection from hours earlier.
+                        takeoff_detected.store(false, Ordering::Relaxed);
+                    }
+                }
+                // PX4's own camera_trigger driver echoes every pulse it
+                // issues as a `CAMERA_TRIGGER` on the link, independent of
+                // the physical GPIO edge this process decodes; see
+                // `crate::session::Session::check_missed_triggers`.
+                Ok((_, MavMessage::CAMERA_TRIGGER(data))) => {
+                    px4_trigger_sequence.store(data.seq as i64, Ordering::Relaxed);
+                    // Also feeds `MavlinkConfig::trigger_source` deployments,
+                    // where this same message (PX4's own distance-based
+                    // camera trigger, say) is the trigger itself rather than
+                    // just an echo to cross-check a GPIO edge against.
+                    if let Some(trigger_tx) = trigger_tx.lock().unwrap().as_ref() {
+                        let _ = trigger_tx.send(());
+                    }
+                }
+                Ok((_, MavMessage::COMMAND_LONG(data))) if data.target_component == header.component_id => {
+                    let (command, result) = match data.command {
+                        MavCmd::MAV_CMD_VIDEO_START_CAPTURE => {
+                            (Some(ControlCommand::Start), MavResult::MAV_RESULT_ACCEPTED)
+                        }
+                        MavCmd::MAV_CMD_VIDEO_STOP_CAPTURE => {
+                            (Some(ControlCommand::Stop), MavResult::MAV_RESULT_ACCEPTED)
+                        }
+                        // A GCS's manual "trigger camera" button; there's no
+                        // `ControlCommand` for a single-shot trigger, so this
+                        // feeds `trigger_tx` directly instead, the same as an
+                        // incoming `CAMERA_TRIGGER` above. Unsupported (rather
+                        // than silently accepted) when no
+                        // `MavlinkTriggerSource` is listening, since without
+                        // one this deployment has no way to act on it.
+                        MavCmd::MAV_CMD_DO_DIGICAM_CONTROL => match trigger_tx.lock().unwrap().as_ref() {
+                            Some(trigger_tx) => {
+                                let _ = trigger_tx.send(());
+                                (None, MavResult::MAV_RESULT_ACCEPTED)
+                            }
+                            None => (None, MavResult::MAV_RESULT_UNSUPPORTED),
+                        },
+                        _ => (None, MavResult::MAV_RESULT_UNSUPPORTED),
+                    };
+
+                    if let Some(command) = command {
+                        if commands.send(command).is_err() {
+                            warn!("mavlink command {:?} received, but the event loop has shut down", data.command);
+                        }
+                    }
+
+                    let ack = MavMessage::COMMAND_ACK(COMMAND_ACK_DATA { command: data.command, result, ..Default::default() });
+                    if let Err(error) = responses.send(&header, &ack) {
+                        warn!("failed to send COMMAND_ACK over mavlink: {error}");
+                    }
+                }
+                Ok((_, MavMessage::PARAM_SET(data))) if data.target_component == header.component_id => {
+                    let param_id = param_id_str(&data.param_id);
+                    if let Some(controls) = decode_param_set(param_id, data.param_value) {
+                        if commands.send(ControlCommand::SetCameraControls(controls)).is_err() {
+                            warn!("mavlink PARAM_SET {param_id} received, but the event loop has shut down");
+                        }
+                    } else if let Some(roi) = decode_roi_param_set(param_id, data.param_value) {
+                        if commands.send(ControlCommand::SetRegionOfInterest(roi)).is_err() {
+                            warn!("mavlink PARAM_SET {param_id} received, but the event loop has shut down");
+                        }
+                    } else {
+                        warn!("mavlink PARAM_SET for unknown param {param_id:?}");
+                    }
+
+                    // PX4 (and QGroundControl) expect every PARAM_SET
+                    // acknowledged with a PARAM_VALUE echo, whether or not
+                    // the param was recognized - there's no negative ack for
+                    // an unknown param in this protocol.
+                    let value = MavMessage::PARAM_VALUE(PARAM_VALUE_DATA {
+                        param_value: data.param_value,
+                        param_count: 0,
+                        param_index: 0,
+                        param_id: data.param_id,
+                        param_type: MavParamType::MAV_PARAM_TYPE_REAL32,
+                    });
+                    if let Err(error) = responses.send(&header, &value) {
+                        warn!("failed to send PARAM_VALUE over mavlink: {error}");
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => warn!("mavlink receive error: {error}"),
+            }
+        });
+    }
+
+    /// Whether PX4's last `HEARTBEAT` reported the vehicle armed. `true`
+    /// (permissive) until the first heartbeat arrives; see [`Self::armed`].
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::Relaxed)
+    }
+
+    /// Whether PX4's last `HEARTBEAT` reported the `AUTO`/`RTL` custom mode,
+    /// for [`MavlinkConfig::block_triggers_during_rtl`]. `false` until the
+    /// first heartbeat arrives, same as a freshly-booted, not-yet-flying
+    /// vehicle would report.
+    pub fn is_rtl(&self) -> bool {
+        let custom_mode = self.custom_mode.load(Ordering::Relaxed);
+        let main_mode = (custom_mode >> 16) & 0xFF;
+        let sub_mode = (custom_mode >> 24) & 0xFF;
+        main_mode == PX4_CUSTOM_MAIN_MODE_AUTO && sub_mode == PX4_CUSTOM_SUB_MODE_AUTO_RTL
+    }
+
+    /// Whether the vehicle's relative altitude has climbed above
+    /// [`MavlinkConfig::min_altitude_gate_m`] at least once since it was
+    /// last armed, for [`crate::session::Session::handle_edge`] to tell a
+    /// still-on-the-ground trigger apart from an in-flight one. Always
+    /// `true` if no altitude gate is configured.
+    pub fn takeoff_detected(&self) -> bool {
+        self.takeoff_detected.load(Ordering::Relaxed)
+    }
+
+    /// Spawns a background task that polls [`Self::is_armed`] and calls
+    /// `on_disarm` once, each time it observes the vehicle transition from
+    /// armed to disarmed - for [`MavlinkConfig::auto_stop_on_disarm`], where
+    /// waiting for a stop edge that a bench disarm may never produce isn't
+    /// good enough. Polls rather than reacting inline to `HEARTBEAT` so it
+    /// stays independent of `spawn_timesync`'s receive loop, the same
+    /// separation-of-concerns [`crate::disk_space::spawn_monitor`] uses for
+    /// its own background condition.
+    pub fn spawn_disarm_watch(&self, on_disarm: impl Fn() + Send + Sync + 'static) {
+        let armed = Arc::clone(&self.armed);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(DISARM_WATCH_INTERVAL);
+            let mut was_armed = armed.load(Ordering::Relaxed);
+            loop {
+                ticker.tick().await;
+                let is_armed = armed.load(Ordering::Relaxed);
+                if was_armed && !is_armed {
+                    on_disarm();
+                }
+                was_armed = is_armed;
+            }
+        });
+    }
+
+    /// Spawns a background task that periodically publishes
+    /// `CAMERA_CAPTURE_STATUS` (aggregated across every camera in `metrics`,
+    /// since this process registers as a single MAVLink camera component)
+    /// and one `VIDEO_STREAM_STATUS` per entry in `video_streams`, so
+    /// QGroundControl shows the onboard recorder as a live camera/stream
+    /// rather than a silent component. Runs on the blocking thread pool,
+    /// same reasoning as [`MavlinkFeedback::spawn_timesync`].
+    pub fn spawn_status(&self, metrics: Arc<Metrics>, video_streams: Vec<VideoStreamInfo>) {
+        let connection = Arc::clone(&self.connection);
+        let header = self.header;
+        tokio::task::spawn_blocking(move || loop {
+            let cameras = metrics.cameras();
+
+            let recording = cameras.iter().any(|(_, camera)| camera.is_recording());
+            let recording_time_ms = cameras
+                .iter()
+                .filter_map(|(_, camera)| camera.recording_elapsed_ms())
+                .max()
+                .unwrap_or(0);
+            let available_capacity_mib = cameras
+                .iter()
+                .filter_map(|(_, camera)| fs4::available_space(camera.output_dir()).ok())
+                .map(|bytes| bytes as f32 / (1024.0 * 1024.0))
+                .fold(f32::INFINITY, f32::min);
+            let image_count: i32 = cameras.iter().map(|(_, camera)| camera.recordings_started() as i32).sum();
+
+            let status = MavMessage::CAMERA_CAPTURE_STATUS(CAMERA_CAPTURE_STATUS_DATA {
+                time_boot_ms: (now_ns() / 1_000_000) as u32,
+                image_interval: 0.0,
+                recording_time_ms,
+                available_capacity: if available_capacity_mib.is_finite() { available_capacity_mib } else { 0.0 },
+                image_status: 0,
+                video_status: recording as u8,
+                image_count,
+            });
+            if let Err(error) = connection.send(&header, &status) {
+                warn!("failed to send CAMERA_CAPTURE_STATUS over mavlink: {error}");
+            }
+
+            for stream in &video_streams {
+                if !stream.enabled {
+                    continue;
+                }
+
+                let video_status = MavMessage::VIDEO_STREAM_STATUS(VIDEO_STREAM_STATUS_DATA {
+                    framerate: 0.0,
+                    bitrate: stream.bitrate_kbps * 1000,
+                    flags: 1, // VIDEO_STREAM_STATUS_FLAGS_RUNNING
+                    resolution_h: stream.width.unwrap_or(0) as u16,
+                    resolution_v: stream.height.unwrap_or(0) as u16,
+                    rotation: 0,
+                    hfov: 0,
+                    stream_id: stream.stream_id,
+                });
+                if let Err(error) = connection.send(&header, &video_status) {
+                    warn!("failed to send VIDEO_STREAM_STATUS over mavlink: {error}");
+                }
+            }
+
+            std::thread::sleep(STATUS_INTERVAL);
+        });
+    }
+
+    /// The `seq` field of the last `CAMERA_TRIGGER` PX4 sent us, or `None`
+    /// if none has arrived yet this run. See
+    /// [`crate::session::Session::check_missed_triggers`].
+    pub fn last_px4_trigger_sequence(&self) -> Option<u32> {
+        match self.px4_trigger_sequence.load(Ordering::Relaxed) {
+            -1 => None,
+            sequence => Some(sequence as u32),
+        }
+    }
+
+    /// Returns the latest position/attitude sample, for geotagging captures
+    /// (see [`crate::geotag`]) and for filling `CAMERA_IMAGE_CAPTURED`'s
+    /// position fields in [`MavlinkFeedback::publish_trigger`]. Zeroed until
+    /// PX4 has sent at least one `GLOBAL_POSITION_INT`/`ATTITUDE`.
+    pub fn latest_position(&self) -> Position {
+        *self.latest_position.lock().unwrap()
+    }
+
+    /// Returns the latest [`GimbalAttitude`], for geotagging stills with the
+    /// gimbal's own orientation rather than the vehicle's (see
+    /// [`crate::geotag`]) - `None` until PX4 has reported a
+    /// `GIMBAL_DEVICE_ATTITUDE_STATUS`, or forever if this airframe has no
+    /// gimbal.
+    pub fn latest_gimbal_attitude(&self) -> Option<GimbalAttitude> {
+        *self.latest_gimbal_attitude.lock().unwrap()
+    }
+
+    /// Returns a [`CaptureTelemetry`] snapshot combining the latest attitude,
+    /// IMU and gimbal samples, for [`crate::trigger_log::TriggerLog::log_event`]
+    /// to attach to a capture's row. Zeroed the same way `latest_position`
+    /// and `latest_imu` are until PX4 has reported anything; `gimbal` is
+    /// `None` the same way [`Self::latest_gimbal_attitude`] is.
+    pub fn latest_capture_telemetry(&self) -> CaptureTelemetry {
+        let position = self.latest_position();
+        let imu = *self.latest_imu.lock().unwrap();
+        let gimbal = self.latest_gimbal_attitude();
+        CaptureTelemetry { roll: position.roll, pitch: position.pitch, yaw: position.yaw, imu, gimbal }
+    }
+
+    /// Sends `CAMERA_TRIGGER` and `CAMERA_IMAGE_CAPTURED` for a shot taken
+    /// at `timestamp_ns` (the GPIO trigger edge's local hardware timestamp,
+    /// in nanoseconds), translated into the autopilot's clock via the
+    /// running `clock_offset_ns` estimate, so the autopilot log carries an
+    /// authoritative capture time and sequence number for later geotagging.
+    /// Position/attitude fields are filled from the latest sample
+    /// [`MavlinkFeedback::spawn_timesync`] has received, so they read zero
+    /// until PX4 has reported a fix.
+    pub fn publish_trigger(&self, timestamp_ns: u64) {
+        let offset_ns = self.clock_offset_ns.load(Ordering::Relaxed);
+        let time_usec = ((timestamp_ns as i64 + offset_ns).max(0) / 1_000) as u64;
+
+        let sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        if let Err(error) = write_atomically(&self.sequence_state_path, &(sequence + 1).to_string()) {
+            warn!(
+                "failed to persist mavlink image sequence counter to {}: {error}",
+                self.sequence_state_path.display()
+            );
+        }
+        let position = self.latest_position();
+
+        let trigger = MavMessage::CAMERA_TRIGGER(CAMERA_TRIGGER_DATA {
+            time_usec,
+            seq: sequence,
+        });
+        if let Err(error) = self.connection.send(&self.header, &trigger) {
+            warn!("failed to send CAMERA_TRIGGER over mavlink: {error}");
+        }
+
+        let captured = MavMessage::CAMERA_IMAGE_CAPTURED(CAMERA_IMAGE_CAPTURED_DATA {
+            time_usec,
+            time_boot_ms: 0,
+            lat: position.lat,
+            lon: position.lon,
+            alt: position.alt,
+            relative_alt: position.relative_alt,
+            q: euler_to_quaternion(position.roll, position.pitch, position.yaw),
+            image_index: sequence as i32,
+            camera_id: 0,
+            capture_result: 1,
+            file_url: Default::default(),
+        });
+        if let Err(error) = self.connection.send(&self.header, &captured) {
+            error!("failed to send CAMERA_IMAGE_CAPTURED over mavlink: {error}");
+        }
+    }
+
+    /// Sends an error-severity `STATUSTEXT` so an operator-facing problem
+    /// (e.g. disk space running out) shows up in the ground station, not
+    /// just this process's own log. `text` is truncated to fit MAVLink's
+    /// 50-byte field.
+    pub fn send_error_statustext(&self, text: &str) {
+        self.send_statustext(MavSeverity::MAV_SEVERITY_ERROR, text);
+    }
+
+    /// Sends a warning-severity `STATUSTEXT`, for an operator-facing problem
+    /// (e.g. approaching a thermal limit) that isn't yet bad enough to stop
+    /// recording. `text` is truncated to fit MAVLink's 50-byte field.
+    pub fn send_warning_statustext(&self, text: &str) {
+        self.send_statustext(MavSeverity::MAV_SEVERITY_WARNING, text);
+    }
+
+    fn send_statustext(&self, severity: MavSeverity, text: &str) {
+        let mut bytes = [0u8; 50];
+        for (slot, byte) in bytes.iter_mut().zip(text.as_bytes()) {
+            *slot = *byte;
+        }
+        let text: [char; 50] = bytes.map(|byte| byte as char);
+
+        let statustext = MavMessage::STATUSTEXT(STATUSTEXT_DATA { severity, text, id: 0, chunk_seq: 0 });
+        if let Err(error) = self.connection.send(&self.header, &statustext) {
+            warn!("failed to send STATUSTEXT over mavlink: {error}");
+        }
+    }
+}
+
+/// Converts a roll/pitch/yaw Euler triplet (radians, PX4's aerospace ZYX
+/// convention) into the `[w, x, y, z]` quaternion `CAMERA_IMAGE_CAPTURED.q`
+/// expects.
+fn euler_to_quaternion(roll: f32, pitch: f32, yaw: f32) -> [f32; 4] {
+    let (sr, cr) = (roll * 0.5).sin_cos();
+    let (sp, cp) = (pitch * 0.5).sin_cos();
+    let (sy, cy) = (yaw * 0.5).sin_cos();
+
+    [
+        cr * cp * cy + sr * sp * sy,
+        sr * cp * cy - cr * sp * sy,
+        cr * sp * cy + sr * cp * sy,
+        cr * cp * sy - sr * sp * cy,
+    ]
+}
+
+/// Converts a `[w, x, y, z]` quaternion (the `GIMBAL_DEVICE_ATTITUDE_STATUS.q`
+/// convention, matching [`euler_to_quaternion`]'s output ordering) back into
+/// a roll/pitch/yaw Euler triplet, in radians.
+fn quaternion_to_euler(q: [f32; 4]) -> (f32, f32, f32) {
+    let [w, x, y, z] = q;
+
+    let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+    let pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+    let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+
+    (roll, pitch, yaw)
+}
+
+/// Our clock, in nanoseconds since the Unix epoch, for comparison against
+/// PX4's `TIMESYNC.tc1`.
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
+/// Renders a `PARAM_SET`/`PARAM_VALUE` fixed-size `param_id` as a `&str`,
+/// trimming the trailing NUL padding a shorter name leaves behind.
+fn param_id_str(param_id: &[u8]) -> &str {
+    let len = param_id.iter().position(|&byte| byte == 0).unwrap_or(param_id.len());
+    std::str::from_utf8(&param_id[..len]).unwrap_or("")
+}
+
+/// Maps a single `PARAM_SET` onto the one [`CameraControls`] field it names,
+/// returning `None` for a `param_id` that isn't one of ours - PX4 shares this
+/// message type for its own onboard params, most of which we're not the
+/// target of.
+fn decode_param_set(param_id: &str, param_value: f32) -> Option<CameraControls> {
+    let mut controls = CameraControls::default();
+    match param_id {
+        PARAM_EXPOSURE_MICROS => controls.exposure_micros = Some(param_value as u32),
+        PARAM_GAIN => controls.gain = Some(param_value),
+        PARAM_WHITE_BALANCE_KELVIN => controls.white_balance_kelvin = Some(param_value as u32),
+        PARAM_FOCUS_POSITION => controls.focus_position = Some(param_value),
+        _ => return None,
+    }
+    Some(controls)
+}
+
+/// Maps a single `PARAM_SET` onto the one [`RegionOfInterest`] field it
+/// names, same shape (and same "not every `PARAM_SET` is ours" rationale) as
+/// [`decode_param_set`].
+fn decode_roi_param_set(param_id: &str, param_value: f32) -> Option<RegionOfInterest> {
+    let mut roi = RegionOfInterest::default();
+    match param_id {
+        PARAM_ROI_X => roi.x = Some(param_value),
+        PARAM_ROI_Y => roi.y = Some(param_value),
+        PARAM_ROI_WIDTH => roi.width = Some(param_value),
+        PARAM_ROI_HEIGHT => roi.height = Some(param_value),
+        _ => return None,
+    }
+    Some(roi)
+}
@@ -0,0 +1,654 @@
+//! A minimal Prometheus text-exposition endpoint, so an ops dashboard can
+//! scrape counters/gauges off this process during bench tests and flights
+//! without needing to tail its logs.
+//!
+//! Hand-rolled over a bare [`TcpListener`] rather than pulling in an HTTP
+//! framework: there's exactly one route to serve, so the framework's routing
+//! and middleware machinery would be pure overhead.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::recorder::BackpressureAction;
+
+/// Parameters for the metrics HTTP endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsConfig {
+    /// Whether to serve the endpoint at all. Off by default since not every
+    /// deployment has an ops dashboard scraping it.
+    pub enabled: bool,
+    /// `host:port` to listen for scrapes on.
+    pub address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self { enabled: false, address: "0.0.0.0:9273".to_string() }
+    }
+}
+
+/// Bucket boundaries (milliseconds) for [`CameraMetrics::first_frame_latency_ms`]
+/// and [`CameraMetrics::frame_interval_ms`]: fine-grained under 100ms, where
+/// scheduling jitter actually shows up, coarser above it.
+const LATENCY_BUCKETS_MS: &[u64] = &[1, 2, 5, 10, 20, 50, 100, 200, 500, 1000, 2000];
+
+/// Bucket boundaries (milliseconds) for [`Metrics::inter_trigger_interval_ms`]:
+/// triggers are seconds to minutes apart in normal operation, not
+/// milliseconds, so this covers a much wider range than the latency buckets.
+const INTERVAL_BUCKETS_MS: &[u64] = &[100, 500, 1000, 5000, 10_000, 30_000, 60_000, 300_000];
+
+/// A fixed-bucket cumulative histogram, Prometheus's own convention: bucket
+/// boundaries are baked in at construction rather than computed from
+/// observed data, so a scrape stays cheap and comparable run over run.
+pub struct Histogram {
+    bounds_ms: &'static [u64],
+    bucket_counts: Vec<AtomicU64>,
+    sum_ms: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds_ms: &'static [u64]) -> Self {
+        Self {
+            bounds_ms,
+            bucket_counts: bounds_ms.iter().map(|_| AtomicU64::new(0)).collect(),
+            sum_ms: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, value_ms: u64) {
+        for (bound, bucket) in self.bounds_ms.iter().zip(&self.bucket_counts) {
+            if value_ms <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.sum_ms.fetch_add(value_ms, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Appends this histogram's `_bucket`/`_sum`/`_count` lines to `output`,
+    /// per Prometheus's histogram exposition convention. `labels` are
+    /// already-known key/value pairs (e.g. `[("camera", name)]`); an `le`
+    /// label is appended to each bucket line on top of them.
+    fn render(&self, output: &mut String, name: &str, labels: &[(&str, &str)]) {
+        for (bound, bucket) in self.bounds_ms.iter().zip(&self.bucket_counts) {
+            output.push_str(&format!(
+                "{name}_bucket{} {}\n",
+                labelled(labels, Some(&bound.to_string())),
+                bucket.load(Ordering::Relaxed)
+            ));
+        }
+        output.push_str(&format!(
+            "{name}_bucket{} {}\n",
+            labelled(labels, Some("+Inf")),
+            self.count.load(Ordering::Relaxed)
+        ));
+        output.push_str(&format!("{name}_sum{} {}\n", labelled(labels, None), self.sum_ms.load(Ordering::Relaxed)));
+        output.push_str(&format!("{name}_count{} {}\n", labelled(labels, None), self.count.load(Ordering::Relaxed)));
+    }
+}
+
+/// Formats `labels` as Prometheus label-set syntax, appending an `le` label
+/// for histogram bucket lines when given one. Renders as `""` (matching the
+/// counters/gauges above, rather than an empty `{}`) when there's nothing to
+/// show.
+fn labelled(labels: &[(&str, &str)], le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels.iter().map(|(key, value)| format!("{key}={value:?}")).collect();
+    if let Some(le) = le {
+        parts.push(format!("le={le:?}"));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// Per-camera counters/gauges, registered once at startup (see
+/// [`Metrics::register_camera`]) and updated from each camera's transition
+/// worker in [`crate::main::run`].
+pub struct CameraMetrics {
+    output_dir: PathBuf,
+    recordings_started: AtomicU64,
+    recordings_stopped: AtomicU64,
+    recording: AtomicBool,
+    dropped_frames: AtomicU64,
+    total_recorded_ns: AtomicU64,
+    errors: AtomicU64,
+    /// Breakdown of `dropped_frames` by which [`BackpressureAction`] the
+    /// writer thread took; see [`CameraMetrics::record_backpressure_action`].
+    backpressure_dropped_newest: AtomicU64,
+    backpressure_dropped_oldest: AtomicU64,
+    backpressure_reduced_framerate: AtomicU64,
+    /// Unix epoch milliseconds when the current recording started, per the
+    /// last `record_start` call; 0 while not recording. See
+    /// [`CameraMetrics::recording_elapsed_ms`].
+    recording_started_at_ms: AtomicU64,
+    /// The file the current recording is being written to, per the last
+    /// `record_start` call; `None` while not recording. See
+    /// [`crate::status`].
+    current_file: Mutex<Option<PathBuf>>,
+    /// Trigger-to-first-frame latency, one observation per recording start
+    /// that actually produced a frame. See [`CameraMetrics::record_first_frame_latency`].
+    first_frame_latency_ms: Histogram,
+    /// Gap between consecutive frames, for spotting capture/encode jitter.
+    /// Only fed by the backends sharing [`crate::recorder::frame_writer`]
+    /// (v4l2-direct, libcamera-native, GigE Vision); see
+    /// [`CameraMetrics::record_frame`].
+    frame_interval_ms: Histogram,
+    /// Monotonic timestamp of the previous [`CameraMetrics::record_frame`]
+    /// call, nanoseconds; 0 before the first frame.
+    last_frame_monotonic_ns: AtomicU64,
+}
+
+impl CameraMetrics {
+    pub fn record_start(&self, file: Option<&Path>) {
+        self.recordings_started.fetch_add(1, Ordering::Relaxed);
+        self.recording.store(true, Ordering::Relaxed);
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        self.recording_started_at_ms.store(now_ms, Ordering::Relaxed);
+        *self.current_file.lock().unwrap() = file.map(Path::to_path_buf);
+    }
+
+    pub fn record_stop(&self) {
+        self.recordings_stopped.fetch_add(1, Ordering::Relaxed);
+        self.recording.store(false, Ordering::Relaxed);
+        self.recording_started_at_ms.store(0, Ordering::Relaxed);
+        *self.current_file.lock().unwrap() = None;
+    }
+
+    /// Whether this camera is currently recording, per the last
+    /// `record_start`/`record_stop` call.
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Milliseconds since the current recording started, for
+    /// `CAMERA_CAPTURE_STATUS.recording_time_ms` (see [`crate::mavlink`]).
+    /// `None` while not recording.
+    pub fn recording_elapsed_ms(&self) -> Option<u32> {
+        let started_at_ms = self.recording_started_at_ms.load(Ordering::Relaxed);
+        if started_at_ms == 0 {
+            return None;
+        }
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        Some(now_ms.saturating_sub(started_at_ms) as u32)
+    }
+
+    /// Records that `count` more frames were dropped or arrived late, per
+    /// [`crate::recorder::Recorder::new`]'s `on_dropped_frames` callback.
+    /// Only ever called for backends that can detect this: the GStreamer
+    /// backend via `Qos` bus messages, and the v4l2-direct and
+    /// libcamera-native backends when their writer thread falls behind.
+    pub fn record_dropped_frames(&self, count: u64) {
+        self.dropped_frames.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Records which [`BackpressureAction`] the writer thread took for a
+    /// dropped frame, per [`crate::recorder::Recorder::new`]'s
+    /// `on_backpressure_action` callback. Always paired with a
+    /// `record_dropped_frames(1)` call for the same frame, so this is a
+    /// breakdown of `dropped_frames`, not an additional count.
+    pub fn record_backpressure_action(&self, action: BackpressureAction) {
+        match action {
+            BackpressureAction::DroppedNewest => self.backpressure_dropped_newest.fetch_add(1, Ordering::Relaxed),
+            BackpressureAction::DroppedOldest => self.backpressure_dropped_oldest.fetch_add(1, Ordering::Relaxed),
+            BackpressureAction::ReducedFramerate => {
+                self.backpressure_reduced_framerate.fetch_add(1, Ordering::Relaxed)
+            }
+        };
+    }
+
+    /// Adds `duration_ns` to this camera's running total of recorded
+    /// footage, for [`crate::summary`].
+    pub fn record_duration(&self, duration_ns: u64) {
+        self.total_recorded_ns.fetch_add(duration_ns, Ordering::Relaxed);
+    }
+
+    /// Records that `on_fatal_error` fired for this camera, for
+    /// [`crate::summary`].
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a trigger-to-first-frame latency observation, per
+    /// [`crate::recorder::Recorder::new`]'s `on_first_frame` callback.
+    /// Skipped for a `gpio_timestamp_ns` of 0 (a manually-issued start, see
+    /// [`crate::session::Session::dispatch`]), since there's no real edge to
+    /// measure the latency from.
+    pub fn record_first_frame_latency(&self, gpio_timestamp_ns: u64, frame_timestamp_ns: u64) {
+        if gpio_timestamp_ns == 0 {
+            return;
+        }
+        let latency_ms = frame_timestamp_ns.saturating_sub(gpio_timestamp_ns) / 1_000_000;
+        self.first_frame_latency_ms.observe(latency_ms);
+    }
+
+    /// Records the gap since this camera's previous frame, for spotting
+    /// capture/encode jitter. Called once per frame by the backends sharing
+    /// [`crate::recorder::frame_writer`]: v4l2-direct, libcamera-native and
+    /// GigE Vision.
+    pub fn record_frame(&self) {
+        let now_ns = crate::clock::monotonic_now_ns();
+        let previous_ns = self.last_frame_monotonic_ns.swap(now_ns, Ordering::Relaxed);
+        if previous_ns != 0 {
+            self.frame_interval_ms.observe(now_ns.saturating_sub(previous_ns) / 1_000_000);
+        }
+    }
+
+    pub fn recordings_started(&self) -> u64 {
+        self.recordings_started.load(Ordering::Relaxed)
+    }
+
+    pub fn recordings_stopped(&self) -> u64 {
+        self.recordings_stopped.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames.load(Ordering::Relaxed)
+    }
+
+    pub fn backpressure_dropped_newest(&self) -> u64 {
+        self.backpressure_dropped_newest.load(Ordering::Relaxed)
+    }
+
+    pub fn backpressure_dropped_oldest(&self) -> u64 {
+        self.backpressure_dropped_oldest.load(Ordering::Relaxed)
+    }
+
+    pub fn backpressure_reduced_framerate(&self) -> u64 {
+        self.backpressure_reduced_framerate.load(Ordering::Relaxed)
+    }
+
+    pub fn total_recorded_ns(&self) -> u64 {
+        self.total_recorded_ns.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// The file the current recording is being written to, for
+    /// [`crate::status`]'s status payload. `None` while not recording.
+    pub fn current_file(&self) -> Option<PathBuf> {
+        self.current_file.lock().unwrap().clone()
+    }
+}
+
+/// Process-wide metrics registry, shared across the event loop and every
+/// camera's transition worker like `mavlink_feedback`/`trigger_log`.
+pub struct Metrics {
+    trigger_count: AtomicU64,
+    last_trigger_timestamp: AtomicU64,
+    /// Number of times our own trigger sequence has been observed to
+    /// diverge from PX4's `CAMERA_TRIGGER.seq`. See
+    /// [`crate::session::Session::check_missed_triggers`].
+    missed_triggers: AtomicU64,
+    cameras: Mutex<Vec<(String, Arc<CameraMetrics>)>>,
+    /// SoC temperature, in thousandths of a degree Celsius, per the last
+    /// [`Metrics::record_thermal`] call; 0 before the first reading. See
+    /// [`crate::thermal`].
+    soc_temp_millicelsius: AtomicI64,
+    /// Whether the Pi firmware last reported any active throttling
+    /// condition (frequency capping, undervoltage, or a hard thermal
+    /// throttle). See [`crate::thermal`].
+    throttled: AtomicBool,
+    /// Most recent recording medium wear reading, as a percentage of its
+    /// rated life used; -1 before the first reading. See
+    /// [`crate::storage_health`].
+    storage_health_percent_used: AtomicI64,
+    /// Gap between consecutive accepted trigger events, for spotting an
+    /// intervalometer or pilot cadence drifting from what was expected. See
+    /// [`Metrics::record_trigger`].
+    inter_trigger_interval_ms: Histogram,
+    /// Monotonic timestamp of the previous [`Metrics::record_trigger`] call,
+    /// nanoseconds; 0 before the first trigger this run.
+    last_trigger_monotonic_ns: AtomicU64,
+    /// The last [`RECENT_TRIGGERS_CAPACITY`] accepted trigger events, newest
+    /// last, for [`crate::control_api`]'s status dashboard. Not exposed over
+    /// Prometheus (a bounded list doesn't fit that format well); see
+    /// [`Metrics::recent_triggers`].
+    recent_triggers: Mutex<VecDeque<RecentTrigger>>,
+}
+
+/// One entry in [`Metrics::recent_triggers`].
+#[derive(Debug, Clone)]
+pub struct RecentTrigger {
+    /// Unix timestamp, seconds.
+    pub timestamp: u64,
+    /// `Debug`-formatted [`crate::trigger::Transition`], e.g. `"Start"`.
+    pub kind: String,
+}
+
+/// How many [`RecentTrigger`]s [`Metrics::record_trigger`] keeps around;
+/// enough for a field crew glancing at the dashboard to see the last few
+/// pulses without the list growing unbounded over a long flight.
+const RECENT_TRIGGERS_CAPACITY: usize = 20;
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            trigger_count: AtomicU64::new(0),
+            last_trigger_timestamp: AtomicU64::new(0),
+            missed_triggers: AtomicU64::new(0),
+            cameras: Mutex::new(Vec::new()),
+            soc_temp_millicelsius: AtomicI64::new(0),
+            throttled: AtomicBool::new(false),
+            storage_health_percent_used: AtomicI64::new(-1),
+            inter_trigger_interval_ms: Histogram::new(INTERVAL_BUCKETS_MS),
+            last_trigger_monotonic_ns: AtomicU64::new(0),
+            recent_triggers: Mutex::new(VecDeque::with_capacity(RECENT_TRIGGERS_CAPACITY)),
+        }
+    }
+
+    /// Registers a camera, returning the handle its transition worker should
+    /// update on every start/stop. `output_dir` is read at scrape time to
+    /// report free disk space, the same filesystem [`crate::disk_space`]
+    /// monitors.
+    pub fn register_camera(&self, name: String, output_dir: PathBuf) -> Arc<CameraMetrics> {
+        let metrics = Arc::new(CameraMetrics {
+            output_dir,
+            recordings_started: AtomicU64::new(0),
+            recordings_stopped: AtomicU64::new(0),
+            recording: AtomicBool::new(false),
+            dropped_frames: AtomicU64::new(0),
+            total_recorded_ns: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            backpressure_dropped_newest: AtomicU64::new(0),
+            backpressure_dropped_oldest: AtomicU64::new(0),
+            backpressure_reduced_framerate: AtomicU64::new(0),
+            recording_started_at_ms: AtomicU64::new(0),
+            current_file: Mutex::new(None),
+            first_frame_latency_ms: Histogram::new(LATENCY_BUCKETS_MS),
+            frame_interval_ms: Histogram::new(LATENCY_BUCKETS_MS),
+            last_frame_monotonic_ns: AtomicU64::new(0),
+        });
+        self.cameras.lock().unwrap().push((name, Arc::clone(&metrics)));
+        metrics
+    }
+
+    /// Total trigger events accepted so far, for callers (e.g.
+    /// [`crate::control_api`]) that want it without rendering the whole
+    /// exposition text.
+    pub fn trigger_count(&self) -> u64 {
+        self.trigger_count.load(Ordering::Relaxed)
+    }
+
+    /// Unix timestamp of the most recently accepted trigger event, for
+    /// [`crate::status`]'s status payload; 0 if none yet this run.
+    pub fn last_trigger_timestamp(&self) -> u64 {
+        self.last_trigger_timestamp.load(Ordering::Relaxed)
+    }
+
+    /// Every registered camera and its metrics handle, in registration
+    /// order.
+    pub fn cameras(&self) -> Vec<(String, Arc<CameraMetrics>)> {
+        self.cameras.lock().unwrap().clone()
+    }
+
+    /// Records that a trigger event was accepted - `kind` is the
+    /// `Debug`-formatted [`crate::trigger::Transition`] it decoded to, kept
+    /// as a plain `&str` so this module doesn't need to depend on
+    /// `crate::trigger` - and the gap since the previous one (0 before the
+    /// first trigger this run, so nothing is observed then).
+    pub fn record_trigger(&self, kind: &str) {
+        self.trigger_count.fetch_add(1, Ordering::Relaxed);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.last_trigger_timestamp.store(now, Ordering::Relaxed);
+
+        let now_ns = crate::clock::monotonic_now_ns();
+        let previous_ns = self.last_trigger_monotonic_ns.swap(now_ns, Ordering::Relaxed);
+        if previous_ns != 0 {
+            self.inter_trigger_interval_ms.observe(now_ns.saturating_sub(previous_ns) / 1_000_000);
+        }
+
+        let mut recent_triggers = self.recent_triggers.lock().unwrap();
+        if recent_triggers.len() == RECENT_TRIGGERS_CAPACITY {
+            recent_triggers.pop_front();
+        }
+        recent_triggers.push_back(RecentTrigger { timestamp: now, kind: kind.to_string() });
+    }
+
+    /// The last few accepted trigger events, oldest first, for
+    /// [`crate::control_api`]'s status dashboard.
+    pub fn recent_triggers(&self) -> Vec<RecentTrigger> {
+        self.recent_triggers.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Records that our own trigger sequence has diverged from the one PX4
+    /// reported over MAVLink, i.e. a pulse either side decoded and the other
+    /// missed. See [`crate::session::Session::check_missed_triggers`].
+    pub fn record_missed_trigger(&self) {
+        self.missed_triggers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the latest SoC temperature reading and throttling state, per
+    /// [`crate::thermal::spawn_monitor`]'s poll loop.
+    pub fn record_thermal(&self, temp_millicelsius: i64, throttled: bool) {
+        self.soc_temp_millicelsius.store(temp_millicelsius, Ordering::Relaxed);
+        self.throttled.store(throttled, Ordering::Relaxed);
+    }
+
+    /// Records the latest recording medium wear reading, per
+    /// [`crate::storage_health::spawn_monitor`]'s poll loop.
+    pub fn record_storage_health(&self, percent_used: u8) {
+        self.storage_health_percent_used.store(percent_used as i64, Ordering::Relaxed);
+    }
+
+    /// The most recent recording medium wear reading, for
+    /// [`crate::status`]'s status payload; `None` before the first reading.
+    pub fn storage_health_percent_used(&self) -> Option<u8> {
+        match self.storage_health_percent_used.load(Ordering::Relaxed) {
+            value if value < 0 => None,
+            value => Some(value as u8),
+        }
+    }
+
+    /// Renders the current state in Prometheus text exposition format.
+    fn render(&self) -> String {
+        let mut output = String::new();
+
+        output.push_str("# HELP camera_trigger_events_total Trigger events accepted this run (start, stop, and still-capture pulses combined).\n");
+        output.push_str("# TYPE camera_trigger_events_total counter\n");
+        output.push_str(&format!(
+            "camera_trigger_events_total {}\n",
+            self.trigger_count.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP camera_trigger_last_timestamp_seconds Unix timestamp of the most recently accepted trigger event, 0 if none yet this run.\n");
+        output.push_str("# TYPE camera_trigger_last_timestamp_seconds gauge\n");
+        output.push_str(&format!(
+            "camera_trigger_last_timestamp_seconds {}\n",
+            self.last_trigger_timestamp.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP camera_trigger_interval_ms Time between consecutive accepted trigger events.\n");
+        output.push_str("# TYPE camera_trigger_interval_ms histogram\n");
+        self.inter_trigger_interval_ms.render(&mut output, "camera_trigger_interval_ms", &[]);
+
+        let cameras = self.cameras.lock().unwrap();
+
+        output.push_str("# HELP camera_recordings_started_total Recordings started, per camera.\n");
+        output.push_str("# TYPE camera_recordings_started_total counter\n");
+        for (name, metrics) in cameras.iter() {
+            output.push_str(&format!(
+                "camera_recordings_started_total{{camera={name:?}}} {}\n",
+                metrics.recordings_started.load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP camera_recordings_stopped_total Recordings stopped, per camera.\n");
+        output.push_str("# TYPE camera_recordings_stopped_total counter\n");
+        for (name, metrics) in cameras.iter() {
+            output.push_str(&format!(
+                "camera_recordings_stopped_total{{camera={name:?}}} {}\n",
+                metrics.recordings_stopped.load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP camera_recording_active Whether a camera is currently recording (1) or not (0).\n");
+        output.push_str("# TYPE camera_recording_active gauge\n");
+        for (name, metrics) in cameras.iter() {
+            output.push_str(&format!(
+                "camera_recording_active{{camera={name:?}}} {}\n",
+                metrics.recording.load(Ordering::Relaxed) as u8
+            ));
+        }
+
+        output.push_str("# HELP camera_dropped_frames_total Frames reported dropped or arriving late by the recording pipeline, per camera. Only the GStreamer, v4l2-direct and libcamera-native backends can currently detect these, so this stays 0 for other backends.\n");
+        output.push_str("# TYPE camera_dropped_frames_total counter\n");
+        for (name, metrics) in cameras.iter() {
+            output.push_str(&format!(
+                "camera_dropped_frames_total{{camera={name:?}}} {}\n",
+                metrics.dropped_frames.load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP camera_backpressure_actions_total Breakdown of camera_dropped_frames_total by which backpressure_policy action the writer thread took, per camera. Only fed by backends sharing crate::recorder::frame_writer: v4l2-direct, libcamera-native and GigE Vision.\n");
+        output.push_str("# TYPE camera_backpressure_actions_total counter\n");
+        for (name, metrics) in cameras.iter() {
+            output.push_str(&format!(
+                "camera_backpressure_actions_total{{camera={name:?},action=\"dropped_newest\"}} {}\n",
+                metrics.backpressure_dropped_newest.load(Ordering::Relaxed)
+            ));
+            output.push_str(&format!(
+                "camera_backpressure_actions_total{{camera={name:?},action=\"dropped_oldest\"}} {}\n",
+                metrics.backpressure_dropped_oldest.load(Ordering::Relaxed)
+            ));
+            output.push_str(&format!(
+                "camera_backpressure_actions_total{{camera={name:?},action=\"reduced_framerate\"}} {}\n",
+                metrics.backpressure_reduced_framerate.load(Ordering::Relaxed)
+            ));
+        }
+
+        output.push_str("# HELP camera_trigger_to_first_frame_latency_ms Time from a trigger edge to that recording's first captured frame, per camera. Only recorded for edge-driven starts (not manually-issued ones, which have no edge timestamp to measure from) on backends that report a first-frame timestamp: v4l2-direct, libcamera-native and GigE Vision.\n");
+        output.push_str("# TYPE camera_trigger_to_first_frame_latency_ms histogram\n");
+        for (name, metrics) in cameras.iter() {
+            metrics.first_frame_latency_ms.render(
+                &mut output,
+                "camera_trigger_to_first_frame_latency_ms",
+                &[("camera", name)],
+            );
+        }
+
+        output.push_str("# HELP camera_frame_interval_ms Gap between consecutive captured frames, per camera, for spotting capture/encode jitter. Only fed by backends sharing crate::recorder::frame_writer: v4l2-direct, libcamera-native and GigE Vision.\n");
+        output.push_str("# TYPE camera_frame_interval_ms histogram\n");
+        for (name, metrics) in cameras.iter() {
+            metrics.frame_interval_ms.render(&mut output, "camera_frame_interval_ms", &[("camera", name)]);
+        }
+
+        output.push_str("# HELP camera_free_disk_bytes Free space on the filesystem holding each camera's output directory.\n");
+        output.push_str("# TYPE camera_free_disk_bytes gauge\n");
+        for (name, metrics) in cameras.iter() {
+            match fs4::available_space(&metrics.output_dir) {
+                Ok(available) => {
+                    output.push_str(&format!("camera_free_disk_bytes{{camera={name:?}}} {available}\n"));
+                }
+                Err(error) => {
+                    warn!("failed to check free space on {}: {error}", metrics.output_dir.display());
+                }
+            }
+        }
+
+        output.push_str("# HELP camera_missed_triggers_total Times our own trigger sequence has been observed to diverge from PX4's CAMERA_TRIGGER.seq, 0 if PX4 mavlink feedback isn't enabled or no divergence has been seen.\n");
+        output.push_str("# TYPE camera_missed_triggers_total counter\n");
+        output.push_str(&format!(
+            "camera_missed_triggers_total {}\n",
+            self.missed_triggers.load(Ordering::Relaxed)
+        ));
+
+        output.push_str("# HELP soc_temp_celsius Last SoC temperature reading, 0 before the first reading.\n");
+        output.push_str("# TYPE soc_temp_celsius gauge\n");
+        output.push_str(&format!(
+            "soc_temp_celsius {:.3}\n",
+            self.soc_temp_millicelsius.load(Ordering::Relaxed) as f64 / 1000.0
+        ));
+
+        output.push_str("# HELP soc_throttled Whether the Pi firmware last reported an active throttling condition (1) or not (0).\n");
+        output.push_str("# TYPE soc_throttled gauge\n");
+        output.push_str(&format!("soc_throttled {}\n", self.throttled.load(Ordering::Relaxed) as u8));
+
+        output.push_str("# HELP storage_health_percent_used Most recent recording medium wear reading as a percentage of its rated life, -1 before the first reading.\n");
+        output.push_str("# TYPE storage_health_percent_used gauge\n");
+        output.push_str(&format!(
+            "storage_health_percent_used {}\n",
+            self.storage_health_percent_used.load(Ordering::Relaxed)
+        ));
+
+        output
+    }
+}
+
+/// Binds `config.address` and serves `/metrics` (anything else gets a 404)
+/// until the process exits.
+pub async fn spawn_server(metrics: Arc<Metrics>, address: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|error| format!("failed to bind metrics endpoint on {address}: {error}"))?;
+
+    info!("metrics endpoint listening on {address}");
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    warn!("failed to accept metrics connection: {error}");
+                    continue;
+                }
+            };
+
+            tokio::spawn(handle_connection(socket, Arc::clone(&metrics)));
+        }
+    });
+
+    Ok(())
+}
+
+/// Reads a single request off `socket`, ignores everything but the request
+/// path, and writes back either the rendered metrics or a 404.
+async fn handle_connection(mut socket: TcpStream, metrics: Arc<Metrics>) {
+    let mut buffer = [0u8; 1024];
+    let read = match socket.read(&mut buffer).await {
+        Ok(read) => read,
+        Err(error) => {
+            warn!("failed to read metrics request: {error}");
+            return;
+        }
+    };
+
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request.lines().next().and_then(|line| line.split_whitespace().nth(1)).unwrap_or("/");
+
+    let response = if path == "/metrics" {
+        let body = metrics.render();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        let body = "not found";
+        format!(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    };
+
+    if let Err(error) = socket.write_all(response.as_bytes()).await {
+        warn!("failed to write metrics response: {error}");
+    }
+}
@@ -0,0 +1,130 @@
+//! An optional MQTT status/event publisher, so an existing onboard
+//! telemetry aggregator can fuse recorder state with other subsystems' data
+//! instead of scraping [`crate::control_api`]'s `/status`, which only
+//! answers when polled.
+
+use log::{info, warn};
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use tokio::sync::mpsc;
+
+/// Parameters for the MQTT status/event publisher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttConfig {
+    /// Whether to publish to MQTT at all. Off by default: this is a fan-out
+    /// into existing telemetry infra, not every deployment has a broker.
+    pub enabled: bool,
+    /// `host:port` of the MQTT broker.
+    pub address: String,
+    /// Prepended to every published topic, e.g. `"px4-camera-trigger"`
+    /// publishes a camera's recording state to
+    /// `px4-camera-trigger/state/<camera>`.
+    pub topic_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:1883".to_string(),
+            topic_prefix: "px4-camera-trigger".to_string(),
+        }
+    }
+}
+
+/// An event forwarded to [`spawn`]'s publisher task, one per publishable
+/// topic.
+#[derive(Debug, Clone)]
+pub enum MqttEvent {
+    /// Published to `<prefix>/state/<camera>` whenever a camera starts or
+    /// stops recording.
+    RecordingState { camera: String, recording: bool },
+    /// Published to `<prefix>/trigger` on every trigger edge or manual
+    /// start/stop/snapshot command.
+    Trigger { trigger_id: u64, kind: String },
+    /// Published to `<prefix>/error` for operator-visible errors, mirroring
+    /// what already goes to the log at `error!` level.
+    Error(String),
+}
+
+/// A handle for sending [`MqttEvent`]s to the publisher task. Cheap to
+/// clone, so every part of the program that reports state gets its own.
+#[derive(Clone)]
+pub struct MqttPublisher {
+    events: mpsc::UnboundedSender<MqttEvent>,
+}
+
+impl MqttPublisher {
+    /// A publisher that drops every event, for when `MqttConfig::enabled` is
+    /// off - callers don't need to branch on whether MQTT is configured.
+    pub fn disabled() -> Self {
+        let (events, _rx) = mpsc::unbounded_channel();
+        Self { events }
+    }
+
+    /// Fire-and-forget: the publisher task's own channel absorbs backlog if
+    /// the broker connection is briefly down, same reasoning as
+    /// [`crate::control_api::ControlCommand`]'s channel.
+    pub fn publish(&self, event: MqttEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Connects to `config.address` and spawns a background task that
+/// serializes and publishes [`MqttEvent`]s sent to the returned
+/// [`MqttPublisher`]. `rumqttc`'s event loop reconnects on its own if the
+/// broker connection drops, so there's no reconnect logic to hand-roll here
+/// - the driver task below just keeps polling it.
+pub fn spawn(config: &MqttConfig) -> Result<MqttPublisher, String> {
+    let (host, port) = config
+        .address
+        .rsplit_once(':')
+        .ok_or_else(|| format!("mqtt address {:?} is not host:port", config.address))?;
+    let port: u16 =
+        port.parse().map_err(|_| format!("mqtt address {:?} has a non-numeric port", config.address))?;
+
+    // Derived from `topic_prefix` rather than hard-coded, so two instances
+    // configured with distinct prefixes (e.g. one per payload bay, per
+    // `Cli::instance`) don't also fight over the same MQTT client id, which
+    // would have the broker repeatedly disconnect whichever connected first.
+    let mut options = MqttOptions::new(config.topic_prefix.clone(), host, port);
+    options.set_keep_alive(std::time::Duration::from_secs(5));
+
+    let (client, mut event_loop) = AsyncClient::new(options, 16);
+
+    tokio::spawn(async move {
+        loop {
+            if let Err(error) = event_loop.poll().await {
+                warn!("mqtt connection error, retrying: {error}");
+            }
+        }
+    });
+
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel::<MqttEvent>();
+    let topic_prefix = config.topic_prefix.clone();
+    tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            let (topic, payload) = render(&topic_prefix, &event);
+            if let Err(error) = client.publish(topic, QoS::AtMostOnce, false, payload).await {
+                warn!("failed to publish mqtt event: {error}");
+            }
+        }
+    });
+
+    info!("mqtt publisher connecting to {}", config.address);
+
+    Ok(MqttPublisher { events: events_tx })
+}
+
+/// Hand-rolled rather than pulling in `serde_json` for a handful of fields,
+/// same as [`crate::control_api::render_status`].
+fn render(topic_prefix: &str, event: &MqttEvent) -> (String, String) {
+    match event {
+        MqttEvent::RecordingState { camera, recording } => {
+            (format!("{topic_prefix}/state/{camera}"), format!("{{\"recording\":{recording}}}"))
+        }
+        MqttEvent::Trigger { trigger_id, kind } => {
+            (format!("{topic_prefix}/trigger"), format!("{{\"trigger_id\":{trigger_id},\"kind\":{kind:?}}}"))
+        }
+        MqttEvent::Error(message) => (format!("{topic_prefix}/error"), format!("{{\"message\":{message:?}}}")),
+    }
+}
@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+use log::warn;
+
+/// Generates structured, sortable recording paths of the form
+/// `<output_dir>/<flight_session>/<camera_id>_<utc_iso8601>_<seq>.<extension>`.
+///
+/// The sequence counter is persisted to a small file alongside the
+/// recordings so a mid-flight process restart continues numbering from
+/// where it left off instead of risking a collision with a file already
+/// written earlier in the same flight session.
+pub struct NamingScheme {
+    session_dir: PathBuf,
+    camera_id: String,
+    counter_path: PathBuf,
+    next_seq: Mutex<u64>,
+}
+
+impl NamingScheme {
+    /// Creates (if needed) `<output_dir>/<flight_session>` and picks up
+    /// wherever its persisted sequence counter left off, starting a new one
+    /// at 0 if this is the first recording of the session.
+    pub fn new(output_dir: &Path, flight_session: &str, camera_id: &str) -> Result<Self, String> {
+        let session_dir = output_dir.join(flight_session);
+        std::fs::create_dir_all(&session_dir).map_err(|error| {
+            format!("failed to create session directory {}: {error}", session_dir.display())
+        })?;
+
+        let counter_path = session_dir.join(format!(".{camera_id}.sequence"));
+        let next_seq = std::fs::read_to_string(&counter_path)
+            .ok()
+            .and_then(|contents| contents.trim().parse().ok())
+            .unwrap_or(0);
+
+        Ok(Self {
+            session_dir,
+            camera_id: camera_id.to_string(),
+            counter_path,
+            next_seq: Mutex::new(next_seq),
+        })
+    }
+
+    /// Allocates and returns the next recording path, skipping past any
+    /// name that's already on disk (e.g. the counter file was lost) rather
+    /// than silently overwriting it.
+    pub fn next_path(&self, extension: &str) -> PathBuf {
+        self.next_named(extension, "")
+    }
+
+    /// Like [`NamingScheme::next_path`], but with a `splitmuxsink`-style
+    /// `-%05d` fragment placeholder inserted before the extension, for
+    /// backends that hand `splitmuxsink` a location pattern rather than a
+    /// single concrete path.
+    pub fn next_fragment_pattern(&self, extension: &str) -> PathBuf {
+        self.next_named(extension, "-%05d")
+    }
+
+    fn next_named(&self, extension: &str, suffix: &str) -> PathBuf {
+        let mut next_seq = self.next_seq.lock().unwrap();
+
+        loop {
+            let seq = *next_seq;
+            *next_seq += 1;
+
+            if let Err(error) = write_atomically(&self.counter_path, &next_seq.to_string()) {
+                warn!(
+                    "failed to persist recording sequence counter to {}: {error}",
+                    self.counter_path.display()
+                );
+            }
+
+            let timestamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+            let path = self.session_dir.join(format!(
+                "{}_{timestamp}_{seq:06}{suffix}.{extension}",
+                self.camera_id
+            ));
+
+            if suffix.is_empty() && path.exists() {
+                warn!("recording path {} already exists, skipping sequence number", path.display());
+                continue;
+            }
+            return path;
+        }
+    }
+}
+
+/// Writes `contents` to `path` via a temp file plus rename, so a power loss
+/// mid-write can't leave a truncated or empty counter file that would replay
+/// an already-used sequence number on the next start.
+pub(crate) fn write_atomically(path: &Path, contents: &str) -> std::io::Result<()> {
+    let tmp_path = path.with_file_name(format!(
+        "{}.tmp",
+        path.file_name().and_then(|name| name.to_str()).unwrap_or("counter")
+    ));
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path)
+}
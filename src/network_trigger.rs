@@ -0,0 +1,154 @@
+//! A small UDP control channel for driving the recorder without any
+//! physical wiring at all - HIL simulations and indoor bench tests have
+//! neither a GPIO trigger line nor a MAVLink link to piggyback on, but still
+//! need some way to start/stop recording and snap a still.
+//!
+//! Each packet is a single command byte followed by a shared secret, so a
+//! stray or malicious packet on the same network segment can't trigger a
+//! recording without knowing it; there's no confidentiality or replay
+//! protection beyond that, which is fine for a trusted link between the
+//! mission computer and this process, same trust model as
+//! [`crate::control_api`]'s unauthenticated HTTP endpoint.
+//!
+//! Distinct from [`crate::trigger_source::TriggerSource`]: a packet already
+//! names the exact action the sender wants, unlike a GPIO pulse whose
+//! meaning depends on [`crate::trigger::TriggerStateMachine`]'s current
+//! state, so this forwards straight onto the same [`ControlCommand`]
+//! channel [`crate::control_api`] and MAVLink's `MAV_CMD_VIDEO_START_CAPTURE`/
+//! `_STOP_CAPTURE` handling use, rather than synthesizing edges for the
+//! state machine to reinterpret.
+
+use log::{info, warn};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+use crate::control_api::ControlCommand;
+
+/// Longest packet this module will read. Comfortably above any shared
+/// secret this deployment is likely to configure; a longer packet is simply
+/// truncated by `recv_from` and will fail to authenticate.
+const MAX_PACKET_LEN: usize = 256;
+
+/// Command byte values recognized in a trigger packet's first byte.
+const COMMAND_START: u8 = 1;
+const COMMAND_STOP: u8 = 2;
+const COMMAND_PHOTO: u8 = 3;
+
+/// Whether/how to listen for authenticated UDP start/stop/photo packets.
+/// Off by default: the GPIO trigger line is the primary control path, this
+/// is a fallback for rigs with no wiring at all, same reasoning as
+/// [`crate::control_api::ControlApiConfig`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkTriggerConfig {
+    pub enabled: bool,
+    /// `host:port` to listen for trigger packets on.
+    pub address: String,
+    /// Shared secret every packet must carry after its command byte.
+    /// Required if `enabled` is set - see [`crate::main::run`]'s startup
+    /// check.
+    pub shared_secret: String,
+}
+
+impl Default for NetworkTriggerConfig {
+    fn default() -> Self {
+        Self { enabled: false, address: "0.0.0.0:14555".to_string(), shared_secret: String::new() }
+    }
+}
+
+/// Binds `config.address` and forwards each authenticated packet as a
+/// [`ControlCommand`] to `commands`, for [`crate::main::run`]'s event loop
+/// to act on alongside real GPIO edges, until the process exits.
+pub async fn spawn_server(config: &NetworkTriggerConfig, commands: mpsc::UnboundedSender<ControlCommand>) -> Result<(), String> {
+    let socket = UdpSocket::bind(&config.address)
+        .await
+        .map_err(|error| format!("failed to bind network trigger socket on {}: {error}", config.address))?;
+
+    info!("network trigger listening on {}", config.address);
+
+    let shared_secret = config.shared_secret.clone().into_bytes();
+    tokio::spawn(async move {
+        let mut buffer = [0u8; MAX_PACKET_LEN];
+        loop {
+            let (length, from) = match socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(error) => {
+                    warn!("failed to read network trigger packet: {error}");
+                    continue;
+                }
+            };
+
+            match decode_packet(&buffer[..length], &shared_secret) {
+                Some(command) => {
+                    if commands.send(command).is_err() {
+                        warn!("network trigger packet received from {from}, but the event loop has shut down");
+                    }
+                }
+                None => warn!("dropping unauthenticated or malformed network trigger packet from {from}"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Decodes a single command byte followed by `shared_secret`, comparing the
+/// secret in constant time so a timing side channel doesn't leak how many
+/// leading bytes a guessed secret got right. Returns `None` for a packet
+/// that's too short, carries an unrecognized command byte, or whose secret
+/// doesn't match.
+fn decode_packet(packet: &[u8], shared_secret: &[u8]) -> Option<ControlCommand> {
+    let (&command, secret) = packet.split_first()?;
+    if secret.len() != shared_secret.len() || !constant_time_eq(secret, shared_secret) {
+        return None;
+    }
+
+    match command {
+        COMMAND_START => Some(ControlCommand::Start),
+        COMMAND_STOP => Some(ControlCommand::Stop),
+        COMMAND_PHOTO => Some(ControlCommand::Snapshot),
+        _ => None,
+    }
+}
+
+/// Compares two equal-length byte slices without short-circuiting on the
+/// first mismatch, so comparison time doesn't depend on how much of a
+/// guessed secret is correct.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.iter().zip(b).fold(0u8, |acc, (&x, &y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_secret_decodes_the_command() {
+        let secret = b"topsecret";
+        let mut packet = vec![COMMAND_START];
+        packet.extend_from_slice(secret);
+
+        assert!(matches!(decode_packet(&packet, secret), Some(ControlCommand::Start)));
+    }
+
+    #[test]
+    fn wrong_secret_is_rejected() {
+        let mut packet = vec![COMMAND_START];
+        packet.extend_from_slice(b"wrongsecret");
+
+        assert!(decode_packet(&packet, b"topsecret").is_none());
+    }
+
+    #[test]
+    fn unrecognized_command_byte_is_rejected() {
+        let secret = b"topsecret";
+        let mut packet = vec![0xFF];
+        packet.extend_from_slice(secret);
+
+        assert!(decode_packet(&packet, secret).is_none());
+    }
+
+    #[test]
+    fn empty_packet_is_rejected() {
+        assert!(decode_packet(&[], b"topsecret").is_none());
+    }
+}
@@ -0,0 +1,220 @@
+//! Post-flight offload of a completed session's files to a ground station
+//! over the WiFi link, so a transfer can start the moment the aircraft
+//! disarms within range instead of waiting for someone to plug in a drive
+//! after landing.
+//!
+//! Transfers run via `rsync` over `ssh`: its `--partial` (keep a
+//! partially-transferred file instead of deleting it) and default
+//! incremental-resume behavior already cover "the link dropped mid-way
+//! through and came back later" far better than anything hand-rolled here
+//! would, and `--bwlimit` covers not starving whatever else is sharing the
+//! link. Once a file's transfer succeeds, it's additionally re-hashed on
+//! the ground host and compared against
+//! [`crate::manifest::Manifest::recorded_files`] - rsync's own checksum
+//! only proves the bytes it copied match what it read off the SD card, not
+//! that what it read was ever what the manifest recorded as a genuinely
+//! finalized recording.
+//!
+//! Triggered either by [`crate::control_api::ControlCommand::Offload`] or
+//! automatically on vehicle disarm, the same
+//! [`crate::mavlink::MavlinkFeedback::spawn_disarm_watch`] wiring
+//! [`crate::session`] uses for `auto_stop_on_disarm`.
+//!
+//! Optionally deletes each recording's local copy once its transfer is
+//! verified (see [`OffloadConfig::delete_after_verified`]), so the onboard
+//! SSD self-manages between flights without a human pulling a card.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use tokio::sync::mpsc;
+
+use crate::manifest::Manifest;
+
+/// Parameters for post-flight offload to a ground station.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffloadConfig {
+    /// Off by default: offloading needs a ground host to actually be
+    /// reachable, which isn't true of every deployment.
+    pub enabled: bool,
+    /// `user@host` (or bare `host`, for the ssh config's default user)
+    /// rsync/ssh connect to. Offload is skipped with a warning if unset
+    /// while `enabled` is on.
+    pub ground_host: Option<String>,
+    /// Destination directory on `ground_host`. Defaults to the ground
+    /// user's home directory (rsync/ssh's own default) if unset.
+    pub remote_dir: Option<PathBuf>,
+    /// `ssh -i` private key for connecting to `ground_host`. Uses the
+    /// caller's default identity (`~/.ssh/id_*`) if unset.
+    pub ssh_key_path: Option<PathBuf>,
+    /// `rsync --bwlimit` in KB/s, so offload doesn't starve a MAVLink link
+    /// sharing the same radio. Unlimited if unset.
+    pub bandwidth_limit_kbps: Option<u32>,
+    /// Delete a recording's local copy once its post-transfer checksum has
+    /// been verified against the manifest, so the onboard SSD self-manages
+    /// between flights. Off by default: silently losing a copy of a flight's
+    /// footage because a config file was wrong is worse than running out of
+    /// disk, and [`crate::disk_space`] already warns/reacts to that case.
+    /// A file that fails verification is always kept regardless of this
+    /// setting.
+    pub delete_after_verified: bool,
+}
+
+impl Default for OffloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ground_host: None,
+            remote_dir: None,
+            ssh_key_path: None,
+            bandwidth_limit_kbps: None,
+            delete_after_verified: false,
+        }
+    }
+}
+
+/// Sends offload requests to [`spawn`]'s background task.
+#[derive(Clone)]
+pub struct OffloadHandle {
+    requests: mpsc::UnboundedSender<()>,
+}
+
+impl OffloadHandle {
+    /// Requests an offload run. Fire-and-forget: multiple requests while one
+    /// is already in flight just mean the next run starts as soon as the
+    /// current one finishes, rather than queuing up redundant repeats.
+    pub fn trigger(&self) {
+        let _ = self.requests.send(());
+    }
+}
+
+/// Spawns the background task that serializes offload runs (so a disarm and
+/// a manually-issued `/offload` request racing each other never run two
+/// `rsync` invocations against the same files at once), and returns a
+/// handle to request one.
+pub fn spawn(config: OffloadConfig, session_dir: PathBuf, manifest: Arc<Manifest>) -> OffloadHandle {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            // Coalesce any requests that arrived while this run was already
+            // queued, so a burst of `trigger()` calls does one run, not one
+            // per call.
+            while rx.try_recv().is_ok() {}
+
+            let config = config.clone();
+            let session_dir = session_dir.clone();
+            let manifest = Arc::clone(&manifest);
+            let result = tokio::task::spawn_blocking(move || run_offload(&config, &session_dir, &manifest)).await;
+            match result {
+                Ok(Ok(())) => info!("offload of {} completed", session_dir.display()),
+                Ok(Err(error)) => error!("offload of {} failed: {error}", session_dir.display()),
+                Err(error) => error!("offload task panicked: {error}"),
+            }
+        }
+    });
+
+    OffloadHandle { requests: tx }
+}
+
+/// Runs one offload attempt: rsyncs `session_dir` to the ground host, then
+/// verifies every finalized recording's checksum against `manifest`.
+fn run_offload(config: &OffloadConfig, session_dir: &Path, manifest: &Manifest) -> Result<(), String> {
+    let Some(ground_host) = &config.ground_host else {
+        return Err("offload is enabled but no ground-offload-host is configured".to_string());
+    };
+
+    let remote_prefix = match &config.remote_dir {
+        Some(remote_dir) => format!("{ground_host}:{}/", remote_dir.display()),
+        None => format!("{ground_host}:"),
+    };
+
+    let mut rsync = Command::new("rsync");
+    rsync.arg("--archive").arg("--partial").arg("--compress");
+    if let Some(bandwidth_limit_kbps) = config.bandwidth_limit_kbps {
+        rsync.arg(format!("--bwlimit={bandwidth_limit_kbps}"));
+    }
+    if let Some(ssh_key_path) = &config.ssh_key_path {
+        rsync.arg("-e").arg(format!("ssh -i {}", ssh_key_path.display()));
+    }
+    rsync.arg(format!("{}/", session_dir.display())).arg(&remote_prefix);
+
+    info!("offloading {} to {remote_prefix}", session_dir.display());
+    let output = rsync.output().map_err(|error| format!("failed to run rsync: {error}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "rsync to {remote_prefix} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let recorded_files = manifest.recorded_files();
+    if recorded_files.is_empty() {
+        warn!("offload: no finalized recordings to verify in {}", session_dir.display());
+        return Ok(());
+    }
+
+    let mut verified = 0;
+    let mut mismatched = 0;
+    for (file, expected_sha256) in &recorded_files {
+        let Some(file_name) = file.file_name() else { continue };
+        let remote_path = match &config.remote_dir {
+            Some(remote_dir) => remote_dir.join(file_name),
+            None => PathBuf::from(file_name),
+        };
+
+        match remote_sha256(config, ground_host, &remote_path) {
+            Ok(actual_sha256) if &actual_sha256 == expected_sha256 => {
+                verified += 1;
+                if config.delete_after_verified {
+                    if let Err(error) = std::fs::remove_file(file) {
+                        warn!("offload: verified {} but failed to delete local copy: {error}", file.display());
+                    }
+                }
+            }
+            Ok(actual_sha256) => {
+                mismatched += 1;
+                warn!(
+                    "offload: {} sha256 mismatch after transfer (expected {expected_sha256}, ground host reports \
+                     {actual_sha256})",
+                    file.display()
+                );
+            }
+            Err(error) => {
+                mismatched += 1;
+                warn!("offload: failed to verify {} on ground host: {error}", file.display());
+            }
+        }
+    }
+
+    if mismatched > 0 {
+        return Err(format!("{mismatched} of {} files failed post-transfer verification", recorded_files.len()));
+    }
+
+    info!("offload: verified {verified} files against the session manifest");
+    Ok(())
+}
+
+/// Runs `sha256sum` over `ssh` for one already-transferred file, returning
+/// just its hex digest.
+fn remote_sha256(config: &OffloadConfig, ground_host: &str, remote_path: &Path) -> Result<String, String> {
+    let mut ssh = Command::new("ssh");
+    if let Some(ssh_key_path) = &config.ssh_key_path {
+        ssh.arg("-i").arg(ssh_key_path);
+    }
+    ssh.arg(ground_host).arg("sha256sum").arg(remote_path);
+
+    let output = ssh.output().map_err(|error| format!("failed to run ssh: {error}"))?;
+    if !output.status.success() {
+        return Err(format!("ssh exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| "sha256sum produced no output".to_string())
+}
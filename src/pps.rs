@@ -0,0 +1,198 @@
+//! Disciplines [`crate::clock::RealtimeClock`]'s monotonic-to-realtime
+//! offset against a GPS module's pulse-per-second output, for sub-
+//! millisecond phase accuracy independent of whether NTP has converged (or
+//! is even reachable) out in the field.
+//!
+//! A PPS pulse only marks *when* a second boundary occurred, not *which*
+//! second it is, so [`crate::clock::RealtimeClock::discipline_pps`] only
+//! ever nudges the offset's sub-second phase - it trusts the whole-second
+//! value the offset already carries (from the system clock, however that
+//! got set) and ignores a pulse that would imply that value itself is
+//! wrong, since PPS alone can't fix that.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::StreamExt;
+use gpio_cdev::{AsyncLineEventHandle, Chip, EventRequestFlags, LineRequestFlags};
+use log::{error, info, warn};
+
+use crate::clock::RealtimeClock;
+
+/// How often a sysfs [`PpsSource::Device`] is polled for a new pulse.
+/// Polling (rather than kernel-timestamped edges, like [`PpsSource::Gpio`]
+/// gets for free) means this source's accuracy is bounded by how promptly
+/// the poll happens to land after the actual pulse.
+const SYSFS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where PPS pulses are read from. See [`crate::config::PpsConfig`] for how
+/// this is configured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PpsSource {
+    /// A dedicated GPIO line pulsed once a second by the GPS module,
+    /// decoded the same way as [`crate::aux_lines`]'s extra lines - kernel-
+    /// timestamped at the interrupt, so (like the primary trigger line)
+    /// accurate well under a millisecond.
+    Gpio { gpiochip: PathBuf, line_offset: u32 },
+    /// A kernel LinuxPPS source's sysfs report, e.g.
+    /// `/sys/class/pps/pps0/assert`, for boards wiring the GPS module's PPS
+    /// pin to a dedicated PPS-capable input instead of a general-purpose
+    /// GPIO.
+    Device(PathBuf),
+}
+
+/// Whether/how to discipline [`crate::clock::RealtimeClock`] against a GPS
+/// PPS signal. Off by default since not every deployment wires one up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PpsConfig {
+    pub enabled: bool,
+    /// GPIO chip carrying the PPS line, if using [`PpsSource::Gpio`]. Only
+    /// used if `line_offset` is also set.
+    pub gpiochip: Option<PathBuf>,
+    /// Line offset of the PPS signal on `gpiochip`. Only used if `gpiochip`
+    /// is also set.
+    pub line_offset: Option<u32>,
+    /// A LinuxPPS sysfs assert file, if using [`PpsSource::Device`] instead.
+    /// Only used if `gpiochip`/`line_offset` aren't both set.
+    pub device: Option<PathBuf>,
+}
+
+impl Default for PpsConfig {
+    fn default() -> Self {
+        Self { enabled: false, gpiochip: None, line_offset: None, device: None }
+    }
+}
+
+impl PpsConfig {
+    /// Resolves `self` to a concrete [`PpsSource`], preferring the GPIO
+    /// method (see its docs for why) when both are configured. Only
+    /// meaningful when `self.enabled`; callers should check that first.
+    pub fn source(&self) -> Result<PpsSource, String> {
+        match (&self.gpiochip, self.line_offset, &self.device) {
+            (Some(gpiochip), Some(line_offset), _) => {
+                Ok(PpsSource::Gpio { gpiochip: gpiochip.clone(), line_offset })
+            }
+            (_, _, Some(device)) => Ok(PpsSource::Device(device.clone())),
+            _ => Err(
+                "pps is enabled but neither pps-gpiochip/pps-line-offset nor pps-device is configured".to_string(),
+            ),
+        }
+    }
+}
+
+/// Spawns a background task that watches `source` for pulses and disciplines
+/// `clock` against each one. Runs for the process's lifetime; a read/decode
+/// error is logged and retried rather than treated as fatal, since a
+/// temporarily glitchy GPS fix shouldn't take capture down.
+pub fn spawn(source: PpsSource, clock: Arc<RealtimeClock>) {
+    match source {
+        PpsSource::Gpio { gpiochip, line_offset } => spawn_gpio(gpiochip, line_offset, clock),
+        PpsSource::Device(path) => spawn_sysfs(path, clock),
+    }
+}
+
+fn spawn_gpio(gpiochip: PathBuf, line_offset: u32, clock: Arc<RealtimeClock>) {
+    tokio::spawn(async move {
+        let mut chip = match Chip::new(&gpiochip) {
+            Ok(chip) => chip,
+            Err(error) => {
+                error!("pps gpio chip {} is not accessible: {error}", gpiochip.display());
+                return;
+            }
+        };
+
+        let events = match chip
+            .get_line(line_offset)
+            .map_err(|error| format!("line {line_offset} does not exist on {}: {error}", gpiochip.display()))
+            .and_then(|line| {
+                line.events(LineRequestFlags::INPUT, EventRequestFlags::RISING_EDGE, "px4-camera-trigger-pps")
+                    .map_err(|error| format!("line {line_offset} on {} is already in use: {error}", gpiochip.display()))
+            }) {
+            Ok(events) => events,
+            Err(error) => {
+                error!("failed to watch pps line: {error}");
+                return;
+            }
+        };
+
+        let mut events = match AsyncLineEventHandle::new(events) {
+            Ok(events) => events,
+            Err(error) => {
+                error!("failed to watch pps line {line_offset} on {}: {error}", gpiochip.display());
+                return;
+            }
+        };
+
+        info!("disciplining clock from pps pulses on line {line_offset} of {}", gpiochip.display());
+
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(event) => clock.discipline_pps(event.timestamp()),
+                Err(error) => warn!("pps line read error: {error}"),
+            }
+        }
+
+        warn!("pps line event stream ended; clock will no longer be disciplined by pps");
+    });
+}
+
+fn spawn_sysfs(path: PathBuf, clock: Arc<RealtimeClock>) {
+    tokio::spawn(async move {
+        info!("disciplining clock from pps pulses reported at {}", path.display());
+
+        let mut last_sequence = None;
+        let mut ticker = tokio::time::interval(SYSFS_POLL_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            let assertion = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents,
+                Err(error) => {
+                    warn!("failed to read pps device {}: {error}", path.display());
+                    continue;
+                }
+            };
+
+            match parse_sysfs_assert(&assertion) {
+                Some((timestamp_ns, sequence)) if last_sequence != Some(sequence) => {
+                    last_sequence = Some(sequence);
+                    clock.discipline_pps(timestamp_ns);
+                }
+                Some(_) => {} // same pulse as last poll; nothing new to discipline against
+                None => warn!("failed to parse pps device {} contents {assertion:?}", path.display()),
+            }
+        }
+    });
+}
+
+/// Parses a LinuxPPS sysfs `assert`/`clear` file's `<seconds>.<nanoseconds>#<sequence>`
+/// format, returning the timestamp in nanoseconds and the sequence number
+/// (which increments once per pulse, so callers can tell a fresh read from
+/// one that just re-reports the last pulse).
+fn parse_sysfs_assert(contents: &str) -> Option<(u64, u64)> {
+    let (timestamp, sequence) = contents.trim().split_once('#')?;
+    let (seconds, nanoseconds) = timestamp.split_once('.')?;
+    let seconds: u64 = seconds.parse().ok()?;
+    let nanoseconds: u64 = nanoseconds.parse().ok()?;
+    let sequence: u64 = sequence.parse().ok()?;
+
+    Some((seconds * 1_000_000_000 + nanoseconds, sequence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sysfs_assert_format() {
+        assert_eq!(parse_sysfs_assert("1699999999.123456789#42\n"), Some((1_699_999_999_123_456_789, 42)));
+    }
+
+    #[test]
+    fn rejects_malformed_sysfs_assert() {
+        assert_eq!(parse_sysfs_assert("garbage"), None);
+        assert_eq!(parse_sysfs_assert(""), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Drops root privileges and Linux capabilities after startup has finished
+//! acquiring every resource that actually needs them (the gpiochip line
+//! request, camera device opens, output directory creation), so a later
+//! compromise of a network-facing surface (`crate::control_api`,
+//! `crate::grpc_api`, `crate::dbus_api`) can't reach the rest of the system
+//! with root's access. See [`drop_privileges`].
+
+use std::ffi::CString;
+
+use caps::CapSet;
+use log::{info, warn};
+
+/// Parameters for [`drop_privileges`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrivsepConfig {
+    /// Whether to drop privileges at all. Off by default: it requires
+    /// running as root to begin with and a correctly provisioned
+    /// unprivileged user, so enabling it blind would just break device
+    /// access after the drop instead of tightening anything.
+    pub enabled: bool,
+    /// Unprivileged user to switch to once startup is done, e.g.
+    /// `"px4-camera-trigger"`. Its primary and supplementary groups
+    /// (`video`/`gpio`/`dialout`, or whatever this deployment's udev rules
+    /// grant device access to) must already cover every device opened
+    /// above - an fd stays open across the switch, but anything re-opened
+    /// later (e.g. [`crate::supervisor::LineSupervisor`] recovering a
+    /// dropped trigger line) is re-checked against the new, unprivileged
+    /// credentials.
+    pub user: Option<String>,
+}
+
+impl Default for PrivsepConfig {
+    fn default() -> Self {
+        Self { enabled: false, user: None }
+    }
+}
+
+/// Switches this process to `config.user` and clears every capability set
+/// (effective, permitted, inheritable, ambient), so nothing - not even a
+/// later `execve` - can recover root's access afterwards. A no-op if
+/// `config.enabled` is false.
+///
+/// Must be called after every resource requiring root (gpiochip line
+/// requests, camera device opens, output directory creation) has already
+/// happened, and before any network-facing listener starts accepting
+/// connections from outside this process. Exits the process on failure
+/// rather than returning a `Result`, since continuing to run as root after a
+/// failed drop attempt would defeat the entire point of calling this.
+pub fn drop_privileges(config: &PrivsepConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    let Some(user) = &config.user else {
+        crate::exit_code::ExitReason::Config.exit("privsep is enabled but no user is configured");
+    };
+
+    let (uid, gid) = match lookup_user(user) {
+        Ok(ids) => ids,
+        Err(error) => crate::exit_code::ExitReason::Config.exit(&format!("privsep: {error}")),
+    };
+    let name = CString::new(user.as_str()).unwrap_or_else(|_| {
+        crate::exit_code::ExitReason::Config.exit(&format!("privsep: user name {user:?} contains a nul byte"))
+    });
+
+    // Populates this process's supplementary groups from `user`'s own
+    // `/etc/group` memberships, replacing whatever root's happened to be, so
+    // none of root's group memberships leak through the switch.
+    if unsafe { libc::initgroups(name.as_ptr(), gid) } != 0 {
+        crate::exit_code::ExitReason::Config
+            .exit(&format!("privsep: initgroups({user:?}) failed: {}", std::io::Error::last_os_error()));
+    }
+
+    // Group before user: dropping the user first would leave this process
+    // without permission to change its own group.
+    if unsafe { libc::setgid(gid) } != 0 {
+        crate::exit_code::ExitReason::Config
+            .exit(&format!("privsep: setgid({gid}) failed: {}", std::io::Error::last_os_error()));
+    }
+    if unsafe { libc::setuid(uid) } != 0 {
+        crate::exit_code::ExitReason::Config
+            .exit(&format!("privsep: setuid({uid}) failed: {}", std::io::Error::last_os_error()));
+    }
+
+    for cap_set in [CapSet::Effective, CapSet::Permitted, CapSet::Inheritable, CapSet::Ambient] {
+        if let Err(error) = caps::clear(None, cap_set) {
+            warn!("privsep: failed to clear {cap_set:?} capabilities: {error}");
+        }
+    }
+
+    info!("privsep: dropped root, now running as {user:?} (uid={uid}, gid={gid})");
+}
+
+/// Looks up `user`'s uid/primary gid via `getpwnam`, since this crate has no
+/// other reason to depend on a full `users`/`nix` crate for a single lookup.
+fn lookup_user(user: &str) -> Result<(u32, u32), String> {
+    let name = CString::new(user).map_err(|_| format!("user name {user:?} contains a nul byte"))?;
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+    if passwd.is_null() {
+        return Err(format!("no such user {user:?}"));
+    }
+    let passwd = unsafe { &*passwd };
+    Ok((passwd.pw_uid, passwd.pw_gid))
+}
@@ -0,0 +1,325 @@
+//! A GenICam/GigE Vision capture backend using the `aravis` crate's bindings
+//! to libaravis, for industrial machine-vision cameras (our inspection
+//! payload's global-shutter GigE camera) that speak GenICam rather than
+//! UVC/V4L2 or a CSI sensor's libcamera pipeline.
+//!
+//! Unlike [`super::v4l2_backend`] and [`super::libcamera_native_backend`],
+//! this backend configures the camera for external hardware triggering
+//! (`TriggerMode`/`TriggerSource`/`TriggerSelector`) rather than free-running
+//! capture: the sensor exposes each frame off the same physical trigger line
+//! the flight controller's GPIO pulse also drives, so frame timing isn't at
+//! the mercy of however long it takes our own `start()` call to reach the
+//! camera over the network. `start`/`stop` only arm/disarm *writing* those
+//! already hardware-triggered frames to disk - same
+//! continuously-capture-but-conditionally-write architecture as
+//! [`super::v4l2_backend`], sharing its [`super::frame_writer`] writer
+//! thread.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use aravis::{BufferExt, CameraExt, CameraExtManual, DeviceExt, StreamExt};
+use log::{error, warn};
+
+use super::frame_writer::{self, FrameWriterHandle, RecordingState};
+use super::RecorderConfig;
+
+/// The GenICam trigger line most GigE Vision cameras expose as their default
+/// opto-isolated hardware trigger input. Hardcoded rather than added as a
+/// config knob: every deployment of this backend so far wires the same
+/// line, and `TriggerSource` is one of dozens of GenICam features a future
+/// request can expose if a payload actually needs a different one.
+const TRIGGER_SOURCE: &str = "Line1";
+
+/// Drives a GenICam-compliant GigE Vision camera through `libaravis`. See the
+/// module docs for the hardware-trigger/continuous-capture architecture.
+pub struct AravisRecorder {
+    output_dir: PathBuf,
+    secondary_output_dir: Option<PathBuf>,
+    encryption_recipient: Option<String>,
+    file_pattern: String,
+    next_trigger_id: AtomicU64,
+    recording: Arc<Mutex<Option<RecordingState>>>,
+    /// Hands captured frames off to [`frame_writer`]'s dedicated writer
+    /// thread instead of writing them to disk from the capture thread.
+    writer: FrameWriterHandle,
+    /// Whether a recording is currently armed; checked by the capture thread
+    /// so frames are read (and buffers requeued to the stream) continuously
+    /// but only written to disk while armed.
+    armed: Arc<AtomicBool>,
+    /// Set on every `start()`, cleared by the capture thread once it has
+    /// reported the first frame of the new recording via `on_first_frame`,
+    /// so later frames don't re-report it.
+    first_frame_pending: Arc<AtomicBool>,
+}
+
+impl AravisRecorder {
+    /// Opens the camera named by `config.source_device` (an Aravis device ID
+    /// or IP address; an empty string autodetects whichever single GigE
+    /// Vision camera is on the network), configures it for external hardware
+    /// triggering on [`TRIGGER_SOURCE`], and starts the background capture
+    /// thread. `on_fatal_error`/`on_dropped_frames`/`on_first_frame` mirror
+    /// [`super::v4l2_backend::V4l2Recorder::new`], as does `on_frame` and
+    /// `on_backpressure_action`.
+    pub fn new(
+        config: &RecorderConfig,
+        on_fatal_error: impl Fn() + Send + Sync + 'static,
+        on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+        on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+        on_frame: impl Fn() + Send + Sync + 'static,
+        on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+        realtime_clock: Arc<crate::clock::RealtimeClock>,
+    ) -> Result<Self, String> {
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+        if let Some(secondary_output_dir) = &config.secondary_output_dir {
+            if let Err(error) = std::fs::create_dir_all(secondary_output_dir) {
+                warn!(
+                    "failed to create redundant recording output directory {}: {error}; continuing without it",
+                    secondary_output_dir.display()
+                );
+            }
+        }
+
+        let device_id = config.source_device.to_string_lossy();
+        let camera = aravis::Camera::new(if device_id.is_empty() { None } else { Some(device_id.as_ref()) })
+            .map_err(|error| format!("failed to open GenICam camera {device_id:?}: {error}"))?;
+
+        configure_hardware_trigger(&camera)?;
+
+        let stream = camera
+            .create_stream(None::<fn(_, _)>, None)
+            .map_err(|error| format!("failed to create GenICam acquisition stream: {error}"))?;
+
+        let payload_size =
+            camera.payload().map_err(|error| format!("failed to query GenICam payload size: {error}"))?;
+        for _ in 0..4 {
+            stream.push_buffer(&aravis::Buffer::new_allocate(payload_size as usize));
+        }
+
+        camera
+            .start_acquisition()
+            .map_err(|error| format!("failed to start GenICam acquisition: {error}"))?;
+
+        let recording = Arc::new(Mutex::new(None));
+        let armed = Arc::new(AtomicBool::new(false));
+        let first_frame_pending = Arc::new(AtomicBool::new(false));
+        let writer = frame_writer::spawn(
+            config.write_queue_depth,
+            config.backpressure_policy,
+            Arc::clone(&recording),
+            on_frame,
+            realtime_clock,
+        );
+
+        spawn_capture_loop(
+            camera,
+            stream,
+            Arc::clone(&armed),
+            Arc::clone(&first_frame_pending),
+            writer.clone(),
+            on_fatal_error,
+            on_dropped_frames,
+            on_first_frame,
+            on_backpressure_action,
+        );
+
+        Ok(Self {
+            output_dir: config.output_dir.clone(),
+            secondary_output_dir: config.secondary_output_dir.clone(),
+            encryption_recipient: config.encryption_recipient.clone(),
+            file_pattern: config.file_pattern.clone(),
+            next_trigger_id: AtomicU64::new(0),
+            recording,
+            writer,
+            armed,
+            first_frame_pending,
+        })
+    }
+
+    /// Opens fresh output files (the recording itself, and its timestamp
+    /// sidecar) and arms the capture thread to start writing hardware-
+    /// triggered frames into them. Returns the location of the recording
+    /// file.
+    pub fn start(&self) -> String {
+        let trigger_id = self.next_trigger_id.fetch_add(1, Ordering::Relaxed);
+        let location = self.output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string()));
+        let secondary_location = self
+            .secondary_output_dir
+            .as_ref()
+            .map(|secondary_output_dir| secondary_output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string())));
+
+        match RecordingState::open(&location, secondary_location.as_deref(), self.encryption_recipient.as_deref()) {
+            Ok(state) => {
+                *self.recording.lock().unwrap() = Some(state);
+                self.first_frame_pending.store(true, Ordering::Relaxed);
+                self.armed.store(true, Ordering::Relaxed);
+            }
+            Err(error) => error!("failed to start aravis recording at {}: {error}", location.display()),
+        }
+
+        location.display().to_string()
+    }
+
+    /// Disarms the capture thread and flushes/closes the recording's output
+    /// files. Returns how many frames were written to it, for
+    /// [`crate::manifest`].
+    pub fn stop(&self) -> Option<u64> {
+        self.armed.store(false, Ordering::Relaxed);
+
+        let Some(state) = self.recording.lock().unwrap().take() else {
+            warn!("stop requested but no aravis recording is currently active");
+            return None;
+        };
+
+        Some(state.finish())
+    }
+}
+
+/// Sets up `camera` to expose each frame off an external electrical pulse on
+/// [`TRIGGER_SOURCE`] rather than free-running or a software trigger: sets
+/// the `FrameStart` trigger selector, points its source at the hardware
+/// line, then turns triggering on last (GenICam cameras generally reject
+/// `TriggerSource` writes while `TriggerMode` is already `On`).
+fn configure_hardware_trigger(camera: &aravis::Camera) -> Result<(), String> {
+    let device = camera.device().ok_or_else(|| "GenICam camera has no underlying device".to_string())?;
+
+    device
+        .set_string_feature_value("TriggerSelector", "FrameStart")
+        .map_err(|error| format!("failed to set TriggerSelector: {error}"))?;
+    device
+        .set_string_feature_value("TriggerSource", TRIGGER_SOURCE)
+        .map_err(|error| format!("failed to set TriggerSource: {error}"))?;
+    device
+        .set_string_feature_value("TriggerMode", "On")
+        .map_err(|error| format!("failed to enable hardware TriggerMode: {error}"))?;
+
+    Ok(())
+}
+
+/// Spawns the sole thread allowed to pop buffers off `stream`. Runs for the
+/// lifetime of the process: frames are pulled (and immediately requeued to
+/// the stream) continuously, since the camera is free to fire its hardware
+/// trigger regardless of whether we're currently recording, but only
+/// enqueued to `writer` while `armed`.
+fn spawn_capture_loop(
+    camera: aravis::Camera,
+    stream: aravis::Stream,
+    armed: Arc<AtomicBool>,
+    first_frame_pending: Arc<AtomicBool>,
+    writer: FrameWriterHandle,
+    on_fatal_error: impl Fn() + Send + Sync + 'static,
+    on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+    on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+    on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+) {
+    // Kept alive for the capture thread's lifetime even though the loop below
+    // never touches it again: dropping it would stop acquisition out from
+    // under `stream`.
+    let _camera = camera;
+
+    std::thread::spawn(move || loop {
+        let Some(buffer) = stream.timeout_pop_buffer(5_000_000) else {
+            error!("aravis acquisition stream produced no buffer within 5s; camera may have disconnected");
+            on_fatal_error();
+            return;
+        };
+
+        if buffer.status() != aravis::BufferStatus::Success {
+            warn!("dropping incomplete GenICam buffer (status {:?})", buffer.status());
+            stream.push_buffer(&buffer);
+            continue;
+        }
+
+        if armed.load(Ordering::Relaxed) {
+            // The camera's own timestamp counter, not `CLOCK_MONOTONIC` -
+            // unlike `libcamera_native_backend`'s `SensorTimestamp`, this
+            // isn't directly comparable to `crate::trigger`'s GPIO edge
+            // timestamps without knowing the camera's clock offset, so it's
+            // recorded as-is for post-processing rather than fed to
+            // `on_first_frame` as a latency measurement.
+            let timestamp_ns = buffer.timestamp() as i64;
+
+            if let Some(data) = buffer.data() {
+                match writer.enqueue(data.to_vec(), timestamp_ns, None) {
+                    None => {
+                        if first_frame_pending.swap(false, Ordering::Relaxed) {
+                            on_first_frame(timestamp_ns.max(0) as u64);
+                        }
+                    }
+                    Some(action) => {
+                        on_dropped_frames(1);
+                        on_backpressure_action(action);
+                    }
+                }
+            }
+        }
+
+        stream.push_buffer(&buffer);
+    });
+}
+
+/// Blocking single-frame capture for callers that need one image right now
+/// rather than an armed recording: [`super::still::StillCapture`]'s
+/// survey-mode captures and [`crate::camera_self_test`]'s startup probe
+/// frame. Opens its own camera handle independent of any running
+/// [`AravisRecorder`] - Aravis, like V4L2, allows more than one open control
+/// channel to the same device, unlike [`super::gphoto2_backend`]'s
+/// single-session USB cameras.
+///
+/// Uses a software trigger instead of [`configure_hardware_trigger`]'s
+/// external line, since an on-demand still shouldn't have to wait on the
+/// flight controller pulsing the hardware trigger input.
+pub fn capture_still(source_device: &std::path::Path, location: &str) -> bool {
+    let result = (|| -> Result<(), String> {
+        let device_id = source_device.to_string_lossy();
+        let camera = aravis::Camera::new(if device_id.is_empty() { None } else { Some(device_id.as_ref()) })
+            .map_err(|error| format!("failed to open GenICam camera {device_id:?}: {error}"))?;
+        let device = camera.device().ok_or_else(|| "GenICam camera has no underlying device".to_string())?;
+
+        device
+            .set_string_feature_value("TriggerSelector", "FrameStart")
+            .map_err(|error| format!("failed to set TriggerSelector: {error}"))?;
+        device
+            .set_string_feature_value("TriggerSource", "Software")
+            .map_err(|error| format!("failed to set TriggerSource: {error}"))?;
+        device
+            .set_string_feature_value("TriggerMode", "On")
+            .map_err(|error| format!("failed to enable software TriggerMode: {error}"))?;
+
+        let stream = camera
+            .create_stream(None::<fn(_, _)>, None)
+            .map_err(|error| format!("failed to create GenICam acquisition stream: {error}"))?;
+        let payload_size =
+            camera.payload().map_err(|error| format!("failed to query GenICam payload size: {error}"))?;
+        stream.push_buffer(&aravis::Buffer::new_allocate(payload_size as usize));
+
+        camera.start_acquisition().map_err(|error| format!("failed to start GenICam acquisition: {error}"))?;
+        camera.software_trigger().map_err(|error| format!("failed to issue software trigger: {error}"))?;
+
+        let buffer = stream
+            .timeout_pop_buffer(5_000_000)
+            .ok_or_else(|| "no frame received within 5s of software trigger".to_string())?;
+        camera.stop_acquisition().ok();
+
+        if buffer.status() != aravis::BufferStatus::Success {
+            return Err(format!("incomplete GenICam buffer (status {:?})", buffer.status()));
+        }
+        let data = buffer.data().ok_or_else(|| "GenICam buffer had no data".to_string())?;
+
+        std::fs::write(location, data).map_err(|error| format!("failed to save {location}: {error}"))
+    })();
+
+    match result {
+        Ok(()) => true,
+        Err(error) => {
+            error!("aravis still capture to {location} failed: {error}");
+            false
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Streaming at-rest encryption for recordings written by
+//! [`super::frame_writer`], using the [age](https://age-encryption.org)
+//! format so a memory card or SSD recovered after a flight over a sensitive
+//! site can't be read without the operator's private key.
+//!
+//! Encryption is optional (recordings are written in the clear unless
+//! [`super::RecorderConfig::encryption_recipient`] is set) and per-recording:
+//! each file [`super::frame_writer::RecordingState::open`] creates is its own
+//! age stream, not one continuous stream spanning a flight, so a
+//! recording cut short by power loss still decrypts everything written to
+//! it before the cut. Only [`super::RecordingBackend::V4l2Direct`],
+//! [`super::RecordingBackend::LibcameraNative`] and
+//! [`super::RecordingBackend::GigeVision`] go through this - the same
+//! backends `encryption_recipient` is scoped to - since they write frames to
+//! disk themselves rather than handing a `filesink` element to GStreamer or
+//! a `libcamera-vid` child process to finalize.
+//!
+//! The `.timestamps.csv`/`.frame-stats.csv` sidecars are left in the clear;
+//! they carry no image data, and `cameras recover`-style tooling on the
+//! ground workstation needs to read them without the private key in hand.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use age::x25519::{Identity, Recipient};
+use age::{Decryptor, Encryptor};
+
+/// Either a plain buffered file, or one wrapped in an age encryption stream
+/// keyed to [`super::RecorderConfig::encryption_recipient`].
+/// [`super::frame_writer::RecordingState`] writes through this the same way
+/// either way; only [`RecordingWriter::finish`] differs, since age's
+/// `StreamWriter` must write a final authenticated chunk that a plain flush
+/// doesn't produce.
+pub enum RecordingWriter {
+    Plain(BufWriter<File>),
+    Encrypted(age::stream::StreamWriter<BufWriter<File>>),
+}
+
+/// Checks that `recipient` parses as an age public key, without opening any
+/// file, so `cameras check-config` can catch a typo'd `encryption_recipient`
+/// before a flight rather than on the first frame written after it.
+pub fn validate_recipient(recipient: &str) -> Result<(), String> {
+    Recipient::from_str(recipient).map(|_| ()).map_err(|error| format!("invalid encryption_recipient: {error}"))
+}
+
+impl RecordingWriter {
+    /// Opens `file` for writing, wrapping it in an age encryption stream
+    /// addressed to `recipient` if given. `recipient` is an age public key
+    /// (`age1...`), the same format `age-keygen` prints and
+    /// [`super::RecorderConfig::encryption_recipient`] expects.
+    pub fn create(file: File, recipient: Option<&str>) -> Result<Self, String> {
+        let buffered = BufWriter::new(file);
+        let Some(recipient) = recipient else {
+            return Ok(Self::Plain(buffered));
+        };
+
+        let parsed = Recipient::from_str(recipient)
+            .map_err(|error| format!("invalid encryption_recipient {recipient:?}: {error}"))?;
+        let encryptor = Encryptor::with_recipients(vec![Box::new(parsed)])
+            .ok_or_else(|| "age::Encryptor::with_recipients was given no recipients".to_string())?;
+        let stream = encryptor
+            .wrap_output(buffered)
+            .map_err(|error| format!("failed to start age encryption stream: {error}"))?;
+        Ok(Self::Encrypted(stream))
+    }
+
+    /// Finalizes the output: writes age's final authenticated chunk (a
+    /// no-op for [`Self::Plain`]) and flushes the underlying file. Takes
+    /// `self` by value because age's `StreamWriter::finish` does - once a
+    /// stream is finished it can't be written to again, which matches this
+    /// only ever being called once a recording has actually stopped.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::Plain(mut writer) => writer.flush(),
+            Self::Encrypted(writer) => {
+                let mut buffered = writer.finish()?;
+                buffered.flush()
+            }
+        }
+    }
+}
+
+impl Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            Self::Encrypted(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::Encrypted(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Decrypts `input` with the age identity (private key) in `identity_file`,
+/// writing alongside it with `.decrypted` inserted before the extension.
+/// Used by the `cameras decrypt` subcommand; the drone itself never needs
+/// this, since it only ever holds `encryption_recipient`, the public half.
+pub fn decrypt(input: &Path, identity_file: &Path) -> Result<PathBuf, String> {
+    let identity_text = std::fs::read_to_string(identity_file)
+        .map_err(|error| format!("failed to read {}: {error}", identity_file.display()))?;
+    let identity = identity_text
+        .lines()
+        .find(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| format!("{} contains no age identity", identity_file.display()))?;
+    let identity = Identity::from_str(identity.trim())
+        .map_err(|error| format!("invalid age identity in {}: {error}", identity_file.display()))?;
+
+    let input_file =
+        File::open(input).map_err(|error| format!("failed to open {}: {error}", input.display()))?;
+    let decryptor = match Decryptor::new(input_file)
+        .map_err(|error| format!("failed to read age header from {}: {error}", input.display()))?
+    {
+        Decryptor::Recipients(decryptor) => decryptor,
+        Decryptor::Passphrase(_) => {
+            return Err(format!(
+                "{} was encrypted with a passphrase, not a recipient key; this tool only supports recipient-keyed recordings",
+                input.display()
+            ));
+        }
+    };
+    let mut reader = decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|error| format!("failed to decrypt {}: {error}", input.display()))?;
+
+    let output = with_inserted_suffix(input, "decrypted");
+    let mut output_file =
+        File::create(&output).map_err(|error| format!("failed to create {}: {error}", output.display()))?;
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|error| format!("failed to read decrypted data from {}: {error}", input.display()))?;
+        if read == 0 {
+            break;
+        }
+        output_file
+            .write_all(&buffer[..read])
+            .map_err(|error| format!("failed to write {}: {error}", output.display()))?;
+    }
+
+    Ok(output)
+}
+
+/// `foo.mp4` with `suffix` inserted before the extension, e.g.
+/// `with_inserted_suffix("foo.mp4", "decrypted")` is `foo.decrypted.mp4`.
+fn with_inserted_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let new_name = match path.extension() {
+        Some(extension) => format!("{stem}.{suffix}.{}", extension.to_string_lossy()),
+        None => format!("{stem}.{suffix}"),
+    };
+    path.with_file_name(new_name)
+}
@@ -0,0 +1,337 @@
+//! A bounded-queue, dedicated-thread disk writer shared by
+//! [`super::v4l2_backend`], [`super::libcamera_native_backend`] and
+//! [`super::aravis_backend`], the backends that write each frame to disk
+//! themselves rather than delegating to GStreamer or a `libcamera-vid`
+//! child process.
+//!
+//! Each backend's capture thread must keep pulling frames off the device's
+//! buffer queue promptly to keep buffers recycling; writing every frame to
+//! disk from that same thread means a slow SD card stalls capture itself.
+//! Instead, the capture thread copies a frame out of its (about to be
+//! recycled) device buffer and hands it to this module's writer thread over
+//! a bounded queue. If the writer thread falls behind and the queue fills
+//! up, [`BackpressurePolicy`] decides what happens to the frame that no
+//! longer fits - an explicit, bounded frame-drop policy instead of an
+//! unbounded queue that would otherwise just move the stall from capture to
+//! memory growth.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use log::warn;
+
+use crate::clock::RealtimeClock;
+
+use super::encryption::RecordingWriter;
+use super::{BackpressureAction, BackpressurePolicy};
+
+/// Per-frame libcamera AE/AGC metadata, logged into `location`'s
+/// `.frame-stats.csv` sidecar alongside its `.timestamps.csv` one, so an
+/// over/under-exposed survey line can be diagnosed after the flight without
+/// re-decoding the video itself. Only [`super::libcamera_native_backend`]
+/// has this metadata available per frame; [`super::v4l2_backend`] always
+/// passes `None` to [`FrameWriterHandle::enqueue`], and the sidecar is
+/// never created for its recordings.
+pub struct FrameStats {
+    pub exposure_time_micros: u32,
+    pub analogue_gain: f32,
+}
+
+/// The currently-armed recording's open output files. Opened/closed by a
+/// backend's `start`/`stop` (on the caller's thread, since that's a rare,
+/// non-per-frame operation); written to by [`spawn`]'s writer thread.
+pub struct RecordingState {
+    frames: RecordingWriter,
+    /// The `.timestamps.csv` frame index: one `frame,monotonic_ns,utc_ns`
+    /// row per written frame, so `cameras recover` (and any other
+    /// post-processing) can map a frame number to the instant it was
+    /// captured in either clock without decoding the video itself.
+    timestamps: BufWriter<File>,
+    /// Where a `.frame-stats.csv` sidecar would go, if this recording ever
+    /// reports a [`FrameStats`]; not created up front.
+    frame_stats_path: PathBuf,
+    /// Opened lazily, by the writer thread, on the first frame carrying
+    /// [`FrameStats`], so a backend that never reports any (`v4l2_backend`)
+    /// doesn't leave behind an empty `.frame-stats.csv` next to every
+    /// recording.
+    frame_stats: Option<BufWriter<File>>,
+    /// The redundant copy opened at `RecorderConfig::secondary_output_dir`,
+    /// if configured. Taken out (leaving `None`) the first time a write to
+    /// it fails, so a medium lost mid-recording doesn't spam a warning for
+    /// every subsequent frame; the primary copy in `frames` keeps going
+    /// either way.
+    secondary: Option<RecordingWriter>,
+    location: String,
+    secondary_location: String,
+    frame_count: u64,
+}
+
+impl RecordingState {
+    /// Creates `location` (the recording itself) and a `.timestamps.csv`
+    /// sidecar next to it, with its header row already written, plus a
+    /// redundant copy at `secondary_location` if given. `encryption_recipient`,
+    /// if given, is an age public key that `location` and
+    /// `secondary_location` are both encrypted to as they're written; see
+    /// [`super::encryption`].
+    pub fn open(
+        location: &std::path::Path,
+        secondary_location: Option<&std::path::Path>,
+        encryption_recipient: Option<&str>,
+    ) -> Result<Self, String> {
+        let frames =
+            File::create(location).map_err(|error| format!("failed to create {}: {error}", location.display()))?;
+        let frames = RecordingWriter::create(frames, encryption_recipient)?;
+        let timestamps_path = location.with_extension("timestamps.csv");
+        let mut timestamps = File::create(&timestamps_path)
+            .map_err(|error| format!("failed to create {}: {error}", timestamps_path.display()))?;
+        writeln!(timestamps, "frame,monotonic_ns,utc_ns")
+            .map_err(|error| format!("failed to write header to {}: {error}", timestamps_path.display()))?;
+
+        let secondary = match secondary_location {
+            Some(secondary_location) => match File::create(secondary_location)
+                .map_err(|error| error.to_string())
+                .and_then(|file| RecordingWriter::create(file, encryption_recipient))
+            {
+                Ok(writer) => Some(writer),
+                Err(error) => {
+                    warn!(
+                        "failed to create redundant recording copy at {}: {error}; continuing with {} alone",
+                        secondary_location.display(),
+                        location.display(),
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            frames,
+            timestamps: BufWriter::new(timestamps),
+            frame_stats_path: location.with_extension("frame-stats.csv"),
+            frame_stats: None,
+            secondary,
+            location: location.display().to_string(),
+            secondary_location: secondary_location.map_or_else(String::new, |path| path.display().to_string()),
+            frame_count: 0,
+        })
+    }
+
+    /// Finalizes every open output file and returns how many frames were
+    /// written, for [`crate::manifest`]. Takes `self` by value rather than
+    /// `&mut self` because age's `StreamWriter::finish` (used when
+    /// `encryption_recipient` was set) does - finalizing an encrypted
+    /// recording writes a final authenticated chunk a plain flush can't
+    /// produce, and a finished stream can't be written to again anyway,
+    /// which matches this only ever running once, from `stop()`.
+    pub fn finish(mut self) -> u64 {
+        if let Err(error) = self.frames.finish() {
+            warn!("failed to finish recording {}: {error}", self.location);
+        }
+        if let Err(error) = self.timestamps.flush() {
+            warn!("failed to flush timestamps for {}: {error}", self.location);
+        }
+        if let Some(mut frame_stats) = self.frame_stats.take() {
+            if let Err(error) = frame_stats.flush() {
+                warn!("failed to flush frame stats for {}: {error}", self.location);
+            }
+        }
+        if let Some(secondary) = self.secondary.take() {
+            if let Err(error) = secondary.finish() {
+                warn!("failed to finish redundant recording copy at {}: {error}", self.secondary_location);
+            }
+        }
+
+        self.frame_count
+    }
+}
+
+/// One captured frame queued for the writer thread. `timestamp` is this
+/// frame's capture time in nanoseconds, `CLOCK_MONOTONIC` domain (the same
+/// one [`crate::trigger`]'s GPIO edge timestamps are in), written to the
+/// `.timestamps.csv` sidecar alongside the `CLOCK_REALTIME`/UTC translation
+/// [`spawn`]'s writer thread derives from it. `stats`, if any, is written to
+/// the `.frame-stats.csv` sidecar; see [`FrameStats`].
+struct WriteJob {
+    data: Vec<u8>,
+    timestamp: i64,
+    stats: Option<FrameStats>,
+}
+
+/// How many additional frames [`BackpressurePolicy::ReduceFramerate`] skips
+/// after each overflow, growing the skip window so sustained backpressure
+/// settles into a steadily lower framerate instead of re-triggering on
+/// every single frame.
+const FRAMERATE_SKIP_STEP: u64 = 4;
+
+/// Upper bound on how many consecutive frames [`BackpressurePolicy::ReduceFramerate`]
+/// will skip before trying to write again, regardless of how long
+/// backpressure has persisted.
+const FRAMERATE_SKIP_MAX: u64 = 64;
+
+/// The bounded queue shared between [`FrameWriterHandle`] (producer side,
+/// one per capture thread) and the writer thread [`spawn`] starts. A plain
+/// `Mutex<VecDeque>` rather than `std::sync::mpsc` because
+/// [`BackpressurePolicy::DropOldest`] needs to pop from the front on the
+/// producer side, which a channel's `Sender` can't do.
+struct Queue {
+    jobs: Mutex<VecDeque<WriteJob>>,
+    not_empty: Condvar,
+    capacity: usize,
+}
+
+/// Handle for a capture thread to hand frames off to the writer thread.
+#[derive(Clone)]
+pub struct FrameWriterHandle {
+    queue: Arc<Queue>,
+    policy: BackpressurePolicy,
+    /// Frames still left to proactively skip under
+    /// [`BackpressurePolicy::ReduceFramerate`]; unused by the other two
+    /// policies. Shared across every clone of this handle so overflow
+    /// detected on one call still throttles the next.
+    framerate_skip_credits: Arc<AtomicU64>,
+}
+
+impl FrameWriterHandle {
+    /// Queues `data`/`timestamp`/`stats` for the writer thread, applying
+    /// `policy` if the queue is already `queue_depth` frames deep. Returns
+    /// `None` if the frame was queued normally, or `Some(action)` describing
+    /// what happened to it instead - the caller should count that action and
+    /// keep going, not retry.
+    pub fn enqueue(&self, data: Vec<u8>, timestamp: i64, stats: Option<FrameStats>) -> Option<BackpressureAction> {
+        if self.policy == BackpressurePolicy::ReduceFramerate {
+            let skipped = self
+                .framerate_skip_credits
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |credits| (credits > 0).then_some(credits - 1))
+                .is_ok();
+            if skipped {
+                return Some(BackpressureAction::ReducedFramerate);
+            }
+        }
+
+        let mut jobs = self.queue.jobs.lock().unwrap();
+        if jobs.len() < self.queue.capacity {
+            jobs.push_back(WriteJob { data, timestamp, stats });
+            drop(jobs);
+            self.queue.not_empty.notify_one();
+            return None;
+        }
+
+        match self.policy {
+            BackpressurePolicy::DropNewest => Some(BackpressureAction::DroppedNewest),
+            BackpressurePolicy::DropOldest => {
+                jobs.pop_front();
+                jobs.push_back(WriteJob { data, timestamp, stats });
+                drop(jobs);
+                self.queue.not_empty.notify_one();
+                Some(BackpressureAction::DroppedOldest)
+            }
+            BackpressurePolicy::ReduceFramerate => {
+                drop(jobs);
+                self.framerate_skip_credits
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |credits| {
+                        Some((credits + FRAMERATE_SKIP_STEP).min(FRAMERATE_SKIP_MAX))
+                    })
+                    .unwrap();
+                Some(BackpressureAction::ReducedFramerate)
+            }
+        }
+    }
+}
+
+/// Spawns the dedicated writer thread and returns the handle capture threads
+/// enqueue frames through. `recording` is the same
+/// `Arc<Mutex<Option<RecordingState>>>` the backend's `start`/`stop` open
+/// and take from; a frame that arrives while it's `None` (already stopped,
+/// or not yet started) is silently discarded. Runs for the lifetime of the
+/// process, same as the capture threads it serves.
+///
+/// `on_frame` is invoked once per frame actually written, for
+/// [`crate::metrics::CameraMetrics::record_frame`]'s jitter histogram.
+///
+/// `realtime_clock` translates each frame's monotonic `timestamp` into a
+/// UTC nanosecond column in the `.timestamps.csv` sidecar, the same
+/// translation [`crate::trigger_log::TriggerLog`] applies to trigger
+/// events.
+pub fn spawn(
+    queue_depth: usize,
+    policy: BackpressurePolicy,
+    recording: Arc<Mutex<Option<RecordingState>>>,
+    on_frame: impl Fn() + Send + Sync + 'static,
+    realtime_clock: Arc<RealtimeClock>,
+) -> FrameWriterHandle {
+    let queue = Arc::new(Queue {
+        jobs: Mutex::new(VecDeque::with_capacity(queue_depth)),
+        not_empty: Condvar::new(),
+        capacity: queue_depth.max(1),
+    });
+
+    let thread_queue = Arc::clone(&queue);
+    std::thread::spawn(move || loop {
+        let WriteJob { data, timestamp, stats } = {
+            let mut jobs = thread_queue.jobs.lock().unwrap();
+            while jobs.is_empty() {
+                jobs = thread_queue.not_empty.wait(jobs).unwrap();
+            }
+            jobs.pop_front().unwrap()
+        };
+
+        let mut recording = recording.lock().unwrap();
+        let Some(state) = recording.as_mut() else { continue };
+
+        if let Err(error) = state.frames.write_all(&data) {
+            warn!("failed to write captured frame to {}: {error}", state.location);
+        }
+        if let Some(secondary) = &mut state.secondary {
+            if let Err(error) = secondary.write_all(&data) {
+                warn!(
+                    "failed to write captured frame to redundant copy {}: {error}; dropping it, {} continues alone",
+                    state.secondary_location, state.location,
+                );
+                state.secondary = None;
+            }
+        }
+        let frame_index = state.frame_count;
+        state.frame_count += 1;
+        on_frame();
+
+        let utc_ns = realtime_clock.to_unix_nanos(timestamp.max(0) as u64);
+        if let Err(error) = writeln!(state.timestamps, "{frame_index},{timestamp},{utc_ns}") {
+            warn!("failed to write frame timestamp for {}: {error}", state.location);
+        }
+
+        if let Some(stats) = stats {
+            if state.frame_stats.is_none() {
+                match File::create(&state.frame_stats_path) {
+                    Ok(file) => {
+                        let mut file = BufWriter::new(file);
+                        if let Err(error) = writeln!(file, "exposure_time_micros,analogue_gain") {
+                            warn!("failed to write frame stats header for {}: {error}", state.location);
+                        }
+                        state.frame_stats = Some(file);
+                    }
+                    Err(error) => {
+                        warn!(
+                            "failed to create {}: {error}",
+                            state.frame_stats_path.display()
+                        );
+                    }
+                }
+            }
+
+            if let Some(frame_stats) = &mut state.frame_stats {
+                if let Err(error) =
+                    writeln!(frame_stats, "{},{}", stats.exposure_time_micros, stats.analogue_gain)
+                {
+                    warn!("failed to write frame stats for {}: {error}", state.location);
+                }
+            }
+        }
+    });
+
+    FrameWriterHandle { queue, policy, framerate_skip_credits: Arc::new(AtomicU64::new(0)) }
+}
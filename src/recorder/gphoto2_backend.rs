@@ -0,0 +1,225 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+use gphoto2::{Camera, Context};
+use log::{debug, error, warn};
+
+use super::{NamingMode, RecorderConfig};
+use crate::naming::NamingScheme;
+
+/// Where [`Gphoto2Recorder::start`] gets its next output location from, one
+/// per [`NamingMode`]. Mirrors [`super::still::StillCapture`]'s
+/// `LocationSource` rather than [`super::subprocess_backend`]'s: a DSLR
+/// shutter release produces one complete image per trigger, with no
+/// fragment/`%05d` placeholder to substitute.
+enum LocationSource {
+    Pattern { location_pattern: String, next_trigger_id: AtomicU64 },
+    Structured(NamingScheme),
+}
+
+impl LocationSource {
+    fn next(&self) -> String {
+        match self {
+            LocationSource::Pattern { location_pattern, next_trigger_id } => {
+                let trigger_id = next_trigger_id.fetch_add(1, Ordering::Relaxed);
+                location_pattern.replace("{trigger}", &trigger_id.to_string())
+            }
+            LocationSource::Structured(naming) => naming.next_path("jpg").display().to_string(),
+        }
+    }
+}
+
+/// An in-camera file [`Gphoto2Recorder::start`] just captured, queued for
+/// [`spawn_download_worker`] to pull off the camera and save to `location`.
+struct PendingDownload {
+    camera_path: gphoto2::file::CameraFilePath,
+    location: String,
+}
+
+/// Drives a USB-connected DSLR/mirrorless camera through libgphoto2:
+/// [`Gphoto2Recorder::start`] fires the shutter and returns immediately,
+/// handing the resulting in-camera file off to a background thread (see
+/// [`spawn_download_worker`]) that performs the actual USB transfer. A DSLR's
+/// write-to-card-then-transfer latency (routinely a second or more at full
+/// resolution) would otherwise stall the trigger source for every other
+/// camera in the session if it were done inline here.
+///
+/// Capture and download share one [`Camera`] handle behind a [`Mutex`]
+/// rather than opening a second session: a USB camera can only have one
+/// libgphoto2 session claiming it at a time, so a capture arriving while the
+/// previous one is still downloading briefly waits on the mutex regardless -
+/// the point of the background thread is only to keep that wait off the
+/// caller of [`Self::start`], not to make captures and downloads run
+/// simultaneously against the same physical device.
+///
+/// There is no equivalent of the video backends' `Start`/`Stop` pair: a
+/// shutter release is a single discrete action, so [`Gphoto2Recorder::stop`]
+/// is a no-op, same as [`super::subprocess_backend::SubprocessRecorder`]
+/// reports no frame count.
+pub struct Gphoto2Recorder {
+    camera: Arc<Mutex<Camera>>,
+    location_source: LocationSource,
+    download_tx: mpsc::Sender<PendingDownload>,
+}
+
+impl Gphoto2Recorder {
+    /// Opens the camera at `config.source_device` (a gphoto2 port
+    /// specification, e.g. `usb:001,004`), or autodetects whichever single
+    /// USB camera is attached if it's empty, and spawns the download worker
+    /// that [`Self::start`] hands captures off to.
+    ///
+    /// `camera_id` and `flight_session` are only consulted when
+    /// `config.naming` is [`NamingMode::Structured`].
+    pub fn new(config: &RecorderConfig, camera_id: &str, flight_session: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+
+        let location_source = match config.naming {
+            NamingMode::Pattern => LocationSource::Pattern {
+                location_pattern: config.output_dir.join(&config.file_pattern).display().to_string(),
+                next_trigger_id: AtomicU64::new(0),
+            },
+            NamingMode::Structured => LocationSource::Structured(NamingScheme::new(
+                &config.output_dir,
+                flight_session,
+                camera_id,
+            )?),
+        };
+
+        let context = Context::new().map_err(|error| format!("failed to initialize libgphoto2: {error}"))?;
+
+        let port = config.source_device.to_string_lossy();
+        let camera = if port.is_empty() {
+            context.autodetect_camera().wait()
+        } else {
+            context.get_camera(&port).wait()
+        }
+        .map_err(|error| format!("failed to open gphoto2 camera at {port:?}: {error}"))?;
+
+        let camera = Arc::new(Mutex::new(camera));
+        let (download_tx, download_rx) = mpsc::channel();
+        spawn_download_worker(download_rx, Arc::clone(&camera));
+
+        Ok(Self { camera, location_source, download_tx })
+    }
+
+    /// Fires the shutter and queues the resulting file for background
+    /// download, returning the location it will (eventually) be saved to.
+    /// Returns `None` if the capture itself failed - the shutter release,
+    /// not the transfer, which by design never blocks this call.
+    pub fn start(&self) -> Option<String> {
+        let location = self.location_source.next();
+
+        let camera_path = match self.camera.lock().unwrap().capture_image().wait() {
+            Ok(camera_path) => camera_path,
+            Err(error) => {
+                error!("gphoto2 shutter release failed: {error}");
+                return None;
+            }
+        };
+
+        debug!("gphoto2 shutter released, queuing download to {location}");
+        if self.download_tx.send(PendingDownload { camera_path, location: location.clone() }).is_err() {
+            error!("gphoto2 download worker has stopped; captured frame will be left on the camera");
+            return None;
+        }
+
+        Some(location)
+    }
+
+    /// No-op: a shutter release has no running state to tear down. Always
+    /// returns `None` since this backend never counts frames.
+    pub fn stop(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Spawns the thread that performs every capture's actual USB transfer, off
+/// of [`Gphoto2Recorder::start`]'s calling thread.
+fn spawn_download_worker(download_rx: mpsc::Receiver<PendingDownload>, camera: Arc<Mutex<Camera>>) {
+    std::thread::spawn(move || {
+        for pending in download_rx {
+            match download_one(&camera, &pending) {
+                Ok(()) => debug!("downloaded gphoto2 capture to {}", pending.location),
+                Err(error) => error!("failed to download gphoto2 capture to {}: {error}", pending.location),
+            }
+        }
+        debug!("gphoto2 download worker exiting, sender dropped");
+    });
+}
+
+/// Blocking capture-and-download of a single frame, for callers that need one
+/// image right now and don't have (or want to share) a running
+/// [`Gphoto2Recorder`]'s session: [`super::still::StillCapture`]'s survey-mode
+/// captures and [`crate::camera_self_test`]'s startup probe frame, mirroring
+/// how those callers already open their own short-lived handle for the other
+/// backends (e.g. `capture_libcamera_native`) rather than reaching into the
+/// long-running recorder. Opens and closes its own libgphoto2 session, so it
+/// must not be called while a [`Gphoto2Recorder`] for the same physical camera
+/// is also open - same one-session-per-device constraint documented on
+/// [`Gphoto2Recorder`].
+pub fn capture_still(source_device: &std::path::Path, location: &str) -> bool {
+    let result = (|| -> Result<(), String> {
+        let context = Context::new().map_err(|error| format!("failed to initialize libgphoto2: {error}"))?;
+
+        let port = source_device.to_string_lossy();
+        let camera = if port.is_empty() {
+            context.autodetect_camera().wait()
+        } else {
+            context.get_camera(&port).wait()
+        }
+        .map_err(|error| format!("failed to open gphoto2 camera at {port:?}: {error}"))?;
+
+        let camera_path = camera.capture_image().wait().map_err(|error| format!("shutter release failed: {error}"))?;
+
+        let file = camera
+            .fs()
+            .download(&camera_path.folder(), &camera_path.name())
+            .wait()
+            .map_err(|error| format!("download failed: {error}"))?;
+
+        file.save(location).wait().map_err(|error| format!("failed to save {location}: {error}"))?;
+
+        if let Err(error) = camera.fs().delete_file(&camera_path.folder(), &camera_path.name()).wait() {
+            warn!("failed to delete in-camera copy of {location}: {error}");
+        }
+
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            debug!("captured gphoto2 still to {location}");
+            true
+        }
+        Err(error) => {
+            error!("gphoto2 still capture to {location} failed: {error}");
+            false
+        }
+    }
+}
+
+/// Downloads `pending.camera_path` off `camera` and saves it to
+/// `pending.location`, deleting the in-camera copy afterward so the card
+/// doesn't fill up over a long flight.
+fn download_one(camera: &Mutex<Camera>, pending: &PendingDownload) -> Result<(), String> {
+    let camera = camera.lock().unwrap();
+
+    let file = camera
+        .fs()
+        .download(&pending.camera_path.folder(), &pending.camera_path.name())
+        .wait()
+        .map_err(|error| format!("download failed: {error}"))?;
+
+    file.save(&pending.location).wait().map_err(|error| format!("failed to save {}: {error}", pending.location))?;
+
+    if let Err(error) = camera.fs().delete_file(&pending.camera_path.folder(), &pending.camera_path.name()).wait() {
+        warn!("failed to delete in-camera copy of {}: {error}", pending.location);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,1542 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gstreamer::prelude::*;
+use gstreamer::{self as gst, MessageView};
+use gstreamer_app::{AppSink, AppSrc};
+use gstreamer_video as gst_video;
+use log::{debug, error, info, warn};
+
+use super::osd_overlay;
+use super::rtsp_preview;
+use super::srt_output;
+use super::webrtc_preview;
+use super::{CaptureSource, ContainerFormat, Encoder, NamingMode, Orientation, RecorderConfig, VideoCodec};
+use crate::mavlink::MavlinkFeedback;
+use crate::naming::NamingScheme;
+
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often [`spawn_device_recovery`] checks whether a lost capture device
+/// has come back.
+const DEVICE_RECOVERY_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long [`spawn_device_recovery`] keeps polling before giving up and
+/// leaving the pipeline stopped for the next trigger to retry naturally.
+const DEVICE_RECOVERY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Whether `error` looks like the camera device disappearing (unplugged,
+/// power-cycled) rather than some other pipeline failure. `v4l2src`/
+/// `libcamerasrc` surface a missing device as a `gst::ResourceError`, since
+/// GStreamer has no dedicated "device unplugged" domain.
+fn is_device_lost(error: &gst::glib::Error) -> bool {
+    matches!(
+        error.kind::<gst::ResourceError>(),
+        Some(gst::ResourceError::NotFound | gst::ResourceError::OpenRead | gst::ResourceError::Read)
+    )
+}
+
+/// The gstreamer element name and parser element name for `codec`/`encoder`,
+/// e.g. (`"x264enc"`, `"h264parse"`) or (`"v4l2h265enc"`, `"h265parse"`).
+/// `pub(super)` rather than private so [`super::recover`] can renegotiate
+/// the same elements a truncated recording was originally encoded with.
+pub(super) fn encoder_elements(codec: VideoCodec, encoder: Encoder) -> (&'static str, &'static str) {
+    match (codec, encoder) {
+        (VideoCodec::H264, Encoder::Software) => ("x264enc", "h264parse"),
+        (VideoCodec::H265, Encoder::Software) => ("x265enc", "h265parse"),
+        (VideoCodec::Av1, Encoder::Software) => ("av1enc", "av1parse"),
+        (VideoCodec::H264, Encoder::Hardware) => ("v4l2h264enc", "h264parse"),
+        (VideoCodec::H265, Encoder::Hardware) => ("v4l2h265enc", "h265parse"),
+        (VideoCodec::Av1, Encoder::Hardware) => ("v4l2av1enc", "av1parse"),
+    }
+}
+
+/// Checks that `encoder_element` is actually installed/registered and, if
+/// `width`/`height`/`framerate` are given, that it advertises support for
+/// that resolution and/or framerate on its sink pad, so a missing hardware
+/// codec block or an unsupported resolution/framerate fails fast at startup
+/// with a message naming the exact element and value, rather than deep
+/// inside pipeline state change negotiation once a trigger arrives.
+fn probe_encoder_capability(
+    encoder_element: &str,
+    width: Option<u32>,
+    height: Option<u32>,
+    framerate: Option<u32>,
+) -> Result<(), String> {
+    let factory = gst::ElementFactory::find(encoder_element).ok_or_else(|| {
+        format!(
+            "encoder element \"{encoder_element}\" is not installed/registered; install the \
+             matching gstreamer plugin (a vendor's V4L2 stateful codec plugin for hardware \
+             encoders, gstreamer1.0-plugins-ugly for x264enc/x265enc)"
+        )
+    })?;
+
+    if width.is_none() && height.is_none() && framerate.is_none() {
+        return Ok(());
+    }
+
+    let sink_caps = factory
+        .static_pad_templates()
+        .iter()
+        .find(|template| template.direction() == gst::PadDirection::Sink)
+        .map(|template| template.caps())
+        .ok_or_else(|| format!("encoder element \"{encoder_element}\" has no sink pad template to check requested capture settings against"))?;
+
+    let mut builder = gst::Caps::builder("video/x-raw");
+    if let (Some(width), Some(height)) = (width, height) {
+        builder = builder.field("width", width as i32).field("height", height as i32);
+    }
+    if let Some(framerate) = framerate {
+        builder = builder.field("framerate", gst::Fraction::new(framerate as i32, 1));
+    }
+    let requested = builder.build();
+
+    if !sink_caps.can_intersect(&requested) {
+        return Err(format!(
+            "encoder element \"{encoder_element}\" does not support the requested capture settings ({requested}); its sink caps are: {sink_caps}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Builds a `video/x-raw` caps string for `format`, with `width`/`height`/
+/// `framerate` fields appended only when requested, so an unset value falls
+/// through to the source element's own default rather than being pinned.
+fn capture_caps_fragment(format: &str, width: Option<u32>, height: Option<u32>, framerate: Option<u32>) -> String {
+    let mut caps = format!("video/x-raw,format={format}");
+    if let (Some(width), Some(height)) = (width, height) {
+        caps.push_str(&format!(",width={width},height={height}"));
+    }
+    if let Some(framerate) = framerate {
+        caps.push_str(&format!(",framerate={framerate}/1"));
+    }
+    caps
+}
+
+/// `videoflip`/`videocrop` pipeline fragment for `config.orientation` and
+/// `config.crop_*`, inserted right after `videoconvert` so both elements see
+/// pixel data in a format they understand. Only the elements actually
+/// needed are included, so a camera mounted right-side-up on an unrotated,
+/// uncropped airframe pays nothing for this feature.
+///
+/// `pub(super)` since [`super::still`]'s `capture_gstreamer` builds the same
+/// kind of one-shot pipeline and wants stills oriented/cropped the same way
+/// as footage from the same camera.
+pub(super) fn orientation_fragment(config: &RecorderConfig) -> String {
+    let mut fragment = String::new();
+
+    let method = match config.orientation {
+        Orientation::None => None,
+        Orientation::Clockwise90 => Some("clockwise"),
+        Orientation::Rotate180 => Some("rotate-180"),
+        Orientation::CounterClockwise90 => Some("counterclockwise"),
+        Orientation::HorizontalFlip => Some("horizontal-flip"),
+        Orientation::VerticalFlip => Some("vertical-flip"),
+    };
+    if let Some(method) = method {
+        fragment.push_str(&format!("videoflip method={method} ! "));
+    }
+
+    if config.crop_left != 0 || config.crop_right != 0 || config.crop_top != 0 || config.crop_bottom != 0 {
+        fragment.push_str(&format!(
+            "videocrop left={} right={} top={} bottom={} ! ",
+            config.crop_left, config.crop_right, config.crop_top, config.crop_bottom,
+        ));
+    }
+
+    fragment
+}
+
+/// Identifies our user-data-unregistered SEI payloads among any others a
+/// downstream tool might encounter, per the ITU-T H.264/H.265 SEI syntax's
+/// 16-byte `uuid_iso_iec_11578` field. Fixed and arbitrary, same idea as a
+/// vendor OUI: it only needs to be unique enough that a post-processing tool
+/// can recognize "this SEI is ours" before parsing the payload.
+const FRAME_METADATA_SEI_UUID: [u8; 16] = [
+    0x70, 0x78, 0x34, 0x5f, 0x63, 0x61, 0x6d, 0x5f, 0x74, 0x72, 0x69, 0x67, 0x5f, 0x6d, 0x65, 0x74,
+];
+
+/// Attaches a buffer probe to `pipeline`'s `frame_metadata` `identity`
+/// element (present only when `config.embed_frame_metadata` is set; see
+/// [`GstreamerRecorder::new`]) that stamps each raw frame with a
+/// user-data-unregistered SEI meta carrying its capture timestamp (the
+/// buffer's running time, in nanoseconds) and `current_trigger_sequence`'s
+/// current value. `x264enc`/`x265enc` (and the V4L2 stateful codecs, via
+/// their own SEI passthrough) read this meta off the incoming raw buffer and
+/// emit it as an SEI NAL alongside the encoded frame it produces from it.
+fn install_frame_metadata_probe(pipeline: &gst::Pipeline, current_trigger_sequence: Arc<AtomicU64>) {
+    let Some(tag) = pipeline.by_name("frame_metadata") else {
+        error!("recording pipeline has no element named \"frame_metadata\" to attach the SEI probe to");
+        return;
+    };
+    let Some(pad) = tag.static_pad("src") else {
+        error!("\"frame_metadata\" element has no src pad to attach the SEI probe to");
+        return;
+    };
+
+    pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, info| {
+        if let Some(buffer) = info.buffer_mut() {
+            let capture_timestamp_ns = buffer.pts().map(gst::ClockTime::nseconds).unwrap_or(0);
+            let sequence = current_trigger_sequence.load(Ordering::Relaxed);
+            let payload = format!("seq={sequence};ts_ns={capture_timestamp_ns}");
+            gst_video::VideoSEIUserDataUnregisteredMeta::add(buffer, FRAME_METADATA_SEI_UUID, payload.as_bytes());
+        }
+        gst::PadProbeReturn::Ok
+    });
+}
+
+/// Pipeline fragment to append alongside a `splitmuxsink name=sink` branch:
+/// an independent ALSA capture, AAC-encoded and muxed in as `sink`'s
+/// `audio_0` request pad, so the archival file ends up with both tracks.
+///
+/// `audiorate` sits between the raw capture and the encoder to correct for
+/// clock drift between the sound card and the pipeline clock (which is
+/// driven by the video source) - without it, a long recording's audio would
+/// gradually slip out of sync with the video as the two clocks diverge,
+/// since nothing else here re-times audio buffers against the pipeline
+/// clock the way `splitmuxsink` does for muxing itself.
+fn audio_branch_fragment(config: &RecorderConfig) -> String {
+    if !config.audio_capture_enabled {
+        return String::new();
+    }
+
+    format!(
+        " alsasrc device={} ! audioconvert ! audioresample ! audiorate ! \
+         avenc_aac bitrate={} ! aacparse ! queue ! sink.audio_0",
+        config.audio_device,
+        config.audio_bitrate_kbps * 1000,
+    )
+}
+
+/// The `splitmuxsink` `muxer-factory`/`muxer-properties` fragment for
+/// `container`, and the file extension its output should use.
+/// `FragmentedMp4`/`Matroska` both set `streamable=true` so the muxer
+/// flushes each fragment/cluster as it's written rather than buffering
+/// index data to patch in at finalize time, which is what makes them
+/// survive an unclean shutdown.
+fn muxer_pipeline_fragment(container: ContainerFormat) -> (&'static str, &'static str) {
+    match container {
+        ContainerFormat::Mp4 => ("muxer-factory=mp4mux", "mp4"),
+        ContainerFormat::FragmentedMp4 => (
+            "muxer-factory=mp4mux muxer-properties=\"properties,streamable=true,fragment-duration=1000\"",
+            "mp4",
+        ),
+        ContainerFormat::Matroska => {
+            ("muxer-factory=matroskamux muxer-properties=\"properties,streamable=true\"", "mkv")
+        }
+    }
+}
+
+/// Outcome of waiting for a pipeline to finish finalizing after EOS,
+/// reported by [`watch_pipeline_bus`]'s thread to a waiting `stop`.
+enum StopOutcome {
+    Finalized,
+    Errored,
+}
+
+/// Where a recording pipeline gets its next output location from, one per
+/// [`NamingMode`].
+enum LocationSource {
+    Pattern { location_pattern: String, next_trigger_id: AtomicU64 },
+    Structured { naming: NamingScheme, extension: &'static str },
+}
+
+impl LocationSource {
+    fn next(&self) -> String {
+        match self {
+            LocationSource::Pattern { location_pattern, next_trigger_id } => {
+                let trigger_id = next_trigger_id.fetch_add(1, Ordering::Relaxed);
+                location_pattern.replace("{trigger}", &trigger_id.to_string())
+            }
+            LocationSource::Structured { naming, extension } => {
+                naming.next_fragment_pattern(extension).display().to_string()
+            }
+        }
+    }
+}
+
+/// Spawns the sole background thread allowed to read `pipeline`'s bus. It
+/// logs errors through the `log` facade instead of letting them panic the
+/// process, notifies `on_fatal_error`, wakes up whichever `stop` call is
+/// currently waiting on `Eos`/`Error` via `stop_waiter`, reports any
+/// newly-dropped frames (see [`qos_dropped_delta`]) via `on_dropped_frames`,
+/// and accumulates the recording's processed-frame count into `frames` (see
+/// [`qos_processed_delta`]) for [`crate::manifest`].
+fn watch_pipeline_bus(
+    pipeline: &gst::Pipeline,
+    stop_waiter: Arc<Mutex<Option<mpsc::Sender<StopOutcome>>>>,
+    on_fatal_error: Arc<dyn Fn() + Send + Sync>,
+    on_dropped_frames: Arc<dyn Fn(u64) + Send + Sync>,
+    frames: Arc<AtomicU64>,
+) {
+    let bus = pipeline.bus().expect("pipeline should have a bus");
+
+    std::thread::spawn(move || {
+        let mut last_dropped = 0u64;
+        let mut last_processed = 0u64;
+
+        for message in bus.iter_timed(gst::ClockTime::NONE) {
+            match message.view() {
+                MessageView::Error(error) => {
+                    error!(
+                        "recording pipeline error from {}: {} ({:?})",
+                        error
+                            .src()
+                            .map(|source| source.path_string())
+                            .unwrap_or_else(|| "<unknown>".into()),
+                        error.error(),
+                        error.debug(),
+                    );
+                    on_fatal_error();
+
+                    if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                        let _ = waiter.send(StopOutcome::Errored);
+                    }
+                }
+                MessageView::Eos(_) => {
+                    debug!("pipeline reported end-of-stream");
+
+                    if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                        let _ = waiter.send(StopOutcome::Finalized);
+                    }
+                }
+                MessageView::Qos(qos) => {
+                    if let Some(delta) = qos_dropped_delta(&qos, &mut last_dropped) {
+                        warn!("recording pipeline dropped {delta} frame(s) ({last_dropped} total this recording)");
+                        on_dropped_frames(delta);
+                    }
+                    if let Some(delta) = qos_processed_delta(&qos, &mut last_processed) {
+                        frames.fetch_add(delta, Ordering::Relaxed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Reads a `Qos` message's cumulative dropped-frame count and returns how
+/// many are new since `last_dropped` (updating it in place), or `None` if
+/// the element reporting doesn't track drops in units this can count (its
+/// `format_processed_dropped` format isn't [`gst::Format::Default`], i.e.
+/// buffers rather than bytes/time).
+fn qos_dropped_delta(qos: &gst::message::Qos, last_dropped: &mut u64) -> Option<u64> {
+    let (format, _processed, dropped) = qos.format_processed_dropped();
+    if format != gst::Format::Default || dropped < 0 {
+        return None;
+    }
+
+    let dropped = dropped as u64;
+    let delta = dropped.saturating_sub(*last_dropped);
+    *last_dropped = dropped;
+
+    (delta > 0).then_some(delta)
+}
+
+/// Like [`qos_dropped_delta`], but for the cumulative processed-frame count,
+/// which doubles as a best-effort per-recording frame count for
+/// [`crate::manifest`] since no element in these pipelines otherwise counts
+/// buffers explicitly. Best-effort because it depends on some element along
+/// the way choosing to emit `Qos` messages at all, and undercounts if the
+/// last one arrives before the final few buffers do.
+fn qos_processed_delta(qos: &gst::message::Qos, last_processed: &mut u64) -> Option<u64> {
+    let (format, processed, _dropped) = qos.format_processed_dropped();
+    if format != gst::Format::Default || processed < 0 {
+        return None;
+    }
+
+    let processed = processed as u64;
+    let delta = processed.saturating_sub(*last_processed);
+    *last_processed = processed;
+
+    (delta > 0).then_some(delta)
+}
+
+/// Steps [`RecorderConfig::video_bitrate_kbps`] down toward
+/// `adaptive_bitrate_min_kbps` on every `Qos`-reported drop (see
+/// [`qos_dropped_delta`]), and back up toward the configured ceiling once
+/// [`spawn_recovery`](Self::spawn_recovery)'s thread has seen
+/// `adaptive_bitrate_recovery_secs` pass without one, so a degraded SD card
+/// trades footage quality for keeping up with capture instead of dropping
+/// frames outright. Only constructed for [`SimpleMode`], whose persistent
+/// pipeline's `Qos` messages actually originate from the encoder this steps;
+/// see [`RecorderConfig::adaptive_bitrate_enabled`].
+struct AdaptiveBitrate {
+    encoder: gst::Element,
+    /// The encoder's own bitrate property name/unit: `("bitrate", 1)` for
+    /// x264enc/x265enc (already kbit/s), `("target-bitrate", 1)` for av1enc
+    /// (also kbit/s), `("video-bitrate", 1000)` for the V4L2 stateful codec
+    /// driver (bit/s). Mirrors the unit handling in the `encoder_fragment`
+    /// construction in [`GstreamerRecorder::new`].
+    property: &'static str,
+    unit_scale: u32,
+    ceiling_kbps: u32,
+    min_kbps: u32,
+    step_kbps: u32,
+    current_kbps: AtomicU32,
+    /// Monotonic timestamp of the last drop report; the recovery thread only
+    /// steps back up once this has been quiet for `recovery_interval`.
+    last_drop_at: Mutex<Instant>,
+}
+
+impl AdaptiveBitrate {
+    fn new(
+        encoder: gst::Element,
+        codec: VideoCodec,
+        encoder_kind: Encoder,
+        ceiling_kbps: u32,
+        min_kbps: u32,
+        step_kbps: u32,
+    ) -> Self {
+        let (property, unit_scale) = match (codec, encoder_kind) {
+            (VideoCodec::Av1, Encoder::Software) => ("target-bitrate", 1),
+            (_, Encoder::Software) => ("bitrate", 1),
+            (_, Encoder::Hardware) => ("video-bitrate", 1000),
+        };
+        Self {
+            encoder,
+            property,
+            unit_scale,
+            ceiling_kbps,
+            min_kbps: min_kbps.min(ceiling_kbps),
+            step_kbps: step_kbps.max(1),
+            current_kbps: AtomicU32::new(ceiling_kbps),
+            last_drop_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn apply(&self, kbps: u32) {
+        self.encoder.set_property(self.property, kbps * self.unit_scale);
+    }
+
+    /// Called from the owning pipeline's `Qos` handler for every batch of
+    /// newly-dropped frames.
+    fn on_dropped_frames(&self) {
+        *self.last_drop_at.lock().unwrap() = Instant::now();
+
+        let previous = self.current_kbps.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |kbps| {
+            (kbps > self.min_kbps).then(|| kbps.saturating_sub(self.step_kbps).max(self.min_kbps))
+        });
+        if let Ok(previous) = previous {
+            let new_kbps = previous.saturating_sub(self.step_kbps).max(self.min_kbps);
+            warn!("stepping recording bitrate down from {previous} to {new_kbps} kbit/s after dropped frames");
+            self.apply(new_kbps);
+        }
+    }
+
+    /// Spawns the thread that steps the bitrate back up by `step_kbps`
+    /// toward `ceiling_kbps` every `recovery_interval` of no reported drops.
+    /// Runs for the process's lifetime, same as the bus-watching threads.
+    fn spawn_recovery(self: Arc<Self>, recovery_interval: Duration) {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(recovery_interval);
+
+            if self.last_drop_at.lock().unwrap().elapsed() < recovery_interval {
+                continue;
+            }
+
+            let previous = self.current_kbps.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |kbps| {
+                (kbps < self.ceiling_kbps).then(|| (kbps + self.step_kbps).min(self.ceiling_kbps))
+            });
+            if let Ok(previous) = previous {
+                let new_kbps = (previous + self.step_kbps).min(self.ceiling_kbps);
+                info!("recording bitrate recovered to {new_kbps} kbit/s after {recovery_interval:?} without dropped frames");
+                self.apply(new_kbps);
+                *self.last_drop_at.lock().unwrap() = Instant::now();
+            }
+        });
+    }
+}
+
+/// Like [`watch_pipeline_bus`], but specific to [`SimpleMode`]'s persistent
+/// pipeline: an `Error` message that looks like the camera device
+/// disappearing (see [`is_device_lost`]) while a recording is `armed` is
+/// treated as recoverable rather than left for the operator to notice and
+/// restart the service. [`PrerollMode`]'s per-trigger pipelines are much
+/// shorter-lived and still use the plain [`watch_pipeline_bus`].
+fn watch_simple_mode_bus(
+    pipeline: gst::Pipeline,
+    location_source: Arc<LocationSource>,
+    stop_waiter: Arc<Mutex<Option<mpsc::Sender<StopOutcome>>>>,
+    armed: Arc<AtomicBool>,
+    source_device: PathBuf,
+    source: CaptureSource,
+    on_fatal_error: Arc<dyn Fn() + Send + Sync>,
+    on_dropped_frames: Arc<dyn Fn(u64) + Send + Sync>,
+    frames: Arc<AtomicU64>,
+    adaptive_bitrate: Option<Arc<AdaptiveBitrate>>,
+) {
+    let bus = pipeline.bus().expect("pipeline should have a bus");
+
+    std::thread::spawn(move || {
+        let mut last_dropped = 0u64;
+        let mut last_processed = 0u64;
+
+        for message in bus.iter_timed(gst::ClockTime::NONE) {
+            match message.view() {
+                MessageView::Error(error) => {
+                    error!(
+                        "recording pipeline error from {}: {} ({:?})",
+                        error
+                            .src()
+                            .map(|source| source.path_string())
+                            .unwrap_or_else(|| "<unknown>".into()),
+                        error.error(),
+                        error.debug(),
+                    );
+                    on_fatal_error();
+
+                    if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                        let _ = waiter.send(StopOutcome::Errored);
+                    }
+
+                    if armed.load(Ordering::Relaxed) && is_device_lost(&error.error()) {
+                        warn!("capture device {} appears to have been lost, attempting recovery", source_device.display());
+
+                        if let Err(error) = pipeline.set_state(gst::State::Null) {
+                            error!("failed to stop recording pipeline before recovery: {error}");
+                        }
+
+                        spawn_device_recovery(
+                            pipeline.clone(),
+                            Arc::clone(&location_source),
+                            Arc::clone(&armed),
+                            source_device.clone(),
+                            source,
+                        );
+                    }
+                }
+                MessageView::Eos(_) => {
+                    debug!("pipeline reported end-of-stream");
+
+                    if let Some(waiter) = stop_waiter.lock().unwrap().take() {
+                        let _ = waiter.send(StopOutcome::Finalized);
+                    }
+
+                    // The pipeline is about to be moved to `Null` (resetting
+                    // every element's internal QoS counters) as part of
+                    // finalizing, so the next recording's `Qos` messages
+                    // start counting from zero again too.
+                    last_dropped = 0;
+                    last_processed = 0;
+                }
+                MessageView::Qos(qos) => {
+                    if let Some(delta) = qos_dropped_delta(&qos, &mut last_dropped) {
+                        warn!("recording pipeline dropped {delta} frame(s) ({last_dropped} total this recording)");
+                        on_dropped_frames(delta);
+                        if let Some(adaptive_bitrate) = &adaptive_bitrate {
+                            adaptive_bitrate.on_dropped_frames();
+                        }
+                    }
+                    if let Some(delta) = qos_processed_delta(&qos, &mut last_processed) {
+                        frames.fetch_add(delta, Ordering::Relaxed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+/// Polls for `source_device` to reappear after [`watch_simple_mode_bus`]
+/// detected it was lost, and once it does, gives the pipeline a fresh output
+/// location and moves it back to `Playing`. Gives up after
+/// [`DEVICE_RECOVERY_TIMEOUT`], or immediately if `armed` goes false (the
+/// recording was stopped normally while recovery was still waiting).
+///
+/// `source_device` existing on disk only means something for
+/// [`CaptureSource::V4l2`] (a `/dev/videoN` node); `libcamerasrc` identifies
+/// cameras by name rather than a path that disappears, so for
+/// [`CaptureSource::Libcamera`] this just retries blindly on the same
+/// interval instead of checking for a path.
+fn spawn_device_recovery(
+    pipeline: gst::Pipeline,
+    location_source: Arc<LocationSource>,
+    armed: Arc<AtomicBool>,
+    source_device: PathBuf,
+    source: CaptureSource,
+) {
+    std::thread::spawn(move || {
+        let started_at = Instant::now();
+
+        loop {
+            if !armed.load(Ordering::Relaxed) {
+                debug!("recording was stopped while waiting for {} to come back, abandoning recovery", source_device.display());
+                return;
+            }
+
+            if started_at.elapsed() > DEVICE_RECOVERY_TIMEOUT {
+                error!(
+                    "gave up waiting for capture device {} to come back after {:?}",
+                    source_device.display(),
+                    DEVICE_RECOVERY_TIMEOUT
+                );
+                return;
+            }
+
+            let device_present = match source {
+                CaptureSource::V4l2 => source_device.exists(),
+                CaptureSource::Libcamera => true,
+            };
+
+            if !device_present {
+                std::thread::sleep(DEVICE_RECOVERY_POLL_INTERVAL);
+                continue;
+            }
+
+            let location = location_source.next();
+            match pipeline.by_name("sink") {
+                Some(sink) => sink.set_property("location", &location),
+                None => error!("recording pipeline has no element named \"sink\" to set location on"),
+            }
+
+            if let Err(error) = pipeline.set_state(gst::State::Playing) {
+                warn!("capture device {} reappeared but pipeline failed to restart ({error}), retrying", source_device.display());
+                std::thread::sleep(DEVICE_RECOVERY_POLL_INTERVAL);
+                continue;
+            }
+
+            info!(
+                "recovered capture device {} after {:?}, resumed recording at {location}",
+                source_device.display(),
+                started_at.elapsed()
+            );
+            return;
+        }
+    });
+}
+
+/// How often [`spawn_frame_stall_watchdog`] checks whether the pipeline's
+/// frame counter has moved.
+const FRAME_STALL_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Restarts [`SimpleMode`]'s pipeline with a fresh output location if
+/// `frames` hasn't advanced for `frame_stall_timeout` while a recording is
+/// `armed`, so a wedged sensor or stuck driver (which rarely surfaces as a
+/// bus `Error`, unlike a lost device) produces a fresh recording instead of
+/// an indefinitely-growing frozen file. Reuses the same best-effort
+/// `Qos`-derived `frames` counter [`watch_simple_mode_bus`] already
+/// maintains, so - like that counter - this only fires if the pipeline
+/// emits `Qos` messages at all.
+fn spawn_frame_stall_watchdog(
+    pipeline: gst::Pipeline,
+    location_source: Arc<LocationSource>,
+    armed: Arc<AtomicBool>,
+    frames: Arc<AtomicU64>,
+    frame_stall_timeout: Duration,
+) {
+    std::thread::spawn(move || {
+        let mut last_frames = frames.load(Ordering::Relaxed);
+        let mut last_progress = Instant::now();
+
+        loop {
+            std::thread::sleep(FRAME_STALL_POLL_INTERVAL);
+
+            if !armed.load(Ordering::Relaxed) {
+                last_frames = frames.load(Ordering::Relaxed);
+                last_progress = Instant::now();
+                continue;
+            }
+
+            let current = frames.load(Ordering::Relaxed);
+            if current != last_frames {
+                last_frames = current;
+                last_progress = Instant::now();
+                continue;
+            }
+
+            if last_progress.elapsed() < frame_stall_timeout {
+                continue;
+            }
+
+            warn!(
+                "no frames from recording pipeline for {:?} while armed, tearing it down and rebuilding it",
+                last_progress.elapsed()
+            );
+
+            if let Err(error) = pipeline.set_state(gst::State::Null) {
+                error!("failed to stop stalled recording pipeline: {error}");
+                continue;
+            }
+
+            let location = location_source.next();
+            match pipeline.by_name("sink") {
+                Some(sink) => sink.set_property("location", &location),
+                None => error!("recording pipeline has no element named \"sink\" to set location on"),
+            }
+
+            if let Err(error) = pipeline.set_state(gst::State::Playing) {
+                error!("failed to restart stalled recording pipeline: {error}");
+            } else {
+                info!("rebuilt stalled recording pipeline, new location = {location}");
+            }
+
+            last_frames = frames.load(Ordering::Relaxed);
+            last_progress = Instant::now();
+        }
+    });
+}
+
+/// Sends EOS on `pipeline` and waits for it to finish finalizing (or times
+/// out) before moving it to `Null`, using the same handshake in both
+/// [`SimpleMode`] and [`PrerollMode`].
+fn stop_and_finalize(
+    pipeline: &gst::Pipeline,
+    stop_waiter: &Mutex<Option<mpsc::Sender<StopOutcome>>>,
+    send_eos: impl FnOnce() -> bool,
+) {
+    let (sender, receiver) = mpsc::channel();
+    *stop_waiter.lock().unwrap() = Some(sender);
+
+    if !send_eos() {
+        warn!("failed to send EOS to recording pipeline, file may not finalize cleanly");
+        stop_waiter.lock().unwrap().take();
+    } else {
+        match receiver.recv_timeout(STOP_TIMEOUT) {
+            Ok(StopOutcome::Finalized) => {}
+            Ok(StopOutcome::Errored) => {
+                warn!("recording pipeline errored while finalizing, file may be invalid");
+            }
+            Err(_) => {
+                warn!("timed out waiting for recording pipeline to finalize");
+                stop_waiter.lock().unwrap().take();
+            }
+        }
+    }
+
+    if let Err(error) = pipeline.set_state(gst::State::Null) {
+        error!("failed to stop recording pipeline: {error}");
+    }
+}
+
+/// The long-standing behavior: one persistent pipeline, driven between
+/// `Playing` (while a recording is armed) and `Null` (otherwise).
+///
+/// `splitmuxsink` resets its own fragment counter every time the pipeline
+/// passes through `Null`, so reusing one `location` pattern across triggers
+/// would make every recording after the first overwrite the last one's
+/// first fragment. [`SimpleMode::start`] works around this by substituting
+/// a fresh location from `location_source` into the sink before each
+/// `Playing`.
+struct SimpleMode {
+    pipeline: gst::Pipeline,
+    location_source: Arc<LocationSource>,
+    stop_waiter: Arc<Mutex<Option<mpsc::Sender<StopOutcome>>>>,
+    /// Whether a recording is currently supposed to be running, i.e.
+    /// whether [`watch_simple_mode_bus`] should treat a device-loss error as
+    /// something to recover from rather than a pipeline sitting idle
+    /// between triggers.
+    armed: Arc<AtomicBool>,
+    /// The current recording's processed-frame count so far, per
+    /// [`qos_processed_delta`]. Reset to zero on every `start()`.
+    frames: Arc<AtomicU64>,
+}
+
+impl SimpleMode {
+    /// Returns the location the pipeline was set to record to, for callers
+    /// that want to log or post-process the resulting file.
+    fn start(&self) -> String {
+        self.armed.store(true, Ordering::Relaxed);
+        self.frames.store(0, Ordering::Relaxed);
+
+        let location = self.location_source.next();
+        debug!("starting recording pipeline, location = {location}");
+
+        match self.pipeline.by_name("sink") {
+            Some(sink) => sink.set_property("location", &location),
+            None => error!("recording pipeline has no element named \"sink\" to set location on"),
+        }
+
+        if let Err(error) = self.pipeline.set_state(gst::State::Playing) {
+            error!("failed to start recording pipeline: {error}");
+        }
+
+        location
+    }
+
+    /// Returns the recording's best-effort frame count; see
+    /// [`qos_processed_delta`].
+    fn stop(&self) -> u64 {
+        self.armed.store(false, Ordering::Relaxed);
+
+        debug!("stopping recording pipeline");
+        stop_and_finalize(&self.pipeline, &self.stop_waiter, || {
+            self.pipeline.send_event(gst::event::Eos::new())
+        });
+
+        self.frames.load(Ordering::Relaxed)
+    }
+}
+
+/// A per-trigger recording pipeline built and torn down by
+/// [`PrerollMode::start`]/[`PrerollMode::stop`], fed by pushing buffers into
+/// its `appsrc` rather than pulling from a live source.
+struct ActiveRecording {
+    pipeline: gst::Pipeline,
+    appsrc: AppSrc,
+    stop_waiter: Arc<Mutex<Option<mpsc::Sender<StopOutcome>>>>,
+    /// This recording's processed-frame count so far, per
+    /// [`qos_processed_delta`], on its own per-trigger pipeline.
+    frames: Arc<AtomicU64>,
+}
+
+/// Keeps a capture pipeline (`source ! ... ! appsink`) running continuously,
+/// tapping its encoded output into a ring buffer covering the last
+/// `preroll_duration`, so that when a trigger arrives the moments
+/// immediately before it aren't lost.
+///
+/// [`PrerollMode::start`] builds a fresh `appsrc ! h264parse ! splitmuxsink`
+/// pipeline per trigger, drains the ring buffer into it first, then keeps
+/// forwarding newly-tapped buffers into it until [`PrerollMode::stop`] sends
+/// EOS and tears it down. The always-on capture pipeline (and its ring
+/// buffer) is unaffected by a recording starting or stopping, so pre-roll
+/// keeps accumulating for the next trigger throughout.
+struct PrerollMode {
+    capture_pipeline: gst::Pipeline,
+    location_source: LocationSource,
+    segment_duration: Duration,
+    /// Parser element matching the codec the capture pipeline encodes with
+    /// (`"h264parse"`/`"h265parse"`/`"av1parse"`, or `"jpegparse"` if
+    /// [`GstreamerRecorder::new`] fell back to MJPEG), so the per-trigger
+    /// pipeline can mux the already-encoded buffers it's fed without
+    /// re-encoding them.
+    parser_element: &'static str,
+    /// `splitmuxsink` `muxer-factory`/`muxer-properties` fragment matching
+    /// `RecorderConfig::container`. See [`muxer_pipeline_fragment`].
+    muxer_fragment: &'static str,
+    /// ALSA capture branch to append to each per-trigger pipeline; see
+    /// [`audio_branch_fragment`]. Empty if `config.audio_capture_enabled` is
+    /// unset.
+    audio_fragment: String,
+    ring_buffer: Arc<Mutex<VecDeque<(Instant, gst::Buffer)>>>,
+    active: Arc<Mutex<Option<ActiveRecording>>>,
+    on_fatal_error: Arc<dyn Fn() + Send + Sync>,
+    on_dropped_frames: Arc<dyn Fn(u64) + Send + Sync>,
+    /// Set when `config.still_dual_stream_enabled` is on, so a still capture
+    /// can grab a frame off this already-running pipeline instead of opening
+    /// a second, independent one against the same device; see
+    /// [`GstreamerRecorder::still_tap`].
+    still_tap: Option<StillTap>,
+}
+
+impl PrerollMode {
+    /// Returns the location the fresh per-trigger pipeline was set to record
+    /// to, or `None` if it couldn't be built.
+    fn start(&self) -> Option<String> {
+        let location = self.location_source.next();
+        let max_size_time_ns = self.segment_duration.as_nanos();
+        let parser_element = self.parser_element;
+        let muxer_fragment = self.muxer_fragment;
+        let audio_fragment = &self.audio_fragment;
+
+        let description = format!(
+            "appsrc name=src is-live=true format=time do-timestamp=true ! {parser_element} ! \
+             splitmuxsink name=sink location={location} {muxer_fragment} \
+             max-size-time={max_size_time_ns}{audio_fragment}",
+        );
+
+        let pipeline = match gst::parse::launch(&description) {
+            Ok(element) => match element.downcast::<gst::Pipeline>() {
+                Ok(pipeline) => pipeline,
+                Err(_) => {
+                    error!("parsed per-trigger recording pipeline was not a gst::Pipeline");
+                    return None;
+                }
+            },
+            Err(error) => {
+                error!("failed to build per-trigger recording pipeline: {error}");
+                return None;
+            }
+        };
+
+        let appsrc = match pipeline.by_name("src").and_then(|element| element.downcast::<AppSrc>().ok()) {
+            Some(appsrc) => appsrc,
+            None => {
+                error!("per-trigger recording pipeline has no appsrc named \"src\"");
+                return None;
+            }
+        };
+
+        let stop_waiter = Arc::new(Mutex::new(None));
+        let frames = Arc::new(AtomicU64::new(0));
+        watch_pipeline_bus(
+            &pipeline,
+            Arc::clone(&stop_waiter),
+            Arc::clone(&self.on_fatal_error),
+            Arc::clone(&self.on_dropped_frames),
+            Arc::clone(&frames),
+        );
+
+        debug!("starting preroll-backed recording pipeline, location = {location}");
+        if let Err(error) = pipeline.set_state(gst::State::Playing) {
+            error!("failed to start per-trigger recording pipeline: {error}");
+            return None;
+        }
+
+        // Hold the ring buffer lock across both the backlog drain and
+        // arming `active`, so a buffer arriving from the capture thread in
+        // between can't be forwarded ahead of the pre-roll backlog.
+        let mut ring_buffer = self.ring_buffer.lock().unwrap();
+        let backlog: Vec<_> = ring_buffer.drain(..).map(|(_, buffer)| buffer).collect();
+        *self.active.lock().unwrap() =
+            Some(ActiveRecording { pipeline, appsrc: appsrc.clone(), stop_waiter, frames });
+
+        for buffer in backlog {
+            push_rebased(&appsrc, buffer);
+        }
+
+        Some(location)
+    }
+
+    /// Returns the recording's best-effort frame count, if one was active;
+    /// see [`qos_processed_delta`].
+    fn stop(&self) -> Option<u64> {
+        let Some(active) = self.active.lock().unwrap().take() else {
+            warn!("stop requested but no preroll-backed recording is currently active");
+            return None;
+        };
+
+        debug!("stopping preroll-backed recording pipeline");
+        stop_and_finalize(&active.pipeline, &active.stop_waiter, || {
+            active.appsrc.end_of_stream().is_ok()
+        });
+
+        Some(active.frames.load(Ordering::Relaxed))
+    }
+}
+
+/// A handle for grabbing a single JPEG snapshot off an already-running
+/// [`PrerollMode`] capture pipeline's `still_valve`/`still_sink` branch,
+/// instead of a still capture opening a second, independent pipeline against
+/// the same device. Cloning just bumps the underlying `GstElement`
+/// refcounts, same as cloning any other `gst::Element` handle.
+///
+/// Only exists when `config.still_dual_stream_enabled` is on; see
+/// [`GstreamerRecorder::still_tap`] and
+/// [`super::still::StillCapture::capture`].
+#[derive(Clone)]
+pub struct StillTap {
+    valve: gst::Element,
+    sink: gst::Element,
+}
+
+impl StillTap {
+    /// Points `still_sink` at `location`, opens `still_valve` until exactly
+    /// one buffer passes through, then closes it again. Blocks the calling
+    /// thread, like every other backend's still capture, for up to 5 seconds
+    /// before giving up.
+    pub fn capture(&self, location: &str) -> bool {
+        self.sink.set_property("location", location);
+
+        let (frame_tx, frame_rx) = mpsc::channel();
+        let frame_tx = Mutex::new(Some(frame_tx));
+        let pad = match self.valve.static_pad("src") {
+            Some(pad) => pad,
+            None => {
+                error!("still tap valve has no src pad");
+                return false;
+            }
+        };
+        let probe_id = pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, _info| {
+            if let Some(frame_tx) = frame_tx.lock().unwrap().take() {
+                let _ = frame_tx.send(());
+            }
+            gst::PadProbeReturn::Ok
+        });
+
+        self.valve.set_property("drop", false);
+        let captured = frame_rx.recv_timeout(Duration::from_secs(5)).is_ok();
+        self.valve.set_property("drop", true);
+
+        if let Some(probe_id) = probe_id {
+            pad.remove_probe(probe_id);
+        }
+
+        if captured {
+            debug!("captured still to {location} via gstreamer preroll tap");
+        } else {
+            error!("timed out waiting for a frame on the gstreamer still tap for {location}");
+        }
+        captured
+    }
+}
+
+/// Pushes `buffer` into `appsrc`, clearing its PTS/DTS first: it was
+/// timestamped against the always-on capture pipeline's clock, which has no
+/// relation to this fresh per-trigger pipeline's, so `do-timestamp` is left
+/// to assign new ones on arrival instead.
+fn push_rebased(appsrc: &AppSrc, mut buffer: gst::Buffer) {
+    if let Some(buffer_mut) = buffer.make_mut() {
+        buffer_mut.set_pts(gst::ClockTime::NONE);
+        buffer_mut.set_dts(gst::ClockTime::NONE);
+    }
+    if let Err(error) = appsrc.push_buffer(buffer) {
+        warn!("failed to push buffer into recording pipeline: {error}");
+    }
+}
+
+/// One of [`SimpleMode`] (the default) or [`PrerollMode`] (when
+/// `config.preroll_duration` is nonzero).
+enum Mode {
+    Simple(SimpleMode),
+    Preroll(PrerollMode),
+}
+
+/// Wraps a GStreamer pipeline that captures from a camera source, encodes to
+/// H.264 and muxes into one or more files per trigger via `splitmuxsink`,
+/// splitting into fragments of `config.segment_duration` when that's
+/// nonzero.
+pub struct GstreamerRecorder {
+    mode: Mode,
+    /// The most recent `start()`'s trigger sequence, read by the
+    /// `frame_metadata` pad probe (see [`install_frame_metadata_probe`]) so
+    /// it can stamp each frame's SEI metadata without threading the sequence
+    /// through the pipeline description itself. Only meaningful when
+    /// `config.embed_frame_metadata` is set; otherwise nothing reads it.
+    current_trigger_sequence: Arc<AtomicU64>,
+}
+
+impl GstreamerRecorder {
+    /// Builds the capture pipeline (and, if `config.preroll_duration` is
+    /// nonzero, starts it immediately so its ring buffer starts filling).
+    /// Returns `Err` (rather than panicking) if GStreamer cannot be
+    /// initialized, the output directory cannot be created, or the pipeline
+    /// description fails to parse, since all three stem from
+    /// user-configurable values and should be handled with the same
+    /// log-and-exit pattern used for the GPIO chip/line at startup.
+    ///
+    /// `camera_id` and `flight_session` are only consulted when
+    /// `config.naming` is [`NamingMode::Structured`].
+    ///
+    /// `on_fatal_error` is invoked (from a bus-watching thread) whenever a
+    /// recording pipeline reports an error, so callers can react, e.g. by no
+    /// longer petting the systemd watchdog.
+    ///
+    /// `on_dropped_frames` is invoked (also from a bus-watching thread) with
+    /// however many frames a `Qos` message reports as newly dropped since
+    /// the last one, so callers can count them (e.g.
+    /// [`crate::metrics::CameraMetrics::record_dropped_frames`]) instead of
+    /// only finding out about a gap in the footage during editing. See
+    /// [`qos_dropped_delta`].
+    ///
+    /// `mavlink_feedback` is sampled for `config.osd_overlay_enabled`'s
+    /// telemetry overlay; see [`osd_overlay`].
+    ///
+    /// `on_degraded_encoding` is invoked once, with a human-readable reason,
+    /// if `config.encoder = Encoder::Hardware` was requested but the element
+    /// isn't installed/registered or doesn't support the requested capture
+    /// settings, in which case this falls back to `jpegenc`/MJPEG-in-
+    /// matroska so the camera still comes home with usable footage instead
+    /// of failing to start; see [`crate::manifest::Manifest::
+    /// record_degraded_encoding`].
+    pub fn new(
+        config: &RecorderConfig,
+        camera_id: &str,
+        flight_session: &str,
+        on_fatal_error: impl Fn() + Send + Sync + 'static,
+        on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+        on_degraded_encoding: impl Fn(&str) + Send + Sync + 'static,
+        mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+    ) -> Result<Self, String> {
+        gst::init().map_err(|error| format!("failed to initialize gstreamer: {error}"))?;
+
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+
+        let (encoder_element, parser_element) = encoder_elements(config.codec, config.encoder);
+        let capability = probe_encoder_capability(
+            encoder_element,
+            config.capture_width,
+            config.capture_height,
+            config.capture_framerate,
+        );
+        let (encoder_element, parser_element, mjpeg_fallback) = match (config.encoder, capability) {
+            (_, Ok(())) => (encoder_element, parser_element, false),
+            (Encoder::Software, Err(error)) => return Err(error),
+            (Encoder::Hardware, Err(error)) => {
+                let reason = format!("hardware encoder unavailable ({error}); fell back to MJPEG-in-matroska");
+                warn!(
+                    "{reason}, so this camera still comes home with usable footage instead of \
+                     failing to start"
+                );
+                on_degraded_encoding(&reason);
+                ("jpegenc", "jpegparse", true)
+            }
+        };
+
+        let (muxer_fragment, container_extension) = if mjpeg_fallback {
+            if config.container != ContainerFormat::Matroska {
+                warn!(
+                    "MJPEG fallback recording requires the matroska container; overriding \
+                     container={:?} for this camera",
+                    config.container
+                );
+            }
+            muxer_pipeline_fragment(ContainerFormat::Matroska)
+        } else {
+            muxer_pipeline_fragment(config.container)
+        };
+
+        let location_source = match config.naming {
+            NamingMode::Pattern => LocationSource::Pattern {
+                location_pattern: config.output_dir.join(&config.file_pattern).display().to_string(),
+                next_trigger_id: AtomicU64::new(0),
+            },
+            NamingMode::Structured => LocationSource::Structured {
+                naming: NamingScheme::new(&config.output_dir, flight_session, camera_id)?,
+                extension: container_extension,
+            },
+        };
+
+        let source = match config.source {
+            CaptureSource::V4l2 => {
+                format!("v4l2src device={}", config.source_device.display())
+            }
+            CaptureSource::Libcamera => {
+                format!("libcamerasrc camera-name={}", config.source_device.display())
+            }
+        };
+
+        // x264enc/x265enc take `tune`/`key-int-max` properties for
+        // low-latency, seekable output; av1enc has no `tune` and uses
+        // `keyframe-max-dist` for the GOP length instead; jpegenc (the MJPEG
+        // fallback) takes neither, and has no bitrate property at all - it's
+        // quality-driven, not rate-driven; the V4L2 stateful codec driver has
+        // no equivalent properties and is left at its defaults. Named
+        // `video_encoder` so [`AdaptiveBitrate`] can look it up by name and
+        // adjust its bitrate property live.
+        let encoder_fragment = match (config.codec, config.encoder) {
+            _ if mjpeg_fallback => format!("{encoder_element} name=video_encoder"),
+            (VideoCodec::H264 | VideoCodec::H265, Encoder::Software) => {
+                let mut fragment = format!("{encoder_element} name=video_encoder tune=zerolatency key-int-max=30");
+                if let Some(video_bitrate_kbps) = config.video_bitrate_kbps {
+                    // x264enc/x265enc's `bitrate` property is already in kbit/s.
+                    fragment.push_str(&format!(" bitrate={video_bitrate_kbps}"));
+                }
+                fragment
+            }
+            (VideoCodec::Av1, Encoder::Software) => {
+                let mut fragment = format!("{encoder_element} name=video_encoder keyframe-max-dist=30");
+                if let Some(video_bitrate_kbps) = config.video_bitrate_kbps {
+                    // av1enc's `target-bitrate` property is already in kbit/s.
+                    fragment.push_str(&format!(" target-bitrate={video_bitrate_kbps}"));
+                }
+                fragment
+            }
+            (_, Encoder::Hardware) => {
+                let mut fragment = format!("{encoder_element} name=video_encoder");
+                if let Some(video_bitrate_kbps) = config.video_bitrate_kbps {
+                    // The V4L2 stateful codec driver's `video-bitrate` property is in bit/s.
+                    fragment.push_str(&format!(" video-bitrate={}", video_bitrate_kbps * 1000));
+                }
+                fragment
+            }
+        };
+
+        if config.embed_frame_metadata && (config.codec == VideoCodec::Av1 || mjpeg_fallback) {
+            warn!(
+                "embed_frame_metadata requires H264/H265's SEI NAL passthrough, which neither \
+                 av1enc nor the MJPEG fallback's jpegenc have an equivalent for; ignoring it for \
+                 this recording"
+            );
+        }
+        let embed_frame_metadata = config.embed_frame_metadata && config.codec != VideoCodec::Av1 && !mjpeg_fallback;
+
+        if config.zero_copy_enabled && (config.source != CaptureSource::V4l2 || config.encoder != Encoder::Hardware || mjpeg_fallback) {
+            warn!(
+                "zero_copy_enabled requires the v4l2 capture source and the hardware encoder; \
+                 ignoring it and copying frames through videoconvert as usual"
+            );
+        }
+        let zero_copy =
+            config.zero_copy_enabled && config.source == CaptureSource::V4l2 && config.encoder == Encoder::Hardware && !mjpeg_fallback;
+
+        let orientation_requested =
+            config.orientation != Orientation::None || config.crop_left != 0 || config.crop_right != 0 || config.crop_top != 0 || config.crop_bottom != 0;
+        if zero_copy && orientation_requested {
+            warn!(
+                "orientation/crop requires copying frames through videoconvert, which \
+                 zero_copy_enabled exists to avoid; ignoring zero_copy_enabled for this camera"
+            );
+        }
+        let zero_copy = zero_copy && !orientation_requested;
+
+        let source = if zero_copy { format!("{source} io-mode=dmabuf-import") } else { source };
+
+        // `v4l2h264enc`/`v4l2h265enc` accept NV12 DMABUFs directly from
+        // `v4l2src`'s `dmabuf-import` io-mode, so the frame never leaves the
+        // capture device's own memory until the encoder reads it; YUY2
+        // needs `videoconvert` to reach NV12 first, which is exactly the
+        // CPU copy zero-copy mode exists to avoid.
+        let (capture_caps, convert_fragment) = if zero_copy {
+            let caps = capture_caps_fragment("NV12", config.capture_width, config.capture_height, config.capture_framerate);
+            (caps, "")
+        } else {
+            let caps = capture_caps_fragment("YUY2", config.capture_width, config.capture_height, config.capture_framerate);
+            (caps, "videoconvert ! ")
+        };
+
+        let orientation_fragment = orientation_fragment(config);
+
+        // Tagged so [`install_frame_metadata_probe`] can find it by name and
+        // attach the SEI meta before the encoder consumes each raw frame.
+        let frame_metadata_fragment = if embed_frame_metadata { "identity name=frame_metadata ! " } else { "" };
+
+        let current_trigger_sequence = Arc::new(AtomicU64::new(0));
+
+        let on_fatal_error: Arc<dyn Fn() + Send + Sync> = Arc::new(on_fatal_error);
+        let on_dropped_frames: Arc<dyn Fn(u64) + Send + Sync> = Arc::new(on_dropped_frames);
+
+        let mode = if config.preroll_duration.is_zero() {
+            if config.rtsp_preview_enabled {
+                warn!(
+                    "rtsp preview requires an always-on capture pipeline, which only exists when \
+                     preroll_duration is set; ignoring rtsp_preview_enabled"
+                );
+            }
+            if config.webrtc_preview_enabled {
+                warn!(
+                    "webrtc preview requires an always-on capture pipeline, which only exists when \
+                     preroll_duration is set; ignoring webrtc_preview_enabled"
+                );
+            }
+            if config.srt_output_enabled {
+                warn!(
+                    "srt output requires an always-on capture pipeline, which only exists when \
+                     preroll_duration is set; ignoring srt_output_enabled"
+                );
+            }
+            if config.osd_overlay_enabled {
+                warn!(
+                    "osd overlay requires an always-on capture pipeline, which only exists when \
+                     preroll_duration is set; ignoring osd_overlay_enabled"
+                );
+            }
+
+            let initial_location = location_source.next();
+            let audio_fragment = audio_branch_fragment(config);
+            let description = format!(
+                "{source} ! {capture_caps} ! {convert_fragment}{orientation_fragment}\
+                 {frame_metadata_fragment}{encoder_fragment} ! {parser_element} ! \
+                 splitmuxsink name=sink location={initial_location} {muxer_fragment} \
+                 max-size-time={}{audio_fragment}",
+                config.segment_duration.as_nanos(),
+            );
+
+            let pipeline = gst::parse::launch(&description)
+                .map_err(|error| format!("failed to parse recording pipeline description: {error}"))?
+                .downcast::<gst::Pipeline>()
+                .map_err(|_| "parsed recording pipeline was not a gst::Pipeline".to_string())?;
+
+            if embed_frame_metadata {
+                install_frame_metadata_probe(&pipeline, Arc::clone(&current_trigger_sequence));
+            }
+
+            let adaptive_bitrate = if config.adaptive_bitrate_enabled && mjpeg_fallback {
+                warn!(
+                    "adaptive bitrate control has no bitrate property to adjust on jpegenc, the \
+                     MJPEG fallback's quality-driven encoder; ignoring adaptive_bitrate_enabled"
+                );
+                None
+            } else if config.adaptive_bitrate_enabled {
+                match config.video_bitrate_kbps {
+                    None => {
+                        warn!(
+                            "adaptive bitrate control requires video_bitrate_kbps to be set (as the \
+                             ceiling to recover back to); ignoring adaptive_bitrate_enabled"
+                        );
+                        None
+                    }
+                    Some(ceiling_kbps) => match pipeline.by_name("video_encoder") {
+                        Some(encoder) => Some(Arc::new(AdaptiveBitrate::new(
+                            encoder,
+                            config.codec,
+                            config.encoder,
+                            ceiling_kbps,
+                            config.adaptive_bitrate_min_kbps,
+                            config.adaptive_bitrate_step_kbps,
+                        ))),
+                        None => {
+                            error!("recording pipeline has no encoder named \"video_encoder\"; ignoring adaptive_bitrate_enabled");
+                            None
+                        }
+                    },
+                }
+            } else {
+                None
+            };
+            if let Some(adaptive_bitrate) = &adaptive_bitrate {
+                Arc::clone(adaptive_bitrate)
+                    .spawn_recovery(Duration::from_secs(config.adaptive_bitrate_recovery_secs));
+            }
+
+            let location_source = Arc::new(location_source);
+            let stop_waiter = Arc::new(Mutex::new(None));
+            let armed = Arc::new(AtomicBool::new(false));
+            let frames = Arc::new(AtomicU64::new(0));
+            watch_simple_mode_bus(
+                pipeline.clone(),
+                Arc::clone(&location_source),
+                Arc::clone(&stop_waiter),
+                Arc::clone(&armed),
+                config.source_device.clone(),
+                config.source,
+                Arc::clone(&on_fatal_error),
+                Arc::clone(&on_dropped_frames),
+                Arc::clone(&frames),
+                adaptive_bitrate,
+            );
+
+            if !config.frame_stall_timeout.is_zero() {
+                spawn_frame_stall_watchdog(
+                    pipeline.clone(),
+                    Arc::clone(&location_source),
+                    Arc::clone(&armed),
+                    Arc::clone(&frames),
+                    config.frame_stall_timeout,
+                );
+            }
+
+            Mode::Simple(SimpleMode { pipeline, location_source, stop_waiter, armed, frames })
+        } else {
+            if !config.frame_stall_timeout.is_zero() {
+                warn!(
+                    "frame stall watchdog requires the non-preroll capture pipeline, which only \
+                     exists when preroll_duration is unset; ignoring frame_stall_timeout"
+                );
+            }
+            if config.adaptive_bitrate_enabled {
+                warn!(
+                    "adaptive bitrate control requires the non-preroll capture pipeline, since with \
+                     preroll_duration set the dropped-frame reports come from the per-trigger mux \
+                     pipeline downstream of the encoder, not the encoder itself; ignoring \
+                     adaptive_bitrate_enabled"
+                );
+            }
+
+            let osd_overlay_element =
+                |name: &'static str| if config.osd_overlay_enabled { Some(name) } else { None };
+
+            let mut preview_branches = String::new();
+            if config.rtsp_preview_enabled {
+                preview_branches.push_str(&format!(
+                    " {}",
+                    rtsp_preview::preview_branch(config.rtsp_preview_bitrate_kbps, osd_overlay_element("osd_rtsp")),
+                ));
+            }
+            if config.webrtc_preview_enabled {
+                preview_branches.push_str(&format!(
+                    " {}",
+                    webrtc_preview::preview_branch(
+                        &config.webrtc_preview_whip_endpoint,
+                        config.webrtc_preview_bitrate_kbps,
+                        osd_overlay_element("osd_webrtc"),
+                    ),
+                ));
+            }
+            if config.srt_output_enabled {
+                preview_branches.push_str(&format!(
+                    " {}",
+                    srt_output::output_branch(
+                        &config.srt_output_address,
+                        config.srt_output_bitrate_kbps,
+                        osd_overlay_element("osd_srt"),
+                    ),
+                ));
+            }
+
+            let still_tap_enabled = config.still_capture && config.still_dual_stream_enabled;
+            if still_tap_enabled {
+                preview_branches.push_str(
+                    " preview_tee. ! queue ! valve name=still_valve drop=true ! jpegenc ! \
+                     multifilesink name=still_sink location=/dev/null max-files=1",
+                );
+            }
+
+            let description = if config.rtsp_preview_enabled
+                || config.webrtc_preview_enabled
+                || config.srt_output_enabled
+                || still_tap_enabled
+            {
+                format!(
+                    "{source} ! {capture_caps} ! {convert_fragment}{orientation_fragment}tee name=preview_tee ! \
+                     queue ! {frame_metadata_fragment}{encoder_fragment} ! {parser_element} ! \
+                     appsink name=tap emit-signals=false sync=false{preview_branches}",
+                )
+            } else {
+                format!(
+                    "{source} ! {capture_caps} ! {convert_fragment}{orientation_fragment}\
+                     {frame_metadata_fragment}{encoder_fragment} ! {parser_element} ! \
+                     appsink name=tap emit-signals=false sync=false",
+                )
+            };
+
+            let capture_pipeline = gst::parse::launch(&description)
+                .map_err(|error| format!("failed to parse capture pipeline description: {error}"))?
+                .downcast::<gst::Pipeline>()
+                .map_err(|_| "parsed capture pipeline was not a gst::Pipeline".to_string())?;
+
+            if embed_frame_metadata {
+                install_frame_metadata_probe(&capture_pipeline, Arc::clone(&current_trigger_sequence));
+            }
+
+            let appsink = capture_pipeline
+                .by_name("tap")
+                .and_then(|element| element.downcast::<AppSink>().ok())
+                .ok_or_else(|| "capture pipeline has no appsink named \"tap\"".to_string())?;
+
+            if let Err(error) = capture_pipeline.set_state(gst::State::Playing) {
+                return Err(format!("failed to start capture pipeline: {error}"));
+            }
+
+            if config.rtsp_preview_enabled {
+                if let Err(error) = rtsp_preview::spawn(&config.rtsp_preview_address, &capture_pipeline) {
+                    error!("failed to start rtsp preview: {error}");
+                }
+            }
+            if config.webrtc_preview_enabled {
+                info!("webrtc preview pushing to {}", config.webrtc_preview_whip_endpoint);
+            }
+            if config.srt_output_enabled {
+                info!("srt output pushing to {}", config.srt_output_address);
+            }
+
+            if config.osd_overlay_enabled {
+                let session_id = format!("{flight_session}/{camera_id}");
+                let interval = Duration::from_secs_f64(config.osd_overlay_interval_secs.max(0.1));
+                for (enabled, element_name) in [
+                    (config.rtsp_preview_enabled, "osd_rtsp"),
+                    (config.webrtc_preview_enabled, "osd_webrtc"),
+                    (config.srt_output_enabled, "osd_srt"),
+                ] {
+                    if enabled {
+                        osd_overlay::spawn(
+                            &capture_pipeline,
+                            element_name,
+                            session_id.clone(),
+                            interval,
+                            Arc::clone(&mavlink_feedback),
+                        );
+                    }
+                }
+            }
+
+            let still_tap = if still_tap_enabled {
+                let valve = capture_pipeline
+                    .by_name("still_valve")
+                    .ok_or_else(|| "capture pipeline has no valve named \"still_valve\"".to_string())?;
+                let sink = capture_pipeline
+                    .by_name("still_sink")
+                    .ok_or_else(|| "capture pipeline has no sink named \"still_sink\"".to_string())?;
+                Some(StillTap { valve, sink })
+            } else {
+                None
+            };
+
+            let ring_buffer = Arc::new(Mutex::new(VecDeque::new()));
+            let active = Arc::new(Mutex::new(None));
+            let preroll_duration = config.preroll_duration;
+
+            spawn_capture_tap(appsink, Arc::clone(&ring_buffer), Arc::clone(&active), preroll_duration);
+
+            Mode::Preroll(PrerollMode {
+                capture_pipeline,
+                location_source,
+                segment_duration: config.segment_duration,
+                parser_element,
+                muxer_fragment,
+                audio_fragment: audio_branch_fragment(config),
+                ring_buffer,
+                active,
+                on_fatal_error,
+                on_dropped_frames,
+                still_tap,
+            })
+        };
+
+        Ok(Self { mode, current_trigger_sequence })
+    }
+
+    /// Begins (or, in preroll mode, arms) a recording, backdating it with
+    /// whatever pre-roll buffer is available if `config.preroll_duration`
+    /// is nonzero. Returns the location it was started at, or `None` if
+    /// preroll mode failed to build its per-trigger pipeline.
+    ///
+    /// `sequence` is stamped into every frame's SEI metadata (see
+    /// [`install_frame_metadata_probe`]) when `config.embed_frame_metadata`
+    /// is set; ignored otherwise.
+    pub fn start(&self, sequence: u64) -> Option<String> {
+        self.current_trigger_sequence.store(sequence, Ordering::Relaxed);
+        match &self.mode {
+            Mode::Simple(mode) => Some(mode.start()),
+            Mode::Preroll(mode) => mode.start(),
+        }
+    }
+
+    /// Sends EOS and waits for the muxer to flush before tearing the
+    /// recording pipeline down, so the output file is left in a valid
+    /// state. In preroll mode the always-on capture pipeline is left
+    /// running so pre-roll keeps accumulating for the next trigger. Returns
+    /// the recording's best-effort frame count; see [`qos_processed_delta`].
+    pub fn stop(&self) -> Option<u64> {
+        match &self.mode {
+            Mode::Simple(mode) => Some(mode.stop()),
+            Mode::Preroll(mode) => mode.stop(),
+        }
+    }
+
+    /// A handle for grabbing a still frame off this camera's already-running
+    /// capture pipeline, if `config.preroll_duration` and
+    /// `config.still_dual_stream_enabled` were both on when this was built.
+    /// `None` otherwise (including in [`Mode::Simple`], which has no
+    /// always-on pipeline to tap), in which case
+    /// [`super::still::StillCapture`] falls back to opening its own
+    /// independent pipeline.
+    pub fn still_tap(&self) -> Option<StillTap> {
+        match &self.mode {
+            Mode::Simple(_) => None,
+            Mode::Preroll(mode) => mode.still_tap.clone(),
+        }
+    }
+}
+
+/// Spawns the background thread that continuously pulls encoded buffers off
+/// `appsink`, keeps the trailing `preroll_duration` of them in
+/// `ring_buffer` (oldest evicted first), and forwards each one into
+/// `active`'s pipeline, if a recording is currently armed.
+fn spawn_capture_tap(
+    appsink: AppSink,
+    ring_buffer: Arc<Mutex<VecDeque<(Instant, gst::Buffer)>>>,
+    active: Arc<Mutex<Option<ActiveRecording>>>,
+    preroll_duration: Duration,
+) {
+    std::thread::spawn(move || loop {
+        let sample = match appsink.pull_sample() {
+            Ok(sample) => sample,
+            Err(_) => {
+                debug!("capture pipeline appsink stopped, pre-roll tap exiting");
+                break;
+            }
+        };
+
+        let Some(buffer) = sample.buffer_owned() else { continue };
+
+        let mut ring_buffer = ring_buffer.lock().unwrap();
+        ring_buffer.push_back((Instant::now(), buffer.copy()));
+        while ring_buffer.front().is_some_and(|(seen_at, _)| seen_at.elapsed() > preroll_duration) {
+            ring_buffer.pop_front();
+        }
+
+        if let Some(active) = active.lock().unwrap().as_ref() {
+            push_rebased(&active.appsrc, buffer);
+        }
+    });
+}
@@ -0,0 +1,476 @@
+//! In-process libcamera capture via the `libcamera` crate, as a more
+//! capable alternative to [`super::subprocess_backend`]'s `libcamera-vid`
+//! child process for CSI cameras.
+//!
+//! Configuring sensor mode, AE/AWB and frame duration limits here (rather
+//! than via `libcamera-vid`'s CLI flags) means a misconfiguration is caught
+//! at [`LibcameraNativeRecorder::new`] time instead of buried in a spawned
+//! process's stderr, and recording each frame's libcamera `SensorTimestamp`
+//! to a `.timestamps.csv` sidecar - nominally the same `CLOCK_MONOTONIC`
+//! domain as [`crate::trigger`]'s GPIO edge timestamps, and continuously
+//! drift-corrected against it via [`super::sensor_clock`] before being
+//! written - lets a caller correlate sensor frames with trigger pulses
+//! without an extra clock translation step, the same way
+//! [`super::v4l2_backend`] does for its kernel timestamps.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use libcamera::camera::CameraConfigurationStatus;
+use libcamera::camera_manager::CameraManager;
+use libcamera::control::ControlList;
+use libcamera::controls::{
+    AeEnable, AfMode, AfModeEnum, AnalogueGain, AwbEnable, ColourTemperature, ExposureTime, FrameDurationLimits,
+    LensPosition, ScalerCrop, SensorTimestamp,
+};
+use libcamera::framebuffer_allocator::FrameBufferAllocator;
+use libcamera::framebuffer_map::MemoryMappedFrameBuffer;
+use libcamera::geometry::{Rectangle, Size};
+use libcamera::stream::StreamRole;
+use log::{error, warn};
+
+use super::frame_writer::{self, FrameStats, FrameWriterHandle, RecordingState};
+use super::sensor_clock::DriftEstimator;
+use super::{CameraControls, RecorderConfig, RegionOfInterest};
+
+/// Sets whichever of `controls`' fields are present onto `list`, switching
+/// autofocus to manual first since `LensPosition` is ignored while `AfMode`
+/// is still `Continuous`/`Auto`. Mirrors [`super::v4l2_backend::apply_controls`]'s
+/// shape, just against libcamera's typed controls instead of raw V4L2 CIDs.
+fn set_camera_controls(list: &mut ControlList, controls: &CameraControls) {
+    if let Some(exposure_micros) = controls.exposure_micros {
+        let _ = list.set(ExposureTime(exposure_micros as i32));
+    }
+    if let Some(gain) = controls.gain {
+        let _ = list.set(AnalogueGain(gain));
+    }
+    if let Some(white_balance_kelvin) = controls.white_balance_kelvin {
+        let _ = list.set(ColourTemperature(white_balance_kelvin));
+    }
+    if let Some(focus_position) = controls.focus_position {
+        let _ = list.set(AfMode(AfModeEnum::Manual));
+        let _ = list.set(LensPosition(focus_position));
+    }
+}
+
+/// Converts `roi` (normalized `0.0..=1.0` against `frame_width`/`frame_height`)
+/// into a pixel [`Rectangle`] and sets it as the request's `ScalerCrop`.
+/// Clamps an out-of-bounds request (e.g. `x + width` past `1.0`) rather than
+/// rejecting it, same rationale as [`super::v4l2_backend::apply_roi`].
+fn set_roi(list: &mut ControlList, roi: (f32, f32, f32, f32), frame_width: u32, frame_height: u32) {
+    let (x, y, width, height) = roi;
+    let x = x.clamp(0.0, 1.0);
+    let y = y.clamp(0.0, 1.0);
+    let width = width.clamp(0.0, 1.0 - x);
+    let height = height.clamp(0.0, 1.0 - y);
+
+    let rect = Rectangle {
+        x: (x * frame_width as f32) as i32,
+        y: (y * frame_height as f32) as i32,
+        width: (width * frame_width as f32) as u32,
+        height: (height * frame_height as f32) as u32,
+    };
+    let _ = list.set(ScalerCrop(rect));
+}
+
+/// Drives capture directly against a libcamera camera via the `libcamera`
+/// crate, bypassing both GStreamer and the `libcamera-vid` subprocess.
+pub struct LibcameraNativeRecorder {
+    output_dir: PathBuf,
+    secondary_output_dir: Option<PathBuf>,
+    encryption_recipient: Option<String>,
+    file_pattern: String,
+    next_trigger_id: AtomicU64,
+    recording: Arc<Mutex<Option<RecordingState>>>,
+    /// Hands captured frames off to [`frame_writer`]'s dedicated writer
+    /// thread instead of writing them to disk from the capture thread.
+    writer: FrameWriterHandle,
+    /// Continuously refits the `SensorTimestamp` counter against
+    /// `CLOCK_MONOTONIC` so a long recording's sidecar timestamps don't drift
+    /// away from GPIO trigger timestamps. Shared with the capture thread,
+    /// which is the only place samples are recorded and corrections applied.
+    drift_estimator: Arc<DriftEstimator>,
+    /// Whether a recording is currently armed; checked by the capture
+    /// thread so frames are pulled off the camera continuously (and their
+    /// buffers requeued) but only written to disk while armed. Same "always
+    /// running, gated by a flag" shape as [`super::v4l2_backend`].
+    armed: Arc<AtomicBool>,
+    /// Set on every `start()`, cleared by the capture thread once it has
+    /// reported the first frame of the new recording via `on_first_frame`,
+    /// so later frames don't re-report it.
+    first_frame_pending: Arc<AtomicBool>,
+    /// A pending [`CameraControls`] change from [`Self::set_controls`],
+    /// applied and cleared by the capture thread (the sole owner of `camera`)
+    /// on its next loop iteration. Same idiom as
+    /// [`super::v4l2_backend::V4l2Recorder::pending_controls`].
+    pending_controls: Arc<Mutex<Option<CameraControls>>>,
+    /// The full-frame crop rectangle (`x, y, width, height`, normalized
+    /// `0.0..=1.0`) [`Self::set_roi`] merges [`RegionOfInterest`]'s
+    /// independent fields into before handing off to the capture thread,
+    /// since `ScalerCrop` sets the whole rectangle at once. Same idiom as
+    /// [`super::v4l2_backend::V4l2Recorder::current_roi`].
+    current_roi: Mutex<(f32, f32, f32, f32)>,
+    /// A pending crop rectangle from [`Self::set_roi`], applied and cleared
+    /// by the capture thread on its next loop iteration.
+    pending_roi: Arc<Mutex<Option<(f32, f32, f32, f32)>>>,
+}
+
+impl LibcameraNativeRecorder {
+    /// Acquires the camera named by `config.source_device`, negotiates a
+    /// video-recording stream at `config.capture_width`/`capture_height`
+    /// and `config.libcamera_sensor_mode` (whichever are set), applies
+    /// `config.libcamera_ae_enabled`/`libcamera_awb_enabled`/frame duration
+    /// limits, then starts the background capture thread. `on_fatal_error`
+    /// is invoked if the request queue reports the camera has stopped,
+    /// mirroring [`super::v4l2_backend::V4l2Recorder::new`]. `on_first_frame`
+    /// is invoked once per `start()`, with the `SensorTimestamp` (drift-
+    /// corrected against `CLOCK_MONOTONIC`, the domain [`crate::trigger`]'s
+    /// GPIO edge timestamps are in - see [`super::sensor_clock`]) of the
+    /// first frame written to the new recording, so callers can measure
+    /// trigger-to-frame latency. `on_dropped_frames` is
+    /// invoked whenever [`frame_writer`]'s writer thread falls more than
+    /// `config.write_queue_depth` frames behind and a captured frame has to
+    /// be dropped instead of blocking the capture thread. `on_frame` is
+    /// invoked once per frame actually written to disk, for
+    /// [`crate::metrics::CameraMetrics::record_frame`]'s jitter histogram.
+    /// `on_backpressure_action` is invoked alongside `on_dropped_frames`
+    /// with which [`super::BackpressureAction`] `config.backpressure_policy`
+    /// took.
+    ///
+    /// The [`CameraManager`] is leaked rather than kept in `Self`: libcamera
+    /// requires it to outlive every camera acquired from it, and this
+    /// recorder (like the camera itself) is expected to live for the
+    /// process's lifetime, so there's no meaningful moment to drop it early.
+    pub fn new(
+        config: &RecorderConfig,
+        on_fatal_error: impl Fn() + Send + Sync + 'static,
+        on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+        on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+        on_frame: impl Fn() + Send + Sync + 'static,
+        on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+        realtime_clock: Arc<crate::clock::RealtimeClock>,
+    ) -> Result<Self, String> {
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+        if let Some(secondary_output_dir) = &config.secondary_output_dir {
+            if let Err(error) = std::fs::create_dir_all(secondary_output_dir) {
+                warn!(
+                    "failed to create redundant recording output directory {}: {error}; continuing without it",
+                    secondary_output_dir.display()
+                );
+            }
+        }
+
+        let camera_manager: &'static CameraManager =
+            Box::leak(Box::new(CameraManager::new().map_err(|error| format!("failed to start libcamera: {error}"))?));
+
+        let camera_id = config.source_device.to_string_lossy().into_owned();
+        let camera = camera_manager
+            .cameras()
+            .iter()
+            .find(|camera| camera.id() == camera_id)
+            .ok_or_else(|| format!("no libcamera camera named {camera_id:?}"))?;
+
+        let mut camera = camera
+            .acquire()
+            .map_err(|error| format!("failed to acquire libcamera camera {camera_id:?}: {error}"))?;
+
+        let mut pipeline_config = camera
+            .generate_configuration(&[StreamRole::VideoRecording])
+            .ok_or_else(|| format!("libcamera camera {camera_id:?} has no video-recording role"))?;
+
+        {
+            let stream_config = pipeline_config.get_mut(0).expect("requested exactly one stream role");
+            if let (Some(width), Some(height)) = (config.capture_width, config.capture_height) {
+                stream_config.set_size(Size { width, height });
+            }
+            if let Some(sensor_mode) = config.libcamera_sensor_mode {
+                stream_config.set_sensor_mode(sensor_mode);
+            }
+        }
+
+        match pipeline_config.validate() {
+            CameraConfigurationStatus::Invalid => {
+                return Err(format!("libcamera rejected the requested configuration for {camera_id:?}"));
+            }
+            CameraConfigurationStatus::Adjusted => {
+                warn!("libcamera adjusted the requested configuration for {camera_id:?} to a supported one");
+            }
+            CameraConfigurationStatus::Valid => {}
+        }
+
+        camera
+            .configure(&mut pipeline_config)
+            .map_err(|error| format!("failed to configure libcamera camera {camera_id:?}: {error}"))?;
+
+        let stream_config = pipeline_config.get(0).expect("requested exactly one stream role");
+        let frame_size = stream_config.size();
+        let stream = stream_config
+            .stream()
+            .ok_or_else(|| format!("libcamera camera {camera_id:?} configuration has no stream"))?;
+
+        let mut allocator = FrameBufferAllocator::new(&camera);
+        let buffers = allocator
+            .alloc(&stream)
+            .map_err(|error| format!("failed to allocate libcamera framebuffers for {camera_id:?}: {error}"))?;
+
+        let mut requests = Vec::with_capacity(buffers.len());
+        for buffer in buffers {
+            let mapped = MemoryMappedFrameBuffer::new(buffer)
+                .map_err(|error| format!("failed to mmap libcamera framebuffer for {camera_id:?}: {error}"))?;
+
+            let mut request = camera
+                .create_request(None)
+                .ok_or_else(|| format!("failed to create libcamera request for {camera_id:?}"))?;
+            request
+                .add_buffer(&stream, mapped)
+                .map_err(|error| format!("failed to attach buffer to libcamera request for {camera_id:?}: {error}"))?;
+
+            let mut controls = ControlList::new();
+            let _ = controls.set(AeEnable(config.libcamera_ae_enabled));
+            let _ = controls.set(AwbEnable(config.libcamera_awb_enabled));
+            if let (Some(min), Some(max)) =
+                (config.libcamera_min_frame_duration_micros, config.libcamera_max_frame_duration_micros)
+            {
+                let _ = controls.set(FrameDurationLimits([min as i64, max as i64]));
+            }
+            set_camera_controls(&mut controls, &config.initial_controls);
+            *request.controls_mut() = controls;
+
+            requests.push(request);
+        }
+
+        camera
+            .start(None)
+            .map_err(|error| format!("failed to start libcamera camera {camera_id:?}: {error}"))?;
+
+        let recording = Arc::new(Mutex::new(None));
+        let armed = Arc::new(AtomicBool::new(false));
+        let first_frame_pending = Arc::new(AtomicBool::new(false));
+        let pending_controls = Arc::new(Mutex::new(None));
+        let pending_roi = Arc::new(Mutex::new(None));
+        let writer = frame_writer::spawn(
+            config.write_queue_depth,
+            config.backpressure_policy,
+            Arc::clone(&recording),
+            on_frame,
+            realtime_clock,
+        );
+        let drift_estimator = Arc::new(DriftEstimator::new());
+
+        spawn_capture_loop(
+            camera,
+            requests,
+            Arc::clone(&armed),
+            Arc::clone(&first_frame_pending),
+            Arc::clone(&pending_controls),
+            Arc::clone(&pending_roi),
+            frame_size.width,
+            frame_size.height,
+            writer.clone(),
+            Arc::clone(&drift_estimator),
+            on_fatal_error,
+            on_dropped_frames,
+            on_first_frame,
+            on_backpressure_action,
+        );
+
+        Ok(Self {
+            output_dir: config.output_dir.clone(),
+            secondary_output_dir: config.secondary_output_dir.clone(),
+            encryption_recipient: config.encryption_recipient.clone(),
+            file_pattern: config.file_pattern.clone(),
+            next_trigger_id: AtomicU64::new(0),
+            recording,
+            writer,
+            drift_estimator,
+            armed,
+            first_frame_pending,
+            pending_controls,
+            current_roi: Mutex::new((0.0, 0.0, 1.0, 1.0)),
+            pending_roi,
+        })
+    }
+
+    /// Opens fresh output files (the recording itself, and its sensor
+    /// timestamp sidecar) and arms the capture thread to start writing
+    /// frames into them. Returns the location of the recording file.
+    pub fn start(&self) -> String {
+        let trigger_id = self.next_trigger_id.fetch_add(1, Ordering::Relaxed);
+        let location = self.output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string()));
+        let secondary_location = self
+            .secondary_output_dir
+            .as_ref()
+            .map(|secondary_output_dir| secondary_output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string())));
+
+        match RecordingState::open(&location, secondary_location.as_deref(), self.encryption_recipient.as_deref()) {
+            Ok(state) => {
+                *self.recording.lock().unwrap() = Some(state);
+                self.first_frame_pending.store(true, Ordering::Relaxed);
+                self.armed.store(true, Ordering::Relaxed);
+            }
+            Err(error) => error!("failed to start libcamera-native recording at {}: {error}", location.display()),
+        }
+
+        location.display().to_string()
+    }
+
+    /// Disarms the capture thread and flushes/closes the recording's output
+    /// files. Returns how many frames were written to it, for
+    /// [`crate::manifest`].
+    pub fn stop(&self) -> Option<u64> {
+        self.armed.store(false, Ordering::Relaxed);
+
+        let Some(state) = self.recording.lock().unwrap().take() else {
+            warn!("stop requested but no libcamera-native recording is currently active");
+            return None;
+        };
+
+        Some(state.finish())
+    }
+
+    /// Queues `controls` for the capture thread to apply to the next request
+    /// it requeues. Fire-and-forget, same as
+    /// [`super::v4l2_backend::V4l2Recorder::set_controls`].
+    pub fn set_controls(&self, controls: CameraControls) {
+        *self.pending_controls.lock().unwrap() = Some(controls);
+    }
+
+    /// Merges `roi`'s independent fields into `current_roi` and queues the
+    /// resulting rectangle for the capture thread to apply on its next loop
+    /// iteration. Fire-and-forget, same as [`Self::set_controls`].
+    pub fn set_roi(&self, roi: RegionOfInterest) {
+        let mut current = self.current_roi.lock().unwrap();
+        if let Some(x) = roi.x {
+            current.0 = x;
+        }
+        if let Some(y) = roi.y {
+            current.1 = y;
+        }
+        if let Some(width) = roi.width {
+            current.2 = width;
+        }
+        if let Some(height) = roi.height {
+            current.3 = height;
+        }
+        *self.pending_roi.lock().unwrap() = Some(*current);
+    }
+}
+
+/// Spawns the sole thread allowed to drive `camera`'s request queue. Runs
+/// for the lifetime of the process: completed requests are read
+/// continuously (recycling their buffer back onto the queue as a fresh
+/// request) but only enqueued to `writer` while `armed`.
+fn spawn_capture_loop(
+    mut camera: libcamera::camera::ActiveCamera<'static>,
+    requests: Vec<libcamera::request::Request>,
+    armed: Arc<AtomicBool>,
+    first_frame_pending: Arc<AtomicBool>,
+    pending_controls: Arc<Mutex<Option<CameraControls>>>,
+    pending_roi: Arc<Mutex<Option<(f32, f32, f32, f32)>>>,
+    frame_width: u32,
+    frame_height: u32,
+    writer: FrameWriterHandle,
+    drift_estimator: Arc<DriftEstimator>,
+    on_fatal_error: impl Fn() + Send + Sync + 'static,
+    on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+    on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+    on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+) {
+    std::thread::spawn(move || {
+        let (completed_tx, completed_rx) = std::sync::mpsc::channel();
+        camera.on_request_completed(move |request| {
+            let _ = completed_tx.send(request);
+        });
+
+        for request in requests {
+            if let Err(error) = camera.queue_request(request) {
+                error!("failed to queue initial libcamera request: {error}");
+                on_fatal_error();
+                return;
+            }
+        }
+
+        loop {
+            let mut request = match completed_rx.recv() {
+                Ok(request) => request,
+                Err(_) => {
+                    error!("libcamera request-completed channel closed unexpectedly");
+                    on_fatal_error();
+                    return;
+                }
+            };
+
+            if armed.load(Ordering::Relaxed) {
+                let (data, sensor_timestamp_ns, stats) = read_frame(&request);
+                drift_estimator.record(sensor_timestamp_ns, crate::clock::monotonic_now_ns());
+                let corrected_timestamp_ns = drift_estimator.correct(sensor_timestamp_ns);
+                match writer.enqueue(data, corrected_timestamp_ns as i64, stats) {
+                    None => {
+                        if first_frame_pending.swap(false, Ordering::Relaxed) {
+                            on_first_frame(corrected_timestamp_ns);
+                        }
+                    }
+                    Some(action) => {
+                        on_dropped_frames(1);
+                        on_backpressure_action(action);
+                    }
+                }
+            }
+
+            request.reuse(libcamera::request::ReuseFlag::REUSE_BUFFERS);
+            let controls = pending_controls.lock().unwrap().take();
+            let roi = pending_roi.lock().unwrap().take();
+            if controls.is_some() || roi.is_some() {
+                let mut list = ControlList::new();
+                if let Some(controls) = controls {
+                    set_camera_controls(&mut list, &controls);
+                }
+                if let Some(roi) = roi {
+                    set_roi(&mut list, roi, frame_width, frame_height);
+                }
+                *request.controls_mut() = list;
+            }
+            if let Err(error) = camera.queue_request(request) {
+                warn!("failed to requeue libcamera request: {error}");
+            }
+        }
+    });
+}
+
+/// Copies `request`'s frame data out of its (about to be recycled)
+/// framebuffer and reads its sensor timestamp and AE/AGC metadata, so all of
+/// it can be handed to [`frame_writer`]'s writer thread once the capture
+/// thread is done with `request` for this loop iteration. `FrameStats` is
+/// always `Some` here - libcamera reports both controls on every completed
+/// request in practice - but callers shouldn't rely on that; a missing
+/// control just means no `.frame-stats.csv` row for this frame.
+fn read_frame(request: &libcamera::request::Request) -> (Vec<u8>, u64, Option<FrameStats>) {
+    let mut data = Vec::new();
+    if let Some(framebuffer) = request.buffers().values().next() {
+        for plane in framebuffer.data() {
+            data.extend_from_slice(plane);
+        }
+    } else {
+        warn!("completed libcamera request had no buffers");
+    }
+
+    let metadata = request.metadata();
+
+    let sensor_timestamp_ns: u64 =
+        metadata.get::<SensorTimestamp>().map(|timestamp| timestamp.0 as u64).unwrap_or_default();
+
+    let stats = match (metadata.get::<ExposureTime>(), metadata.get::<AnalogueGain>()) {
+        (Some(exposure_time), Some(analogue_gain)) => {
+            Some(FrameStats { exposure_time_micros: exposure_time.0 as u32, analogue_gain: analogue_gain.0 })
+        }
+        _ => None,
+    };
+
+    (data, sensor_timestamp_ns, stats)
+}
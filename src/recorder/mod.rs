@@ -0,0 +1,1048 @@
+mod aravis_backend;
+mod encryption;
+mod frame_writer;
+mod gphoto2_backend;
+mod gstreamer_backend;
+mod libcamera_native_backend;
+mod osd_overlay;
+mod ptpip_backend;
+mod recover;
+mod rtsp_preview;
+mod sensor_clock;
+mod srt_output;
+mod still;
+mod subprocess_backend;
+mod v4l2_backend;
+mod webrtc_preview;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::mavlink::MavlinkFeedback;
+use aravis_backend::AravisRecorder;
+use gphoto2_backend::Gphoto2Recorder;
+use gstreamer_backend::GstreamerRecorder;
+use libcamera_native_backend::LibcameraNativeRecorder;
+use ptpip_backend::PtpIpRecorder;
+use still::StillCapture;
+use subprocess_backend::SubprocessRecorder;
+use v4l2_backend::V4l2Recorder;
+
+/// Which GStreamer source element the [`gstreamer_backend`] builds its
+/// capture pipeline around. Only meaningful when `RecorderConfig::backend`
+/// is [`RecordingBackend::Gstreamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureSource {
+    /// `v4l2src`, for UVC/V4L2 capture devices such as `/dev/video0`.
+    V4l2,
+    /// `libcamerasrc`, for CSI cameras driven through libcamera on Raspberry
+    /// Pi boards, where `source_device` names the camera rather than a
+    /// `/dev` node.
+    Libcamera,
+}
+
+impl std::str::FromStr for CaptureSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown capture source {value:?}"))
+    }
+}
+
+/// Which implementation [`Recorder::new`] builds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordingBackend {
+    /// An in-process GStreamer pipeline. See [`gstreamer_backend`].
+    Gstreamer,
+    /// A supervised `libcamera-vid`/`rpicam-vid` child process. See
+    /// [`subprocess_backend`].
+    LibcameraVid,
+    /// A pure-Rust V4L2 capture via the `v4l` crate, bypassing GStreamer
+    /// entirely. Requires the device to natively produce MJPG. See
+    /// [`v4l2_backend`].
+    V4l2Direct,
+    /// An in-process libcamera capture via the `libcamera` crate, bypassing
+    /// both GStreamer and the `libcamera-vid` child process. Unlike
+    /// [`RecordingBackend::LibcameraVid`], this exposes sensor mode
+    /// selection, AE/AWB control, and frame duration limits directly, and
+    /// records each frame's libcamera `SensorTimestamp` (already in the
+    /// same `CLOCK_MONOTONIC` domain as [`crate::trigger`]'s GPIO edge
+    /// timestamps) rather than relying on a subprocess's own framing. See
+    /// [`libcamera_native_backend`].
+    LibcameraNative,
+    /// A USB-connected DSLR/mirrorless camera driven through libgphoto2,
+    /// for payloads that need still-image quality/optics a UVC or CSI
+    /// sensor can't match. Each trigger is a discrete shutter release rather
+    /// than a `Start`/`Stop`-bounded recording; see [`gphoto2_backend`].
+    Gphoto2,
+    /// A WiFi/USB PTP-IP-connected mirrorless or DSLR body (e.g. a Sony
+    /// Alpha in PC Remote mode), driven over the network rather than
+    /// libgphoto2's USB session. Like [`RecordingBackend::Gphoto2`], each
+    /// trigger is a discrete shutter release; unlike it, the camera's own
+    /// reported capture timestamp is recovered from the confirming
+    /// `ObjectAdded` event rather than assumed from when we asked. See
+    /// [`ptpip_backend`].
+    PtpIp,
+    /// A GenICam-compliant GigE Vision camera driven through `libaravis`
+    /// bindings, with hardware trigger configuration for machine-vision
+    /// payloads (e.g. a global-shutter inspection camera) that need frame
+    /// exposure locked to the trigger line rather than however long a
+    /// software command takes to arrive. See [`aravis_backend`].
+    GigeVision,
+}
+
+impl std::str::FromStr for RecordingBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown recording backend {value:?}"))
+    }
+}
+
+/// Which naming strategy [`Recorder::new`]'s backend uses to pick each
+/// recording's output path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NamingMode {
+    /// `file_pattern`'s `{trigger}` template, substituted with a per-trigger
+    /// counter. The long-standing default.
+    Pattern,
+    /// `<output_dir>/<flight_session>/<camera_id>_<utc_iso8601>_<seq>`, via
+    /// [`crate::naming::NamingScheme`]. Produces names that sort
+    /// chronologically and carry a wall-clock timestamp without needing to
+    /// cross-reference logs, at the cost of ignoring `file_pattern`.
+    Structured,
+}
+
+impl std::str::FromStr for NamingMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown naming mode {value:?}"))
+    }
+}
+
+/// What [`frame_writer::FrameWriterHandle::enqueue`] does when its bounded
+/// queue is more than `write_queue_depth` frames behind. Only used by
+/// [`RecordingBackend::V4l2Direct`], [`RecordingBackend::LibcameraNative`]
+/// and [`RecordingBackend::GigeVision`], the backends sharing
+/// [`frame_writer`]; the other backends manage their own internal buffering
+/// and have no equivalent choice to make.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackpressurePolicy {
+    /// Drop the just-captured frame and leave the queue as-is. The
+    /// long-standing default: simplest to reason about, but under sustained
+    /// backpressure it drops the freshest frame every single capture cycle.
+    DropNewest,
+    /// Discard the oldest still-queued frame to make room for the one just
+    /// captured, so the recording keeps up with the present at the cost of
+    /// a gap further back instead of at its newest edge.
+    DropOldest,
+    /// Rather than dropping one frame at a time, proactively skip a growing
+    /// run of subsequent frames after an overflow (decaying back to none
+    /// once the queue keeps up), so sustained backpressure settles into a
+    /// steadily lower effective framerate instead of a constant trickle of
+    /// individual drops.
+    ReduceFramerate,
+}
+
+impl std::str::FromStr for BackpressurePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown backpressure policy {value:?}"))
+    }
+}
+
+/// Which action [`frame_writer::FrameWriterHandle::enqueue`] took for a
+/// given [`BackpressurePolicy`] because its queue was more than
+/// `write_queue_depth` frames behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressureAction {
+    /// [`BackpressurePolicy::DropNewest`] dropped the just-captured frame.
+    DroppedNewest,
+    /// [`BackpressurePolicy::DropOldest`] evicted the oldest queued frame to
+    /// make room for the just-captured one.
+    DroppedOldest,
+    /// [`BackpressurePolicy::ReduceFramerate`] skipped the just-captured
+    /// frame as part of its proactive skip window.
+    ReducedFramerate,
+}
+
+/// Which video codec the [`gstreamer_backend`] encodes recordings as. Only
+/// used by [`RecordingBackend::Gstreamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VideoCodec {
+    /// The long-standing default.
+    H264,
+    H265,
+    /// Roughly halves storage versus H264 at the same quality. Encoded in
+    /// software via `av1enc` (aom), since hardware AV1 encoders are rare in
+    /// the V4L2 stateful codec ecosystem; selectable via `--encoder hardware`
+    /// too, should a board expose one as `v4l2av1enc`.
+    Av1,
+}
+
+impl std::str::FromStr for VideoCodec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown video codec {value:?}"))
+    }
+}
+
+/// Which encoder implementation the [`gstreamer_backend`] uses for `codec`.
+/// Only used by [`RecordingBackend::Gstreamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Encoder {
+    /// `x264enc`/`x265enc`/`av1enc`. Works everywhere, but is the bottleneck
+    /// on boards without a fast CPU - especially for `VideoCodec::Av1`,
+    /// whose software encode is far slower than realtime on anything but a
+    /// beefy CPU. The long-standing default.
+    Software,
+    /// The V4L2 stateful (memory-to-memory) codec driver -
+    /// `v4l2h264enc`/`v4l2h265enc`/`v4l2av1enc` - offloading encoding onto dedicated
+    /// hardware such as the Pi's. `GstreamerRecorder::new` probes for the
+    /// element and, if `capture_width`/`capture_height` are set, that it
+    /// advertises support for that resolution, since a board without the
+    /// matching hardware block (or one requesting an unsupported
+    /// resolution) would otherwise fail deep inside pipeline negotiation
+    /// with a much less legible error.
+    Hardware,
+}
+
+impl std::str::FromStr for Encoder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown encoder {value:?}"))
+    }
+}
+
+/// Which container [`gstreamer_backend`] muxes recordings into. Only used by
+/// [`RecordingBackend::Gstreamer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContainerFormat {
+    /// A conventional MP4, whose `moov` atom (the index needed to play the
+    /// file back) is only written once the file is finalized. The
+    /// long-standing default; a recording lost to a brownout or a kill -9
+    /// mid-flight is unrecoverable.
+    Mp4,
+    /// A fragmented MP4 (`moof`/`mdat` pairs with `streamable=true`), whose
+    /// fragments up to the last one flushed are independently playable even
+    /// if the file is never finalized.
+    FragmentedMp4,
+    /// Matroska, whose fragments up to the last cluster written are
+    /// likewise independently playable without a final index.
+    Matroska,
+}
+
+impl std::str::FromStr for ContainerFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown container format {value:?}"))
+    }
+}
+
+/// How to re-orient captured frames before encoding, for a camera mounted
+/// upside down or sideways on a given airframe, named after
+/// [`gstreamer_backend`]'s `videoflip` element's own `method` property
+/// (which this maps onto 1:1) rather than decomposing into independent
+/// rotation/flip settings that would need recombining into one `videoflip`
+/// call anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    /// The long-standing default: no transform.
+    None,
+    Clockwise90,
+    Rotate180,
+    CounterClockwise90,
+    /// Mirror left-right, no rotation.
+    HorizontalFlip,
+    /// Mirror top-bottom, no rotation.
+    VerticalFlip,
+}
+
+impl std::str::FromStr for Orientation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(value, true)
+            .map_err(|_| format!("unknown orientation {value:?}"))
+    }
+}
+
+/// A runtime camera parameter change, applied via [`Recorder::set_controls`]
+/// on top of whatever `RecorderConfig` started the camera with. Every field
+/// is independent: `None` leaves that parameter alone rather than resetting
+/// it to auto. Only the v4l2-direct and libcamera-native backends can drive
+/// live controls; see [`Recorder::set_controls`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CameraControls {
+    /// Manual exposure time, in microseconds. Switches the camera out of
+    /// auto-exposure.
+    pub exposure_micros: Option<u32>,
+    /// Manual analogue gain (a multiplier, e.g. `2.0`). Switches the camera
+    /// out of auto-exposure, same as `exposure_micros`.
+    pub gain: Option<f32>,
+    /// Manual white balance colour temperature, in Kelvin. Switches the
+    /// camera out of auto white balance.
+    pub white_balance_kelvin: Option<u32>,
+    /// Manual focus position (backend-specific units - dioptres for
+    /// libcamera, an absolute step count for V4L2 UVC). Switches the camera
+    /// out of autofocus.
+    pub focus_position: Option<f32>,
+}
+
+/// A runtime region-of-interest (digital zoom/pan) change, applied via
+/// [`Recorder::set_roi`] on top of the full-frame view `RecorderConfig`
+/// started the camera with. Coordinates are normalized fractions of the full
+/// captured frame (`0.0..=1.0`, top-left origin) rather than pixels, so the
+/// same request works regardless of `capture_width`/`capture_height`. Every
+/// field is independent, same idiom as [`CameraControls`]: `None` leaves
+/// that edge of the current crop alone rather than resetting it to the full
+/// frame. Only the v4l2-direct and libcamera-native backends can drive this,
+/// via their selection/`ScalerCrop` APIs; see [`Recorder::set_roi`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RegionOfInterest {
+    /// Left edge of the crop rectangle.
+    pub x: Option<f32>,
+    /// Top edge of the crop rectangle.
+    pub y: Option<f32>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+}
+
+/// Parameters describing how a [`Recorder`]'s capture pipeline is built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecorderConfig {
+    /// Which implementation drives the actual capture.
+    pub backend: RecordingBackend,
+    /// Which source element `source_device` is fed into. Only used by
+    /// [`RecordingBackend::Gstreamer`].
+    pub source: CaptureSource,
+    /// Capture device to read frames from: a `/dev/videoN` node for
+    /// [`CaptureSource::V4l2`], or a libcamera camera name/index for
+    /// [`CaptureSource::Libcamera`] and [`RecordingBackend::LibcameraVid`].
+    /// Overridden once at startup if `usb_serial`/`usb_port_path` is set;
+    /// see [`crate::usb_discovery`].
+    pub source_device: PathBuf,
+    /// Resolve `source_device` by USB serial number instead of using the
+    /// configured path, so `/dev/video*` renumbering across boots doesn't
+    /// matter. Takes priority over `usb_port_path` if both are set. Only
+    /// meaningful for [`CaptureSource::V4l2`] devices; see
+    /// [`crate::usb_discovery::find_device_by_usb_serial`].
+    pub usb_serial: Option<String>,
+    /// Resolve `source_device` by USB port path instead of using the
+    /// configured path. Only meaningful for [`CaptureSource::V4l2`]
+    /// devices; see [`crate::usb_discovery::find_device_by_usb_port`].
+    pub usb_port_path: Option<String>,
+    /// Which video codec to encode recordings as. Only used by
+    /// [`RecordingBackend::Gstreamer`].
+    pub codec: VideoCodec,
+    /// Which encoder implementation to use for `codec`. Only used by
+    /// [`RecordingBackend::Gstreamer`].
+    pub encoder: Encoder,
+    /// Import capture buffers into `encoder`'s hardware codec as DMABUFs
+    /// instead of copying each frame through `videoconvert`, to keep CPU
+    /// usage down at high resolutions. Only takes effect for
+    /// [`CaptureSource::V4l2`] with [`Encoder::Hardware`]; ignored (with a
+    /// warning) otherwise, since `videoconvert` can't be skipped when the
+    /// capture source's native format needs converting for the software
+    /// encoder or `libcamerasrc` already manages its own zero-copy path.
+    /// Only used by [`RecordingBackend::Gstreamer`].
+    pub zero_copy_enabled: bool,
+    /// Requested capture resolution, checked against `encoder`'s advertised
+    /// capabilities at startup when `encoder` is [`Encoder::Hardware`].
+    /// `None` (the default) leaves the source element's default resolution
+    /// in place and skips the resolution check. Only used by
+    /// [`RecordingBackend::Gstreamer`].
+    pub capture_width: Option<u32>,
+    pub capture_height: Option<u32>,
+    /// Requested capture framerate, in frames per second, checked against
+    /// `encoder`'s advertised capabilities at startup when `encoder` is
+    /// [`Encoder::Hardware`]. `None` (the default) leaves the source
+    /// element's default framerate in place and skips the framerate check.
+    /// Only used by [`RecordingBackend::Gstreamer`].
+    pub capture_framerate: Option<u32>,
+    /// How to re-orient captured frames before encoding, e.g. for a camera
+    /// mounted upside down or sideways on this airframe. Applied via a
+    /// `videoflip` element, hardware-accelerated where the platform's
+    /// `videoflip` implementation supports it (e.g. `imxvideoconvert` on
+    /// i.MX, `v4l2convert` on some other SoCs), software otherwise. Only
+    /// used by [`RecordingBackend::Gstreamer`]; [`RecordingBackend::LibcameraVid`]
+    /// supports [`Orientation::Rotate180`], [`Orientation::HorizontalFlip`]
+    /// and [`Orientation::VerticalFlip`] via its own `--rotation`/`--hflip`/
+    /// `--vflip` flags, but not a 90-degree rotation (ignored with a
+    /// warning).
+    pub orientation: Orientation,
+    /// Pixels to crop from each edge of the captured frame before encoding,
+    /// applied after `orientation`. All zero (the default) disables
+    /// cropping. Only used by [`RecordingBackend::Gstreamer`], via a
+    /// `videocrop` element.
+    pub crop_left: u32,
+    pub crop_right: u32,
+    pub crop_top: u32,
+    pub crop_bottom: u32,
+    /// Target bitrate for `codec`, in kbit/s. `None` (the default) leaves
+    /// the encoder element at its own default bitrate. Only used by
+    /// [`RecordingBackend::Gstreamer`].
+    pub video_bitrate_kbps: Option<u32>,
+    /// Step `video_bitrate_kbps` down toward `adaptive_bitrate_min_kbps`
+    /// (never below it) whenever the encoder reports dropped frames, and
+    /// back up toward `video_bitrate_kbps` after `adaptive_bitrate_recovery_secs`
+    /// without a drop, so a degraded SD card trades footage quality for
+    /// keeping up with capture instead of dropping frames outright.
+    /// Ignored (with a warning) unless `video_bitrate_kbps` is set, since
+    /// there would otherwise be no ceiling to recover back to; also ignored
+    /// with `preroll_duration` set, since that pipeline's encoder runs
+    /// ahead of the per-trigger mux pipeline the dropped-frame reports
+    /// actually come from. Only used by [`RecordingBackend::Gstreamer`].
+    pub adaptive_bitrate_enabled: bool,
+    /// Floor for `adaptive_bitrate_enabled`'s bitrate stepping, in kbit/s.
+    pub adaptive_bitrate_min_kbps: u32,
+    /// How much to step the bitrate by, in kbit/s, per drop event or
+    /// recovery interval elapsed.
+    pub adaptive_bitrate_step_kbps: u32,
+    /// How long the encoder must go without a reported drop before
+    /// `adaptive_bitrate_enabled` steps the bitrate back up.
+    pub adaptive_bitrate_recovery_secs: u64,
+    /// Which container to mux recordings into. Only used by
+    /// [`RecordingBackend::Gstreamer`]; see [`ContainerFormat`].
+    pub container: ContainerFormat,
+    /// Directory that finalized recordings are written into.
+    pub output_dir: PathBuf,
+    /// Also write every recording into this directory, e.g. a second SD
+    /// card or a USB SSD, so a medium lost in a hard landing doesn't take
+    /// the footage with it. `None` (the default) disables the second copy.
+    /// The two targets fail independently: a write error on one (the card
+    /// going read-only, the SSD being unplugged) is logged and only
+    /// disables further writes to that target, not to `output_dir` or the
+    /// other way around. Only used by [`RecordingBackend::V4l2Direct`],
+    /// [`RecordingBackend::LibcameraNative`] and
+    /// [`RecordingBackend::GigeVision`], which write frames to disk
+    /// themselves rather than delegating to GStreamer or a `libcamera-vid`
+    /// child process; see [`frame_writer`].
+    pub secondary_output_dir: Option<PathBuf>,
+    /// Age public key (the `age1...` string `age-keygen` prints) that
+    /// recordings are encrypted to as they're written, so a card or SSD
+    /// recovered from a flight over a sensitive site can't be read without
+    /// the matching private key. `None` (the default) writes recordings in
+    /// the clear. Each recording is its own age stream rather than one
+    /// continuous stream spanning a flight, so a partial/truncated
+    /// recording still decrypts everything written to it before the cut.
+    /// Only used by [`RecordingBackend::V4l2Direct`],
+    /// [`RecordingBackend::LibcameraNative`] and
+    /// [`RecordingBackend::GigeVision`], the same backends
+    /// `secondary_output_dir` is scoped to; see [`frame_writer`] and
+    /// [`crate::recorder::decrypt_recording`] for the ground-workstation
+    /// side.
+    pub encryption_recipient: Option<String>,
+    /// Output file location pattern, relative to `output_dir`. Must contain
+    /// a `{trigger}` placeholder (substituted with a counter unique to each
+    /// trigger). For [`RecordingBackend::Gstreamer`] it must also contain a
+    /// printf integer directive such as `%05d`, substituted by
+    /// `splitmuxsink` itself per output fragment of that trigger's
+    /// recording. Ignored when `naming` is [`NamingMode::Structured`].
+    pub file_pattern: String,
+    /// `libcamera-vid`/`rpicam-vid` binary to spawn, for
+    /// [`RecordingBackend::LibcameraVid`].
+    pub libcamera_vid_binary: PathBuf,
+    /// Sensor mode index to request, e.g. a CSI sensor's binned
+    /// high-frame-rate mode. `None` (the default) leaves the choice to
+    /// libcamera's own pipeline handler. Only used by
+    /// [`RecordingBackend::LibcameraNative`].
+    pub libcamera_sensor_mode: Option<u32>,
+    /// Whether to leave auto-exposure enabled. Only used by
+    /// [`RecordingBackend::LibcameraNative`].
+    pub libcamera_ae_enabled: bool,
+    /// Whether to leave auto white balance enabled. Only used by
+    /// [`RecordingBackend::LibcameraNative`].
+    pub libcamera_awb_enabled: bool,
+    /// Lower/upper bounds on frame duration, in microseconds; setting both
+    /// to the same value pins the sensor to a fixed frame rate. `None`
+    /// leaves the corresponding bound unset. Only used by
+    /// [`RecordingBackend::LibcameraNative`].
+    pub libcamera_min_frame_duration_micros: Option<u32>,
+    pub libcamera_max_frame_duration_micros: Option<u32>,
+    /// How many captured frames may be queued for [`RecordingBackend::V4l2Direct`]'s
+    /// and [`RecordingBackend::LibcameraNative`]'s dedicated writer thread
+    /// before newly-captured frames are dropped instead of blocking capture.
+    /// A slow SD card then causes controlled frame dropping - counted the
+    /// same way as [`crate::metrics::CameraMetrics::record_dropped_frames`] -
+    /// instead of stalling the device's buffer queue. Unused by the other
+    /// backends, which manage their own internal buffering.
+    pub write_queue_depth: usize,
+    /// What happens to a captured frame when `write_queue_depth` is
+    /// exceeded. See [`BackpressurePolicy`].
+    pub backpressure_policy: BackpressurePolicy,
+    /// Which strategy picks each recording's output path.
+    pub naming: NamingMode,
+    /// Split a recording into fragments no longer than this, so a crash or
+    /// power loss only loses the most recent fragment and no single file
+    /// grows past a FAT32/exFAT-friendly size. [`Duration::ZERO`] (the
+    /// default) disables splitting: a recording is a single file from
+    /// start to stop.
+    pub segment_duration: Duration,
+    /// Keep this much encoded footage from just before each trigger and
+    /// prepend it to the resulting recording, so the moments leading up to
+    /// the trigger aren't lost. [`Duration::ZERO`] (the default) disables
+    /// pre-roll. Only used by [`RecordingBackend::Gstreamer`]; see
+    /// [`gstreamer_backend`].
+    pub preroll_duration: Duration,
+    /// If no new frame arrives from the capture pipeline for this long while
+    /// a recording is armed, tear it down and rebuild it with a fresh output
+    /// location instead of silently continuing to write a frozen file.
+    /// [`Duration::ZERO`] (the default) disables the check. Only used by
+    /// [`RecordingBackend::Gstreamer`]'s non-preroll mode; see
+    /// [`gstreamer_backend::SimpleMode`].
+    pub frame_stall_timeout: Duration,
+    /// Hard cap on how long a single recording may run once started. If no
+    /// stop trigger arrives before this elapses (a broken wire, PX4
+    /// rebooting mid-flight), the recording is finalized and the camera
+    /// returns to idle instead of recording until the disk fills.
+    /// [`Duration::ZERO`] (the default) disables the cap. See
+    /// [`crate::session::Session::new`]'s per-camera transition worker.
+    pub max_recording_duration: Duration,
+    /// Capture one throwaway frame at startup and verify it's nonempty with
+    /// plausible JPEG dimensions before this camera is considered ready,
+    /// catching a camera that's electrically present but producing garbage
+    /// (bad power, wrong sensor mode, a stuck ISP). See
+    /// [`crate::camera_self_test`].
+    pub self_test_enabled: bool,
+    /// If the self-test fails, skip building this camera's recording
+    /// pipeline and let the rest of the session start up instead of
+    /// aborting the whole process. Trigger events for this camera are still
+    /// logged to [`crate::trigger_log::TriggerLog`]; only its own recording
+    /// pipeline is left out.
+    pub self_test_degraded_on_failure: bool,
+    /// If building this camera's recording pipeline itself fails (as
+    /// opposed to `self_test_enabled` catching a camera that opens but
+    /// produces garbage), keep the rest of the session running and this
+    /// camera's trigger events logged with no filename instead of aborting
+    /// the whole process - the same degraded mode `self_test_
+    /// degraded_on_failure` puts a camera in. Unlike that flag, this one
+    /// also retries bring-up in the background every `init_retry_interval`,
+    /// since an init failure (camera unplugged, backend crashed at startup)
+    /// is more often transient than a self-test failure (usually a hardware
+    /// fault that needs someone to go look at it). Absolute trigger timing
+    /// (the trigger log row, and MAVLink's `CAMERA_TRIGGER` feedback, which
+    /// doesn't depend on any camera at all) stays valuable even with the
+    /// imagery missing.
+    pub init_degraded_on_failure: bool,
+    /// How often to retry building this camera's recording pipeline while
+    /// degraded from an init failure. See `init_degraded_on_failure`.
+    pub init_retry_interval: Duration,
+    /// Capture a still image (or, with `still_burst_count` above 1, a burst
+    /// of them) on [`crate::trigger::Transition::CaptureStill`] instead of
+    /// leaving it unhandled. Independent of `backend`/`naming`, which still
+    /// govern how video recording (if this camera also does any) behaves.
+    pub still_capture: bool,
+    /// How many frames to capture per still trigger when `still_capture` is
+    /// on. 1 (the default) captures a single photo per pulse.
+    pub still_burst_count: u32,
+    /// Output file location pattern for still captures, relative to
+    /// `output_dir`. Same `{trigger}` placeholder as `file_pattern`, but
+    /// ignored when `naming` is [`NamingMode::Structured`].
+    pub still_file_pattern: String,
+    /// Capture an auto-exposure bracket - one frame per entry in
+    /// `still_aeb_ev_stops` - instead of `still_burst_count` identical
+    /// frames, for HDR post-processing. Only
+    /// [`RecordingBackend::LibcameraNative`] can drive per-shot exposure;
+    /// other backends log a warning and fall back to a single normal
+    /// capture. Takes priority over `still_burst_count` when on.
+    pub still_aeb_enabled: bool,
+    /// Comma-separated EV offsets to bracket across when `still_aeb_enabled`
+    /// is on, e.g. `"-2,0,2"`. A handful of offsets never needs more
+    /// structure than a CLI-friendly string; see [`super::still::StillCapture`]
+    /// for where this gets parsed.
+    pub still_aeb_ev_stops: String,
+    /// Also save each still capture's raw Bayer sensor data as a `.dng`
+    /// alongside its JPEG. Only [`RecordingBackend::LibcameraNative`] can
+    /// drive a raw stream, and only for sensor raw formats
+    /// [`super::still::StillCapture`] knows how to unpack; other cases log a
+    /// warning and capture the JPEG alone.
+    pub still_raw_enabled: bool,
+    /// Save each still capture as a 16-bit grayscale TIFF of the raw sensor
+    /// readout instead of a JPEG, for FLIR Boson/Lepton-style radiometric
+    /// thermal cameras. Only [`RecordingBackend::V4l2Direct`] can switch the
+    /// device into its raw `Y16` output mode; see
+    /// [`super::still::StillCapture`].
+    pub still_thermal_radiometric_enabled: bool,
+    /// Capture stills off the always-on preview/preroll capture pipeline's
+    /// `tee` instead of opening a second, independent pipeline against the
+    /// same device, so a camera can record continuous video and take
+    /// full-resolution triggered stills from the same sensor at once. Only
+    /// takes effect for [`RecordingBackend::Gstreamer`] with
+    /// `preroll_duration` set, since only then is there an always-on
+    /// pipeline to tap; other backends/configurations fall back to
+    /// [`super::still::StillCapture`]'s independent-pipeline capture, which
+    /// may contend with a concurrently-running recording on the same device.
+    pub still_dual_stream_enabled: bool,
+    /// `libcamera-still`/`rpicam-still` binary to spawn for still captures
+    /// when `backend` is [`RecordingBackend::LibcameraVid`].
+    pub libcamera_still_binary: PathBuf,
+    /// Serve a low-bitrate RTSP preview of this camera's feed. Only takes
+    /// effect for [`RecordingBackend::Gstreamer`] with `preroll_duration`
+    /// set, since only then is there an always-on capture pipeline to tap;
+    /// see [`gstreamer_backend::GstreamerRecorder::new`].
+    pub rtsp_preview_enabled: bool,
+    /// `host:port` to serve the RTSP preview on, at the fixed path
+    /// `/preview`.
+    pub rtsp_preview_address: String,
+    /// Bitrate of the preview encode, distinct from (and much lower than)
+    /// the full-quality recording's.
+    pub rtsp_preview_bitrate_kbps: u32,
+    /// Push a low-latency WebRTC preview of this camera's feed to
+    /// `webrtc_preview_whip_endpoint` via WHIP. Same capture-pipeline
+    /// requirement as `rtsp_preview_enabled` (needs an always-on pipeline to
+    /// tap), but is a push to a ground-side ingest URL rather than a server
+    /// clients dial into, since WHIP already handles the offer/answer
+    /// signaling over plain HTTP.
+    pub webrtc_preview_enabled: bool,
+    /// WHIP ingest URL to push the preview to, e.g.
+    /// `http://10.0.0.1:8889/preview/whip`. Only used if
+    /// `webrtc_preview_enabled` is set.
+    pub webrtc_preview_whip_endpoint: String,
+    /// Bitrate of the WebRTC preview encode, distinct from (and much lower
+    /// than) the full-quality recording's.
+    pub webrtc_preview_bitrate_kbps: u32,
+    /// Embed each frame's capture timestamp and trigger sequence as a
+    /// user-data-unregistered SEI NAL in the encoded bitstream, so
+    /// post-processing tools can recover exact per-frame timing even if the
+    /// trigger log or [`crate::subtitle_log`] sidecar is lost. Only used by
+    /// [`RecordingBackend::Gstreamer`]; see [`gstreamer_backend`].
+    pub embed_frame_metadata: bool,
+    /// Simultaneously push this camera's feed to the ground station over
+    /// SRT. Same capture-pipeline requirement as `rtsp_preview_enabled`.
+    pub srt_output_enabled: bool,
+    /// `host:port` of the ground station's SRT listener. Only used if
+    /// `srt_output_enabled` is set.
+    pub srt_output_address: String,
+    /// Bitrate of the SRT output's encode, capped separately from (and much
+    /// lower than) the full-quality recording's, since it travels over the
+    /// long-range link.
+    pub srt_output_bitrate_kbps: u32,
+    /// Burn a telemetry overlay (timestamp, flight session id,
+    /// altitude/ground speed from MAVLink) into every enabled preview/output
+    /// branch (`rtsp_preview_enabled`/`webrtc_preview_enabled`/
+    /// `srt_output_enabled`) - never into the archival recording. Only used
+    /// by [`RecordingBackend::Gstreamer`]; see [`osd_overlay`].
+    pub osd_overlay_enabled: bool,
+    /// How often the overlay text refreshes. Only used if
+    /// `osd_overlay_enabled` is set.
+    pub osd_overlay_interval_secs: f64,
+    /// Capture an ALSA audio track alongside the video and mux it into the
+    /// same archival file, so a payload's onboard microphone (e.g. for
+    /// acoustic inspection) ends up in the same container instead of a
+    /// separate sidecar to keep in sync by hand. Only used by
+    /// [`RecordingBackend::Gstreamer`]; see [`gstreamer_backend`].
+    pub audio_capture_enabled: bool,
+    /// ALSA device to capture from, e.g. `hw:1,0`. Only used if
+    /// `audio_capture_enabled` is set.
+    pub audio_device: String,
+    /// Bitrate, in kbps, of the AAC encode. Only used if
+    /// `audio_capture_enabled` is set.
+    pub audio_bitrate_kbps: u32,
+    /// Initial manual camera controls, applied at startup on top of the
+    /// backend's own defaults. `None` fields leave the backend on auto for
+    /// that parameter. Only the v4l2-direct and libcamera-native backends
+    /// apply these; see [`CameraControls`] and [`Recorder::set_controls`]
+    /// for changing them at runtime instead.
+    pub initial_controls: CameraControls,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            backend: RecordingBackend::Gstreamer,
+            source: CaptureSource::V4l2,
+            source_device: PathBuf::from("/dev/video0"),
+            usb_serial: None,
+            usb_port_path: None,
+            codec: VideoCodec::H264,
+            encoder: Encoder::Software,
+            zero_copy_enabled: false,
+            capture_width: None,
+            capture_height: None,
+            capture_framerate: None,
+            orientation: Orientation::None,
+            crop_left: 0,
+            crop_right: 0,
+            crop_top: 0,
+            crop_bottom: 0,
+            video_bitrate_kbps: None,
+            adaptive_bitrate_enabled: false,
+            adaptive_bitrate_min_kbps: 500,
+            adaptive_bitrate_step_kbps: 500,
+            adaptive_bitrate_recovery_secs: 10,
+            container: ContainerFormat::Mp4,
+            output_dir: PathBuf::from("/var/lib/px4-camera-trigger/recordings"),
+            secondary_output_dir: None,
+            encryption_recipient: None,
+            file_pattern: "trigger-{trigger}-%05d.mp4".to_string(),
+            libcamera_vid_binary: PathBuf::from("libcamera-vid"),
+            libcamera_sensor_mode: None,
+            libcamera_ae_enabled: true,
+            libcamera_awb_enabled: true,
+            libcamera_min_frame_duration_micros: None,
+            libcamera_max_frame_duration_micros: None,
+            write_queue_depth: 32,
+            backpressure_policy: BackpressurePolicy::DropNewest,
+            naming: NamingMode::Pattern,
+            segment_duration: Duration::ZERO,
+            preroll_duration: Duration::ZERO,
+            frame_stall_timeout: Duration::ZERO,
+            max_recording_duration: Duration::ZERO,
+            self_test_enabled: true,
+            self_test_degraded_on_failure: false,
+            init_degraded_on_failure: false,
+            init_retry_interval: Duration::from_secs(30),
+            still_capture: false,
+            still_burst_count: 1,
+            still_file_pattern: "trigger-{trigger}-still.jpg".to_string(),
+            still_aeb_enabled: false,
+            still_aeb_ev_stops: "-2,0,2".to_string(),
+            still_raw_enabled: false,
+            still_thermal_radiometric_enabled: false,
+            still_dual_stream_enabled: false,
+            libcamera_still_binary: PathBuf::from("libcamera-still"),
+            rtsp_preview_enabled: false,
+            rtsp_preview_address: "0.0.0.0:8554".to_string(),
+            rtsp_preview_bitrate_kbps: 512,
+            webrtc_preview_enabled: false,
+            webrtc_preview_whip_endpoint: String::new(),
+            webrtc_preview_bitrate_kbps: 512,
+            embed_frame_metadata: false,
+            srt_output_enabled: false,
+            srt_output_address: String::new(),
+            srt_output_bitrate_kbps: 2048,
+            osd_overlay_enabled: false,
+            osd_overlay_interval_secs: 1.0,
+            audio_capture_enabled: false,
+            audio_device: "default".to_string(),
+            audio_bitrate_kbps: 128,
+            initial_controls: CameraControls::default(),
+        }
+    }
+}
+
+/// The active recording implementation, selected by
+/// [`RecorderConfig::backend`].
+enum Backend {
+    Gstreamer(GstreamerRecorder),
+    LibcameraVid(SubprocessRecorder),
+    V4l2Direct(V4l2Recorder),
+    LibcameraNative(LibcameraNativeRecorder),
+    Gphoto2(Gphoto2Recorder),
+    PtpIp(PtpIpRecorder),
+    GigeVision(AravisRecorder),
+}
+
+/// Starts and stops recordings on a falling/rising trigger edge, backed by
+/// whichever [`RecordingBackend`] `config` selects, and (if
+/// `config.still_capture` is on) captures stills on demand alongside it.
+pub struct Recorder {
+    backend: Backend,
+    still: Option<StillCapture>,
+}
+
+impl Recorder {
+    /// Builds the configured backend. See [`GstreamerRecorder::new`],
+    /// [`SubprocessRecorder::new`], [`V4l2Recorder::new`] and
+    /// [`LibcameraNativeRecorder::new`] for what can fail.
+    ///
+    /// `camera_id` and `flight_session` are only consulted when
+    /// `config.naming` is [`NamingMode::Structured`]; see
+    /// [`crate::naming::NamingScheme`].
+    ///
+    /// `on_fatal_error` is invoked whenever the backend detects it can no
+    /// longer reliably record, so callers can react, e.g. by no longer
+    /// petting the systemd watchdog. The GStreamer backend (a bus `Error`
+    /// message) and the v4l2-direct backend (a capture stream error) both
+    /// have a way to detect this; the subprocess backend restarts crashes on
+    /// its own instead of surfacing them.
+    ///
+    /// `Sync` (not just `Send`) is required because the GStreamer backend
+    /// may reuse this closure across several per-trigger pipelines (e.g.
+    /// when `preroll_duration` is nonzero), each watched from its own bus
+    /// thread.
+    ///
+    /// `on_dropped_frames` is invoked with however many frames were newly
+    /// detected as dropped, whenever the backend can detect it: the
+    /// GStreamer backend via `Qos` bus messages (see
+    /// [`gstreamer_backend::GstreamerRecorder::new`]), and the v4l2-direct
+    /// and libcamera-native backends when their shared [`frame_writer`]
+    /// thread falls behind and has to drop a captured frame.
+    ///
+    /// `on_first_frame` is invoked once per `start()`, with the first
+    /// frame's capture timestamp in nanoseconds (the same `CLOCK_MONOTONIC`
+    /// domain as [`crate::trigger`]'s GPIO edge timestamps), so callers can
+    /// measure trigger-to-frame latency; only the v4l2-direct and
+    /// libcamera-native backends currently report this, since they're the
+    /// only ones with in-process access to each frame's capture timestamp.
+    ///
+    /// `mavlink_feedback` is only consulted by the GStreamer backend, to
+    /// sample altitude/ground speed for `config.osd_overlay_enabled`'s
+    /// telemetry overlay; see [`osd_overlay`].
+    ///
+    /// `on_frame` is invoked once per frame actually written to disk, for
+    /// [`crate::metrics::CameraMetrics::record_frame`]'s jitter histogram.
+    /// Only the v4l2-direct, libcamera-native and GigE Vision backends call
+    /// it, since they're the ones sharing [`frame_writer`]; the GStreamer,
+    /// subprocess, gphoto2 and PTP/IP backends have no equivalent per-frame
+    /// hook.
+    ///
+    /// `on_backpressure_action` is invoked whenever `config.write_queue_depth`
+    /// is exceeded, with which [`BackpressureAction`]
+    /// `config.backpressure_policy` took. Same three backends as `on_frame`;
+    /// every call is paired with an `on_dropped_frames(1)` call as well, so
+    /// existing aggregate-drop consumers don't need to change.
+    ///
+    /// `on_degraded_encoding` is invoked at most once, during construction,
+    /// with a human-readable reason, if the GStreamer backend had to fall
+    /// back to MJPEG because `config.encoder = Encoder::Hardware` was
+    /// requested but unavailable; see
+    /// [`gstreamer_backend::GstreamerRecorder::new`]. No other backend calls
+    /// it.
+    /// `realtime_clock` is only consulted by the v4l2-direct, libcamera-native
+    /// and GigE Vision backends, to translate each written frame's
+    /// `CLOCK_MONOTONIC` capture timestamp into a UTC column in their shared
+    /// [`frame_writer`]'s `.timestamps.csv` sidecar.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: &RecorderConfig,
+        camera_id: &str,
+        flight_session: &str,
+        on_fatal_error: impl Fn() + Send + Sync + 'static,
+        on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+        on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+        on_frame: impl Fn() + Send + Sync + 'static,
+        on_backpressure_action: impl Fn(BackpressureAction) + Send + Sync + 'static,
+        on_degraded_encoding: impl Fn(&str) + Send + Sync + 'static,
+        mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+        realtime_clock: Arc<crate::clock::RealtimeClock>,
+    ) -> Result<Self, String> {
+        let backend = match config.backend {
+            RecordingBackend::Gstreamer => Backend::Gstreamer(GstreamerRecorder::new(
+                config,
+                camera_id,
+                flight_session,
+                on_fatal_error,
+                on_dropped_frames,
+                on_degraded_encoding,
+                mavlink_feedback,
+            )?),
+            RecordingBackend::LibcameraVid => {
+                Backend::LibcameraVid(SubprocessRecorder::new(config, camera_id, flight_session)?)
+            }
+            RecordingBackend::V4l2Direct => Backend::V4l2Direct(V4l2Recorder::new(
+                config,
+                on_fatal_error,
+                on_dropped_frames,
+                on_first_frame,
+                on_frame,
+                on_backpressure_action,
+                Arc::clone(&realtime_clock),
+            )?),
+            RecordingBackend::LibcameraNative => Backend::LibcameraNative(LibcameraNativeRecorder::new(
+                config,
+                on_fatal_error,
+                on_dropped_frames,
+                on_first_frame,
+                on_frame,
+                on_backpressure_action,
+                Arc::clone(&realtime_clock),
+            )?),
+            RecordingBackend::Gphoto2 => {
+                Backend::Gphoto2(Gphoto2Recorder::new(config, camera_id, flight_session)?)
+            }
+            RecordingBackend::PtpIp => {
+                Backend::PtpIp(PtpIpRecorder::new(config, camera_id, flight_session)?)
+            }
+            RecordingBackend::GigeVision => Backend::GigeVision(AravisRecorder::new(
+                config,
+                on_fatal_error,
+                on_dropped_frames,
+                on_first_frame,
+                on_frame,
+                on_backpressure_action,
+                realtime_clock,
+            )?),
+        };
+
+        let gstreamer_still_tap = match &backend {
+            Backend::Gstreamer(recorder) => recorder.still_tap(),
+            _ => None,
+        };
+        let still = StillCapture::new(config, camera_id, flight_session, gstreamer_still_tap)?;
+
+        Ok(Self { backend, still })
+    }
+
+    /// Starts a recording, returning the location it was started at (for
+    /// callers that want to log or post-process the resulting file), or
+    /// `None` if the backend failed to start one. Note that with
+    /// `segment_duration` set this is a `splitmuxsink`/`libcamera-vid`
+    /// fragment location pattern rather than a single concrete path.
+    ///
+    /// `sequence` is this trigger's sequence number; only the GStreamer
+    /// backend consults it, to stamp it into each frame's SEI metadata when
+    /// `config.embed_frame_metadata` is set. See
+    /// [`gstreamer_backend::GstreamerRecorder::start`].
+    pub fn start(&self, sequence: u64) -> Option<PathBuf> {
+        match &self.backend {
+            Backend::Gstreamer(recorder) => recorder.start(sequence).map(PathBuf::from),
+            Backend::LibcameraVid(recorder) => Some(PathBuf::from(recorder.start())),
+            Backend::V4l2Direct(recorder) => Some(PathBuf::from(recorder.start())),
+            Backend::LibcameraNative(recorder) => Some(PathBuf::from(recorder.start())),
+            Backend::Gphoto2(recorder) => recorder.start().map(PathBuf::from),
+            Backend::PtpIp(recorder) => recorder.start().map(PathBuf::from),
+            Backend::GigeVision(recorder) => Some(PathBuf::from(recorder.start())),
+        }
+    }
+
+    /// Stops the current recording. Returns its frame count where the
+    /// backend can report one, for [`crate::manifest`]: the GStreamer
+    /// backend reports a best-effort count derived from `Qos` messages (see
+    /// [`gstreamer_backend::qos_processed_delta`]), the v4l2-direct and
+    /// libcamera-native backends count exactly since they write each frame
+    /// themselves, and the subprocess backend always reports `None` since it
+    /// has no visibility into the child process's frame writes.
+    pub fn stop(&self) -> Option<u64> {
+        match &self.backend {
+            Backend::Gstreamer(recorder) => recorder.stop(),
+            Backend::LibcameraVid(recorder) => recorder.stop(),
+            Backend::V4l2Direct(recorder) => recorder.stop(),
+            Backend::LibcameraNative(recorder) => recorder.stop(),
+            Backend::Gphoto2(recorder) => recorder.stop(),
+            Backend::PtpIp(recorder) => recorder.stop(),
+            Backend::GigeVision(recorder) => recorder.stop(),
+        }
+    }
+
+    /// Captures a still image, if `config.still_capture` was on when this
+    /// `Recorder` was built. Returns `None` if it wasn't, so callers can
+    /// warn once instead of silently dropping still-capture triggers for
+    /// cameras that don't have it configured; otherwise `Some` of whichever
+    /// frames were actually written, for post-processing (e.g.
+    /// [`crate::geotag`]).
+    pub fn capture_still(&self) -> Option<Vec<PathBuf>> {
+        self.still.as_ref().map(|still| still.capture())
+    }
+
+    /// The EV offsets the last (or next) [`Self::capture_still`] bracketed
+    /// across, or an empty slice if AEB isn't configured or enabled for this
+    /// camera. See [`still::StillCapture::aeb_ev_stops`].
+    pub fn still_aeb_ev_stops(&self) -> &[f32] {
+        self.still.as_ref().map_or(&[], |still| still.aeb_ev_stops())
+    }
+
+    /// Applies `controls` to the running camera, for the operator to fix
+    /// exposure/gain/white balance/focus mid-flight instead of relying on
+    /// auto modes. Only the v4l2-direct and libcamera-native backends can
+    /// drive this; other backends return an error the caller can surface
+    /// (e.g. as a control API response) instead of silently no-op-ing.
+    pub fn set_controls(&self, controls: CameraControls) -> Result<(), String> {
+        match &self.backend {
+            Backend::Gstreamer(_)
+            | Backend::LibcameraVid(_)
+            | Backend::Gphoto2(_)
+            | Backend::PtpIp(_)
+            | Backend::GigeVision(_) => {
+                Err(format!("{:?} backend can't drive runtime camera controls", self.backend_kind()))
+            }
+            Backend::V4l2Direct(recorder) => {
+                recorder.set_controls(controls);
+                Ok(())
+            }
+            Backend::LibcameraNative(recorder) => {
+                recorder.set_controls(controls);
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies `roi` to the running camera, for the operator to punch in a
+    /// digital zoom/pan on a target mid-flight. Only the v4l2-direct and
+    /// libcamera-native backends can drive this, same restriction (and same
+    /// rationale) as [`Self::set_controls`].
+    pub fn set_roi(&self, roi: RegionOfInterest) -> Result<(), String> {
+        match &self.backend {
+            Backend::Gstreamer(_)
+            | Backend::LibcameraVid(_)
+            | Backend::Gphoto2(_)
+            | Backend::PtpIp(_)
+            | Backend::GigeVision(_) => {
+                Err(format!("{:?} backend can't drive a runtime region of interest", self.backend_kind()))
+            }
+            Backend::V4l2Direct(recorder) => {
+                recorder.set_roi(roi);
+                Ok(())
+            }
+            Backend::LibcameraNative(recorder) => {
+                recorder.set_roi(roi);
+                Ok(())
+            }
+        }
+    }
+
+    fn backend_kind(&self) -> RecordingBackend {
+        match &self.backend {
+            Backend::Gstreamer(_) => RecordingBackend::Gstreamer,
+            Backend::LibcameraVid(_) => RecordingBackend::LibcameraVid,
+            Backend::V4l2Direct(_) => RecordingBackend::V4l2Direct,
+            Backend::LibcameraNative(_) => RecordingBackend::LibcameraNative,
+            Backend::Gphoto2(_) => RecordingBackend::Gphoto2,
+            Backend::PtpIp(_) => RecordingBackend::PtpIp,
+            Backend::GigeVision(_) => RecordingBackend::GigeVision,
+        }
+    }
+}
+
+/// Captures one throwaway frame from `config`'s source into `location`,
+/// independent of building a full [`Recorder`]. Used by
+/// [`crate::camera_self_test`]'s startup sanity check.
+pub(crate) fn capture_probe_frame(config: &RecorderConfig, location: &str) -> bool {
+    still::capture_probe_frame(config, location)
+}
+
+/// Reconstructs a playable copy of `input`, a [`RecordingBackend::Gstreamer`]
+/// recording with [`ContainerFormat::Mp4`] truncated by a brownout or
+/// `kill -9`, using `config`'s codec/encoder/resolution. Used by the
+/// `cameras recover` subcommand. See [`recover::recover`].
+pub fn recover_recording(input: &std::path::Path, config: &RecorderConfig) -> Result<PathBuf, String> {
+    recover::recover(input, config)
+}
+
+/// Decrypts `input`, a recording written under `encryption_recipient`, with
+/// the age identity (private key) in `identity_file`. Used by the `cameras
+/// decrypt` subcommand on the ground workstation; see [`encryption::decrypt`].
+pub fn decrypt_recording(input: &std::path::Path, identity_file: &std::path::Path) -> Result<PathBuf, String> {
+    encryption::decrypt(input, identity_file)
+}
+
+/// Checks that `recipient` parses as an age public key. See
+/// [`encryption::validate_recipient`].
+pub fn validate_encryption_recipient(recipient: &str) -> Result<(), String> {
+    encryption::validate_recipient(recipient)
+}
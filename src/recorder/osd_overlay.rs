@@ -0,0 +1,66 @@
+//! Burns an on-screen telemetry overlay - a running timestamp, the flight
+//! session id, and altitude/ground speed sampled from MAVLink - into the
+//! low-bitrate preview/output branches only, for the ground operator's
+//! situational awareness. Never spliced into the archival encode leg, so it
+//! never touches the file(s) actually kept; see [`super::rtsp_preview`],
+//! [`super::webrtc_preview`] and [`super::srt_output`], the only branches
+//! [`overlay_fragment`] is meant to be spliced into.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use gstreamer::prelude::*;
+use log::warn;
+
+use crate::mavlink::MavlinkFeedback;
+
+/// Pipeline fragment to splice into a preview/output branch right before its
+/// encoder, so the overlay is burned in ahead of compression instead of
+/// fighting it afterward. `element_name` must be unique within the
+/// pipeline - callers running more than one preview/output branch at once
+/// need a distinct name per branch (e.g. `"osd_rtsp"`, `"osd_webrtc"`) - and
+/// is later passed to [`spawn`] to find and update this exact element.
+pub fn overlay_fragment(element_name: &str) -> String {
+    format!(
+        "clockoverlay time-mode=running-time halignment=left valignment=top font-desc=\"Sans 10\" ! \
+         textoverlay name={element_name} text=\"\" halignment=left valignment=bottom font-desc=\"Sans 10\" ! "
+    )
+}
+
+/// Spawns the background task that keeps `element_name`'s `textoverlay`
+/// `text` property current with `session_id` and the latest altitude/ground
+/// speed, ticking every `interval`. A no-op (with a warning) if `pipeline`
+/// has no such element, since that means it was built without the matching
+/// [`overlay_fragment`] spliced in.
+pub fn spawn(
+    pipeline: &gstreamer::Pipeline,
+    element_name: &str,
+    session_id: String,
+    interval: Duration,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+) {
+    let Some(overlay) = pipeline.by_name(element_name) else {
+        warn!("recording pipeline has no element named \"{element_name}\" to update the osd overlay on");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let text = match mavlink_feedback.as_ref() {
+                Some(mavlink_feedback) => {
+                    let position = mavlink_feedback.latest_position();
+                    format!(
+                        "{session_id}  Alt: {:.1}m  Speed: {:.1}m/s",
+                        position.relative_alt as f64 / 1000.0,
+                        position.ground_speed_mps,
+                    )
+                }
+                None => session_id.clone(),
+            };
+            overlay.set_property("text", &text);
+        }
+    });
+}
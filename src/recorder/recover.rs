@@ -0,0 +1,379 @@
+//! Reconstructing a playable file from a [`RecordingBackend::Gstreamer`]
+//! recording with [`ContainerFormat::Mp4`] that was truncated mid-write by a
+//! brownout or `kill -9`, invoked as `cameras recover <file>` (see
+//! `main.rs`). [`ContainerFormat::FragmentedMp4`] and
+//! [`ContainerFormat::Matroska`] don't need this - they're already
+//! independently playable up to their last flushed fragment/cluster - and
+//! the v4l2-direct/libcamera-native/GigE Vision backends' raw
+//! elementary-stream output (see [`super::frame_writer`]) never had a
+//! finalization step to lose in the first place.
+//!
+//! A conventional (non-fragmented) MP4's `moov` box - the sample table
+//! `mp4mux` keeps in memory for the whole recording - is only written after
+//! the last buffer, not before, so a kill mid-flight loses it even though
+//! the encoded frames in `mdat` are intact on disk. Recovery re-derives a
+//! `moov`: it scans `mdat` for the length-prefixed AVC/HEVC samples
+//! `mp4mux` wrote, regenerates the SPS/PPS `mp4mux` would have put in
+//! `moov`'s `stsd` by renegotiating the same encoder element
+//! [`RecorderConfig`] describes, and re-muxes both into a fresh `mp4mux`.
+//! Per-sample durations come from the `.timestamps.csv` sidecar if
+//! [`super::frame_writer::spawn`] happened to write one next to this
+//! recording, otherwise a constant `RecorderConfig::capture_framerate` is
+//! assumed - the same fallback `mp4mux` itself would have made.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use gstreamer::prelude::*;
+use gstreamer::{self as gst, MessageView};
+use gstreamer_app::{AppSink, AppSrc};
+use log::{info, warn};
+
+use super::gstreamer_backend::encoder_elements;
+use super::{Encoder, RecorderConfig, VideoCodec};
+
+/// Assumed framerate when neither `RecorderConfig::capture_framerate` nor
+/// the `.timestamps.csv` sidecar says otherwise. 30fps is this repo's most
+/// common configured rate; see [`RecorderConfig::default`].
+const DEFAULT_RECOVERY_FRAMERATE: u32 = 30;
+
+/// Reconstructs a playable copy of `input` (a truncated
+/// [`ContainerFormat::Mp4`] recording made with `recorder_config`'s codec,
+/// encoder and resolution) at `input` with its extension replaced by
+/// `recovered.mp4`, and returns that path. Best-effort: multiple NAL units
+/// belonging to one encoded frame (possible, though rare, for `encoder`'s
+/// AVC output) are recovered as separate frames rather than being
+/// reassembled, and the partial sample being written at the moment of
+/// truncation is dropped rather than passed on half-formed.
+pub fn recover(input: &Path, recorder_config: &RecorderConfig) -> Result<PathBuf, String> {
+    if recorder_config.codec == VideoCodec::Av1 {
+        return Err(
+            "mp4 recovery only understands the avc/hvcc length-prefixed sample framing mp4mux \
+             uses for h264/h265; av1 recordings aren't supported yet"
+                .to_string(),
+        );
+    }
+
+    let mut file = File::open(input).map_err(|error| format!("failed to open {}: {error}", input.display()))?;
+    let (mdat_offset, mdat_len) = find_mdat(&mut file)?;
+    file.seek(SeekFrom::Start(mdat_offset)).map_err(|error| format!("failed to seek to mdat in {}: {error}", input.display()))?;
+    let mut mdat = vec![0u8; mdat_len as usize];
+    file.read_exact(&mut mdat).map_err(|error| format!("failed to read mdat from {}: {error}", input.display()))?;
+
+    let samples = split_avc_samples(&mdat);
+    if samples.is_empty() {
+        return Err(format!("{} has no recoverable samples in its mdat box", input.display()));
+    }
+    info!("{}: recovered {} samples from a truncated mdat box", input.display(), samples.len());
+
+    let width = recorder_config.capture_width.unwrap_or(1920);
+    let height = recorder_config.capture_height.unwrap_or(1080);
+    let framerate = recorder_config.capture_framerate.unwrap_or(DEFAULT_RECOVERY_FRAMERATE);
+    let codec_data = negotiate_codec_data(recorder_config.codec, recorder_config.encoder, width, height, framerate)?;
+    let durations = frame_durations(input, samples.len(), framerate);
+
+    let output = input.with_extension("recovered.mp4");
+    mux_samples(recorder_config.codec, recorder_config.encoder, width, height, &samples, codec_data, &durations, &output)?;
+
+    Ok(output)
+}
+
+/// A minimal ISOBMFF box walk: reads each top-level box's 8-byte header (or
+/// 16, for the rare 64-bit-size form) until it finds `mdat`, and returns its
+/// payload's `(offset, length)`.
+fn find_mdat(file: &mut File) -> Result<(u64, u64), String> {
+    let file_len = file.metadata().map_err(|error| format!("failed to stat recovery input: {error}"))?.len();
+
+    let mut offset = 0u64;
+    loop {
+        if offset + 8 > file_len {
+            return Err("reached end of file without finding an mdat box".to_string());
+        }
+        file.seek(SeekFrom::Start(offset)).map_err(|error| format!("failed to seek to box at offset {offset}: {error}"))?;
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).map_err(|error| format!("failed to read box header at offset {offset}: {error}"))?;
+        let mut size = u32::from_be_bytes(header[0..4].try_into().unwrap()) as u64;
+        let box_type = header[4..8].to_vec();
+        let mut data_offset = offset + 8;
+        // Whether `size` came from the 16-byte extended-size header rather
+        // than the normal 8-byte one, so the overrun/payload-length math
+        // below subtracts the header it actually read, not always 8.
+        let mut header_len = 8;
+
+        if size == 1 {
+            let mut extended = [0u8; 8];
+            file.read_exact(&mut extended).map_err(|error| format!("failed to read 64-bit box size at offset {offset}: {error}"))?;
+            size = u64::from_be_bytes(extended);
+            data_offset += 8;
+            header_len = 16;
+        }
+
+        if box_type == b"mdat" {
+            return Ok((data_offset, mdat_payload_len(offset, size, header_len, data_offset, file_len)));
+        }
+
+        if size == 0 {
+            return Err(format!("box {:?} at offset {offset} has no declared size and isn't mdat", String::from_utf8_lossy(&box_type)));
+        }
+        offset += size;
+    }
+}
+
+/// Computes `mdat`'s payload length given its box header at `box_offset`,
+/// its declared `box_size` (the 32- or 64-bit field, whichever the caller
+/// read), the `header_len` that size came from (8 for the normal header, 16
+/// for the 64-bit extended form) and its payload's `data_offset`.
+///
+/// `mp4mux` reserves a placeholder size for `mdat` and only rewrites it once
+/// the recording finalizes; a size of 0 (or one that would overrun the
+/// file, box-header-inclusive) here means that never happened, so everything
+/// from `data_offset` to EOF is the sample data. Otherwise the payload is
+/// `box_size` minus whichever header size was actually read - getting this
+/// wrong for the 64-bit form reads 8 bytes too many into the recovered
+/// samples, the realistic case once a flight recording exceeds 4GB.
+fn mdat_payload_len(box_offset: u64, box_size: u64, header_len: u64, data_offset: u64, file_len: u64) -> u64 {
+    if box_size == 0 || box_offset + box_size > file_len {
+        file_len - data_offset
+    } else {
+        box_size - header_len
+    }
+}
+
+/// Splits `mdat`'s raw bytes into AVC/HEVC samples: each is a 4-byte
+/// big-endian length prefix followed by that many bytes of NAL data, the
+/// framing `mp4mux` writes in place of Annex-B start codes. A trailing
+/// partial sample - the one being written when the recording was cut off -
+/// is dropped rather than passed on truncated.
+fn split_avc_samples(mdat: &[u8]) -> Vec<&[u8]> {
+    let mut samples = Vec::new();
+    let mut offset = 0;
+    while offset + 4 <= mdat.len() {
+        let length = u32::from_be_bytes(mdat[offset..offset + 4].try_into().unwrap()) as usize;
+        let start = offset + 4;
+        if start + length > mdat.len() {
+            break;
+        }
+        samples.push(&mdat[start..start + length]);
+        offset = start + length;
+    }
+    samples
+}
+
+/// Renegotiates `codec`/`encoder`'s SPS/PPS by running one throwaway frame
+/// through the exact element [`encoder_elements`] would have used at
+/// recording time, so the recovered file's `stsd` matches what `mp4mux`
+/// would have written - that codec config data lived only in the `moov` box
+/// this recording lost, not anywhere in `mdat` itself.
+fn negotiate_codec_data(codec: VideoCodec, encoder: Encoder, width: u32, height: u32, framerate: u32) -> Result<gst::Buffer, String> {
+    let (encoder_element, parser_element) = encoder_elements(codec, encoder);
+    let description = format!(
+        "videotestsrc num-buffers=1 ! video/x-raw,width={width},height={height},framerate={framerate}/1 ! \
+         videoconvert ! {encoder_element} ! {parser_element} config-interval=-1 ! appsink name=sink sync=false",
+    );
+    let pipeline = gst::parse::launch(&description)
+        .map_err(|error| format!("failed to build codec-data probe pipeline: {error}"))?
+        .downcast::<gst::Pipeline>()
+        .expect("parse::launch of a pipeline description returns a Pipeline");
+
+    let appsink = pipeline
+        .by_name("sink")
+        .and_then(|element| element.downcast::<AppSink>().ok())
+        .ok_or_else(|| "codec-data probe pipeline has no appsink named \"sink\"".to_string())?;
+
+    pipeline.set_state(gst::State::Playing).map_err(|error| format!("failed to start codec-data probe pipeline: {error}"))?;
+    let sample = appsink.pull_sample().map_err(|_| format!("{encoder_element} produced no sample to recover codec_data from"));
+    let _ = pipeline.set_state(gst::State::Null);
+    let sample = sample?;
+
+    let caps = sample.caps().ok_or_else(|| "codec-data probe sample has no caps".to_string())?;
+    let structure = caps.structure(0).ok_or_else(|| "codec-data probe caps are empty".to_string())?;
+    structure.get::<gst::Buffer>("codec_data").map_err(|_| format!("{encoder_element} did not negotiate codec_data"))
+}
+
+/// Reads `input`'s `.timestamps.csv` sidecar (see
+/// [`super::frame_writer::spawn`]) and returns each of `expected_samples`'
+/// durations, computed from consecutive `monotonic_ns` columns. Falls back
+/// to `expected_samples` evenly-spaced durations at `fallback_fps` - the
+/// same constant-framerate assumption `mp4mux` itself would have made - if
+/// the sidecar is missing, unreadable, or has a different row count than
+/// `expected_samples` (the sidecar and the recovered sample count can
+/// legitimately disagree by the one in-flight frame each was writing when
+/// the recording was cut off).
+fn frame_durations(input: &Path, expected_samples: usize, fallback_fps: u32) -> Vec<u64> {
+    let fallback = || vec![1_000_000_000 / u64::from(fallback_fps.max(1)); expected_samples];
+
+    let sidecar_path = input.with_extension("timestamps.csv");
+    let Ok(contents) = std::fs::read_to_string(&sidecar_path) else {
+        return fallback();
+    };
+
+    let monotonic_ns: Vec<u64> = contents.lines().skip(1).filter_map(|line| line.split(',').nth(1)?.parse().ok()).collect();
+    if monotonic_ns.len() != expected_samples {
+        warn!(
+            "{} recovered {expected_samples} samples but {} has {} rows; falling back to a constant {fallback_fps}fps",
+            input.display(),
+            sidecar_path.display(),
+            monotonic_ns.len(),
+        );
+        return fallback();
+    }
+
+    let average = 1_000_000_000 / u64::from(fallback_fps.max(1));
+    (0..monotonic_ns.len())
+        .map(|index| match monotonic_ns.get(index + 1) {
+            Some(&next) => next.saturating_sub(monotonic_ns[index]),
+            None => average,
+        })
+        .collect()
+}
+
+/// The `video/x-{format}` name and `stream-format` value the recovery mux
+/// appsrc's caps need for `codec`. `avc` is only a legal `stream-format` for
+/// `video/x-h264`; `h265parse`'s sink caps never accept it, only
+/// `byte-stream`/`hvc1`/`hev1`/`lhvc` - `hvc1` matches `h265parse`'s own
+/// default output format, so it's what the downstream `mp4mux` expects.
+fn mux_caps_format(codec: VideoCodec) -> (&'static str, &'static str) {
+    if codec == VideoCodec::H264 { ("h264", "avc") } else { ("h265", "hvc1") }
+}
+
+/// Pushes `samples` (each paired with its `durations` entry) through
+/// `codec`/`encoder`'s parser and a fresh `mp4mux`, producing a
+/// conventionally playable file at `output`.
+fn mux_samples(
+    codec: VideoCodec,
+    encoder: Encoder,
+    width: u32,
+    height: u32,
+    samples: &[&[u8]],
+    codec_data: gst::Buffer,
+    durations: &[u64],
+    output: &Path,
+) -> Result<(), String> {
+    let (_, parser_element) = encoder_elements(codec, encoder);
+    let (format, stream_format) = mux_caps_format(codec);
+    let description = format!(
+        "appsrc name=src format=time ! video/x-{format},stream-format={stream_format},alignment=au,width={width},height={height} ! \
+         {parser_element} ! mp4mux ! filesink location={}",
+        output.display(),
+    );
+    let pipeline = gst::parse::launch(&description)
+        .map_err(|error| format!("failed to build recovery mux pipeline: {error}"))?
+        .downcast::<gst::Pipeline>()
+        .expect("parse::launch of a pipeline description returns a Pipeline");
+
+    let appsrc = pipeline
+        .by_name("src")
+        .and_then(|element| element.downcast::<AppSrc>().ok())
+        .ok_or_else(|| "recovery mux pipeline has no appsrc named \"src\"".to_string())?;
+    let mut src_caps = appsrc.caps().ok_or_else(|| "recovery mux appsrc negotiated no caps".to_string())?;
+    src_caps.get_mut().expect("freshly-negotiated caps aren't shared yet").set("codec_data", codec_data);
+    appsrc.set_caps(Some(&src_caps));
+
+    pipeline.set_state(gst::State::Playing).map_err(|error| format!("failed to start recovery mux pipeline: {error}"))?;
+
+    let mut pts_ns = 0u64;
+    for (sample, duration_ns) in samples.iter().zip(durations) {
+        let mut buffer = gst::Buffer::from_slice(sample.to_vec());
+        {
+            let buffer = buffer.get_mut().expect("freshly-allocated buffer has no other owners");
+            buffer.set_pts(gst::ClockTime::from_nseconds(pts_ns));
+            buffer.set_duration(gst::ClockTime::from_nseconds(*duration_ns));
+        }
+        if let Err(error) = appsrc.push_buffer(buffer) {
+            let _ = pipeline.set_state(gst::State::Null);
+            return Err(format!("failed to push a recovered sample into the mux pipeline: {error}"));
+        }
+        pts_ns += duration_ns;
+    }
+    let _ = appsrc.end_of_stream();
+
+    let bus = pipeline.bus().expect("pipeline should have a bus");
+    for message in bus.iter_timed(gst::ClockTime::NONE) {
+        match message.view() {
+            MessageView::Eos(_) => break,
+            MessageView::Error(error) => {
+                let _ = pipeline.set_state(gst::State::Null);
+                return Err(format!("recovery mux pipeline failed: {} ({:?})", error.error(), error.debug()));
+            }
+            _ => {}
+        }
+    }
+
+    pipeline.set_state(gst::State::Null).map_err(|error| format!("failed to stop recovery mux pipeline: {error}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mux_caps_format_h264_uses_avc() {
+        assert_eq!(mux_caps_format(VideoCodec::H264), ("h264", "avc"));
+    }
+
+    #[test]
+    fn mux_caps_format_h265_uses_hvc1_not_avc() {
+        // Regression: `avc` is only legal for `video/x-h264`; an h265
+        // recording's recovery mux would otherwise fail caps negotiation.
+        assert_eq!(mux_caps_format(VideoCodec::H265), ("h265", "hvc1"));
+    }
+
+    #[test]
+    fn mdat_payload_len_normal_header_finalized_box() {
+        // An 8-byte header box at offset 100 with a declared size of 1108
+        // (i.e. a 1100-byte payload) that fits inside the file.
+        assert_eq!(mdat_payload_len(100, 1108, 8, 108, 10_000), 1100);
+    }
+
+    #[test]
+    fn mdat_payload_len_extended_header_finalized_box() {
+        // Same box, but via the 64-bit extended-size form: the 16-byte
+        // header must come off the declared size, not 8, or this reads 8
+        // bytes too many into the recovered sample data.
+        assert_eq!(mdat_payload_len(100, 1116, 16, 116, 10_000), 1100);
+    }
+
+    #[test]
+    fn mdat_payload_len_placeholder_size_reads_to_eof() {
+        // `mp4mux`'s unfinalized placeholder size of 0, normal header.
+        assert_eq!(mdat_payload_len(100, 0, 8, 108, 10_000), 9_892);
+    }
+
+    #[test]
+    fn mdat_payload_len_overrunning_size_reads_to_eof() {
+        // A declared size past EOF (truncated mid-write before `mp4mux`
+        // finished patching it) falls back to everything up to EOF, same as
+        // the placeholder case - normal header.
+        assert_eq!(mdat_payload_len(100, 50_000, 8, 108, 10_000), 9_892);
+    }
+
+    #[test]
+    fn mdat_payload_len_overrunning_extended_size_reads_to_eof() {
+        // Same overrun fallback, but for a box that took the 64-bit
+        // extended-size path.
+        assert_eq!(mdat_payload_len(100, 50_000, 16, 116, 10_000), 9_884);
+    }
+
+    #[test]
+    fn split_avc_samples_drops_trailing_partial_sample() {
+        let mut mdat = Vec::new();
+        mdat.extend_from_slice(&3u32.to_be_bytes());
+        mdat.extend_from_slice(&[1, 2, 3]);
+        mdat.extend_from_slice(&2u32.to_be_bytes());
+        mdat.extend_from_slice(&[4, 5]);
+        // A length prefix claiming more bytes than remain: the in-flight
+        // sample at the moment of truncation.
+        mdat.extend_from_slice(&10u32.to_be_bytes());
+        mdat.extend_from_slice(&[6, 7]);
+
+        let samples = split_avc_samples(&mdat);
+        assert_eq!(samples, vec![&[1u8, 2, 3][..], &[4u8, 5][..]]);
+    }
+
+    #[test]
+    fn split_avc_samples_empty_mdat() {
+        assert!(split_avc_samples(&[]).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+//! An opt-in low-bitrate RTSP preview stream, tapped off a recording
+//! backend's always-on capture pipeline via [`preview_branch`]'s `tee`, so a
+//! GCS operator can watch a live low-bitrate feed without touching the
+//! full-quality file being written.
+//!
+//! Only [`super::gstreamer_backend::PrerollMode`] has an always-on capture
+//! pipeline to tap; [`super::gstreamer_backend::SimpleMode`]'s pipeline only
+//! runs while armed, so there's nothing continuous to preview from. See
+//! [`super::gstreamer_backend::GstreamerRecorder::new`].
+//!
+//! `gst-rtsp-server` drives its own GLib main loop, so a dedicated thread
+//! runs it independently of the tokio runtime the rest of this process
+//! uses.
+
+use gstreamer::glib;
+use gstreamer::prelude::*;
+use gstreamer_app::{AppSink, AppSrc};
+use gstreamer_rtsp_server::prelude::*;
+use gstreamer_rtsp_server::{RTSPMediaFactory, RTSPServer};
+use log::{debug, error, info, warn};
+
+/// Pipeline fragment to splice into a capture pipeline description at a
+/// `tee name=preview_tee`: a low-bitrate encode ending in an `appsink`,
+/// bridged into the RTSP server's own pipeline by [`spawn`].
+///
+/// `osd_overlay_element`, if given, splices
+/// [`super::osd_overlay::overlay_fragment`] in right before the encoder, so
+/// this stream (and only this stream) gets a burned-in telemetry overlay.
+pub fn preview_branch(bitrate_kbps: u32, osd_overlay_element: Option<&str>) -> String {
+    let overlay_fragment = osd_overlay_element.map(super::osd_overlay::overlay_fragment).unwrap_or_default();
+    format!(
+        "preview_tee. ! queue leaky=downstream max-size-buffers=2 ! videoscale ! \
+         video/x-raw,width=640,height=360 ! {overlay_fragment}x264enc bitrate={bitrate_kbps} tune=zerolatency \
+         key-int-max=15 ! h264parse config-interval=1 ! \
+         appsink name=preview_sink emit-signals=false sync=false drop=true max-buffers=1",
+    )
+}
+
+/// Starts an RTSP server on `address` (`host:port`) serving `/preview`,
+/// bridging its media's `appsrc` to `pipeline`'s `preview_sink` appsink
+/// (built by [`preview_branch`]) the first time a client connects.
+pub fn spawn(address: &str, pipeline: &gstreamer::Pipeline) -> Result<(), String> {
+    let appsink = pipeline
+        .by_name("preview_sink")
+        .and_then(|element| element.downcast::<AppSink>().ok())
+        .ok_or_else(|| "capture pipeline has no appsink named \"preview_sink\"".to_string())?;
+
+    let (host, port) = address
+        .rsplit_once(':')
+        .ok_or_else(|| format!("rtsp preview address {address:?} is not host:port"))?;
+
+    let server = RTSPServer::new();
+    server.set_address(host);
+    server.set_service(port);
+
+    let factory = RTSPMediaFactory::new();
+    factory.set_launch("( appsrc name=src is-live=true format=time ! rtph264pay name=pay0 pt=96 )");
+    // Every client shares the one pipeline/appsrc built on first connect,
+    // rather than each getting an independent decode of the source.
+    factory.set_shared(true);
+
+    factory.connect_media_configure(move |_factory, media| {
+        let element = media.element();
+        let Some(bin) = element.downcast_ref::<gstreamer::Bin>() else {
+            error!("rtsp preview media's top-level element was not a bin");
+            return;
+        };
+        let Some(rtsp_appsrc) = bin.by_name("src").and_then(|element| element.downcast::<AppSrc>().ok()) else {
+            error!("rtsp preview media has no appsrc named \"src\"");
+            return;
+        };
+
+        let appsink = appsink.clone();
+        std::thread::spawn(move || loop {
+            let sample = match appsink.pull_sample() {
+                Ok(sample) => sample,
+                Err(_) => {
+                    debug!("capture pipeline appsink stopped, rtsp preview bridge exiting");
+                    break;
+                }
+            };
+
+            let Some(buffer) = sample.buffer_owned() else { continue };
+            if let Err(error) = rtsp_appsrc.push_buffer(buffer) {
+                warn!("failed to push buffer into rtsp preview: {error}");
+            }
+        });
+    });
+
+    let mount_points = server
+        .mount_points()
+        .ok_or_else(|| "rtsp server has no mount points".to_string())?;
+    mount_points.add_factory("/preview", factory);
+
+    let source_id = server
+        .attach(None)
+        .map_err(|error| format!("failed to attach rtsp server: {error}"))?;
+
+    info!("rtsp preview available at rtsp://{address}/preview");
+
+    std::thread::spawn(move || {
+        glib::MainLoop::new(None, false).run();
+        drop(source_id);
+    });
+
+    Ok(())
+}
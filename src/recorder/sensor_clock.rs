@@ -0,0 +1,125 @@
+//! Tracks a continuous linear fit between a backend's own frame timestamp
+//! counter (libcamera's `SensorTimestamp`, or a V4L2 driver's buffer
+//! timestamp) and `CLOCK_MONOTONIC`, so a multi-hour flight's frame
+//! timestamps don't slowly drift away from the GPIO trigger timestamps
+//! they're meant to line up with in [`super::frame_writer`]'s
+//! `.timestamps.csv` sidecar.
+//!
+//! Both backends' frame timestamps are nominally `CLOCK_MONOTONIC` already
+//! (see their own module docs), but in practice come from the sensor/kernel's
+//! own free-running counter, which can drift a few parts per million against
+//! the host's `CLOCK_MONOTONIC` over a long enough recording - small per
+//! frame, but enough to matter after hours of survey flight. Refitting
+//! continuously over a rolling window (rather than measuring the offset once
+//! at startup) tracks that drift instead of assuming it's constant.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// How many of the most recent (sensor, monotonic) sample pairs the linear
+/// fit is computed over. Large enough to average out per-frame jitter in
+/// exactly when the capture thread happens to read `CLOCK_MONOTONIC`, small
+/// enough that the fit tracks drift on the timescale of seconds rather than
+/// smearing it over the whole flight.
+const WINDOW: usize = 256;
+
+/// Continuously refits `monotonic_ns ~= slope * sensor_ns + intercept` over
+/// the last [`WINDOW`] recorded samples.
+pub struct DriftEstimator {
+    samples: Mutex<VecDeque<(u64, u64)>>,
+}
+
+impl DriftEstimator {
+    pub fn new() -> Self {
+        Self { samples: Mutex::new(VecDeque::with_capacity(WINDOW)) }
+    }
+
+    /// Records one (sensor_ns, monotonic_ns) pair. Callers should read
+    /// `monotonic_ns` as close as possible to when the backend handed back
+    /// `sensor_ns`, so the pair reflects the same instant in both clocks.
+    pub fn record(&self, sensor_ns: u64, monotonic_ns: u64) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == WINDOW {
+            samples.pop_front();
+        }
+        samples.push_back((sensor_ns, monotonic_ns));
+    }
+
+    /// Maps `sensor_ns` onto the `CLOCK_MONOTONIC` domain via the current
+    /// linear fit. Returns `sensor_ns` unchanged until at least two distinct
+    /// samples have been recorded to fit against.
+    pub fn correct(&self, sensor_ns: u64) -> u64 {
+        let samples = self.samples.lock().unwrap();
+        match linear_fit(&samples) {
+            Some((slope, intercept)) => (slope * sensor_ns as f64 + intercept).round().max(0.0) as u64,
+            None => sensor_ns,
+        }
+    }
+}
+
+/// Ordinary least-squares slope/intercept for `y ~= slope * x + intercept`
+/// over `samples`. `x` is shifted by its first value before fitting to keep
+/// the sums well-conditioned against nanosecond-scale timestamps, then the
+/// intercept is shifted back to apply to raw (unshifted) `x` values. `None`
+/// if there aren't at least two samples, or the fit is numerically
+/// degenerate (e.g. every sample has the same `x`).
+fn linear_fit(samples: &VecDeque<(u64, u64)>) -> Option<(f64, f64)> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let x0 = samples[0].0 as f64;
+    let n = samples.len() as f64;
+    let (sum_x, sum_y, sum_xx, sum_xy) = samples.iter().fold((0.0, 0.0, 0.0, 0.0), |(sx, sy, sxx, sxy), &(x, y)| {
+        let x = x as f64 - x0;
+        let y = y as f64;
+        (sx + x, sy + y, sxx + x * x, sxy + x * y)
+    });
+
+    let denominator = n * sum_xx - sum_x * sum_x;
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let slope = (n * sum_xy - sum_x * sum_y) / denominator;
+    let intercept = (sum_y - slope * sum_x) / n - slope * x0;
+    Some((slope, intercept))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uncorrected_until_two_samples_exist() {
+        let estimator = DriftEstimator::new();
+        assert_eq!(estimator.correct(1_000), 1_000);
+
+        estimator.record(1_000, 1_050);
+        assert_eq!(estimator.correct(2_000), 2_000);
+    }
+
+    #[test]
+    fn fits_a_constant_offset() {
+        let estimator = DriftEstimator::new();
+        for sensor_ns in (0..10_000).step_by(1_000) {
+            estimator.record(sensor_ns, sensor_ns + 500);
+        }
+
+        assert_eq!(estimator.correct(20_000), 20_500);
+    }
+
+    #[test]
+    fn tracks_a_linear_drift() {
+        let estimator = DriftEstimator::new();
+        // Sensor clock runs 1% fast relative to CLOCK_MONOTONIC.
+        for sensor_ns in (0..1_000_000u64).step_by(100_000) {
+            let monotonic_ns = (sensor_ns as f64 * 0.99) as u64;
+            estimator.record(sensor_ns, monotonic_ns);
+        }
+
+        let corrected = estimator.correct(2_000_000);
+        let expected = (2_000_000f64 * 0.99) as u64;
+        assert!(corrected.abs_diff(expected) < 10, "corrected={corrected} expected={expected}");
+    }
+}
@@ -0,0 +1,26 @@
+//! An opt-in SRT push output, tapped off a recording backend's always-on
+//! capture pipeline via [`output_branch`]'s `tee`, so the ground station can
+//! monitor footage over the long-range link without touching the
+//! full-quality file being written.
+//!
+//! `srtsink` in caller mode (the URI's `mode=caller`) is the party that
+//! dials out and keeps retrying the connection on its own whenever it drops
+//! - e.g. the long-range link cutting out mid-flight - so there's no
+//! reconnect loop to hand-roll here, unlike [`super::rtsp_preview`]'s own
+//! server.
+
+/// Pipeline fragment to splice into a capture pipeline description at a
+/// `tee name=preview_tee`: an independent encode, muxed into MPEG-TS (SRT's
+/// usual payload) and pushed to `address` (`host:port`) via `srtsink`.
+///
+/// `osd_overlay_element`, if given, splices
+/// [`super::osd_overlay::overlay_fragment`] in right before the encoder, so
+/// this stream (and only this stream) gets a burned-in telemetry overlay.
+pub fn output_branch(address: &str, bitrate_kbps: u32, osd_overlay_element: Option<&str>) -> String {
+    let overlay_fragment = osd_overlay_element.map(super::osd_overlay::overlay_fragment).unwrap_or_default();
+    format!(
+        "preview_tee. ! queue leaky=downstream max-size-buffers=2 ! \
+         {overlay_fragment}x264enc tune=zerolatency bitrate={bitrate_kbps} key-int-max=30 ! h264parse config-interval=1 ! \
+         mpegtsmux alignment=7 ! srtsink uri=srt://{address}?mode=caller",
+    )
+}
@@ -0,0 +1,989 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use gstreamer::prelude::*;
+use gstreamer::{self as gst, MessageView};
+use log::{debug, error, warn};
+
+use super::gstreamer_backend::orientation_fragment;
+use super::{CaptureSource, NamingMode, RecorderConfig, RecordingBackend};
+use crate::naming::NamingScheme;
+
+/// Where [`StillCapture::capture`] gets its next output location from, one
+/// per [`NamingMode`]. Kept separate from the identically-shaped enums in
+/// [`super::gstreamer_backend`] and [`super::subprocess_backend`] since a
+/// still capture and this camera's video recorder (if any) each pick their
+/// own locations independently.
+enum LocationSource {
+    Pattern { location_pattern: String, next_trigger_id: AtomicU64 },
+    Structured(NamingScheme),
+}
+
+impl LocationSource {
+    fn next(&self) -> String {
+        match self {
+            LocationSource::Pattern { location_pattern, next_trigger_id } => {
+                let trigger_id = next_trigger_id.fetch_add(1, Ordering::Relaxed);
+                location_pattern.replace("{trigger}", &trigger_id.to_string())
+            }
+            LocationSource::Structured(naming) => naming.next_path("jpg").display().to_string(),
+        }
+    }
+}
+
+/// Captures one (or, with `still_burst_count` above 1, several) full-
+/// resolution JPEGs per trigger, for survey-style photo missions rather than
+/// continuous video.
+///
+/// Built alongside a camera's [`super::Recorder`] but driven independently,
+/// on [`crate::trigger::Transition::CaptureStill`] rather than
+/// `Start`/`Stop`.
+pub struct StillCapture {
+    backend: RecordingBackend,
+    source: CaptureSource,
+    source_device: PathBuf,
+    libcamera_still_binary: PathBuf,
+    location_source: LocationSource,
+    burst_count: u32,
+    /// EV offsets to bracket across instead of capturing `burst_count`
+    /// identical frames, per `still_aeb_enabled`/`still_aeb_ev_stops`. Empty
+    /// whenever bracketing isn't in effect - either it's off, or `backend`
+    /// can't drive per-shot exposure - so [`Self::capture`] can treat "empty"
+    /// as "fall back to a plain burst" without a separate flag.
+    aeb_ev_stops: Vec<f32>,
+    /// Whether to also request a raw Bayer stream and save it alongside each
+    /// JPEG, per `still_raw_enabled`. Only ever true when `backend` is
+    /// [`RecordingBackend::LibcameraNative`]; see [`RecorderConfig::still_raw_enabled`].
+    raw_enabled: bool,
+    /// Whether to save a 16-bit radiometric TIFF instead of a JPEG, per
+    /// `still_thermal_radiometric_enabled`. Only ever true when `backend` is
+    /// [`RecordingBackend::V4l2Direct`]; see
+    /// [`RecorderConfig::still_thermal_radiometric_enabled`].
+    radiometric_enabled: bool,
+    /// A handle for grabbing a frame off this camera's already-running
+    /// video recorder instead of opening a second, independent pipeline
+    /// against the same device, per `still_dual_stream_enabled`. Only ever
+    /// set when `backend` is [`RecordingBackend::Gstreamer`]; see
+    /// [`RecorderConfig::still_dual_stream_enabled`] and
+    /// [`super::gstreamer_backend::GstreamerRecorder::still_tap`].
+    gstreamer_tap: Option<super::gstreamer_backend::StillTap>,
+    /// `videoflip`/`videocrop` fragment for `config.orientation`/`config.crop_*`,
+    /// precomputed so [`Self::capture`]'s `RecordingBackend::Gstreamer` arm
+    /// doesn't need to hold onto the whole config just for this. Only
+    /// consulted when `gstreamer_tap` is `None`: a dual-stream still already
+    /// comes off the video recorder's already-oriented/cropped pipeline.
+    orientation_fragment: String,
+}
+
+impl StillCapture {
+    /// Returns `Ok(None)` when `config.still_capture` is off, so callers can
+    /// skip building one entirely for video-only cameras.
+    ///
+    /// `camera_id` and `flight_session` are only consulted when
+    /// `config.naming` is [`NamingMode::Structured`]. A `"-still"` suffix is
+    /// appended to `camera_id` for this purpose, so a camera doing both
+    /// video and stills gets two independent sequence counters (and
+    /// distinguishable filenames) instead of racing the video recorder's
+    /// [`NamingScheme`] over the same counter file.
+    ///
+    /// `gstreamer_tap` is the running video recorder's still-capture tap, if
+    /// `config.backend` is [`RecordingBackend::Gstreamer`] and it built one
+    /// (see [`super::gstreamer_backend::GstreamerRecorder::still_tap`]); pass
+    /// `None` for any other backend, or when this camera isn't recording
+    /// video at all.
+    pub fn new(
+        config: &RecorderConfig,
+        camera_id: &str,
+        flight_session: &str,
+        gstreamer_tap: Option<super::gstreamer_backend::StillTap>,
+    ) -> Result<Option<Self>, String> {
+        if !config.still_capture {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create still-capture output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+
+        let location_source = match config.naming {
+            NamingMode::Pattern => LocationSource::Pattern {
+                location_pattern: config.output_dir.join(&config.still_file_pattern).display().to_string(),
+                next_trigger_id: AtomicU64::new(0),
+            },
+            NamingMode::Structured => LocationSource::Structured(NamingScheme::new(
+                &config.output_dir,
+                flight_session,
+                &format!("{camera_id}-still"),
+            )?),
+        };
+
+        let aeb_ev_stops = if !config.still_aeb_enabled {
+            Vec::new()
+        } else if !matches!(config.backend, RecordingBackend::LibcameraNative) {
+            warn!(
+                "still_aeb_enabled is on, but {:?} can't drive per-shot exposure; \
+                 falling back to a plain still_burst_count capture",
+                config.backend
+            );
+            Vec::new()
+        } else {
+            let stops = parse_ev_stops(&config.still_aeb_ev_stops);
+            if stops.is_empty() {
+                warn!("still_aeb_enabled is on, but still_aeb_ev_stops ({:?}) has no usable offsets", config.still_aeb_ev_stops);
+            }
+            stops
+        };
+
+        let raw_enabled = if !config.still_raw_enabled {
+            false
+        } else if !matches!(config.backend, RecordingBackend::LibcameraNative) {
+            warn!(
+                "still_raw_enabled is on, but {:?} can't drive a raw stream; capturing JPEG only",
+                config.backend
+            );
+            false
+        } else {
+            true
+        };
+
+        let radiometric_enabled = if !config.still_thermal_radiometric_enabled {
+            false
+        } else if !matches!(config.backend, RecordingBackend::V4l2Direct) {
+            warn!(
+                "still_thermal_radiometric_enabled is on, but {:?} can't switch into a raw Y16 \
+                 output mode; capturing a normal JPEG instead",
+                config.backend
+            );
+            false
+        } else {
+            true
+        };
+
+        Ok(Some(Self {
+            backend: config.backend,
+            source: config.source,
+            source_device: config.source_device.clone(),
+            libcamera_still_binary: config.libcamera_still_binary.clone(),
+            location_source,
+            burst_count: config.still_burst_count,
+            aeb_ev_stops,
+            raw_enabled,
+            radiometric_enabled,
+            gstreamer_tap: if matches!(config.backend, RecordingBackend::Gstreamer) { gstreamer_tap } else { None },
+            orientation_fragment: orientation_fragment(config),
+        }))
+    }
+
+    /// The EV offsets [`Self::capture`] is bracketing across, in shooting
+    /// order, or an empty slice if bracketing isn't in effect. Callers use
+    /// this to decide whether to group a capture's frames into
+    /// [`crate::manifest::Manifest::record_bracket`] instead of logging them
+    /// as independent stills.
+    pub fn aeb_ev_stops(&self) -> &[f32] {
+        &self.aeb_ev_stops
+    }
+
+    /// Captures `burst_count` frames (1 unless configured otherwise) for a
+    /// single trigger, blocking the calling thread until each one finishes.
+    /// Returns the paths of the frames that were actually written, so
+    /// callers can post-process them (e.g. [`crate::geotag`]) or log them
+    /// without re-deriving the naming scheme; a frame that failed to
+    /// capture is simply omitted.
+    ///
+    /// If [`Self::aeb_ev_stops`] is non-empty, captures one frame per EV
+    /// offset instead, in the same shooting order, so callers can zip the
+    /// two together to group the burst as an AEB bracket.
+    pub fn capture(&self) -> Vec<PathBuf> {
+        let base_location = self.location_source.next();
+
+        if !self.aeb_ev_stops.is_empty() {
+            let mut captured = Vec::with_capacity(self.aeb_ev_stops.len());
+            for (frame_index, &ev) in self.aeb_ev_stops.iter().enumerate() {
+                let location = with_frame_suffix(&base_location, frame_index as u32);
+                if capture_libcamera_native(&self.source_device, &location, Some(ev), self.raw_enabled) {
+                    captured.push(PathBuf::from(location));
+                }
+            }
+            return captured;
+        }
+
+        let burst_count = self.burst_count.max(1);
+        let mut captured = Vec::with_capacity(burst_count as usize);
+
+        for frame_index in 0..burst_count {
+            let mut location = if burst_count > 1 {
+                with_frame_suffix(&base_location, frame_index)
+            } else {
+                base_location.clone()
+            };
+            if self.radiometric_enabled {
+                location = PathBuf::from(&location).with_extension("tiff").display().to_string();
+            }
+
+            let succeeded = match self.backend {
+                RecordingBackend::Gstreamer => match &self.gstreamer_tap {
+                    Some(tap) => tap.capture(&location),
+                    None => capture_gstreamer(self.source, &self.source_device, &self.orientation_fragment, &location),
+                },
+                RecordingBackend::LibcameraVid => {
+                    capture_libcamera_still(&self.libcamera_still_binary, &self.source_device, &location)
+                }
+                RecordingBackend::V4l2Direct if self.radiometric_enabled => {
+                    capture_v4l2_radiometric_tiff(&self.source_device, &location)
+                }
+                RecordingBackend::V4l2Direct => capture_v4l2_direct(&self.source_device, &location),
+                RecordingBackend::LibcameraNative => {
+                    capture_libcamera_native(&self.source_device, &location, None, self.raw_enabled)
+                }
+                RecordingBackend::Gphoto2 => {
+                    super::gphoto2_backend::capture_still(&self.source_device, &location)
+                }
+                RecordingBackend::PtpIp => super::ptpip_backend::capture_still(&self.source_device, &location),
+                RecordingBackend::GigeVision => super::aravis_backend::capture_still(&self.source_device, &location),
+            };
+            if succeeded {
+                captured.push(PathBuf::from(location));
+            }
+        }
+
+        captured
+    }
+}
+
+/// Runs a one-shot `source ! videoconvert ! [videoflip/videocrop] ! jpegenc !
+/// filesink` pipeline to completion, blocking until it reports EOS (or
+/// errors, or times out). Returns whether it reached EOS successfully.
+fn capture_gstreamer(source: CaptureSource, source_device: &std::path::Path, orientation_fragment: &str, location: &str) -> bool {
+    if let Err(error) = gst::init() {
+        error!("failed to initialize gstreamer for still capture: {error}");
+        return false;
+    }
+
+    let source = match source {
+        CaptureSource::V4l2 => {
+            format!("v4l2src device={} num-buffers=1", source_device.display())
+        }
+        CaptureSource::Libcamera => {
+            format!("libcamerasrc camera-name={} num-buffers=1", source_device.display())
+        }
+    };
+    let description = format!("{source} ! videoconvert ! {orientation_fragment}jpegenc ! filesink location={location}");
+
+    let pipeline = match gst::parse::launch(&description) {
+        Ok(element) => match element.downcast::<gst::Pipeline>() {
+            Ok(pipeline) => pipeline,
+            Err(_) => {
+                error!("parsed still-capture pipeline was not a gst::Pipeline");
+                return false;
+            }
+        },
+        Err(error) => {
+            error!("failed to parse still-capture pipeline description: {error}");
+            return false;
+        }
+    };
+
+    if let Err(error) = pipeline.set_state(gst::State::Playing) {
+        error!("failed to start still-capture pipeline: {error}");
+        return false;
+    }
+
+    let bus = pipeline.bus().expect("pipeline should have a bus");
+    let succeeded = match bus.timed_pop_filtered(gst::ClockTime::from_seconds(5), &[gst::MessageType::Eos, gst::MessageType::Error]) {
+        Some(message) => match message.view() {
+            MessageView::Eos(_) => {
+                debug!("captured still image to {location}");
+                true
+            }
+            MessageView::Error(error) => {
+                error!("still-capture pipeline error: {} ({:?})", error.error(), error.debug());
+                false
+            }
+            _ => false,
+        },
+        None => {
+            warn!("timed out waiting for still-capture pipeline to finish, image may be incomplete");
+            false
+        }
+    };
+
+    if let Err(error) = pipeline.set_state(gst::State::Null) {
+        error!("failed to stop still-capture pipeline: {error}");
+    }
+
+    succeeded
+}
+
+/// Shells out to `libcamera-still`/`rpicam-still`, blocking until it exits.
+/// Returns whether it exited successfully.
+fn capture_libcamera_still(binary: &std::path::Path, source_device: &std::path::Path, location: &str) -> bool {
+    debug!("capturing still image via {} to {location}", binary.display());
+
+    let status = Command::new(binary)
+        .arg("--camera")
+        .arg(source_device)
+        .arg("-o")
+        .arg(location)
+        .arg("-n")
+        // Skip the usual autofocus/auto-exposure settle delay: this is
+        // triggered on demand mid-flight, not from an interactive shell.
+        .arg("--immediate")
+        .status();
+
+    match status {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            error!("{} exited with {status}", binary.display());
+            false
+        }
+        Err(error) => {
+            error!("failed to spawn {}: {error}", binary.display());
+            false
+        }
+    }
+}
+
+/// Acquires `source_device` directly via `libcamera`, captures a single
+/// still-capture-role frame, and writes it to `location`. Opens its own
+/// short-lived camera handle rather than sharing the video recorder's, since
+/// a still can be requested whether or not this camera is also recording
+/// video.
+///
+/// `ev` sets the request's exposure compensation, for
+/// [`StillCapture::aeb_ev_stops`] bracketing; `None` leaves exposure on
+/// whatever the camera's auto-exposure otherwise picks.
+///
+/// `raw` additionally requests a raw Bayer stream alongside the JPEG one
+/// and, if the sensor's raw format is one [`write_dng`] knows how to
+/// unpack, saves it next to `location` with a `.dng` extension. A sensor
+/// raw format we don't recognize only logs a warning; the JPEG capture
+/// still proceeds.
+fn capture_libcamera_native(source_device: &std::path::Path, location: &str, ev: Option<f32>, raw: bool) -> bool {
+    use libcamera::camera_manager::CameraManager;
+    use libcamera::control::ControlList;
+    use libcamera::controls::ExposureValue;
+    use libcamera::stream::StreamRole;
+
+    let camera_manager = match CameraManager::new() {
+        Ok(camera_manager) => camera_manager,
+        Err(error) => {
+            error!("failed to start libcamera for still capture: {error}");
+            return false;
+        }
+    };
+
+    let camera_id = source_device.to_string_lossy().into_owned();
+    let Some(camera) = camera_manager.cameras().iter().find(|camera| camera.id() == camera_id) else {
+        error!("no libcamera camera named {camera_id:?} for still capture");
+        return false;
+    };
+
+    let mut camera = match camera.acquire() {
+        Ok(camera) => camera,
+        Err(error) => {
+            error!("failed to acquire libcamera camera {camera_id:?} for still capture: {error}");
+            return false;
+        }
+    };
+
+    let roles = if raw { vec![StreamRole::StillCapture, StreamRole::Raw] } else { vec![StreamRole::StillCapture] };
+    let Some(mut pipeline_config) = camera.generate_configuration(&roles) else {
+        error!("libcamera camera {camera_id:?} has no still-capture role");
+        return false;
+    };
+
+    if let Err(error) = camera.configure(&mut pipeline_config) {
+        error!("failed to configure libcamera camera {camera_id:?} for still capture: {error}");
+        return false;
+    }
+
+    let Some(still_stream_config) = pipeline_config.get(0) else {
+        error!("libcamera camera {camera_id:?} still-capture configuration is missing");
+        return false;
+    };
+    let Some(stream) = still_stream_config.stream() else {
+        error!("libcamera camera {camera_id:?} still-capture configuration has no stream");
+        return false;
+    };
+
+    let raw_stream_config = raw.then(|| pipeline_config.get(1)).flatten();
+    let raw_stream = raw_stream_config.as_ref().and_then(|config| config.stream());
+    if raw && raw_stream.is_none() {
+        warn!("libcamera camera {camera_id:?} has no raw stream; capturing JPEG only");
+    }
+
+    let mut allocator = libcamera::framebuffer_allocator::FrameBufferAllocator::new(&camera);
+    let buffers = match allocator.alloc(&stream) {
+        Ok(buffers) => buffers,
+        Err(error) => {
+            error!("failed to allocate libcamera framebuffers for {camera_id:?} still capture: {error}");
+            return false;
+        }
+    };
+    let Some(buffer) = buffers.into_iter().next() else {
+        error!("libcamera allocator returned no framebuffers for {camera_id:?} still capture");
+        return false;
+    };
+    let mapped = match libcamera::framebuffer_map::MemoryMappedFrameBuffer::new(buffer) {
+        Ok(mapped) => mapped,
+        Err(error) => {
+            error!("failed to mmap libcamera framebuffer for {camera_id:?} still capture: {error}");
+            return false;
+        }
+    };
+
+    let raw_mapped = match &raw_stream {
+        Some(raw_stream) => match allocator.alloc(raw_stream) {
+            Ok(buffers) => match buffers.into_iter().next() {
+                Some(buffer) => libcamera::framebuffer_map::MemoryMappedFrameBuffer::new(buffer)
+                    .map_err(|error| {
+                        error!("failed to mmap raw libcamera framebuffer for {camera_id:?} still capture: {error}")
+                    })
+                    .ok(),
+                None => {
+                    error!("libcamera allocator returned no raw framebuffers for {camera_id:?} still capture");
+                    None
+                }
+            },
+            Err(error) => {
+                error!("failed to allocate raw libcamera framebuffers for {camera_id:?} still capture: {error}");
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut request = match camera.create_request(None) {
+        Some(request) => request,
+        None => {
+            error!("failed to create libcamera request for {camera_id:?} still capture");
+            return false;
+        }
+    };
+    if let Err(error) = request.add_buffer(&stream, mapped) {
+        error!("failed to attach buffer to libcamera request for {camera_id:?} still capture: {error}");
+        return false;
+    }
+    if let (Some(raw_stream), Some(raw_mapped)) = (&raw_stream, raw_mapped) {
+        if let Err(error) = request.add_buffer(raw_stream, raw_mapped) {
+            warn!("failed to attach raw buffer to libcamera request for {camera_id:?}: {error}; capturing JPEG only");
+        }
+    }
+
+    if let Some(ev) = ev {
+        let mut controls = ControlList::new();
+        let _ = controls.set(ExposureValue(ev));
+        *request.controls_mut() = controls;
+    }
+
+    let (completed_tx, completed_rx) = std::sync::mpsc::channel();
+    camera.on_request_completed(move |request| {
+        let _ = completed_tx.send(request);
+    });
+
+    if let Err(error) = camera.start(None) {
+        error!("failed to start libcamera camera {camera_id:?} for still capture: {error}");
+        return false;
+    }
+    if let Err(error) = camera.queue_request(request) {
+        error!("failed to queue libcamera still-capture request for {camera_id:?}: {error}");
+        return false;
+    }
+
+    let request = match completed_rx.recv_timeout(std::time::Duration::from_secs(5)) {
+        Ok(request) => request,
+        Err(error) => {
+            error!("timed out waiting for libcamera still-capture frame from {camera_id:?}: {error}");
+            return false;
+        }
+    };
+
+    let Some(framebuffer) = request.buffers().get(&stream) else {
+        error!("completed libcamera still-capture request had no buffer for {camera_id:?}");
+        return false;
+    };
+
+    let file = match File::create(location) {
+        Ok(file) => file,
+        Err(error) => {
+            error!("failed to create {location}: {error}");
+            return false;
+        }
+    };
+    let mut file = std::io::BufWriter::new(file);
+    for plane in framebuffer.data() {
+        if let Err(error) = file.write_all(plane) {
+            error!("failed to write still-capture frame to {location}: {error}");
+            return false;
+        }
+    }
+
+    debug!("captured still image to {location}");
+
+    if let Some(raw_stream) = &raw_stream {
+        if let (Some(raw_config), Some(raw_framebuffer)) = (&raw_stream_config, request.buffers().get(raw_stream)) {
+            let raw_format = raw_config.pixel_format().to_string();
+            let size = raw_config.size();
+            let stride = raw_config.stride();
+            let mut raw_data = Vec::new();
+            for plane in raw_framebuffer.data() {
+                raw_data.extend_from_slice(plane);
+            }
+            save_raw_frame(location, &raw_format, size.width, size.height, stride, &raw_data);
+        }
+    }
+
+    true
+}
+
+/// Opens `source_device` directly via `v4l`, negotiates MJPG, and reads a
+/// single frame - which, being MJPG, already *is* a JPEG - straight to
+/// `location`. Mirrors [`super::v4l2_backend`]'s format negotiation, but
+/// opens its own short-lived device handle rather than sharing the video
+/// recorder's, since a still can be requested whether or not this camera is
+/// also recording video.
+fn capture_v4l2_direct(source_device: &std::path::Path, location: &str) -> bool {
+    use v4l::buffer::Type;
+    use v4l::io::mmap::Stream;
+    use v4l::io::traits::CaptureStream;
+    use v4l::video::Capture;
+    use v4l::{Device, FourCC};
+
+    let mut device = match Device::with_path(source_device) {
+        Ok(device) => device,
+        Err(error) => {
+            error!("failed to open v4l2 device {} for still capture: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    let mut format = match device.format() {
+        Ok(format) => format,
+        Err(error) => {
+            error!("failed to query v4l2 capture format on {}: {error}", source_device.display());
+            return false;
+        }
+    };
+    format.fourcc = FourCC::new(b"MJPG");
+
+    if let Err(error) = device.set_format(&format) {
+        error!("failed to negotiate v4l2 capture format on {}: {error}", source_device.display());
+        return false;
+    }
+
+    let mut stream = match Stream::with_buffers(&mut device, Type::VideoCapture, 2) {
+        Ok(stream) => stream,
+        Err(error) => {
+            error!("failed to allocate v4l2 capture buffers on {}: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    // The first frame off a freshly-opened stream is often a stale buffer
+    // queued before capture settled; discard it and take the second.
+    if let Err(error) = stream.next() {
+        error!("failed to read still-capture frame from {}: {error}", source_device.display());
+        return false;
+    }
+
+    let (buffer, _) = match stream.next() {
+        Ok(frame) => frame,
+        Err(error) => {
+            error!("failed to read still-capture frame from {}: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    match std::fs::write(location, buffer) {
+        Ok(()) => {
+            debug!("captured still image to {location}");
+            true
+        }
+        Err(error) => {
+            error!("failed to write still-capture frame to {location}: {error}");
+            false
+        }
+    }
+}
+
+/// Switches `source_device` into its raw 16-bit `Y16` output mode and reads
+/// a single frame into `location` as a baseline TIFF, for FLIR Boson/Lepton-
+/// style thermal cameras whose normal AGC/YUV preview mode throws away the
+/// actual per-pixel radiometric (temperature) data. Opens its own short-lived
+/// device handle, same rationale as [`capture_v4l2_direct`].
+fn capture_v4l2_radiometric_tiff(source_device: &std::path::Path, location: &str) -> bool {
+    use v4l::buffer::Type;
+    use v4l::io::mmap::Stream;
+    use v4l::io::traits::CaptureStream;
+    use v4l::video::Capture;
+    use v4l::{Device, FourCC};
+
+    let mut device = match Device::with_path(source_device) {
+        Ok(device) => device,
+        Err(error) => {
+            error!("failed to open v4l2 device {} for radiometric capture: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    let mut format = match device.format() {
+        Ok(format) => format,
+        Err(error) => {
+            error!("failed to query v4l2 capture format on {}: {error}", source_device.display());
+            return false;
+        }
+    };
+    format.fourcc = FourCC::new(b"Y16 ");
+
+    let format = match device.set_format(&format) {
+        Ok(format) => format,
+        Err(error) => {
+            error!("failed to negotiate v4l2 Y16 radiometric format on {}: {error}", source_device.display());
+            return false;
+        }
+    };
+    if format.fourcc != FourCC::new(b"Y16 ") {
+        error!(
+            "radiometric capture requires a raw Y16 output mode, but {} only accepted {}; this \
+             camera may not support raw radiometric readout",
+            source_device.display(),
+            format.fourcc,
+        );
+        return false;
+    }
+
+    let mut stream = match Stream::with_buffers(&mut device, Type::VideoCapture, 2) {
+        Ok(stream) => stream,
+        Err(error) => {
+            error!("failed to allocate v4l2 capture buffers on {}: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    // Same stale-first-frame discard as `capture_v4l2_direct`.
+    if let Err(error) = stream.next() {
+        error!("failed to read radiometric frame from {}: {error}", source_device.display());
+        return false;
+    }
+
+    let (buffer, _) = match stream.next() {
+        Ok(frame) => frame,
+        Err(error) => {
+            error!("failed to read radiometric frame from {}: {error}", source_device.display());
+            return false;
+        }
+    };
+
+    let pixel_bytes = (format.width as usize) * (format.height as usize) * 2;
+    let pixels = &buffer[..pixel_bytes.min(buffer.len())];
+
+    match write_radiometric_tiff(location, format.width, format.height, pixels) {
+        Ok(()) => {
+            debug!("captured radiometric still to {location}");
+            true
+        }
+        Err(error) => {
+            error!("failed to write radiometric TIFF to {location}: {error}");
+            false
+        }
+    }
+}
+
+/// Writes `pixels` (16-bit little-endian grayscale samples, row-major, no
+/// padding) as a minimal single-strip, uncompressed baseline TIFF. Skips the
+/// optional resolution tags (they'd need an external `RATIONAL` blob for no
+/// benefit here) since nothing downstream cares about print DPI for a
+/// radiometric analysis file - only the pixel data and dimensions matter.
+///
+/// A full FLIR-style R-JPEG (a JPEG with a proprietary embedded radiometric
+/// metadata block) was considered instead, but that block's layout isn't
+/// documented well enough to reproduce correctly; a lossless 16-bit TIFF
+/// preserves the same per-pixel data without that risk.
+fn write_radiometric_tiff(location: &str, width: u32, height: u32, pixels: &[u8]) -> std::io::Result<()> {
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+
+    let entries: &[(u16, u16, u32, u32)] = &[
+        (256, LONG, 1, width),            // ImageWidth
+        (257, LONG, 1, height),           // ImageLength
+        (258, SHORT, 1, 16),              // BitsPerSample
+        (259, SHORT, 1, 1),               // Compression: none
+        (262, SHORT, 1, 1),               // PhotometricInterpretation: BlackIsZero
+        (273, LONG, 1, 0),                // StripOffsets (patched in below)
+        (277, SHORT, 1, 1),               // SamplesPerPixel
+        (278, LONG, 1, height),           // RowsPerStrip
+        (279, LONG, 1, pixels.len() as u32), // StripByteCounts
+    ];
+
+    let ifd_offset: u32 = 8;
+    let ifd_size = 2 + (entries.len() as u32) * 12 + 4;
+    let pixel_data_offset = ifd_offset + ifd_size;
+
+    let mut buffer = Vec::with_capacity((pixel_data_offset as usize) + pixels.len());
+    buffer.extend_from_slice(b"II");
+    buffer.extend_from_slice(&42u16.to_le_bytes());
+    buffer.extend_from_slice(&ifd_offset.to_le_bytes());
+
+    buffer.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    for &(tag, field_type, count, value) in entries {
+        let value = if tag == 273 { pixel_data_offset } else { value };
+        buffer.extend_from_slice(&tag.to_le_bytes());
+        buffer.extend_from_slice(&field_type.to_le_bytes());
+        buffer.extend_from_slice(&count.to_le_bytes());
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    buffer.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+    buffer.extend_from_slice(pixels);
+
+    let mut file = File::create(location)?;
+    file.write_all(&buffer)
+}
+
+/// Captures one throwaway frame directly from `config`'s source into
+/// `location`, bypassing [`StillCapture`] entirely (and its `still_capture`/
+/// naming-scheme requirements), for [`crate::camera_self_test`]'s startup
+/// sanity check.
+pub fn capture_probe_frame(config: &super::RecorderConfig, location: &str) -> bool {
+    match config.backend {
+        RecordingBackend::Gstreamer => {
+            capture_gstreamer(config.source, &config.source_device, &orientation_fragment(config), location)
+        }
+        RecordingBackend::LibcameraVid => {
+            capture_libcamera_still(&config.libcamera_still_binary, &config.source_device, location)
+        }
+        RecordingBackend::V4l2Direct => capture_v4l2_direct(&config.source_device, location),
+        RecordingBackend::LibcameraNative => capture_libcamera_native(&config.source_device, location, None, false),
+        RecordingBackend::Gphoto2 => super::gphoto2_backend::capture_still(&config.source_device, location),
+        RecordingBackend::PtpIp => super::ptpip_backend::capture_still(&config.source_device, location),
+        RecordingBackend::GigeVision => super::aravis_backend::capture_still(&config.source_device, location),
+    }
+}
+
+/// A sensor raw pixel format this crate knows how to unpack into plain
+/// 16-bit-per-sample Bayer data, parsed from libcamera's pixel-format name
+/// (e.g. `"SBGGR12_CSI2P"`).
+struct RawFormat {
+    /// CFA pattern as DNG's `CFAPattern` tag wants it: four color indices
+    /// (0=R, 1=G, 2=B) for the 2x2 tile in reading order, derived from the
+    /// four-letter Bayer order in the format name (e.g. `BGGR`).
+    cfa_pattern: [u8; 4],
+    /// Sensor bit depth, e.g. 10 or 12. Always upconverted to 16-bit samples
+    /// on write, so this only affects unpacking and the `WhiteLevel` tag.
+    bits: u8,
+    /// Whether samples are bit-packed (libcamera's `_CSI2P` MIPI CSI-2
+    /// packed formats) rather than one sample per 16-bit word.
+    packed: bool,
+}
+
+/// Parses a libcamera pixel-format name into a [`RawFormat`], recognizing
+/// only the handful of sensor raw formats this crate can unpack: 8/10/12-bit
+/// Bayer, packed (MIPI CSI-2, `_CSI2P` suffix) or unpacked. Anything else
+/// (e.g. a debayered or compressed format) returns `None`, so the caller can
+/// skip DNG output for that frame without failing the JPEG capture.
+fn parse_raw_format(name: &str) -> Option<RawFormat> {
+    let cfa_pattern = match &name[..name.len().min(5)] {
+        "SBGGR" => [2, 1, 1, 0],
+        "SGBRG" => [1, 2, 0, 1],
+        "SGRBG" => [1, 0, 2, 1],
+        "SRGGB" => [0, 1, 1, 2],
+        _ => return None,
+    };
+
+    let rest = &name[5..];
+    let packed = rest.ends_with("_CSI2P");
+    let bits_str = rest.strip_suffix("_CSI2P").unwrap_or(rest);
+    let bits: u8 = bits_str.parse().ok()?;
+    if !matches!(bits, 8 | 10 | 12) {
+        return None;
+    }
+
+    Some(RawFormat { cfa_pattern, bits, packed })
+}
+
+/// Unpacks `data` (one raw frame, `stride` bytes per row) into 16-bit
+/// samples according to `format`, left-justified so unused low bits are
+/// zero (e.g. a 10-bit sample becomes `sample << 6`). Rows shorter than
+/// `width` after unpacking are silently truncated/left as zero, which
+/// should only happen if `stride` disagrees with `format` - a corrupt frame
+/// either way.
+fn unpack_raw(format: &RawFormat, data: &[u8], width: u32, height: u32, stride: u32) -> Vec<u16> {
+    let width = width as usize;
+    let height = height as usize;
+    let stride = stride as usize;
+    let mut pixels = vec![0u16; width * height];
+
+    for row in 0..height {
+        let Some(row_data) = data.get(row * stride..(row + 1) * stride) else { break };
+        let out_row = &mut pixels[row * width..(row + 1) * width];
+
+        match (format.bits, format.packed) {
+            (10, true) => {
+                // MIPI RAW10: 4 pixels packed into 5 bytes, the 5th byte
+                // holding each pixel's 2 low bits (MSB-first per pixel).
+                for (group, chunk) in row_data.chunks_exact(5).enumerate() {
+                    let low_bits = chunk[4];
+                    for (index, &high_byte) in chunk[..4].iter().enumerate() {
+                        let Some(out) = out_row.get_mut(group * 4 + index) else { break };
+                        let low = (low_bits >> (index * 2)) & 0b11;
+                        let sample = ((high_byte as u16) << 2) | low as u16;
+                        *out = sample << 6;
+                    }
+                }
+            }
+            (12, true) => {
+                // MIPI RAW12: 2 pixels packed into 3 bytes, the 3rd byte
+                // holding both pixels' 4 low bits (first pixel in the low
+                // nibble, second in the high nibble).
+                for (pair, chunk) in row_data.chunks_exact(3).enumerate() {
+                    let low_bits = chunk[2];
+                    if let Some(out) = out_row.get_mut(pair * 2) {
+                        let sample = ((chunk[0] as u16) << 4) | (low_bits & 0x0F) as u16;
+                        *out = sample << 4;
+                    }
+                    if let Some(out) = out_row.get_mut(pair * 2 + 1) {
+                        let sample = ((chunk[1] as u16) << 4) | (low_bits >> 4) as u16;
+                        *out = sample << 4;
+                    }
+                }
+            }
+            (bits, false) => {
+                // Unpacked: one little-endian 16-bit word per sample,
+                // left-justified from `bits`.
+                let shift = 16 - bits;
+                for (index, sample) in row_data.chunks_exact(2).enumerate() {
+                    let Some(out) = out_row.get_mut(index) else { break };
+                    *out = u16::from_le_bytes([sample[0], sample[1]]) << shift;
+                }
+            }
+            (bits, true) => {
+                warn!("unsupported packed raw bit depth {bits}, skipping frame");
+                return pixels;
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Parses `format_name` and, if recognized, writes `data` out as a `.dng`
+/// next to `location` (same stem, `.dng` extension in place of the JPEG's).
+/// An unrecognized format only logs a warning; the JPEG capture this raw
+/// frame accompanied has already succeeded independently.
+fn save_raw_frame(location: &str, format_name: &str, width: u32, height: u32, stride: u32, data: &[u8]) {
+    let Some(format) = parse_raw_format(format_name) else {
+        warn!("still capture's raw stream uses unsupported format {format_name:?}, skipping .dng output");
+        return;
+    };
+
+    let pixels = unpack_raw(&format, data, width, height, stride);
+    let dng_path = match location.rsplit_once('.') {
+        Some((stem, _)) => format!("{stem}.dng"),
+        None => format!("{location}.dng"),
+    };
+
+    let white_level: u16 = (1u32 << format.bits.min(15)).saturating_sub(1) as u16;
+    match write_dng(&dng_path, width, height, format.cfa_pattern, 0, white_level, &pixels) {
+        Ok(()) => debug!("captured raw still image to {dng_path}"),
+        Err(error) => error!("failed to write raw still image to {dng_path}: {error}"),
+    }
+}
+
+/// Writes `pixels` (already unpacked to 16-bit-per-sample, row-major,
+/// `width * height` long) as a minimal baseline-TIFF DNG: an 8-byte header,
+/// one IFD whose 15 tags all fit inline in the directory (no separate
+/// overflow-data section needed), and the pixel data immediately after.
+/// Handrolled rather than pulling in a TIFF crate for one write path, same
+/// as [`crate::manifest`]'s hand-rolled JSON.
+fn write_dng(
+    path: &str,
+    width: u32,
+    height: u32,
+    cfa_pattern: [u8; 4],
+    black_level: u16,
+    white_level: u16,
+    pixels: &[u16],
+) -> std::io::Result<()> {
+    const ENTRY_COUNT: u16 = 15;
+    const IFD_OFFSET: u32 = 8;
+    const PIXEL_DATA_OFFSET: u32 = IFD_OFFSET + 2 + (ENTRY_COUNT as u32 * 12) + 4;
+
+    let mut buffer = Vec::with_capacity(PIXEL_DATA_OFFSET as usize + pixels.len() * 2);
+
+    // TIFF header: little-endian, magic 42, first IFD right after the header.
+    buffer.extend_from_slice(b"II");
+    buffer.extend_from_slice(&42u16.to_le_bytes());
+    buffer.extend_from_slice(&IFD_OFFSET.to_le_bytes());
+
+    buffer.extend_from_slice(&ENTRY_COUNT.to_le_bytes());
+
+    let mut entry = |buffer: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: [u8; 4]| {
+        buffer.extend_from_slice(&tag.to_le_bytes());
+        buffer.extend_from_slice(&kind.to_le_bytes());
+        buffer.extend_from_slice(&count.to_le_bytes());
+        buffer.extend_from_slice(&value);
+    };
+    let short = |value: u16| [value.to_le_bytes()[0], value.to_le_bytes()[1], 0, 0];
+    let long = |value: u32| value.to_le_bytes();
+
+    const TYPE_SHORT: u16 = 3;
+    const TYPE_LONG: u16 = 4;
+    const TYPE_BYTE: u16 = 1;
+
+    entry(&mut buffer, 0x00FE, TYPE_LONG, 1, long(0)); // NewSubfileType
+    entry(&mut buffer, 0x0100, TYPE_LONG, 1, long(width)); // ImageWidth
+    entry(&mut buffer, 0x0101, TYPE_LONG, 1, long(height)); // ImageLength
+    entry(&mut buffer, 0x0102, TYPE_SHORT, 1, short(16)); // BitsPerSample
+    entry(&mut buffer, 0x0103, TYPE_SHORT, 1, short(1)); // Compression: none
+    entry(&mut buffer, 0x0106, TYPE_SHORT, 1, short(32803)); // PhotometricInterpretation: CFA
+    entry(&mut buffer, 0x0111, TYPE_LONG, 1, long(PIXEL_DATA_OFFSET)); // StripOffsets
+    entry(&mut buffer, 0x0115, TYPE_SHORT, 1, short(1)); // SamplesPerPixel
+    entry(&mut buffer, 0x0116, TYPE_LONG, 1, long(height)); // RowsPerStrip
+    entry(&mut buffer, 0x0117, TYPE_LONG, 1, long(width * height * 2)); // StripByteCounts
+    entry(&mut buffer, 0x828D, TYPE_SHORT, 2, [2, 0, 2, 0]); // CFARepeatPatternDim
+    entry(&mut buffer, 0x828E, TYPE_BYTE, 4, cfa_pattern); // CFAPattern
+    entry(&mut buffer, 0xC612, TYPE_BYTE, 4, [1, 4, 0, 0]); // DNGVersion
+    entry(&mut buffer, 0xC61A, TYPE_SHORT, 1, short(black_level)); // BlackLevel
+    entry(&mut buffer, 0xC61D, TYPE_SHORT, 1, short(white_level)); // WhiteLevel
+
+    buffer.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+    debug_assert_eq!(buffer.len(), PIXEL_DATA_OFFSET as usize);
+    for &pixel in pixels {
+        buffer.extend_from_slice(&pixel.to_le_bytes());
+    }
+
+    std::fs::write(path, buffer)
+}
+
+/// Parses a comma-separated `still_aeb_ev_stops` string (e.g. `"-2,0,2"`)
+/// into EV offsets, skipping and warning about any entry that isn't a valid
+/// float rather than failing the whole list over one typo.
+fn parse_ev_stops(raw: &str) -> Vec<f32> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse::<f32>() {
+            Ok(ev) => Some(ev),
+            Err(error) => {
+                warn!("ignoring invalid still_aeb_ev_stops entry {entry:?}: {error}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Inserts `_<frame_index>` before `location`'s extension, so a burst
+/// capture's frames don't overwrite each other.
+fn with_frame_suffix(location: &str, frame_index: u32) -> String {
+    match location.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}_{frame_index:02}.{extension}"),
+        None => format!("{location}_{frame_index:02}"),
+    }
+}
@@ -0,0 +1,268 @@
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use log::{debug, error, warn};
+
+use super::{NamingMode, Orientation, RecorderConfig};
+use crate::naming::NamingScheme;
+
+/// How often the supervisor thread polls the child for exit while it's
+/// expected to still be running.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Delay before the first restart of a crashed `libcamera-vid`, doubled on
+/// every restart that keeps crash-looping (see [`RESTART_BACKOFF_MAX`] and
+/// [`RESTART_BACKOFF_RESET_AFTER`]). Without this, a camera stuck crash-
+/// looping (bad `--camera` index, disconnected sensor, out-of-space output
+/// directory) would respawn as fast as the OS allows, burning CPU that the
+/// other camera on the same board needs.
+const RESTART_BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+
+/// Upper bound the doubling backoff is capped at.
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/// A run that stays up at least this long is treated as healthy again: the
+/// backoff resets to [`RESTART_BACKOFF_INITIAL`] instead of continuing to
+/// grow from whatever it reached during an earlier, unrelated crash loop.
+const RESTART_BACKOFF_RESET_AFTER: Duration = Duration::from_secs(60);
+
+/// Where [`SubprocessRecorder::start`] gets the next output location from,
+/// one per [`NamingMode`].
+enum LocationSource {
+    Pattern { location_pattern: String, next_trigger_id: AtomicU64 },
+    Structured(NamingScheme),
+}
+
+/// Supervises `libcamera-vid`/`rpicam-vid` as a child process instead of
+/// driving a GStreamer pipeline in-process, for boards where the vendored
+/// libcamera GStreamer element isn't available but the CLI tool is.
+///
+/// Unlike [`super::gstreamer_backend::GstreamerRecorder`], "stop" here is a
+/// process kill rather than an EOS handshake, so there's no equivalent
+/// finalization wait: `libcamera-vid` is expected to write a container that
+/// tolerates being killed mid-stream (e.g. an unbounded `.h264` or a
+/// fragmented `.mp4`).
+pub struct SubprocessRecorder {
+    binary: PathBuf,
+    camera: PathBuf,
+    location_source: LocationSource,
+    /// Whether `extra_args` includes `--segment`, i.e. whether the location
+    /// handed to `libcamera-vid` needs a `%05d` fragment placeholder rather
+    /// than a single concrete path.
+    segmented: bool,
+    extra_args: Vec<String>,
+    /// Whether a recording is currently supposed to be running. The
+    /// supervisor thread spawned by [`SubprocessRecorder::start`] reads this
+    /// to tell an expected shutdown (`stop` just killed the child) apart
+    /// from a genuine mid-flight crash that should be restarted.
+    armed: Arc<AtomicBool>,
+    /// The currently running child, if any, so `stop` can kill it.
+    child: Arc<Mutex<Option<Child>>>,
+}
+
+impl SubprocessRecorder {
+    /// Validates the output directory exists; the child process itself
+    /// isn't spawned until [`SubprocessRecorder::start`].
+    ///
+    /// `camera_id` and `flight_session` are only consulted when
+    /// `config.naming` is [`NamingMode::Structured`].
+    pub fn new(config: &RecorderConfig, camera_id: &str, flight_session: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+
+        let location_source = match config.naming {
+            NamingMode::Pattern => LocationSource::Pattern {
+                location_pattern: config.output_dir.join(&config.file_pattern).display().to_string(),
+                next_trigger_id: AtomicU64::new(0),
+            },
+            NamingMode::Structured => LocationSource::Structured(NamingScheme::new(
+                &config.output_dir,
+                flight_session,
+                camera_id,
+            )?),
+        };
+
+        let mut extra_args = vec!["--inline".to_string(), "-t".to_string(), "0".to_string()];
+        // `--segment` tells libcamera-vid to close and reopen the output
+        // (substituting its own fragment counter into a `%05d` in the
+        // filename) every `segment_duration`, instead of writing one file
+        // for the whole recording.
+        if !config.segment_duration.is_zero() {
+            extra_args.push("--segment".to_string());
+            extra_args.push(config.segment_duration.as_millis().to_string());
+        }
+
+        // `libcamera-vid` has no 90-degree rotation flag; `--rotation` only
+        // accepts 0/180. Rather than reject the config outright, warn and
+        // fall back to unrotated, consistent with how other backends degrade
+        // unsupported settings instead of failing to start.
+        match config.orientation {
+            Orientation::None => {}
+            Orientation::Rotate180 => {
+                extra_args.push("--rotation".to_string());
+                extra_args.push("180".to_string());
+            }
+            Orientation::HorizontalFlip => extra_args.push("--hflip".to_string()),
+            Orientation::VerticalFlip => extra_args.push("--vflip".to_string()),
+            Orientation::Clockwise90 | Orientation::CounterClockwise90 => {
+                warn!(
+                    "orientation {:?} is not supported by libcamera-vid (only 180-degree rotation and \
+                     horizontal/vertical flips are); ignoring",
+                    config.orientation
+                );
+            }
+        }
+
+        Ok(Self {
+            binary: config.libcamera_vid_binary.clone(),
+            camera: config.source_device.clone(),
+            location_source,
+            segmented: !config.segment_duration.is_zero(),
+            extra_args,
+            armed: Arc::new(AtomicBool::new(false)),
+            child: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Spawns a supervisor thread that starts `libcamera-vid`, forwards its
+    /// stderr into our log, and restarts it (with the same output location)
+    /// if it exits while still armed. Returns the location it was started
+    /// at.
+    pub fn start(&self) -> String {
+        self.armed.store(true, Ordering::SeqCst);
+
+        let location = match &self.location_source {
+            LocationSource::Pattern { location_pattern, next_trigger_id } => {
+                let trigger_id = next_trigger_id.fetch_add(1, Ordering::Relaxed);
+                location_pattern.replace("{trigger}", &trigger_id.to_string())
+            }
+            LocationSource::Structured(naming) if self.segmented => {
+                naming.next_fragment_pattern("mp4").display().to_string()
+            }
+            LocationSource::Structured(naming) => naming.next_path("mp4").display().to_string(),
+        };
+
+        let binary = self.binary.clone();
+        let camera = self.camera.clone();
+        let extra_args = self.extra_args.clone();
+        let armed = Arc::clone(&self.armed);
+        let child_slot = Arc::clone(&self.child);
+        let thread_location = location.clone();
+
+        std::thread::spawn(move || {
+            let location = thread_location;
+            let mut backoff = RESTART_BACKOFF_INITIAL;
+
+            while armed.load(Ordering::SeqCst) {
+                debug!(
+                    "starting {} for camera {}, output = {location}",
+                    binary.display(),
+                    camera.display()
+                );
+
+                let started_at = Instant::now();
+
+                let mut command = Command::new(&binary);
+                command
+                    .arg("--camera")
+                    .arg(&camera)
+                    .arg("-o")
+                    .arg(&location)
+                    .args(&extra_args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped());
+
+                let mut child = match command.spawn() {
+                    Ok(child) => child,
+                    Err(error) => {
+                        error!("failed to spawn {}: {error}", binary.display());
+                        break;
+                    }
+                };
+
+                if let Some(stderr) = child.stderr.take() {
+                    std::thread::spawn(move || {
+                        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                            warn!("libcamera-vid: {line}");
+                        }
+                    });
+                }
+
+                *child_slot.lock().unwrap() = Some(child);
+
+                // Poll rather than blocking on `wait()` so the lock is only
+                // held briefly: `stop` needs to be able to take and kill the
+                // child from the other side of this same mutex without
+                // deadlocking against a blocking wait held here.
+                let exited = loop {
+                    std::thread::sleep(POLL_INTERVAL);
+
+                    let mut guard = child_slot.lock().unwrap();
+                    match guard.as_mut() {
+                        Some(child) => match child.try_wait() {
+                            Ok(Some(status)) => {
+                                guard.take();
+                                break Some(status);
+                            }
+                            Ok(None) => continue,
+                            Err(error) => {
+                                error!("failed to poll {}: {error}", binary.display());
+                                guard.take();
+                                break None;
+                            }
+                        },
+                        // `stop` already took and killed it.
+                        None => break None,
+                    }
+                };
+
+                if !armed.load(Ordering::SeqCst) {
+                    debug!("libcamera-vid exited after a requested stop");
+                    break;
+                }
+
+                if started_at.elapsed() >= RESTART_BACKOFF_RESET_AFTER {
+                    backoff = RESTART_BACKOFF_INITIAL;
+                }
+
+                warn!(
+                    "libcamera-vid exited unexpectedly ({exited:?}) mid-recording, restarting in \
+                     {backoff:?}"
+                );
+                std::thread::sleep(backoff);
+                backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+            }
+        });
+
+        location
+    }
+
+    /// Marks the recording as no longer armed and kills the running child,
+    /// if any (a crash/restart cycle may briefly leave none in flight).
+    /// Always returns `None`: this backend has no in-process visibility into
+    /// how many frames the child process wrote, unlike the others (see
+    /// [`crate::recorder::Recorder::stop`]).
+    pub fn stop(&self) -> Option<u64> {
+        self.armed.store(false, Ordering::SeqCst);
+
+        if let Some(mut child) = self.child.lock().unwrap().take() {
+            debug!("stopping {}", self.binary.display());
+
+            if let Err(error) = child.kill() {
+                error!("failed to kill {}: {error}", self.binary.display());
+            }
+            let _ = child.wait();
+        }
+
+        None
+    }
+}
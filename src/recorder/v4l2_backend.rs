@@ -0,0 +1,396 @@
+//! A pure-Rust V4L2 capture backend using the `v4l` crate directly, as an
+//! alternative to [`super::gstreamer_backend`] for boards where GStreamer's
+//! runtime footprint isn't worth it on a stripped-down flight image.
+//!
+//! Frames are read from the device's mmap'd buffer queue (no user-space
+//! copy through a pipe, unlike [`super::subprocess_backend`]) and this
+//! backend requires the device to natively produce the `MJPG` fourcc: an
+//! uncompressed sensor needing software/hardware encoding still needs
+//! [`super::gstreamer_backend`]. Each frame is a self-contained JPEG, so the
+//! output file is simply the frames concatenated - an "MJPEG elementary
+//! stream" that tools like `ffmpeg -f mjpeg` can decode directly - alongside
+//! a `.timestamps.csv` sidecar of each frame's kernel capture timestamp
+//! (continuously drift-corrected against `CLOCK_MONOTONIC` via
+//! [`super::sensor_clock`]), since that's not recoverable from the frame
+//! data itself once written.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use log::{error, warn};
+use v4l::buffer::Type;
+use v4l::control::{Control, Value};
+use v4l::io::mmap::Stream;
+use v4l::io::traits::CaptureStream;
+use v4l::video::Capture;
+use v4l::{Device, FourCC};
+
+use super::frame_writer::{self, FrameWriterHandle, RecordingState};
+use super::sensor_clock::DriftEstimator;
+use super::{CameraControls, RecorderConfig, RegionOfInterest};
+
+/// V4L2 UVC extension unit control IDs (`linux/uvcvideo.h`/`videodev2.h`).
+/// Not exposed as named constants by the `v4l` crate, so hand-rolled here.
+const V4L2_CID_EXPOSURE_AUTO: u32 = 0x009a_0901;
+const V4L2_CID_EXPOSURE_ABSOLUTE: u32 = 0x009a_0902;
+const V4L2_CID_AUTO_WHITE_BALANCE: u32 = 0x0098_090c;
+const V4L2_CID_WHITE_BALANCE_TEMPERATURE: u32 = 0x0098_091a;
+const V4L2_CID_GAIN: u32 = 0x0098_0913;
+const V4L2_CID_FOCUS_AUTO: u32 = 0x009a_090c;
+const V4L2_CID_FOCUS_ABSOLUTE: u32 = 0x009a_090a;
+
+/// `V4L2_EXPOSURE_MANUAL`/`V4L2_EXPOSURE_AUTO`, the values `V4L2_CID_EXPOSURE_AUTO`
+/// takes (it's a mode enum, not a boolean, despite the name).
+const V4L2_EXPOSURE_MANUAL: i64 = 1;
+
+/// Sets whichever of `controls`' fields are present, switching the
+/// corresponding auto mode off first since a manual value is ignored by most
+/// UVC devices while its auto mode is still on. Logs (rather than failing
+/// the caller) on a control this particular device doesn't support, since
+/// not every UVC camera implements every one of these.
+fn apply_controls(device: &mut Device, controls: &CameraControls) {
+    if let Some(exposure_micros) = controls.exposure_micros {
+        set_control(device, V4L2_CID_EXPOSURE_AUTO, Value::Integer(V4L2_EXPOSURE_MANUAL));
+        set_control(device, V4L2_CID_EXPOSURE_ABSOLUTE, Value::Integer(exposure_micros as i64 / 100));
+    }
+    if let Some(gain) = controls.gain {
+        set_control(device, V4L2_CID_GAIN, Value::Integer(gain as i64));
+    }
+    if let Some(white_balance_kelvin) = controls.white_balance_kelvin {
+        set_control(device, V4L2_CID_AUTO_WHITE_BALANCE, Value::Integer(0));
+        set_control(device, V4L2_CID_WHITE_BALANCE_TEMPERATURE, Value::Integer(white_balance_kelvin as i64));
+    }
+    if let Some(focus_position) = controls.focus_position {
+        set_control(device, V4L2_CID_FOCUS_AUTO, Value::Integer(0));
+        set_control(device, V4L2_CID_FOCUS_ABSOLUTE, Value::Integer(focus_position as i64));
+    }
+}
+
+fn set_control(device: &mut Device, id: u32, value: Value) {
+    if let Err(error) = device.set_control(Control { id, value }) {
+        warn!("failed to set v4l2 control {id:#x}: {error}");
+    }
+}
+
+/// Converts `roi` (normalized `0.0..=1.0` against `frame_width`/`frame_height`)
+/// into a pixel rectangle and sets it via the `VIDIOC_S_SELECTION` crop
+/// target. Clamps an out-of-bounds request (e.g. `x + width` past `1.0`)
+/// rather than rejecting it, since punching in near an edge is the common
+/// case, not an error.
+fn apply_roi(device: &mut Device, roi: (f32, f32, f32, f32), frame_width: u32, frame_height: u32) {
+    let (x, y, width, height) = roi;
+    let x = x.clamp(0.0, 1.0);
+    let y = y.clamp(0.0, 1.0);
+    let width = width.clamp(0.0, 1.0 - x);
+    let height = height.clamp(0.0, 1.0 - y);
+
+    let rect = v4l::Rect {
+        left: (x * frame_width as f32) as i32,
+        top: (y * frame_height as f32) as i32,
+        width: (width * frame_width as f32) as u32,
+        height: (height * frame_height as f32) as u32,
+    };
+    if let Err(error) = device.set_crop(rect) {
+        warn!("failed to set v4l2 ROI crop: {error}");
+    }
+}
+
+/// Drives capture directly against a V4L2 device via `v4l`, bypassing
+/// GStreamer entirely. See the module docs for the `MJPG`-only limitation.
+pub struct V4l2Recorder {
+    output_dir: PathBuf,
+    secondary_output_dir: Option<PathBuf>,
+    encryption_recipient: Option<String>,
+    file_pattern: String,
+    next_trigger_id: AtomicU64,
+    recording: Arc<Mutex<Option<RecordingState>>>,
+    /// Hands captured frames off to [`frame_writer`]'s dedicated writer
+    /// thread instead of writing them to disk from the capture thread.
+    writer: FrameWriterHandle,
+    /// Continuously refits the kernel capture timestamp against
+    /// `CLOCK_MONOTONIC` so a long recording's sidecar timestamps don't drift
+    /// away from GPIO trigger timestamps. Shared with the capture thread,
+    /// which is the only place samples are recorded and corrections applied.
+    drift_estimator: Arc<DriftEstimator>,
+    /// Whether a recording is currently armed; checked by the capture
+    /// thread so frames are read (and buffers recycled) continuously but
+    /// only written to disk while armed.
+    armed: Arc<AtomicBool>,
+    /// Set on every `start()`, cleared by the capture thread once it has
+    /// reported the first frame of the new recording via `on_first_frame`,
+    /// so later frames don't re-report it.
+    first_frame_pending: Arc<AtomicBool>,
+    /// A pending [`CameraControls`] change from [`Self::set_controls`],
+    /// applied and cleared by the capture thread (the sole owner of the
+    /// open `Device`) on its next loop iteration.
+    pending_controls: Arc<Mutex<Option<CameraControls>>>,
+    /// The full-frame crop rectangle (`x, y, width, height`, normalized
+    /// `0.0..=1.0`) [`Self::set_roi`] merges [`RegionOfInterest`]'s
+    /// independent fields into before handing off to the capture thread,
+    /// since the v4l2 selection API sets the whole rectangle at once.
+    current_roi: Mutex<(f32, f32, f32, f32)>,
+    /// A pending crop rectangle from [`Self::set_roi`], applied and cleared
+    /// by the capture thread on its next loop iteration. Same idiom as
+    /// `pending_controls`.
+    pending_roi: Arc<Mutex<Option<(f32, f32, f32, f32)>>>,
+}
+
+impl V4l2Recorder {
+    /// Opens `config.source_device` and negotiates `MJPG` capture (at
+    /// `config.capture_width`/`config.capture_height` if given), failing
+    /// fast if the device doesn't support it, then starts the background
+    /// capture thread. `on_fatal_error` is invoked if the capture stream
+    /// errors out, mirroring [`super::gstreamer_backend::GstreamerRecorder::new`].
+    /// `on_first_frame` is invoked once per `start()`, with the kernel
+    /// capture timestamp (nanoseconds, drift-corrected against
+    /// `CLOCK_MONOTONIC` - the domain [`crate::trigger`]'s GPIO edge
+    /// timestamps are in on Linux - see [`super::sensor_clock`]) of the first
+    /// frame written to the new recording, so callers can measure
+    /// trigger-to-frame latency. `on_dropped_frames` is invoked whenever
+    /// [`frame_writer`]'s writer thread falls more than
+    /// `config.write_queue_depth` frames behind and a captured frame has to
+    /// be dropped instead of blocking the capture thread. `on_frame` is
+    /// invoked once per frame actually written to disk, for
+    /// [`crate::metrics::CameraMetrics::record_frame`]'s jitter histogram.
+    /// `on_backpressure_action` is invoked alongside `on_dropped_frames`
+    /// with which [`super::BackpressureAction`] `config.backpressure_policy`
+    /// took.
+    pub fn new(
+        config: &RecorderConfig,
+        on_fatal_error: impl Fn() + Send + Sync + 'static,
+        on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+        on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+        on_frame: impl Fn() + Send + Sync + 'static,
+        on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+        realtime_clock: Arc<crate::clock::RealtimeClock>,
+    ) -> Result<Self, String> {
+        std::fs::create_dir_all(&config.output_dir).map_err(|error| {
+            format!(
+                "failed to create recording output directory {}: {error}",
+                config.output_dir.display()
+            )
+        })?;
+        if let Some(secondary_output_dir) = &config.secondary_output_dir {
+            if let Err(error) = std::fs::create_dir_all(secondary_output_dir) {
+                warn!(
+                    "failed to create redundant recording output directory {}: {error}; continuing without it",
+                    secondary_output_dir.display()
+                );
+            }
+        }
+
+        let mut device = Device::with_path(&config.source_device)
+            .map_err(|error| format!("failed to open v4l2 device {}: {error}", config.source_device.display()))?;
+
+        let mut format = device.format().map_err(|error| {
+            format!("failed to query v4l2 capture format on {}: {error}", config.source_device.display())
+        })?;
+        format.fourcc = FourCC::new(b"MJPG");
+        if let (Some(width), Some(height)) = (config.capture_width, config.capture_height) {
+            format.width = width;
+            format.height = height;
+        }
+
+        let format = device.set_format(&format).map_err(|error| {
+            format!("failed to negotiate v4l2 capture format on {}: {error}", config.source_device.display())
+        })?;
+        if format.fourcc != FourCC::new(b"MJPG") {
+            return Err(format!(
+                "v4l2-direct backend requires MJPG capture, but {} only accepted {}; use the gstreamer \
+                 backend instead for sensors that need software/hardware encoding",
+                config.source_device.display(),
+                format.fourcc,
+            ));
+        }
+
+        apply_controls(&mut device, &config.initial_controls);
+
+        let recording = Arc::new(Mutex::new(None));
+        let armed = Arc::new(AtomicBool::new(false));
+        let first_frame_pending = Arc::new(AtomicBool::new(false));
+        let pending_controls = Arc::new(Mutex::new(None));
+        let pending_roi = Arc::new(Mutex::new(None));
+        let writer = frame_writer::spawn(
+            config.write_queue_depth,
+            config.backpressure_policy,
+            Arc::clone(&recording),
+            on_frame,
+            realtime_clock,
+        );
+        let drift_estimator = Arc::new(DriftEstimator::new());
+
+        spawn_capture_loop(
+            device,
+            Arc::clone(&armed),
+            Arc::clone(&first_frame_pending),
+            Arc::clone(&pending_controls),
+            Arc::clone(&pending_roi),
+            format.width,
+            format.height,
+            writer.clone(),
+            Arc::clone(&drift_estimator),
+            on_fatal_error,
+            on_dropped_frames,
+            on_first_frame,
+            on_backpressure_action,
+        );
+
+        Ok(Self {
+            output_dir: config.output_dir.clone(),
+            secondary_output_dir: config.secondary_output_dir.clone(),
+            encryption_recipient: config.encryption_recipient.clone(),
+            file_pattern: config.file_pattern.clone(),
+            next_trigger_id: AtomicU64::new(0),
+            recording,
+            writer,
+            drift_estimator,
+            armed,
+            first_frame_pending,
+            pending_controls,
+            current_roi: Mutex::new((0.0, 0.0, 1.0, 1.0)),
+            pending_roi,
+        })
+    }
+
+    /// Opens fresh output files (the recording itself, and its timestamp
+    /// sidecar) and arms the capture thread to start writing frames into
+    /// them. Returns the location of the recording file.
+    pub fn start(&self) -> String {
+        let trigger_id = self.next_trigger_id.fetch_add(1, Ordering::Relaxed);
+        let location = self.output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string()));
+        let secondary_location = self
+            .secondary_output_dir
+            .as_ref()
+            .map(|secondary_output_dir| secondary_output_dir.join(self.file_pattern.replace("{trigger}", &trigger_id.to_string())));
+
+        match RecordingState::open(&location, secondary_location.as_deref(), self.encryption_recipient.as_deref()) {
+            Ok(state) => {
+                *self.recording.lock().unwrap() = Some(state);
+                self.first_frame_pending.store(true, Ordering::Relaxed);
+                self.armed.store(true, Ordering::Relaxed);
+            }
+            Err(error) => error!("failed to start v4l2-direct recording at {}: {error}", location.display()),
+        }
+
+        location.display().to_string()
+    }
+
+    /// Disarms the capture thread and flushes/closes the recording's output
+    /// files. Returns how many frames were written to it, for
+    /// [`crate::manifest`].
+    pub fn stop(&self) -> Option<u64> {
+        self.armed.store(false, Ordering::Relaxed);
+
+        let Some(state) = self.recording.lock().unwrap().take() else {
+            warn!("stop requested but no v4l2-direct recording is currently active");
+            return None;
+        };
+
+        Some(state.finish())
+    }
+
+    /// Queues `controls` for the capture thread to apply on its next loop
+    /// iteration. Fire-and-forget, same as `armed`/`recording`: failures are
+    /// logged by the capture thread rather than reported back here, since
+    /// there's no synchronous round trip to the device from this side.
+    pub fn set_controls(&self, controls: CameraControls) {
+        *self.pending_controls.lock().unwrap() = Some(controls);
+    }
+
+    /// Merges `roi`'s independent fields into `current_roi` and queues the
+    /// resulting rectangle for the capture thread to apply on its next loop
+    /// iteration. Fire-and-forget, same as [`Self::set_controls`].
+    pub fn set_roi(&self, roi: RegionOfInterest) {
+        let mut current = self.current_roi.lock().unwrap();
+        if let Some(x) = roi.x {
+            current.0 = x;
+        }
+        if let Some(y) = roi.y {
+            current.1 = y;
+        }
+        if let Some(width) = roi.width {
+            current.2 = width;
+        }
+        if let Some(height) = roi.height {
+            current.3 = height;
+        }
+        *self.pending_roi.lock().unwrap() = Some(*current);
+    }
+}
+
+/// Spawns the sole thread allowed to read `device`'s mmap'd buffer queue.
+/// Runs for the lifetime of the process: frames are pulled continuously
+/// (recycling buffers back to the kernel as `Stream::next` is called again)
+/// but only enqueued to `writer` while `armed`.
+fn spawn_capture_loop(
+    mut device: Device,
+    armed: Arc<AtomicBool>,
+    first_frame_pending: Arc<AtomicBool>,
+    pending_controls: Arc<Mutex<Option<CameraControls>>>,
+    pending_roi: Arc<Mutex<Option<(f32, f32, f32, f32)>>>,
+    frame_width: u32,
+    frame_height: u32,
+    writer: FrameWriterHandle,
+    drift_estimator: Arc<DriftEstimator>,
+    on_fatal_error: impl Fn() + Send + Sync + 'static,
+    on_dropped_frames: impl Fn(u64) + Send + Sync + 'static,
+    on_first_frame: impl Fn(u64) + Send + Sync + 'static,
+    on_backpressure_action: impl Fn(super::BackpressureAction) + Send + Sync + 'static,
+) {
+    // `Stream::with_buffers` borrows `device` mutably for the stream's whole
+    // lifetime, so controls (a handful of independent ioctls on the same fd)
+    // are applied through this separate clone rather than through `device`
+    // itself, which the loop below never touches again.
+    let mut control_device = device.clone();
+
+    std::thread::spawn(move || {
+        let mut stream = match Stream::with_buffers(&mut device, Type::VideoCapture, 4) {
+            Ok(stream) => stream,
+            Err(error) => {
+                error!("failed to allocate v4l2 capture buffers: {error}");
+                on_fatal_error();
+                return;
+            }
+        };
+
+        loop {
+            if let Some(controls) = pending_controls.lock().unwrap().take() {
+                apply_controls(&mut control_device, &controls);
+            }
+            if let Some(roi) = pending_roi.lock().unwrap().take() {
+                apply_roi(&mut control_device, roi, frame_width, frame_height);
+            }
+
+            let (buffer, metadata) = match stream.next() {
+                Ok(frame) => frame,
+                Err(error) => {
+                    error!("v4l2 capture stream error: {error}");
+                    on_fatal_error();
+                    return;
+                }
+            };
+
+            if !armed.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            let kernel_timestamp_us = metadata.timestamp.sec as i64 * 1_000_000 + metadata.timestamp.usec as i64;
+            let kernel_timestamp_ns = kernel_timestamp_us.max(0) as u64 * 1_000;
+            drift_estimator.record(kernel_timestamp_ns, crate::clock::monotonic_now_ns());
+            let corrected_timestamp_ns = drift_estimator.correct(kernel_timestamp_ns);
+
+            match writer.enqueue(buffer.to_vec(), corrected_timestamp_ns as i64, None) {
+                None => {
+                    if first_frame_pending.swap(false, Ordering::Relaxed) {
+                        on_first_frame(corrected_timestamp_ns);
+                    }
+                }
+                Some(action) => {
+                    on_dropped_frames(1);
+                    on_backpressure_action(action);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,26 @@
+//! An opt-in low-bitrate WebRTC preview stream, tapped off a recording
+//! backend's always-on capture pipeline via [`preview_branch`]'s `tee`, so a
+//! GCS operator's browser can watch a sub-second-latency preview for gimbal
+//! alignment before takeoff.
+//!
+//! Unlike [`super::rtsp_preview`], `whipsink` pushes to a ground-side WHIP
+//! ingest URL and does its own offer/answer signaling over plain HTTP, so
+//! there's no server to stand up here - the branch below is just spliced
+//! straight into the capture pipeline, same as any other sink.
+
+/// Pipeline fragment to splice into a capture pipeline description at a
+/// `tee name=preview_tee`: a low-bitrate VP8 encode pushed to
+/// `whip_endpoint` via `whipsink`.
+///
+/// `osd_overlay_element`, if given, splices
+/// [`super::osd_overlay::overlay_fragment`] in right before the encoder, so
+/// this stream (and only this stream) gets a burned-in telemetry overlay.
+pub fn preview_branch(whip_endpoint: &str, bitrate_kbps: u32, osd_overlay_element: Option<&str>) -> String {
+    let overlay_fragment = osd_overlay_element.map(super::osd_overlay::overlay_fragment).unwrap_or_default();
+    format!(
+        "preview_tee. ! queue leaky=downstream max-size-buffers=2 ! videoscale ! \
+         video/x-raw,width=640,height=360 ! {overlay_fragment}vp8enc target-bitrate={} deadline=1 ! \
+         rtpvp8pay ! whipsink name=webrtc_whip whip-endpoint={whip_endpoint}",
+        bitrate_kbps * 1000,
+    )
+}
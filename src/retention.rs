@@ -0,0 +1,143 @@
+//! Reclaims disk space under a camera's `output_dir` by deleting whole
+//! completed flight-session directories, oldest first, once the total size
+//! exceeds a configured quota or free space on that filesystem drops below a
+//! threshold. Never deletes the current run's own session directory, so a
+//! long-deployed drone's SSD never fills up without ever losing an
+//! in-progress recording.
+//!
+//! Distinct from [`crate::disk_space`], which stops the *active* recording
+//! when space runs critically low; this instead makes room ahead of time by
+//! clearing out old sessions, and only ever touches directories that are
+//! already done.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::{error, info, warn};
+
+/// How often usage is checked. Matches [`crate::disk_space`]'s cadence,
+/// since both watch the same filesystem for the same reason.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Whether/how to enforce a retention quota on a camera's `output_dir`. Off
+/// by default: an operator who wants unattended cleanup has to opt in and
+/// pick a threshold, since deleting old footage is not something to do by
+/// accident.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetentionConfig {
+    pub enabled: bool,
+    /// Delete the oldest completed session once everything under
+    /// `output_dir` exceeds this many bytes. Only used if `enabled`.
+    pub max_bytes: Option<u64>,
+    /// Delete the oldest completed session once free space on `output_dir`'s
+    /// filesystem drops below this many bytes. Only used if `enabled`.
+    pub min_free_bytes: Option<u64>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self { enabled: false, max_bytes: None, min_free_bytes: None }
+    }
+}
+
+/// Spawns a background task enforcing `config` against `output_dir` for the
+/// process's lifetime. `active_session` (the current run's flight-session
+/// directory name under `output_dir`) is never a deletion candidate.
+pub fn spawn_monitor(output_dir: PathBuf, active_session: String, config: RetentionConfig) {
+    if !config.enabled {
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+            enforce(&output_dir, &active_session, &config);
+        }
+    });
+}
+
+/// Deletes the oldest completed session under `output_dir` repeatedly, until
+/// neither threshold in `config` is exceeded or there's nothing left to
+/// delete.
+fn enforce(output_dir: &Path, active_session: &str, config: &RetentionConfig) {
+    loop {
+        let over_quota = config.max_bytes.is_some_and(|max_bytes| match directory_size(output_dir) {
+            Ok(used) => used > max_bytes,
+            Err(error) => {
+                warn!("retention: failed to measure {}: {error}", output_dir.display());
+                false
+            }
+        });
+
+        let below_free_space = config.min_free_bytes.is_some_and(|min_free_bytes| {
+            match fs4::available_space(output_dir) {
+                Ok(available) => available < min_free_bytes,
+                Err(error) => {
+                    warn!("retention: failed to check free space on {}: {error}", output_dir.display());
+                    false
+                }
+            }
+        });
+
+        if !over_quota && !below_free_space {
+            return;
+        }
+
+        let Some(session) = oldest_session(output_dir, active_session) else {
+            warn!(
+                "retention: {} is over its retention threshold, but no completed session is left to delete",
+                output_dir.display()
+            );
+            return;
+        };
+
+        let reclaimed = directory_size(&session).unwrap_or(0);
+        match std::fs::remove_dir_all(&session) {
+            Ok(()) => info!(
+                "retention: deleted {} ({reclaimed} bytes) to stay within the retention quota for {}",
+                session.display(),
+                output_dir.display()
+            ),
+            Err(error) => {
+                error!("retention: failed to delete {}: {error}", session.display());
+                return;
+            }
+        }
+    }
+}
+
+/// The oldest (by modification time) immediate subdirectory of `output_dir`
+/// other than `active_session` - i.e. the next completed session eligible
+/// for cleanup. `None` if `output_dir` can't be read or holds nothing else.
+fn oldest_session(output_dir: &Path, active_session: &str) -> Option<PathBuf> {
+    std::fs::read_dir(output_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() != std::ffi::OsStr::new(active_session))
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, entry.path()))
+        })
+        .min_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Total size in bytes of every file under `path`, recursing into
+/// subdirectories. `pub(crate)` so [`crate::main::bench`] can reuse it to
+/// measure write throughput instead of duplicating the walk.
+pub(crate) fn directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,176 @@
+//! An optional ROS 2 bridge, so the perception stack running on the same
+//! companion computer can consume trigger timestamps and captured-image
+//! paths as topics, and issue start/stop as services, instead of going
+//! through [`crate::control_api`]'s HTTP routes or scraping the trigger log.
+//!
+//! Built on `r2r`, same reasoning as [`crate::mqtt`]/[`crate::dbus_api`]/
+//! [`crate::grpc_api`] pulling in a real crate for a protocol with its own
+//! framing/discovery rather than hand-rolling it.
+
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use std::time::Duration;
+
+use log::info;
+use tokio::sync::mpsc;
+
+use crate::control_api::ControlCommand;
+
+/// Parameters for the ROS 2 bridge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RosConfig {
+    /// Whether to start the bridge node at all. Off by default: not every
+    /// deployment runs ROS 2.
+    pub enabled: bool,
+    /// The node's name within its ROS 2 graph.
+    pub node_name: String,
+}
+
+impl Default for RosConfig {
+    fn default() -> Self {
+        Self { enabled: false, node_name: "px4_camera_trigger".to_string() }
+    }
+}
+
+/// An event forwarded to [`spawn`]'s bridge thread, one per publishable
+/// topic.
+#[derive(Debug, Clone)]
+pub enum RosEvent {
+    /// Published to `~/trigger` on every trigger edge or manual
+    /// start/stop/snapshot command.
+    Trigger { gpio_timestamp_ns: u64 },
+    /// Published to `~/image_path` for every still captured.
+    ImageCaptured { camera: String, path: PathBuf },
+}
+
+/// A handle for sending [`RosEvent`]s to the bridge thread. Cheap to clone,
+/// same as [`crate::mqtt::MqttPublisher`].
+#[derive(Clone)]
+pub struct RosPublisher {
+    events: mpsc::UnboundedSender<RosEvent>,
+}
+
+impl RosPublisher {
+    /// A publisher that drops every event, for when `RosConfig::enabled` is
+    /// off - callers don't need to branch on whether the bridge is running.
+    pub fn disabled() -> Self {
+        let (events, _rx) = mpsc::unbounded_channel();
+        Self { events }
+    }
+
+    /// Fire-and-forget, same as [`crate::mqtt::MqttPublisher::publish`].
+    pub fn publish(&self, event: RosEvent) {
+        let _ = self.events.send(event);
+    }
+}
+
+/// Creates `config.node_name`, publishers on `~/trigger` and
+/// `~/image_path`, and `~/start_recording`/`~/stop_recording`
+/// (`std_srvs/Trigger`) services forwarding to `commands`.
+///
+/// An `r2r::Node` isn't `Send`, so it's owned entirely by a dedicated OS
+/// thread that spins it and drains both the service request streams and the
+/// returned [`RosPublisher`]'s events channel - same reasoning as
+/// [`crate::recorder::rtsp_preview`]'s dedicated GLib main-loop thread.
+pub fn spawn(config: &RosConfig, commands: mpsc::UnboundedSender<ControlCommand>) -> Result<RosPublisher, String> {
+    let (events_tx, events_rx) = mpsc::unbounded_channel::<RosEvent>();
+    let node_name = config.node_name.clone();
+    let (ready_tx, ready_rx) = std_mpsc::channel::<Result<(), String>>();
+
+    {
+        let node_name = node_name.clone();
+        thread::spawn(move || run_node(&node_name, commands, events_rx, ready_tx));
+    }
+
+    ready_rx
+        .recv()
+        .map_err(|_| "ros2 bridge thread exited before it finished starting".to_string())??;
+
+    info!("ros2 bridge node {node_name} started");
+
+    Ok(RosPublisher { events: events_tx })
+}
+
+/// Runs on its own OS thread for as long as the process does. Reports
+/// whether node/publisher/service setup succeeded over `ready_tx` before
+/// entering the spin loop, so [`spawn`] can fail fast on a setup error
+/// instead of appearing to succeed.
+fn run_node(
+    node_name: &str,
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    mut events_rx: mpsc::UnboundedReceiver<RosEvent>,
+    ready_tx: std_mpsc::Sender<Result<(), String>>,
+) {
+    let ctx = match r2r::Context::create() {
+        Ok(ctx) => ctx,
+        Err(error) => {
+            let _ = ready_tx.send(Err(format!("failed to create ros2 context: {error}")));
+            return;
+        }
+    };
+    let mut node = match r2r::Node::create(ctx, node_name, "") {
+        Ok(node) => node,
+        Err(error) => {
+            let _ = ready_tx.send(Err(format!("failed to create ros2 node {node_name:?}: {error}")));
+            return;
+        }
+    };
+
+    let trigger_pub =
+        match node.create_publisher::<r2r::std_msgs::msg::UInt64>("~/trigger", r2r::QosProfile::default()) {
+            Ok(publisher) => publisher,
+            Err(error) => {
+                let _ = ready_tx.send(Err(format!("failed to create ~/trigger publisher: {error}")));
+                return;
+            }
+        };
+    let image_pub =
+        match node.create_publisher::<r2r::std_msgs::msg::String>("~/image_path", r2r::QosProfile::default()) {
+            Ok(publisher) => publisher,
+            Err(error) => {
+                let _ = ready_tx.send(Err(format!("failed to create ~/image_path publisher: {error}")));
+                return;
+            }
+        };
+
+    let mut start_requests = match node.create_service::<r2r::std_srvs::srv::Trigger::Service>("~/start_recording") {
+        Ok(service) => service,
+        Err(error) => {
+            let _ = ready_tx.send(Err(format!("failed to create ~/start_recording service: {error}")));
+            return;
+        }
+    };
+    let mut stop_requests = match node.create_service::<r2r::std_srvs::srv::Trigger::Service>("~/stop_recording") {
+        Ok(service) => service,
+        Err(error) => {
+            let _ = ready_tx.send(Err(format!("failed to create ~/stop_recording service: {error}")));
+            return;
+        }
+    };
+
+    let _ = ready_tx.send(Ok(()));
+
+    loop {
+        node.spin_once(Duration::from_millis(50));
+
+        while let Ok(request) = start_requests.try_recv() {
+            let success = commands.send(ControlCommand::Start).is_ok();
+            let _ = request.respond(r2r::std_srvs::srv::Trigger::Response { success, message: String::new() });
+        }
+        while let Ok(request) = stop_requests.try_recv() {
+            let success = commands.send(ControlCommand::Stop).is_ok();
+            let _ = request.respond(r2r::std_srvs::srv::Trigger::Response { success, message: String::new() });
+        }
+        while let Ok(event) = events_rx.try_recv() {
+            match event {
+                RosEvent::Trigger { gpio_timestamp_ns } => {
+                    let _ = trigger_pub.publish(&r2r::std_msgs::msg::UInt64 { data: gpio_timestamp_ns });
+                }
+                RosEvent::ImageCaptured { path, .. } => {
+                    let _ = image_pub.publish(&r2r::std_msgs::msg::String { data: path.display().to_string() });
+                }
+            }
+        }
+    }
+}
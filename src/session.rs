@@ -0,0 +1,1121 @@
+//! Bundles the trigger state machine and every configured camera's
+//! transition worker, so [`crate::main::run`]'s event loop only needs to
+//! decide *when* something happened - a GPIO/simulated edge
+//! ([`Session::handle_edge`]), or a manually-issued command from the
+//! control API or a signal handler ([`Session::dispatch`]) - and hand it
+//! off. The fan-out to every camera, trigger-log/metrics bookkeeping and
+//! MAVLink feedback all live here instead, so they're reusable outside this
+//! binary.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use log::{debug, error, info, warn};
+use tokio::sync::mpsc;
+
+use crate::buzzer::{BuzzerEvent, BuzzerHandle};
+use crate::capture_feedback::CaptureFeedback;
+use crate::config::Config;
+use crate::control_api::ControlCommand;
+use crate::dbus_api::DbusPublisher;
+use crate::disk_space;
+use crate::dronecan::{DroneCanEvent, DroneCanPublisher};
+use crate::durability;
+use crate::geotag;
+use crate::intervalometer::{self, IntervalometerConfig};
+use crate::manifest::Manifest;
+use crate::mavlink::MavlinkFeedback;
+use crate::metrics::Metrics;
+use crate::mqtt::{MqttEvent, MqttPublisher};
+use crate::recorder::Recorder;
+use crate::ros2_bridge::{RosEvent, RosPublisher};
+use crate::shutdown_inhibitor::ShutdownInhibitor;
+use crate::state_journal::StateJournal;
+use crate::subtitle_log;
+use crate::trigger::{PwmThresholds, Transition, TriggerEvent, TriggerStateMachine};
+use crate::trigger_log::TriggerLog;
+use crate::trigger_source::Edge;
+use crate::watchdog::Watchdog;
+
+/// A camera's transition worker, driven by [`Session::handle_edge`]/
+/// [`Session::dispatch`].
+pub struct Camera {
+    name: String,
+    transition_tx: mpsc::UnboundedSender<TriggerEvent>,
+    worker: tokio::task::JoinHandle<()>,
+    /// Kept alongside the transition worker's own clone so
+    /// [`Session::dispatch`] can reach the backend directly for commands
+    /// (e.g. [`ControlCommand::SetCameraControls`]) that don't go through
+    /// the trigger state machine. `None` for a camera that's degraded from
+    /// an initialization failure; see
+    /// [`crate::recorder::RecorderConfig::init_degraded_on_failure`].
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// The trigger state machine plus every camera it drives, for one run of
+/// the service.
+pub struct Session {
+    trigger: TriggerStateMachine,
+    cameras: Vec<Camera>,
+    trigger_sequence: Arc<AtomicU64>,
+    metrics: Arc<Metrics>,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+    trigger_log: Arc<TriggerLog>,
+    /// See [`crate::mqtt::MqttPublisher::disabled`]; always valid, so
+    /// [`Session::fan_out`] never needs to branch on whether MQTT is
+    /// configured.
+    mqtt: MqttPublisher,
+    /// See [`crate::dbus_api::DbusPublisher::disabled`]; always valid, same
+    /// reasoning as `mqtt` above.
+    dbus: DbusPublisher,
+    /// See [`crate::ros2_bridge::RosPublisher::disabled`]; always valid,
+    /// same reasoning as `mqtt` above.
+    ros: RosPublisher,
+    /// See [`crate::dronecan::DroneCanPublisher::disabled`]; always valid,
+    /// same reasoning as `mqtt` above.
+    dronecan: DroneCanPublisher,
+    /// See [`crate::buzzer::BuzzerHandle::disabled`]; always valid, same
+    /// reasoning as `mqtt` above.
+    buzzer: BuzzerHandle,
+    /// The primary camera's output directory, used only to locate
+    /// `<output_dir>/<flight_session>/summary.txt` at [`Session::shutdown`];
+    /// see [`crate::summary`].
+    output_dir: PathBuf,
+    flight_session: String,
+    /// See [`crate::mavlink::MavlinkConfig::require_armed`]. Checked in
+    /// [`Session::handle_edge`] before fanning out a `Start` transition.
+    require_armed: bool,
+    /// See [`crate::mavlink::MavlinkConfig::min_altitude_gate_m`]. Checked in
+    /// [`Session::handle_edge`] alongside `block_during_rtl` before fanning
+    /// out a `Start`/`CaptureStill` transition.
+    min_altitude_gate_m: Option<f32>,
+    /// See [`crate::mavlink::MavlinkConfig::block_triggers_during_rtl`].
+    block_during_rtl: bool,
+    intervalometer: IntervalometerConfig,
+    /// Set for the duration of an armed recording when `intervalometer` is
+    /// enabled; stopped and cleared on the matching `Stop`. See
+    /// [`crate::intervalometer::spawn`].
+    intervalometer_stop: Option<Arc<AtomicBool>>,
+    /// `PX4's CAMERA_TRIGGER.seq - our own trigger sequence`, learned from
+    /// the first edge [`Session::check_missed_triggers`] sees a PX4 sequence
+    /// for. `None` until then, or if `mavlink` isn't connected.
+    missed_trigger_offset: Option<i64>,
+    /// `None` if [`crate::offload::OffloadConfig::enabled`] is off. See
+    /// [`crate::offload`].
+    offload: Option<crate::offload::OffloadHandle>,
+    /// Held for as long as any recording is active; see
+    /// [`crate::shutdown_inhibitor`]. Always valid, same reasoning as `mqtt`
+    /// above.
+    shutdown_inhibitor: ShutdownInhibitor,
+}
+
+impl Session {
+    /// Builds a recorder, transition worker and disk-space monitor for every
+    /// camera in `config`, registering each with `metrics`. Fails fast (no
+    /// camera is left half-initialized) if any camera's recording pipeline
+    /// can't be built.
+    pub fn new(
+        config: &Config,
+        mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+        trigger_log: Arc<TriggerLog>,
+        manifest: Arc<Manifest>,
+        state_journal: Arc<StateJournal>,
+        initial_trigger_sequence: u64,
+        metrics: Arc<Metrics>,
+        capture_feedback: Arc<Option<CaptureFeedback>>,
+        watchdog: Watchdog,
+        mqtt: MqttPublisher,
+        dbus: DbusPublisher,
+        ros: RosPublisher,
+        dronecan: DroneCanPublisher,
+        buzzer: BuzzerHandle,
+        shutdown_inhibitor: ShutdownInhibitor,
+        realtime_clock: Arc<crate::clock::RealtimeClock>,
+    ) -> Result<Self, String> {
+        let pwm = config.pwm_mode.then_some(PwmThresholds {
+            record_above: config.pwm_record_above,
+            stop_below: config.pwm_stop_below,
+        });
+        let trigger =
+            TriggerStateMachine::new(config.min_pulse_width, config.short_pulse_max, config.invert_polarity, pwm);
+
+        // Shared by every camera's disk-space monitor and `handle_edge`, so
+        // a synthetic disk-space-triggered stop and a real trigger-driven
+        // one never collide on the same trigger sequence number in the log.
+        // Seeded from `initial_trigger_sequence` (recovered from the
+        // previous run's [`StateJournal`], if any) rather than always
+        // restarting at `0`, so a mid-flight restart doesn't reuse sequence
+        // numbers already written to the trigger log/manifest.
+        let trigger_sequence = Arc::new(AtomicU64::new(initial_trigger_sequence));
+
+        let mut cameras = Vec::new();
+        for mut camera in config.cameras() {
+            if let Some(serial) = &camera.recorder.usb_serial {
+                camera.recorder.source_device = crate::usb_discovery::find_device_by_usb_serial(serial)
+                    .map_err(|error| format!("camera {}: {error}", camera.name))?;
+            } else if let Some(port_path) = &camera.recorder.usb_port_path {
+                camera.recorder.source_device = crate::usb_discovery::find_device_by_usb_port(port_path)
+                    .map_err(|error| format!("camera {}: {error}", camera.name))?;
+            }
+
+            // `Some` once either the self-test or `Recorder::new` itself
+            // fails and the matching `*_degraded_on_failure` flag is set, so
+            // this camera still gets a `Camera` entry (and so still
+            // subscribes to triggers and logs them) with no real recording
+            // backend, instead of `Session::new` failing the whole session
+            // or - the self-test's older behavior - silently dropping the
+            // camera and its trigger-log entries entirely.
+            let mut degraded_reason = None;
+            let mut retry_on_background = false;
+
+            if let Err(error) = crate::camera_self_test::run(&camera.recorder, &camera.name) {
+                if camera.recorder.self_test_degraded_on_failure {
+                    error!("camera {}: self-test failed, entering degraded mode: {error}", camera.name);
+                    degraded_reason = Some(format!("self-test failed: {error}"));
+                } else {
+                    return Err(format!("camera {}: self-test failed: {error}", camera.name));
+                }
+            }
+
+            let camera_metrics = metrics.register_camera(camera.name.clone(), camera.recorder.output_dir.clone());
+
+            let watchdog = watchdog.clone();
+            let retry_watchdog = watchdog.clone();
+            let name = camera.name.clone();
+            let dropped_frames_metrics = Arc::clone(&camera_metrics);
+            let backpressure_action_metrics = Arc::clone(&camera_metrics);
+            let fatal_error_metrics = Arc::clone(&camera_metrics);
+            let fatal_error_mqtt = mqtt.clone();
+            let fatal_error_buzzer = buzzer.clone();
+            let fatal_error_name = camera.name.clone();
+            // Set by the transition worker's `Start` arm, and consumed by
+            // `on_first_frame` once the backend reports the new recording's
+            // first frame, so the two can meet up without the backend
+            // needing to know anything about triggers.
+            let pending_start: Arc<Mutex<Option<(u64, u64)>>> = Arc::new(Mutex::new(None));
+            let first_frame_pending_start = Arc::clone(&pending_start);
+            let first_frame_name = camera.name.clone();
+            let first_frame_capture_feedback = Arc::clone(&capture_feedback);
+            let first_frame_metrics = Arc::clone(&camera_metrics);
+            let frame_interval_metrics = Arc::clone(&camera_metrics);
+            let degraded_encoding_manifest = Arc::clone(&manifest);
+            let degraded_encoding_name = camera.name.clone();
+            let recorder = if degraded_reason.is_some() {
+                None
+            } else {
+                match Recorder::new(
+                    &camera.recorder,
+                    &camera.name,
+                    &config.flight_session,
+                    move || {
+                        watchdog.mark_unhealthy();
+                        fatal_error_metrics.record_error();
+                        fatal_error_mqtt.publish(MqttEvent::Error(format!(
+                            "camera {fatal_error_name}: recording pipeline failed"
+                        )));
+                        fatal_error_buzzer.signal(BuzzerEvent::Error);
+                    },
+                    move |count| {
+                        dropped_frames_metrics.record_dropped_frames(count);
+                    },
+                    move |frame_timestamp_ns| {
+                        if let Some(capture_feedback) = first_frame_capture_feedback.as_ref() {
+                            capture_feedback.pulse();
+                        }
+
+                        if let Some((sequence, gpio_timestamp_ns)) = first_frame_pending_start.lock().unwrap().take() {
+                            let latency_ms =
+                                (frame_timestamp_ns as i64 - gpio_timestamp_ns as i64) as f64 / 1_000_000.0;
+                            info!(
+                                "camera {first_frame_name}: trigger {sequence} to first frame latency: \
+                                 {latency_ms:.1} ms"
+                            );
+                            first_frame_metrics.record_first_frame_latency(gpio_timestamp_ns, frame_timestamp_ns);
+                        }
+                    },
+                    move || {
+                        frame_interval_metrics.record_frame();
+                    },
+                    move |action| {
+                        backpressure_action_metrics.record_backpressure_action(action);
+                    },
+                    move |reason| {
+                        degraded_encoding_manifest.record_degraded_encoding(&degraded_encoding_name, reason);
+                    },
+                    Arc::clone(&mavlink_feedback),
+                    Arc::clone(&realtime_clock),
+                ) {
+                    Ok(recorder) => Some(Arc::new(recorder)),
+                    Err(error) if camera.recorder.init_degraded_on_failure => {
+                        error!(
+                            "camera {name}: failed to initialize recording pipeline, entering degraded mode: {error}"
+                        );
+                        degraded_reason = Some(format!("failed to initialize recording pipeline: {error}"));
+                        retry_on_background = true;
+                        None
+                    }
+                    Err(error) => {
+                        return Err(format!("failed to initialize recording pipeline for camera {name}: {error}"));
+                    }
+                }
+            };
+
+            if let Some(reason) = &degraded_reason {
+                warn!(
+                    "camera {name}: running degraded ({reason}); this camera's trigger events will still be \
+                     logged and timestamped, but nothing will be recorded"
+                );
+            }
+
+            if retry_on_background {
+                spawn_init_retry(
+                    camera.recorder.clone(),
+                    name.clone(),
+                    config.flight_session.clone(),
+                    Arc::clone(&mavlink_feedback),
+                    retry_watchdog,
+                    Arc::clone(&realtime_clock),
+                );
+            }
+
+            let (transition_tx, mut transition_rx) = mpsc::unbounded_channel::<TriggerEvent>();
+            let worker = {
+                let recorder = recorder.clone();
+                let name = camera.name.clone();
+                let mavlink_feedback = Arc::clone(&mavlink_feedback);
+                let trigger_log = Arc::clone(&trigger_log);
+                let manifest = Arc::clone(&manifest);
+                let state_journal = Arc::clone(&state_journal);
+                let camera_metrics = Arc::clone(&camera_metrics);
+                let pending_start = Arc::clone(&pending_start);
+                let mqtt = mqtt.clone();
+                let dbus = dbus.clone();
+                let ros = ros.clone();
+                let buzzer = buzzer.clone();
+                let transition_tx = transition_tx.clone();
+                let trigger_sequence = Arc::clone(&trigger_sequence);
+                let max_recording_duration = camera.recorder.max_recording_duration;
+                let subtitle_config = config.subtitle.clone();
+                let durability_config = config.durability;
+                tokio::task::spawn_blocking(move || {
+                    // Remembered across iterations so the `stop` row can
+                    // report the file that `start` began, without the
+                    // backend needing to hand it back a second time.
+                    let mut current_recording = None;
+                    // The `start` event that opened `current_recording`, so
+                    // the paired `stop` can compute the recording's duration
+                    // and report both bounding trigger sequences to the
+                    // manifest.
+                    let mut recording_started_at: Option<(u64, u64)> = None;
+                    // `camera_metrics.dropped_frames()` as of the matching
+                    // `start`, so the paired `stop` can report how many
+                    // frames this recording itself dropped rather than the
+                    // run's cumulative total.
+                    let mut dropped_frames_at_start: u64 = 0;
+                    // Set on `start` when `subtitle` is enabled, and torn
+                    // down on the matching `stop`; see
+                    // [`crate::subtitle_log::spawn`].
+                    let mut current_subtitle_stop: Option<Arc<AtomicBool>> = None;
+                    // Set on `start` when `durability_config` enables a
+                    // sync trigger, and torn down on the matching `stop`;
+                    // see [`crate::durability::spawn`].
+                    let mut current_durability_stop: Option<Arc<AtomicBool>> = None;
+
+                    // Snapshotted fresh for every logged event, rather than
+                    // once per worker, so each row reflects the vehicle's
+                    // attitude/IMU state at the moment of that specific
+                    // event instead of whenever the worker happened to
+                    // start.
+                    let capture_telemetry =
+                        || mavlink_feedback.as_ref().as_ref().map(MavlinkFeedback::latest_capture_telemetry);
+
+                    while let Some(event) = transition_rx.blocking_recv() {
+                        let TriggerEvent { sequence, gpio_timestamp_ns, transition } = event;
+                        match transition {
+                            Transition::Start => {
+                                *pending_start.lock().unwrap() = Some((sequence, gpio_timestamp_ns));
+                                current_recording = recorder.as_ref().and_then(|recorder| recorder.start(sequence));
+                                recording_started_at = Some((sequence, gpio_timestamp_ns));
+                                dropped_frames_at_start = camera_metrics.dropped_frames();
+                                camera_metrics.record_start(current_recording.as_deref());
+                                mqtt.publish(MqttEvent::RecordingState { camera: name.clone(), recording: true });
+                                dbus.publish_state_changed(&name, true);
+                                buzzer.signal(BuzzerEvent::RecordingStarted);
+                                trigger_log.log_event(
+                                    sequence,
+                                    gpio_timestamp_ns,
+                                    &name,
+                                    "start",
+                                    current_recording.as_deref(),
+                                    capture_telemetry(),
+                                );
+                                if let Some(file) = current_recording.as_deref() {
+                                    state_journal.record_start(&name, sequence, file);
+                                }
+
+                                if subtitle_config.enabled {
+                                    current_subtitle_stop = current_recording.as_deref().map(|video_path| {
+                                        subtitle_log::spawn(
+                                            subtitle_config.clone(),
+                                            video_path,
+                                            Arc::clone(&mavlink_feedback),
+                                        )
+                                    });
+                                }
+
+                                if durability_config.interval.is_some() || durability_config.max_bytes.is_some() {
+                                    current_durability_stop = current_recording
+                                        .as_deref()
+                                        .map(|video_path| durability::spawn(durability_config, video_path));
+                                }
+
+                                if !max_recording_duration.is_zero() {
+                                    let transition_tx = transition_tx.clone();
+                                    let trigger_sequence = Arc::clone(&trigger_sequence);
+                                    let camera_metrics = Arc::clone(&camera_metrics);
+                                    let name = name.clone();
+                                    tokio::spawn(async move {
+                                        tokio::time::sleep(max_recording_duration).await;
+
+                                        // If the recording already stopped
+                                        // (a real trigger, a manual stop, a
+                                        // disk-space/disarm failsafe) this is
+                                        // a stale timer for a since-finished
+                                        // recording; leave the new one (if
+                                        // any) alone.
+                                        if !camera_metrics.is_recording() {
+                                            return;
+                                        }
+
+                                        warn!(
+                                            "camera {name}: hit max recording duration of \
+                                             {max_recording_duration:?}, stopping automatically"
+                                        );
+                                        let sequence = trigger_sequence.fetch_add(1, Ordering::Relaxed);
+                                        let _ = transition_tx.send(TriggerEvent {
+                                            sequence,
+                                            // Not a real GPIO edge; same
+                                            // sentinel used by the
+                                            // disk-space and disarm-watch
+                                            // synthetic stops.
+                                            gpio_timestamp_ns: 0,
+                                            transition: Transition::Stop,
+                                        });
+                                    });
+                                }
+                            }
+                            Transition::Stop => {
+                                if let Some(stop) = current_subtitle_stop.take() {
+                                    stop.store(true, Ordering::Relaxed);
+                                }
+                                if let Some(stop) = current_durability_stop.take() {
+                                    stop.store(true, Ordering::Relaxed);
+                                }
+                                let frame_count = recorder.as_ref().and_then(|recorder| recorder.stop());
+                                camera_metrics.record_stop();
+                                mqtt.publish(MqttEvent::RecordingState { camera: name.clone(), recording: false });
+                                dbus.publish_state_changed(&name, false);
+                                buzzer.signal(BuzzerEvent::RecordingStopped);
+                                trigger_log.log_event(
+                                    sequence,
+                                    gpio_timestamp_ns,
+                                    &name,
+                                    "stop",
+                                    current_recording.as_deref(),
+                                    capture_telemetry(),
+                                );
+                                if current_recording.is_some() {
+                                    state_journal.record_stop(&name, sequence);
+                                }
+
+                                if let (Some(file), Some((start_sequence, start_gpio_timestamp_ns))) =
+                                    (current_recording.take(), recording_started_at.take())
+                                {
+                                    if durability_config.interval.is_some() || durability_config.max_bytes.is_some() {
+                                        if let Some(parent) = file.parent() {
+                                            durability::sync_directory(parent);
+                                        }
+                                    }
+
+                                    let duration_ns = gpio_timestamp_ns.saturating_sub(start_gpio_timestamp_ns);
+                                    camera_metrics.record_duration(duration_ns);
+                                    let duration_seconds = duration_ns as f64 / 1_000_000_000.0;
+                                    let dropped_frames =
+                                        camera_metrics.dropped_frames().saturating_sub(dropped_frames_at_start);
+                                    let average_bitrate_kbps = std::fs::metadata(&file)
+                                        .ok()
+                                        .filter(|_| duration_seconds > 0.0)
+                                        .map(|metadata| (metadata.len() as f64 * 8.0 / 1000.0) / duration_seconds);
+                                    let frame_count_display =
+                                        frame_count.map(|count| count.to_string()).unwrap_or_else(|| "unknown".to_string());
+                                    let average_bitrate_kbps_display = average_bitrate_kbps
+                                        .map(|kbps| format!("{kbps:.0}"))
+                                        .unwrap_or_else(|| "unknown".to_string());
+
+                                    info!(
+                                        camera_id = name.as_str(),
+                                        recording_file = file.display().to_string().as_str(),
+                                        duration_seconds = duration_seconds,
+                                        frame_count = frame_count_display.as_str(),
+                                        average_bitrate_kbps = average_bitrate_kbps_display.as_str(),
+                                        dropped_frames = dropped_frames;
+                                        "camera {name}: recording stopped ({duration_seconds:.1}s, \
+                                         {frame_count_display} frames, ~{average_bitrate_kbps_display} kbit/s avg, \
+                                         {dropped_frames} dropped)"
+                                    );
+
+                                    manifest.record_recording(
+                                        &name,
+                                        &file,
+                                        duration_seconds,
+                                        frame_count,
+                                        average_bitrate_kbps,
+                                        dropped_frames,
+                                        start_sequence,
+                                        sequence,
+                                    );
+                                }
+                            }
+                            Transition::CaptureStill => match recorder.as_ref().and_then(|recorder| {
+                                recorder.capture_still().map(|paths| (paths, recorder.still_aeb_ev_stops()))
+                            }) {
+                                Some((paths, ev_stops)) => {
+                                    geotag_stills(&paths, mavlink_feedback.as_ref().as_ref());
+                                    if paths.is_empty() {
+                                        trigger_log.log_event(
+                                            sequence,
+                                            gpio_timestamp_ns,
+                                            &name,
+                                            "capture_still",
+                                            None,
+                                            capture_telemetry(),
+                                        );
+                                    }
+                                    for path in &paths {
+                                        trigger_log.log_event(
+                                            sequence,
+                                            gpio_timestamp_ns,
+                                            &name,
+                                            "capture_still",
+                                            Some(path),
+                                            capture_telemetry(),
+                                        );
+                                    }
+
+                                    if !ev_stops.is_empty() {
+                                        manifest.record_bracket(&name, &paths, ev_stops, sequence);
+                                    }
+
+                                    for path in &paths {
+                                        ros.publish(RosEvent::ImageCaptured {
+                                            camera: name.clone(),
+                                            path: path.clone(),
+                                        });
+                                    }
+                                }
+                                None if recorder.is_none() => {
+                                    warn!(
+                                        "camera {name} received a still-capture command, but this camera is \
+                                         degraded and has no recording backend"
+                                    );
+                                    trigger_log.log_event(
+                                        sequence,
+                                        gpio_timestamp_ns,
+                                        &name,
+                                        "capture_still_disabled",
+                                        None,
+                                        capture_telemetry(),
+                                    );
+                                }
+                                None => {
+                                    warn!(
+                                        "camera {name} received a still-capture command, but still \
+                                         capture is not enabled for it"
+                                    );
+                                    trigger_log.log_event(
+                                        sequence,
+                                        gpio_timestamp_ns,
+                                        &name,
+                                        "capture_still_disabled",
+                                        None,
+                                        capture_telemetry(),
+                                    );
+                                }
+                            },
+                        }
+                    }
+                    debug!("camera {name} transition worker exiting");
+                })
+            };
+
+            {
+                let output_dir = camera.recorder.output_dir.clone();
+                let name = camera.name.clone();
+                let transition_tx = transition_tx.clone();
+                let mavlink_feedback = Arc::clone(&mavlink_feedback);
+                let trigger_sequence = Arc::clone(&trigger_sequence);
+                disk_space::spawn_monitor(output_dir, config.min_free_disk_bytes, move || {
+                    let event = TriggerEvent {
+                        sequence: trigger_sequence.fetch_add(1, Ordering::Relaxed),
+                        // Not a real GPIO edge; there's nothing meaningful
+                        // to put here.
+                        gpio_timestamp_ns: 0,
+                        transition: Transition::Stop,
+                    };
+                    let _ = transition_tx.send(event);
+                    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                        mavlink_feedback
+                            .send_error_statustext(&format!("camera {name}: low disk space, recording stopped"));
+                    }
+                });
+            }
+
+            if config.mavlink.require_armed && config.mavlink.auto_stop_on_disarm {
+                if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                    let name = camera.name.clone();
+                    let transition_tx = transition_tx.clone();
+                    let trigger_sequence = Arc::clone(&trigger_sequence);
+                    let camera_metrics = Arc::clone(&camera_metrics);
+                    mavlink_feedback.spawn_disarm_watch(move || {
+                        if !camera_metrics.is_recording() {
+                            return;
+                        }
+                        info!("camera {name}: vehicle disarmed, stopping recording");
+                        let event = TriggerEvent {
+                            sequence: trigger_sequence.fetch_add(1, Ordering::Relaxed),
+                            // Not a real GPIO edge; there's nothing
+                            // meaningful to put here.
+                            gpio_timestamp_ns: 0,
+                            transition: Transition::Stop,
+                        };
+                        let _ = transition_tx.send(event);
+                    });
+                }
+            }
+
+            crate::retention::spawn_monitor(
+                camera.recorder.output_dir.clone(),
+                config.flight_session.clone(),
+                config.retention,
+            );
+
+            cameras.push(Camera { name: camera.name, transition_tx, worker, recorder });
+        }
+
+        let offload = if config.offload.enabled {
+            let handle = crate::offload::spawn(
+                config.offload.clone(),
+                config.recorder.output_dir.join(&config.flight_session),
+                Arc::clone(&manifest),
+            );
+
+            // Session-wide rather than per-camera (unlike the disarm watch
+            // above): one offload run already covers every camera's files.
+            if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                let offload_on_disarm = handle.clone();
+                mavlink_feedback.spawn_disarm_watch(move || offload_on_disarm.trigger());
+            }
+
+            Some(handle)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            trigger,
+            cameras,
+            trigger_sequence,
+            metrics,
+            mavlink_feedback,
+            trigger_log,
+            mqtt,
+            dbus,
+            ros,
+            dronecan,
+            buzzer,
+            output_dir: config.recorder.output_dir.clone(),
+            flight_session: config.flight_session.clone(),
+            require_armed: config.mavlink.require_armed,
+            min_altitude_gate_m: config.mavlink.min_altitude_gate_m,
+            block_during_rtl: config.mavlink.block_triggers_during_rtl,
+            intervalometer: config.intervalometer.clone(),
+            intervalometer_stop: None,
+            missed_trigger_offset: None,
+            offload,
+            shutdown_inhibitor,
+        })
+    }
+
+    /// Names of every configured camera, primary first.
+    pub fn camera_names(&self) -> Vec<&str> {
+        self.cameras.iter().map(|camera| camera.name.as_str()).collect()
+    }
+
+    /// Total number of edges rejected as bounce/glitch so far.
+    pub fn glitch_count(&self) -> u64 {
+        self.trigger.glitch_count()
+    }
+
+    /// Resyncs the trigger state machine to idle, e.g. after
+    /// [`crate::supervisor::LineSupervisor`] recovers a dropped GPIO line
+    /// and an edge may have been missed during the gap.
+    pub fn reset_trigger(&mut self) {
+        self.trigger.reset();
+    }
+
+    /// See [`crate::trigger::TriggerStateMachine::set_min_pulse_width`].
+    pub fn set_min_pulse_width(&mut self, min_pulse_width: Duration) {
+        self.trigger.set_min_pulse_width(min_pulse_width);
+    }
+
+    /// See [`crate::trigger::TriggerStateMachine::set_short_pulse_max`].
+    pub fn set_short_pulse_max(&mut self, short_pulse_max: Duration) {
+        self.trigger.set_short_pulse_max(short_pulse_max);
+    }
+
+    /// See [`crate::trigger::TriggerStateMachine::set_invert_polarity`].
+    pub fn set_invert_polarity(&mut self, invert_polarity: bool) {
+        self.trigger.set_invert_polarity(invert_polarity);
+    }
+
+    /// See [`crate::trigger::TriggerStateMachine::set_pwm`]. `pwm_mode`
+    /// selects whether `record_above`/`stop_below` apply at all.
+    pub fn set_pwm_mode(&mut self, pwm_mode: bool, record_above: Duration, stop_below: Duration) {
+        self.trigger.set_pwm(pwm_mode.then_some(PwmThresholds { record_above, stop_below }));
+    }
+
+    /// Decodes `edge` and, if it completes a pulse, fans the resulting
+    /// transition out to every camera under a fresh sequence number.
+    pub fn handle_edge(&mut self, edge: Edge) {
+        match self.trigger.on_event(edge.timestamp_ns, edge.event_type) {
+            Some(transition @ Transition::Start) => {
+                if self.require_armed && !self.vehicle_armed() {
+                    warn!("trigger edge at {} ignored: vehicle is disarmed", edge.timestamp_ns);
+                    self.trigger.set_video_recording(false);
+                    let sequence = self.trigger_sequence.fetch_add(1, Ordering::Relaxed);
+                    self.trigger_log.log_event(
+                        sequence,
+                        edge.timestamp_ns,
+                        "-",
+                        "start_ignored_disarmed",
+                        None,
+                        self.capture_telemetry(),
+                    );
+                    return;
+                }
+                if let Some(reason) = self.mission_phase_gate() {
+                    warn!("trigger edge at {} ignored: {reason}", edge.timestamp_ns);
+                    self.trigger.set_video_recording(false);
+                    let sequence = self.trigger_sequence.fetch_add(1, Ordering::Relaxed);
+                    self.trigger_log.log_event(
+                        sequence,
+                        edge.timestamp_ns,
+                        "-",
+                        "start_ignored_mission_phase",
+                        None,
+                        self.capture_telemetry(),
+                    );
+                    return;
+                }
+                info!("trigger armed at {}, starting recording on {:?}", edge.timestamp_ns, self.camera_names());
+                self.shutdown_inhibitor.acquire();
+                let sequence = self.fan_out(transition, edge.timestamp_ns);
+                self.publish_trigger(edge.timestamp_ns);
+                self.check_missed_triggers(sequence);
+                self.start_intervalometer();
+            }
+            Some(transition @ Transition::Stop) => {
+                info!("trigger released at {}, stopping recording on {:?}", edge.timestamp_ns, self.camera_names());
+                self.stop_intervalometer();
+                self.shutdown_inhibitor.release();
+                let sequence = self.fan_out(transition, edge.timestamp_ns);
+                self.check_missed_triggers(sequence);
+            }
+            Some(transition @ Transition::CaptureStill) => {
+                if let Some(reason) = self.mission_phase_gate() {
+                    warn!("short pulse at {} ignored: {reason}", edge.timestamp_ns);
+                    let sequence = self.trigger_sequence.fetch_add(1, Ordering::Relaxed);
+                    self.trigger_log.log_event(
+                        sequence,
+                        edge.timestamp_ns,
+                        "-",
+                        "capture_ignored_mission_phase",
+                        None,
+                        self.capture_telemetry(),
+                    );
+                    return;
+                }
+                info!("short pulse at {}, still capture requested", edge.timestamp_ns);
+                let sequence = self.fan_out(transition, edge.timestamp_ns);
+                self.check_missed_triggers(sequence);
+            }
+            None => {}
+        }
+    }
+
+    /// Applies a manually-issued (as opposed to edge-decoded) [`ControlCommand`],
+    /// shared by the control API and the SIGUSR1/SIGUSR2 handlers in
+    /// [`crate::main::run`]'s event loop, so both paths stay consistent with
+    /// the trigger's recording toggle and go through the same fan-out and
+    /// logging as a real trigger pulse. `source` is only used for logging,
+    /// to tell the different callers apart.
+    pub fn dispatch(&mut self, command: ControlCommand, source: &str) {
+        let transition = match command {
+            ControlCommand::Start if self.trigger.video_recording() => {
+                info!("{source} requested start, but video is already recording");
+                None
+            }
+            ControlCommand::Start => {
+                info!("{source} requested start");
+                self.trigger.set_video_recording(true);
+                self.shutdown_inhibitor.acquire();
+                Some(Transition::Start)
+            }
+            ControlCommand::Stop if !self.trigger.video_recording() => {
+                info!("{source} requested stop, but video isn't recording");
+                None
+            }
+            ControlCommand::Stop => {
+                info!("{source} requested stop");
+                self.trigger.set_video_recording(false);
+                self.shutdown_inhibitor.release();
+                Some(Transition::Stop)
+            }
+            ControlCommand::Snapshot => {
+                info!("{source} requested a snapshot");
+                Some(Transition::CaptureStill)
+            }
+            ControlCommand::SetCameraControls(controls) => {
+                info!("{source} requested a camera controls change");
+                self.apply_camera_controls(controls);
+                None
+            }
+            ControlCommand::SetRegionOfInterest(roi) => {
+                info!("{source} requested a region-of-interest change");
+                self.apply_region_of_interest(roi);
+                None
+            }
+            ControlCommand::Offload => {
+                info!("{source} requested an offload run");
+                match &self.offload {
+                    Some(offload) => offload.trigger(),
+                    None => warn!("{source} requested an offload run, but offload isn't configured"),
+                }
+                None
+            }
+        };
+
+        // No hardware timestamp exists for a manually-issued command, so
+        // `gpio_timestamp_ns` is 0 (same sentinel used for other synthetic
+        // events) and MAVLink feedback (which needs one to convert to
+        // PX4's clock) is skipped.
+        if let Some(transition) = transition {
+            self.fan_out(transition, 0);
+        }
+    }
+
+    /// Applies `controls` to every camera's backend, logging (rather than
+    /// failing the caller) any camera whose backend can't drive runtime
+    /// controls, since one unsupported camera shouldn't block the rest.
+    fn apply_camera_controls(&self, controls: crate::recorder::CameraControls) {
+        for camera in &self.cameras {
+            let Some(recorder) = &camera.recorder else {
+                continue;
+            };
+            if let Err(error) = recorder.set_controls(controls) {
+                warn!("camera {}: failed to apply camera controls: {error}", camera.name);
+            }
+        }
+    }
+
+    /// Applies `roi` to every camera's backend, logging (rather than failing
+    /// the caller) any camera whose backend can't drive a live ROI, same
+    /// rationale as [`Self::apply_camera_controls`].
+    fn apply_region_of_interest(&self, roi: crate::recorder::RegionOfInterest) {
+        for camera in &self.cameras {
+            let Some(recorder) = &camera.recorder else {
+                continue;
+            };
+            if let Err(error) = recorder.set_roi(roi) {
+                warn!("camera {}: failed to apply region of interest: {error}", camera.name);
+            }
+        }
+    }
+
+    /// Logs a marker row in the trigger event log for an
+    /// [`crate::aux_lines::LineAction::MarkEvent`] line, without affecting
+    /// any camera's recording. Uses its own sequence number, same as every
+    /// other accepted event, so it lines up against the rest of the log in
+    /// order.
+    pub fn mark_event(&self, label: &str) {
+        info!("aux line {label} pulsed, marking event");
+        let sequence = self.trigger_sequence.fetch_add(1, Ordering::Relaxed);
+        self.trigger_log.log_event(sequence, 0, "-", label, None, self.capture_telemetry());
+    }
+
+    /// Whether PX4 currently reports armed, per [`MavlinkFeedback::is_armed`].
+    /// `true` if MAVLink isn't connected, so `require_armed` only ever gates
+    /// triggers when there's an actual link to check against.
+    fn vehicle_armed(&self) -> bool {
+        self.mavlink_feedback.as_ref().as_ref().map_or(true, MavlinkFeedback::is_armed)
+    }
+
+    /// Returns why a `Start`/`CaptureStill` trigger should be ignored based
+    /// on the vehicle's current mission phase - `min_altitude_gate_m`
+    /// (ground handling before takeoff and landing bounce pulses after
+    /// touchdown) and `block_during_rtl` - or `None` if it should proceed.
+    /// `None` if MAVLink isn't connected, same permissive default as
+    /// `vehicle_armed`.
+    fn mission_phase_gate(&self) -> Option<&'static str> {
+        let feedback = self.mavlink_feedback.as_ref().as_ref()?;
+
+        if let Some(min_altitude_gate_m) = self.min_altitude_gate_m {
+            let relative_alt_m = feedback.latest_position().relative_alt as f32 / 1000.0;
+            if !feedback.takeoff_detected() {
+                return Some("below the takeoff altitude gate, takeoff not yet detected");
+            }
+            if relative_alt_m < min_altitude_gate_m {
+                return Some("below the altitude gate");
+            }
+        }
+
+        if self.block_during_rtl && feedback.is_rtl() {
+            return Some("vehicle is in RTL");
+        }
+
+        None
+    }
+
+    /// See [`MavlinkFeedback::latest_capture_telemetry`]. `None` if MAVLink
+    /// isn't connected, for [`Session::trigger_log`] rows logged outside a
+    /// camera's transition worker (e.g. a disarmed-ignored start or an aux
+    /// line marker).
+    fn capture_telemetry(&self) -> Option<crate::mavlink::CaptureTelemetry> {
+        self.mavlink_feedback.as_ref().as_ref().map(MavlinkFeedback::latest_capture_telemetry)
+    }
+
+    fn fan_out(&self, transition: Transition, gpio_timestamp_ns: u64) -> u64 {
+        let sequence = self.trigger_sequence.fetch_add(1, Ordering::Relaxed);
+        let kind = format!("{transition:?}");
+        self.metrics.record_trigger(&kind);
+        self.mqtt.publish(MqttEvent::Trigger { trigger_id: sequence, kind });
+        self.ros.publish(RosEvent::Trigger { gpio_timestamp_ns });
+        self.dronecan.publish(DroneCanEvent { sequence, gpio_timestamp_ns });
+        for camera in &self.cameras {
+            let _ = camera.transition_tx.send(TriggerEvent { sequence, gpio_timestamp_ns, transition });
+        }
+        sequence
+    }
+
+    /// Sends the `CAMERA_TRIGGER`/persists the image sequence counter on a
+    /// spawned task rather than inline, since
+    /// [`MavlinkFeedback::publish_trigger`] does a blocking disk write on
+    /// every call - at a high trigger rate (a fast survey line pulsing at
+    /// 10+ Hz) that would otherwise sit on the same hot path as
+    /// [`Session::fan_out`], which needs to hand a fresh transition to every
+    /// camera without delay. A no-op if `mavlink` isn't connected.
+    fn publish_trigger(&self, gpio_timestamp_ns: u64) {
+        if self.mavlink_feedback.is_none() {
+            return;
+        }
+        let mavlink_feedback = Arc::clone(&self.mavlink_feedback);
+        tokio::spawn(async move {
+            if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                mavlink_feedback.publish_trigger(gpio_timestamp_ns);
+            }
+        });
+    }
+
+    /// Compares our own trigger sequence against the last `CAMERA_TRIGGER.seq`
+    /// PX4 reported over MAVLink, warning and counting a
+    /// [`Metrics::record_missed_trigger`] if they've diverged since the last
+    /// check. Learns the offset between the two counters from the first
+    /// comparison, since PX4's counter and ours start from unrelated bases
+    /// (its own boot count vs. this process's own run), then expects it to
+    /// hold steady - any change means a pulse was decoded on one side and
+    /// missed on the other. Resyncs to the new offset after warning rather
+    /// than repeating the same warning on every later trigger, the same
+    /// "don't keep re-reporting a gap that's already been reported"
+    /// reasoning as [`TriggerStateMachine`]'s line-recovery resync. A no-op
+    /// if `mavlink` isn't connected or PX4 hasn't sent a `CAMERA_TRIGGER`
+    /// yet.
+    fn check_missed_triggers(&mut self, sequence: u64) {
+        let Some(mavlink_feedback) = self.mavlink_feedback.as_ref().as_ref() else {
+            return;
+        };
+        let Some(px4_sequence) = mavlink_feedback.last_px4_trigger_sequence() else {
+            return;
+        };
+
+        let offset = px4_sequence as i64 - sequence as i64;
+        if let Some(expected_offset) = self.missed_trigger_offset {
+            if offset != expected_offset {
+                warn!(
+                    "trigger sequence diverged from PX4's: ours is {sequence}, PX4 reports {px4_sequence} \
+                     (expected offset {expected_offset}, now {offset}) - a pulse was likely missed"
+                );
+                self.metrics.record_missed_trigger();
+            }
+        }
+        self.missed_trigger_offset = Some(offset);
+    }
+
+    /// If [`IntervalometerConfig::enabled`], spawns the background task that
+    /// drives this armed session's timelapse captures, storing its stop
+    /// handle so [`Session::stop_intervalometer`] can tear it down on the
+    /// matching `Stop`. Fans a synthetic `CaptureStill` out to every camera
+    /// on the same trigger sequence/metrics path as a real short pulse, so
+    /// it shows up identically in the trigger log.
+    fn start_intervalometer(&mut self) {
+        if !self.intervalometer.enabled {
+            return;
+        }
+
+        let cameras: Vec<_> = self.cameras.iter().map(|camera| camera.transition_tx.clone()).collect();
+        let trigger_sequence = Arc::clone(&self.trigger_sequence);
+        let metrics = Arc::clone(&self.metrics);
+        let mavlink_feedback = Arc::clone(&self.mavlink_feedback);
+
+        self.intervalometer_stop = Some(intervalometer::spawn(self.intervalometer.clone(), mavlink_feedback, move || {
+            let sequence = trigger_sequence.fetch_add(1, Ordering::Relaxed);
+            metrics.record_trigger("CaptureStill");
+            for transition_tx in &cameras {
+                let _ = transition_tx.send(TriggerEvent { sequence, gpio_timestamp_ns: 0, transition: Transition::CaptureStill });
+            }
+        }));
+    }
+
+    /// Stops the running intervalometer task, if any. A no-op if it's
+    /// disabled or already stopped, so callers can call it unconditionally
+    /// on every `Stop`.
+    fn stop_intervalometer(&mut self) {
+        if let Some(stop) = self.intervalometer_stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Sends a final stop through the same queue as every other transition,
+    /// then drops the sender and waits for the worker to drain, per camera,
+    /// so shutdown doesn't race an in-flight start/stop and one camera's
+    /// finalization can't delay another's.
+    pub async fn shutdown(mut self) {
+        self.stop_intervalometer();
+        // Whatever the trigger state was, we're finalizing everything below
+        // - so there's no longer anything left for logind to wait on.
+        self.shutdown_inhibitor.release();
+
+        let Session { cameras, trigger_sequence, metrics, output_dir, flight_session, .. } = self;
+
+        for camera in cameras {
+            let _ = camera.transition_tx.send(TriggerEvent {
+                sequence: trigger_sequence.fetch_add(1, Ordering::Relaxed),
+                // Not a real GPIO edge; there's nothing meaningful to put
+                // here.
+                gpio_timestamp_ns: 0,
+                transition: Transition::Stop,
+            });
+            drop(camera.transition_tx);
+            let _ = camera.worker.await;
+        }
+
+        crate::summary::write(&output_dir, &flight_session, metrics.trigger_count(), &metrics);
+    }
+}
+
+/// Embeds the latest MAVLink position/attitude sample, plus the latest
+/// gimbal orientation if a gimbal is present, into each of `paths`' EXIF
+/// metadata, if `mavlink_feedback` is connected. Best-effort: a single image
+/// failing to geotag is logged and skipped rather than losing the whole
+/// batch.
+fn geotag_stills(paths: &[PathBuf], mavlink_feedback: Option<&MavlinkFeedback>) {
+    let Some(mavlink_feedback) = mavlink_feedback else {
+        return;
+    };
+
+    let position = mavlink_feedback.latest_position();
+    let gimbal = mavlink_feedback.latest_gimbal_attitude();
+    let captured_at = SystemTime::now();
+    for path in paths {
+        if let Err(error) = geotag::embed_gps_exif(path, &position, captured_at, gimbal) {
+            warn!("failed to geotag {}: {error}", path.display());
+        }
+    }
+}
+
+/// Upper bound [`spawn_init_retry`]'s backoff is allowed to grow to,
+/// regardless of how small `recorder_config.init_retry_interval` is
+/// configured. Without a cap, a camera that never comes back (unplugged,
+/// dead SD card slot) would otherwise retry at an ever-growing interval
+/// forever rather than settling into a steady, bounded poll.
+const INIT_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Background half of
+/// [`crate::recorder::RecorderConfig::init_degraded_on_failure`]: retries
+/// building `recorder_config`'s recording pipeline, starting at
+/// `recorder_config.init_retry_interval` and doubling (capped at
+/// [`INIT_RETRY_MAX_BACKOFF`]) after every failed attempt, until one
+/// succeeds. This session's `Camera` entries aren't hot-swappable, so a
+/// successful retry doesn't plug the new pipeline in directly - it marks
+/// the watchdog unhealthy, which (via the same systemd `Restart=on-watchdog`
+/// path used by a fatal pipeline error elsewhere in this file) restarts the
+/// whole process, which then builds every camera fresh. Cruder than
+/// swapping the live camera in place, but it reuses a self-healing path
+/// this service already depends on instead of adding a second, riskier one.
+fn spawn_init_retry(
+    recorder_config: crate::recorder::RecorderConfig,
+    name: String,
+    flight_session: String,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+    watchdog: Watchdog,
+    realtime_clock: Arc<crate::clock::RealtimeClock>,
+) {
+    let mut backoff = recorder_config.init_retry_interval;
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(backoff).await;
+
+            let blocking_recorder_config = recorder_config.clone();
+            let blocking_name = name.clone();
+            let blocking_flight_session = flight_session.clone();
+            let blocking_mavlink_feedback = Arc::clone(&mavlink_feedback);
+            let blocking_realtime_clock = Arc::clone(&realtime_clock);
+            let result = tokio::task::spawn_blocking(move || {
+                Recorder::new(
+                    &blocking_recorder_config,
+                    &blocking_name,
+                    &blocking_flight_session,
+                    || {},
+                    |_| {},
+                    |_| {},
+                    || {},
+                    |_| {},
+                    |_| {},
+                    blocking_mavlink_feedback,
+                    blocking_realtime_clock,
+                )
+            })
+            .await;
+
+            match result {
+                Ok(Ok(_recorder)) => {
+                    info!("camera {name}: recording pipeline recovered; restarting to bring it back online");
+                    watchdog.mark_unhealthy();
+                    return;
+                }
+                Ok(Err(error)) => {
+                    debug!(
+                        "camera {name}: still degraded, retrying pipeline init failed: {error} \
+                         (next retry in {backoff:?})"
+                    );
+                    backoff = (backoff * 2).min(INIT_RETRY_MAX_BACKOFF);
+                }
+                Err(error) => {
+                    warn!("camera {name}: init retry task panicked, giving up on background retry: {error}");
+                    return;
+                }
+            }
+        }
+    });
+}
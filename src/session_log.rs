@@ -0,0 +1,86 @@
+//! Wraps whichever logging backend [`crate::main`] installs (journald,
+//! newline-delimited JSON via [`crate::json_logger`], or plain text) so every
+//! record emitted once the flight session id is known carries it as a
+//! `session_id` structured field, the same key [`crate::trigger_log`]
+//! already attaches by hand to its own trigger-event log lines. Saves every
+//! other `log::info!`/`warn!`/etc. call site in the process from having to
+//! remember to attach it itself, and covers third-party crates' log calls
+//! too.
+
+use std::sync::OnceLock;
+
+use log::{Log, Metadata, Record};
+
+static CURRENT_SESSION_ID: OnceLock<String> = OnceLock::new();
+
+/// Sets the flight session id every subsequent log record is stamped with.
+/// Meant to be called exactly once, right after [`crate::config::Config::load`]
+/// resolves it; a second call is ignored (logged at `warn`) rather than
+/// panicking, since a live config reload re-resolving the same field
+/// shouldn't be able to take the process down.
+pub fn set_session_id(session_id: &str) {
+    if CURRENT_SESSION_ID.set(session_id.to_string()).is_err() {
+        log::warn!("flight session id already set, ignoring later value {session_id:?}");
+    }
+}
+
+/// Installs `inner` as the global [`log`] logger, wrapped so that once
+/// [`set_session_id`] has been called, every record passing through it gets
+/// a `session_id` field alongside whatever it already carries. Records
+/// logged before [`set_session_id`] is called (startup, config resolution)
+/// pass through unchanged.
+pub struct SessionLog {
+    inner: Box<dyn Log>,
+}
+
+impl SessionLog {
+    pub fn install(inner: Box<dyn Log>) {
+        log::set_boxed_logger(Box::new(Self { inner })).expect("logger already installed");
+    }
+}
+
+impl Log for SessionLog {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let Some(session_id) = CURRENT_SESSION_ID.get() else {
+            self.inner.log(record);
+            return;
+        };
+
+        let key_values = WithSessionId { session_id, inner: record.key_values() };
+        let stamped = Record::builder()
+            .args(*record.args())
+            .metadata(record.metadata().clone())
+            .module_path(record.module_path())
+            .file(record.file())
+            .line(record.line())
+            .key_values(&key_values)
+            .build();
+        self.inner.log(&stamped);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// A [`log::kv::Source`] that adds `session_id` ahead of whatever key-values
+/// the original record already carried.
+struct WithSessionId<'a> {
+    session_id: &'a str,
+    inner: &'a dyn log::kv::Source,
+}
+
+impl<'a> log::kv::Source for WithSessionId<'a> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn log::kv::VisitSource<'kvs>) -> Result<(), log::kv::Error> {
+        visitor.visit_pair(log::kv::Key::from("session_id"), log::kv::Value::from(self.session_id))?;
+        self.inner.visit(visitor)
+    }
+}
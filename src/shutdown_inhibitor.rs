@@ -0,0 +1,169 @@
+//! Takes a systemd-logind shutdown inhibitor lock (`delay` mode) while a
+//! recording is active, so an operator-initiated `poweroff`/`reboot` waits
+//! (up to logind's own `InhibitDelayMaxUSec`) for
+//! [`crate::session::Session::shutdown`] to finalize every camera's file
+//! instead of the process being killed mid-write. `zbus` handles the wire
+//! protocol, same reasoning as [`crate::dbus_api`].
+
+use futures::stream::StreamExt;
+use log::{info, warn};
+use tokio::sync::mpsc;
+use zbus::zvariant::OwnedFd;
+use zbus::{proxy, Connection};
+
+/// Parameters for the shutdown inhibitor lock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShutdownInhibitorConfig {
+    /// Whether to take the lock at all. Off by default: it requires a
+    /// system bus and a logind implementation to be present, neither of
+    /// which every deployment target has.
+    pub enabled: bool,
+}
+
+impl Default for ShutdownInhibitorConfig {
+    fn default() -> Self {
+        Self { enabled: false }
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_shutdown(&self, active: bool) -> zbus::Result<()>;
+}
+
+/// Requested of the background task started by [`spawn`]; see
+/// [`ShutdownInhibitor::acquire`]/[`ShutdownInhibitor::release`].
+enum InhibitorCommand {
+    Acquire,
+    Release,
+}
+
+/// A handle for taking/releasing the inhibitor lock around an active
+/// recording, cheap to clone same as [`crate::mqtt::MqttPublisher`].
+#[derive(Clone)]
+pub struct ShutdownInhibitor {
+    commands: mpsc::UnboundedSender<InhibitorCommand>,
+}
+
+impl ShutdownInhibitor {
+    /// A handle that drops every request, for when
+    /// `ShutdownInhibitorConfig::enabled` is off - callers don't need to
+    /// branch on whether it's configured.
+    pub fn disabled() -> Self {
+        let (commands, _rx) = mpsc::unbounded_channel();
+        Self { commands }
+    }
+
+    /// Fire-and-forget, same as [`crate::buzzer::BuzzerHandle::signal`]:
+    /// takes the `delay`-mode inhibitor lock on the background task, if not
+    /// already held.
+    pub fn acquire(&self) {
+        let _ = self.commands.send(InhibitorCommand::Acquire);
+    }
+
+    /// Fire-and-forget release of the inhibitor lock, if held, letting a
+    /// delayed shutdown proceed immediately instead of waiting out logind's
+    /// timeout. Safe to call unconditionally on every stop, whether or not
+    /// the lock is currently held.
+    pub fn release(&self) {
+        let _ = self.commands.send(InhibitorCommand::Release);
+    }
+}
+
+/// Connects to the system bus and spawns two background tasks: one that
+/// takes/releases the inhibitor lock as [`ShutdownInhibitor::acquire`]/
+/// [`ShutdownInhibitor::release`] are called, and one that forwards
+/// logind's `PrepareForShutdown` signal onto `prepare_for_shutdown` so
+/// [`crate::main::run`]'s event loop can trigger the same clean
+/// finalization a SIGTERM does if the actual shutdown proceeds - whether
+/// because we released the lock ourselves or because logind's own delay
+/// timeout ran out first.
+pub async fn spawn(
+    _config: &ShutdownInhibitorConfig,
+    prepare_for_shutdown: mpsc::UnboundedSender<()>,
+) -> Result<ShutdownInhibitor, String> {
+    let connection = Connection::system().await.map_err(|error| format!("failed to connect to system bus: {error}"))?;
+
+    let signal_connection = connection.clone();
+    tokio::spawn(async move {
+        let manager = match LoginManagerProxy::new(&signal_connection).await {
+            Ok(manager) => manager,
+            Err(error) => {
+                warn!("failed to build logind manager proxy for PrepareForShutdown: {error}");
+                return;
+            }
+        };
+
+        let mut signals = match manager.receive_prepare_for_shutdown().await {
+            Ok(signals) => signals,
+            Err(error) => {
+                warn!("failed to subscribe to logind PrepareForShutdown signal: {error}");
+                return;
+            }
+        };
+
+        while let Some(signal) = signals.next().await {
+            match signal.args() {
+                Ok(args) if args.active => {
+                    info!("logind reported shutdown is proceeding, requesting a clean stop");
+                    let _ = prepare_for_shutdown.send(());
+                }
+                Ok(_) => {
+                    // `active: false` just means a previous shutdown was
+                    // cancelled; nothing for us to do.
+                }
+                Err(error) => warn!("failed to decode PrepareForShutdown signal: {error}"),
+            }
+        }
+    });
+
+    let (commands, mut command_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        // Owns the fd that actually holds the lock open; dropping it (by
+        // replacing it with `None`) is what releases it.
+        let mut lock: Option<OwnedFd> = None;
+
+        while let Some(command) = command_rx.recv().await {
+            match command {
+                InhibitorCommand::Acquire => {
+                    if lock.is_some() {
+                        continue;
+                    }
+                    let manager = match LoginManagerProxy::new(&connection).await {
+                        Ok(manager) => manager,
+                        Err(error) => {
+                            warn!("failed to build logind manager proxy: {error}");
+                            continue;
+                        }
+                    };
+                    match manager
+                        .inhibit("shutdown", "px4-camera-trigger", "finalizing an active recording", "delay")
+                        .await
+                    {
+                        Ok(fd) => {
+                            info!("took shutdown inhibitor lock while recording");
+                            lock = Some(fd);
+                        }
+                        Err(error) => warn!("failed to take shutdown inhibitor lock: {error}"),
+                    }
+                }
+                InhibitorCommand::Release => {
+                    if lock.take().is_some() {
+                        info!("released shutdown inhibitor lock");
+                    }
+                }
+            }
+        }
+    });
+
+    info!("subscribed to logind shutdown inhibitor/PrepareForShutdown");
+
+    Ok(ShutdownInhibitor { commands })
+}
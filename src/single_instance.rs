@@ -0,0 +1,71 @@
+//! Guards against a second copy of this service running at the same time
+//! (a manual `cargo run`/binary invocation alongside the systemd unit, or a
+//! restart racing a still-shutting-down old process), since two instances
+//! would otherwise both try to request the same GPIO line and open the same
+//! camera devices.
+//!
+//! An `flock`ed pidfile rather than e.g. a well-known abstract socket: it's
+//! the standard Unix daemon idiom, survives a `kill -9` of the holder (the
+//! kernel drops the lock when the fd closes), and doubles as a normal
+//! `/run/*.pid` file an operator can `cat` to find the running PID.
+
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use fs2::FileExt;
+
+/// Distinct from the generic `exit(1)` used for other startup failures, so
+/// an operator (or a systemd `Restart=` policy inspecting exit codes) can
+/// tell "another instance is already running" apart from a config or
+/// hardware error.
+pub const ALREADY_RUNNING_EXIT_CODE: i32 = 4;
+
+/// Holds `path` locked for as long as it's alive; the lock is released
+/// automatically when this is dropped (or the process exits/crashes), since
+/// `flock` is tied to the file descriptor rather than needing an explicit
+/// unlock.
+pub struct InstanceLock {
+    /// Never read again after [`acquire`] writes the PID into it; kept
+    /// alive purely so its `flock` (and the open fd backing it) lasts for
+    /// the process's lifetime instead of being released as soon as this
+    /// function returns.
+    _file: File,
+}
+
+/// Opens (creating if necessary) and `flock`s `path`, writing this
+/// process's PID into it on success. If another live instance already holds
+/// the lock, returns an error naming its PID (read back out of the
+/// contended file) so the operator doesn't have to go hunting for it
+/// themselves.
+pub fn acquire(path: &Path) -> Result<InstanceLock, String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .write(true)
+        .mode(0o644)
+        .open(path)
+        .map_err(|error| format!("failed to open pidfile {}: {error}", path.display()))?;
+
+    if let Err(error) = file.try_lock_exclusive() {
+        if error.kind() == ErrorKind::WouldBlock {
+            let mut contents = String::new();
+            let _ = file.read_to_string(&mut contents);
+            let holder = contents.trim();
+            let holder = if holder.is_empty() { "unknown pid".to_string() } else { format!("pid {holder}") };
+            return Err(format!(
+                "another instance is already running ({holder}, per {}); refusing to start a second one",
+                path.display()
+            ));
+        }
+        return Err(format!("failed to lock pidfile {}: {error}", path.display()));
+    }
+
+    file.set_len(0).map_err(|error| format!("failed to truncate pidfile {}: {error}", path.display()))?;
+    file.seek(SeekFrom::Start(0)).map_err(|error| format!("failed to seek pidfile {}: {error}", path.display()))?;
+    write!(file, "{}", std::process::id())
+        .map_err(|error| format!("failed to write pidfile {}: {error}", path.display()))?;
+
+    Ok(InstanceLock { _file: file })
+}
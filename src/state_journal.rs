@@ -0,0 +1,257 @@
+//! A tiny write-ahead ledger of each camera's recording start/stop
+//! transitions, so [`StateJournal::open`] can tell, on the next run, whether
+//! the previous one shut down cleanly or was killed/crashed mid-recording -
+//! and if the latter, which camera's recording was left open and what
+//! trigger sequence to resume numbering from, so a mid-flight systemd
+//! restart doesn't silently drop that recording from the session's
+//! bookkeeping or collide a fresh trigger sequence with one already used.
+//!
+//! Deliberately separate from [`crate::trigger_log`]'s CSV sidecar: that one
+//! is the human-facing, unbounded record of every trigger event across every
+//! action type for the whole flight. This one only ever holds the handful of
+//! start/stop rows since the last clean recovery - [`StateJournal::open`]
+//! truncates it once it has read back whatever a previous run left behind,
+//! so it can't grow across a long flight the way the trigger log can. Uses
+//! the same plain-CSV approach as the trigger log (and for the same reason -
+//! see [`crate::manifest`]'s module docs) rather than JSON, since this one,
+//! unlike the manifest, actually needs to be read back on the next startup.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::warn;
+
+use crate::trigger_log::{csv_escape, split_csv_row};
+
+/// One camera's recording that was still open (a `start` row with no
+/// matching `stop`) in the journal [`StateJournal::open`] just recovered
+/// from, for [`crate::manifest::Manifest::record_orphaned_recording`] to
+/// flag.
+pub struct OrphanedRecording {
+    pub camera: String,
+    /// `None` if the recovered `start` row's file field was empty.
+    /// `StateJournal::record_start`'s only call site always has a real file
+    /// in hand before calling it, so this can't currently happen from a
+    /// journal this binary wrote itself; kept so [`recover`] stays a
+    /// faithful, non-panicking parse of whatever a `state.journal` file on
+    /// disk actually contains.
+    pub file: Option<PathBuf>,
+    pub sequence: u64,
+}
+
+/// What [`StateJournal::open`] recovered from a previous run's journal, if
+/// any existed.
+pub struct RecoveredState {
+    /// The trigger sequence to resume numbering from, i.e. one past the
+    /// highest sequence seen in the recovered journal. `0` for a fresh
+    /// session with no prior journal.
+    pub next_sequence: u64,
+    pub orphaned: Vec<OrphanedRecording>,
+}
+
+/// Append-only CSV ledger at `<output_dir>/<flight_session>/state.journal`;
+/// see the module docs.
+pub struct StateJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl StateJournal {
+    /// Reads back a previous run's journal (if any) into a
+    /// [`RecoveredState`], then truncates and reopens it fresh - everything
+    /// in it has now either been matched to a clean `stop` or handed back as
+    /// `orphaned` for the caller to record elsewhere, so there's nothing left
+    /// worth keeping around.
+    pub fn open(output_dir: &Path, flight_session: &str) -> Result<(Self, RecoveredState), String> {
+        let session_dir = output_dir.join(flight_session);
+        std::fs::create_dir_all(&session_dir).map_err(|error| {
+            format!("failed to create session directory {}: {error}", session_dir.display())
+        })?;
+
+        let path = session_dir.join("state.journal");
+        let recovered = match std::fs::read_to_string(&path) {
+            Ok(contents) => recover(&contents),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                RecoveredState { next_sequence: 0, orphaned: Vec::new() }
+            }
+            Err(error) => return Err(format!("failed to read state journal {}: {error}", path.display())),
+        };
+
+        let file = File::create(&path)
+            .map_err(|error| format!("failed to open state journal {}: {error}", path.display()))?;
+
+        Ok((Self { path, file: Mutex::new(file) }, recovered))
+    }
+
+    /// Appends a `start` row for `camera`'s new recording at `file`.
+    pub fn record_start(&self, camera: &str, sequence: u64, file: &Path) {
+        self.append(&format!(
+            "{sequence},{},start,{}",
+            csv_escape(camera),
+            csv_escape(&file.display().to_string()),
+        ));
+    }
+
+    /// Appends a `stop` row closing out `camera`'s currently open recording.
+    pub fn record_stop(&self, camera: &str, sequence: u64) {
+        self.append(&format!("{sequence},{},stop,", csv_escape(camera)));
+    }
+
+    /// Writes `line` and `fdatasync`s it before returning - buffering this
+    /// defeats the purpose of a write-ahead journal, since the whole point
+    /// is that a crash immediately after this call must still see the row on
+    /// the next run's recovery.
+    fn append(&self, line: &str) {
+        let mut file = self.file.lock().unwrap();
+        let result = writeln!(file, "{line}").and_then(|()| file.sync_data());
+        if let Err(error) = result {
+            warn!("failed to append to state journal {}: {error}", self.path.display());
+        }
+    }
+}
+
+/// Replays `contents` (one `sequence,camera,event,file` row per line, same
+/// shape [`StateJournal::record_start`]/`record_stop` write) into a
+/// [`RecoveredState`]: the highest sequence seen across every row, plus one,
+/// and whichever camera's last row was a `start` never followed by a
+/// matching `stop`.
+fn recover(contents: &str) -> RecoveredState {
+    let mut max_sequence: Option<u64> = None;
+    let mut open_recordings: HashMap<String, (u64, Option<PathBuf>)> = HashMap::new();
+
+    for line in contents.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields = split_csv_row(line);
+        let [sequence, camera, event, file] = fields.as_slice() else {
+            warn!("skipping malformed state journal row: {line:?}");
+            continue;
+        };
+        let Ok(sequence) = sequence.parse::<u64>() else {
+            warn!("skipping state journal row with a bad sequence: {line:?}");
+            continue;
+        };
+
+        max_sequence = Some(max_sequence.map_or(sequence, |max| max.max(sequence)));
+
+        match event.as_str() {
+            "start" => {
+                let file = if file.is_empty() { None } else { Some(PathBuf::from(file)) };
+                open_recordings.insert(camera.clone(), (sequence, file));
+            }
+            "stop" => {
+                open_recordings.remove(camera);
+            }
+            _ => warn!("skipping state journal row with an unknown event {event:?}: {line:?}"),
+        }
+    }
+
+    let orphaned = open_recordings
+        .into_iter()
+        .map(|(camera, (sequence, file))| OrphanedRecording { camera, file, sequence })
+        .collect();
+
+    RecoveredState { next_sequence: max_sequence.map_or(0, |max| max + 1), orphaned }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_journal_starts_at_sequence_zero_with_nothing_orphaned() {
+        let recovered = recover("");
+        assert_eq!(recovered.next_sequence, 0);
+        assert!(recovered.orphaned.is_empty());
+    }
+
+    #[test]
+    fn matched_start_and_stop_leaves_nothing_orphaned() {
+        let recovered = recover("0,cam-a,start,/video/a.mp4\n1,cam-a,stop,\n");
+        assert_eq!(recovered.next_sequence, 2);
+        assert!(recovered.orphaned.is_empty());
+    }
+
+    #[test]
+    fn unmatched_start_is_orphaned() {
+        let recovered = recover("5,cam-a,start,/video/a.mp4\n");
+        assert_eq!(recovered.next_sequence, 6);
+        assert_eq!(recovered.orphaned.len(), 1);
+        assert_eq!(recovered.orphaned[0].camera, "cam-a");
+        assert_eq!(recovered.orphaned[0].sequence, 5);
+        assert_eq!(recovered.orphaned[0].file, Some(PathBuf::from("/video/a.mp4")));
+    }
+
+    #[test]
+    fn unmatched_start_with_empty_file_field_orphans_with_none() {
+        // Not produced by `record_start` itself (see `OrphanedRecording`'s
+        // doc comment) - this covers `recover` parsing an empty file field
+        // without panicking, for whatever else might end up in a
+        // `state.journal` file on disk.
+        let recovered = recover("0,cam-a,start,\n");
+        assert_eq!(recovered.orphaned[0].file, None);
+    }
+
+    #[test]
+    fn interleaved_cameras_are_tracked_independently() {
+        let recovered = recover(
+            "0,cam-a,start,/video/a0.mp4\n\
+             1,cam-b,start,/video/b0.mp4\n\
+             2,cam-a,stop,\n\
+             3,cam-a,start,/video/a1.mp4\n",
+        );
+        assert_eq!(recovered.next_sequence, 4);
+        assert_eq!(recovered.orphaned.len(), 2);
+
+        let cam_a = recovered.orphaned.iter().find(|orphaned| orphaned.camera == "cam-a").unwrap();
+        assert_eq!(cam_a.sequence, 3);
+        assert_eq!(cam_a.file, Some(PathBuf::from("/video/a1.mp4")));
+
+        let cam_b = recovered.orphaned.iter().find(|orphaned| orphaned.camera == "cam-b").unwrap();
+        assert_eq!(cam_b.sequence, 1);
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        let recovered = recover("0,cam-a,start,/video/a.mp4\n\n1,cam-a,stop,\n");
+        assert_eq!(recovered.next_sequence, 2);
+        assert!(recovered.orphaned.is_empty());
+    }
+
+    #[test]
+    fn malformed_row_with_wrong_field_count_is_skipped() {
+        let recovered = recover("0,cam-a,start,/video/a.mp4,extra\n1,cam-b,start,/video/b.mp4\n");
+        assert_eq!(recovered.next_sequence, 2);
+        assert_eq!(recovered.orphaned.len(), 1);
+        assert_eq!(recovered.orphaned[0].camera, "cam-b");
+    }
+
+    #[test]
+    fn row_with_unparseable_sequence_is_skipped() {
+        let recovered = recover("not-a-number,cam-a,start,/video/a.mp4\n1,cam-b,start,/video/b.mp4\n");
+        assert_eq!(recovered.next_sequence, 2);
+        assert_eq!(recovered.orphaned.len(), 1);
+        assert_eq!(recovered.orphaned[0].camera, "cam-b");
+    }
+
+    #[test]
+    fn row_with_unknown_event_is_skipped_but_sequence_still_counts() {
+        let recovered = recover("0,cam-a,pause,/video/a.mp4\n1,cam-b,start,/video/b.mp4\n");
+        assert_eq!(recovered.next_sequence, 2);
+        assert_eq!(recovered.orphaned.len(), 1);
+        assert_eq!(recovered.orphaned[0].camera, "cam-b");
+    }
+
+    #[test]
+    fn a_later_start_overwrites_an_earlier_unmatched_start_for_the_same_camera() {
+        let recovered = recover("0,cam-a,start,/video/a0.mp4\n1,cam-a,start,/video/a1.mp4\n");
+        assert_eq!(recovered.orphaned.len(), 1);
+        assert_eq!(recovered.orphaned[0].sequence, 1);
+        assert_eq!(recovered.orphaned[0].file, Some(PathBuf::from("/video/a1.mp4")));
+    }
+}
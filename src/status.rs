@@ -0,0 +1,100 @@
+//! A Unix-socket status query interface and periodic tmpfs status file, so
+//! other onboard services (a supervisor, a health-check script) can read
+//! this process's state without going through a network-facing HTTP surface
+//! like [`crate::control_api`]. Reuses [`crate::control_api::render_status`]
+//! for the JSON payload itself, so every status surface agrees on what
+//! "status" means.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+
+use crate::control_api::render_status;
+use crate::metrics::Metrics;
+
+/// Parameters for the status socket/file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusConfig {
+    /// Whether to serve the status socket (and, if `file_path` is set, the
+    /// status file) at all. Off by default: not every deployment has
+    /// another onboard service polling this process's state.
+    pub enabled: bool,
+    /// Filesystem path of the Unix socket to bind. Removed and rebound on
+    /// startup if a stale socket file (left behind by an unclean shutdown)
+    /// already exists there.
+    pub socket_path: PathBuf,
+    /// If set, the same status JSON is also written here every
+    /// `file_interval`, for a reader that would rather poll a file (e.g. on
+    /// a tmpfs `/run` mount) than open a socket.
+    pub file_path: Option<PathBuf>,
+    /// How often the status file is rewritten. Only used if `file_path` is
+    /// set.
+    pub file_interval: Duration,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            socket_path: PathBuf::from("/run/px4-camera-trigger.sock"),
+            file_path: None,
+            file_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Binds `config.socket_path` and answers every connection with the current
+/// status JSON, then closes it - a query-response protocol, not a
+/// persistent connection, since a caller just wants one snapshot at a time.
+/// If `config.file_path` is set, also spawns a task that rewrites the same
+/// JSON there every `config.file_interval`.
+pub async fn spawn_server(config: &StatusConfig, metrics: Arc<Metrics>) -> Result<(), String> {
+    if config.socket_path.exists() {
+        std::fs::remove_file(&config.socket_path).map_err(|error| {
+            format!("failed to remove stale status socket {}: {error}", config.socket_path.display())
+        })?;
+    }
+
+    let listener = UnixListener::bind(&config.socket_path)
+        .map_err(|error| format!("failed to bind status socket {}: {error}", config.socket_path.display()))?;
+
+    info!("status socket listening on {}", config.socket_path.display());
+
+    let socket_metrics = Arc::clone(&metrics);
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    warn!("failed to accept status socket connection: {error}");
+                    continue;
+                }
+            };
+
+            let status = render_status(&socket_metrics);
+            if let Err(error) = socket.write_all(status.as_bytes()).await {
+                warn!("failed to write status socket response: {error}");
+            }
+        }
+    });
+
+    if let Some(file_path) = config.file_path.clone() {
+        let interval = config.file_interval;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let status = render_status(&metrics);
+                if let Err(error) = tokio::fs::write(&file_path, status).await {
+                    warn!("failed to write status file {}: {error}", file_path.display());
+                }
+            }
+        });
+    }
+
+    Ok(())
+}
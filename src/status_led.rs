@@ -0,0 +1,104 @@
+//! Drives a GPIO output line as a status indicator: solid while idle-ready,
+//! blinking while any camera is recording, fast-blinking once any camera has
+//! reported an error, so a field crew can tell from outside the airframe
+//! whether the recorder is actually running without opening a laptop.
+//!
+//! Polls [`crate::metrics::Metrics`] on a timer rather than being pushed
+//! updates - the same design [`crate::disk_space`] uses for its own
+//! background check - since the LED doesn't need to react any faster than a
+//! human eye can watch it anyway. The error state is sticky for the rest of
+//! the session, since [`crate::metrics::CameraMetrics::errors`] is a
+//! monotonic counter that's never reset.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gpio_cdev::{Chip, LineRequestFlags};
+use log::{error, info, warn};
+
+use crate::metrics::Metrics;
+
+/// How often the LED state is reevaluated and, if blinking, toggled.
+const TICK_INTERVAL: Duration = Duration::from_millis(125);
+
+/// Ticks the LED stays on, then off, while a recording is in progress -
+/// roughly a 1Hz blink at [`TICK_INTERVAL`].
+const RECORDING_BLINK_TICKS: u32 = 4;
+
+/// Ticks the LED stays on, then off, once an error has been recorded -
+/// roughly a 4Hz blink at [`TICK_INTERVAL`], to stand out from a normal
+/// recording blink at a glance.
+const ERROR_BLINK_TICKS: u32 = 1;
+
+/// Whether/how to drive a status LED. Off by default since not every rig has
+/// one wired up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusLedConfig {
+    pub enabled: bool,
+    /// GPIO chip carrying the LED line. Only used if `line_offset` is also
+    /// set.
+    pub gpiochip: Option<PathBuf>,
+    /// Line offset of the LED on `gpiochip`. Only used if `gpiochip` is also
+    /// set.
+    pub line_offset: Option<u32>,
+}
+
+impl Default for StatusLedConfig {
+    fn default() -> Self {
+        Self { enabled: false, gpiochip: None, line_offset: None }
+    }
+}
+
+/// Spawns a background task that drives the LED on `gpiochip`/`line_offset`
+/// for the process's lifetime, reflecting `metrics`' current state. Failing
+/// to request the line is logged and treated as non-fatal: a missing or
+/// already-claimed LED line shouldn't take capture down.
+pub fn spawn(gpiochip: PathBuf, line_offset: u32, metrics: Arc<Metrics>) {
+    tokio::spawn(async move {
+        let mut chip = match Chip::new(&gpiochip) {
+            Ok(chip) => chip,
+            Err(error) => {
+                error!("status led gpio chip {} is not accessible: {error}", gpiochip.display());
+                return;
+            }
+        };
+
+        let handle = match chip
+            .get_line(line_offset)
+            .map_err(|error| format!("line {line_offset} does not exist on {}: {error}", gpiochip.display()))
+            .and_then(|line| {
+                line.request(LineRequestFlags::OUTPUT, 0, "px4-camera-trigger-status-led")
+                    .map_err(|error| format!("line {line_offset} on {} is already in use: {error}", gpiochip.display()))
+            }) {
+            Ok(handle) => handle,
+            Err(error) => {
+                error!("failed to request status led line: {error}");
+                return;
+            }
+        };
+
+        info!("driving status led on line {line_offset} of {}", gpiochip.display());
+
+        let mut ticker = tokio::time::interval(TICK_INTERVAL);
+        let mut tick: u32 = 0;
+
+        loop {
+            ticker.tick().await;
+
+            let cameras = metrics.cameras();
+            let error = cameras.iter().any(|(_, camera)| camera.errors() > 0);
+            let recording = cameras.iter().any(|(_, camera)| camera.is_recording());
+
+            let blink_ticks =
+                if error { ERROR_BLINK_TICKS } else if recording { RECORDING_BLINK_TICKS } else { 0 };
+            let on = blink_ticks == 0 || (tick / blink_ticks) % 2 == 0;
+
+            if let Err(error) = handle.set_value(on as u8) {
+                warn!("failed to set status led value: {error}");
+            }
+
+            tick = tick.wrapping_add(1);
+        }
+    });
+}
@@ -0,0 +1,170 @@
+//! Periodic SMART (NVMe/USB-SATA) or eMMC/SD wear monitoring, so a
+//! recording medium nearing end of life shows up as a warning - in the log,
+//! over MAVLink, and in the session manifest - well before it fails
+//! outright mid-flight.
+//!
+//! See [`StorageHealthConfig`] and its use in [`crate::main::run`].
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use crate::manifest::Manifest;
+use crate::mavlink::MavlinkFeedback;
+use crate::metrics::Metrics;
+
+/// How often the recording medium's wear level is checked. Coarser than
+/// [`crate::thermal::CHECK_INTERVAL`]: wear changes slowly enough that a
+/// tighter poll would just be needless `smartctl` invocations.
+const CHECK_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Parameters for periodic storage health monitoring.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageHealthConfig {
+    /// Whether to poll the recording medium's health at all. Off by
+    /// default: it requires `device` to be set to a block device this
+    /// process may not have permission to query, and not every deployment
+    /// cares to track wear separately from just watching free space (see
+    /// [`crate::disk_space`]).
+    pub enabled: bool,
+    /// Block device backing the recording output directory, e.g.
+    /// `/dev/mmcblk0` or `/dev/nvme0n1`. There's no reliable way to derive
+    /// this from `output_dir` across the storage stacks this reads, so it
+    /// has to be configured explicitly.
+    pub device: Option<PathBuf>,
+    /// Warn once reported wear reaches this percentage of the medium's
+    /// rated life.
+    pub warn_percent_used: u8,
+}
+
+impl Default for StorageHealthConfig {
+    fn default() -> Self {
+        Self { enabled: false, device: None, warn_percent_used: 80 }
+    }
+}
+
+/// Spawns a background task that checks `config.device`'s wear level every
+/// [`CHECK_INTERVAL`], recording the latest reading into `metrics` and
+/// `manifest` and warning (in the log, and over MAVLink if
+/// `mavlink_feedback` is connected) the first time it crosses
+/// `config.warn_percent_used`.
+///
+/// Tries `smartctl` (NVMe/USB-SATA) first, falling back to the eMMC/SD
+/// sysfs `life_time` attribute, since a companion computer's recording
+/// medium is one or the other depending on the airframe. Does nothing
+/// beyond a debug log on a given tick if neither source is readable, since
+/// not every deployment target exposes either.
+///
+/// The warning fires once per episode, the same debounce
+/// [`crate::thermal::spawn_monitor`] uses: it's not re-logged on every tick
+/// while the medium stays worn, but fires again if the reading drops back
+/// under the threshold (e.g. a swapped card) and later crosses it again.
+pub fn spawn_monitor(
+    config: StorageHealthConfig,
+    metrics: Arc<Metrics>,
+    manifest: Arc<Manifest>,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+) {
+    let Some(device) = config.device.clone() else {
+        warn!("storage health monitoring is enabled but no device is configured");
+        return;
+    };
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        let mut warned = false;
+
+        loop {
+            ticker.tick().await;
+
+            let Some(percent_used) = read_percent_used(&device) else {
+                debug!("failed to read wear level for {}", device.display());
+                continue;
+            };
+
+            metrics.record_storage_health(percent_used);
+            manifest.record_storage_health(&device.display().to_string(), percent_used);
+
+            if percent_used >= config.warn_percent_used {
+                if !warned {
+                    let message = format!(
+                        "{} is at {percent_used}% of its rated life, at or above the {}% warning threshold",
+                        device.display(),
+                        config.warn_percent_used
+                    );
+                    warn!("{message}");
+                    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                        mavlink_feedback.send_warning_statustext(&message);
+                    }
+                    warned = true;
+                }
+            } else {
+                warned = false;
+            }
+        }
+    });
+}
+
+/// Reads `device`'s wear level as a percentage of its rated life used,
+/// trying `smartctl` (NVMe/USB-SATA) first and falling back to the eMMC/SD
+/// sysfs `life_time` attribute.
+fn read_percent_used(device: &Path) -> Option<u8> {
+    read_smart_percentage_used(device).or_else(|| read_emmc_life_time_percent(device))
+}
+
+/// Shells out to `smartctl -A <device>` and parses NVMe's `Percentage
+/// Used` attribute. Returns `None` if `smartctl` isn't available, `device`
+/// isn't NVMe, or the output isn't in the expected shape. Doesn't check
+/// `smartctl`'s exit status: it returns a bitmask of SMART conditions
+/// rather than a plain success/failure code, so a nonzero status here
+/// doesn't necessarily mean the attribute read failed.
+fn read_smart_percentage_used(device: &Path) -> Option<u8> {
+    let output = match Command::new("smartctl").arg("-A").arg(device).output() {
+        Ok(output) => output,
+        Err(error) => {
+            debug!("failed to run smartctl -A {}: {error}", device.display());
+            return None;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        if let Some(value) = line.strip_prefix("Percentage Used:") {
+            if let Ok(percent) = value.trim().trim_end_matches('%').parse() {
+                return Some(percent);
+            }
+        }
+    }
+
+    debug!("no Percentage Used attribute in smartctl output for {}", device.display());
+    None
+}
+
+/// Reads the eMMC/SD `life_time` sysfs attribute (JEDEC
+/// `EXT_CSD_DEVICE_LIFE_TIME_EST_TYP_A`/`_B`), reported as two
+/// space-separated hex values from `0x01` (0-10% used) to `0x0b` (exceeded
+/// its estimated life), each in 10% increments. Takes the worse of the two
+/// estimation types and converts it to a percentage. Returns `None` if
+/// `device` isn't an eMMC/SD device or the attribute can't be read/parsed.
+fn read_emmc_life_time_percent(device: &Path) -> Option<u8> {
+    let device_name = device.file_name()?.to_str()?;
+    let life_time_path = PathBuf::from(format!("/sys/block/{device_name}/device/life_time"));
+
+    let raw = match std::fs::read_to_string(&life_time_path) {
+        Ok(raw) => raw,
+        Err(error) => {
+            debug!("failed to read {}: {error}", life_time_path.display());
+            return None;
+        }
+    };
+
+    let worst = raw
+        .split_whitespace()
+        .filter_map(|field| field.strip_prefix("0x").and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+        .max()?;
+
+    Some((worst.saturating_sub(1) * 10).min(100))
+}
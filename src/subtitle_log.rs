@@ -0,0 +1,105 @@
+//! Writes a `.srt` sidecar alongside each recording, with periodic
+//! GPS/altitude/heading cues sampled from MAVLink, so a reviewer can see
+//! where the aircraft was for any given frame in a standard video player
+//! without cross-referencing the flight log separately.
+//!
+//! Off by default, and only produces cues once `mavlink` is connected - see
+//! [`crate::mavlink::MavlinkFeedback::latest_position`].
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::mavlink::MavlinkFeedback;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleConfig {
+    /// Off by default: most deployments don't need a per-frame telemetry
+    /// overlay, and it only has anything to write once `mavlink` is
+    /// connected.
+    pub enabled: bool,
+    /// How often a new cue is written.
+    pub interval_secs: f64,
+}
+
+impl Default for SubtitleConfig {
+    fn default() -> Self {
+        Self { enabled: false, interval_secs: 1.0 }
+    }
+}
+
+/// Spawns the background task writing cues for one recording at `video_path`
+/// (the `.srt` sidecar is `video_path` with its extension replaced), and
+/// returns a handle the caller stores and sets on the matching `Stop` to
+/// close it out. A fresh task (and fresh sidecar) is expected for every
+/// `Start`, the same "one task per armed recording" shape as
+/// [`crate::intervalometer::spawn`].
+pub fn spawn(config: SubtitleConfig, video_path: &Path, mavlink_feedback: Arc<Option<MavlinkFeedback>>) -> Arc<AtomicBool> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let task_stop = Arc::clone(&stop);
+
+    let srt_path = video_path.with_extension("srt");
+    let file = match File::create(&srt_path) {
+        Ok(file) => file,
+        Err(error) => {
+            warn!("failed to create subtitle sidecar {}: {error}", srt_path.display());
+            return stop;
+        }
+    };
+
+    tokio::spawn(async move {
+        let Some(mavlink_feedback) = mavlink_feedback.as_ref() else {
+            warn!("telemetry subtitles are enabled but mavlink isn't connected, not writing {}", srt_path.display());
+            return;
+        };
+
+        let mut file = file;
+        let cue_duration = Duration::from_secs_f64(config.interval_secs.max(0.1));
+        let mut ticker = tokio::time::interval(cue_duration);
+        let mut index = 1u32;
+        let mut cue_start = Duration::ZERO;
+
+        loop {
+            ticker.tick().await;
+            if task_stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let position = mavlink_feedback.latest_position();
+            let cue_end = cue_start + cue_duration;
+            let cue = format!(
+                "{index}\n{} --> {}\nLat: {:.6}  Lon: {:.6}  Alt: {:.1} m  Heading: {:.0}\u{b0}\n\n",
+                format_timestamp(cue_start),
+                format_timestamp(cue_end),
+                position.lat as f64 / 1e7,
+                position.lon as f64 / 1e7,
+                position.alt as f64 / 1000.0,
+                position.yaw.to_degrees().rem_euclid(360.0),
+            );
+            if let Err(error) = file.write_all(cue.as_bytes()) {
+                warn!("failed to write subtitle cue to {}: {error}", srt_path.display());
+                break;
+            }
+
+            index += 1;
+            cue_start = cue_end;
+        }
+    });
+
+    stop
+}
+
+/// Formats `elapsed` as an SRT timestamp: `HH:MM:SS,mmm`.
+fn format_timestamp(elapsed: Duration) -> String {
+    let total_millis = elapsed.as_millis();
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis / 60_000) % 60;
+    let seconds = (total_millis / 1_000) % 60;
+    let millis = total_millis % 1_000;
+    format!("{hours:02}:{minutes:02}:{seconds:02},{millis:03}")
+}
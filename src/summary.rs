@@ -0,0 +1,70 @@
+//! Renders a human-readable end-of-session digest - trigger/recording
+//! counts, footage duration, dropped frames, disk used and errors, per
+//! camera - to the journal and to
+//! `<output_dir>/<flight_session>/summary.txt`, once
+//! [`crate::session::Session::shutdown`] has drained every camera's
+//! transition worker, for whoever files the flight away to skim without
+//! digging through the trigger event log.
+//!
+//! Distinct from [`crate::metrics`]'s live Prometheus counters (scraped
+//! continuously during a flight) and [`crate::manifest`]'s per-file ledger
+//! (machine-readable, for transfer verification): this is a single
+//! plain-text snapshot taken once, at the end.
+
+use std::path::Path;
+
+use log::{info, warn};
+
+use crate::metrics::Metrics;
+
+/// Renders the summary for `metrics` (every camera's counters as of the
+/// call) and `trigger_count`, logs it, and writes it to
+/// `<output_dir>/<flight_session>/summary.txt`.
+pub fn write(output_dir: &Path, flight_session: &str, trigger_count: u64, metrics: &Metrics) {
+    let text = render(flight_session, trigger_count, metrics);
+
+    info!("session summary:\n{text}");
+
+    let path = output_dir.join(flight_session).join("summary.txt");
+    if let Err(error) = std::fs::write(&path, &text) {
+        warn!("failed to write session summary to {}: {error}", path.display());
+    }
+}
+
+fn render(flight_session: &str, trigger_count: u64, metrics: &Metrics) -> String {
+    let mut text = String::new();
+    text.push_str(&format!("flight session: {flight_session}\n"));
+    text.push_str(&format!("trigger events accepted: {trigger_count}\n"));
+
+    for (name, camera) in metrics.cameras() {
+        let recorded_seconds = camera.total_recorded_ns() as f64 / 1_000_000_000.0;
+        let disk_used_bytes = directory_size(&camera.output_dir().join(flight_session)).unwrap_or(0);
+        text.push_str(&format!(
+            "camera {name}: {} recording(s), {recorded_seconds:.1}s of footage, {} frame(s) dropped, \
+             {disk_used_bytes} byte(s) on disk, {} error(s)\n",
+            camera.recordings_stopped(),
+            camera.dropped_frames(),
+            camera.errors(),
+        ));
+    }
+
+    text
+}
+
+/// Total size in bytes of every file under `path`, recursing into
+/// subdirectories. Mirrors [`crate::retention`]'s helper of the same name;
+/// kept separate since it's a handful of lines and the two modules
+/// otherwise have nothing to do with each other.
+fn directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use gpio_cdev::Chip;
+use gpiocdev::tokio::AsyncRequest;
+use log::{info, warn};
+use tokio::sync::mpsc::UnboundedSender;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LineSnapshot {
+    consumer: Option<String>,
+    requested: bool,
+}
+
+/// Watches the trigger line for external requests, release or
+/// reconfiguration, and re-requests it if another process takes ownership.
+///
+/// `gpio-cdev`'s chardev v1 ABI doesn't expose a line-info *watch* the way
+/// libgpiod's `watch_line_info`/`wait_info_event` do, so this approximates
+/// one by polling the line's info on an interval and diffing it against the
+/// last observed snapshot. The polling itself still goes through `gpio_cdev`
+/// v1 (it's read-only line-info lookups, which v1 exposes just as well as
+/// v2), but the re-request on recovery goes through the v2 uAPI, same as the
+/// initial request, so a recovered line keeps its debounce/event-clock
+/// settings. See [`crate::trigger_source::GpioTriggerSource`].
+pub struct LineSupervisor {
+    gpiochip: PathBuf,
+    line_offset: u32,
+    consumer_label: String,
+    /// Re-requested with every recovery, so a dropped-and-reacquired line
+    /// keeps whatever bias/active-low/debounce/event-clock settings it was
+    /// originally requested with. See
+    /// [`crate::config::Config::trigger_line_config`].
+    line_config: gpiocdev::line::Config,
+}
+
+impl LineSupervisor {
+    pub fn new(
+        gpiochip: PathBuf,
+        line_offset: u32,
+        consumer_label: String,
+        line_config: gpiocdev::line::Config,
+    ) -> Self {
+        Self {
+            gpiochip,
+            line_offset,
+            consumer_label,
+            line_config,
+        }
+    }
+
+    /// Spawns the supervisory task. Whenever it successfully re-requests the
+    /// line after losing ownership, the new event stream is sent down
+    /// `recovered` so the caller can swap it into its event loop.
+    pub fn spawn(self, recovered: UnboundedSender<AsyncRequest>) {
+        tokio::spawn(async move {
+            let mut last = self.read_snapshot();
+
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                let current = self.read_snapshot();
+                if current == last {
+                    continue;
+                }
+
+                info!(
+                    "trigger line {} on {} changed: consumer {:?} -> {:?}, requested {} -> {}",
+                    self.line_offset,
+                    self.gpiochip.display(),
+                    last.consumer,
+                    current.consumer,
+                    last.requested,
+                    current.requested,
+                );
+
+                let owned_by_us = current.consumer.as_deref() == Some(self.consumer_label.as_str());
+
+                if !owned_by_us {
+                    warn!(
+                        "lost ownership of trigger line {} on {} to {:?}, re-requesting",
+                        self.line_offset,
+                        self.gpiochip.display(),
+                        current.consumer
+                    );
+
+                    match self.reacquire().await {
+                        Some(handle) => {
+                            if recovered.send(handle).is_err() {
+                                // Event loop is gone, nothing left to supervise.
+                                return;
+                            }
+                        }
+                        None => return,
+                    }
+                }
+
+                last = self.read_snapshot();
+            }
+        });
+    }
+
+    /// Retries [`LineSupervisor::try_request`] with exponential backoff
+    /// until it succeeds or the sender side of `recovered` is dropped.
+    async fn reacquire(&self) -> Option<AsyncRequest> {
+        let mut backoff = INITIAL_BACKOFF;
+
+        loop {
+            match self.try_request() {
+                Ok(handle) => {
+                    info!(
+                        "re-acquired trigger line {} on {}",
+                        self.line_offset,
+                        self.gpiochip.display()
+                    );
+                    return Some(handle);
+                }
+                Err(error) => {
+                    warn!(
+                        "failed to re-request trigger line {} on {} ({error}), retrying in {backoff:?}",
+                        self.line_offset,
+                        self.gpiochip.display()
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    fn try_request(&self) -> Result<AsyncRequest, String> {
+        let request = gpiocdev::Request::builder()
+            .on_chip(&self.gpiochip)
+            .with_consumer(&self.consumer_label)
+            .with_line(self.line_offset)
+            .with_line_config(&self.line_config)
+            .request()
+            .map_err(|error| error.to_string())?;
+
+        AsyncRequest::new(request).map_err(|error| error.to_string())
+    }
+
+    fn read_snapshot(&self) -> LineSnapshot {
+        Chip::new(&self.gpiochip)
+            .ok()
+            .and_then(|mut chip| chip.get_line(self.line_offset).ok())
+            .and_then(|line| line.info().ok())
+            .map(|info| LineSnapshot {
+                consumer: (!info.consumer().is_empty()).then(|| info.consumer().to_string()),
+                requested: info.is_used(),
+            })
+            .unwrap_or(LineSnapshot {
+                consumer: None,
+                requested: false,
+            })
+    }
+}
@@ -0,0 +1,195 @@
+//! Periodic SoC temperature and Raspberry Pi throttling-flag monitoring, so
+//! overheating - a common cause of mid-flight frame drops once the encoder
+//! starts fighting for CPU with a throttled clock - shows up as a warning
+//! well before it takes a camera down.
+//!
+//! See [`ThermalConfig`] and its use in [`crate::main::run`].
+
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, error, warn};
+use tokio::sync::mpsc;
+
+use crate::mavlink::MavlinkFeedback;
+use crate::metrics::Metrics;
+
+/// How often temperature and throttling flags are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Where the SoC reports its temperature, in millidegrees Celsius, on
+/// Raspberry Pi and most other Linux SBCs.
+const THERMAL_ZONE_PATH: &str = "/sys/class/thermal/thermal_zone0/temp";
+
+/// Bits `vcgencmd get_throttled` sets (in any of the four nibbles) when
+/// *any* throttling condition - current or since-boot - is active. See
+/// `vcgencmd`'s own documentation for the full bit layout; we only care
+/// whether the SoC has ever had to intervene, not which specific condition.
+const THROTTLED_MASK: u32 = 0x000F_000F;
+
+/// Bit 0 of `vcgencmd get_throttled`: the 5V rail is *currently* below the
+/// Pi's brownout threshold, as opposed to the "has happened since boot"
+/// bits higher up in the word. This is the one condition specific enough
+/// (as opposed to `THROTTLED_MASK`'s catch-all) to warrant its own
+/// dedicated warning and, if configured, an early finalize - a capped
+/// clock speed loses frames, but a brownout can take the SBC down entirely
+/// before the current segment is closed out.
+const UNDERVOLTAGE_BIT: u32 = 0x1;
+
+/// Parameters for periodic thermal monitoring.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalConfig {
+    /// Whether to poll temperature/throttling at all. On by default: the
+    /// checks are cheap and this is exactly the kind of problem that's easy
+    /// to miss until a flight review.
+    pub enabled: bool,
+    /// Log (and, if MAVLink is enabled, send a `STATUSTEXT`) warning once
+    /// the SoC temperature reaches this many degrees Celsius.
+    pub warn_temp_celsius: f32,
+    /// Stop the active recording as soon as [`UNDERVOLTAGE_BIT`] is seen, so
+    /// the current segment is finalized before a brownout takes the
+    /// companion computer down mid-write. Off by default: some airframes
+    /// would rather keep recording through a brief sag than lose the tail
+    /// end of a segment to a false positive.
+    pub finalize_on_undervoltage: bool,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self { enabled: true, warn_temp_celsius: 75.0, finalize_on_undervoltage: false }
+    }
+}
+
+/// Spawns a background task that checks SoC temperature and throttling
+/// flags every [`CHECK_INTERVAL`], recording the latest reading into
+/// `metrics` and warning (in the log, and over MAVLink if `mavlink_feedback`
+/// is connected) the first time temperature crosses `config.warn_temp_celsius`
+/// or throttling becomes active. If `config.finalize_on_undervoltage` is
+/// set, one message is also sent on `finalize_on_undervoltage` the first
+/// time [`UNDERVOLTAGE_BIT`] is seen, for [`crate::main::run`]'s event loop
+/// to stop the active recording early.
+///
+/// Each warning fires once per episode, the same debounce
+/// [`crate::disk_space::spawn_monitor`] uses for low disk space: it's not
+/// re-logged on every tick while the condition persists, but fires again if
+/// it clears and then recurs.
+pub fn spawn_monitor(
+    config: ThermalConfig,
+    metrics: Arc<Metrics>,
+    mavlink_feedback: Arc<Option<MavlinkFeedback>>,
+    finalize_on_undervoltage: mpsc::UnboundedSender<()>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        let mut hot = false;
+        let mut throttled_warned = false;
+        let mut undervoltage_warned = false;
+
+        loop {
+            ticker.tick().await;
+
+            let temp_celsius = match read_soc_temp_celsius() {
+                Ok(temp_celsius) => temp_celsius,
+                Err(error) => {
+                    debug!("failed to read SoC temperature: {error}");
+                    continue;
+                }
+            };
+            let throttled_bits = read_throttled_bits().unwrap_or(0);
+            let throttled = throttled_bits & THROTTLED_MASK != 0;
+            let undervoltage = throttled_bits & UNDERVOLTAGE_BIT != 0;
+
+            metrics.record_thermal((temp_celsius * 1000.0) as i64, throttled);
+
+            if temp_celsius >= config.warn_temp_celsius {
+                if !hot {
+                    let message = format!(
+                        "SoC temperature {temp_celsius:.1}C at or above the {:.1}C warning threshold",
+                        config.warn_temp_celsius
+                    );
+                    warn!("{message}");
+                    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                        mavlink_feedback.send_warning_statustext(&message);
+                    }
+                    hot = true;
+                }
+            } else {
+                hot = false;
+            }
+
+            if throttled {
+                if !throttled_warned {
+                    let message = "Pi firmware reports active throttling (frequency capping or undervoltage)";
+                    warn!("{message}");
+                    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                        mavlink_feedback.send_warning_statustext(message);
+                    }
+                    throttled_warned = true;
+                }
+            } else {
+                throttled_warned = false;
+            }
+
+            if undervoltage {
+                if !undervoltage_warned {
+                    let message = "Pi firmware reports the 5V rail is currently below the brownout threshold";
+                    error!("{message}");
+                    if let Some(mavlink_feedback) = mavlink_feedback.as_ref() {
+                        mavlink_feedback.send_error_statustext(message);
+                    }
+                    if config.finalize_on_undervoltage {
+                        warn!("finalizing the active recording early to protect it from an imminent power loss");
+                        let _ = finalize_on_undervoltage.send(());
+                    }
+                    undervoltage_warned = true;
+                }
+            } else {
+                undervoltage_warned = false;
+            }
+        }
+    });
+}
+
+/// Reads [`THERMAL_ZONE_PATH`] and converts its millidegree reading to
+/// degrees Celsius.
+fn read_soc_temp_celsius() -> Result<f32, String> {
+    let raw = std::fs::read_to_string(THERMAL_ZONE_PATH)
+        .map_err(|error| format!("failed to read {THERMAL_ZONE_PATH}: {error}"))?;
+    let millicelsius: i64 = raw
+        .trim()
+        .parse()
+        .map_err(|error| format!("unexpected contents of {THERMAL_ZONE_PATH} ({raw:?}): {error}"))?;
+    Ok(millicelsius as f32 / 1000.0)
+}
+
+/// Shells out to `vcgencmd get_throttled` and returns its raw bitfield.
+/// Returns `None` (rather than an error) if `vcgencmd` isn't available,
+/// since not every deployment target is a Raspberry Pi and this is a
+/// secondary signal on top of the temperature reading.
+fn read_throttled_bits() -> Option<u32> {
+    let output = match Command::new("vcgencmd").arg("get_throttled").output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            debug!("vcgencmd get_throttled exited with {}", output.status);
+            return None;
+        }
+        Err(error) => {
+            debug!("failed to run vcgencmd get_throttled: {error}");
+            return None;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(hex) = stdout.trim().strip_prefix("throttled=0x") else {
+        debug!("unexpected vcgencmd get_throttled output: {stdout:?}");
+        return None;
+    };
+    match u32::from_str_radix(hex, 16) {
+        Ok(bits) => Some(bits),
+        Err(error) => {
+            debug!("failed to parse vcgencmd get_throttled output {stdout:?}: {error}");
+            None
+        }
+    }
+}
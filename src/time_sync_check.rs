@@ -0,0 +1,123 @@
+//! One-shot pre-flight check that the system realtime clock is actually
+//! synchronized before the first recording starts, since [`crate::geotag`]
+//! and [`crate::trigger_log`]'s reported capture times are only as good as
+//! the `CLOCK_REALTIME`/Unix-epoch mapping [`crate::clock::RealtimeClock`]
+//! bridges `CLOCK_MONOTONIC` to - an unsynchronized clock means every
+//! reported time (and any geotag derived from it) is offset from true time
+//! by however far the clock has drifted since it was last set.
+//!
+//! Checked via `timedatectl`, which reports whether *any* time sync service
+//! (systemd-timesyncd or chrony, both of which register with
+//! `org.freedesktop.timedate1`) considers itself synchronized, rather than
+//! shelling out to a specific one - a flight controller's onboard computer
+//! might run either. `chronyc tracking` is additionally queried, best
+//! effort, for a human-readable offset to put in the warning.
+//!
+//! See [`TimeSyncConfig`] and its use in [`crate::main::run`].
+
+use std::process::Command;
+
+use log::{debug, info, warn};
+
+use crate::manifest::Manifest;
+use crate::mavlink::MavlinkFeedback;
+
+/// Parameters for the pre-flight clock-sync check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSyncConfig {
+    /// Whether to run the check at all. On by default: an unsynchronized
+    /// clock silently poisons every timestamp this service reports, and
+    /// the check itself is a single cheap subprocess call.
+    pub enabled: bool,
+}
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Runs the check once, logging the result and - if the clock isn't
+/// synchronized - sending a warning `STATUSTEXT` over `mavlink_feedback` (if
+/// connected) and annotating `manifest` so the ground pipeline can flag the
+/// session's geotags as suspect without needing the clock state, which is no
+/// longer available once the flight is over.
+///
+/// A no-op if `config.enabled` is off, or if `timedatectl` isn't available
+/// (e.g. a non-systemd image) - the check degrades to "unknown" rather than
+/// failing startup, since it's advisory, not a precondition for recording.
+pub fn run(config: &TimeSyncConfig, mavlink_feedback: &Option<MavlinkFeedback>, manifest: &Manifest) {
+    if !config.enabled {
+        return;
+    }
+
+    let Some(synchronized) = read_ntp_synchronized() else {
+        debug!("could not determine system clock sync status (timedatectl unavailable?); skipping pre-flight check");
+        return;
+    };
+
+    if synchronized {
+        info!("system clock is synchronized{}", offset_suffix());
+        return;
+    }
+
+    let message = format!(
+        "system clock is NOT synchronized{} - reported timestamps and geotags for this session may be offset from true time",
+        offset_suffix()
+    );
+    warn!("{message}");
+    if let Some(mavlink_feedback) = mavlink_feedback {
+        mavlink_feedback.send_warning_statustext(&message);
+    }
+    manifest.record_clock_sync(false, &message);
+}
+
+/// Shells out to `timedatectl show -p NTPSynchronized --value`, returning
+/// `Some(true)`/`Some(false)` for its `yes`/`no` answer, or `None` if
+/// `timedatectl` isn't installed or its output isn't one of those two.
+fn read_ntp_synchronized() -> Option<bool> {
+    let output = match Command::new("timedatectl").args(["show", "-p", "NTPSynchronized", "--value"]).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            debug!("timedatectl exited with {}", output.status);
+            return None;
+        }
+        Err(error) => {
+            debug!("failed to run timedatectl: {error}");
+            return None;
+        }
+    };
+
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        other => {
+            debug!("unexpected timedatectl output: {other:?}");
+            None
+        }
+    }
+}
+
+/// Best-effort `" (chrony reports N seconds fast/slow of NTP time)"` suffix
+/// for a log/`STATUSTEXT` message, parsed out of `chronyc tracking`'s
+/// `System time` line. Empty if `chronyc` isn't installed, isn't running
+/// chronyd, or its output doesn't parse - this is a nice-to-have detail, not
+/// something the check depends on.
+fn offset_suffix() -> String {
+    let Ok(output) = Command::new("chronyc").arg("tracking").output() else {
+        return String::new();
+    };
+    if !output.status.success() {
+        return String::new();
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(line) = stdout.lines().find(|line| line.starts_with("System time")) else {
+        return String::new();
+    };
+    let Some((_, detail)) = line.split_once(':') else {
+        return String::new();
+    };
+
+    format!(" (chrony reports {})", detail.trim())
+}
@@ -0,0 +1,416 @@
+use std::time::Duration;
+
+use gpio_cdev::EventType;
+use log::{debug, warn};
+
+/// The action a caller should take in response to a completed trigger pulse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// A long pulse while video wasn't already recording: start it.
+    Start,
+    /// A long pulse while video was already recording: stop it.
+    Stop,
+    /// A short pulse: capture a single still image.
+    CaptureStill,
+}
+
+/// Pulse-width thresholds for decoding an RC/PWM signal fed into the trigger
+/// line instead of a clean logic edge - see [`TriggerStateMachine::new`].
+/// `stop_below` must be less than `record_above`; the gap between them is
+/// the hysteresis band that keeps a pulse width hovering near either
+/// threshold from chattering between [`Transition::Start`] and
+/// [`Transition::Stop`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PwmThresholds {
+    /// A pulse at or above this width is decoded as "record".
+    pub record_above: Duration,
+    /// A pulse at or below this width is decoded as "stop". Anything between
+    /// this and `record_above` holds whatever state was last decoded.
+    pub stop_below: Duration,
+}
+
+/// A [`Transition`] along with the context [`crate::trigger_log::TriggerLog`]
+/// needs to record it, bundled together so each camera's transition channel
+/// carries one value instead of three.
+#[derive(Debug, Clone, Copy)]
+pub struct TriggerEvent {
+    /// Monotonically increasing across every accepted event this run, shared
+    /// by all cameras, so rows from the same physical trigger can be matched
+    /// up across a multi-camera session's log.
+    pub sequence: u64,
+    /// The GPIO edge's hardware timestamp that completed this event's pulse,
+    /// in nanoseconds; the same value passed to [`TriggerStateMachine::on_event`].
+    pub gpio_timestamp_ns: u64,
+    pub transition: Transition,
+}
+
+/// Whether the trigger line is currently mid-pulse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PulseState {
+    Idle,
+    /// A pulse started at this (already-polarity-normalized) timestamp and
+    /// hasn't ended yet.
+    Pulsing { started_at: u64 },
+}
+
+/// Decodes GPIO edges on the trigger line into recording commands, based on
+/// how long the line was held.
+///
+/// The line is requested with both-edge detection (see
+/// [`crate::main::run`] and [`crate::supervisor::LineSupervisor`]) so both
+/// halves of a pulse reach this state machine. A command is only decided
+/// once a pulse completes (its matching end edge arrives), since a short
+/// pulse and a long pulse mean different things: PX4 pulses briefly for a
+/// still-capture shot and holds the line for the duration of a video
+/// recording, toggling it on the first long pulse and off on the next.
+/// Repeated edges of the same polarity mid-pulse are ignored, and edges that
+/// arrive less than `min_pulse_width` after the previous one are treated as
+/// contact bounce and dropped.
+///
+/// Some airframes wire the trigger line inverted (idle high, pulsed low for
+/// each shot); `invert_polarity` swaps which edge is treated as the start of
+/// a pulse and which as its end, without the caller needing to know which
+/// wiring is in use.
+///
+/// If `pwm` is set, a completed pulse is instead decoded as an RC/PWM
+/// signal: its width is compared against [`PwmThresholds`] rather than
+/// `short_pulse_max`, and the result maps directly to "recording should be
+/// on" or "off" instead of toggling on each pulse - see
+/// [`TriggerStateMachine::decode_pwm`].
+pub struct TriggerStateMachine {
+    pulse_state: PulseState,
+    video_recording: bool,
+    min_pulse_width: Duration,
+    short_pulse_max: Duration,
+    invert_polarity: bool,
+    pwm: Option<PwmThresholds>,
+    last_edge_timestamp: Option<u64>,
+    /// Running total of edges dropped for arriving under `min_pulse_width`
+    /// after the previous one. Surfaced via [`TriggerStateMachine::glitch_count`]
+    /// so a noisy trigger line (e.g. picking up ESC wiring interference)
+    /// shows up in logs/metrics instead of silently vanishing into `debug!`.
+    glitch_count: u64,
+}
+
+impl TriggerStateMachine {
+    /// `min_pulse_width` is the shortest gap between edges that is trusted
+    /// as a real edge rather than bounce. `short_pulse_max` is the longest a
+    /// pulse can be while still being decoded as a still-capture command
+    /// rather than a video start/stop toggle. `invert_polarity` treats a
+    /// falling edge as the start of a pulse and a rising edge as its end,
+    /// for airframes with an inverted trigger line. `pwm` switches pulse
+    /// decoding from the still/toggle logic above to RC/PWM range decoding;
+    /// see [`PwmThresholds`].
+    pub fn new(
+        min_pulse_width: Duration,
+        short_pulse_max: Duration,
+        invert_polarity: bool,
+        pwm: Option<PwmThresholds>,
+    ) -> Self {
+        Self {
+            pulse_state: PulseState::Idle,
+            video_recording: false,
+            min_pulse_width,
+            short_pulse_max,
+            invert_polarity,
+            pwm,
+            last_edge_timestamp: None,
+            glitch_count: 0,
+        }
+    }
+
+    /// Total number of edges rejected as bounce/glitch so far.
+    pub fn glitch_count(&self) -> u64 {
+        self.glitch_count
+    }
+
+    /// Whether video is currently considered to be recording.
+    pub fn video_recording(&self) -> bool {
+        self.video_recording
+    }
+
+    /// Forces the video toggle to `recording`, without going through pulse
+    /// decoding, so an out-of-band control path (see [`crate::control_api`])
+    /// can start/stop recording and have the next physical pulse still
+    /// toggle in the right direction afterward.
+    pub fn set_video_recording(&mut self, recording: bool) {
+        self.video_recording = recording;
+    }
+
+    /// Updates the debounce/pulse-classification thresholds in place, e.g.
+    /// for a config reload (see [`crate::main::reload_config`]) without
+    /// dropping the GPIO subscription. Takes effect on the next edge; an
+    /// in-progress pulse's `started_at` isn't affected.
+    pub fn set_min_pulse_width(&mut self, min_pulse_width: Duration) {
+        self.min_pulse_width = min_pulse_width;
+    }
+
+    /// See [`Self::set_min_pulse_width`].
+    pub fn set_short_pulse_max(&mut self, short_pulse_max: Duration) {
+        self.short_pulse_max = short_pulse_max;
+    }
+
+    /// See [`Self::set_min_pulse_width`].
+    pub fn set_invert_polarity(&mut self, invert_polarity: bool) {
+        self.invert_polarity = invert_polarity;
+    }
+
+    /// See [`Self::set_min_pulse_width`].
+    pub fn set_pwm(&mut self, pwm: Option<PwmThresholds>) {
+        self.pwm = pwm;
+    }
+
+    /// Feeds a hardware edge into the state machine, returning the command
+    /// to act on, if the edge is both debounced and completes a pulse.
+    /// `timestamp` is the edge's hardware timestamp in nanoseconds.
+    pub fn on_event(&mut self, timestamp: u64, event_type: EventType) -> Option<Transition> {
+        if let Some(last_edge_timestamp) = self.last_edge_timestamp {
+            let elapsed = Duration::from_nanos(timestamp.saturating_sub(last_edge_timestamp));
+
+            if elapsed < self.min_pulse_width {
+                self.glitch_count += 1;
+                debug!(
+                    "ignoring bouncy edge at {timestamp} ({elapsed:?} since last edge, \
+                     minimum pulse width is {:?}, {} rejected so far)",
+                    self.min_pulse_width, self.glitch_count
+                );
+                // A steady trickle of individually-harmless glitches can
+                // still mean flaky wiring; surface it at a visible level
+                // every so often instead of only in `debug!` output.
+                if self.glitch_count % 10 == 0 {
+                    warn!(
+                        "trigger line has rejected {} glitches so far (min pulse width {:?})",
+                        self.glitch_count, self.min_pulse_width
+                    );
+                }
+                return None;
+            }
+        }
+        self.last_edge_timestamp = Some(timestamp);
+
+        let event_type = if self.invert_polarity {
+            match event_type {
+                EventType::RisingEdge => EventType::FallingEdge,
+                EventType::FallingEdge => EventType::RisingEdge,
+            }
+        } else {
+            event_type
+        };
+
+        match (event_type, self.pulse_state) {
+            (EventType::RisingEdge, PulseState::Idle) => {
+                self.pulse_state = PulseState::Pulsing { started_at: timestamp };
+                None
+            }
+            (EventType::FallingEdge, PulseState::Pulsing { started_at }) => {
+                self.pulse_state = PulseState::Idle;
+
+                let width = Duration::from_nanos(timestamp.saturating_sub(started_at));
+                if let Some(pwm) = self.pwm {
+                    self.decode_pwm(width, pwm)
+                } else if width < self.short_pulse_max {
+                    debug!("short pulse ({width:?}) at {timestamp}, decoding as still capture");
+                    Some(Transition::CaptureStill)
+                } else if self.video_recording {
+                    self.video_recording = false;
+                    Some(Transition::Stop)
+                } else {
+                    self.video_recording = true;
+                    Some(Transition::Start)
+                }
+            }
+            (EventType::RisingEdge, PulseState::Pulsing { .. }) => {
+                debug!("rising edge at {timestamp} mid-pulse, ignoring");
+                None
+            }
+            (EventType::FallingEdge, PulseState::Idle) => {
+                debug!("falling edge at {timestamp} while idle, ignoring");
+                None
+            }
+        }
+    }
+
+    /// Maps a completed pulse's `width` to a recording state via `thresholds`,
+    /// only emitting a [`Transition`] when that state actually changes -
+    /// unlike the still/toggle logic in [`Self::on_event`], a PWM pulse
+    /// arrives continuously (an RC channel repeats at some fixed rate
+    /// regardless of stick position), so every pulse re-affirming the
+    /// current state must be a no-op rather than a repeated toggle. A width
+    /// inside the hysteresis band between `thresholds.stop_below` and
+    /// `thresholds.record_above` holds whatever state was last decoded,
+    /// so a signal hovering near either threshold doesn't chatter between
+    /// [`Transition::Start`] and [`Transition::Stop`].
+    fn decode_pwm(&mut self, width: Duration, thresholds: PwmThresholds) -> Option<Transition> {
+        if width >= thresholds.record_above && !self.video_recording {
+            self.video_recording = true;
+            Some(Transition::Start)
+        } else if width <= thresholds.stop_below && self.video_recording {
+            self.video_recording = false;
+            Some(Transition::Stop)
+        } else {
+            None
+        }
+    }
+
+    /// Resyncs to idle, forgetting any in-progress pulse, the debounce
+    /// clock, and the video toggle state, without emitting a [`Transition`].
+    /// Used after a gap in the event stream (e.g. the trigger line was
+    /// re-requested by [`crate::supervisor::LineSupervisor`]) during which
+    /// an edge could have been missed, so a stale mid-pulse or
+    /// already-recording state doesn't misinterpret the next real edge.
+    pub fn reset(&mut self) {
+        self.pulse_state = PulseState::Idle;
+        self.video_recording = false;
+        self.last_edge_timestamp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN_PULSE_WIDTH: Duration = Duration::from_millis(10);
+    const SHORT_PULSE_MAX: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn short_pulse_is_decoded_as_still_capture() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(30_000_000, EventType::FallingEdge),
+            Some(Transition::CaptureStill)
+        );
+    }
+
+    #[test]
+    fn long_pulse_toggles_video_recording() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(200_000_000, EventType::FallingEdge),
+            Some(Transition::Start)
+        );
+
+        assert_eq!(trigger.on_event(400_000_000, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(600_000_000, EventType::FallingEdge),
+            Some(Transition::Stop)
+        );
+    }
+
+    #[test]
+    fn pulse_at_exactly_short_pulse_max_is_a_video_toggle() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(50_000_000, EventType::FallingEdge),
+            Some(Transition::Start)
+        );
+    }
+
+    #[test]
+    fn edge_faster_than_min_pulse_width_is_debounced() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        // 1ms later, well under the 10ms minimum pulse width.
+        assert_eq!(trigger.on_event(1_000_000, EventType::FallingEdge), None);
+        assert_eq!(trigger.glitch_count(), 1);
+    }
+
+    #[test]
+    fn repeated_rising_edges_mid_pulse_are_ignored() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        assert_eq!(trigger.on_event(20_000_000, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(400_000_000, EventType::FallingEdge),
+            Some(Transition::Start)
+        );
+    }
+
+    #[test]
+    fn repeated_falling_edges_while_idle_are_ignored() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::FallingEdge), None);
+        assert_eq!(trigger.on_event(20_000_000, EventType::FallingEdge), None);
+    }
+
+    #[test]
+    fn reset_forgets_pulse_and_toggle_state() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false, None);
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+
+        trigger.reset();
+
+        // Idle again, and the debounce clock was forgotten, so an edge
+        // arriving sooner than `min_pulse_width` after the pre-reset edge
+        // is still accepted as a fresh pulse start.
+        assert_eq!(trigger.on_event(1_000_000, EventType::RisingEdge), None);
+    }
+
+    #[test]
+    fn inverted_polarity_swaps_pulse_start_and_end_edges() {
+        let mut trigger = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, true, None);
+
+        assert_eq!(trigger.on_event(0, EventType::FallingEdge), None);
+        assert_eq!(
+            trigger.on_event(200_000_000, EventType::RisingEdge),
+            Some(Transition::Start)
+        );
+    }
+
+    const PWM_THRESHOLDS: PwmThresholds =
+        PwmThresholds { record_above: Duration::from_micros(1800), stop_below: Duration::from_micros(1200) };
+
+    #[test]
+    fn pwm_pulse_above_record_threshold_starts_recording() {
+        // RC PWM pulses are 1-2ms, well under the 10ms `MIN_PULSE_WIDTH`
+        // used by the still/toggle tests above, so PWM mode needs its own
+        // much smaller debounce width here.
+        let mut trigger = TriggerStateMachine::new(Duration::from_micros(100), SHORT_PULSE_MAX, false, Some(PWM_THRESHOLDS));
+
+        assert_eq!(trigger.on_event(0, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(1_900_000, EventType::FallingEdge),
+            Some(Transition::Start)
+        );
+    }
+
+    #[test]
+    fn pwm_pulse_below_stop_threshold_stops_recording() {
+        let mut trigger = TriggerStateMachine::new(Duration::from_micros(100), SHORT_PULSE_MAX, false, Some(PWM_THRESHOLDS));
+
+        trigger.on_event(0, EventType::RisingEdge);
+        trigger.on_event(1_900_000, EventType::FallingEdge);
+        assert!(trigger.video_recording());
+
+        assert_eq!(trigger.on_event(3_000_000, EventType::RisingEdge), None);
+        assert_eq!(
+            trigger.on_event(4_100_000, EventType::FallingEdge),
+            Some(Transition::Stop)
+        );
+    }
+
+    #[test]
+    fn pwm_pulse_in_hysteresis_band_holds_current_state() {
+        let mut trigger = TriggerStateMachine::new(Duration::from_micros(100), SHORT_PULSE_MAX, false, Some(PWM_THRESHOLDS));
+
+        trigger.on_event(0, EventType::RisingEdge);
+        trigger.on_event(1_900_000, EventType::FallingEdge);
+        assert!(trigger.video_recording());
+
+        // 1.5ms, between `stop_below` and `record_above`: neither threshold
+        // is crossed, so the still-recording state is held rather than
+        // toggled.
+        assert_eq!(trigger.on_event(3_000_000, EventType::RisingEdge), None);
+        assert_eq!(trigger.on_event(4_500_000, EventType::FallingEdge), None);
+        assert!(trigger.video_recording());
+    }
+}
@@ -0,0 +1,95 @@
+//! Drives the trigger line as an *output*, pulsing it at a configured
+//! rate/pattern, so a bench setup can exercise a third-party camera's
+//! trigger input - or PX4's own `CAMERA_TRIGGER` feedback wiring - without
+//! a flight controller, or even this program's own
+//! [`crate::trigger_source::GpioTriggerSource`] input path, in the loop.
+//! See [`Command::Generate`](crate::config::Command::Generate).
+
+use std::path::Path;
+use std::time::Duration;
+
+use gpiocdev::line::{Direction, Value};
+use log::info;
+
+/// Rate/pattern for [`run`]. Mirrors the pulse shape
+/// [`crate::trigger_source::SimulatedTriggerSource`] synthesizes
+/// internally, except these pulses are driven onto real hardware instead of
+/// decoded straight back by this same process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorConfig {
+    /// Time between the start of one pulse and the next.
+    pub interval: Duration,
+    /// How long the line is held active before releasing it.
+    pub pulse_width: Duration,
+    /// Number of pulses to emit before returning. `None` runs until the
+    /// caller is interrupted (e.g. Ctrl-C).
+    pub count: Option<u64>,
+    /// See [`crate::config::Config::active_low`]; applied to the output
+    /// request the same way it's applied to the trigger line's input
+    /// request, so a generator run against a loopback wire decodes the
+    /// same polarity a real flight controller would produce.
+    pub active_low: bool,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            // Comfortably above `short_pulse_max`'s default, matching
+            // `SimulatedTriggerSource`'s own hardcoded pulse width, so a
+            // default `generate` run reliably decodes as a video
+            // start/stop toggle rather than a still-capture command.
+            pulse_width: Duration::from_millis(200),
+            count: None,
+            active_low: false,
+        }
+    }
+}
+
+/// Requests `line_offset` on `gpiochip` as an output, holding it inactive
+/// between pulses, and drives `config.count` pulses (or runs forever) at
+/// `config.interval`, returning once the last one has released. Exits early
+/// with an error on the first failed request or line write; a bench tool
+/// like this one has no good in-place recovery to fall back to.
+pub async fn run(gpiochip: &Path, line_offset: u32, consumer_label: &str, config: &GeneratorConfig) -> Result<(), String> {
+    let mut line_config = gpiocdev::line::Config::default();
+    line_config.direction = Some(Direction::Output);
+    line_config.active_low = config.active_low;
+    line_config.value = Some(Value::Inactive);
+
+    let request = gpiocdev::Request::builder()
+        .on_chip(gpiochip)
+        .with_consumer(consumer_label)
+        .with_line(line_offset)
+        .with_line_config(&line_config)
+        .request()
+        .map_err(|error| {
+            format!("line {line_offset} on {} does not exist or is already in use: {error}", gpiochip.display())
+        })?;
+
+    let mut pulses_sent = 0u64;
+    loop {
+        if config.count.is_some_and(|count| pulses_sent >= count) {
+            break;
+        }
+
+        request
+            .set_value(line_offset, Value::Active)
+            .map_err(|error| format!("failed to drive line {line_offset} active: {error}"))?;
+        tokio::time::sleep(config.pulse_width).await;
+        request
+            .set_value(line_offset, Value::Inactive)
+            .map_err(|error| format!("failed to release line {line_offset}: {error}"))?;
+
+        pulses_sent += 1;
+        let progress = config.count.map(|count| format!("{pulses_sent}/{count}")).unwrap_or_else(|| pulses_sent.to_string());
+        info!("generated pulse {progress}");
+
+        if config.count.is_some_and(|count| pulses_sent >= count) {
+            break;
+        }
+        tokio::time::sleep(config.interval.saturating_sub(config.pulse_width)).await;
+    }
+
+    Ok(())
+}
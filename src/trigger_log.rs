@@ -0,0 +1,347 @@
+//! Appends a CSV row for every accepted trigger event, so post-processing
+//! tools can correlate captured frames/recordings with the PX4 log without
+//! re-deriving trigger timing from the video files themselves.
+//!
+//! On a long endurance flight this sidecar would otherwise grow for the
+//! whole flight; [`TriggerLogConfig`] optionally rotates it by size and/or
+//! age, gzip-compressing each rotated-out file, the same "close, don't grow
+//! forever" idea as the segmented video files it accompanies (see
+//! [`crate::recorder::RecorderConfig::segment_duration`]).
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+
+use crate::clock::RealtimeClock;
+use crate::mavlink::CaptureTelemetry;
+
+/// How [`TriggerLog`] rotates its CSV sidecar. `None` in a field disables
+/// that rotation trigger; both can be set together, whichever is hit first
+/// rotates. Off (both `None`, the default) preserves the old unbounded
+/// behavior.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriggerLogConfig {
+    /// Rotate once the live CSV sidecar exceeds this many bytes.
+    pub max_bytes: Option<u64>,
+    /// Rotate once the live CSV sidecar has been open this long, regardless
+    /// of size.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for TriggerLogConfig {
+    fn default() -> Self {
+        Self { max_bytes: None, max_age: None }
+    }
+}
+
+/// A per-session sidecar log at
+/// `<output_dir>/<flight_session>/trigger-events.csv`, one row per accepted
+/// trigger event across all cameras.
+pub struct TriggerLog {
+    session_dir: PathBuf,
+    live_path: PathBuf,
+    file: Mutex<OpenLog>,
+    clock: Arc<RealtimeClock>,
+    flight_session: String,
+    config: TriggerLogConfig,
+}
+
+/// The currently-open live CSV file plus enough state to decide when
+/// [`TriggerLog::rotate_if_needed`] should roll it over.
+struct OpenLog {
+    file: File,
+    opened_at: Instant,
+}
+
+const CSV_HEADER: &str = "sequence,gpio_timestamp_ns,system_realtime,camera,action,filename,roll,pitch,yaw,xacc,yacc,\
+                           zacc,xgyro,ygyro,zgyro,gimbal_roll,gimbal_pitch,gimbal_yaw";
+
+impl TriggerLog {
+    /// Opens (creating if needed) the session's CSV sidecar, writing a
+    /// header row only if the file didn't already exist, so restarting this
+    /// process mid-session appends to the same log instead of clobbering it.
+    /// `clock` translates each row's `gpio_timestamp_ns` (`CLOCK_MONOTONIC`)
+    /// into `system_realtime`, so the two columns describe the same instant
+    /// in different timebases instead of the latter drifting by however
+    /// long the event took to reach here.
+    pub fn open(
+        output_dir: &Path,
+        flight_session: &str,
+        clock: Arc<RealtimeClock>,
+        config: TriggerLogConfig,
+    ) -> Result<Self, String> {
+        let session_dir = output_dir.join(flight_session);
+        std::fs::create_dir_all(&session_dir).map_err(|error| {
+            format!("failed to create session directory {}: {error}", session_dir.display())
+        })?;
+
+        let live_path = session_dir.join("trigger-events.csv");
+        let file = open_live_file(&live_path)?;
+
+        Ok(Self {
+            session_dir,
+            live_path,
+            file: Mutex::new(OpenLog { file, opened_at: Instant::now() }),
+            clock,
+            flight_session: flight_session.to_string(),
+            config,
+        })
+    }
+
+    /// Appends one row for `camera`'s handling of trigger `sequence`, and
+    /// mirrors it as a structured log record (`SESSION_ID`, `CAMERA_ID`,
+    /// `TRIGGER_SEQ`, `RECORDING_FILE`, `GPIO_TIMESTAMP_NS` fields) so
+    /// `journalctl -o json` can filter/correlate events without parsing the
+    /// CSV sidecar. `filename` is `None` when the action produced no file (a
+    /// `stop`, or a still-capture request on a camera that doesn't have it
+    /// enabled). `telemetry` is `None` when `mavlink` isn't connected;
+    /// callers pass [`crate::mavlink::MavlinkFeedback::latest_capture_telemetry`]
+    /// otherwise, so structure-from-motion tooling gets an orientation/IMU
+    /// estimate per captured image without a separate PX4 log to correlate.
+    pub fn log_event(
+        &self,
+        sequence: u64,
+        gpio_timestamp_ns: u64,
+        camera: &str,
+        action: &str,
+        filename: Option<&Path>,
+        telemetry: Option<CaptureTelemetry>,
+    ) {
+        // `gpio_timestamp_ns` is 0 for synthetic events with no hardware
+        // timestamp to translate (see `Session::dispatch`/low-disk-space
+        // auto-stop); fall back to wall-clock time for those instead of
+        // reporting a bogus realtime near this process's boot.
+        let system_realtime = if gpio_timestamp_ns == 0 {
+            Utc::now()
+        } else {
+            let unix_nanos = self.clock.to_unix_nanos(gpio_timestamp_ns);
+            DateTime::from_timestamp(unix_nanos / 1_000_000_000, (unix_nanos % 1_000_000_000) as u32)
+                .unwrap_or_else(Utc::now)
+        };
+        let system_realtime = system_realtime.to_rfc3339();
+        let filename = filename.map(|path| path.display().to_string()).unwrap_or_default();
+
+        info!(
+            session_id = self.flight_session.as_str(),
+            camera_id = camera,
+            trigger_seq = sequence,
+            gpio_timestamp_ns = gpio_timestamp_ns,
+            recording_file = filename.as_str();
+            "camera {camera}: {action}"
+        );
+
+        let (roll, pitch, yaw, xacc, yacc, zacc, xgyro, ygyro, zgyro) = match telemetry {
+            Some(telemetry) => (
+                telemetry.roll.to_string(),
+                telemetry.pitch.to_string(),
+                telemetry.yaw.to_string(),
+                telemetry.imu.xacc.to_string(),
+                telemetry.imu.yacc.to_string(),
+                telemetry.imu.zacc.to_string(),
+                telemetry.imu.xgyro.to_string(),
+                telemetry.imu.ygyro.to_string(),
+                telemetry.imu.zgyro.to_string(),
+            ),
+            None => Default::default(),
+        };
+        let (gimbal_roll, gimbal_pitch, gimbal_yaw) = match telemetry.and_then(|telemetry| telemetry.gimbal) {
+            Some(gimbal) => (gimbal.roll.to_string(), gimbal.pitch.to_string(), gimbal.yaw.to_string()),
+            None => Default::default(),
+        };
+
+        let mut log = self.file.lock().unwrap();
+        let result = writeln!(
+            log.file,
+            "{sequence},{gpio_timestamp_ns},{system_realtime},{},{},{},{roll},{pitch},{yaw},{xacc},{yacc},{zacc},\
+             {xgyro},{ygyro},{zgyro},{gimbal_roll},{gimbal_pitch},{gimbal_yaw}",
+            csv_escape(camera),
+            csv_escape(action),
+            csv_escape(&filename),
+        );
+        if let Err(error) = result {
+            warn!("failed to append to trigger event log {}: {error}", self.live_path.display());
+        }
+
+        self.rotate_if_needed(&mut log);
+    }
+
+    /// Rotates the live CSV out to a timestamped, gzip-compressed file and
+    /// reopens a fresh one at `live_path`, if `config` says it's due. A
+    /// no-op (after one cheap `metadata()` call) whenever neither threshold
+    /// is set or hit.
+    fn rotate_if_needed(&self, log: &mut OpenLog) {
+        let due_to_age = self.config.max_age.is_some_and(|max_age| log.opened_at.elapsed() >= max_age);
+        let due_to_size = self.config.max_bytes.is_some_and(|max_bytes| {
+            log.file.metadata().map(|metadata| metadata.len() >= max_bytes).unwrap_or(false)
+        });
+        if !due_to_age && !due_to_size {
+            return;
+        }
+
+        let rotated_path =
+            self.session_dir.join(format!("trigger-events-{}.csv", Utc::now().format("%Y%m%dT%H%M%SZ")));
+        if let Err(error) = fs::rename(&self.live_path, &rotated_path) {
+            warn!("failed to rotate trigger event log {}: {error}", self.live_path.display());
+            return;
+        }
+
+        match open_live_file(&self.live_path) {
+            Ok(file) => {
+                log.file = file;
+                log.opened_at = Instant::now();
+            }
+            Err(error) => warn!("{error}"),
+        }
+
+        // Off the trigger-dispatch path: gzip is unbounded latency for what
+        // could be a several-hour flight's worth of rows, and nothing else
+        // needs `rotated_path` to exist as plain CSV once the rename above
+        // has already made it the rotated file's final resting name.
+        std::thread::spawn(move || compress_and_remove(&rotated_path));
+    }
+}
+
+/// Opens (creating if needed) `path` in append mode, writing the CSV header
+/// only if it didn't already exist - shared by [`TriggerLog::open`] and
+/// every rotation, since a rotation always starts a fresh file the same way
+/// startup does.
+fn open_live_file(path: &Path) -> Result<File, String> {
+    let is_new = !path.exists();
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|error| format!("failed to open trigger event log {}: {error}", path.display()))?;
+
+    if is_new {
+        writeln!(file, "{CSV_HEADER}")
+            .map_err(|error| format!("failed to write header to trigger event log {}: {error}", path.display()))?;
+    }
+
+    Ok(file)
+}
+
+/// Gzips `path` to `path` with a `.gz` extension appended and removes the
+/// plain-text original, so a rotated-out sidecar doesn't sit around
+/// uncompressed alongside the segmented video files it accompanies. Logs and
+/// gives up (leaving the plain file in place) on any I/O error, rather than
+/// panicking a detached thread with nothing watching its result.
+fn compress_and_remove(path: &Path) {
+    let compress = || -> std::io::Result<()> {
+        let mut contents = Vec::new();
+        File::open(path)?.read_to_end(&mut contents)?;
+
+        let gz_path = path.with_extension("csv.gz");
+        let gz_file = File::create(&gz_path)?;
+        let mut encoder = GzEncoder::new(gz_file, Compression::default());
+        encoder.write_all(&contents)?;
+        encoder.finish()?;
+
+        fs::remove_file(path)
+    };
+
+    if let Err(error) = compress() {
+        warn!("failed to compress rotated trigger event log {}: {error}", path.display());
+    }
+}
+
+/// Quotes `value` for a CSV field if it contains a comma, quote, or newline,
+/// doubling any embedded quotes, per RFC 4180. `pub(crate)` since
+/// [`crate::state_journal`] reuses it for its own small CSV ledger rather
+/// than duplicating the same escaping logic.
+pub(crate) fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One accepted-event row read back from a `trigger-events.csv` sidecar, for
+/// [`crate::trigger_source::ReplayTriggerSource`] to re-issue against the
+/// recording stack.
+#[derive(Debug, Clone)]
+pub struct TriggerLogEntry {
+    pub sequence: u64,
+    pub gpio_timestamp_ns: u64,
+    /// The action string [`TriggerLog::log_event`] was called with, e.g.
+    /// `"start"`, `"stop"`, `"capture_still"`.
+    pub action: String,
+}
+
+/// Reads back the rows a prior run's [`TriggerLog`] wrote to `path`.
+/// Skips rows with a `gpio_timestamp_ns` of 0 (a synthetic event with no
+/// real trigger-line edge behind it, e.g. a disk-space auto-stop), since
+/// there's no hardware edge to replay for those.
+pub fn read_entries(path: &Path) -> Result<Vec<TriggerLogEntry>, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|error| format!("failed to read {}: {error}", path.display()))?;
+
+    let mut entries = Vec::new();
+    for line in contents.lines().skip(1) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let fields = split_csv_row(line);
+        let [sequence, gpio_timestamp_ns, _system_realtime, _camera, action, _filename, ..] = fields.as_slice() else {
+            return Err(format!("malformed trigger event log row: {line:?}"));
+        };
+
+        let gpio_timestamp_ns: u64 = gpio_timestamp_ns
+            .parse()
+            .map_err(|error| format!("bad gpio_timestamp_ns in row {line:?}: {error}"))?;
+        if gpio_timestamp_ns == 0 {
+            continue;
+        }
+
+        entries.push(TriggerLogEntry {
+            sequence: sequence.parse().map_err(|error| format!("bad sequence in row {line:?}: {error}"))?,
+            gpio_timestamp_ns,
+            action: action.to_string(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Splits one CSV row into its fields, undoing [`csv_escape`]'s quoting.
+/// `pub(crate)`, same reasoning as `csv_escape`.
+pub(crate) fn split_csv_row(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else {
+            match c {
+                '"' => in_quotes = true,
+                ',' => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c),
+            }
+        }
+    }
+    fields.push(field);
+
+    fields
+}
@@ -0,0 +1,402 @@
+//! Abstracts "where trigger edges come from" behind one trait, so
+//! [`crate::session::Session`] and [`crate::main`]'s event loop don't need
+//! to know whether they're reading a real GPIO line or a synthetic one
+//! (`--simulate`/`--replay-log`).
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
+use gpio_cdev::EventType;
+use gpiocdev::line::EdgeKind;
+use gpiocdev::tokio::AsyncRequest;
+use log::warn;
+use tokio::sync::mpsc;
+
+use crate::clock::RealtimeClock;
+use crate::trigger_log;
+
+/// A single trigger-line transition, decoded from hardware or synthesized.
+#[derive(Debug, Clone, Copy)]
+pub struct Edge {
+    /// Nanosecond timestamp: a hardware counter for a real GPIO edge, or a
+    /// monotonically increasing counter since the source was created for a
+    /// synthetic one. Passed straight through to
+    /// [`crate::trigger::TriggerStateMachine::on_event`], which only cares
+    /// about the difference between consecutive edges.
+    pub timestamp_ns: u64,
+    pub event_type: EventType,
+}
+
+/// What happened while waiting for the next edge.
+#[derive(Debug)]
+pub enum Next {
+    Edge(Edge),
+    /// The source hit an error decoding one edge; safe to keep polling.
+    Error(String),
+    /// The source is exhausted and will never produce another edge, e.g.
+    /// [`ReplayTriggerSource`] running out of logged events.
+    /// [`GpioTriggerSource`]'s gpio uAPI v2 request reads one edge at a time
+    /// rather than exposing an end-of-stream, so it never produces this.
+    Ended,
+    /// No edge arrived within the source's own stall timeout.
+    TimedOut,
+}
+
+/// How many extra edges [`TriggerSource::drain_ready`] will opportunistically
+/// pull off a source in one call, so a burst (a fast survey line pulsing at
+/// 10+ Hz) is processed from a bounded, preallocated buffer instead of
+/// growing it unboundedly if the line ever misbehaves.
+pub const DRAIN_BATCH_CAPACITY: usize = 32;
+
+/// A source of trigger edges: the real GPIO line ([`GpioTriggerSource`]) or
+/// a synthetic one ([`SimulatedTriggerSource`]).
+pub trait TriggerSource: Send {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>>;
+
+    /// Opportunistically appends any further edges already queued on this
+    /// source to `buffer`, without waiting for one, up to `buffer`'s
+    /// capacity. Meant to be called right after [`TriggerSource::next_edge`]
+    /// returns an edge, so [`crate::main::run`]'s event loop can drain a
+    /// whole burst in one pass instead of paying a full arbitration round
+    /// trip per edge at high trigger rates. Only [`GpioTriggerSource`] has
+    /// anything to batch this way (a kernel-side event queue); every other
+    /// source produces edges no faster than this process asks for them, so
+    /// the default here is a no-op.
+    fn drain_ready(&mut self, buffer: &mut Vec<Edge>) {
+        let _ = buffer;
+    }
+}
+
+/// Reads edges off a real GPIO line via the gpio uAPI v2 driver
+/// (`gpiocdev`), applying `stall_timeout` itself so
+/// [`TriggerSource::next_edge`] never blocks its caller forever. Requesting
+/// the trigger line through v2 rather than the `gpio_cdev` v1 ABI everything
+/// else in this crate still uses is what makes
+/// [`crate::config::Config::debounce_period`] and
+/// [`crate::config::Config::event_clock_realtime`] possible; see
+/// [`crate::config::Config::trigger_line_config`].
+pub struct GpioTriggerSource {
+    request: AsyncRequest,
+    stall_timeout: Duration,
+    /// Set when the request was made with `event_clock_realtime`, so every
+    /// edge's kernel-reported `CLOCK_REALTIME` timestamp gets folded back
+    /// into the `CLOCK_MONOTONIC` domain [`crate::trigger::TriggerStateMachine`]
+    /// and everything downstream of it expects, before it ever leaves this
+    /// module. See [`RealtimeClock::discipline_from_realtime_edge`].
+    realtime_clock: Option<Arc<RealtimeClock>>,
+}
+
+impl GpioTriggerSource {
+    pub fn new(request: AsyncRequest, stall_timeout: Duration, realtime_clock: Option<Arc<RealtimeClock>>) -> Self {
+        Self {
+            request,
+            stall_timeout,
+            realtime_clock,
+        }
+    }
+}
+
+impl GpioTriggerSource {
+    /// Converts a raw kernel edge event into an [`Edge`], folding its
+    /// timestamp into the monotonic domain if `realtime_clock` is set.
+    /// Shared by [`TriggerSource::next_edge`] and
+    /// [`TriggerSource::drain_ready`] so both decode a kernel event the same
+    /// way.
+    fn decode(&self, timestamp_ns: u64, kind: EdgeKind) -> Edge {
+        let timestamp_ns = match &self.realtime_clock {
+            Some(clock) => clock.discipline_from_realtime_edge(timestamp_ns),
+            None => timestamp_ns,
+        };
+        Edge {
+            timestamp_ns,
+            event_type: match kind {
+                EdgeKind::Rising => EventType::RisingEdge,
+                EdgeKind::Falling => EventType::FallingEdge,
+            },
+        }
+    }
+}
+
+impl TriggerSource for GpioTriggerSource {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>> {
+        Box::pin(async move {
+            match tokio::time::timeout(self.stall_timeout, self.request.read_edge_event()).await {
+                Ok(Ok(event)) => Next::Edge(self.decode(event.timestamp_ns, event.kind)),
+                Ok(Err(error)) => Next::Error(error.to_string()),
+                Err(_) => Next::TimedOut,
+            }
+        })
+    }
+
+    /// Drains any further edges the kernel has already queued on the line
+    /// (e.g. a fast survey line pulsing at 10+ Hz) without waiting, so a
+    /// burst is picked up in one pass instead of one `next_edge` round trip
+    /// per edge. Stops at the first `now_or_never` miss - either the queue
+    /// is empty, or reading it would block - since anything past that is no
+    /// longer "already ready".
+    fn drain_ready(&mut self, buffer: &mut Vec<Edge>) {
+        while buffer.len() < buffer.capacity() {
+            match self.request.read_edge_event().now_or_never() {
+                Some(Ok(event)) => buffer.push(self.decode(event.timestamp_ns, event.kind)),
+                Some(Err(error)) => {
+                    warn!("failed to drain queued trigger line edge: {error}");
+                    break;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+/// Emits a rising edge, then (after `pulse_width`) a falling edge, every
+/// `interval`, decoded by the exact same [`crate::trigger::TriggerStateMachine`]
+/// a real GPIO edge would be - so `--simulate` exercises the actual
+/// start/stop toggle logic instead of a separate bypass path. Used when
+/// there's no `/dev/gpiochip0` to develop or test against.
+pub struct SimulatedTriggerSource {
+    interval: tokio::time::Interval,
+    pulse_width: Duration,
+    clock: Instant,
+    pending_falling_edge: bool,
+}
+
+impl SimulatedTriggerSource {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval: tokio::time::interval(interval),
+            // Comfortably above `short_pulse_max`'s default so a simulated
+            // pulse reliably decodes as a video start/stop toggle rather
+            // than a still-capture command.
+            pulse_width: Duration::from_millis(200),
+            clock: Instant::now(),
+            pending_falling_edge: false,
+        }
+    }
+}
+
+impl TriggerSource for SimulatedTriggerSource {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>> {
+        Box::pin(async move {
+            if self.pending_falling_edge {
+                tokio::time::sleep(self.pulse_width).await;
+                self.pending_falling_edge = false;
+                return Next::Edge(Edge {
+                    timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                    event_type: EventType::FallingEdge,
+                });
+            }
+
+            self.interval.tick().await;
+            self.pending_falling_edge = true;
+            Next::Edge(Edge {
+                timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                event_type: EventType::RisingEdge,
+            })
+        })
+    }
+}
+
+/// Pulse width used to replay a `"start"`/`"stop"` row, same reasoning as
+/// [`SimulatedTriggerSource`]'s hardcoded pulse width: comfortably above
+/// `short_pulse_max`'s default so it reliably decodes as a toggle rather
+/// than a still capture.
+const REPLAY_TOGGLE_PULSE_WIDTH: Duration = Duration::from_millis(200);
+
+/// Pulse width used to replay a `"capture_still"` row, comfortably below
+/// `short_pulse_max`'s default.
+const REPLAY_STILL_PULSE_WIDTH: Duration = Duration::from_millis(20);
+
+/// Re-issues a prior run's `trigger-events.csv` sidecar (see
+/// [`crate::trigger_log::TriggerLog`]) against the recording stack with its
+/// original inter-event timing, so a field issue (double triggers, rapid
+/// start/stop sequences) can be reproduced on the bench without the
+/// hardware that originally produced it. Replays at the decoded-transition
+/// level rather than raw edges, since that's what the sidecar records; each
+/// row becomes a synthetic rising/falling edge pair timed to reliably
+/// decode back to the same transition, not a bit-for-bit replay of the
+/// original edges' own timing.
+pub struct ReplayTriggerSource {
+    entries: std::vec::IntoIter<trigger_log::TriggerLogEntry>,
+    clock: Instant,
+    previous_gpio_timestamp_ns: Option<u64>,
+    pending_falling_edge: Option<Duration>,
+}
+
+impl ReplayTriggerSource {
+    /// Reads back `path`, keeping only the first row logged for each trigger
+    /// `sequence` (every camera logs its own row for the same physical
+    /// edge) and sorting by `gpio_timestamp_ns`, since a multi-camera log
+    /// interleaves rows in whatever order each camera's worker thread
+    /// happened to log them in, not necessarily edge order.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut seen_sequences = HashSet::new();
+        let mut entries: Vec<_> = trigger_log::read_entries(path)?
+            .into_iter()
+            .filter(|entry| seen_sequences.insert(entry.sequence))
+            .collect();
+        entries.sort_by_key(|entry| entry.gpio_timestamp_ns);
+
+        Ok(Self {
+            entries: entries.into_iter(),
+            clock: Instant::now(),
+            previous_gpio_timestamp_ns: None,
+            pending_falling_edge: None,
+        })
+    }
+}
+
+impl TriggerSource for ReplayTriggerSource {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>> {
+        Box::pin(async move {
+            if let Some(pulse_width) = self.pending_falling_edge.take() {
+                tokio::time::sleep(pulse_width).await;
+                return Next::Edge(Edge {
+                    timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                    event_type: EventType::FallingEdge,
+                });
+            }
+
+            let Some(entry) = self.entries.next() else {
+                return Next::Ended;
+            };
+
+            if let Some(previous) = self.previous_gpio_timestamp_ns {
+                tokio::time::sleep(Duration::from_nanos(entry.gpio_timestamp_ns.saturating_sub(previous))).await;
+            }
+            self.previous_gpio_timestamp_ns = Some(entry.gpio_timestamp_ns);
+
+            self.pending_falling_edge = Some(if entry.action.starts_with("capture_still") {
+                REPLAY_STILL_PULSE_WIDTH
+            } else {
+                REPLAY_TOGGLE_PULSE_WIDTH
+            });
+
+            Next::Edge(Edge {
+                timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                event_type: EventType::RisingEdge,
+            })
+        })
+    }
+}
+
+/// Decodes incoming MAVLink `CAMERA_TRIGGER`/`MAV_CMD_DO_DIGICAM_CONTROL`
+/// messages as trigger edges, for [`crate::config::Config::mavlink`]
+/// deployments with no spare GPIO wiring to a trigger line - see
+/// [`crate::mavlink::MavlinkFeedback::trigger_source`], which decodes those
+/// messages off the shared MAVLink connection and forwards a signal here.
+/// Each signal becomes a synthesized rising/falling edge pair,
+/// `REPLAY_STILL_PULSE_WIDTH` apart, the same as [`ReplayTriggerSource`] uses
+/// to replay a logged still capture, since both messages represent a
+/// single-shot trigger rather than a held-line video start/stop toggle.
+pub struct MavlinkTriggerSource {
+    receiver: mpsc::UnboundedReceiver<()>,
+    clock: Instant,
+    pending_falling_edge: bool,
+}
+
+impl MavlinkTriggerSource {
+    pub fn new(receiver: mpsc::UnboundedReceiver<()>) -> Self {
+        Self {
+            receiver,
+            clock: Instant::now(),
+            pending_falling_edge: false,
+        }
+    }
+}
+
+impl TriggerSource for MavlinkTriggerSource {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>> {
+        Box::pin(async move {
+            if self.pending_falling_edge {
+                tokio::time::sleep(REPLAY_STILL_PULSE_WIDTH).await;
+                self.pending_falling_edge = false;
+                return Next::Edge(Edge {
+                    timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                    event_type: EventType::FallingEdge,
+                });
+            }
+
+            let Some(()) = self.receiver.recv().await else {
+                // The sending `MavlinkFeedback` was dropped, e.g. the
+                // process is shutting down.
+                return Next::Ended;
+            };
+            self.pending_falling_edge = true;
+            Next::Edge(Edge {
+                timestamp_ns: self.clock.elapsed().as_nanos() as u64,
+                event_type: EventType::RisingEdge,
+            })
+        })
+    }
+}
+
+/// Combines a primary source (the physical trigger line) with a secondary
+/// one (e.g. [`MavlinkTriggerSource`]) so both can drive the trigger state
+/// machine at once, for airframes that have the GPIO wiring but still want a
+/// MAVLink command as a backup path - see
+/// [`crate::config::Config::mavlink`]'s `trigger_fusion`.
+///
+/// `next_edge` always prefers a primary edge over a secondary one that's
+/// ready at the same time (a `tokio::select!` `biased` race), and beyond
+/// that, drops a secondary edge arriving within `dedup_window` of the last
+/// accepted primary edge - both wired up to report the same underlying
+/// physical trigger pull would otherwise double-trigger a single event. A
+/// secondary edge arriving outside that window (the physical line hasn't
+/// fired recently) is passed straight through, so the backup path still
+/// works when the primary genuinely didn't see anything.
+pub struct FusedTriggerSource {
+    primary: Box<dyn TriggerSource>,
+    secondary: Box<dyn TriggerSource>,
+    dedup_window: Duration,
+    last_primary_edge_at: Option<Instant>,
+}
+
+impl FusedTriggerSource {
+    pub fn new(primary: Box<dyn TriggerSource>, secondary: Box<dyn TriggerSource>, dedup_window: Duration) -> Self {
+        Self {
+            primary,
+            secondary,
+            dedup_window,
+            last_primary_edge_at: None,
+        }
+    }
+}
+
+impl TriggerSource for FusedTriggerSource {
+    fn next_edge(&mut self) -> Pin<Box<dyn Future<Output = Next> + Send + '_>> {
+        Box::pin(async move {
+            loop {
+                let (next, from_secondary) = tokio::select! {
+                    biased;
+                    next = self.primary.next_edge() => (next, false),
+                    next = self.secondary.next_edge() => (next, true),
+                };
+
+                if matches!(next, Next::Edge(_)) {
+                    let now = Instant::now();
+                    if from_secondary {
+                        if let Some(last_primary_edge_at) = self.last_primary_edge_at {
+                            if now.duration_since(last_primary_edge_at) < self.dedup_window {
+                                warn!(
+                                    "dropping secondary trigger source edge within {:?} of the last \
+                                     primary edge, treating it as the same physical event",
+                                    self.dedup_window
+                                );
+                                continue;
+                            }
+                        }
+                    } else {
+                        self.last_primary_edge_at = Some(now);
+                    }
+                }
+
+                return next;
+            }
+        })
+    }
+}
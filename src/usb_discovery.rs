@@ -0,0 +1,94 @@
+//! Resolves a UVC camera's `/dev/videoN` node by USB serial number or port
+//! path instead of a hard-coded path, since `/dev/video*` numbering is
+//! assigned in enumeration order and shifts across reboots (or even a
+//! camera being unplugged and replugged) whenever more than one UVC device
+//! is attached.
+//!
+//! [`crate::recorder::RecorderConfig::source_device`] remains the primary,
+//! always-available way to select a device - this is an opt-in resolution
+//! step [`crate::session::Session::new`] runs once at startup for each
+//! camera with `usb_serial`/`usb_port_path` set, since it needs to actually
+//! walk sysfs and so can't live in [`crate::config`] itself, the same
+//! reasoning as [`crate::gpio_discovery`].
+
+use std::path::{Path, PathBuf};
+
+const VIDEO4LINUX_CLASS_DIR: &str = "/sys/class/video4linux";
+
+/// Finds the `/dev/videoN` node backed by the USB device whose `serial`
+/// sysfs attribute matches `serial` exactly. Returns an error listing every
+/// UVC device's serial if none match, so a typo'd serial is easy to spot
+/// without needing `lsusb` on hand.
+pub fn find_device_by_usb_serial(serial: &str) -> Result<PathBuf, String> {
+    let mut seen = Vec::new();
+
+    for (video_node, usb_device_dir) in video4linux_usb_devices()? {
+        match std::fs::read_to_string(usb_device_dir.join("serial")) {
+            Ok(device_serial) => {
+                let device_serial = device_serial.trim();
+                if device_serial == serial {
+                    return Ok(video_node);
+                }
+                seen.push(format!("{} ({device_serial})", video_node.display()));
+            }
+            Err(_) => seen.push(format!("{} (<no serial>)", video_node.display())),
+        }
+    }
+
+    Err(format!("no UVC camera with serial {serial:?} found; available devices: [{}]", seen.join(", ")))
+}
+
+/// Finds the `/dev/videoN` node backed by the USB device attached at
+/// `port_path` (the bus-relative topology path udev calls `DEVPATH`, e.g.
+/// `"1-1.2"` for hub port 2 on the device plugged into port 1 of bus 1).
+/// Unlike a serial number this survives swapping in an otherwise-identical
+/// replacement camera, at the cost of breaking if the camera is ever moved
+/// to a different physical port.
+pub fn find_device_by_usb_port(port_path: &str) -> Result<PathBuf, String> {
+    let mut seen = Vec::new();
+
+    for (video_node, usb_device_dir) in video4linux_usb_devices()? {
+        let device_port_path = usb_device_dir.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if device_port_path == port_path {
+            return Ok(video_node);
+        }
+        seen.push(format!("{} ({device_port_path})", video_node.display()));
+    }
+
+    Err(format!("no UVC camera at USB port {port_path:?} found; available devices: [{}]", seen.join(", ")))
+}
+
+/// Enumerates every `/dev/videoN` node backed by a USB device, paired with
+/// that USB device's own sysfs directory (e.g. `/sys/bus/usb/devices/1-1.2`,
+/// one level up from the UVC interface's directory that
+/// `/sys/class/video4linux/videoN/device` actually links to).
+fn video4linux_usb_devices() -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let entries = std::fs::read_dir(VIDEO4LINUX_CLASS_DIR)
+        .map_err(|error| format!("failed to enumerate {VIDEO4LINUX_CLASS_DIR}: {error}"))?;
+
+    let mut devices = Vec::new();
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+
+        let video_node_name = entry.file_name();
+        let Some(video_node_name) = video_node_name.to_str() else { continue };
+        let video_node = PathBuf::from("/dev").join(video_node_name);
+
+        if let Some(usb_device_dir) = usb_device_dir_for(&entry.path()) {
+            devices.push((video_node, usb_device_dir));
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Resolves `video_class_dir`'s `device` symlink (which points at the UVC
+/// interface, e.g. `.../1-1.2:1.0`) and returns its parent, the USB device's
+/// own directory (`.../1-1.2`) that carries `serial`/`idVendor`/`idProduct`.
+fn usb_device_dir_for(video_class_dir: &Path) -> Option<PathBuf> {
+    let interface_dir = std::fs::canonicalize(video_class_dir.join("device")).ok()?;
+    interface_dir.parent().map(Path::to_path_buf)
+}
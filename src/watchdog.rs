@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use log::{debug, warn};
+use sd_notify::NotifyState;
+
+/// Drives the systemd `sd_notify` lifecycle (readiness, watchdog keepalive,
+/// stopping) for the service.
+///
+/// Cloning shares the same health flag, so the recording pipeline's bus
+/// watcher and the GPIO event loop can both report trouble through the same
+/// handle the keepalive task reads from.
+#[derive(Clone)]
+pub struct Watchdog {
+    healthy: Arc<AtomicBool>,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self {
+            healthy: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Tells systemd the service has finished starting up. A no-op (with a
+    /// debug log) when not running under systemd.
+    pub fn notify_ready(&self) {
+        if let Err(error) = sd_notify::notify(false, &[NotifyState::Ready]) {
+            debug!("failed to notify systemd readiness (not running under systemd?): {error}");
+        }
+    }
+
+    /// Tells systemd the service is shutting down.
+    pub fn notify_stopping(&self) {
+        if let Err(error) = sd_notify::notify(false, &[NotifyState::Stopping]) {
+            debug!("failed to notify systemd stopping: {error}");
+        }
+    }
+
+    /// If `WATCHDOG_USEC` is set, spawns a task that pets the watchdog at
+    /// half the requested interval, for as long as the service stays
+    /// healthy. Does nothing if systemd didn't request a watchdog.
+    pub fn spawn_keepalive(&self) {
+        let Some(interval) = sd_notify::watchdog_enabled(true) else {
+            debug!("systemd watchdog not requested (WATCHDOG_USEC unset)");
+            return;
+        };
+
+        let healthy = Arc::clone(&self.healthy);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval / 2);
+
+            loop {
+                ticker.tick().await;
+
+                if !healthy.load(Ordering::Relaxed) {
+                    warn!("not petting systemd watchdog, service is unhealthy");
+                    continue;
+                }
+
+                if let Err(error) = sd_notify::notify(false, &[NotifyState::Watchdog]) {
+                    warn!("failed to pet systemd watchdog: {error}");
+                }
+            }
+        });
+    }
+
+    /// Stops petting the watchdog so systemd restarts the unit, e.g. once
+    /// the GPIO event stream stalls or the recording pipeline reports a
+    /// fatal bus error.
+    pub fn mark_unhealthy(&self) {
+        self.healthy.store(false, Ordering::Relaxed);
+    }
+
+    /// Resumes petting the watchdog once the event loop has evidence it's
+    /// making progress again, e.g. a GPIO edge was observed after a stall.
+    pub fn mark_healthy(&self) {
+        self.healthy.store(true, Ordering::Relaxed);
+    }
+
+    #[cfg(test)]
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_healthy() {
+        assert!(Watchdog::new().is_healthy());
+    }
+
+    #[test]
+    fn mark_unhealthy_then_healthy_round_trips() {
+        let watchdog = Watchdog::new();
+
+        watchdog.mark_unhealthy();
+        assert!(!watchdog.is_healthy());
+
+        watchdog.mark_healthy();
+        assert!(watchdog.is_healthy());
+    }
+
+    #[test]
+    fn clones_share_the_same_health_flag() {
+        let watchdog = Watchdog::new();
+        let clone = watchdog.clone();
+
+        watchdog.mark_unhealthy();
+
+        assert!(!clone.is_healthy());
+    }
+}
@@ -0,0 +1,96 @@
+//! End-to-end coverage of the primary trigger line's GPIO path - edge
+//! decode through [`GpioTriggerSource`] into a [`TriggerStateMachine`]
+//! transition - against a real chardev v2 request on a simulated chip,
+//! rather than just the pure state-machine unit tests in `src/trigger.rs`.
+//!
+//! Uses `gpio-sim` (`CONFIG_GPIO_SIM`, present on any 5.17+ kernel) via the
+//! `gpiosim` crate so this runs against the same uAPI a real trigger line
+//! would, without needing actual hardware. Skips itself with a message
+//! instead of failing if the kernel module isn't loaded/configfs isn't
+//! mounted, so this suite doesn't break CI images that don't carry it.
+
+use std::time::Duration;
+
+use gpiosim::Simpleton;
+
+use px4_camera_trigger::trigger::{Transition, TriggerStateMachine};
+use px4_camera_trigger::trigger_source::{GpioTriggerSource, Next, TriggerSource};
+
+const MIN_PULSE_WIDTH: Duration = Duration::from_millis(50);
+const SHORT_PULSE_MAX: Duration = Duration::from_millis(400);
+
+fn gpio_sim_available() -> bool {
+    std::path::Path::new("/sys/kernel/config/gpio-sim").is_dir()
+}
+
+/// Requests the sim's one line the same way `crate::main::open_event_stream`
+/// requests the real trigger line: uAPI v2, both-edge detection.
+async fn open(sim: &Simpleton) -> GpioTriggerSource {
+    let request = gpiocdev::Request::builder()
+        .on_chip(sim.dev_path())
+        .with_consumer("gpio-sim-trigger-test")
+        .with_line(0)
+        .with_edge_detection(gpiocdev::line::EdgeDetection::BothEdges)
+        .request()
+        .expect("gpio-sim chip should accept the trigger line request");
+    let request = gpiocdev::tokio::AsyncRequest::new(request).expect("request should be pollable on tokio");
+    GpioTriggerSource::new(request, Duration::from_secs(5), None)
+}
+
+async fn next_edge(source: &mut GpioTriggerSource) -> px4_camera_trigger::trigger_source::Edge {
+    match source.next_edge().await {
+        Next::Edge(edge) => edge,
+        other => panic!("expected an edge, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn a_long_pulse_starts_then_stops_recording() {
+    if !gpio_sim_available() {
+        eprintln!("skipping: gpio-sim not available (needs CONFIG_GPIO_SIM and configfs mounted)");
+        return;
+    }
+
+    let sim = Simpleton::new(1);
+    let mut source = open(&sim).await;
+    let mut state_machine = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false);
+
+    sim.pullup(0);
+    let start_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(start_edge.timestamp_ns, start_edge.event_type), None);
+
+    tokio::time::sleep(SHORT_PULSE_MAX + Duration::from_millis(100)).await;
+    sim.pulldown(0);
+    let end_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(end_edge.timestamp_ns, end_edge.event_type), Some(Transition::Start));
+
+    sim.pullup(0);
+    let start_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(start_edge.timestamp_ns, start_edge.event_type), None);
+
+    tokio::time::sleep(SHORT_PULSE_MAX + Duration::from_millis(100)).await;
+    sim.pulldown(0);
+    let end_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(end_edge.timestamp_ns, end_edge.event_type), Some(Transition::Stop));
+}
+
+#[tokio::test]
+async fn a_short_pulse_captures_a_still() {
+    if !gpio_sim_available() {
+        eprintln!("skipping: gpio-sim not available (needs CONFIG_GPIO_SIM and configfs mounted)");
+        return;
+    }
+
+    let sim = Simpleton::new(1);
+    let mut source = open(&sim).await;
+    let mut state_machine = TriggerStateMachine::new(MIN_PULSE_WIDTH, SHORT_PULSE_MAX, false);
+
+    sim.pullup(0);
+    let start_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(start_edge.timestamp_ns, start_edge.event_type), None);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    sim.pulldown(0);
+    let end_edge = next_edge(&mut source).await;
+    assert_eq!(state_machine.on_event(end_edge.timestamp_ns, end_edge.event_type), Some(Transition::CaptureStill));
+}